@@ -0,0 +1,1543 @@
+//! instruction builders for the universal nft program
+//!
+//! every builder returns a plain `Instruction` with the accounts in the order the
+//! program expects them, pdas are derived from their seeds so callers only pass
+//! the keys that cant be derived (payer, mint, recipient, ...). the data is the
+//! anchor discriminator (`sha256("global:<name>")[..8]`) followed by the borsh args,
+//! so this doesnt need the program built with the `cpi` feature.
+//!
+//! optional accounts the program doesnt need for a call are passed the way anchor
+//! expects a `None`: the program id, readonly.
+//!
+//! covered: the token program paths (metaplex or extensions), pnfts and the
+//! program/bridged collections. compressed mode needs the bubblegum accounts and
+//! isnt built here.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    ed25519_program,
+    pubkey::Pubkey,
+    secp256k1_program, system_program, sysvar,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+pub use universal_nft::{
+    admin_message, seeds, tss_message, AdminAction, Attribute, ChainConfig, ChainKind,
+    DeadLetterRetry, InboundMessage, MintParams, NftCreator, NftInfoStatus, NftInfoView,
+    NftStandard, TransferParams, UriRewrite, ID as PROGRAM_ID,
+};
+
+/// pda lookups, program pdas come from `universal_nft::seeds`
+pub mod pda {
+    use super::*;
+
+    pub fn nft_program() -> Pubkey {
+        seeds::find_program_state().0
+    }
+
+    pub fn treasury() -> Pubkey {
+        seeds::find_treasury().0
+    }
+
+    pub fn nft_info(mint: &Pubkey) -> Pubkey {
+        seeds::find_nft_info(mint).0
+    }
+
+    pub fn owner_stats(owner: &Pubkey) -> Pubkey {
+        seeds::find_owner_stats(owner).0
+    }
+
+    pub fn minter_role(minter: &Pubkey) -> Pubkey {
+        seeds::find_minter_role(minter).0
+    }
+
+    /// slot `index` of the enumerable index, new mints take the state `index_count`
+    pub fn mint_index(index: u64) -> Pubkey {
+        seeds::find_mint_index(index).0
+    }
+
+    /// mint mint_nft_pda creates next, `mint_count` is the state mint count before it
+    pub fn pda_mint(mint_count: u64) -> Pubkey {
+        seeds::find_pda_mint(mint_count).0
+    }
+
+    pub fn chain_info(chain_id: u64) -> Pubkey {
+        seeds::find_chain_info(chain_id).0
+    }
+
+    /// mint on_call creates for an nft that comes in with `origin_mint` in the message
+    pub fn bridged_mint(origin_mint: &Pubkey) -> Pubkey {
+        seeds::find_bridged_mint(origin_mint).0
+    }
+
+    pub fn dead_letter(message_hash: &[u8; 32]) -> Pubkey {
+        seeds::find_dead_letter(message_hash).0
+    }
+
+    pub fn pending_nonce(nonce: u64) -> Pubkey {
+        seeds::find_pending_nonce(nonce).0
+    }
+
+    pub fn pending_claim(nonce: u64) -> Pubkey {
+        seeds::find_pending_claim(nonce).0
+    }
+
+    pub fn failed_inbound(nonce: u64) -> Pubkey {
+        seeds::find_failed_inbound(nonce).0
+    }
+
+    pub fn skipped_message(nonce: u64) -> Pubkey {
+        seeds::find_skipped_message(nonce).0
+    }
+
+    pub fn inbound_batch(batch_id: &Pubkey) -> Pubkey {
+        seeds::find_inbound_batch(batch_id).0
+    }
+
+    /// exists only while the wallet is blocked, the program instructions that
+    /// deliver to a wallet take it either way
+    pub fn blocked_recipient(recipient: &Pubkey) -> Pubkey {
+        seeds::find_blocked_recipient(recipient).0
+    }
+
+    /// exists only while the mint is blocked from the bridge
+    pub fn blocked_mint(mint: &Pubkey) -> Pubkey {
+        seeds::find_blocked_mint(mint).0
+    }
+
+    pub fn approved_collection(collection_mint: &Pubkey) -> Pubkey {
+        seeds::find_approved_collection(collection_mint).0
+    }
+
+    /// the treasury's ata of a fee mint, token chain fees are collected here
+    pub fn fee_vault(fee_mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(&treasury(), fee_mint, token_program)
+    }
+
+    pub fn collection() -> Pubkey {
+        seeds::find_collection().0
+    }
+
+    pub fn collection_mint() -> Pubkey {
+        seeds::find_collection_mint().0
+    }
+
+    pub fn bridged_collection() -> Pubkey {
+        seeds::find_bridged_collection().0
+    }
+
+    pub fn bridged_collection_mint() -> Pubkey {
+        seeds::find_bridged_collection_mint().0
+    }
+
+    /// anchor event authority, emit_cpi signs the self call with it
+    pub fn event_authority() -> Pubkey {
+        Pubkey::find_program_address(&[b"__event_authority"], &PROGRAM_ID).0
+    }
+
+    // token metadata pdas
+
+    pub fn metadata(mint: &Pubkey) -> Pubkey {
+        mpl_token_metadata::accounts::Metadata::find_pda(mint).0
+    }
+
+    pub fn master_edition(mint: &Pubkey) -> Pubkey {
+        mpl_token_metadata::accounts::MasterEdition::find_pda(mint).0
+    }
+
+    pub fn token_record(mint: &Pubkey, token_account: &Pubkey) -> Pubkey {
+        mpl_token_metadata::accounts::TokenRecord::find_pda(mint, token_account).0
+    }
+}
+
+/// keys for mint_nft and mint_nft_pda that cant be derived
+#[derive(Clone, Debug, Default)]
+pub struct MintNftKeys {
+    pub payer: Pubkey,
+    /// fresh mint keypair that signs the transaction, `pda::pda_mint` for mint_nft_pda
+    pub mint: Pubkey,
+    /// spl token or token-2022
+    pub token_program: Pubkey,
+    /// state `index_count` right before the mint
+    pub index_count: u64,
+    /// metaplex metadata mode, adds the metadata account and token metadata program
+    pub metaplex: bool,
+    /// the payer mints through its minter role, needed when public minting is off
+    pub minter_role: bool,
+    /// program collection exists, the nft gets added to it
+    pub collection: bool,
+}
+
+/// keys for register_existing_nft, deregister_nft, transfer_to_zetachain and unlock_nft
+#[derive(Clone, Debug, Default)]
+pub struct LockKeys {
+    /// holder of the nft, signs and pays
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    /// pnft, adds the token metadata accounts for transfer_v1
+    pub programmable: bool,
+    /// registered nft: the collection verified in its metadata, adds the metadata
+    /// and the approval pda of the collection
+    pub collection: Option<Pubkey>,
+    /// the destination chain takes its fee in a token, paid from the owner's ata
+    pub fee_token: Option<FeeTokenKeys>,
+}
+
+/// fee mint of a chain that charges in an spl token, see set_chain_fee_currency
+#[derive(Clone, Copy, Debug)]
+pub struct FeeTokenKeys {
+    pub mint: Pubkey,
+    /// token program of the fee mint, not necessarily the nft's
+    pub token_program: Pubkey,
+}
+
+/// keys for on_call, retry_dead_letter, retry_inbound, drain_pending, claim_inbound and
+/// claim_nft
+#[derive(Clone, Debug, Default)]
+pub struct OnCallKeys {
+    pub payer: Pubkey,
+    pub source_chain_id: u64,
+    /// mint from the message. a transfer arrives on the mint derived from it, for an
+    /// unlock it is the local mint the nft went out with
+    pub origin_mint: Pubkey,
+    /// the message is an unlock
+    pub unlock: bool,
+    pub token_program: Pubkey,
+    /// the recipient for transfers, nft_info.owner for unlocks
+    pub nft_owner: Pubkey,
+    /// metaplex metadata mode
+    pub metaplex: bool,
+    /// pnft unlock, adds the master edition and both token records. a metaplex
+    /// transfer always passes the master edition, on_call creates it with the metadata
+    pub programmable: bool,
+    /// state `index_count` when this is the first arrival of the nft, None otherwise
+    pub index_count: Option<u64>,
+    /// program collection exists
+    pub collection: bool,
+    /// bridged collection exists, new nfts join it instead of the program collection
+    pub bridged_collection: bool,
+    /// message hash of the dead letter record to pass, see retry_dead_letter
+    pub dead_letter: Option<[u8; 32]>,
+    /// nonce of the pending record to pass in ordered delivery, see drain_pending
+    pub pending_nonce: Option<u64>,
+    /// batch id of the batch record to pass, for a batch root or claim_inbound
+    pub inbound_batch: Option<Pubkey>,
+    /// nonce of the pending claim to pass in claim mode, see claim_nft
+    pub pending_claim: Option<u64>,
+    /// nonce of the failed inbound record to pass, see retry_inbound
+    pub failed_inbound: Option<u64>,
+}
+
+/// anchor instruction discriminator
+pub fn sighash(name: &str) -> [u8; 8] {
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&hash(format!("global:{name}").as_bytes()).to_bytes()[..8]);
+    out
+}
+
+fn ix_data<T: BorshSerialize>(name: &str, args: &T) -> Vec<u8> {
+    let mut data = sighash(name).to_vec();
+    args.serialize(&mut data).expect("borsh into a vec cant fail");
+    data
+}
+
+// anchor reads the program id in an optional slot as None
+fn optional(key: Option<Pubkey>, writable: bool) -> AccountMeta {
+    match key {
+        Some(key) if writable => AccountMeta::new(key, false),
+        Some(key) => AccountMeta::new_readonly(key, false),
+        None => AccountMeta::new_readonly(PROGRAM_ID, false),
+    }
+}
+
+// #[event_cpi] instructions take these two last
+fn event_cpi_accounts() -> [AccountMeta; 2] {
+    [
+        AccountMeta::new_readonly(pda::event_authority(), false),
+        AccountMeta::new_readonly(PROGRAM_ID, false),
+    ]
+}
+
+// the token fee accounts the transfers out take last, before the event accounts
+fn fee_token_accounts(owner: &Pubkey, fee_token: Option<FeeTokenKeys>) -> [AccountMeta; 4] {
+    let owner_fee_token_account = |fee: FeeTokenKeys| {
+        get_associated_token_address_with_program_id(owner, &fee.mint, &fee.token_program)
+    };
+    [
+        optional(fee_token.map(|fee| fee.mint), false),
+        optional(fee_token.map(owner_fee_token_account), true),
+        optional(fee_token.map(|fee| pda::fee_vault(&fee.mint, &fee.token_program)), true),
+        optional(fee_token.map(|fee| fee.token_program), false),
+    ]
+}
+
+fn instruction(accounts: Vec<AccountMeta>, data: Vec<u8>) -> Instruction {
+    Instruction { program_id: PROGRAM_ID, accounts, data }
+}
+
+/// initialize, the authority only signs when it is the payer or `authority_must_sign` is set
+pub fn build_initialize(
+    payer: Pubkey,
+    authority: Pubkey,
+    gateway: Pubkey,
+    authority_must_sign: bool,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(pda::nft_program(), false),
+        AccountMeta::new(pda::treasury(), false),
+        AccountMeta::new_readonly(authority, authority_must_sign),
+        AccountMeta::new(payer, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    instruction(accounts, ix_data("initialize", &(gateway, authority_must_sign)))
+}
+
+/// mint_nft, the mint keypair and the payer sign
+pub fn build_mint_nft(keys: &MintNftKeys, params: &MintParams) -> Instruction {
+    instruction(mint_accounts(keys, params, true), ix_data("mint_nft", params))
+}
+
+/// mint_nft_pda, only the payer signs
+pub fn build_mint_nft_pda(keys: &MintNftKeys, params: &MintParams) -> Instruction {
+    instruction(mint_accounts(keys, params, false), ix_data("mint_nft_pda", params))
+}
+
+// both mint instructions take the same accounts, only the keypair mint signs
+fn mint_accounts(keys: &MintNftKeys, params: &MintParams, mint_signs: bool) -> Vec<AccountMeta> {
+    let recipient = params.recipient;
+    let token_account =
+        get_associated_token_address_with_program_id(&recipient, &keys.mint, &keys.token_program);
+    let programmable = params.token_standard == Some(NftStandard::ProgrammableNonFungible);
+    let collection_mint = pda::collection_mint();
+
+    let mut accounts = vec![
+        AccountMeta::new(pda::nft_program(), false),
+        AccountMeta::new(keys.mint, mint_signs),
+        AccountMeta::new(token_account, false),
+        AccountMeta::new_readonly(recipient, false),
+        AccountMeta::new(pda::nft_info(&keys.mint), false),
+        optional(keys.metaplex.then(|| pda::metadata(&keys.mint)), true),
+        AccountMeta::new(pda::treasury(), false),
+        optional(keys.minter_role.then(|| pda::minter_role(&keys.payer)), false),
+        AccountMeta::new(pda::owner_stats(&recipient), false),
+        AccountMeta::new(pda::mint_index(keys.index_count), false),
+        optional(programmable.then(|| pda::master_edition(&keys.mint)), true),
+        optional(programmable.then(|| pda::token_record(&keys.mint, &token_account)), true),
+        optional((programmable || keys.collection).then_some(sysvar::instructions::ID), false),
+        optional(keys.collection.then(pda::collection), true),
+        optional(keys.collection.then_some(collection_mint), false),
+        optional(keys.collection.then(|| pda::metadata(&collection_mint)), true),
+        optional(keys.collection.then(|| pda::master_edition(&collection_mint)), false),
+        AccountMeta::new(keys.payer, true),
+        AccountMeta::new_readonly(sysvar::rent::ID, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(keys.token_program, false),
+        AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        optional(keys.metaplex.then_some(mpl_token_metadata::ID), false),
+        AccountMeta::new_readonly(pda::blocked_recipient(&recipient), false),
+    ];
+    accounts.extend(event_cpi_accounts());
+    accounts
+}
+
+/// register_existing_nft, the owner signs and pays, the nft has to be in its ata
+pub fn build_register_existing_nft(keys: &LockKeys) -> Instruction {
+    let holder_token_account =
+        get_associated_token_address_with_program_id(&keys.owner, &keys.mint, &keys.token_program);
+    let accounts = vec![
+        AccountMeta::new_readonly(pda::nft_program(), false),
+        AccountMeta::new(pda::nft_info(&keys.mint), false),
+        AccountMeta::new(keys.owner, true),
+        AccountMeta::new_readonly(keys.mint, false),
+        AccountMeta::new_readonly(holder_token_account, false),
+        AccountMeta::new_readonly(pda::metadata(&keys.mint), false),
+        AccountMeta::new_readonly(keys.token_program, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        optional(keys.collection.map(|collection| pda::approved_collection(&collection)), false),
+    ];
+    instruction(accounts, ix_data("register_existing_nft", &keys.mint))
+}
+
+/// deregister_nft, the owner signs and gets the rent, the nft has to be in its ata
+pub fn build_deregister_nft(keys: &LockKeys) -> Instruction {
+    let holder_token_account =
+        get_associated_token_address_with_program_id(&keys.owner, &keys.mint, &keys.token_program);
+    let accounts = vec![
+        AccountMeta::new_readonly(pda::nft_program(), false),
+        AccountMeta::new(pda::nft_info(&keys.mint), false),
+        AccountMeta::new(keys.owner, true),
+        AccountMeta::new_readonly(keys.mint, false),
+        AccountMeta::new_readonly(holder_token_account, false),
+        AccountMeta::new_readonly(keys.token_program, false),
+    ];
+    instruction(accounts, ix_data("deregister_nft", &keys.mint))
+}
+
+/// transfer_to_zetachain, the owner signs and pays
+pub fn build_transfer_to_zetachain(keys: &LockKeys, params: &TransferParams) -> Instruction {
+    let nft_program = pda::nft_program();
+    let owner_token_account =
+        get_associated_token_address_with_program_id(&keys.owner, &keys.mint, &keys.token_program);
+    let program_token_account =
+        get_associated_token_address_with_program_id(&nft_program, &keys.mint, &keys.token_program);
+    let pnft = keys.programmable;
+    let registered = keys.collection.is_some();
+
+    let mut accounts = vec![
+        AccountMeta::new(nft_program, false),
+        AccountMeta::new_readonly(pda::chain_info(params.destination_chain_id), false),
+        AccountMeta::new(pda::treasury(), false),
+        AccountMeta::new(pda::nft_info(&keys.mint), false),
+        AccountMeta::new(keys.owner, true),
+        AccountMeta::new_readonly(keys.mint, false),
+        AccountMeta::new(owner_token_account, false),
+        AccountMeta::new(program_token_account, false),
+        optional((pnft || registered).then(|| pda::metadata(&keys.mint)), true),
+        optional(pnft.then(|| pda::master_edition(&keys.mint)), false),
+        optional(pnft.then(|| pda::token_record(&keys.mint, &owner_token_account)), true),
+        optional(pnft.then(|| pda::token_record(&keys.mint, &program_token_account)), true),
+        optional(pnft.then_some(sysvar::instructions::ID), false),
+        optional(pnft.then_some(mpl_token_metadata::ID), false),
+        AccountMeta::new_readonly(keys.token_program, false),
+        AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(pda::blocked_mint(&keys.mint), false),
+        optional(keys.collection.map(|collection| pda::approved_collection(&collection)), false),
+    ];
+    accounts.extend(fee_token_accounts(&keys.owner, keys.fee_token));
+    accounts.extend(event_cpi_accounts());
+    instruction(accounts, ix_data("transfer_to_zetachain", params))
+}
+
+/// unlock_nft, the authority signs and pays, the nft goes back to keys.owner
+pub fn build_unlock_nft(keys: &LockKeys, authority: Pubkey) -> Instruction {
+    let nft_program = pda::nft_program();
+    let owner_token_account =
+        get_associated_token_address_with_program_id(&keys.owner, &keys.mint, &keys.token_program);
+    let program_token_account =
+        get_associated_token_address_with_program_id(&nft_program, &keys.mint, &keys.token_program);
+    let pnft = keys.programmable;
+
+    let mut accounts = vec![
+        AccountMeta::new(nft_program, false),
+        AccountMeta::new(pda::nft_info(&keys.mint), false),
+        AccountMeta::new_readonly(keys.owner, false),
+        AccountMeta::new(authority, true),
+        AccountMeta::new_readonly(keys.mint, false),
+        AccountMeta::new(owner_token_account, false),
+        AccountMeta::new(program_token_account, false),
+        optional(pnft.then(|| pda::metadata(&keys.mint)), true),
+        optional(pnft.then(|| pda::master_edition(&keys.mint)), false),
+        optional(pnft.then(|| pda::token_record(&keys.mint, &owner_token_account)), true),
+        optional(pnft.then(|| pda::token_record(&keys.mint, &program_token_account)), true),
+        optional(pnft.then_some(sysvar::instructions::ID), false),
+        optional(pnft.then_some(mpl_token_metadata::ID), false),
+        optional(pnft.then_some(system_program::ID), false),
+        optional(pnft.then_some(spl_associated_token_account::ID), false),
+        AccountMeta::new_readonly(keys.token_program, false),
+        AccountMeta::new_readonly(pda::blocked_recipient(&keys.owner), false),
+    ];
+    accounts.extend(event_cpi_accounts());
+    instruction(accounts, ix_data("unlock_nft", &()))
+}
+
+// on_call, retry_dead_letter, retry_inbound, drain_pending, claim_inbound and claim_nft
+// share the accounts. the token accounts of the message type come last, after the event
+// cpi accounts: InboundTransfer (the recipient ata) or InboundUnlock (the escrow and the
+// owner ata)
+fn on_call_accounts(keys: &OnCallKeys) -> Vec<AccountMeta> {
+    let nft_program = pda::nft_program();
+    let mint = if keys.unlock { keys.origin_mint } else { pda::bridged_mint(&keys.origin_mint) };
+    let owner_token_account =
+        get_associated_token_address_with_program_id(&keys.nft_owner, &mint, &keys.token_program);
+    let program_token_account =
+        get_associated_token_address_with_program_id(&nft_program, &mint, &keys.token_program);
+    let pnft = keys.programmable;
+    let edition = pnft || (keys.metaplex && !keys.unlock);
+    let collection_mint = pda::collection_mint();
+
+    let mut accounts = vec![
+        AccountMeta::new(nft_program, false),
+        AccountMeta::new_readonly(pda::chain_info(keys.source_chain_id), false),
+        AccountMeta::new(mint, false),
+        AccountMeta::new(pda::nft_info(&mint), false),
+        optional(keys.metaplex.then(|| pda::metadata(&mint)), true),
+        optional(Some(keys.nft_owner), false),
+        // compressed mode: tree config, merkle tree, bubblegum, compression, noop
+        optional(None, true),
+        optional(None, true),
+        optional(None, false),
+        optional(None, false),
+        optional(None, false),
+        optional(edition.then(|| pda::master_edition(&mint)), true),
+        optional(pnft.then(|| pda::token_record(&mint, &owner_token_account)), true),
+        optional(pnft.then(|| pda::token_record(&mint, &program_token_account)), true),
+        optional(keys.collection.then(pda::collection), true),
+        optional(keys.collection.then_some(collection_mint), false),
+        optional(keys.collection.then(|| pda::metadata(&collection_mint)), true),
+        optional(keys.collection.then(|| pda::master_edition(&collection_mint)), false),
+        optional(keys.bridged_collection.then(pda::bridged_collection), true),
+        optional(keys.bridged_collection.then(pda::bridged_collection_mint), false),
+        optional(keys.index_count.map(pda::mint_index), true),
+        AccountMeta::new(keys.payer, true),
+        AccountMeta::new_readonly(sysvar::rent::ID, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(keys.token_program, false),
+        AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        optional(keys.metaplex.then_some(mpl_token_metadata::ID), false),
+        AccountMeta::new_readonly(sysvar::instructions::ID, false),
+        optional(keys.dead_letter.as_ref().map(pda::dead_letter), true),
+        optional(keys.pending_nonce.map(pda::pending_nonce), true),
+        optional(keys.inbound_batch.as_ref().map(pda::inbound_batch), true),
+        AccountMeta::new_readonly(pda::blocked_recipient(&keys.nft_owner), false),
+        AccountMeta::new_readonly(pda::blocked_mint(&mint), false),
+        optional(keys.pending_claim.map(pda::pending_claim), true),
+        optional(keys.failed_inbound.map(pda::failed_inbound), true),
+    ];
+    accounts.extend(event_cpi_accounts());
+    if keys.unlock {
+        accounts.push(AccountMeta::new(program_token_account, false));
+    }
+    accounts.push(AccountMeta::new(owner_token_account, false));
+    accounts
+}
+
+/// on_call as the gateway invokes it, relayers pass these accounts along with the
+/// gateway call. the payer signs
+pub fn build_on_call(keys: &OnCallKeys, message: &InboundMessage) -> Instruction {
+    instruction(on_call_accounts(keys), ix_data("on_call", message))
+}
+
+/// retry_dead_letter, same accounts as on_call with `keys.dead_letter` set to the
+/// record's message hash
+pub fn build_retry_dead_letter(keys: &OnCallKeys) -> Instruction {
+    let message_hash = keys.dead_letter.unwrap_or_default();
+    instruction(
+        on_call_accounts(keys),
+        ix_data("retry_dead_letter", &(keys.origin_mint, message_hash)),
+    )
+}
+
+/// retry_inbound, same accounts as on_call with `keys.failed_inbound` set to the
+/// nonce of the failed message, which goes along as it was delivered
+pub fn build_retry_inbound(keys: &OnCallKeys, message: &InboundMessage) -> Instruction {
+    let nonce = keys.failed_inbound.unwrap_or_default();
+    instruction(on_call_accounts(keys), ix_data("retry_inbound", &(nonce, message)))
+}
+
+/// drain_pending, same accounts as on_call with `keys.pending_nonce` set to the
+/// nonce of the held back message, which goes along as it was delivered
+pub fn build_drain_pending(keys: &OnCallKeys, message: &InboundMessage) -> Instruction {
+    instruction(on_call_accounts(keys), ix_data("drain_pending", message))
+}
+
+/// claim_inbound, same accounts as on_call for the leaf message with
+/// `keys.inbound_batch` set to the batch id. `proof` comes from `wire::batch_proof`
+pub fn build_claim_inbound(
+    keys: &OnCallKeys,
+    leaf_index: u64,
+    leaf: &[u8],
+    proof: &[[u8; 32]],
+) -> Instruction {
+    instruction(
+        on_call_accounts(keys),
+        ix_data("claim_inbound", &(leaf_index, leaf, proof)),
+    )
+}
+
+/// claim_nft, same accounts as on_call for the message with `keys.pending_claim` set
+/// to its nonce. the recipient is the payer and signs
+pub fn build_claim_nft(keys: &OnCallKeys) -> Instruction {
+    let nonce = keys.pending_claim.unwrap_or_default();
+    instruction(on_call_accounts(keys), ix_data("claim_nft", &nonce))
+}
+
+/// transfer_claim_to_zetachain, the recipient of the pending claim at `claim_nonce`
+/// signs and pays. `origin_mint` is the mint from the message the claim was made for,
+/// `fee_token` the fee mint of a destination chain that charges in a token
+pub fn build_transfer_claim_to_zetachain(
+    owner: Pubkey,
+    origin_mint: Pubkey,
+    claim_nonce: u64,
+    params: &TransferParams,
+    fee_token: Option<FeeTokenKeys>,
+) -> Instruction {
+    let mint = pda::bridged_mint(&origin_mint);
+    let mut accounts = vec![
+        AccountMeta::new(pda::nft_program(), false),
+        AccountMeta::new_readonly(pda::chain_info(params.destination_chain_id), false),
+        AccountMeta::new(pda::treasury(), false),
+        AccountMeta::new(pda::pending_claim(claim_nonce), false),
+        AccountMeta::new(owner, true),
+        AccountMeta::new_readonly(pda::blocked_mint(&mint), false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    accounts.extend(fee_token_accounts(&owner, fee_token));
+    accounts.extend(event_cpi_accounts());
+    instruction(accounts, ix_data("transfer_claim_to_zetachain", &(claim_nonce, params)))
+}
+
+/// the secp256k1 program instruction carrying the tss signature over
+/// `tss_message(hash, nonce, chain_id)`, for when the program requires one. it goes
+/// in the same transaction as the gateway call, anywhere, and only points into its
+/// own data. `index` is where it sits in the transaction
+pub fn build_tss_signature(
+    index: u8,
+    tss_address: [u8; 20],
+    signature: [u8; 64],
+    recovery_id: u8,
+    message: &[u8],
+) -> Instruction {
+    // count and offsets, then address, signature, recovery id and message
+    let (address_at, signature_at, message_at) = (12u16, 32u16, 97u16);
+    let mut data = vec![1];
+    data.extend_from_slice(&signature_at.to_le_bytes());
+    data.push(index);
+    data.extend_from_slice(&address_at.to_le_bytes());
+    data.push(index);
+    data.extend_from_slice(&message_at.to_le_bytes());
+    data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+    data.push(index);
+    data.extend_from_slice(&tss_address);
+    data.extend_from_slice(&signature);
+    data.push(recovery_id);
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: secp256k1_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+/// execute_signed_admin, the relayer signs and pays. it needs
+/// `build_admin_signature` for the same action and nonce in the transaction
+pub fn build_execute_signed_admin(relayer: Pubkey, action: &AdminAction, nonce: u64) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(pda::nft_program(), false),
+        AccountMeta::new_readonly(sysvar::instructions::ID, false),
+        AccountMeta::new_readonly(relayer, true),
+    ];
+    instruction(accounts, ix_data("execute_signed_admin", &(action, nonce)))
+}
+
+/// the ed25519 program instruction carrying the authority's signature over
+/// `admin_message(action, nonce)`, made offline. it only points into its own data
+pub fn build_admin_signature(authority: Pubkey, signature: [u8; 64], message: &[u8]) -> Instruction {
+    // count and padding, offsets, then pubkey, signature and message. u16::MAX is
+    // the instruction itself
+    let (key_at, signature_at, message_at) = (16u16, 48u16, 112u16);
+    let mut data = vec![1, 0];
+    for offset in [signature_at, u16::MAX, key_at, u16::MAX, message_at, message.len() as u16, u16::MAX] {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(authority.as_ref());
+    data.extend_from_slice(&signature);
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+// the program state and the authority, what the authority only setters take
+fn setter_accounts(authority: Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(pda::nft_program(), false),
+        AccountMeta::new_readonly(authority, true),
+    ]
+}
+
+// a record the authority opens, the payer covers its rent
+fn open_record_accounts(record: Pubkey, authority: Pubkey, payer: Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(pda::nft_program(), false),
+        AccountMeta::new(record, false),
+        AccountMeta::new_readonly(authority, true),
+        AccountMeta::new(payer, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ]
+}
+
+// a record the authority closes, the rent goes back to it
+fn close_record_accounts(record: Pubkey, authority: Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(pda::nft_program(), false),
+        AccountMeta::new(record, false),
+        AccountMeta::new(authority, true),
+    ]
+}
+
+// a chain entry the authority changes
+fn chain_accounts(authority: Pubkey, chain_id: u64) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(pda::nft_program(), false),
+        AccountMeta::new(pda::chain_info(chain_id), false),
+        AccountMeta::new_readonly(authority, true),
+    ]
+}
+
+/// set_max_supply, the authority signs
+pub fn build_set_max_supply(authority: Pubkey, max_supply: u64) -> Instruction {
+    instruction(setter_accounts(authority), ix_data("set_max_supply", &max_supply))
+}
+
+/// set_allow_program_recipients, the authority signs
+pub fn build_set_allow_program_recipients(authority: Pubkey, allow: bool) -> Instruction {
+    instruction(setter_accounts(authority), ix_data("set_allow_program_recipients", &allow))
+}
+
+/// set_dead_letter_retry, the authority signs
+pub fn build_set_dead_letter_retry(authority: Pubkey, retry: DeadLetterRetry) -> Instruction {
+    instruction(setter_accounts(authority), ix_data("set_dead_letter_retry", &retry))
+}
+
+/// set_uri_rewrite, the authority signs, `None` turns it off
+pub fn build_set_uri_rewrite(authority: Pubkey, uri_rewrite: Option<&UriRewrite>) -> Instruction {
+    instruction(setter_accounts(authority), ix_data("set_uri_rewrite", &uri_rewrite))
+}
+
+/// set_placeholder_uri, the authority signs
+pub fn build_set_placeholder_uri(authority: Pubkey, placeholder_uri: &str) -> Instruction {
+    instruction(setter_accounts(authority), ix_data("set_placeholder_uri", &placeholder_uri))
+}
+
+/// set_inbound_metadata_mutable, the authority signs
+pub fn build_set_inbound_metadata_mutable(
+    authority: Pubkey,
+    inbound_metadata_mutable: bool,
+) -> Instruction {
+    instruction(
+        setter_accounts(authority),
+        ix_data("set_inbound_metadata_mutable", &inbound_metadata_mutable),
+    )
+}
+
+/// set_lazy_metadata, the authority signs
+pub fn build_set_lazy_metadata(authority: Pubkey, lazy_metadata: bool) -> Instruction {
+    instruction(setter_accounts(authority), ix_data("set_lazy_metadata", &lazy_metadata))
+}
+
+/// set_ordered_delivery, the authority signs
+pub fn build_set_ordered_delivery(authority: Pubkey, enabled: bool) -> Instruction {
+    instruction(setter_accounts(authority), ix_data("set_ordered_delivery", &enabled))
+}
+
+/// set_claim_mode, the authority signs
+pub fn build_set_claim_mode(authority: Pubkey, claim_mode: bool) -> Instruction {
+    instruction(setter_accounts(authority), ix_data("set_claim_mode", &claim_mode))
+}
+
+/// set_tss_address, the authority signs
+pub fn build_set_tss_address(authority: Pubkey, tss_address: [u8; 20]) -> Instruction {
+    instruction(setter_accounts(authority), ix_data("set_tss_address", &tss_address))
+}
+
+/// set_require_tss_signature, the authority signs
+pub fn build_set_require_tss_signature(authority: Pubkey, required: bool) -> Instruction {
+    instruction(setter_accounts(authority), ix_data("set_require_tss_signature", &required))
+}
+
+/// skip_message, the authority signs, the payer covers the skipped message record
+pub fn build_skip_message(
+    authority: Pubkey,
+    payer: Pubkey,
+    nonce: u64,
+    reason: &str,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(pda::nft_program(), false),
+        AccountMeta::new(pda::skipped_message(nonce), false),
+        AccountMeta::new_readonly(authority, true),
+        AccountMeta::new(payer, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    instruction(accounts, ix_data("skip_message", &(nonce, reason)))
+}
+
+/// block_recipient, the authority signs, the payer covers the blocked recipient record
+pub fn build_block_recipient(authority: Pubkey, payer: Pubkey, recipient: Pubkey) -> Instruction {
+    let accounts = open_record_accounts(pda::blocked_recipient(&recipient), authority, payer);
+    instruction(accounts, ix_data("block_recipient", &recipient))
+}
+
+/// unblock_recipient, the authority signs and gets the rent of the record back
+pub fn build_unblock_recipient(authority: Pubkey, recipient: Pubkey) -> Instruction {
+    let accounts = close_record_accounts(pda::blocked_recipient(&recipient), authority);
+    instruction(accounts, ix_data("unblock_recipient", &recipient))
+}
+
+/// block_mint, the authority signs, the payer covers the blocked mint record
+pub fn build_block_mint(
+    authority: Pubkey,
+    payer: Pubkey,
+    mint: Pubkey,
+    reason: &str,
+) -> Instruction {
+    let accounts = open_record_accounts(pda::blocked_mint(&mint), authority, payer);
+    instruction(accounts, ix_data("block_mint", &(mint, reason)))
+}
+
+/// unblock_mint, the authority signs and gets the rent of the record back
+pub fn build_unblock_mint(authority: Pubkey, mint: Pubkey, reason: &str) -> Instruction {
+    let accounts = close_record_accounts(pda::blocked_mint(&mint), authority);
+    instruction(accounts, ix_data("unblock_mint", &(mint, reason)))
+}
+
+/// approve_collection, the authority signs, the payer covers the approval record
+pub fn build_approve_collection(
+    authority: Pubkey,
+    payer: Pubkey,
+    collection_mint: Pubkey,
+) -> Instruction {
+    let approved_collection = pda::approved_collection(&collection_mint);
+    let accounts = open_record_accounts(approved_collection, authority, payer);
+    instruction(accounts, ix_data("approve_collection", &collection_mint))
+}
+
+/// revoke_collection, the authority signs and gets the rent of the record back
+pub fn build_revoke_collection(authority: Pubkey, collection_mint: Pubkey) -> Instruction {
+    let accounts = close_record_accounts(pda::approved_collection(&collection_mint), authority);
+    instruction(accounts, ix_data("revoke_collection", &collection_mint))
+}
+
+/// register_chain, the authority signs, the payer covers the chain entry
+pub fn build_register_chain(
+    authority: Pubkey,
+    payer: Pubkey,
+    chain_id: u64,
+    config: &ChainConfig,
+) -> Instruction {
+    let accounts = open_record_accounts(pda::chain_info(chain_id), authority, payer);
+    instruction(accounts, ix_data("register_chain", &(chain_id, config)))
+}
+
+/// update_chain, the authority signs
+pub fn build_update_chain(authority: Pubkey, chain_id: u64, config: &ChainConfig) -> Instruction {
+    instruction(chain_accounts(authority, chain_id), ix_data("update_chain", &(chain_id, config)))
+}
+
+/// set_chain_inbound, the authority signs
+pub fn build_set_chain_inbound(
+    authority: Pubkey,
+    chain_id: u64,
+    inbound_enabled: bool,
+) -> Instruction {
+    let data = ix_data("set_chain_inbound", &(chain_id, inbound_enabled));
+    instruction(chain_accounts(authority, chain_id), data)
+}
+
+/// set_chain_inbound_paused, the authority signs
+pub fn build_set_chain_inbound_paused(
+    authority: Pubkey,
+    chain_id: u64,
+    inbound_paused: bool,
+) -> Instruction {
+    let data = ix_data("set_chain_inbound_paused", &(chain_id, inbound_paused));
+    instruction(chain_accounts(authority, chain_id), data)
+}
+
+/// get_nft_info, simulate it and pass the return data to `decode_nft_info`
+pub fn build_get_nft_info(mint: Pubkey) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(pda::nft_program(), false),
+        AccountMeta::new_readonly(pda::nft_info(&mint), false),
+    ];
+    instruction(accounts, ix_data("get_nft_info", &mint))
+}
+
+/// decodes what get_nft_info returned, the return data of the simulation
+pub fn decode_nft_info(return_data: &[u8]) -> std::io::Result<NftInfoView> {
+    NftInfoView::try_from_slice(return_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{InstructionData, ToAccountMetas};
+    use universal_nft::{accounts, instruction as ix};
+
+    #[test]
+    fn test_get_nft_info_matches_anchor() {
+        let built = build_get_nft_info(key(2));
+        let expected = accounts::GetNftInfo {
+            nft_program: pda::nft_program(),
+            nft_info: pda::nft_info(&key(2)),
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::GetNftInfo { mint: key(2) }.data());
+
+        let view = NftInfoView { version: 1, mint: key(2), status: NftInfoStatus::NotFound };
+        assert_eq!(decode_nft_info(&view.try_to_vec().unwrap()).unwrap(), view);
+    }
+
+    fn spl_token_id() -> Pubkey {
+        solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf3Ss623VQ5DA")
+    }
+
+    fn key(n: u8) -> Pubkey {
+        Pubkey::new_from_array([n; 32])
+    }
+
+    fn mint_params() -> MintParams {
+        MintParams {
+            name: "Test".to_string(),
+            symbol: "TST".to_string(),
+            uri: "https://example.com/1.json".to_string(),
+            recipient: key(3),
+            token_standard: Some(NftStandard::ProgrammableNonFungible),
+            creators: Some(vec![NftCreator { address: key(4), share: 100 }]),
+            metadata_hash: Some([7; 32]),
+            attributes: None,
+            royalty_bps: Some(500),
+        }
+    }
+
+    #[test]
+    fn test_sighash_matches_anchor() {
+        assert_eq!(sighash("initialize").to_vec(), ix::Initialize { gateway: key(1), authority_must_sign: false }.data()[..8]);
+        assert_eq!(sighash("on_call").to_vec(), ix::OnCall { message: InboundMessage { amount: 0, sender: [0; 20], data: vec![] } }.data()[..8]);
+    }
+
+    #[test]
+    fn test_initialize_matches_anchor() {
+        let built = build_initialize(key(1), key(2), key(9), false);
+        let expected = accounts::Initialize {
+            nft_program: pda::nft_program(),
+            treasury: pda::treasury(),
+            authority: key(2),
+            payer: key(1),
+            system_program: system_program::ID,
+        };
+        assert_eq!(built.program_id, PROGRAM_ID);
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::Initialize { gateway: key(9), authority_must_sign: false }.data());
+
+        // anchor cant know the authority signs, the builder marks it
+        let signed = build_initialize(key(1), key(2), key(9), true);
+        assert!(signed.accounts[2].is_signer);
+    }
+
+    #[test]
+    fn test_mint_nft_matches_anchor() {
+        let params = mint_params();
+        let keys = MintNftKeys {
+            payer: key(1),
+            mint: key(2),
+            token_program: spl_token_id(),
+            index_count: 5,
+            metaplex: true,
+            minter_role: true,
+            collection: true,
+        };
+        let built = build_mint_nft(&keys, &params);
+
+        let token_account = get_associated_token_address_with_program_id(&key(3), &key(2), &spl_token_id());
+        let collection_mint = pda::collection_mint();
+        let expected = accounts::MintNft {
+            nft_program: pda::nft_program(),
+            mint: key(2),
+            token_account,
+            recipient: key(3),
+            nft_info: pda::nft_info(&key(2)),
+            metadata: Some(pda::metadata(&key(2))),
+            treasury: pda::treasury(),
+            minter_role: Some(pda::minter_role(&key(1))),
+            owner_stats: pda::owner_stats(&key(3)),
+            mint_index: pda::mint_index(5),
+            master_edition: Some(pda::master_edition(&key(2))),
+            token_record: Some(pda::token_record(&key(2), &token_account)),
+            sysvar_instructions: Some(sysvar::instructions::ID),
+            collection: Some(pda::collection()),
+            collection_mint: Some(collection_mint),
+            collection_metadata: Some(pda::metadata(&collection_mint)),
+            collection_master_edition: Some(pda::master_edition(&collection_mint)),
+            payer: key(1),
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: spl_token_id(),
+            associated_token_program: spl_associated_token_account::ID,
+            token_metadata_program: Some(mpl_token_metadata::ID),
+            blocked_recipient: pda::blocked_recipient(&key(3)),
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        };
+        // the mint is an unchecked account to anchor, the builder marks it as signing
+        let mut metas = expected.to_account_metas(None);
+        metas[1].is_signer = true;
+        assert_eq!(built.accounts, metas);
+        assert_eq!(
+            built.data,
+            ix::MintNft { params }.data()
+        );
+    }
+
+    #[test]
+    fn test_mint_nft_pda_matches_anchor() {
+        let params = MintParams { token_standard: None, ..mint_params() };
+        let mint = pda::pda_mint(4);
+        let keys = MintNftKeys { payer: key(1), mint, token_program: spl_token_id(), ..Default::default() };
+        let built = build_mint_nft_pda(&keys, &params);
+
+        let token_account = get_associated_token_address_with_program_id(&key(3), &mint, &spl_token_id());
+        let expected = accounts::MintNftPda {
+            nft_program: pda::nft_program(),
+            mint,
+            token_account,
+            recipient: key(3),
+            nft_info: pda::nft_info(&mint),
+            metadata: None,
+            treasury: pda::treasury(),
+            minter_role: None,
+            owner_stats: pda::owner_stats(&key(3)),
+            mint_index: pda::mint_index(0),
+            master_edition: None,
+            token_record: None,
+            sysvar_instructions: None,
+            collection: None,
+            collection_mint: None,
+            collection_metadata: None,
+            collection_master_edition: None,
+            payer: key(1),
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: spl_token_id(),
+            associated_token_program: spl_associated_token_account::ID,
+            token_metadata_program: None,
+            blocked_recipient: pda::blocked_recipient(&key(3)),
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::MintNftPda { params }.data());
+    }
+
+    #[test]
+    fn test_mint_nft_leaves_unused_slots_empty() {
+        let params = MintParams { token_standard: None, ..mint_params() };
+        let keys = MintNftKeys { payer: key(1), mint: key(2), token_program: spl_token_id(), ..Default::default() };
+        let built = build_mint_nft(&keys, &params);
+        // metadata, minter role, pnft accounts, sysvar and the collection are all None
+        for i in [5, 7, 10, 11, 12, 13, 14, 15, 16, 22] {
+            assert_eq!(built.accounts[i], AccountMeta::new_readonly(PROGRAM_ID, false), "slot {i}");
+        }
+    }
+
+    #[test]
+    fn test_register_existing_nft_matches_anchor() {
+        let keys = LockKeys {
+            owner: key(1),
+            mint: key(2),
+            token_program: spl_token_id(),
+            programmable: false,
+            collection: Some(key(3)),
+            fee_token: None,
+        };
+        let built = build_register_existing_nft(&keys);
+
+        let expected = accounts::RegisterExistingNft {
+            nft_program: pda::nft_program(),
+            nft_info: pda::nft_info(&key(2)),
+            holder: key(1),
+            mint: key(2),
+            holder_token_account: get_associated_token_address_with_program_id(&key(1), &key(2), &spl_token_id()),
+            metadata: pda::metadata(&key(2)),
+            token_program: spl_token_id(),
+            system_program: system_program::ID,
+            approved_collection: Some(pda::approved_collection(&key(3))),
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::RegisterExistingNft { mint: key(2) }.data());
+    }
+
+    #[test]
+    fn test_deregister_nft_matches_anchor() {
+        let keys = LockKeys {
+            owner: key(1),
+            mint: key(2),
+            token_program: spl_token_id(),
+            programmable: false,
+            collection: None,
+            fee_token: None,
+        };
+        let built = build_deregister_nft(&keys);
+
+        let expected = accounts::DeregisterNft {
+            nft_program: pda::nft_program(),
+            nft_info: pda::nft_info(&key(2)),
+            holder: key(1),
+            mint: key(2),
+            holder_token_account: get_associated_token_address_with_program_id(&key(1), &key(2), &spl_token_id()),
+            token_program: spl_token_id(),
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::DeregisterNft { mint: key(2) }.data());
+    }
+
+    #[test]
+    fn test_transfer_to_zetachain_matches_anchor() {
+        let keys = LockKeys {
+            owner: key(1),
+            mint: key(2),
+            token_program: spl_token_id(),
+            programmable: false,
+            collection: None,
+            fee_token: None,
+        };
+        let params = TransferParams { destination_chain_id: 7001, recipient: [5; 32], nonce: 0 };
+        let built = build_transfer_to_zetachain(&keys, &params);
+
+        let expected = accounts::TransferToZetachain {
+            nft_program: pda::nft_program(),
+            chain_info: pda::chain_info(7001),
+            treasury: pda::treasury(),
+            nft_info: pda::nft_info(&key(2)),
+            owner: key(1),
+            mint: key(2),
+            owner_token_account: get_associated_token_address_with_program_id(&key(1), &key(2), &spl_token_id()),
+            program_token_account: get_associated_token_address_with_program_id(&pda::nft_program(), &key(2), &spl_token_id()),
+            metadata: None,
+            master_edition: None,
+            owner_token_record: None,
+            program_token_record: None,
+            sysvar_instructions: None,
+            token_metadata_program: None,
+            token_program: spl_token_id(),
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: system_program::ID,
+            blocked_mint: pda::blocked_mint(&key(2)),
+            approved_collection: None,
+            fee_mint: None,
+            owner_fee_token_account: None,
+            fee_vault: None,
+            fee_token_program: None,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::TransferToZetachain { params }.data());
+
+        // a registered nft adds its metadata and the approval of its collection
+        let registered = LockKeys { collection: Some(key(3)), ..keys };
+        let built = build_transfer_to_zetachain(&registered, &params);
+        let expected = accounts::TransferToZetachain {
+            metadata: Some(pda::metadata(&key(2))),
+            approved_collection: Some(pda::approved_collection(&key(3))),
+            ..expected
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+
+        // a chain with a token fee adds the fee mint, the owner's ata of it and the vault
+        let fee_token = FeeTokenKeys { mint: key(4), token_program: spl_token_id() };
+        let paying = LockKeys { fee_token: Some(fee_token), ..registered };
+        let built = build_transfer_to_zetachain(&paying, &params);
+        let expected = accounts::TransferToZetachain {
+            fee_mint: Some(key(4)),
+            owner_fee_token_account: Some(get_associated_token_address_with_program_id(&key(1), &key(4), &spl_token_id())),
+            fee_vault: Some(pda::fee_vault(&key(4), &spl_token_id())),
+            fee_token_program: Some(spl_token_id()),
+            ..expected
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+    }
+
+    #[test]
+    fn test_transfer_claim_to_zetachain_matches_anchor() {
+        let params = TransferParams { destination_chain_id: 7001, recipient: [5; 32], nonce: 0 };
+        let built = build_transfer_claim_to_zetachain(key(1), key(2), 4, &params, None);
+
+        let expected = accounts::TransferClaimToZetachain {
+            nft_program: pda::nft_program(),
+            chain_info: pda::chain_info(7001),
+            treasury: pda::treasury(),
+            pending_claim: pda::pending_claim(4),
+            owner: key(1),
+            blocked_mint: pda::blocked_mint(&pda::bridged_mint(&key(2))),
+            system_program: system_program::ID,
+            fee_mint: None,
+            owner_fee_token_account: None,
+            fee_vault: None,
+            fee_token_program: None,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::TransferClaimToZetachain { claim_nonce: 4, params }.data());
+    }
+
+    #[test]
+    fn test_unlock_nft_matches_anchor() {
+        let keys = LockKeys {
+            owner: key(1),
+            mint: key(2),
+            token_program: spl_token_id(),
+            programmable: true,
+            collection: None,
+            fee_token: None,
+        };
+        let built = build_unlock_nft(&keys, key(3));
+
+        let owner_token_account = get_associated_token_address_with_program_id(&key(1), &key(2), &spl_token_id());
+        let program_token_account = get_associated_token_address_with_program_id(&pda::nft_program(), &key(2), &spl_token_id());
+        let expected = accounts::UnlockNft {
+            nft_program: pda::nft_program(),
+            nft_info: pda::nft_info(&key(2)),
+            owner: key(1),
+            authority: key(3),
+            mint: key(2),
+            owner_token_account,
+            program_token_account,
+            metadata: Some(pda::metadata(&key(2))),
+            master_edition: Some(pda::master_edition(&key(2))),
+            owner_token_record: Some(pda::token_record(&key(2), &owner_token_account)),
+            program_token_record: Some(pda::token_record(&key(2), &program_token_account)),
+            sysvar_instructions: Some(sysvar::instructions::ID),
+            token_metadata_program: Some(mpl_token_metadata::ID),
+            system_program: Some(system_program::ID),
+            associated_token_program: Some(spl_associated_token_account::ID),
+            token_program: spl_token_id(),
+            blocked_recipient: pda::blocked_recipient(&key(1)),
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::UnlockNft.data());
+    }
+
+    #[test]
+    fn test_tss_signature_points_into_its_own_data() {
+        let message = tss_message(&[1; 32], 2, 7001);
+        let built = build_tss_signature(3, [4; 20], [5; 64], 1, &message);
+
+        assert_eq!(built.program_id, secp256k1_program::ID);
+        assert!(built.accounts.is_empty());
+        let data = &built.data;
+        assert_eq!(data[0], 1);
+        // all three offsets name instruction 3
+        assert_eq!((data[3], data[6], data[11]), (3, 3, 3));
+        let at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]) as usize;
+        assert_eq!(data[at(1)..at(1) + 64], [5; 64]);
+        assert_eq!(data[at(1) + 64], 1);
+        assert_eq!(data[at(4)..at(4) + 20], [4; 20]);
+        assert_eq!(&data[at(7)..at(7) + at(9)], &message[..]);
+        assert_eq!(data.len(), 97 + message.len());
+    }
+
+    #[test]
+    fn test_execute_signed_admin_matches_anchor() {
+        let action = AdminAction::SetMintFee { mint_fee_lamports: 5 };
+        let built = build_execute_signed_admin(key(1), &action, 3);
+        let expected = accounts::ExecuteSignedAdmin {
+            nft_program: pda::nft_program(),
+            instruction_sysvar_account: sysvar::instructions::ID,
+            relayer: key(1),
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::ExecuteSignedAdmin { action, nonce: 3 }.data());
+    }
+
+    #[test]
+    fn test_admin_signature_points_into_its_own_data() {
+        let message = admin_message(&AdminAction::SetMintPaused { mint_paused: true }, 0);
+        let built = build_admin_signature(key(4), [5; 64], &message);
+
+        assert_eq!(built.program_id, ed25519_program::ID);
+        assert!(built.accounts.is_empty());
+        let data = &built.data;
+        assert_eq!(data[..2], [1, 0]);
+        let at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]) as usize;
+        assert_eq!((at(4), at(8), at(14)), (0xffff, 0xffff, 0xffff));
+        assert_eq!(data[at(2)..at(2) + 64], [5; 64]);
+        assert_eq!(data[at(6)..at(6) + 32], key(4).to_bytes());
+        assert_eq!(&data[at(10)..at(10) + at(12)], &message[..]);
+        assert_eq!(data.len(), 112 + message.len());
+    }
+
+    #[test]
+    fn test_on_call_retry_drain_and_claim_match_anchor() {
+        let keys = OnCallKeys {
+            payer: key(1),
+            source_chain_id: 7001,
+            origin_mint: key(2),
+            unlock: false,
+            token_program: spl_token_id(),
+            nft_owner: key(3),
+            metaplex: true,
+            programmable: false,
+            index_count: Some(4),
+            collection: false,
+            bridged_collection: true,
+            dead_letter: Some([8; 32]),
+            pending_nonce: Some(9),
+            inbound_batch: Some(key(5)),
+            pending_claim: Some(6),
+            failed_inbound: Some(10),
+        };
+        let mint = pda::bridged_mint(&key(2));
+        let recipient_ata = get_associated_token_address_with_program_id(&key(3), &mint, &spl_token_id());
+        let expected = accounts::OnCall {
+            nft_program: pda::nft_program(),
+            chain_info: pda::chain_info(7001),
+            mint,
+            nft_info: pda::nft_info(&mint),
+            metadata: Some(pda::metadata(&mint)),
+            nft_owner: Some(key(3)),
+            tree_config: None,
+            merkle_tree: None,
+            bubblegum_program: None,
+            compression_program: None,
+            log_wrapper: None,
+            master_edition: Some(pda::master_edition(&mint)),
+            owner_token_record: None,
+            program_token_record: None,
+            collection: None,
+            collection_mint: None,
+            collection_metadata: None,
+            collection_master_edition: None,
+            bridged_collection: Some(pda::bridged_collection()),
+            bridged_collection_mint: Some(pda::bridged_collection_mint()),
+            mint_index: Some(pda::mint_index(4)),
+            payer: key(1),
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: spl_token_id(),
+            associated_token_program: spl_associated_token_account::ID,
+            token_metadata_program: Some(mpl_token_metadata::ID),
+            instruction_sysvar_account: sysvar::instructions::ID,
+            dead_letter: Some(pda::dead_letter(&[8; 32])),
+            pending_nonce: Some(pda::pending_nonce(9)),
+            inbound_batch: Some(pda::inbound_batch(&key(5))),
+            blocked_recipient: pda::blocked_recipient(&key(3)),
+            blocked_mint: pda::blocked_mint(&mint),
+            pending_claim: Some(pda::pending_claim(6)),
+            failed_inbound: Some(pda::failed_inbound(10)),
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        };
+
+        let mut expected = expected.to_account_metas(None);
+        // InboundTransfer follows the on_call accounts, anchor keeps its client struct
+        // private to the program crate
+        expected.push(AccountMeta::new(recipient_ata, false));
+
+        let message = InboundMessage { amount: 0, sender: [6; 20], data: vec![1, 2, 3] };
+        let built = build_on_call(&keys, &message);
+        assert_eq!(built.accounts, expected);
+        assert_eq!(built.data, ix::OnCall { message: message.clone() }.data());
+
+        let retry = build_retry_dead_letter(&keys);
+        assert_eq!(retry.accounts, expected);
+        assert_eq!(retry.data, ix::RetryDeadLetter { mint_key: key(2), message_hash: [8; 32] }.data());
+
+        let retry = build_retry_inbound(&keys, &message);
+        assert_eq!(retry.accounts, expected);
+        assert_eq!(retry.data, ix::RetryInbound { nonce: 10, message: message.clone() }.data());
+
+        let drain = build_drain_pending(&keys, &message);
+        assert_eq!(drain.accounts, expected);
+        assert_eq!(drain.data, ix::DrainPending { message }.data());
+
+        let proof = [[1; 32], [2; 32]];
+        let claim = build_claim_inbound(&keys, 3, &[4, 5], &proof);
+        assert_eq!(claim.accounts, expected);
+        assert_eq!(
+            claim.data,
+            ix::ClaimInbound { leaf_index: 3, leaf: vec![4, 5], proof: proof.to_vec() }.data()
+        );
+
+        let claim = build_claim_nft(&keys);
+        assert_eq!(claim.accounts, expected);
+        assert_eq!(claim.data, ix::ClaimNft { nonce: 6 }.data());
+
+        // an unlock names the local mint itself
+        let unlock = OnCallKeys { unlock: true, index_count: None, ..keys };
+        let built = build_on_call(&unlock, &InboundMessage { amount: 0, sender: [6; 20], data: vec![] });
+        assert_eq!(built.accounts[2], AccountMeta::new(key(2), false));
+        assert_eq!(built.accounts[3], AccountMeta::new(pda::nft_info(&key(2)), false));
+        // and passes the escrow and the owner ata instead of the recipient ata
+        let escrow = get_associated_token_address_with_program_id(&pda::nft_program(), &key(2), &spl_token_id());
+        let owner_ata = get_associated_token_address_with_program_id(&key(3), &key(2), &spl_token_id());
+        assert_eq!(built.accounts.len(), expected.len() + 1);
+        assert_eq!(
+            built.accounts[expected.len() - 1..],
+            [AccountMeta::new(escrow, false), AccountMeta::new(owner_ata, false)]
+        );
+        // the master edition exists by then, only a pnft unlock needs it
+        assert_eq!(built.accounts[11], AccountMeta::new_readonly(PROGRAM_ID, false));
+    }
+
+    fn chain_config() -> ChainConfig {
+        ChainConfig {
+            name: "ZetaChain".to_string(),
+            chain_kind: ChainKind::Evm,
+            enabled: true,
+            recipient_len: 20,
+            connected: [6; 32],
+        }
+    }
+
+    #[test]
+    fn test_setters_match_anchor() {
+        let setter = || accounts::SetMaxSupply { nft_program: pda::nft_program(), authority: key(1) };
+        let rewrite = UriRewrite { pattern: "ipfs://".to_string(), replacement: "https://gw/".to_string() };
+        let cases = [
+            (build_set_max_supply(key(1), 9), ix::SetMaxSupply { max_supply: 9 }.data()),
+            (
+                build_set_allow_program_recipients(key(1), true),
+                ix::SetAllowProgramRecipients { allow: true }.data(),
+            ),
+            (
+                build_set_dead_letter_retry(key(1), DeadLetterRetry::Anyone),
+                ix::SetDeadLetterRetry { retry: DeadLetterRetry::Anyone }.data(),
+            ),
+            (
+                build_set_uri_rewrite(key(1), Some(&rewrite)),
+                ix::SetUriRewrite { uri_rewrite: Some(rewrite.clone()) }.data(),
+            ),
+            (build_set_uri_rewrite(key(1), None), ix::SetUriRewrite { uri_rewrite: None }.data()),
+            (
+                build_set_placeholder_uri(key(1), "https://example.com/missing.json"),
+                ix::SetPlaceholderUri { placeholder_uri: "https://example.com/missing.json".to_string() }.data(),
+            ),
+            (
+                build_set_inbound_metadata_mutable(key(1), true),
+                ix::SetInboundMetadataMutable { inbound_metadata_mutable: true }.data(),
+            ),
+            (build_set_lazy_metadata(key(1), true), ix::SetLazyMetadata { lazy_metadata: true }.data()),
+            (build_set_ordered_delivery(key(1), true), ix::SetOrderedDelivery { enabled: true }.data()),
+            (build_set_claim_mode(key(1), true), ix::SetClaimMode { claim_mode: true }.data()),
+            (build_set_tss_address(key(1), [4; 20]), ix::SetTssAddress { tss_address: [4; 20] }.data()),
+            (
+                build_set_require_tss_signature(key(1), true),
+                ix::SetRequireTssSignature { required: true }.data(),
+            ),
+        ];
+        // every setter takes the same two accounts
+        for (built, data) in cases {
+            assert_eq!(built.program_id, PROGRAM_ID);
+            assert_eq!(built.accounts, setter().to_account_metas(None));
+            assert_eq!(built.data, data);
+        }
+    }
+
+    #[test]
+    fn test_skip_message_matches_anchor() {
+        let built = build_skip_message(key(1), key(2), 7, "bad payload");
+        let expected = accounts::SkipMessage {
+            nft_program: pda::nft_program(),
+            skipped_message: seeds::find_skipped_message(7).0,
+            authority: key(1),
+            payer: key(2),
+            system_program: system_program::ID,
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::SkipMessage { nonce: 7, reason: "bad payload".to_string() }.data());
+    }
+
+    #[test]
+    fn test_block_lists_match_anchor() {
+        let built = build_block_recipient(key(1), key(2), key(3));
+        let expected = accounts::BlockRecipient {
+            nft_program: pda::nft_program(),
+            blocked_recipient: pda::blocked_recipient(&key(3)),
+            authority: key(1),
+            payer: key(2),
+            system_program: system_program::ID,
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::BlockRecipient { recipient: key(3) }.data());
+
+        let built = build_unblock_recipient(key(1), key(3));
+        let expected = accounts::UnblockRecipient {
+            nft_program: pda::nft_program(),
+            blocked_recipient: pda::blocked_recipient(&key(3)),
+            authority: key(1),
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::UnblockRecipient { recipient: key(3) }.data());
+
+        let built = build_block_mint(key(1), key(2), key(4), "stolen");
+        let expected = accounts::BlockMint {
+            nft_program: pda::nft_program(),
+            blocked_mint: pda::blocked_mint(&key(4)),
+            authority: key(1),
+            payer: key(2),
+            system_program: system_program::ID,
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::BlockMint { mint: key(4), reason: "stolen".to_string() }.data());
+
+        let built = build_unblock_mint(key(1), key(4), "recovered");
+        let expected = accounts::UnblockMint {
+            nft_program: pda::nft_program(),
+            blocked_mint: pda::blocked_mint(&key(4)),
+            authority: key(1),
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::UnblockMint { mint: key(4), reason: "recovered".to_string() }.data());
+    }
+
+    #[test]
+    fn test_collection_approval_matches_anchor() {
+        let built = build_approve_collection(key(1), key(2), key(5));
+        let expected = accounts::ApproveCollection {
+            nft_program: pda::nft_program(),
+            approved_collection: pda::approved_collection(&key(5)),
+            authority: key(1),
+            payer: key(2),
+            system_program: system_program::ID,
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::ApproveCollection { collection_mint: key(5) }.data());
+
+        let built = build_revoke_collection(key(1), key(5));
+        let expected = accounts::RevokeCollection {
+            nft_program: pda::nft_program(),
+            approved_collection: pda::approved_collection(&key(5)),
+            authority: key(1),
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::RevokeCollection { collection_mint: key(5) }.data());
+    }
+
+    #[test]
+    fn test_chain_entries_match_anchor() {
+        let built = build_register_chain(key(1), key(2), 7001, &chain_config());
+        let expected = accounts::RegisterChain {
+            nft_program: pda::nft_program(),
+            chain_info: pda::chain_info(7001),
+            authority: key(1),
+            payer: key(2),
+            system_program: system_program::ID,
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::RegisterChain { chain_id: 7001, config: chain_config() }.data());
+
+        let built = build_update_chain(key(1), 7001, &chain_config());
+        let expected = accounts::UpdateChain {
+            nft_program: pda::nft_program(),
+            chain_info: pda::chain_info(7001),
+            authority: key(1),
+        };
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::UpdateChain { chain_id: 7001, config: chain_config() }.data());
+
+        let expected = accounts::SetChainInbound {
+            nft_program: pda::nft_program(),
+            chain_info: pda::chain_info(7001),
+            authority: key(1),
+        };
+        let built = build_set_chain_inbound(key(1), 7001, false);
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::SetChainInbound { chain_id: 7001, inbound_enabled: false }.data());
+
+        // paused shares the accounts of set_chain_inbound, only the discriminator differs
+        let built = build_set_chain_inbound_paused(key(1), 7001, true);
+        assert_eq!(built.accounts, expected.to_account_metas(None));
+        assert_eq!(built.data, ix::SetChainInboundPaused { chain_id: 7001, inbound_paused: true }.data());
+        assert_ne!(built.data[..8], build_set_chain_inbound(key(1), 7001, true).data[..8]);
+    }
+}
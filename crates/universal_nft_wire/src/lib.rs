@@ -0,0 +1,777 @@
+//! wire format of the universal nft program: the crosschain message, its layout
+//! version and the canonical hash. no_std (alloc only) and no solana deps, so
+//! relayers and other off-chain tools can encode and decode messages without
+//! anchor. the program re-exports this crate and uses these types as they are,
+//! with `Pubkey` as the address type
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use borsh::maybestd::io;
+#[cfg(not(feature = "anchor"))]
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha3::{Digest, Keccak256};
+
+#[cfg(feature = "anchor")]
+use anchor_lang::{AnchorDeserialize as BorshDeserialize, AnchorSerialize as BorshSerialize};
+
+/// layout of CrossChainMessage, bump it with every change to the layout below
+pub const MESSAGE_VERSION: u8 = 1;
+
+/// solana addresses in the message, the program uses `Pubkey` which encodes the same
+pub type Address = [u8; 32];
+
+// caps CrossChainMessage::decode enforces before it allocates anything. they only
+// bound the work an untrusted message can cause, the program checks the fields
+// against its own tighter limits afterwards. names, symbols and attributes get room
+// above those so a long one still fails (or gets cut down) with the programs own error
+pub const MAX_MESSAGE_URI_LEN: usize = 1024; // the highest max_uri_len the program allows
+pub const MAX_MESSAGE_NAME_LEN: usize = 128;
+pub const MAX_MESSAGE_SYMBOL_LEN: usize = 64;
+pub const MAX_MESSAGE_ATTRIBUTES: usize = 16;
+pub const MAX_MESSAGE_ATTRIBUTE_KEY_LEN: usize = 64;
+pub const MAX_MESSAGE_ATTRIBUTE_VALUE_LEN: usize = 128;
+
+/// largest encoded message, every string and the attribute list at its cap
+pub const MAX_MESSAGE_LEN: usize = 1 // message_type
+    + 32 // mint
+    + 32 // recipient
+    + 4 + MAX_MESSAGE_URI_LEN
+    + 4 + MAX_MESSAGE_NAME_LEN
+    + 4 + MAX_MESSAGE_SYMBOL_LEN
+    + 8 // nonce
+    + 32 // master_mint
+    + 8 // edition
+    + 1 + 32 // metadata_hash
+    + 1 + 2 // royalty_bps
+    + 1 + 32 // royalty_recipient
+    + 4 + MAX_MESSAGE_ATTRIBUTES
+        * (4 + MAX_MESSAGE_ATTRIBUTE_KEY_LEN + 4 + MAX_MESSAGE_ATTRIBUTE_VALUE_LEN);
+
+/// why CrossChainMessage::decode turned the bytes away
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    TooLarge, // over MAX_MESSAGE_LEN, or a length prefix over its cap
+    Invalid,  // anything else borsh would reject too
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageType {
+    Transfer,
+    Unlock,
+    // root of an inbound batch tree, the transfers in it get claimed one by one. the
+    // fields mean something else here: mint is the batch id, metadata_hash the root
+    // and edition the number of leaves, see batch_leaf_hash
+    BatchRoot,
+}
+
+/// an on-chain trait, like the attributes in the metadata json but bounded
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "anchor", derive(anchor_lang::InitSpace))]
+pub struct Attribute {
+    #[cfg_attr(feature = "anchor", max_len(16))]
+    pub key: String,
+    #[cfg_attr(feature = "anchor", max_len(32))]
+    pub value: String,
+}
+
+// crosschain message struct, used for sending nft data between chains.
+// generic over the address type so the program can keep Pubkey, borsh bytes are the same
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CrossChainMessage<A = Address> {
+    pub message_type: MessageType,
+    pub mint: A,
+    pub recipient: [u8; 32],
+    pub metadata_uri: String, // at most MAX_MESSAGE_URI_LEN bytes
+    pub name: String,         // at most MAX_MESSAGE_NAME_LEN bytes
+    pub symbol: String,       // at most MAX_MESSAGE_SYMBOL_LEN bytes
+    pub nonce: u64,
+    pub master_mint: A, // prints only: mint of the master on the origin chain
+    pub edition: u64,   // prints only: edition number, 0 if not a print
+    pub metadata_hash: Option<[u8; 32]>, // sha256 of the metadata json, if known
+    pub royalty_bps: Option<u16>, // seller fee in basis points, 10_000 = 100%, None = not known
+    pub royalty_recipient: Option<A>, // royalty payee on the origin chain, if any
+    pub attributes: Vec<Attribute>, // on-chain traits, empty if none
+}
+
+impl<A: BorshSerialize + BorshDeserialize> CrossChainMessage<A> {
+    /// borsh bytes, what transfer_to_zetachain sends and on_call takes as data
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // writing into a vec cant fail
+        self.try_to_vec().expect("message serializes")
+    }
+
+    /// decodes the whole slice, trailing bytes are an error
+    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        Self::try_from_slice(data)
+    }
+
+    /// see canonical_message_hash, borsh is canonical so this is the hash of the bytes
+    /// the message was decoded from
+    pub fn canonical_hash(&self) -> [u8; 32] {
+        canonical_message_hash(&self.to_bytes())
+    }
+}
+
+impl<A: From<Address>> CrossChainMessage<A> {
+    /// decodes a message from an untrusted source. same layout as borsh but every
+    /// length prefix is checked against its cap before anything gets allocated, and
+    /// the whole slice has to be consumed
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        if data.len() > MAX_MESSAGE_LEN {
+            return Err(DecodeError::TooLarge);
+        }
+        let mut reader = Reader { data };
+        let message = CrossChainMessage {
+            message_type: match reader.u8()? {
+                0 => MessageType::Transfer,
+                1 => MessageType::Unlock,
+                2 => MessageType::BatchRoot,
+                _ => return Err(DecodeError::Invalid),
+            },
+            mint: reader.address()?,
+            recipient: reader.bytes32()?,
+            metadata_uri: reader.string(MAX_MESSAGE_URI_LEN)?,
+            name: reader.string(MAX_MESSAGE_NAME_LEN)?,
+            symbol: reader.string(MAX_MESSAGE_SYMBOL_LEN)?,
+            nonce: reader.u64()?,
+            master_mint: reader.address()?,
+            edition: reader.u64()?,
+            metadata_hash: reader.option(Reader::bytes32)?,
+            royalty_bps: reader.option(Reader::u16)?,
+            royalty_recipient: reader.option(Reader::address)?,
+            attributes: reader.attributes()?,
+        };
+        if !reader.data.is_empty() {
+            return Err(DecodeError::Invalid);
+        }
+        Ok(message)
+    }
+}
+
+// reads borsh values off the front of the slice
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if len > self.data.len() {
+            return Err(DecodeError::Invalid);
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        // take returned exactly N bytes
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn bytes32(&mut self) -> Result<[u8; 32], DecodeError> {
+        self.array()
+    }
+
+    fn address<A: From<Address>>(&mut self) -> Result<A, DecodeError> {
+        Ok(A::from(self.array()?))
+    }
+
+    // u32 length prefix, checked against the cap before the bytes get read
+    fn len(&mut self, cap: usize) -> Result<usize, DecodeError> {
+        let len = u32::from_le_bytes(self.array()?) as usize;
+        if len > cap {
+            return Err(DecodeError::TooLarge);
+        }
+        Ok(len)
+    }
+
+    fn string(&mut self, cap: usize) -> Result<String, DecodeError> {
+        let len = self.len(cap)?;
+        let bytes = self.take(len)?;
+        core::str::from_utf8(bytes)
+            .map(String::from)
+            .map_err(|_| DecodeError::Invalid)
+    }
+
+    fn option<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<Option<T>, DecodeError> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => read(self).map(Some),
+            _ => Err(DecodeError::Invalid),
+        }
+    }
+
+    fn attributes(&mut self) -> Result<Vec<Attribute>, DecodeError> {
+        let count = self.len(MAX_MESSAGE_ATTRIBUTES)?;
+        let mut attributes = Vec::with_capacity(count);
+        for _ in 0..count {
+            attributes.push(Attribute {
+                key: self.string(MAX_MESSAGE_ATTRIBUTE_KEY_LEN)?,
+                value: self.string(MAX_MESSAGE_ATTRIBUTE_VALUE_LEN)?,
+            });
+        }
+        Ok(attributes)
+    }
+}
+
+/// id of a crosschain message: keccak256 over the layout version byte followed by
+/// the borsh encoded message, so `keccak256(abi.encodePacked(uint8(MESSAGE_VERSION), message))`
+/// on the evm side
+pub fn canonical_message_hash(data: &[u8]) -> [u8; 32] {
+    canonical_hash_with(data, keccak256)
+}
+
+/// canonical_message_hash with the keccak supplied by the caller, the program passes
+/// the keccak syscall so it doesnt hash in software
+pub fn canonical_hash_with(data: &[u8], hashv: impl FnOnce(&[&[u8]]) -> [u8; 32]) -> [u8; 32] {
+    hashv(&[&[MESSAGE_VERSION], data])
+}
+
+/// most leaves an inbound batch can have, the program keeps a claimed bit for each
+pub const MAX_BATCH_LEAVES: u64 = 16_384;
+
+/// levels of an inbound batch tree with `leaf_count` leaves, so the length of every
+/// proof. the leaves are filled up to a power of two with zero hashes
+pub fn batch_depth(leaf_count: u64) -> usize {
+    leaf_count.next_power_of_two().trailing_zeros() as usize
+}
+
+/// leaf of an inbound batch tree: keccak256 over 0x00, the leaf index little endian
+/// and the canonical hash of the message. with the index in it a message can only be
+/// claimed at its own position
+pub fn batch_leaf_hash(index: u64, data: &[u8]) -> [u8; 32] {
+    batch_leaf_hash_with(index, data, keccak256)
+}
+
+/// batch_leaf_hash with the keccak supplied by the caller, like canonical_hash_with
+pub fn batch_leaf_hash_with(index: u64, data: &[u8], hashv: impl Fn(&[&[u8]]) -> [u8; 32]) -> [u8; 32] {
+    let message_hash = canonical_hash_with(data, &hashv);
+    hashv(&[&[0], &index.to_le_bytes(), &message_hash])
+}
+
+/// inner node of an inbound batch tree: keccak256 over 0x01, the left and the right
+/// child. the prefixes keep a leaf from passing as a node
+pub fn batch_node_hash_with(
+    left: &[u8; 32],
+    right: &[u8; 32],
+    hashv: impl Fn(&[&[u8]]) -> [u8; 32],
+) -> [u8; 32] {
+    hashv(&[&[1], left, right])
+}
+
+/// the root a proof leads to from the leaf at `index`, siblings from the leaf up. bit
+/// n of the index says if the node at level n is a right child
+pub fn batch_root_from_proof_with(
+    index: u64,
+    leaf: [u8; 32],
+    proof: &[[u8; 32]],
+    hashv: impl Fn(&[&[u8]]) -> [u8; 32],
+) -> [u8; 32] {
+    proof.iter().enumerate().fold(leaf, |node, (level, sibling)| {
+        if index >> level & 1 == 0 {
+            batch_node_hash_with(&node, sibling, &hashv)
+        } else {
+            batch_node_hash_with(sibling, &node, &hashv)
+        }
+    })
+}
+
+/// root of the inbound batch tree over these encoded messages, in leaf order
+pub fn batch_root(leaves: &[Vec<u8>]) -> [u8; 32] {
+    let levels = batch_levels(leaves);
+    levels[levels.len() - 1][0]
+}
+
+/// proof for the leaf at `index`, what claim_inbound takes along with the message
+pub fn batch_proof(leaves: &[Vec<u8>], index: usize) -> Vec<[u8; 32]> {
+    let levels = batch_levels(leaves);
+    levels[..levels.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(level, nodes)| nodes[(index >> level) ^ 1])
+        .collect()
+}
+
+// every level of the tree, the padded leaves first and the root last
+fn batch_levels(leaves: &[Vec<u8>]) -> Vec<Vec<[u8; 32]>> {
+    let mut level: Vec<[u8; 32]> = leaves
+        .iter()
+        .zip(0..)
+        .map(|(data, index)| batch_leaf_hash(index, data))
+        .collect();
+    level.resize((leaves.len() as u64).next_power_of_two() as usize, [0; 32]);
+    let mut levels = alloc::vec![level];
+    while levels[levels.len() - 1].len() > 1 {
+        let next = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| batch_node_hash_with(&pair[0], &pair[1], keccak256))
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+fn keccak256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn hex(s: &str) -> Vec<u8> {
+        let s: String = s.split_whitespace().collect();
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn hash(s: &str) -> [u8; 32] {
+        hex(s).try_into().unwrap()
+    }
+
+    fn attribute(key: &str, value: &str) -> Attribute {
+        Attribute {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    // same two messages the program pins in its own tests
+    fn transfer_fixture() -> CrossChainMessage {
+        CrossChainMessage {
+            message_type: MessageType::Transfer,
+            mint: [1; 32],
+            recipient: [2; 32],
+            metadata_uri: "https://example.com/nft.json".to_string(),
+            name: "Punk".to_string(),
+            symbol: "PNK".to_string(),
+            nonce: 7,
+            master_mint: [0; 32],
+            edition: 0,
+            metadata_hash: None,
+            royalty_bps: Some(500),
+            royalty_recipient: None,
+            attributes: vec![attribute("eyes", "laser")],
+        }
+    }
+
+    // one field per line, in layout order
+    const TRANSFER_HEX: &str = "
+        00
+        0101010101010101010101010101010101010101010101010101010101010101
+        0202020202020202020202020202020202020202020202020202020202020202
+        1c000000 68747470733a2f2f6578616d706c652e636f6d2f6e66742e6a736f6e
+        04000000 50756e6b
+        03000000 504e4b
+        0700000000000000
+        0000000000000000000000000000000000000000000000000000000000000000
+        0000000000000000
+        00
+        01 f401
+        00
+        01000000 04000000 65796573 05000000 6c61736572
+    ";
+
+    fn unlock_fixture() -> CrossChainMessage {
+        CrossChainMessage {
+            message_type: MessageType::Unlock,
+            mint: [3; 32],
+            recipient: [0; 32],
+            metadata_uri: String::new(),
+            name: String::new(),
+            symbol: String::new(),
+            nonce: u64::MAX,
+            master_mint: [5; 32],
+            edition: 12,
+            metadata_hash: Some([9; 32]),
+            royalty_bps: None,
+            royalty_recipient: Some([4; 32]),
+            attributes: vec![],
+        }
+    }
+
+    const UNLOCK_HEX: &str = "
+        01
+        0303030303030303030303030303030303030303030303030303030303030303
+        0000000000000000000000000000000000000000000000000000000000000000
+        00000000
+        00000000
+        00000000
+        ffffffffffffffff
+        0505050505050505050505050505050505050505050505050505050505050505
+        0c00000000000000
+        01 0909090909090909090909090909090909090909090909090909090909090909
+        00
+        01 0404040404040404040404040404040404040404040404040404040404040404
+        00000000
+    ";
+
+    #[test]
+    fn test_fixtures_encode_to_the_pinned_bytes() {
+        assert_eq!(transfer_fixture().to_bytes(), hex(TRANSFER_HEX));
+        assert_eq!(unlock_fixture().to_bytes(), hex(UNLOCK_HEX));
+        assert_eq!(hex(TRANSFER_HEX).len(), 186);
+        assert_eq!(hex(UNLOCK_HEX).len(), 196);
+    }
+
+    #[test]
+    fn test_fixtures_decode_from_the_pinned_bytes() {
+        assert_eq!(
+            CrossChainMessage::<Address>::from_bytes(&hex(TRANSFER_HEX)).unwrap(),
+            transfer_fixture()
+        );
+        assert_eq!(
+            CrossChainMessage::<Address>::from_bytes(&hex(UNLOCK_HEX)).unwrap(),
+            unlock_fixture()
+        );
+    }
+
+    #[test]
+    fn test_canonical_hash_known_answers() {
+        // if one of these changes the layout changed and MESSAGE_VERSION has to go up
+        assert_eq!(MESSAGE_VERSION, 1);
+        assert_eq!(
+            transfer_fixture().canonical_hash(),
+            hash("3965a9d8843b91d87e42f03f708bff9df57e420ad92328aaac40c0f816758720")
+        );
+        assert_eq!(
+            unlock_fixture().canonical_hash(),
+            hash("e7ad542a8fcb6301f7bce79815c5cdd5c248f88a6d23dd6fffc8bfdf52f86808")
+        );
+        // keccak256(0x01), just the version byte
+        assert_eq!(
+            canonical_message_hash(&[]),
+            hash("5fe7f977e71dba2ea1a68e21057beebb9be2ac30c6410aa38d4f3fbe41dcffd2")
+        );
+    }
+
+    #[test]
+    fn test_canonical_hash_is_over_the_version_and_the_bytes() {
+        let bytes = transfer_fixture().to_bytes();
+        let hashed = canonical_hash_with(&bytes, |parts| {
+            assert_eq!(parts, &[&[MESSAGE_VERSION][..], &bytes[..]]);
+            [7; 32]
+        });
+        assert_eq!(hashed, [7; 32]);
+        assert_eq!(
+            canonical_message_hash(&bytes),
+            transfer_fixture().canonical_hash()
+        );
+    }
+
+    #[test]
+    fn test_message_type_tags() {
+        assert_eq!(MessageType::Transfer.try_to_vec().unwrap(), [0]);
+        assert_eq!(MessageType::Unlock.try_to_vec().unwrap(), [1]);
+        assert_eq!(MessageType::BatchRoot.try_to_vec().unwrap(), [2]);
+        assert!(MessageType::try_from_slice(&[3]).is_err());
+
+        let mut bytes = hex(TRANSFER_HEX);
+        bytes[0] = 3;
+        assert!(CrossChainMessage::<Address>::from_bytes(&bytes).is_err());
+        assert_eq!(CrossChainMessage::<Address>::decode(&bytes), Err(DecodeError::Invalid));
+        bytes[0] = 2;
+        let root = CrossChainMessage::<Address>::decode(&bytes).unwrap();
+        assert_eq!(root.message_type, MessageType::BatchRoot);
+    }
+
+    fn batch(count: u64) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|nonce| CrossChainMessage { nonce, ..transfer_fixture() }.to_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_depth_rounds_up_to_a_power_of_two() {
+        assert_eq!(batch_depth(1), 0);
+        assert_eq!(batch_depth(2), 1);
+        assert_eq!(batch_depth(3), 2);
+        assert_eq!(batch_depth(4), 2);
+        assert_eq!(batch_depth(5), 3);
+        assert_eq!(batch_depth(MAX_BATCH_LEAVES), 14);
+    }
+
+    #[test]
+    fn test_batch_tree_small_cases() {
+        let leaves = batch(3);
+        let hashes: Vec<_> = (0..3).map(|i| batch_leaf_hash(i, &leaves[i as usize])).collect();
+
+        // a single leaf is its own root
+        assert_eq!(batch_root(&leaves[..1]), hashes[0]);
+        assert!(batch_proof(&leaves[..1], 0).is_empty());
+
+        // three leaves get a zero leaf as the fourth
+        let left = batch_node_hash_with(&hashes[0], &hashes[1], keccak256);
+        let right = batch_node_hash_with(&hashes[2], &[0; 32], keccak256);
+        assert_eq!(batch_root(&leaves), batch_node_hash_with(&left, &right, keccak256));
+        assert_eq!(batch_proof(&leaves, 2), vec![[0; 32], left]);
+
+        // the index is part of the leaf, the same message elsewhere hashes differently
+        assert_ne!(batch_leaf_hash(0, &leaves[0]), batch_leaf_hash(1, &leaves[0]));
+    }
+
+    #[test]
+    fn test_every_batch_proof_leads_to_the_root() {
+        for count in [1, 2, 5, 8, 13] {
+            let leaves = batch(count);
+            let root = batch_root(&leaves);
+            for (index, data) in leaves.iter().enumerate() {
+                let proof = batch_proof(&leaves, index);
+                assert_eq!(proof.len(), batch_depth(count));
+                let leaf = batch_leaf_hash(index as u64, data);
+                assert_eq!(batch_root_from_proof_with(index as u64, leaf, &proof, keccak256), root);
+
+                // the same proof doesnt work for another leaf or at another index
+                let other = batch_leaf_hash(index as u64, &leaves[(index + 1) % leaves.len()]);
+                if count > 1 {
+                    assert_ne!(batch_root_from_proof_with(index as u64, other, &proof, keccak256), root);
+                    let moved = index as u64 ^ 1;
+                    assert_ne!(batch_root_from_proof_with(moved, leaf, &proof, keccak256), root);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_field_round_trips() {
+        let mut message = transfer_fixture();
+        message.metadata_uri = "u".repeat(200);
+        message.name = "n".repeat(32);
+        message.symbol = "s".repeat(10);
+        message.nonce = u64::MAX;
+        message.master_mint = [8; 32];
+        message.edition = u64::MAX;
+        message.metadata_hash = Some([6; 32]);
+        message.royalty_bps = Some(10_000);
+        message.royalty_recipient = Some([7; 32]);
+        message.attributes = vec![attribute(&"k".repeat(16), &"v".repeat(32)); 8];
+
+        assert_eq!(
+            CrossChainMessage::<Address>::from_bytes(&message.to_bytes()).unwrap(),
+            message
+        );
+
+        // utf-8 goes through as bytes, lengths count bytes not chars
+        message.name = "ñé".to_string();
+        let bytes = message.to_bytes();
+        assert_eq!(
+            CrossChainMessage::<Address>::from_bytes(&bytes)
+                .unwrap()
+                .name,
+            "ñé"
+        );
+    }
+
+    #[test]
+    fn test_truncated_and_trailing_bytes_are_rejected() {
+        let bytes = hex(TRANSFER_HEX);
+        for len in 0..bytes.len() {
+            assert!(CrossChainMessage::<Address>::from_bytes(&bytes[..len]).is_err());
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(CrossChainMessage::<Address>::from_bytes(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_option_tags_have_to_be_0_or_1() {
+        // metadata_hash tag of the transfer fixture sits right after edition
+        let mut bytes = hex(TRANSFER_HEX);
+        let tag = 1 + 32 + 32 + (4 + 28) + (4 + 4) + (4 + 3) + 8 + 32 + 8;
+        assert_eq!(bytes[tag], 0);
+        bytes[tag] = 2;
+        assert!(CrossChainMessage::<Address>::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_rejected() {
+        let mut bytes = hex(TRANSFER_HEX);
+        // first byte of the name
+        let name = 1 + 32 + 32 + (4 + 28) + 4;
+        bytes[name] = 0xff;
+        assert!(CrossChainMessage::<Address>::from_bytes(&bytes).is_err());
+    }
+
+    // every string and the attribute list at its cap
+    fn max_message() -> CrossChainMessage {
+        CrossChainMessage {
+            message_type: MessageType::Transfer,
+            mint: [1; 32],
+            recipient: [2; 32],
+            metadata_uri: "u".repeat(MAX_MESSAGE_URI_LEN),
+            name: "n".repeat(MAX_MESSAGE_NAME_LEN),
+            symbol: "s".repeat(MAX_MESSAGE_SYMBOL_LEN),
+            nonce: u64::MAX,
+            master_mint: [3; 32],
+            edition: u64::MAX,
+            metadata_hash: Some([4; 32]),
+            royalty_bps: Some(u16::MAX),
+            royalty_recipient: Some([5; 32]),
+            attributes: vec![
+                attribute(
+                    &"k".repeat(MAX_MESSAGE_ATTRIBUTE_KEY_LEN),
+                    &"v".repeat(MAX_MESSAGE_ATTRIBUTE_VALUE_LEN)
+                );
+                MAX_MESSAGE_ATTRIBUTES
+            ],
+        }
+    }
+
+    #[test]
+    fn test_decode_matches_borsh() {
+        for message in [transfer_fixture(), unlock_fixture(), max_message()] {
+            let bytes = message.to_bytes();
+            assert_eq!(CrossChainMessage::<Address>::decode(&bytes), Ok(message));
+        }
+    }
+
+    #[test]
+    fn test_decode_takes_the_largest_message() {
+        let bytes = max_message().to_bytes();
+        assert_eq!(bytes.len(), MAX_MESSAGE_LEN);
+        assert_eq!(
+            CrossChainMessage::<Address>::decode(&bytes),
+            Ok(max_message())
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_payloads() {
+        let mut bytes = max_message().to_bytes();
+        bytes.push(0);
+        assert_eq!(
+            CrossChainMessage::<Address>::decode(&bytes),
+            Err(DecodeError::TooLarge)
+        );
+        assert_eq!(
+            CrossChainMessage::<Address>::decode(&vec![0; 64 * 1024]),
+            Err(DecodeError::TooLarge)
+        );
+    }
+
+    #[test]
+    fn test_decode_checks_lengths_before_reading() {
+        // the uri of the transfer fixture declares 4gb
+        let uri = 1 + 32 + 32;
+        let mut bytes = hex(TRANSFER_HEX);
+        bytes[uri..uri + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            CrossChainMessage::<Address>::decode(&bytes),
+            Err(DecodeError::TooLarge)
+        );
+
+        // one over the cap, even with the bytes there
+        let mut message = transfer_fixture();
+        message.name = "n".repeat(MAX_MESSAGE_NAME_LEN + 1);
+        assert_eq!(
+            CrossChainMessage::<Address>::decode(&message.to_bytes()),
+            Err(DecodeError::TooLarge)
+        );
+
+        let mut message = transfer_fixture();
+        message.attributes = vec![attribute("k", "v"); MAX_MESSAGE_ATTRIBUTES + 1];
+        assert_eq!(
+            CrossChainMessage::<Address>::decode(&message.to_bytes()),
+            Err(DecodeError::TooLarge)
+        );
+
+        let mut message = transfer_fixture();
+        message.attributes = vec![attribute(
+            "k",
+            &"v".repeat(MAX_MESSAGE_ATTRIBUTE_VALUE_LEN + 1),
+        )];
+        assert_eq!(
+            CrossChainMessage::<Address>::decode(&message.to_bytes()),
+            Err(DecodeError::TooLarge)
+        );
+
+        // under the cap but more than there is
+        let mut bytes = hex(TRANSFER_HEX);
+        bytes[uri..uri + 4].copy_from_slice(&1000u32.to_le_bytes());
+        assert_eq!(
+            CrossChainMessage::<Address>::decode(&bytes),
+            Err(DecodeError::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_what_borsh_rejects() {
+        let bytes = hex(TRANSFER_HEX);
+        for len in 0..bytes.len() {
+            assert_eq!(
+                CrossChainMessage::<Address>::decode(&bytes[..len]),
+                Err(DecodeError::Invalid)
+            );
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            CrossChainMessage::<Address>::decode(&trailing),
+            Err(DecodeError::Invalid)
+        );
+
+        let mut message_type = bytes.clone();
+        message_type[0] = 3;
+        let mut tag = bytes.clone();
+        tag[1 + 32 + 32 + (4 + 28) + (4 + 4) + (4 + 3) + 8 + 32 + 8] = 2;
+        let mut utf8 = bytes.clone();
+        utf8[1 + 32 + 32 + (4 + 28) + 4] = 0xff;
+        for bytes in [message_type, tag, utf8] {
+            assert!(CrossChainMessage::<Address>::from_bytes(&bytes).is_err());
+            assert_eq!(
+                CrossChainMessage::<Address>::decode(&bytes),
+                Err(DecodeError::Invalid)
+            );
+        }
+    }
+
+    #[test]
+    fn test_address_type_doesnt_change_the_bytes() {
+        #[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+        struct Key([u8; 32]);
+
+        impl From<Address> for Key {
+            fn from(address: Address) -> Self {
+                Key(address)
+            }
+        }
+
+        let bytes = hex(UNLOCK_HEX);
+        let keyed = CrossChainMessage::<Key>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(keyed.mint, Key([3; 32]));
+        assert_eq!(keyed.royalty_recipient, Some(Key([4; 32])));
+        assert_eq!(keyed.to_bytes(), bytes);
+        assert_eq!(keyed.canonical_hash(), unlock_fixture().canonical_hash());
+        assert_eq!(CrossChainMessage::<Key>::decode(&bytes), Ok(keyed));
+    }
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+
+// the dev gateway id, universal_nft::gateway::LOCALNET
+declare_id!("94U5AHQMKkV5txNJ17QPXWoh474PheGou6cNP2FEuL1d");
+
+// on_call sighash, same as CallableInstruction::OnCall in the gateway
+const ON_CALL_DISCRIMINATOR: [u8; 8] = [16, 136, 66, 32, 254, 40, 181, 8];
+
+// NOTE: test only, stands in for the zetachain gateway on localnet where it isnt
+// deployed. there is no tss signature and no withdrawal, execute only makes the
+// on_call cpi the way the gateway does after it checked the message
+#[program]
+pub mod mock_gateway {
+    use super::*;
+
+    // calls on_call on the target program with the remaining accounts, in the
+    // order they are passed. the data is packed like the gateway packs it, so it
+    // breaks the same way if on_call stops reading it. unlike the gateway the
+    // signer flags are passed through, it drops them
+    pub fn execute<'info>(
+        ctx: Context<'_, '_, '_, 'info, Execute<'info>>,
+        amount: u64,
+        sender: [u8; 20],
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let accounts = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect();
+        let instruction = Instruction {
+            program_id: ctx.accounts.target_program.key(),
+            accounts,
+            data: pack_on_call(amount, sender, &data),
+        };
+
+        let mut account_infos = ctx.remaining_accounts.to_vec();
+        account_infos.push(ctx.accounts.target_program.to_account_info());
+        invoke(&instruction, &account_infos)?;
+        Ok(())
+    }
+}
+
+fn pack_on_call(amount: u64, sender: [u8; 20], data: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(8 + 8 + 20 + 4 + data.len());
+    buf.extend_from_slice(&ON_CALL_DISCRIMINATOR);
+    buf.extend_from_slice(&amount.to_le_bytes());
+    buf.extend_from_slice(&sender);
+    buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buf.extend_from_slice(data);
+    buf
+}
+
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    /// CHECK: program on_call is called on
+    #[account(executable)]
+    pub target_program: UncheckedAccount<'info>,
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+declare_id!("5TXv6wYmTM6R8FWt7xMFpiMgUUaHKTyWqSCKy4c24iBi");
+
+// NOTE: test only, stands in for a squads style multisig whose vault pda is the
+// universal nft authority. there are no approvals, execute just runs the instruction
+#[program]
+pub mod mock_multisig {
+    use super::*;
+
+    // runs `data` on the target program with the remaining accounts, the vault pda
+    // signs through invoke_signed the way a multisig executes a passed proposal
+    pub fn execute<'info>(
+        ctx: Context<'_, '_, '_, 'info, Execute<'info>>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let vault = ctx.accounts.vault.key();
+        let accounts = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer || account.key() == vault,
+                is_writable: account.is_writable,
+            })
+            .collect();
+        let instruction = Instruction {
+            program_id: ctx.accounts.target_program.key(),
+            accounts,
+            data,
+        };
+
+        let mut account_infos = ctx.remaining_accounts.to_vec();
+        account_infos.push(ctx.accounts.target_program.to_account_info());
+        invoke_signed(
+            &instruction,
+            &account_infos,
+            &[&[b"vault", &[ctx.bumps.vault]]],
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    /// CHECK: vault pda, signs the inner instruction and holds no data
+    #[account(seeds = [b"vault"], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// CHECK: program the instruction is for
+    #[account(executable)]
+    pub target_program: UncheckedAccount<'info>,
+}
@@ -0,0 +1,177 @@
+use anchor_lang::prelude::*;
+use universal_nft::{program::UniversalNft, MintParams};
+
+declare_id!("671mFG7beXo3bgePyk19iujCA61dLpf7TZKupiqSp3wf");
+
+// NOTE: example caller, shows how another program mints universal nfts through cpi.
+// a drop caps how many nfts its creator sells, the buyer pays and gets the nft
+#[program]
+pub mod nft_launchpad {
+    use super::*;
+
+    pub fn create_drop(ctx: Context<CreateDrop>, max_supply: u64) -> Result<()> {
+        let drop = &mut ctx.accounts.drop;
+        drop.creator = ctx.accounts.creator.key();
+        drop.max_supply = max_supply;
+        drop.minted = 0;
+        drop.bump = ctx.bumps.drop;
+        Ok(())
+    }
+
+    // the buyer signs the outer transaction, so its signature (and the mint
+    // keypair's) carries over into mint_nft and no pda has to sign
+    pub fn mint_from_drop(
+        ctx: Context<MintFromDrop>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        let drop = &mut ctx.accounts.drop;
+        require!(drop.minted < drop.max_supply, LaunchpadError::SoldOut);
+        drop.minted += 1;
+
+        let accounts = universal_nft::cpi::accounts::MintNft {
+            nft_program: ctx.accounts.nft_program.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            token_account: ctx.accounts.token_account.to_account_info(),
+            recipient: ctx.accounts.buyer.to_account_info(),
+            nft_info: ctx.accounts.nft_info.to_account_info(),
+            metadata: ctx.accounts.metadata.as_ref().map(|a| a.to_account_info()),
+            treasury: ctx.accounts.treasury.to_account_info(),
+            minter_role: None,
+            owner_stats: ctx.accounts.owner_stats.to_account_info(),
+            mint_index: ctx.accounts.mint_index.to_account_info(),
+            master_edition: None,
+            token_record: None,
+            sysvar_instructions: None,
+            collection: None,
+            collection_mint: None,
+            collection_metadata: None,
+            collection_master_edition: None,
+            payer: ctx.accounts.buyer.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            token_metadata_program: ctx
+                .accounts
+                .token_metadata_program
+                .as_ref()
+                .map(|a| a.to_account_info()),
+            blocked_recipient: ctx.accounts.blocked_recipient.to_account_info(),
+            event_authority: ctx.accounts.event_authority.to_account_info(),
+            program: ctx.accounts.universal_nft_program.to_account_info(),
+        };
+        universal_nft::cpi::mint_nft(
+            CpiContext::new(ctx.accounts.universal_nft_program.to_account_info(), accounts),
+            MintParams {
+                name,
+                symbol,
+                uri,
+                recipient: ctx.accounts.buyer.key(),
+                token_standard: None,
+                creators: None,
+                metadata_hash: None,
+                attributes: None,
+                royalty_bps: None,
+            },
+        )
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Drop {
+    pub creator: Pubkey,
+    pub max_supply: u64,
+    pub minted: u64,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+pub struct CreateDrop<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Drop::INIT_SPACE,
+        seeds = [b"drop", creator.key().as_ref()],
+        bump
+    )]
+    pub drop: Account<'info, Drop>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// the universal nft accounts are only passed through, mint_nft checks them
+#[derive(Accounts)]
+pub struct MintFromDrop<'info> {
+    #[account(
+        mut,
+        seeds = [b"drop", drop.creator.as_ref()],
+        bump = drop.bump
+    )]
+    pub drop: Account<'info, Drop>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: Signer<'info>,
+
+    /// CHECK: universal nft state
+    #[account(mut)]
+    pub nft_program: UncheckedAccount<'info>,
+
+    /// CHECK: buyer ata, created by mint_nft
+    #[account(mut)]
+    pub token_account: UncheckedAccount<'info>,
+
+    /// CHECK: nft info pda, created by mint_nft
+    #[account(mut)]
+    pub nft_info: UncheckedAccount<'info>,
+
+    /// CHECK: metaplex mode only
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: universal nft treasury
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+
+    /// CHECK: owner stats of the buyer
+    #[account(mut)]
+    pub owner_stats: UncheckedAccount<'info>,
+
+    /// CHECK: next index slot
+    #[account(mut)]
+    pub mint_index: UncheckedAccount<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: spl token or token-2022, checked by mint_nft
+    pub token_program: UncheckedAccount<'info>,
+
+    /// CHECK: checked by mint_nft
+    pub associated_token_program: UncheckedAccount<'info>,
+
+    /// CHECK: metaplex mode only, checked by mint_nft
+    pub token_metadata_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: block pda of the buyer, checked by mint_nft
+    pub blocked_recipient: UncheckedAccount<'info>,
+
+    /// CHECK: universal nft event authority, checked by mint_nft
+    pub event_authority: UncheckedAccount<'info>,
+
+    pub universal_nft_program: Program<'info, UniversalNft>,
+}
+
+#[error_code]
+pub enum LaunchpadError {
+    #[msg("Drop is sold out")]
+    SoldOut,
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use universal_nft::{CrossChainMessage, MessageType};
+
+declare_id!("3pHxntqSgSHe29a2ArEqj2aHh93Z8WtHeBi1R6NUbrpo");
+
+/// address of the universal app on zetachain, register it as the connected
+/// contract of the chain the echo answers from
+pub const UNIVERSAL_APP: [u8; 20] = [1; 20];
+
+// NOTE: test only, stands in for the universal app on zetachain. it takes the bytes
+// transfer_to_zetachain sent out and answers with the message zetachain would send
+// back, the test hands that to the mock gateway. it cant call the gateway itself,
+// on_call only accepts the gateway as the top level instruction
+#[program]
+pub mod zeta_echo {
+    use super::*;
+
+    // returns the on_call data sending the nft straight back. an nft that left
+    // solana is locked there, so `Unlock` brings it back under the same mint.
+    // `Transfer` sends it back as a new arrival for `recipient`, like an app
+    // that doesnt know where the nft came from. `nonce` is the inbound one for
+    // solana, outbound transfers are numbered apart so it has nothing to do with
+    // the one in the message
+    pub fn echo(
+        _ctx: Context<Echo>,
+        outbound: Vec<u8>,
+        message_type: MessageType,
+        recipient: Pubkey,
+        nonce: u64,
+    ) -> Result<Vec<u8>> {
+        let mut message = CrossChainMessage::try_from_slice(&outbound)
+            .map_err(|_| EchoError::InvalidMessage)?;
+        require!(
+            message.message_type == MessageType::Transfer,
+            EchoError::InvalidMessage
+        );
+
+        message.message_type = message_type;
+        message.recipient = recipient.to_bytes();
+        message.nonce = nonce;
+        Ok(message.try_to_vec()?)
+    }
+}
+
+#[derive(Accounts)]
+pub struct Echo {}
+
+#[error_code]
+pub enum EchoError {
+    #[msg("Not an outbound transfer message")]
+    InvalidMessage,
+}
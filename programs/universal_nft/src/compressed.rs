@@ -0,0 +1,370 @@
+// compressed nft support. with compressed mode on, inbound transfers mint a
+// bubblegum leaf into the program tree instead of a mint + metadata + ata.
+// everything that talks to bubblegum sits behind the `compressed` feature,
+// without it these entry points just fail with CompressedNotEnabled
+
+#[cfg(feature = "compressed")]
+pub use enabled::*;
+
+#[cfg(not(feature = "compressed"))]
+pub use disabled::*;
+
+#[cfg(feature = "compressed")]
+mod enabled {
+    use anchor_lang::prelude::*;
+    use mpl_bubblegum::{
+        accounts::TreeConfig,
+        instructions::{BurnCpiBuilder, CreateTreeConfigCpiBuilder, MintV1CpiBuilder},
+        types::{MetadataArgs, TokenProgramVersion, TokenStandard},
+        utils::get_asset_id,
+    };
+
+    use crate::{
+        bump, canonical_message_hash, charge_chain_fee, charge_fee_token, check_destination, display_uri,
+        ensure_nft_info_space, is_blocked, next_outbound_nonce, release_lock, transfer_message, upgrade_nft_info, CreateTree, CrossChainMessage,
+        FeeTokenAccounts, MessageType, NftError, NftInfo, NftStandard, NftUnlocked, OnCall,
+        TransferCompressedToZetachain, TransferInitiated, UnlockReason, NFT_INFO_VERSION,
+    };
+
+    pub fn create_tree(
+        ctx: Context<CreateTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        let nft_program = &mut ctx.accounts.nft_program;
+
+        CreateTreeConfigCpiBuilder::new(&ctx.accounts.bubblegum_program.to_account_info())
+            .tree_config(&ctx.accounts.tree_config.to_account_info())
+            .merkle_tree(&ctx.accounts.merkle_tree.to_account_info())
+            .payer(&ctx.accounts.payer.to_account_info())
+            .tree_creator(&nft_program.to_account_info())
+            .log_wrapper(&ctx.accounts.log_wrapper.to_account_info())
+            .compression_program(&ctx.accounts.compression_program.to_account_info())
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .max_depth(max_depth)
+            .max_buffer_size(max_buffer_size)
+            .public(false)
+            .invoke_signed(&[&[crate::seeds::NFT_PROGRAM, &[nft_program.bump]]])?;
+
+        nft_program.merkle_tree = ctx.accounts.merkle_tree.key();
+
+        msg!("Merkle tree {} created for compressed mints", nft_program.merkle_tree);
+        Ok(())
+    }
+
+    pub fn transfer_to_zetachain<'info>(
+        ctx: Context<'_, '_, '_, 'info, TransferCompressedToZetachain<'info>>,
+        destination_chain_id: u64,
+        recipient: [u8; 32],
+        root: [u8; 32],
+        data_hash: [u8; 32],
+        creator_hash: [u8; 32],
+        leaf_index: u32,
+    ) -> Result<()> {
+        upgrade_nft_info(
+            &mut ctx.accounts.nft_info,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        check_destination(&ctx.accounts.chain_info, &recipient)?;
+        let nft_info = &mut ctx.accounts.nft_info;
+        let nft_program = &mut ctx.accounts.nft_program;
+
+        require!(nft_info.owner == ctx.accounts.owner.key(), NftError::Unauthorized);
+        require!(!nft_info.is_locked, NftError::TokenLocked);
+        require!(!is_blocked(&ctx.accounts.blocked_mint), NftError::MintBlocked);
+
+        // same bookkeeping as a regular lock and like there before any cpi, the
+        // asset id is stale until it comes back
+        nft_info.is_locked = true;
+        nft_info.cross_chain_recipient = recipient;
+        let nonce = next_outbound_nonce(nft_program)?;
+        nft_info.lock_nonce = nonce;
+        bump(&mut nft_program.locked_count)?;
+
+        charge_chain_fee(
+            &ctx.accounts.chain_info,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        charge_fee_token(
+            &ctx.accounts.chain_info,
+            &FeeTokenAccounts {
+                fee_mint: &ctx.accounts.fee_mint,
+                owner_fee_token_account: &ctx.accounts.owner_fee_token_account,
+                fee_vault: &ctx.accounts.fee_vault,
+                fee_token_program: &ctx.accounts.fee_token_program,
+                owner: &ctx.accounts.owner,
+                treasury: &ctx.accounts.treasury,
+            },
+        )?;
+
+        // the merkle proof comes in as remaining accounts, read only
+        let proof: Vec<(&AccountInfo<'info>, bool, bool)> = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| (account, false, false))
+            .collect();
+        let owner = ctx.accounts.owner.to_account_info();
+        BurnCpiBuilder::new(&ctx.accounts.bubblegum_program.to_account_info())
+            .tree_config(&ctx.accounts.tree_config.to_account_info())
+            .leaf_owner(&owner, true)
+            .leaf_delegate(&owner, false)
+            .merkle_tree(&ctx.accounts.merkle_tree.to_account_info())
+            .log_wrapper(&ctx.accounts.log_wrapper.to_account_info())
+            .compression_program(&ctx.accounts.compression_program.to_account_info())
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .root(root)
+            .data_hash(data_hash)
+            .creator_hash(creator_hash)
+            .nonce(leaf_index as u64)
+            .index(leaf_index)
+            .add_remaining_accounts(&proof)
+            .invoke()?;
+
+        let message = transfer_message(nft_info, recipient, nonce);
+        let message_bytes = message.try_to_vec()?;
+
+        emit_cpi!(TransferInitiated {
+            mint: nft_info.mint,
+            destination_chain_id,
+            recipient,
+            nonce,
+            flat_fee_lamports: ctx.accounts.chain_info.flat_fee_lamports,
+            gas_fee_lamports: ctx.accounts.chain_info.gas_fee_lamports,
+            message_hash: canonical_message_hash(&message_bytes),
+        });
+
+        msg!("Cross-chain transfer initiated for compressed asset {} to chain {} recipient {:?}",
+            nft_info.asset_id, destination_chain_id, recipient);
+        msg!("Message: {:?}", message_bytes);
+
+        Ok(())
+    }
+
+    // inbound transfer in compressed mode: mint a leaf and track it in nft info, the
+    // nft info pda still hangs off the derived mint address so the id stays stable
+    pub fn mint_inbound<'info>(
+        accounts: &mut OnCall<'info>,
+        nft_info_bump: u8,
+        cross_chain_message: &CrossChainMessage,
+        recipient: Pubkey,
+        metadata_uri: String,
+        hash_mismatch: bool,
+        index: Option<u64>,
+    ) -> Result<()> {
+        ensure_nft_info_space(
+            &accounts.nft_info.to_account_info(),
+            &accounts.payer.to_account_info(),
+            &accounts.system_program.to_account_info(),
+            NftInfo::space(accounts.nft_program.max_uri_len),
+        )?;
+
+        let nft_info = &mut accounts.nft_info;
+        nft_info.mint = accounts.mint.key();
+        nft_info.owner = recipient;
+        nft_info.metadata_uri = metadata_uri.clone();
+        nft_info.name = cross_chain_message.name.clone();
+        nft_info.symbol = cross_chain_message.symbol.clone();
+        nft_info.is_locked = false;
+        nft_info.cross_chain_recipient = [0; 32];
+        nft_info.token_standard = NftStandard::NonFungible;
+        nft_info.compressed = true;
+        nft_info.in_collection = false;
+        nft_info.bridged_collection = false;
+        nft_info.master_mint = cross_chain_message.master_mint;
+        nft_info.edition = cross_chain_message.edition;
+        nft_info.metadata_hash = cross_chain_message.metadata_hash;
+        nft_info.hash_mismatch = hash_mismatch;
+        nft_info.minted_to = Pubkey::default(); // bridged in, not a local mint
+        nft_info.bridged = true;
+        nft_info.index = index;
+        nft_info.royalty_bps = cross_chain_message.royalty_bps.unwrap_or_default();
+        nft_info.royalty_recipient = cross_chain_message.royalty_recipient;
+        nft_info.attributes = cross_chain_message.attributes.clone();
+        nft_info.bump = nft_info_bump;
+        nft_info.version = NFT_INFO_VERSION;
+        nft_info.metadata_created = true; // the leaf carries it
+        nft_info.needs_metadata_repair = false; // compressed mode never takes the placeholder
+        nft_info.metadata_immutable = false; // no metaplex metadata to freeze
+        nft_info.external = false;
+        nft_info.lock_nonce = 0;
+
+        let nft_program = &mut accounts.nft_program;
+        bump(&mut nft_program.total_supply)?;
+
+        // everything but the asset id is written before the cpi, bubblegum only
+        // hands the id back once the leaf is in. the leaf gets the rewritten uri
+        let leaf_uri = display_uri(nft_program, &metadata_uri);
+        let asset_id = mint_leaf(
+            &CompressedMint::from_on_call(accounts, recipient)?,
+            cross_chain_message.name.clone(),
+            cross_chain_message.symbol.clone(),
+            leaf_uri,
+            cross_chain_message.royalty_bps.unwrap_or_default(), // resolved in on_call
+        )?;
+        accounts.nft_info.asset_id = asset_id;
+
+        msg!("Compressed NFT minted from cross-chain transfer: {} - {} to {} as asset {}",
+            cross_chain_message.name, metadata_uri, recipient, asset_id);
+        Ok(())
+    }
+
+    // the leaf was burned on the way out, so an unlock mints a new one for the owner
+    pub fn return_to_owner(accounts: &mut OnCall<'_>, nonce: u64) -> Result<NftUnlocked> {
+        let nft_info = &mut accounts.nft_info;
+        nft_info.is_locked = false;
+        nft_info.cross_chain_recipient = [0; 32];
+        let unlocked = release_lock(&mut accounts.nft_program, nft_info, UnlockReason::Returned, nonce)?;
+
+        // the new asset id is the only thing that has to wait for the cpi, a bridged
+        // nft gets its leaf uri rewritten like when it first arrived
+        let nft_info = &accounts.nft_info;
+        let leaf_uri = match nft_info.bridged {
+            true => display_uri(&accounts.nft_program, &nft_info.metadata_uri),
+            false => nft_info.metadata_uri.clone(),
+        };
+        let asset_id = mint_leaf(
+            &CompressedMint::from_on_call(accounts, nft_info.owner)?,
+            nft_info.name.clone(),
+            nft_info.symbol.clone(),
+            leaf_uri,
+            nft_info.royalty_bps,
+        )?;
+        accounts.nft_info.asset_id = asset_id;
+
+        msg!("Compressed NFT returned to owner as asset {}", asset_id);
+        Ok(unlocked)
+    }
+
+    // accounts for minting a compressed nft into the program tree
+    struct CompressedMint<'info> {
+        tree_config: AccountInfo<'info>,
+        merkle_tree: AccountInfo<'info>,
+        leaf_owner: AccountInfo<'info>,
+        payer: AccountInfo<'info>,
+        nft_program: AccountInfo<'info>,
+        nft_program_bump: u8,
+        log_wrapper: AccountInfo<'info>,
+        compression_program: AccountInfo<'info>,
+        bubblegum_program: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    }
+
+    impl<'info> CompressedMint<'info> {
+        // leaf owner comes in as nft_owner and has to be the wallet we expect
+        fn from_on_call(accounts: &OnCall<'info>, owner: Pubkey) -> Result<Self> {
+            let leaf_owner = required(&accounts.nft_owner)?;
+            require_keys_eq!(leaf_owner.key(), owner, NftError::Unauthorized);
+            Ok(Self {
+                tree_config: required(&accounts.tree_config)?,
+                merkle_tree: required(&accounts.merkle_tree)?,
+                leaf_owner,
+                payer: accounts.payer.to_account_info(),
+                nft_program: accounts.nft_program.to_account_info(),
+                nft_program_bump: accounts.nft_program.bump,
+                log_wrapper: required(&accounts.log_wrapper)?,
+                compression_program: required(&accounts.compression_program)?,
+                bubblegum_program: required(&accounts.bubblegum_program)?,
+                system_program: accounts.system_program.to_account_info(),
+            })
+        }
+    }
+
+    // optional on_call accounts that only compressed mode needs
+    fn required<'info, T: ToAccountInfo<'info>>(account: &Option<T>) -> Result<AccountInfo<'info>> {
+        account
+            .as_ref()
+            .map(|account| account.to_account_info())
+            .ok_or_else(|| error!(NftError::MissingCompressedAccount))
+    }
+
+    // mints one leaf to the owner and returns its asset id, which bubblegum derives
+    // from the tree and the mint count before this mint
+    fn mint_leaf(
+        accounts: &CompressedMint<'_>,
+        name: String,
+        symbol: String,
+        uri: String,
+        seller_fee_basis_points: u16,
+    ) -> Result<Pubkey> {
+        let num_minted = TreeConfig::from_bytes(&accounts.tree_config.try_borrow_data()?)
+            .map_err(|_| ProgramError::InvalidAccountData)?
+            .num_minted;
+        let asset_id = get_asset_id(accounts.merkle_tree.key, num_minted);
+
+        MintV1CpiBuilder::new(&accounts.bubblegum_program)
+            .tree_config(&accounts.tree_config)
+            .leaf_owner(&accounts.leaf_owner)
+            .leaf_delegate(&accounts.leaf_owner)
+            .merkle_tree(&accounts.merkle_tree)
+            .payer(&accounts.payer)
+            .tree_creator_or_delegate(&accounts.nft_program)
+            .log_wrapper(&accounts.log_wrapper)
+            .compression_program(&accounts.compression_program)
+            .system_program(&accounts.system_program)
+            .metadata(MetadataArgs {
+                name,
+                symbol,
+                uri,
+                seller_fee_basis_points,
+                primary_sale_happened: false,
+                is_mutable: false,
+                edition_nonce: None,
+                token_standard: Some(TokenStandard::NonFungible),
+                collection: None,
+                uses: None,
+                token_program_version: TokenProgramVersion::Original,
+                creators: vec![],
+            })
+            .invoke_signed(&[&[crate::seeds::NFT_PROGRAM, &[accounts.nft_program_bump]]])?;
+
+        Ok(asset_id)
+    }
+}
+
+#[cfg(not(feature = "compressed"))]
+mod disabled {
+    use anchor_lang::prelude::*;
+
+    use crate::{
+        CreateTree, CrossChainMessage, NftError, NftUnlocked, OnCall, TransferCompressedToZetachain,
+    };
+
+    pub fn create_tree(
+        _ctx: Context<CreateTree>,
+        _max_depth: u32,
+        _max_buffer_size: u32,
+    ) -> Result<()> {
+        err!(NftError::CompressedNotEnabled)
+    }
+
+    pub fn transfer_to_zetachain<'info>(
+        _ctx: Context<'_, '_, '_, 'info, TransferCompressedToZetachain<'info>>,
+        _destination_chain_id: u64,
+        _recipient: [u8; 32],
+        _root: [u8; 32],
+        _data_hash: [u8; 32],
+        _creator_hash: [u8; 32],
+        _leaf_index: u32,
+    ) -> Result<()> {
+        err!(NftError::CompressedNotEnabled)
+    }
+
+    pub fn mint_inbound(
+        _accounts: &mut OnCall<'_>,
+        _nft_info_bump: u8,
+        _cross_chain_message: &CrossChainMessage,
+        _recipient: Pubkey,
+        _metadata_uri: String,
+        _hash_mismatch: bool,
+        _index: Option<u64>,
+    ) -> Result<()> {
+        err!(NftError::CompressedNotEnabled)
+    }
+
+    pub fn return_to_owner(_accounts: &mut OnCall<'_>, _nonce: u64) -> Result<NftUnlocked> {
+        err!(NftError::CompressedNotEnabled)
+    }
+}
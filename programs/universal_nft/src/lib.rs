@@ -1,59 +1,677 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::compute_budget::ComputeBudgetInstruction;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::secp256k1_program;
 use anchor_lang::solana_program::sysvar;
+use anchor_lang::Bumps;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    metadata::{create_metadata_accounts_v3, CreateMetadataAccountsV3, Metadata},
-    token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer},
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    metadata::{
+        create_master_edition_v3, create_metadata_accounts_v3,
+        mint_new_edition_from_master_edition_via_token, sign_metadata, CreateMasterEditionV3,
+        CreateMetadataAccountsV3, Metadata, MintNewEditionFromMasterEditionViaToken,
+        SignMetadata,
+    },
+    token_interface::{
+        burn, close_account, freeze_account, initialize_mint2, metadata_pointer_initialize, mint_to,
+        spl_token_2022, spl_token_2022::extension::ExtensionType,
+        spl_token_metadata_interface::state::TokenMetadata as ExtensionMetadata,
+        spl_token_metadata_interface::state::Field as ExtensionField,
+        thaw_account, token_metadata_initialize, token_metadata_update_field, transfer_checked,
+        Burn, CloseAccount, FreezeAccount, InitializeMint2, Mint, MetadataPointerInitialize,
+        MintTo, ThawAccount, TokenAccount, TokenInterface, TokenMetadataInitialize,
+        TokenMetadataUpdateField, TransferChecked,
+    },
 };
 use mpl_token_metadata::{
-    pda::{find_metadata_account},
-    state::{DataV2, Metadata as TokenMetadata},
+    accounts::{MasterEdition, Metadata as MetaplexMetadata},
+    instructions::{
+        BurnV1CpiBuilder, CreateV1CpiBuilder, DelegateCollectionV1CpiBuilder, MintV1CpiBuilder,
+        RevokeCollectionV1CpiBuilder, TransferV1CpiBuilder, UnverifyCollectionV1CpiBuilder,
+        UpdateV1CpiBuilder, VerifyCollectionV1CpiBuilder,
+    },
+    types::{
+        Collection as MetaplexCollection, CollectionDetails, Creator, Data as MetaplexData,
+        DataV2, PrintSupply, TokenStandard,
+    },
 };
 
+use std::collections::BTreeSet;
+
+mod compressed;
+
+// message layout lives in its own no_std crate so off-chain tools share it
+pub use universal_nft_wire as wire;
+pub use wire::{Attribute, MessageType, MAX_BATCH_LEAVES, MESSAGE_VERSION};
+
 // this is the program id, dont forget to update if u redeploy
-declare_id!("7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsUgit");
+declare_id!("7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU");
 
-/// Gateway program ID
+/// Known gateway program ids. only defaults for initialize, on_call checks the
+/// gateway stored in the program state so one build works on every cluster
 pub mod gateway {
-    use anchor_lang::prelude::declare_id;
-    #[cfg(feature = "dev")]
-    declare_id!("94U5AHQMKkV5txNJ17QPXWoh474PheGou6cNP2FEuL1d");
-    #[cfg(not(feature = "dev"))]
-    declare_id!("ZETAjseVjuFsxdRxo6MmTCvqFwb3ZHUx56Co3vCmGis");
+    use anchor_lang::prelude::{pubkey, Pubkey};
+
+    /// gateway of a local validator running the zetachain localnet
+    pub const LOCALNET: Pubkey = pubkey!("94U5AHQMKkV5txNJ17QPXWoh474PheGou6cNP2FEuL1d");
+    /// gateway deployed on devnet and mainnet-beta
+    pub const ZETACHAIN: Pubkey = pubkey!("ZETAjseVjuFsxdRxo6MmTCvqFwb3ZHUx56Co3vCmGis");
+
+    /// gateway to pass to initialize for a cluster name as the solana cli spells it
+    pub fn default_gateway_for_cluster(cluster: &str) -> Option<Pubkey> {
+        match cluster {
+            "localnet" | "localhost" => Some(LOCALNET),
+            "devnet" | "mainnet-beta" | "mainnet" => Some(ZETACHAIN),
+            _ => None,
+        }
+    }
+}
+
+/// pda seeds of the program accounts and lookups for them, the account constraints
+/// use the same constants so clients dont have to hardcode the strings
+pub mod seeds {
+    use anchor_lang::prelude::Pubkey;
+
+    pub const NFT_PROGRAM: &[u8] = b"nft-program";
+    pub const TREASURY: &[u8] = b"treasury";
+    /// + mint
+    pub const NFT_INFO: &[u8] = b"nft-info";
+    /// + origin mint from the message, the mint on_call creates for a bridged nft
+    pub const NFT_MINT: &[u8] = b"nft-mint";
+    /// + mint_count le bytes, mints from mint_nft_pda
+    pub const PDA_MINT: &[u8] = b"mint";
+    /// + owner
+    pub const OWNER_STATS: &[u8] = b"owner-stats";
+    /// + minter
+    pub const MINTER: &[u8] = b"minter";
+    /// + index le bytes
+    pub const INDEX: &[u8] = b"index";
+    /// + chain id le bytes
+    pub const CHAIN: &[u8] = b"chain";
+    /// + message hash
+    pub const DEAD_LETTER: &[u8] = b"dead-letter";
+    /// + mint + holder
+    pub const COMPLIANCE: &[u8] = b"compliance";
+    /// + nonce le bytes
+    pub const SKIPPED_MESSAGE: &[u8] = b"skipped-message";
+    /// + nonce le bytes
+    pub const PENDING_NONCE: &[u8] = b"pending-nonce";
+    /// + nonce le bytes
+    pub const PENDING_CLAIM: &[u8] = b"pending-claim";
+    /// + nonce le bytes
+    pub const FAILED_INBOUND: &[u8] = b"failed-inbound";
+    /// + batch id
+    pub const INBOUND_BATCH: &[u8] = b"inbound-batch";
+    /// + recipient
+    pub const BLOCKED_RECIPIENT: &[u8] = b"blocked-recipient";
+    /// + mint
+    pub const BLOCKED_MINT: &[u8] = b"blocked-mint";
+    /// + collection mint
+    pub const APPROVED_COLLECTION: &[u8] = b"approved-collection";
+    pub const COLLECTION: &[u8] = b"collection";
+    pub const COLLECTION_MINT: &[u8] = b"collection-mint";
+    pub const BRIDGED_COLLECTION: &[u8] = b"bridged-collection";
+    pub const BRIDGED_COLLECTION_MINT: &[u8] = b"bridged-collection-mint";
+
+    pub fn find_program_state() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[NFT_PROGRAM], &crate::ID)
+    }
+
+    pub fn find_treasury() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[TREASURY], &crate::ID)
+    }
+
+    pub fn find_nft_info(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[NFT_INFO, mint.as_ref()], &crate::ID)
+    }
+
+    /// `origin_mint` is the mint the message carries, for nfts from other chains
+    /// their token id
+    pub fn find_bridged_mint(origin_mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[NFT_MINT, origin_mint.as_ref()], &crate::ID)
+    }
+
+    pub fn find_pda_mint(mint_count: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PDA_MINT, mint_count.to_le_bytes().as_ref()], &crate::ID)
+    }
+
+    pub fn find_owner_stats(owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[OWNER_STATS, owner.as_ref()], &crate::ID)
+    }
+
+    pub fn find_minter_role(minter: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[MINTER, minter.as_ref()], &crate::ID)
+    }
+
+    pub fn find_mint_index(index: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[INDEX, index.to_le_bytes().as_ref()], &crate::ID)
+    }
+
+    pub fn find_chain_info(chain_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[CHAIN, chain_id.to_le_bytes().as_ref()], &crate::ID)
+    }
+
+    pub fn find_dead_letter(message_hash: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[DEAD_LETTER, message_hash.as_ref()], &crate::ID)
+    }
+
+    pub fn find_compliance_action(mint: &Pubkey, holder: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[COMPLIANCE, mint.as_ref(), holder.as_ref()], &crate::ID)
+    }
+
+    pub fn find_skipped_message(nonce: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[SKIPPED_MESSAGE, nonce.to_le_bytes().as_ref()], &crate::ID)
+    }
+
+    pub fn find_pending_nonce(nonce: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PENDING_NONCE, nonce.to_le_bytes().as_ref()], &crate::ID)
+    }
+
+    pub fn find_pending_claim(nonce: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PENDING_CLAIM, nonce.to_le_bytes().as_ref()], &crate::ID)
+    }
+
+    pub fn find_failed_inbound(nonce: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[FAILED_INBOUND, nonce.to_le_bytes().as_ref()], &crate::ID)
+    }
+
+    pub fn find_inbound_batch(batch_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[INBOUND_BATCH, batch_id.as_ref()], &crate::ID)
+    }
+
+    pub fn find_blocked_recipient(recipient: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[BLOCKED_RECIPIENT, recipient.as_ref()], &crate::ID)
+    }
+
+    pub fn find_blocked_mint(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[BLOCKED_MINT, mint.as_ref()], &crate::ID)
+    }
+
+    pub fn find_approved_collection(collection_mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[APPROVED_COLLECTION, collection_mint.as_ref()], &crate::ID)
+    }
+
+    pub fn find_collection() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[COLLECTION], &crate::ID)
+    }
+
+    pub fn find_collection_mint() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[COLLECTION_MINT], &crate::ID)
+    }
+
+    pub fn find_bridged_collection() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[BRIDGED_COLLECTION], &crate::ID)
+    }
+
+    pub fn find_bridged_collection_mint() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[BRIDGED_COLLECTION_MINT], &crate::ID)
+    }
+}
+
+/// the positional cpi signatures from before MintParams / TransferParams, kept for
+/// one release. the instruction data didnt change, so these only save rust callers
+/// the rewrite
+#[cfg(feature = "cpi")]
+pub mod legacy_cpi {
+    use super::*;
+    use anchor_lang::context::CpiContext;
+
+    #[deprecated(note = "use cpi::mint_nft with MintParams")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn mint_nft<'info>(
+        ctx: CpiContext<'_, '_, '_, 'info, cpi::accounts::MintNft<'info>>,
+        name: String,
+        symbol: String,
+        uri: String,
+        recipient: Pubkey,
+        token_standard: Option<NftStandard>,
+        creators: Option<Vec<NftCreator>>,
+        metadata_hash: Option<[u8; 32]>,
+        attributes: Option<Vec<Attribute>>,
+        royalty_bps: Option<u16>,
+    ) -> Result<()> {
+        cpi::mint_nft(
+            ctx,
+            MintParams {
+                name,
+                symbol,
+                uri,
+                recipient,
+                token_standard,
+                creators,
+                metadata_hash,
+                attributes,
+                royalty_bps,
+            },
+        )
+    }
+
+    #[deprecated(note = "use cpi::mint_nft_pda with MintParams")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn mint_nft_pda<'info>(
+        ctx: CpiContext<'_, '_, '_, 'info, cpi::accounts::MintNftPda<'info>>,
+        name: String,
+        symbol: String,
+        uri: String,
+        recipient: Pubkey,
+        token_standard: Option<NftStandard>,
+        creators: Option<Vec<NftCreator>>,
+        metadata_hash: Option<[u8; 32]>,
+        attributes: Option<Vec<Attribute>>,
+        royalty_bps: Option<u16>,
+    ) -> Result<()> {
+        cpi::mint_nft_pda(
+            ctx,
+            MintParams {
+                name,
+                symbol,
+                uri,
+                recipient,
+                token_standard,
+                creators,
+                metadata_hash,
+                attributes,
+                royalty_bps,
+            },
+        )
+    }
+
+    #[deprecated(note = "use cpi::transfer_to_zetachain with TransferParams")]
+    pub fn transfer_to_zetachain<'info>(
+        ctx: CpiContext<'_, '_, '_, 'info, cpi::accounts::TransferToZetachain<'info>>,
+        destination_chain_id: u64,
+        recipient: [u8; 32],
+        nonce: u64, // ignored, the program numbers outbound transfers itself now
+    ) -> Result<()> {
+        cpi::transfer_to_zetachain(
+            ctx,
+            TransferParams {
+                destination_chain_id,
+                recipient,
+                nonce,
+            },
+        )
+    }
 }
 
 #[program]
 pub mod universal_nft {
     use super::*;
 
-    /// initilize the universal nft program, must be called once at start
-    pub fn initialize(ctx: Context<Initialize>, gateway: Pubkey) -> Result<()> {
+    /// initilize the universal nft program, must be called once at start. gateway
+    /// is the program on_call accepts calls from, see gateway::default_gateway_for_cluster.
+    /// the authority doesnt have to sign so a cold key or multisig can be set up
+    /// by a hot deployer, authority_must_sign makes it sign anyway
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        gateway: Pubkey,
+        authority_must_sign: bool,
+    ) -> Result<()> {
+        require!(gateway != Pubkey::default(), NftError::InvalidGateway);
+        let authority = &ctx.accounts.authority;
+        require!(authority.key() != Pubkey::default(), NftError::InvalidAuthority);
+        // same key as the payer means it signed already
+        require!(
+            !authority_must_sign || authority.is_signer,
+            anchor_lang::error::ErrorCode::AccountNotSigner
+        );
+
         let nft_program = &mut ctx.accounts.nft_program;
-        nft_program.authority = ctx.accounts.authority.key();
+        nft_program.authority = authority.key();
+        nft_program.pending_authority = Pubkey::default();
+        nft_program.version = STATE_VERSION;
         nft_program.total_supply = 0;
+        nft_program.mint_count = 0;
+        nft_program.index_count = 0;
         nft_program.gateway = gateway;
         nft_program.nonce = 0;
+        nft_program.nonce_window = [u64::MAX; 4];
+        nft_program.tss_address = [0; 20];
+        nft_program.require_tss_signature = false;
+        nft_program.admin_nonce = 0;
+        nft_program.uri_rewrite = None;
+        nft_program.placeholder_uri = String::new();
+        nft_program.claim_mode = false;
+        nft_program.inbound_metadata_mutable = true;
+        nft_program.immutable_bridged_count = 0;
+        nft_program.max_supply = 0;
+        nft_program.outbound_nonce = 0;
+        nft_program.mint_fee_lamports = 0;
+        nft_program.max_mints_per_wallet = 0;
+        nft_program.public_mint = true;
+        nft_program.mint_paused = false;
+        nft_program.strict_metadata_hash = false;
+        nft_program.clamp_royalty_bps = false;
+        nft_program.default_royalty_bps = 0;
+        nft_program.default_royalty_recipient = Pubkey::default();
+        nft_program.locked_count = 0;
+        nft_program.sweep_rent_destination = SweepRentDestination::Treasury;
+        nft_program.lazy_metadata = false;
+        nft_program.dead_letter_retry = DeadLetterRetry::Authority;
+        nft_program.allow_program_recipients = false;
+        nft_program.ordered_delivery = false;
+        nft_program.default_token_standard = NftStandard::NonFungible;
+        nft_program.default_symbol = DEFAULT_INBOUND_SYMBOL.to_string();
+        nft_program.inbound_string_policy = InboundStringPolicy::Truncate;
+        nft_program.metadata_mode = MetadataMode::Metaplex;
+        nft_program.compressed_mode = false;
+        nft_program.merkle_tree = Pubkey::default();
+        nft_program.collection_mint = Pubkey::default();
+        nft_program.collection_delegate = Pubkey::default();
+        nft_program.defer_collection_verify = false;
+        nft_program.bridged_collection_mint = Pubkey::default();
+        nft_program.max_uri_len = DEFAULT_MAX_URI_LEN;
+        nft_program.allowed_uri_prefixes = DEFAULT_URI_PREFIXES
+            .iter()
+            .map(|prefix| prefix.to_string())
+            .collect();
         nft_program.bump = ctx.bumps.nft_program;
+
+        // treasury starts empty, mint fees pile up here
+        ctx.accounts.treasury.bump = ctx.bumps.treasury;
         
         msg!("Universal NFT program initialized with gateway: {}", gateway);
         Ok(())
     }
 
-    /// mint a new nft, can be called localy or from crosschain
+    /// first step of an authority handover, the nominee gets nothing until it
+    /// accepts. nominating again replaces the pending nominee
+    pub fn nominate_authority(ctx: Context<NominateAuthority>, new_authority: Pubkey) -> Result<()> {
+        let nft_program = &mut ctx.accounts.nft_program;
+        require!(
+            new_authority != Pubkey::default() && new_authority != nft_program.authority,
+            NftError::InvalidAuthority
+        );
+        nft_program.pending_authority = new_authority;
+
+        emit!(AuthorityNominated {
+            authority: nft_program.authority,
+            nominee: new_authority,
+        });
+        msg!("Authority nominated: {}", new_authority);
+        Ok(())
+    }
+
+    /// the nominee signs to take over, the old authority loses its powers here
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let nft_program = &mut ctx.accounts.nft_program;
+        let previous = nft_program.authority;
+        nft_program.authority = ctx.accounts.new_authority.key();
+        nft_program.pending_authority = Pubkey::default();
+
+        emit!(AuthorityAccepted {
+            previous,
+            authority: nft_program.authority,
+        });
+        msg!("Authority transferred from {} to {}", previous, nft_program.authority);
+        Ok(())
+    }
+
+    /// either the authority or the nominee can call off a pending handover
+    pub fn cancel_authority_nomination(ctx: Context<CancelAuthorityNomination>) -> Result<()> {
+        let nft_program = &mut ctx.accounts.nft_program;
+        require!(
+            nft_program.pending_authority != Pubkey::default(),
+            NftError::NoPendingAuthority
+        );
+        let nominee = nft_program.pending_authority;
+        nft_program.pending_authority = Pubkey::default();
+
+        emit!(AuthorityNominationCancelled {
+            nominee,
+            cancelled_by: ctx.accounts.signer.key(),
+        });
+        msg!("Authority nomination of {} cancelled", nominee);
+        Ok(())
+    }
+
+    /// give up the authority for good, nothing can be administered afterwards.
+    /// confirm has to be the current authority pubkey, so it cant happen by accident
+    pub fn renounce_authority(ctx: Context<RenounceAuthority>, confirm: Pubkey) -> Result<()> {
+        let nft_program = &mut ctx.accounts.nft_program;
+        require_keys_eq!(confirm, nft_program.authority, NftError::RenounceNotConfirmed);
+        let previous = nft_program.authority;
+        nft_program.authority = Pubkey::default();
+        nft_program.pending_authority = Pubkey::default();
+
+        emit!(AuthorityRenounced { previous });
+        msg!("Authority {} renounced", previous);
+        Ok(())
+    }
+
+    /// bring the state account of an older deployment up to the current layout,
+    /// other instructions refuse to run until this has been done. authority only
+    pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+        let state_info = ctx.accounts.nft_program.to_account_info();
+        let (state, from_version) = upgrade_state(&state_info.try_borrow_data()?)?;
+        require!(is_authority(&state, ctx.accounts.authority.key()), NftError::Unauthorized);
+
+        let space = 8 + NftProgramState::INIT_SPACE;
+        if state_info.data_len() < space {
+            top_up_rent(
+                &state_info,
+                &ctx.accounts.payer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                space,
+            )?;
+            state_info.realloc(space, true)?;
+        }
+        state.try_serialize(&mut &mut state_info.try_borrow_mut_data()?[..])?;
+
+        msg!("State migrated from version {} to {}", from_version, STATE_VERSION);
+        Ok(())
+    }
+
+    /// upgrade one nft info to the current layout ahead of time, anyone can pay
+    /// for it so keepers can go through them in bulk. already current is a no-op
+    pub fn migrate_nft_info(ctx: Context<MigrateNftInfo>, mint: Pubkey) -> Result<()> {
+        let from_version = ctx.accounts.nft_info.version;
+        upgrade_nft_info(
+            &mut ctx.accounts.nft_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        if from_version != NFT_INFO_VERSION {
+            msg!("Nft info of {} migrated to version {}", mint, NFT_INFO_VERSION);
+        }
+        Ok(())
+    }
+
+    /// mint a new nft, can be called localy or from crosschain. creators are
+    /// optional, if the program pda is one of them it gets verified right away.
+    /// metadata_hash is the sha256 of the json behind the uri, it travels with
+    /// the nft so other chains can tell if the json got swapped. attributes are a
+    /// few traits kept on chain in nft info so integrations dont need the json.
+    /// royalty_bps falls back to the program default royalty when left out.
+    /// the mint is a fresh keypair signing the tx, mint_nft_pda takes a program
+    /// derived mint instead
+    pub fn mint_nft_with_keypair(
+        ctx: Context<MintNft>,
+        params: MintParams,
+    ) -> Result<()> {
+        let MintParams {
+            name,
+            symbol,
+            uri,
+            recipient,
+            token_standard,
+            creators,
+            metadata_hash,
+            attributes,
+            royalty_bps,
+        } = params;
+        let nft_info_bump = ctx.bumps.nft_info;
+        let token_standard =
+            token_standard.unwrap_or(ctx.accounts.nft_program.default_token_standard);
+        // owner stats gets created on the first mint to a wallet
+        ctx.accounts.owner_stats.owner = recipient;
+        ctx.accounts.owner_stats.bump = ctx.bumps.owner_stats;
+        ctx.accounts.mint_index.bump = ctx.bumps.mint_index;
+        // the mint is a fresh keypair that signs the tx itself
+        let minted = process_mint_nft(
+            ctx.accounts.mint_accounts(),
+            &[],
+            nft_info_bump,
+            name,
+            symbol,
+            uri,
+            recipient,
+            token_standard,
+            creators,
+            metadata_hash,
+            attributes.unwrap_or_default(),
+            royalty_bps,
+        )?;
+        emit_cpi!(minted);
+        Ok(())
+    }
+
+    /// same as mint_nft_with_keypair, kept under the old name for clients built
+    /// before the keypair and pda flows got their own names
     pub fn mint_nft(
         ctx: Context<MintNft>,
-        name: String,
-        symbol: String,
-        uri: String,
+        params: MintParams,
+    ) -> Result<()> {
+        mint_nft_with_keypair(ctx, params)
+    }
+
+    /// mint a new nft where the mint is a program pda, seeded by the mint count
+    /// so clients can derive the address up front and dont need a mint keypair
+    pub fn mint_nft_pda(
+        ctx: Context<MintNftPda>,
+        params: MintParams,
+    ) -> Result<()> {
+        // the address is checked on the account, this only finds the bump again
+        let (_, mint_bump) = seeds::find_pda_mint(ctx.accounts.nft_program.mint_count);
+        let MintParams {
+            name,
+            symbol,
+            uri,
+            recipient,
+            token_standard,
+            creators,
+            metadata_hash,
+            attributes,
+            royalty_bps,
+        } = params;
+        let nft_info_bump = ctx.bumps.nft_info;
+        let token_standard =
+            token_standard.unwrap_or(ctx.accounts.nft_program.default_token_standard);
+        // owner stats gets created on the first mint to a wallet
+        ctx.accounts.owner_stats.owner = recipient;
+        ctx.accounts.owner_stats.bump = ctx.bumps.owner_stats;
+        ctx.accounts.mint_index.bump = ctx.bumps.mint_index;
+        let count_bytes = ctx.accounts.nft_program.mint_count.to_le_bytes();
+        let mint_bump = [mint_bump];
+        let minted = process_mint_nft(
+            ctx.accounts.mint_accounts(),
+            &[&[seeds::PDA_MINT, count_bytes.as_ref(), &mint_bump]],
+            nft_info_bump,
+            name,
+            symbol,
+            uri,
+            recipient,
+            token_standard,
+            creators,
+            metadata_hash,
+            attributes.unwrap_or_default(),
+            royalty_bps,
+        )?;
+        emit_cpi!(minted);
+        Ok(())
+    }
+
+    /// turn one of our metaplex nfts into a master edition so prints can be made
+    /// from it. max_supply caps the prints, None means unlimited. authority only
+    pub fn create_master_edition(
+        ctx: Context<CreateMasterEdition>,
+        mint: Pubkey,
+        max_supply: Option<u64>,
+    ) -> Result<()> {
+        let nft_info = &ctx.accounts.nft_info;
+        require!(
+            nft_info.token_standard == NftStandard::NonFungible
+                && !nft_info.compressed
+                && nft_info.master_mint == Pubkey::default(),
+            NftError::NotMasterEligible
+        );
+
+        // token metadata moves the mint and freeze authority over to the edition pda
+        create_master_edition_v3(
+            CpiContext::new(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMasterEditionV3 {
+                    edition: ctx.accounts.master_edition.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    update_authority: ctx.accounts.nft_program.to_account_info(),
+                    mint_authority: ctx.accounts.nft_program.to_account_info(),
+                    payer: ctx.accounts.payer.to_account_info(),
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+            )
+            .with_signer(&[&[seeds::NFT_PROGRAM, &[ctx.accounts.nft_program.bump]]]),
+            max_supply,
+        )?;
+
+        msg!("Master edition created for mint {} max supply {:?}", mint, max_supply);
+        Ok(())
+    }
+
+    /// mint a numbered print of a master edition to the recipient. the master
+    /// holder signs, token metadata makes the edition and enforces max_supply
+    pub fn mint_edition(
+        ctx: Context<MintEdition>,
+        master_mint: Pubkey,
+        edition_number: u64,
         recipient: Pubkey,
     ) -> Result<()> {
-        // check the input lengths so we dont break stuff
-        require!(name.len() <= 32, NftError::InvalidMetadata);
-        require!(symbol.len() <= 10, NftError::InvalidMetadata);
-        require!(uri.len() <= 200, NftError::InvalidMetadata);
+        require!(!ctx.accounts.nft_program.mint_paused, NftError::MintPaused);
+        check_supply_cap(&ctx.accounts.nft_program)?;
+        validate_recipient(&ctx.accounts.nft_program, recipient)?;
+        require!(!is_blocked(&ctx.accounts.blocked_recipient), NftError::RecipientBlocked);
+
+        // token metadata would catch this too, but with a less useful error
+        let master_edition = MasterEdition::from_bytes(&ctx.accounts.master_edition.try_borrow_data()?)
+            .map_err(|_| NftError::NotMasterEdition)?;
+        if let Some(max_supply) = master_edition.max_supply {
+            require!(
+                master_edition.supply < max_supply && edition_number <= max_supply,
+                NftError::MaxEditionsReached
+            );
+        }
+        require!(edition_number > 0, NftError::InvalidEditionNumber);
 
-        // mint the token, only 1 for nft
+        let nft_program_bump = ctx.accounts.nft_program.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[seeds::NFT_PROGRAM, &[nft_program_bump]]];
+
+        // the print mint needs supply 1 in the recipient ata before token metadata
+        // turns it into an edition
+        create_nft_mint(
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.nft_program.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            MetadataMode::Metaplex,
+            &[],
+        )?;
+        anchor_spl::associated_token::create_idempotent(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            anchor_spl::associated_token::Create {
+                payer: ctx.accounts.payer.to_account_info(),
+                associated_token: ctx.accounts.token_account.to_account_info(),
+                authority: ctx.accounts.recipient.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
         mint_to(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
@@ -62,729 +680,11990 @@ pub mod universal_nft {
                     to: ctx.accounts.token_account.to_account_info(),
                     authority: ctx.accounts.nft_program.to_account_info(),
                 },
-            ).with_signer(&[&[
-                b"nft-program",
-                &[ctx.accounts.nft_program.bump]
-            ]]),
-            1, // nfts always have supply 1
-        )?;
-
-        // make the metadata for the nft
-        let data_v2 = DataV2 {
-            name: name.clone(),
-            symbol: symbol.clone(),
-            uri: uri.clone(),
-            seller_fee_basis_points: 0,
-            creators: None,
-            collection: None,
-            uses: None,
-        };
+            )
+            .with_signer(signer_seeds),
+            1,
+        )?;
 
-        create_metadata_accounts_v3(
+        mint_new_edition_from_master_edition_via_token(
             CpiContext::new(
                 ctx.accounts.token_metadata_program.to_account_info(),
-                CreateMetadataAccountsV3 {
-                    metadata: ctx.accounts.metadata.to_account_info(),
-                    mint: ctx.accounts.mint.to_account_info(),
-                    mint_authority: ctx.accounts.nft_program.to_account_info(),
-                    update_authority: ctx.accounts.nft_program.to_account_info(),
+                MintNewEditionFromMasterEditionViaToken {
+                    new_metadata: ctx.accounts.metadata.to_account_info(),
+                    new_edition: ctx.accounts.edition.to_account_info(),
+                    master_edition: ctx.accounts.master_edition.to_account_info(),
+                    new_mint: ctx.accounts.mint.to_account_info(),
+                    edition_mark_pda: ctx.accounts.edition_mark.to_account_info(),
+                    new_mint_authority: ctx.accounts.nft_program.to_account_info(),
                     payer: ctx.accounts.payer.to_account_info(),
+                    token_account_owner: ctx.accounts.master_owner.to_account_info(),
+                    token_account: ctx.accounts.master_token_account.to_account_info(),
+                    new_metadata_update_authority: ctx.accounts.nft_program.to_account_info(),
+                    metadata: ctx.accounts.master_metadata.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
                     system_program: ctx.accounts.system_program.to_account_info(),
                     rent: ctx.accounts.rent.to_account_info(),
+                    metadata_mint: ctx.accounts.master_mint.to_account_info(),
                 },
-            ).with_signer(&[&[
-                b"nft-program",
-                &[ctx.accounts.nft_program.bump]
-            ]]),
-            data_v2,
-            false, // not mutable
-            true,  // update authority is signer
-            None,  // no collection details
+            )
+            .with_signer(signer_seeds),
+            edition_number,
         )?;
 
-        // update the program state, add 1 to supply
         let nft_program = &mut ctx.accounts.nft_program;
-        nft_program.total_supply = nft_program.total_supply
-            .checked_add(1)
-            .ok_or(NftError::Overflow)?;
+        bump(&mut nft_program.total_supply)?;
+        bump(&mut nft_program.mint_count)?;
 
-        // save nft info for crosschain stuff
+        // prints share the master's data, nft info points back at the master
+        let master_info = &ctx.accounts.master_info;
         let nft_info = &mut ctx.accounts.nft_info;
         nft_info.mint = ctx.accounts.mint.key();
         nft_info.owner = recipient;
-        nft_info.metadata_uri = uri.clone();
-        nft_info.name = name.clone();
-        nft_info.symbol = symbol.clone();
+        nft_info.metadata_uri = master_info.metadata_uri.clone();
+        nft_info.name = master_info.name.clone();
+        nft_info.symbol = master_info.symbol.clone();
         nft_info.is_locked = false;
+        nft_info.cross_chain_recipient = [0; 32];
+        nft_info.token_standard = NftStandard::NonFungible;
+        nft_info.compressed = false;
+        nft_info.asset_id = Pubkey::default();
+        nft_info.in_collection = false;
+        nft_info.bridged_collection = false;
+        nft_info.master_mint = master_mint;
+        nft_info.edition = edition_number;
+        nft_info.metadata_hash = master_info.metadata_hash;
+        nft_info.hash_mismatch = false;
+        nft_info.minted_to = Pubkey::default();
+        nft_info.bridged = false;
+        nft_info.index = None; // prints arent in the enumerable index
+        nft_info.royalty_bps = master_info.royalty_bps;
+        nft_info.royalty_recipient = master_info.royalty_recipient;
+        nft_info.attributes = master_info.attributes.clone();
         nft_info.bump = ctx.bumps.nft_info;
+        nft_info.version = NFT_INFO_VERSION;
+        nft_info.metadata_created = true;
+        nft_info.needs_metadata_repair = false;
+        nft_info.metadata_immutable = false;
+        nft_info.external = false;
+        nft_info.lock_nonce = 0;
+
+        emit_cpi!(NftMinted {
+            mint: nft_info.mint,
+            recipient,
+            name: nft_info.name.clone(),
+            symbol: nft_info.symbol.clone(),
+            uri: nft_info.metadata_uri.clone(),
+            token_id: None,
+            slot: Clock::get()?.slot,
+            payer: ctx.accounts.payer.key(),
+            fee_lamports: 0,
+            metadata_hash: nft_info.metadata_hash,
+        });
 
-        msg!("NFT minted: {} - {} to {}", name, uri, recipient);
+        msg!("Edition {} of {} minted", edition_number, master_mint);
         Ok(())
     }
 
-    /// start a crosschain transfer to zetachain, locks the nft
-    pub fn transfer_to_zetachain(
-        ctx: Context<TransferToZetachain>,
-        destination_chain_id: u64,
-        recipient: [u8; 32],
-        nonce: u64,
-    ) -> Result<()> {
-        let nft_info = &mut ctx.accounts.nft_info;
-        let nft_program = &mut ctx.accounts.nft_program;
+    /// lets a listed creator verify themselves on an nft from this program, the
+    /// creator signs and we forward to token metadata
+    pub fn verify_creator(ctx: Context<VerifyCreator>, mint: Pubkey) -> Result<()> {
+        sign_metadata(CpiContext::new(
+            ctx.accounts.token_metadata_program.to_account_info(),
+            SignMetadata {
+                creator: ctx.accounts.creator.to_account_info(),
+                metadata: ctx.accounts.metadata.to_account_info(),
+            },
+        ))?;
 
-        // do some security checks so only owner can transfer and not locked
-        require!(nft_info.owner == ctx.accounts.owner.key(), NftError::Unauthorized);
-        require!(!nft_info.is_locked, NftError::TokenLocked);
-        require!(nonce > nft_program.nonce, NftError::InvalidNonce);
+        msg!("Creator {} verified on mint {}", ctx.accounts.creator.key(), mint);
+        Ok(())
+    }
 
-        // lock the nft by moving it to program, dont burn it
-        transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.owner_token_account.to_account_info(),
-                    to: ctx.accounts.program_token_account.to_account_info(),
-                    authority: ctx.accounts.owner.to_account_info(),
-                },
-            ),
-            1,
-        )?;
+    /// set the fee charged on every local mint, only the authority can do this
+    pub fn set_mint_fee(ctx: Context<SetMintFee>, mint_fee_lamports: u64) -> Result<()> {
+        apply_admin_action(
+            &mut ctx.accounts.nft_program,
+            AdminAction::SetMintFee { mint_fee_lamports },
+        );
+        Ok(())
+    }
 
-        // update nft state to locked and set crosschain recipient
-        nft_info.is_locked = true;
-        nft_info.cross_chain_recipient = recipient;
-        nft_program.nonce = nonce;
+    /// look up the mint at an index, returns the default pubkey if it was burned
+    pub fn get_mint_by_index(ctx: Context<GetMintByIndex>, index: u64) -> Result<Pubkey> {
+        let mint = ctx.accounts.mint_index.mint;
 
-        // make the crosschain message
-        let message = CrossChainMessage {
-            message_type: MessageType::Transfer,
-            mint: nft_info.mint,
-            recipient,
-            metadata_uri: nft_info.metadata_uri.clone(),
-            name: nft_info.name.clone(),
-            symbol: nft_info.symbol.clone(),
-            nonce,
+        msg!("Mint at index {}: {}", index, mint);
+        Ok(mint)
+    }
+
+    /// which build this is, returned through return data (use `.view()`) so
+    /// relayers can tell what the deployed program speaks
+    pub fn get_version(_ctx: Context<GetVersion>) -> Result<ProgramVersion> {
+        let version = ProgramVersion::current();
+
+        msg!("Universal NFT {}", env!("CARGO_PKG_VERSION"));
+        Ok(version)
+    }
+
+    /// one consistent snapshot for dashboards, returned through return data (use
+    /// `.view()`). starts with a layout version, fields only ever get appended
+    pub fn get_stats(ctx: Context<GetStats>) -> Result<ProgramStats> {
+        let nft_program = &ctx.accounts.nft_program;
+        let stats = ProgramStats {
+            version: STATS_VERSION,
+            total_supply: nft_program.total_supply,
+            locked_count: nft_program.locked_count,
+            nonce: nft_program.nonce,
+            mint_paused: nft_program.mint_paused,
+            treasury_lamports: ctx.accounts.treasury.to_account_info().lamports(),
+            immutable_bridged_count: nft_program.immutable_bridged_count,
+            outbound_nonce: nft_program.outbound_nonce,
         };
 
-        // serialize the message for sending
-        let message_bytes = message.try_to_vec()?;
-        
-        // send via gateway (not implemented, just log for now)
-        msg!("Cross-chain transfer initiated for mint {} to chain {} recipient {:?}", 
-            nft_info.mint, destination_chain_id, recipient);
-        msg!("Message: {:?}", message_bytes);
+        msg!("Supply {}, locked {}", stats.total_supply, stats.locked_count);
+        Ok(stats)
+    }
+
+    /// bridge status of one nft, returned through return data (use `.view()`) so
+    /// wallets dont have to decode the account. an unknown mint gives `NotFound`
+    /// instead of an error
+    pub fn get_nft_info(ctx: Context<GetNftInfo>, mint: Pubkey) -> Result<NftInfoView> {
+        let account = ctx.accounts.nft_info.to_account_info();
+        let nft_info = if account.owner == &crate::ID && !account.data_is_empty() {
+            Some(NftInfo::try_deserialize(&mut &account.data.borrow()[..])?)
+        } else {
+            None
+        };
+        let view = nft_info_view(mint, nft_info.as_ref(), ctx.accounts.nft_program.nonce);
 
+        msg!("NFT info for mint {}: {}", mint, if nft_info.is_some() { "found" } else { "not found" });
+        Ok(view)
+    }
+
+    /// emits the highest nonce taken and the next one above it, anyone can call it
+    /// so relayers can resync after a stuck message
+    pub fn report_nonce_gap(ctx: Context<ReportNonceGap>) -> Result<()> {
+        let nft_program = &ctx.accounts.nft_program;
+        let expected_nonce = expected_nonce(nft_program);
+
+        emit!(NonceReported {
+            last_nonce: nft_program.nonce,
+            expected_nonce,
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("Last nonce {}, next expected {}", nft_program.nonce, expected_nonce);
         Ok(())
     }
 
-    /// handle incoming crosschain message from zetachain, like mint or unlock
-    pub fn handle_cross_chain_call(
-        ctx: Context<HandleCrossChainCall>,
-        sender: [u8; 32],
-        source_chain_id: u64,
-        message: Vec<u8>,
-        nonce: u64,
-    ) -> Result<()> {
+    /// give up on an inbound message that can never be delivered, like a payload
+    /// that doesnt decode. the nonce is taken the way a delivery takes it, so the
+    /// message is turned away if it shows up later, and a skipped message pda
+    /// `[b"skipped-message", nonce]` keeps who skipped it and why. authority only
+    pub fn skip_message(ctx: Context<SkipMessage>, nonce: u64, reason: String) -> Result<()> {
+        validate_reason(&reason)?;
         let nft_program = &mut ctx.accounts.nft_program;
-        
-        // replay protection so we dont process same message twice
-        require!(nonce > nft_program.nonce, NftError::InvalidNonce);
-        nft_program.nonce = nonce;
+        let last_nonce = nft_program.nonce;
+        accept_message_nonce(nft_program, nonce)?;
 
-        // try to parse the incoming message, fail if not valid
-        let cross_chain_message: CrossChainMessage = 
-            CrossChainMessage::try_from_slice(&message)
-                .map_err(|_| NftError::InvalidMessage)?;
-        
-        match cross_chain_message.message_type {
-            MessageType::Transfer => {
-                // check the recipient is valid pubkey
-                let recipient_pubkey = Pubkey::try_from(cross_chain_message.recipient)
-                    .map_err(|_| NftError::InvalidRecipient)?;
-                
-                msg!("Handling cross-chain NFT transfer from chain {} to {}", 
-                    source_chain_id, recipient_pubkey);
-                
-                // here we would mint the nft, but not implemented yet
-                // would need to create accounts on the fly
-                msg!("Would mint NFT: {}", cross_chain_message.name);
-            }
-            MessageType::Unlock => {
-                // handle unlock for return transfers
-                msg!("Handling NFT unlock for mint {}", cross_chain_message.mint);
-            }
-        }
-        
+        let skipped = &mut ctx.accounts.skipped_message;
+        skipped.nonce = nonce;
+        skipped.authority = ctx.accounts.authority.key();
+        skipped.reason = reason.clone();
+        skipped.skipped_at = Clock::get()?.unix_timestamp;
+        skipped.bump = ctx.bumps.skipped_message;
+
+        emit!(MessageSkipped {
+            nonce,
+            last_nonce,
+            authority: ctx.accounts.authority.key(),
+            reason,
+        });
+        msg!("Message with nonce {} skipped, last nonce was {}", nonce, last_nonce);
         Ok(())
     }
 
-    /// handle incoming cross-chain call from gateway
-    /// this function is called by the gateway when an nft transfer is initiated from zetachain
-    /// it handles both transfer (minting new nfts) and unlock (returning locked nfts) operations
-    pub fn on_call(
-        ctx: Context<OnCall>,
-        _amount: u64,
-        _sender: [u8; 20],
-        data: Vec<u8>,
+    /// cap how many nfts a wallet can get from local mints, 0 means no cap
+    pub fn set_max_mints_per_wallet(
+        ctx: Context<SetMaxMintsPerWallet>,
+        max_mints_per_wallet: u64,
     ) -> Result<()> {
-        // Verify that the caller is the gateway program
-        let current_ix = anchor_lang::solana_program::sysvar::instructions::get_instruction_relative(
-            0,
-            &ctx.accounts.instruction_sysvar_account.to_account_info(),
-        )
-        .map_err(|_| NftError::Unauthorized)?;
+        ctx.accounts.nft_program.max_mints_per_wallet = max_mints_per_wallet;
+
+        msg!("Max mints per wallet set to {}", max_mints_per_wallet);
+        Ok(())
+    }
+
+    /// cap total_supply, local mints fail at the cap and inbound transfers wait as
+    /// dead letters until it is raised. below the current supply it only stops new
+    /// ones, 0 means no cap
+    pub fn set_max_supply(ctx: Context<SetMaxSupply>, max_supply: u64) -> Result<()> {
+        ctx.accounts.nft_program.max_supply = max_supply;
+
+        msg!("Max supply set to {}", max_supply);
+        Ok(())
+    }
+
+    /// turn public minting on or off, when off only minters can mint
+    pub fn set_public_mint(ctx: Context<SetPublicMint>, public_mint: bool) -> Result<()> {
+        ctx.accounts.nft_program.public_mint = public_mint;
+
+        msg!("Public mint set to {}", public_mint);
+        Ok(())
+    }
+
+    /// point on_call at another gateway program, e.g. after a gateway redeploy
+    pub fn update_gateway(ctx: Context<UpdateGateway>, gateway: Pubkey) -> Result<()> {
+        require!(gateway != Pubkey::default(), NftError::InvalidGateway);
+        let nft_program = &mut ctx.accounts.nft_program;
+        let previous = nft_program.gateway;
+        nft_program.gateway = gateway;
 
+        emit!(GatewayUpdated { previous, gateway });
+        msg!("Gateway updated from {} to {}", previous, gateway);
+        Ok(())
+    }
+
+    /// stop or resume local mints (mint_nft, mint_nft_pda, mint_edition), bridging
+    /// and inbound nfts from on_call arent affected
+    pub fn set_mint_paused(ctx: Context<SetMintPaused>, mint_paused: bool) -> Result<()> {
+        apply_admin_action(
+            &mut ctx.accounts.nft_program,
+            AdminAction::SetMintPaused { mint_paused },
+        );
+        Ok(())
+    }
+
+    /// pick what happens when an inbound nft carries a different metadata hash
+    /// than the one on record: strict rejects it, otherwise it gets flagged
+    pub fn set_strict_metadata_hash(
+        ctx: Context<SetStrictMetadataHash>,
+        strict_metadata_hash: bool,
+    ) -> Result<()> {
+        ctx.accounts.nft_program.strict_metadata_hash = strict_metadata_hash;
+
+        msg!("Strict metadata hash set to {}", strict_metadata_hash);
+        Ok(())
+    }
+
+    /// replace the list of uri prefixes mints are allowed to use
+    pub fn set_allowed_uri_prefixes(
+        ctx: Context<SetAllowedUriPrefixes>,
+        prefixes: Vec<String>,
+    ) -> Result<()> {
         require!(
-            current_ix.program_id == crate::gateway::ID,
-            NftError::Unauthorized
+            !prefixes.is_empty() && prefixes.len() <= MAX_URI_PREFIXES,
+            NftError::InvalidUriPrefixes
+        );
+        require!(
+            prefixes
+                .iter()
+                .all(|prefix| !prefix.is_empty() && prefix.len() <= MAX_URI_PREFIX_LEN),
+            NftError::InvalidUriPrefixes
         );
 
+        msg!("Allowed uri prefixes set to {:?}", prefixes);
+        ctx.accounts.nft_program.allowed_uri_prefixes = prefixes;
+        Ok(())
+    }
+
+    /// rewrite inbound uris for the metadata wallets read, e.g. `ipfs://{cid}` to
+    /// `https://gateway.example/ipfs/{cid}`. nft info keeps the original, which is
+    /// also what goes out again. uris that dont match pass through as they are,
+    /// None turns it off
+    pub fn set_uri_rewrite(
+        ctx: Context<SetUriRewrite>,
+        uri_rewrite: Option<UriRewrite>,
+    ) -> Result<()> {
+        if let Some(uri_rewrite) = &uri_rewrite {
+            validate_uri_rewrite(uri_rewrite)?;
+        }
+
+        msg!("Uri rewrite set to {:?}", uri_rewrite);
+        ctx.accounts.nft_program.uri_rewrite = uri_rewrite;
+        Ok(())
+    }
+
+    /// inbound nfts whose uri fails validation get minted with this uri and flagged
+    /// for repair_metadata instead of going to a dead letter. empty turns it off
+    pub fn set_placeholder_uri(
+        ctx: Context<SetPlaceholderUri>,
+        placeholder_uri: String,
+    ) -> Result<()> {
         let nft_program = &mut ctx.accounts.nft_program;
-        
-        // Parse the incoming message data
-        let cross_chain_message: CrossChainMessage =
-            CrossChainMessage::try_from_slice(&data)
-                .map_err(|_| NftError::InvalidMessage)?;
-        
-        // Update nonce for replay protection
-        require!(cross_chain_message.nonce > nft_program.nonce, NftError::InvalidNonce);
-        nft_program.nonce = cross_chain_message.nonce;
-        
-        match cross_chain_message.message_type {
-            MessageType::Transfer => {
-                // Handle incoming NFT transfer from ZetaChain
-                // Check the recipient is valid pubkey
-                let recipient_pubkey = Pubkey::try_from(cross_chain_message.recipient)
-                    .map_err(|_| NftError::InvalidRecipient)?;
-                
-                msg!("Handling cross-chain NFT transfer from ZetaChain to {}", recipient_pubkey);
-                
-                // Initialize the mint if it hasn't been initialized yet
-                if ctx.accounts.mint.supply == 0 {
-                    anchor_spl::token::initialize_mint(
-                        CpiContext::new_with_signer(
-                            ctx.accounts.token_program.to_account_info(),
-                            anchor_spl::token::InitializeMint {
-                                mint: ctx.accounts.mint.to_account_info(),
-                                rent: ctx.accounts.rent.to_account_info(),
-                            },
-                            &[&[
-                                b"nft-mint",
-                                cross_chain_message.mint.as_ref(),
-                                &[ctx.bumps.mint]
-                            ]]
-                        ),
-                        0, // NFTs have 0 decimals
-                        &ctx.accounts.nft_program.key(),
-                        Some(&ctx.accounts.nft_program.key()),
-                    )?;
-                }
-                
-                // Create associated token account for recipient if it doesn't exist
-                if ctx.accounts.recipient_token_account.data_is_empty() {
-                    anchor_spl::associated_token::create(
-                        CpiContext::new(
-                            ctx.accounts.associated_token_program.to_account_info(),
-                            anchor_spl::associated_token::Create {
-                                payer: ctx.accounts.payer.to_account_info(),
-                                associated_token: ctx.accounts.recipient_token_account.to_account_info(),
-                                authority: recipient_pubkey,
-                                mint: ctx.accounts.mint.to_account_info(),
-                                system_program: ctx.accounts.system_program.to_account_info(),
-                                token_program: ctx.accounts.token_program.to_account_info(),
-                                rent: ctx.accounts.rent.to_account_info(),
-                            }
-                        )
-                    )?;
-                }
-                
-                // Mint the token to the recipient's token account
-                anchor_spl::token::mint_to(
-                    CpiContext::new_with_signer(
-                        ctx.accounts.token_program.to_account_info(),
-                        anchor_spl::token::MintTo {
-                            mint: ctx.accounts.mint.to_account_info(),
-                            to: ctx.accounts.recipient_token_account.to_account_info(),
-                            authority: ctx.accounts.nft_program.to_account_info(),
-                        },
-                        &[&[
-                            b"nft-program",
-                            &[nft_program.bump]
-                        ]]
-                    ),
-                    1 // NFTs have supply of 1
-                )?;
-                
-                // Create metadata for the NFT if it doesn't exist
-                if ctx.accounts.metadata.data_is_empty() {
-                    let data_v2 = DataV2 {
-                        name: cross_chain_message.name.clone(),
-                        symbol: cross_chain_message.symbol.clone(),
-                        uri: cross_chain_message.metadata_uri.clone(),
-                        seller_fee_basis_points: 0,
-                        creators: None,
-                        collection: None,
-                        uses: None,
-                    };
-                    
-                    anchor_spl::metadata::create_metadata_accounts_v3(
-                        CpiContext::new_with_signer(
-                            ctx.accounts.token_metadata_program.to_account_info(),
-                            anchor_spl::metadata::CreateMetadataAccountsV3 {
-                                metadata: ctx.accounts.metadata.to_account_info(),
-                                mint: ctx.accounts.mint.to_account_info(),
-                                mint_authority: ctx.accounts.nft_program.to_account_info(),
-                                update_authority: ctx.accounts.nft_program.to_account_info(),
-                                payer: ctx.accounts.payer.to_account_info(),
-                                system_program: ctx.accounts.system_program.to_account_info(),
-                                rent: ctx.accounts.rent.to_account_info(),
-                            },
-                            &[&[
-                                b"nft-program",
-                                &[nft_program.bump]
-                            ]]
-                        ),
-                        data_v2,
-                        false, // not mutable
-                        true,  // update authority is signer
-                        None,  // no collection details
-                    )?;
-                }
-                
-                // Initialize or update NFT info account to track the NFT
-                let nft_info = &mut ctx.accounts.nft_info;
-                nft_info.mint = ctx.accounts.mint.key();
-                nft_info.owner = recipient_pubkey;
-                nft_info.metadata_uri = cross_chain_message.metadata_uri.clone();
-                nft_info.name = cross_chain_message.name.clone();
-                nft_info.symbol = cross_chain_message.symbol.clone();
-                nft_info.is_locked = false;
-                nft_info.cross_chain_recipient = [0; 32]; // Not applicable for incoming transfers
-                nft_info.bump = ctx.bumps.nft_info;
-                
-                // Update program state
-                nft_program.total_supply = nft_program.total_supply
-                    .checked_add(1)
-                    .ok_or(NftError::Overflow)?;
-                
-                msg!("NFT minted from cross-chain transfer: {} - {} to {}",
-                    cross_chain_message.name, cross_chain_message.metadata_uri, recipient_pubkey);
+        let placeholder_uri = match placeholder_uri.is_empty() {
+            true => placeholder_uri,
+            false => validate_metadata_uri(&placeholder_uri, nft_program)?,
+        };
+        require!(placeholder_uri.len() <= MAX_METAPLEX_URI_LEN, NftError::UriTooLong);
+
+        msg!("Placeholder uri set to {}", placeholder_uri);
+        nft_program.placeholder_uri = placeholder_uri;
+        Ok(())
+    }
+
+    /// royalty for local mints without a royalty arg and inbound nfts whose message
+    /// doesnt carry one, in basis points. the recipient is only used where no
+    /// creators or message recipient are given, the default pubkey means none
+    pub fn set_default_royalty(
+        ctx: Context<SetDefaultRoyalty>,
+        royalty_bps: u16,
+        royalty_recipient: Pubkey,
+    ) -> Result<()> {
+        require!(royalty_bps <= MAX_ROYALTY_BPS, NftError::InvalidRoyaltyBps);
+        let nft_program = &mut ctx.accounts.nft_program;
+        nft_program.default_royalty_bps = royalty_bps;
+        nft_program.default_royalty_recipient = royalty_recipient;
+
+        emit!(DefaultRoyaltySet {
+            royalty_bps,
+            royalty_recipient,
+        });
+        msg!("Default royalty set to {} bps for {}", royalty_bps, royalty_recipient);
+        Ok(())
+    }
+
+    /// inbound royalties over 100% get clamped to 10_000 bps when on, rejected when off
+    pub fn set_clamp_royalty_bps(
+        ctx: Context<SetClampRoyaltyBps>,
+        clamp_royalty_bps: bool,
+    ) -> Result<()> {
+        ctx.accounts.nft_program.clamp_royalty_bps = clamp_royalty_bps;
+
+        msg!("Clamp royalty bps set to {}", clamp_royalty_bps);
+        Ok(())
+    }
+
+    /// set the symbol inbound nfts get when the origin chain sent an empty one
+    pub fn set_default_symbol(ctx: Context<SetDefaultSymbol>, symbol: String) -> Result<()> {
+        require!(
+            !symbol.is_empty() && symbol.len() <= MAX_SYMBOL_LEN,
+            NftError::InvalidDefaultSymbol
+        );
+
+        msg!("Default symbol set to {}", symbol);
+        ctx.accounts.nft_program.default_symbol = symbol;
+        Ok(())
+    }
+
+    /// set the token standard used when a mint doesnt ask for one
+    pub fn set_default_token_standard(
+        ctx: Context<SetDefaultTokenStandard>,
+        token_standard: NftStandard,
+    ) -> Result<()> {
+        ctx.accounts.nft_program.default_token_standard = token_standard;
+
+        msg!("Default token standard set to {:?}", token_standard);
+        Ok(())
+    }
+
+    /// pick what happens to inbound names and symbols over our limits: cut them
+    /// down, or reject the message so it can be retried after a policy change
+    pub fn set_inbound_string_policy(
+        ctx: Context<SetInboundStringPolicy>,
+        policy: InboundStringPolicy,
+    ) -> Result<()> {
+        ctx.accounts.nft_program.inbound_string_policy = policy;
+
+        msg!("Inbound string policy set to {:?}", policy);
+        Ok(())
+    }
+
+    /// pick where new nfts keep their name, symbol and uri: a metaplex metadata
+    /// account, or the token-2022 metadata extension on the mint itself
+    pub fn set_metadata_mode(
+        ctx: Context<SetMetadataMode>,
+        metadata_mode: MetadataMode,
+    ) -> Result<()> {
+        ctx.accounts.nft_program.metadata_mode = metadata_mode;
+
+        msg!("Metadata mode set to {:?}", metadata_mode);
+        Ok(())
+    }
+
+    /// lazy mode leaves the metaplex metadata out of inbound mints, the token and
+    /// nft info are there right away and materialize_metadata adds the metadata
+    /// whenever someone wants the nft to show up on marketplaces
+    pub fn set_lazy_metadata(ctx: Context<SetLazyMetadata>, lazy_metadata: bool) -> Result<()> {
+        ctx.accounts.nft_program.lazy_metadata = lazy_metadata;
+
+        msg!("Lazy metadata set to {}", lazy_metadata);
+        Ok(())
+    }
+
+    /// create the metaplex metadata and master edition an inbound nft skipped in lazy
+    /// mode, from the name, symbol, uri and royalty kept in its nft info. it joins the
+    /// collection like on_call would have. anyone can call it, the payer covers the rent
+    pub fn materialize_metadata(ctx: Context<MaterializeMetadata>, mint: Pubkey) -> Result<()> {
+        upgrade_nft_info(
+            &mut ctx.accounts.nft_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        let nft_info = &ctx.accounts.nft_info;
+        require!(!nft_info.metadata_created, NftError::MetadataAlreadyCreated);
+        let is_mutable =
+            ctx.accounts.nft_program.inbound_metadata_mutable || nft_info.needs_metadata_repair;
+
+        let (verify_now, joins_bridged) = create_inbound_metadata(
+            InboundMetadata {
+                nft_program: &ctx.accounts.nft_program,
+                mint: ctx.accounts.mint.to_account_info(),
+                metadata: ctx.accounts.metadata.to_account_info(),
+                master_edition: ctx.accounts.master_edition.to_account_info(),
+                collection: &mut ctx.accounts.collection,
+                collection_mint: &ctx.accounts.collection_mint,
+                collection_metadata: &ctx.accounts.collection_metadata,
+                collection_master_edition: &ctx.accounts.collection_master_edition,
+                bridged_collection: &mut ctx.accounts.bridged_collection,
+                bridged_collection_mint: &ctx.accounts.bridged_collection_mint,
+                payer: ctx.accounts.payer.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+            },
+            nft_info.name.clone(),
+            nft_info.symbol.clone(),
+            nft_info.metadata_uri.clone(),
+            nft_info.royalty_bps,
+            nft_info.royalty_recipient,
+            is_mutable,
+        )?;
+
+        let accounts = &mut *ctx.accounts;
+        let nft_info = &mut accounts.nft_info;
+        nft_info.metadata_created = true;
+        if verify_now {
+            nft_info.in_collection = true;
+            nft_info.bridged_collection = joins_bridged;
+        }
+        if !is_mutable {
+            record_immutable_metadata(&mut accounts.nft_program, nft_info)?;
+        }
+
+        emit!(MetadataMaterialized {
+            mint,
+            payer: ctx.accounts.payer.key(),
+        });
+        msg!("Metadata created for {}", mint);
+        Ok(())
+    }
+
+    /// put the real uri on an nft that arrived with the placeholder. the owner can do
+    /// it with the metadata hash the message carried, the authority also for nfts
+    /// that came without one (its hash gets recorded then). the metaplex metadata,
+    /// which stayed mutable for this, ends up like any other inbound metadata, so
+    /// immutable if set_inbound_metadata_mutable turned that off
+    pub fn repair_metadata(
+        ctx: Context<RepairMetadata>,
+        mint: Pubkey,
+        uri: String,
+        metadata_hash: [u8; 32],
+    ) -> Result<()> {
+        upgrade_nft_info(
+            &mut ctx.accounts.nft_info,
+            &ctx.accounts.signer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        let nft_program = &ctx.accounts.nft_program;
+        let nft_info = &ctx.accounts.nft_info;
+        let signer = ctx.accounts.signer.key();
+        let by_authority = is_authority(nft_program, signer);
+        require!(by_authority || signer == nft_info.owner, NftError::Unauthorized);
+        require!(nft_info.needs_metadata_repair, NftError::MetadataNotPendingRepair);
+        match nft_info.metadata_hash {
+            Some(recorded) => require!(recorded == metadata_hash, NftError::MetadataHashMismatch),
+            None => require!(by_authority, NftError::Unauthorized),
+        }
+        let uri = validate_metadata_uri(&uri, nft_program)?;
+        let display = display_uri(nft_program, &uri);
+        require!(display.len() <= MAX_METAPLEX_URI_LEN, NftError::UriTooLong);
+
+        let nft_program_seeds: &[&[u8]] = &[seeds::NFT_PROGRAM, &[nft_program.bump]];
+        let metaplex = ctx.accounts.metadata.as_ref().filter(|metadata| !metadata.data_is_empty());
+        let mut frozen = false;
+        match metaplex {
+            Some(metadata) => {
+                let current = MetaplexMetadata::safe_deserialize(&metadata.try_borrow_data()?)
+                    .map_err(|_| NftError::MissingMetaplexAccount)?;
+                let token_metadata_program =
+                    metaplex_account(&ctx.accounts.token_metadata_program)?;
+                let sysvar_instructions = metaplex_account(&ctx.accounts.sysvar_instructions)?;
+                UpdateV1CpiBuilder::new(&token_metadata_program)
+                    .authority(&nft_program.to_account_info())
+                    .mint(&ctx.accounts.mint.to_account_info())
+                    .metadata(&metadata.to_account_info())
+                    .payer(&ctx.accounts.signer.to_account_info())
+                    .system_program(&ctx.accounts.system_program.to_account_info())
+                    .sysvar_instructions(&sysvar_instructions)
+                    // metaplex pads the strings it stores with nuls
+                    .data(MetaplexData {
+                        name: current.name.trim_end_matches('\0').to_string(),
+                        symbol: current.symbol.trim_end_matches('\0').to_string(),
+                        uri: display,
+                        seller_fee_basis_points: current.seller_fee_basis_points,
+                        creators: current.creators,
+                    })
+                    .is_mutable(nft_program.inbound_metadata_mutable)
+                    .invoke_signed(&[nft_program_seeds])?;
+                frozen = !nft_program.inbound_metadata_mutable;
             }
-            MessageType::Unlock => {
-                // Handle unlock for return transfers
-                // This would be when an NFT is being sent back from ZetaChain to Solana
-                msg!("Handling NFT unlock for mint {}", cross_chain_message.mint);
-                
-                // For Unlock, we need to transfer an existing NFT back to the owner
-                // The NFT info account should already exist
-                let nft_info = &mut ctx.accounts.nft_info;
-                
-                // Verify the NFT exists and is locked
-                require!(nft_info.is_locked, NftError::TokenNotLocked);
-                
-                // Create program token account if it doesn't exist
-                if ctx.accounts.program_token_account.data_is_empty() {
-                    anchor_spl::associated_token::create(
-                        CpiContext::new(
-                            ctx.accounts.associated_token_program.to_account_info(),
-                            anchor_spl::associated_token::Create {
-                                payer: ctx.accounts.payer.to_account_info(),
-                                associated_token: ctx.accounts.program_token_account.to_account_info(),
-                                authority: ctx.accounts.nft_program.key(),
-                                mint: ctx.accounts.mint.to_account_info(),
-                                system_program: ctx.accounts.system_program.to_account_info(),
-                                token_program: ctx.accounts.token_program.to_account_info(),
-                                rent: ctx.accounts.rent.to_account_info(),
-                            }
-                        )
-                    )?;
-                }
-                
-                // Create owner token account if it doesn't exist
-                if ctx.accounts.owner_token_account.data_is_empty() {
-                    anchor_spl::associated_token::create(
-                        CpiContext::new(
-                            ctx.accounts.associated_token_program.to_account_info(),
-                            anchor_spl::associated_token::Create {
-                                payer: ctx.accounts.payer.to_account_info(),
-                                associated_token: ctx.accounts.owner_token_account.to_account_info(),
-                                authority: nft_info.owner,
-                                mint: ctx.accounts.mint.to_account_info(),
-                                system_program: ctx.accounts.system_program.to_account_info(),
-                                token_program: ctx.accounts.token_program.to_account_info(),
-                                rent: ctx.accounts.rent.to_account_info(),
-                            }
-                        )
-                    )?;
-                }
-                
-                // Transfer the NFT back to the owner
-                anchor_spl::token::transfer(
+            // token-2022 extension mode keeps it on the mint
+            None if nft_info.metadata_created => {
+                let mint = ctx.accounts.mint.to_account_info();
+                // token-2022 grows the mint for a longer uri, the rent has to be there
+                top_up_rent(
+                    &mint,
+                    &ctx.accounts.signer.to_account_info(),
+                    &ctx.accounts.system_program.to_account_info(),
+                    mint.data_len() + display.len(),
+                )?;
+                token_metadata_update_field(
                     CpiContext::new_with_signer(
                         ctx.accounts.token_program.to_account_info(),
-                        anchor_spl::token::Transfer {
-                            from: ctx.accounts.program_token_account.to_account_info(),
-                            to: ctx.accounts.owner_token_account.to_account_info(),
-                            authority: ctx.accounts.nft_program.to_account_info(),
+                        TokenMetadataUpdateField {
+                            token_program_id: ctx.accounts.token_program.to_account_info(),
+                            metadata: mint,
+                            update_authority: nft_program.to_account_info(),
                         },
-                        &[&[
-                            b"nft-program",
-                            &[nft_program.bump]
-                        ]]
+                        &[nft_program_seeds],
                     ),
-                    1,
+                    ExtensionField::Uri,
+                    display,
                 )?;
-                
-                // Update NFT state to unlocked
-                nft_info.is_locked = false;
-                nft_info.cross_chain_recipient = [0; 32]; // Clear the cross-chain recipient
-                
-                msg!("NFT unlocked and transferred back to owner for mint {}", nft_info.mint);
             }
+            // lazy metadata, materialize_metadata picks the repaired uri up
+            None => {}
         }
-        
+
+        let accounts = &mut *ctx.accounts;
+        let nft_info = &mut accounts.nft_info;
+        nft_info.metadata_uri = uri.clone();
+        nft_info.metadata_hash = Some(metadata_hash);
+        nft_info.needs_metadata_repair = false;
+        if frozen {
+            record_immutable_metadata(&mut accounts.nft_program, nft_info)?;
+        }
+
+        emit!(MetadataRepaired {
+            mint,
+            uri,
+            metadata_hash,
+            repaired_by: signer,
+        });
+        msg!("Metadata of {} repaired", mint);
         Ok(())
     }
 
-    /// unlock nft after it comes back from crosschain, send to owner
-    pub fn unlock_nft(ctx: Context<UnlockNft>, nonce: u64) -> Result<()> {
-        let nft_info = &mut ctx.accounts.nft_info;
+    /// make the metaplex metadata of an nft immutable for good, the owner or the
+    /// authority decides when. inbound metadata stays mutable so the program can
+    /// still fix and extend it, this is the way out once nothing needs to change.
+    /// one waiting for repair_metadata has to be repaired first
+    pub fn freeze_metadata(ctx: Context<FreezeMetadata>, mint: Pubkey) -> Result<()> {
+        upgrade_nft_info(
+            &mut ctx.accounts.nft_info,
+            &ctx.accounts.signer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        let nft_program = &ctx.accounts.nft_program;
+        let nft_info = &ctx.accounts.nft_info;
+        let signer = ctx.accounts.signer.key();
+        require!(
+            is_authority(nft_program, signer) || signer == nft_info.owner,
+            NftError::Unauthorized
+        );
+        require!(!nft_info.needs_metadata_repair, NftError::MetadataPendingRepair);
+        let metadata = ctx.accounts.metadata.to_account_info();
+        require!(!metadata.data_is_empty(), NftError::MissingMetaplexAccount);
+        let is_mutable = MetaplexMetadata::safe_deserialize(&metadata.try_borrow_data()?)
+            .map_err(|_| NftError::MissingMetaplexAccount)?
+            .is_mutable;
+        require!(is_mutable, NftError::MetadataImmutable);
+
+        UpdateV1CpiBuilder::new(&ctx.accounts.token_metadata_program.to_account_info())
+            .authority(&nft_program.to_account_info())
+            .mint(&ctx.accounts.mint.to_account_info())
+            .metadata(&metadata)
+            .payer(&ctx.accounts.signer.to_account_info())
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .sysvar_instructions(&ctx.accounts.sysvar_instructions.to_account_info())
+            .is_mutable(false)
+            .invoke_signed(&[&[seeds::NFT_PROGRAM, &[nft_program.bump]]])?;
+
+        let accounts = &mut *ctx.accounts;
+        record_immutable_metadata(&mut accounts.nft_program, &mut accounts.nft_info)?;
+
+        emit!(MetadataFrozen {
+            mint,
+            frozen_by: signer,
+        });
+        msg!("Metadata of {} frozen", mint);
+        Ok(())
+    }
+
+    /// count a bridged nft whose metaplex metadata is immutable but isnt counted
+    /// yet, those from before inbound metadata became mutable. they cant be fixed
+    /// anymore, get_stats reports how many there are. anyone can call it, an nft
+    /// only ever counts once
+    pub fn count_immutable_metadata(
+        ctx: Context<CountImmutableMetadata>,
+        mint: Pubkey,
+    ) -> Result<()> {
+        upgrade_nft_info(
+            &mut ctx.accounts.nft_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        require!(ctx.accounts.nft_info.bridged, NftError::NotBridged);
+        let metadata = &ctx.accounts.metadata;
+        require!(!metadata.data_is_empty(), NftError::MissingMetaplexAccount);
+        let is_mutable = MetaplexMetadata::safe_deserialize(&metadata.try_borrow_data()?)
+            .map_err(|_| NftError::MissingMetaplexAccount)?
+            .is_mutable;
+        require!(!is_mutable, NftError::MetadataMutable);
+
+        let accounts = &mut *ctx.accounts;
+        record_immutable_metadata(&mut accounts.nft_program, &mut accounts.nft_info)?;
+
+        msg!(
+            "Immutable metadata of {} counted, {} bridged nfts have it",
+            mint,
+            accounts.nft_program.immutable_bridged_count
+        );
+        Ok(())
+    }
+
+    /// pick who gets the rent back when sweep_orphaned_info closes an nft info:
+    /// the treasury, or the caller as a reward for cleaning up
+    pub fn set_sweep_rent_destination(
+        ctx: Context<SetSweepRentDestination>,
+        destination: SweepRentDestination,
+    ) -> Result<()> {
+        ctx.accounts.nft_program.sweep_rent_destination = destination;
+
+        msg!("Sweep rent destination set to {:?}", destination);
+        Ok(())
+    }
+
+    /// pick who can call retry_dead_letter and retry_inbound: only the authority
+    /// (default), or anyone
+    pub fn set_dead_letter_retry(
+        ctx: Context<SetDeadLetterRetry>,
+        retry: DeadLetterRetry,
+    ) -> Result<()> {
+        ctx.accounts.nft_program.dead_letter_retry = retry;
+
+        msg!("Dead letter retry set to {:?}", retry);
+        Ok(())
+    }
+
+    /// let mints and inbound transfers go to the program id or the program state
+    /// pda. off by default, an nft sent there is stuck for good
+    pub fn set_allow_program_recipients(
+        ctx: Context<SetAllowProgramRecipients>,
+        allow: bool,
+    ) -> Result<()> {
+        ctx.accounts.nft_program.allow_program_recipients = allow;
+
+        msg!("Program recipients allowed: {}", allow);
+        Ok(())
+    }
+
+    /// zetachain tss address (20 bytes, evm style) that signs inbound messages, see
+    /// set_require_tss_signature
+    pub fn set_tss_address(ctx: Context<SetTssAddress>, tss_address: [u8; 20]) -> Result<()> {
         let nft_program = &mut ctx.accounts.nft_program;
+        // clearing it while signatures are required would stop every delivery
+        require!(
+            tss_address != [0; 20] || !nft_program.require_tss_signature,
+            NftError::TssAddressNotSet
+        );
+        nft_program.tss_address = tss_address;
 
-        // check if locked and nonce is ok
-        require!(nft_info.is_locked, NftError::TokenNotLocked);
-        require!(nonce > nft_program.nonce, NftError::InvalidNonce);
-        
-        // move nft back to owner
-        transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.program_token_account.to_account_info(),
-                    to: ctx.accounts.owner_token_account.to_account_info(),
-                    authority: ctx.accounts.nft_program.to_account_info(),
-                },
-            ).with_signer(&[&[
-                b"nft-program",
-                &[nft_program.bump]
-            ]]),
-            1,
+        msg!("Tss address set to {:?}", tss_address);
+        Ok(())
+    }
+
+    /// on_call only takes messages the tss signed, on top of the gateway check. the
+    /// signature comes as a secp256k1 program instruction in the same transaction.
+    /// needs the tss address set first
+    pub fn set_require_tss_signature(
+        ctx: Context<SetRequireTssSignature>,
+        required: bool,
+    ) -> Result<()> {
+        let nft_program = &mut ctx.accounts.nft_program;
+        require!(
+            !required || nft_program.tss_address != [0; 20],
+            NftError::TssAddressNotSet
+        );
+        nft_program.require_tss_signature = required;
+
+        msg!("Tss signature required: {}", required);
+        Ok(())
+    }
+
+    /// run an admin action the authority signed offline, whoever submits it only pays
+    /// the fee. the authority's ed25519 signature over `admin_message(action, nonce)`
+    /// comes as an ed25519 program instruction in the same transaction. nonce has to
+    /// be the current admin nonce, so every signed action runs once and in order
+    pub fn execute_signed_admin(
+        ctx: Context<ExecuteSignedAdmin>,
+        action: AdminAction,
+        nonce: u64,
+    ) -> Result<()> {
+        let nft_program = &mut ctx.accounts.nft_program;
+        // a renounced authority signs nothing any more
+        require!(nft_program.authority != Pubkey::default(), NftError::Unauthorized);
+        require!(nonce == nft_program.admin_nonce, NftError::InvalidAdminNonce);
+        check_admin_signature(
+            &ctx.accounts.instruction_sysvar_account,
+            &nft_program.authority,
+            &admin_message(&action, nonce),
+        )?;
+        bump(&mut nft_program.admin_nonce)?;
+        apply_admin_action(nft_program, action.clone());
+
+        emit!(SignedAdminExecuted {
+            action,
+            admin_nonce: nonce,
+            relayer: ctx.accounts.relayer.key(),
+        });
+        Ok(())
+    }
+
+    /// deliver inbound messages in nonce order. one that arrives ahead of the expected
+    /// nonce waits in a pending record until drain_pending runs it, instead of giving
+    /// up the nonces before it. off by default
+    pub fn set_ordered_delivery(ctx: Context<SetOrderedDelivery>, enabled: bool) -> Result<()> {
+        ctx.accounts.nft_program.ordered_delivery = enabled;
+
+        msg!("Ordered delivery: {}", enabled);
+        Ok(())
+    }
+
+    /// claim mode: on_call keeps an inbound transfer as a pending claim instead of
+    /// minting it, the recipient mints it with claim_nft and pays for the accounts.
+    /// off by default, compressed mode mints its leaves either way. authority only
+    pub fn set_claim_mode(ctx: Context<SetClaimMode>, claim_mode: bool) -> Result<()> {
+        ctx.accounts.nft_program.claim_mode = claim_mode;
+
+        msg!("Claim mode: {}", claim_mode);
+        Ok(())
+    }
+
+    /// whether inbound nfts get mutable metaplex metadata. on by default so the
+    /// program can still verify collections, fix royalties and repair uris later,
+    /// off makes new ones immutable right away (counted in get_stats). placeholder
+    /// metadata stays mutable until repair_metadata either way. authority only
+    pub fn set_inbound_metadata_mutable(
+        ctx: Context<SetInboundMetadataMutable>,
+        inbound_metadata_mutable: bool,
+    ) -> Result<()> {
+        ctx.accounts.nft_program.inbound_metadata_mutable = inbound_metadata_mutable;
+
+        msg!("Inbound metadata mutable: {}", inbound_metadata_mutable);
+        Ok(())
+    }
+
+    /// create the sized collection new metaplex nfts get verified into. the
+    /// collection nft is a program pda held by the program, one per deployment
+    pub fn create_collection(
+        ctx: Context<CreateCollection>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        let uri = validate_metadata(&name, &symbol, &uri, &ctx.accounts.nft_program)?;
+        require!(uri.len() <= MAX_METAPLEX_URI_LEN, NftError::UriTooLong);
+
+        create_collection_nft(
+            &CollectionNft {
+                nft_program: ctx.accounts.nft_program.to_account_info(),
+                collection_mint: ctx.accounts.collection_mint.to_account_info(),
+                collection_metadata: ctx.accounts.collection_metadata.to_account_info(),
+                collection_master_edition: ctx.accounts.collection_master_edition.to_account_info(),
+                collection_token_account: ctx.accounts.collection_token_account.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+                token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+            },
+            name.clone(),
+            symbol,
+            uri,
+            ctx.accounts.nft_program.bump,
+            &[seeds::COLLECTION_MINT, &[ctx.bumps.collection_mint]],
         )?;
 
-        // update state to unlocked and set new nonce
-        nft_info.is_locked = false;
-        nft_program.nonce = nonce;
+        let collection_mint = ctx.accounts.collection_mint.key();
+        let collection = &mut ctx.accounts.collection;
+        collection.mint = collection_mint;
+        collection.collection_size = 0;
+        collection.bump = ctx.bumps.collection;
+        ctx.accounts.nft_program.collection_mint = collection_mint;
+
+        msg!("Collection {} created: {}", collection_mint, name);
+        Ok(())
+    }
+
+    /// authority only, once. bridged in nfts join this collection instead of the
+    /// program one, so marketplaces can tell them apart from local mints
+    pub fn create_bridged_collection(
+        ctx: Context<CreateBridgedCollection>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        let uri = validate_metadata(&name, &symbol, &uri, &ctx.accounts.nft_program)?;
+        require!(uri.len() <= MAX_METAPLEX_URI_LEN, NftError::UriTooLong);
+
+        create_collection_nft(
+            &CollectionNft {
+                nft_program: ctx.accounts.nft_program.to_account_info(),
+                collection_mint: ctx.accounts.bridged_collection_mint.to_account_info(),
+                collection_metadata: ctx.accounts.collection_metadata.to_account_info(),
+                collection_master_edition: ctx.accounts.collection_master_edition.to_account_info(),
+                collection_token_account: ctx.accounts.collection_token_account.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+                token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+            },
+            name.clone(),
+            symbol,
+            uri,
+            ctx.accounts.nft_program.bump,
+            &[seeds::BRIDGED_COLLECTION_MINT, &[ctx.bumps.bridged_collection_mint]],
+        )?;
+
+        let collection_mint = ctx.accounts.bridged_collection_mint.key();
+        let collection = &mut ctx.accounts.bridged_collection;
+        collection.mint = collection_mint;
+        collection.collection_size = 0;
+        collection.bump = ctx.bumps.bridged_collection;
+        ctx.accounts.nft_program.bridged_collection_mint = collection_mint;
+
+        msg!("Bridged collection {} created: {}", collection_mint, name);
+        Ok(())
+    }
+
+    /// let an external key verify nfts into the program collection, one delegate
+    /// at a time, revoke the current one before approving another
+    pub fn approve_collection_delegate(
+        ctx: Context<ApproveCollectionDelegate>,
+        delegate: Pubkey,
+    ) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.nft_program.collection_delegate,
+            Pubkey::default(),
+            NftError::CollectionDelegateSet
+        );
+
+        let nft_program = ctx.accounts.nft_program.to_account_info();
+        let payer = ctx.accounts.payer.to_account_info();
+        let collection_master_edition = ctx.accounts.collection_master_edition.to_account_info();
+        DelegateCollectionV1CpiBuilder::new(&ctx.accounts.token_metadata_program.to_account_info())
+            .delegate_record(Some(&ctx.accounts.delegate_record.to_account_info()))
+            .delegate(&ctx.accounts.delegate.to_account_info())
+            .metadata(&ctx.accounts.collection_metadata.to_account_info())
+            .master_edition(Some(&collection_master_edition))
+            .mint(&ctx.accounts.collection_mint.to_account_info())
+            .authority(&nft_program)
+            .payer(&payer)
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .sysvar_instructions(&ctx.accounts.sysvar_instructions.to_account_info())
+            .invoke_signed(&[&[seeds::NFT_PROGRAM, &[ctx.accounts.nft_program.bump]]])?;
+
+        ctx.accounts.nft_program.collection_delegate = delegate;
+
+        msg!("Collection delegate approved: {}", delegate);
+        Ok(())
+    }
+
+    /// drop the collection delegate, only the program can verify after this
+    pub fn revoke_collection_delegate(ctx: Context<RevokeCollectionDelegate>) -> Result<()> {
+        let nft_program = ctx.accounts.nft_program.to_account_info();
+        let payer = ctx.accounts.payer.to_account_info();
+        let collection_master_edition = ctx.accounts.collection_master_edition.to_account_info();
+        RevokeCollectionV1CpiBuilder::new(&ctx.accounts.token_metadata_program.to_account_info())
+            .delegate_record(Some(&ctx.accounts.delegate_record.to_account_info()))
+            .delegate(&ctx.accounts.delegate.to_account_info())
+            .metadata(&ctx.accounts.collection_metadata.to_account_info())
+            .master_edition(Some(&collection_master_edition))
+            .mint(&ctx.accounts.collection_mint.to_account_info())
+            .authority(&nft_program)
+            .payer(&payer)
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .sysvar_instructions(&ctx.accounts.sysvar_instructions.to_account_info())
+            .invoke_signed(&[&[seeds::NFT_PROGRAM, &[ctx.accounts.nft_program.bump]]])?;
+
+        msg!("Collection delegate revoked: {}", ctx.accounts.nft_program.collection_delegate);
+        ctx.accounts.nft_program.collection_delegate = Pubkey::default();
+        Ok(())
+    }
+
+    /// verify an nft that was minted with verification deferred into the program
+    /// collection. the authority goes through the program pda, the delegate signs itself
+    pub fn verify_collection_item(ctx: Context<VerifyCollectionItem>, mint: Pubkey) -> Result<()> {
+        require!(!ctx.accounts.nft_info.in_collection, NftError::AlreadyInCollection);
+
+        let nft_program = &ctx.accounts.nft_program;
+        let verifier = ctx.accounts.verifier.key();
+        let by_delegate = !is_authority(nft_program, verifier);
+        require!(
+            !by_delegate
+                || (nft_program.collection_delegate != Pubkey::default()
+                    && verifier == nft_program.collection_delegate),
+            NftError::Unauthorized
+        );
+
+        let (authority, delegate_record) = if by_delegate {
+            (
+                ctx.accounts.verifier.to_account_info(),
+                Some(collection_account(&ctx.accounts.delegate_record)?),
+            )
+        } else {
+            (nft_program.to_account_info(), None)
+        };
+        let nft_program_bump = [nft_program.bump];
+        let program_seeds: &[&[u8]] = &[seeds::NFT_PROGRAM, &nft_program_bump];
+        let signer_seeds: &[&[&[u8]]] = if by_delegate { &[] } else { &[program_seeds] };
+
+        add_to_collection(
+            &CollectionItem {
+                metadata: ctx.accounts.metadata.to_account_info(),
+                collection_mint: ctx.accounts.collection_mint.to_account_info(),
+                collection_metadata: ctx.accounts.collection_metadata.to_account_info(),
+                collection_master_edition: ctx.accounts.collection_master_edition.to_account_info(),
+                authority,
+                delegate_record,
+                system_program: ctx.accounts.system_program.to_account_info(),
+                sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+                token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+            },
+            &mut ctx.accounts.collection,
+            signer_seeds,
+        )?;
+        upgrade_nft_info(
+            &mut ctx.accounts.nft_info,
+            &ctx.accounts.verifier.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        ctx.accounts.nft_info.in_collection = true;
+
+        msg!("NFT {} verified into the collection by {}", mint, verifier);
+        Ok(())
+    }
+
+    /// leave the collection on new metaplex nfts unverified so verification can
+    /// happen later with verify_collection_item, keeps mint_nft cheaper
+    pub fn set_defer_collection_verify(
+        ctx: Context<SetDeferCollectionVerify>,
+        defer_collection_verify: bool,
+    ) -> Result<()> {
+        ctx.accounts.nft_program.defer_collection_verify = defer_collection_verify;
+
+        msg!("Defer collection verify set to {}", defer_collection_verify);
+        Ok(())
+    }
+
+    /// replace the on-chain attributes of an nft, the holder can do this as long
+    /// as its metadata is mutable. local metaplex mints are made immutable, so in
+    /// practice thats extension mode nfts and inbound ones until freeze_metadata
+    pub fn set_attributes(
+        ctx: Context<SetAttributes>,
+        mint: Pubkey,
+        attributes: Vec<Attribute>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.nft_info.is_locked, NftError::TokenLocked);
+        require!(ctx.accounts.owner_token_account.amount == 1, NftError::NotHolder);
+        validate_attributes(&attributes)?;
+
+        let metadata = &ctx.accounts.metadata;
+        if !metadata.data_is_empty() {
+            let is_mutable = MetaplexMetadata::from_bytes(&metadata.try_borrow_data()?)
+                .map_err(|_| NftError::InvalidMetadata)?
+                .is_mutable;
+            require!(is_mutable, NftError::MetadataImmutable);
+        }
+
+        upgrade_nft_info(
+            &mut ctx.accounts.nft_info,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        ctx.accounts.nft_info.attributes = attributes;
+
+        msg!("Attributes updated for mint {}", mint);
+        Ok(())
+    }
+
+    /// create a bubblegum tree config for a merkle tree the authority already
+    /// allocated, the program pda becomes the tree creator and it gets used for
+    /// compressed inbound mints
+    pub fn create_tree(
+        ctx: Context<CreateTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        compressed::create_tree(ctx, max_depth, max_buffer_size)
+    }
+
+    /// point compressed mints at an existing tree, its tree config has to list
+    /// the program pda as creator or delegate or the mints will fail
+    pub fn set_tree(ctx: Context<SetTree>, merkle_tree: Pubkey) -> Result<()> {
+        ctx.accounts.nft_program.merkle_tree = merkle_tree;
+
+        msg!("Merkle tree set to {}", merkle_tree);
+        Ok(())
+    }
+
+    /// switch inbound transfers between full mints and compressed leaves
+    pub fn set_compressed_mode(ctx: Context<SetCompressedMode>, compressed_mode: bool) -> Result<()> {
+        // without the compressed feature there is nothing that could mint the leaves
+        require!(
+            !compressed_mode || cfg!(feature = "compressed"),
+            NftError::CompressedNotEnabled
+        );
+        let nft_program = &mut ctx.accounts.nft_program;
+        require!(
+            !compressed_mode || nft_program.merkle_tree != Pubkey::default(),
+            NftError::TreeNotSet
+        );
+        nft_program.compressed_mode = compressed_mode;
+
+        msg!("Compressed mode set to {}", compressed_mode);
+        Ok(())
+    }
+
+    /// set the max uri length, new nft info accounts get sized for it
+    pub fn set_max_uri_len(ctx: Context<SetMaxUriLen>, max_uri_len: u16) -> Result<()> {
+        require!(
+            max_uri_len > 0 && max_uri_len <= MAX_URI_LEN_CAP,
+            NftError::InvalidMaxUriLen
+        );
+        ctx.accounts.nft_program.max_uri_len = max_uri_len;
+
+        msg!("Max uri length set to {}", max_uri_len);
+        Ok(())
+    }
+
+    /// give a wallet the minter role, only the authority can do this
+    pub fn grant_minter(ctx: Context<GrantMinter>, minter: Pubkey) -> Result<()> {
+        let minter_role = &mut ctx.accounts.minter_role;
+        minter_role.minter = minter;
+        minter_role.bump = ctx.bumps.minter_role;
+
+        msg!("Minter role granted to {}", minter);
+        Ok(())
+    }
+
+    /// take the minter role away, closes the pda and refunds the authority
+    pub fn revoke_minter(_ctx: Context<RevokeMinter>, minter: Pubkey) -> Result<()> {
+        msg!("Minter role revoked from {}", minter);
+        Ok(())
+    }
+
+    /// stop nfts from being minted, delivered or unlocked to a wallet, for
+    /// compliance. it only holds delivery back, nothing the wallet already has is
+    /// touched. inbound nfts for it go to dead letters. authority only
+    pub fn block_recipient(ctx: Context<BlockRecipient>, recipient: Pubkey) -> Result<()> {
+        let blocked_recipient = &mut ctx.accounts.blocked_recipient;
+        blocked_recipient.recipient = recipient;
+        blocked_recipient.blocked_at = Clock::get()?.unix_timestamp;
+        blocked_recipient.bump = ctx.bumps.blocked_recipient;
+
+        emit!(RecipientBlockSet { recipient, blocked: true });
+        msg!("Recipient {} blocked", recipient);
+        Ok(())
+    }
+
+    /// lift a block, closes the pda and refunds the authority. dead letters held
+    /// back for the wallet can be retried after
+    pub fn unblock_recipient(_ctx: Context<UnblockRecipient>, recipient: Pubkey) -> Result<()> {
+        emit!(RecipientBlockSet { recipient, blocked: false });
+        msg!("Recipient {} unblocked", recipient);
+        Ok(())
+    }
+
+    /// keep one nft out of the bridge, say a stolen item or a takedown. it cant be
+    /// sent out and nothing for it is taken in, holding and moving it on solana
+    /// stays as it is. the reason (1-64 bytes) is kept and emitted. authority only
+    pub fn block_mint(ctx: Context<BlockMint>, mint: Pubkey, reason: String) -> Result<()> {
+        validate_reason(&reason)?;
+        let blocked_mint = &mut ctx.accounts.blocked_mint;
+        blocked_mint.mint = mint;
+        blocked_mint.reason = reason.clone();
+        blocked_mint.blocked_at = Clock::get()?.unix_timestamp;
+        blocked_mint.bump = ctx.bumps.blocked_mint;
+
+        emit!(MintBlockSet { mint, blocked: true, reason });
+        msg!("Mint {} blocked", mint);
+        Ok(())
+    }
+
+    /// lift a mint block, closes the pda and refunds the authority
+    pub fn unblock_mint(_ctx: Context<UnblockMint>, mint: Pubkey, reason: String) -> Result<()> {
+        validate_reason(&reason)?;
+
+        emit!(MintBlockSet { mint, blocked: false, reason });
+        msg!("Mint {} unblocked", mint);
+        Ok(())
+    }
+
+    /// let registered nfts of a collection onto the bridge, authority only. they
+    /// need it verified in their metadata, nfts we minted or bridged in dont need one
+    pub fn approve_collection(ctx: Context<ApproveCollection>, collection_mint: Pubkey) -> Result<()> {
+        let approved_collection = &mut ctx.accounts.approved_collection;
+        approved_collection.collection_mint = collection_mint;
+        approved_collection.approved_at = Clock::get()?.unix_timestamp;
+        approved_collection.bump = ctx.bumps.approved_collection;
+
+        emit!(CollectionApprovalSet { collection_mint, approved: true });
+        msg!("Collection {} approved", collection_mint);
+        Ok(())
+    }
+
+    /// take a collection off the bridge again, closes the pda and refunds the
+    /// authority. nfts of it that are locked right now can still come back
+    pub fn revoke_collection(_ctx: Context<RevokeCollection>, collection_mint: Pubkey) -> Result<()> {
+        emit!(CollectionApprovalSet { collection_mint, approved: false });
+        msg!("Collection {} revoked", collection_mint);
+        Ok(())
+    }
+
+    /// add a chain nfts can be bridged to and from, authority only. the entry
+    /// holds everything transfer_to_zetachain and on_call check per chain
+    pub fn register_chain(
+        ctx: Context<RegisterChain>,
+        chain_id: u64,
+        config: ChainConfig,
+    ) -> Result<()> {
+        validate_chain_config(&config)?;
+        let chain_info = &mut ctx.accounts.chain_info;
+        chain_info.chain_id = chain_id;
+        chain_info.flat_fee_lamports = 0;
+        chain_info.gas_fee_lamports = 0;
+        chain_info.inbound_enabled = true;
+        chain_info.inbound_paused = false;
+        chain_info.fee_token = None;
+        chain_info.bump = ctx.bumps.chain_info;
+        chain_info.apply(config);
+
+        emit!(ChainRegistered {
+            chain_id,
+            name: chain_info.name.clone(),
+            chain_kind: chain_info.chain_kind,
+        });
+        msg!("Chain {} registered as {}", chain_id, chain_info.name);
+        Ok(())
+    }
+
+    /// change a registered chain, disabling it stops transfers both ways. authority only
+    pub fn update_chain(
+        ctx: Context<UpdateChain>,
+        chain_id: u64,
+        config: ChainConfig,
+    ) -> Result<()> {
+        validate_chain_config(&config)?;
+        let chain_info = &mut ctx.accounts.chain_info;
+        chain_info.apply(config);
+
+        emit!(ChainUpdated {
+            chain_id,
+            name: chain_info.name.clone(),
+            enabled: chain_info.enabled,
+        });
+        msg!("Chain {} updated, enabled: {}", chain_id, chain_info.enabled);
+        Ok(())
+    }
+
+    /// set what a transfer out to the chain costs, a flat fee plus the destination
+    /// gas. charged into the treasury on every transfer, right away. authority only
+    pub fn set_chain_fees(
+        ctx: Context<SetChainFees>,
+        chain_id: u64,
+        flat_fee_lamports: u64,
+        gas_fee_lamports: u64,
+    ) -> Result<()> {
+        flat_fee_lamports
+            .checked_add(gas_fee_lamports)
+            .ok_or(NftError::Overflow)?;
+        let chain_info = &mut ctx.accounts.chain_info;
+        chain_info.flat_fee_lamports = flat_fee_lamports;
+        chain_info.gas_fee_lamports = gas_fee_lamports;
+
+        emit!(ChainFeesSet {
+            chain_id,
+            flat_fee_lamports,
+            gas_fee_lamports,
+        });
+        msg!("Chain {} fees set to {} + {} lamports", chain_id, flat_fee_lamports, gas_fee_lamports);
+        Ok(())
+    }
+
+    /// pay the flat chain fee in lamports or in an spl token (usdc, zeta, ...), the
+    /// gas fee stays in lamports. a token fee goes to the treasury's ata of the mint,
+    /// created here, and only withdraw_fee_token takes it out. authority only
+    pub fn set_chain_fee_currency(
+        ctx: Context<SetChainFeeCurrency>,
+        chain_id: u64,
+        fee_currency: FeeCurrency,
+    ) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let (flat_fee_lamports, fee_token) = match fee_currency {
+            FeeCurrency::Sol(lamports) => (lamports, None),
+            FeeCurrency::Spl { mint, amount } => {
+                let (Some(fee_mint), Some(fee_vault), Some(token_program), Some(ata_program)) = (
+                    &accounts.fee_mint,
+                    &accounts.fee_vault,
+                    &accounts.fee_token_program,
+                    &accounts.associated_token_program,
+                ) else {
+                    return err!(NftError::MissingFeeTokenAccounts);
+                };
+                require_keys_eq!(fee_mint.key(), mint, NftError::FeeTokenMismatch);
+                // the ata program checks the vault address against the treasury and mint
+                anchor_spl::associated_token::create_idempotent(CpiContext::new(
+                    ata_program.to_account_info(),
+                    anchor_spl::associated_token::Create {
+                        payer: accounts.payer.to_account_info(),
+                        associated_token: fee_vault.to_account_info(),
+                        authority: accounts.treasury.to_account_info(),
+                        mint: fee_mint.to_account_info(),
+                        system_program: accounts.system_program.to_account_info(),
+                        token_program: token_program.to_account_info(),
+                    },
+                ))?;
+                (0, Some(FeeToken { mint, amount }))
+            }
+        };
+        let chain_info = &mut ctx.accounts.chain_info;
+        flat_fee_lamports
+            .checked_add(chain_info.gas_fee_lamports)
+            .ok_or(NftError::Overflow)?;
+        chain_info.flat_fee_lamports = flat_fee_lamports;
+        chain_info.fee_token = fee_token;
+
+        emit!(ChainFeeCurrencySet { chain_id, fee_currency });
+        msg!("Chain {} fee currency set to {:?}", chain_id, fee_currency);
+        Ok(())
+    }
+
+    /// take or refuse nfts coming in from a registered chain, transfers out to it
+    /// arent affected. on by default, a chain that isnt enabled takes none either way.
+    /// authority only
+    pub fn set_chain_inbound(
+        ctx: Context<SetChainInbound>,
+        chain_id: u64,
+        inbound_enabled: bool,
+    ) -> Result<()> {
+        ctx.accounts.chain_info.inbound_enabled = inbound_enabled;
+
+        emit!(ChainInboundSet {
+            chain_id,
+            inbound_enabled,
+        });
+        msg!("Chain {} inbound enabled: {}", chain_id, inbound_enabled);
+        Ok(())
+    }
+
+    /// hold back messages from one chain without pausing the bridge, say when only
+    /// that chain is compromised. they are kept as dead letters to retry once the
+    /// pause is lifted, transfers out to it arent affected. authority only
+    pub fn set_chain_inbound_paused(
+        ctx: Context<SetChainInbound>,
+        chain_id: u64,
+        inbound_paused: bool,
+    ) -> Result<()> {
+        ctx.accounts.chain_info.inbound_paused = inbound_paused;
+
+        emit!(ChainInboundPausedSet {
+            chain_id,
+            inbound_paused,
+        });
+        msg!("Chain {} inbound paused: {}", chain_id, inbound_paused);
+        Ok(())
+    }
+
+    /// read a chain registry entry, returned through return data (use `.view()`)
+    pub fn get_chain_info(ctx: Context<GetChainInfo>, chain_id: u64) -> Result<ChainInfo> {
+        let chain_info = (*ctx.accounts.chain_info).clone();
+
+        msg!("Chain {}: {}", chain_id, chain_info.name);
+        Ok(chain_info)
+    }
+
+    /// compliance hold: freeze a holder's token account for one of our nfts. the
+    /// reason is required and kept in the compliance action pda. authority only
+    pub fn freeze_token_account(
+        ctx: Context<ComplianceHold>,
+        mint: Pubkey,
+        holder: Pubkey,
+        reason: String,
+    ) -> Result<()> {
+        validate_reason(&reason)?;
+
+        freeze_account(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                FreezeAccount {
+                    account: ctx.accounts.token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: ctx.accounts.nft_program.to_account_info(),
+                },
+            )
+            .with_signer(&[&[seeds::NFT_PROGRAM, &[ctx.accounts.nft_program.bump]]]),
+        )?;
+
+        record_compliance_action(
+            &mut ctx.accounts.compliance_action,
+            ctx.bumps.compliance_action,
+            mint,
+            holder,
+            true,
+            &reason,
+        )?;
+        emit!(AccountFrozen { mint, holder, reason });
+
+        msg!("Token account of {} frozen for mint {}", holder, mint);
+        Ok(())
+    }
+
+    /// lift a compliance hold, same rules as freeze_token_account
+    pub fn thaw_token_account(
+        ctx: Context<ComplianceHold>,
+        mint: Pubkey,
+        holder: Pubkey,
+        reason: String,
+    ) -> Result<()> {
+        validate_reason(&reason)?;
+
+        thaw_account(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                ThawAccount {
+                    account: ctx.accounts.token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: ctx.accounts.nft_program.to_account_info(),
+                },
+            )
+            .with_signer(&[&[seeds::NFT_PROGRAM, &[ctx.accounts.nft_program.bump]]]),
+        )?;
+
+        record_compliance_action(
+            &mut ctx.accounts.compliance_action,
+            ctx.bumps.compliance_action,
+            mint,
+            holder,
+            false,
+            &reason,
+        )?;
+        emit!(AccountThawed { mint, holder, reason });
+
+        msg!("Token account of {} thawed for mint {}", holder, mint);
+        Ok(())
+    }
+
+    /// move collected fees out of the treasury, always keeps it rent exempt
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let destination = ctx.accounts.destination.to_account_info();
+
+        // only whats above the rent exempt minimum can leave
+        let rent_exempt = Rent::get()?.minimum_balance(treasury.data_len());
+        let available = treasury.lamports().saturating_sub(rent_exempt);
+        require!(amount <= available, NftError::InsufficientTreasury);
+
+        // treasury is owned by us so we can move lamports directly
+        **treasury.try_borrow_mut_lamports()? -= amount;
+        **destination.try_borrow_mut_lamports()? = destination
+            .lamports()
+            .checked_add(amount)
+            .ok_or(NftError::Overflow)?;
+
+        emit!(TreasuryWithdrawn {
+            destination: destination.key(),
+            amount,
+            remaining: treasury.lamports(),
+        });
+
+        msg!("Withdrew {} lamports from treasury to {}", amount, destination.key());
+        Ok(())
+    }
+
+    /// move collected token fees out of the treasury's ata of a fee mint, also for
+    /// a mint no chain charges in anymore. authority only
+    pub fn withdraw_fee_token(ctx: Context<WithdrawFeeToken>, amount: u64) -> Result<()> {
+        let fee_vault = &ctx.accounts.fee_vault;
+        require!(amount <= fee_vault.amount, NftError::InsufficientTreasury);
+
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: fee_vault.to_account_info(),
+                    mint: ctx.accounts.fee_mint.to_account_info(),
+                    to: ctx.accounts.destination_token_account.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+            )
+            .with_signer(&[&[seeds::TREASURY, &[ctx.accounts.treasury.bump]]]),
+            amount,
+            ctx.accounts.fee_mint.decimals,
+        )?;
+
+        let mint = ctx.accounts.fee_mint.key();
+        let destination = ctx.accounts.destination_token_account.key();
+        emit!(FeeTokenWithdrawn {
+            mint,
+            destination,
+            amount,
+            remaining: fee_vault.amount - amount,
+        });
+
+        msg!("Withdrew {} of fee token {} from treasury to {}", amount, mint, destination);
+        Ok(())
+    }
+
+    /// close the nft info (and index slot) of an nft that was burned outside our
+    /// instructions, anyone can call it. the mint has to be at zero supply and
+    /// the nft cant be locked for a bridge transfer. the rent goes where
+    /// set_sweep_rent_destination says
+    pub fn sweep_orphaned_info(ctx: Context<SweepOrphanedInfo>, mint: Pubkey) -> Result<()> {
+        let accounts = ctx.accounts;
+        check_sweepable(&accounts.nft_info, accounts.mint.supply)?;
+
+        let destination = match accounts.nft_program.sweep_rent_destination {
+            SweepRentDestination::Treasury => accounts.treasury.to_account_info(),
+            SweepRentDestination::Caller => accounts.caller.to_account_info(),
+        };
+        let mut lamports = accounts.nft_info.to_account_info().lamports();
+
+        // unlike burn_nft the slot goes away too, nothing would ever tombstone it
+        if accounts.nft_info.index.is_some() {
+            let mint_index = accounts.mint_index
+                .as_ref()
+                .ok_or(NftError::InvalidMintIndex)?;
+            lamports = lamports
+                .checked_add(mint_index.to_account_info().lamports())
+                .ok_or(NftError::Overflow)?;
+            mint_index.close(destination.clone())?;
+        }
+        accounts.nft_info.close(destination.clone())?;
+
+        // the burn never went through us, so it still counts as existing. registered
+        // nfts never counted
+        if !is_external(&accounts.nft_info) {
+            bump_down(&mut accounts.nft_program.total_supply)?;
+        }
+        forget_immutable_metadata(&mut accounts.nft_program, &accounts.nft_info);
+
+        emit!(NftInfoSwept {
+            mint,
+            rent_destination: destination.key(),
+            lamports,
+        });
+
+        msg!("Swept nft info of {}, {} lamports to {}", mint, lamports, destination.key());
+        Ok(())
+    }
+
+    /// move tokens someone sent to the program pda by mistake out again. nfts
+    /// locked for a bridge transfer and the collection nfts stay put. authority only
+    pub fn rescue_token(
+        ctx: Context<RescueToken>,
+        mint: Pubkey,
+        amount: u64,
+        destination: Pubkey,
+    ) -> Result<()> {
+        let nft_info = &ctx.accounts.nft_info;
+        let escrow = if nft_info.owner == &crate::ID && !nft_info.data_is_empty() {
+            Some(NftInfo::try_deserialize(&mut &nft_info.try_borrow_data()?[..])?)
+        } else {
+            None
+        };
+        check_rescuable(&ctx.accounts.nft_program, escrow.as_ref(), mint)?;
+
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.program_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.destination_token_account.to_account_info(),
+                    authority: ctx.accounts.nft_program.to_account_info(),
+                },
+            )
+            .with_signer(&[&[seeds::NFT_PROGRAM, &[ctx.accounts.nft_program.bump]]]),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        emit!(TokenRescued { mint, amount, destination });
+        msg!("Rescued {} of mint {} to {}", amount, mint, destination);
+        Ok(())
+    }
+
+    /// let the holder of an nft minted elsewhere on solana bridge it. the mint needs
+    /// 0 decimals, a supply of 1 and metaplex metadata in an approved collection,
+    /// name, symbol, uri and royalty are read from there. the program never becomes
+    /// its mint authority, the nft is only ever locked in escrow and comes back with
+    /// an unlock. after a plain spl transfer the new holder registers again to take
+    /// the nft info over
+    pub fn register_existing_nft(ctx: Context<RegisterExistingNft>, mint: Pubkey) -> Result<()> {
+        upgrade_nft_info(
+            &mut ctx.accounts.nft_info,
+            &ctx.accounts.holder.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        let nft_info_bump = ctx.bumps.nft_info;
+        let accounts = &mut *ctx.accounts;
+        // an nft we minted or bridged in has its nft info already
+        if accounts.nft_info.mint != Pubkey::default() {
+            require!(accounts.nft_info.external, NftError::NotExternal);
+            require!(!accounts.nft_info.is_locked, NftError::TokenLocked);
+        }
+        require!(accounts.holder_token_account.amount == 1, NftError::NotHolder);
+
+        let metadata = external_metadata(&accounts.metadata)?;
+        check_collection_approved(&metadata, accounts.approved_collection.as_deref())?;
+        let name = metadata.name.trim_end_matches('\0').to_string();
+        let symbol = metadata.symbol.trim_end_matches('\0').to_string();
+        // same rules as a local mint, the uri travels in every message
+        let uri = validate_metadata(
+            &name,
+            &symbol,
+            metadata.uri.trim_end_matches('\0'),
+            &accounts.nft_program,
+        )?;
+        let token_standard = match metadata.token_standard {
+            Some(TokenStandard::ProgrammableNonFungible)
+            | Some(TokenStandard::ProgrammableNonFungibleEdition) => {
+                NftStandard::ProgrammableNonFungible
+            }
+            _ => NftStandard::NonFungible,
+        };
+
+        let holder = accounts.holder.key();
+        let nft_info = &mut *accounts.nft_info;
+        nft_info.mint = mint;
+        nft_info.owner = holder;
+        nft_info.metadata_uri = uri.clone();
+        nft_info.name = name.clone();
+        nft_info.symbol = symbol.clone();
+        nft_info.is_locked = false;
+        nft_info.cross_chain_recipient = [0; 32];
+        nft_info.token_standard = token_standard;
+        nft_info.compressed = false;
+        nft_info.asset_id = Pubkey::default();
+        nft_info.in_collection = false;
+        nft_info.bridged_collection = false;
+        nft_info.master_mint = Pubkey::default();
+        nft_info.edition = 0;
+        nft_info.metadata_hash = None;
+        nft_info.hash_mismatch = false;
+        nft_info.minted_to = Pubkey::default(); // not a local mint, owner stats dont count it
+        nft_info.bridged = false;
+        nft_info.index = None; // not minted here, no index slot
+        nft_info.royalty_bps = metadata.seller_fee_basis_points;
+        nft_info.royalty_recipient = None;
+        nft_info.attributes = vec![];
+        nft_info.bump = nft_info_bump;
+        nft_info.version = NFT_INFO_VERSION;
+        nft_info.metadata_created = true;
+        nft_info.needs_metadata_repair = false;
+        nft_info.metadata_immutable = false;
+        nft_info.external = true;
+        nft_info.lock_nonce = 0;
+
+        emit!(NftRegistered {
+            mint,
+            owner: holder,
+            name,
+            symbol,
+            uri,
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("Existing nft {} registered to {}", mint, holder);
+        Ok(())
+    }
+
+    /// undo register_existing_nft, the holder closes the nft info and gets the rent
+    /// back. not while the nft is locked for the bridge, and nfts we minted or
+    /// bridged in keep theirs. registered ones have no index slot and never counted
+    /// towards the supply, so the counters stay as they are
+    pub fn deregister_nft(ctx: Context<DeregisterNft>, mint: Pubkey) -> Result<()> {
+        let nft_info = &ctx.accounts.nft_info;
+        require!(is_external(nft_info), NftError::NotExternal);
+        require!(!nft_info.is_locked, NftError::TokenLocked);
+        require!(ctx.accounts.holder_token_account.amount == 1, NftError::NotHolder);
+
+        emit!(NftDeregistered {
+            mint,
+            owner: ctx.accounts.holder.key(),
+        });
+
+        msg!("Nft {} deregistered by {}", mint, ctx.accounts.holder.key());
+        Ok(())
+    }
+
+    /// start a crosschain transfer to zetachain, locks the nft
+    pub fn transfer_to_zetachain(
+        ctx: Context<TransferToZetachain>,
+        params: TransferParams,
+    ) -> Result<()> {
+        let TransferParams { destination_chain_id, recipient, .. } = params;
+        upgrade_nft_info(
+            &mut ctx.accounts.nft_info,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        // the destination has to be a registered, enabled chain
+        check_destination(&ctx.accounts.chain_info, &recipient)?;
+        let nft_info = &mut ctx.accounts.nft_info;
+        let nft_program = &mut ctx.accounts.nft_program;
+
+        // do some security checks so only owner can transfer and not locked
+        require!(nft_info.owner == ctx.accounts.owner.key(), NftError::Unauthorized);
+        require!(!nft_info.is_locked, NftError::TokenLocked);
+        require!(!is_blocked(&ctx.accounts.blocked_mint), NftError::MintBlocked);
+        // registered nfts have to be in an approved collection still, ours always are
+        if is_external(nft_info) {
+            let metadata = ctx.accounts.metadata.as_ref().ok_or(NftError::MissingMetaplexAccount)?;
+            require_keys_eq!(
+                metadata.key(),
+                MetaplexMetadata::find_pda(&nft_info.mint).0,
+                NftError::MissingMetaplexAccount
+            );
+            check_collection_approved(
+                &external_metadata(metadata)?,
+                ctx.accounts.approved_collection.as_deref(),
+            )?;
+        }
+        // the ata constraint only checks the address, the mint inside is checked here
+        // so the token account stays tied to nft info whatever registered the mint
+        require_keys_eq!(
+            ctx.accounts.owner_token_account.mint,
+            nft_info.mint,
+            NftError::TokenAccountMintMismatch
+        );
+        // an empty account would fail in the token program with an error nobody can read
+        require!(ctx.accounts.owner_token_account.amount == 1, NftError::TokenNotHeld);
+        // pnft accounts are always frozen by token metadata, thats not a compliance hold
+        if nft_info.token_standard != NftStandard::ProgrammableNonFungible {
+            let owner_token_account = &ctx.accounts.owner_token_account;
+            check_not_frozen(owner_token_account.key(), owner_token_account)?;
+            let program_token_account = &ctx.accounts.program_token_account;
+            check_not_frozen(program_token_account.key(), program_token_account)?;
+        }
+
+        // update nft state to locked and set crosschain recipient before any cpi,
+        // the fee, the token move and later the gateway call all see it locked
+        nft_info.is_locked = true;
+        nft_info.cross_chain_recipient = recipient;
+        let nonce = next_outbound_nonce(nft_program)?;
+        nft_info.lock_nonce = nonce;
+        bump(&mut nft_program.locked_count)?;
+
+        charge_chain_fee(
+            &ctx.accounts.chain_info,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        charge_fee_token(
+            &ctx.accounts.chain_info,
+            &FeeTokenAccounts {
+                fee_mint: &ctx.accounts.fee_mint,
+                owner_fee_token_account: &ctx.accounts.owner_fee_token_account,
+                fee_vault: &ctx.accounts.fee_vault,
+                fee_token_program: &ctx.accounts.fee_token_program,
+                owner: &ctx.accounts.owner,
+                treasury: &ctx.accounts.treasury,
+            },
+        )?;
+
+        // lock the nft by moving it to program, dont burn it
+        if nft_info.token_standard == NftStandard::ProgrammableNonFungible {
+            // pnft token accounts are frozen, only token metadata can move them
+            transfer_pnft(
+                &PnftTransfer {
+                    token: ctx.accounts.owner_token_account.to_account_info(),
+                    token_owner: ctx.accounts.owner.to_account_info(),
+                    destination_token: ctx.accounts.program_token_account.to_account_info(),
+                    destination_owner: nft_program.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    metadata: pnft_account(&ctx.accounts.metadata)?,
+                    edition: pnft_account(&ctx.accounts.master_edition)?,
+                    token_record: pnft_account(&ctx.accounts.owner_token_record)?,
+                    destination_token_record: pnft_account(&ctx.accounts.program_token_record)?,
+                    authority: ctx.accounts.owner.to_account_info(),
+                    payer: ctx.accounts.owner.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    sysvar_instructions: pnft_account(&ctx.accounts.sysvar_instructions)?,
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+                    token_metadata_program: pnft_account(&ctx.accounts.token_metadata_program)?,
+                },
+                &[],
+            )?;
+        } else {
+            transfer_checked(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.owner_token_account.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.program_token_account.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                1,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+
+        // make the crosschain message
+        let message = transfer_message(nft_info, recipient, nonce);
+
+        // serialize the message for sending
+        let message_bytes = message.try_to_vec()?;
+
+        emit_cpi!(TransferInitiated {
+            mint: nft_info.mint,
+            destination_chain_id,
+            recipient,
+            nonce,
+            flat_fee_lamports: ctx.accounts.chain_info.flat_fee_lamports,
+            gas_fee_lamports: ctx.accounts.chain_info.gas_fee_lamports,
+            message_hash: canonical_message_hash(&message_bytes),
+        });
+        
+        // send via gateway (not implemented, just log for now)
+        msg!("Cross-chain transfer initiated for mint {} to chain {} recipient {:?}", 
+            nft_info.mint, destination_chain_id, recipient);
+        msg!("Message: {:?}", message_bytes);
+
+        Ok(())
+    }
+
+    /// start a crosschain transfer for a compressed nft. the leaf is burned, the
+    /// merkle proof goes in as remaining accounts and the leaf fields come from
+    /// the asset proof. if it comes back on_call mints a fresh leaf
+    pub fn transfer_compressed_to_zetachain<'info>(
+        ctx: Context<'_, '_, '_, 'info, TransferCompressedToZetachain<'info>>,
+        params: TransferParams,
+        root: [u8; 32],
+        data_hash: [u8; 32],
+        creator_hash: [u8; 32],
+        leaf_index: u32,
+    ) -> Result<()> {
+        compressed::transfer_to_zetachain(
+            ctx,
+            params.destination_chain_id,
+            params.recipient,
+            root,
+            data_hash,
+            creator_hash,
+            leaf_index,
+        )
+    }
+
+    /// handle incoming crosschain message from zetachain, like mint or unlock
+    pub fn handle_cross_chain_call(
+        ctx: Context<HandleCrossChainCall>,
+        _sender: [u8; 32],
+        source_chain_id: u64,
+        message: Vec<u8>,
+        nonce: u64,
+    ) -> Result<()> {
+        let nft_program = &mut ctx.accounts.nft_program;
+        
+        // nowhere to keep a message for later here, in ordered delivery an early one fails
+        require!(!must_wait(nft_program, nonce)?, NftError::NonceNotReady);
+        // replay protection so we dont process same message twice
+        if let Some(gap) = accept_message_nonce(nft_program, nonce)? {
+            msg!("Nonces {} to {} are missing", gap.first_missing, gap.last_missing);
+            emit!(gap);
+        }
+
+        // try to parse the incoming message, fail if not valid
+        let cross_chain_message = decode_message(&message)?;
+        
+        match cross_chain_message.message_type {
+            MessageType::Transfer => {
+                // check the recipient is valid pubkey
+                let recipient_pubkey = validate_recipient(
+                    nft_program,
+                    cross_chain_message.recipient.into(),
+                )?;
+                
+                msg!("Handling cross-chain NFT transfer from chain {} to {}", 
+                    source_chain_id, recipient_pubkey);
+                
+                // here we would mint the nft, but not implemented yet
+                // would need to create accounts on the fly
+                msg!("Would mint NFT: {}", cross_chain_message.name);
+            }
+            MessageType::Unlock => {
+                // handle unlock for return transfers
+                msg!("Handling NFT unlock for mint {}", cross_chain_message.mint);
+            }
+            MessageType::BatchRoot => {
+                // batches need the record on_call writes, claim_inbound reads it
+                msg!("Batch {} has to come through on_call", cross_chain_message.mint);
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// handle incoming cross-chain call from gateway
+    /// this function is called by the gateway when an nft transfer is initiated from zetachain
+    /// it handles both transfer (minting new nfts) and unlock (returning locked nfts) operations
+    pub fn on_call<'info>(
+        ctx: Context<'_, '_, 'info, 'info, OnCall<'info>>,
+        message: InboundMessage,
+    ) -> Result<()> {
+        let InboundMessage { sender, data, .. } = message;
+        // Verify that the caller is the gateway program. the instructions sysvar only
+        // knows the top level instruction, so that has to be the gateway and it has
+        // to call us directly
+        let current_ix = anchor_lang::solana_program::sysvar::instructions::get_instruction_relative(
+            0,
+            &ctx.accounts.instruction_sysvar_account.to_account_info(),
+        )
+        .map_err(|_| NftError::Unauthorized)?;
+
+        let nft_program = &mut ctx.accounts.nft_program;
+        check_gateway_caller(
+            nft_program,
+            current_ix.program_id,
+            anchor_lang::solana_program::instruction::get_stack_height(),
+        )?;
+        // the gateway passes along who sent the call on zetachain, only the
+        // connected contract of an enabled chain gets through
+        check_source(&ctx.accounts.chain_info, &sender)?;
+        
+        // Parse the incoming message data
+        let cross_chain_message = decode_message(&data)?;
+        let nonce = cross_chain_message.nonce;
+        let message_hash = canonical_message_hash(&data);
+
+        // Defense in depth, the tss signed this message for this nonce and chain too
+        if nft_program.require_tss_signature {
+            check_tss_signature(
+                &ctx.accounts.instruction_sysvar_account,
+                &nft_program.tss_address,
+                &tss_message(&message_hash, nonce, ctx.accounts.chain_info.chain_id),
+            )?;
+        }
+
+        // In ordered delivery a message ahead of its turn waits for the ones before it,
+        // only its hash is kept. the nonce stays where it is
+        if must_wait(nft_program, nonce)? {
+            let expected_nonce = expected_nonce(nft_program);
+            record_pending_nonce(ctx.accounts, nonce, message_hash, sender)?;
+            emit_cpi!(MessageBuffered {
+                nonce,
+                expected_nonce,
+                message_hash,
+                source_chain_id: ctx.accounts.chain_info.chain_id,
+                slot: Clock::get()?.slot,
+            });
+            msg!("Message with nonce {} waits for nonce {}", nonce, expected_nonce);
+            return Ok(());
+        }
+
+        // Update nonce for replay protection, the ones jumped over are reported
+        if let Some(gap) = accept_message_nonce(nft_program, nonce)? {
+            msg!("Nonces {} to {} are missing", gap.first_missing, gap.last_missing);
+            emit_cpi!(gap);
+        }
+
+        deliver_inbound(ctx, sender, &data, cross_chain_message, message_hash)
+    }
+
+    /// runs a message ordered delivery kept waiting, once every nonce before it is
+    /// taken. takes the same accounts as on_call with the record as `pending_nonce`,
+    /// and the message the gateway delivered back then: it has to hash to what the
+    /// record kept. anyone can call it, the record is closed and the rent goes to
+    /// the payer. the message goes through like on_call, dead letters included
+    pub fn drain_pending<'info>(
+        ctx: Context<'_, '_, 'info, 'info, OnCall<'info>>,
+        message: InboundMessage,
+    ) -> Result<()> {
+        let pending_nonce = ctx.accounts.pending_nonce
+            .as_ref()
+            .ok_or(NftError::InvalidPendingNonce)?
+            .to_account_info();
+        let message_hash = canonical_message_hash(&message.data);
+        let pending = load_pending_nonce(&pending_nonce, message_hash)?;
+        require!(
+            pending.source_chain_id == ctx.accounts.chain_info.chain_id,
+            NftError::InvalidPendingNonce
+        );
+
+        // a record only gets written for a message that decoded fine
+        let cross_chain_message = decode_message(&message.data)?;
+        let nft_program = &mut ctx.accounts.nft_program;
+        require!(pending.nonce <= expected_nonce(nft_program), NftError::NonceNotReady);
+        // a nonce taken since (skip_message) is turned away like a replay
+        accept_message_nonce(nft_program, pending.nonce)?;
+
+        close_record(pending_nonce, ctx.accounts.payer.to_account_info())?;
+        emit_cpi!(PendingDrained {
+            nonce: pending.nonce,
+            message_hash,
+            caller: ctx.accounts.payer.key(),
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("Pending message with nonce {} drained", pending.nonce);
+        deliver_inbound(ctx, pending.sender, &message.data, cross_chain_message, message_hash)
+    }
+
+    /// runs a dead lettered message again once whatever it failed on is fixed. takes
+    /// the same accounts as on_call with the record as `dead_letter`, mint_key is the
+    /// origin mint from the message (the mint pda hangs off it). a message that still
+    /// fails leaves the record alone, on success it is closed and the rent goes to the
+    /// payer. set_dead_letter_retry decides if only the authority can call it
+    pub fn retry_dead_letter<'info>(
+        ctx: Context<'_, '_, 'info, 'info, OnCall<'info>>,
+        mint_key: Pubkey,
+        message_hash: [u8; 32],
+    ) -> Result<()> {
+        let nft_program = &ctx.accounts.nft_program;
+        require!(
+            nft_program.dead_letter_retry == DeadLetterRetry::Anyone
+                || is_authority(nft_program, ctx.accounts.payer.key()),
+            NftError::Unauthorized
+        );
+        require!(!ctx.accounts.chain_info.inbound_paused, NftError::SourceChainPaused);
+        let dead_letter = ctx.accounts.dead_letter
+            .as_ref()
+            .ok_or(NftError::InvalidDeadLetter)?
+            .to_account_info();
+        let record = load_dead_letter(&dead_letter, message_hash)?;
+        require!(
+            record.source_chain_id == ctx.accounts.chain_info.chain_id,
+            NftError::InvalidDeadLetter
+        );
+
+        // records only get written for messages that decoded fine
+        let message = decode_message(&record.payload)?;
+        require_keys_eq!(message.mint, mint_key, NftError::InvalidMessage);
+        let plan = plan_inbound(nft_program, &ctx.accounts.nft_info, message)?;
+        require!(
+            !recipient_blocked(&ctx.accounts.blocked_recipient, plan.recipient)?,
+            NftError::RecipientBlocked
+        );
+
+        close_record(dead_letter, ctx.accounts.payer.to_account_info())?;
+        emit_cpi!(DeadLetterRetried {
+            message_hash,
+            caller: ctx.accounts.payer.key(),
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("Dead letter {:?} retried", message_hash);
+        receive_message(ctx, record.sender, message_hash, plan)
+    }
+
+    /// runs a failed inbound message again once whatever it failed on is fixed. takes
+    /// the same accounts as on_call with the record as `failed_inbound`, and the message
+    /// the gateway delivered back then: the record only kept its hash, so it has to
+    /// hash to that again. a message that still fails leaves the record alone, on
+    /// success it is closed and the rent goes to the payer. set_dead_letter_retry
+    /// decides if only the authority can call it
+    pub fn retry_inbound<'info>(
+        ctx: Context<'_, '_, 'info, 'info, OnCall<'info>>,
+        nonce: u64,
+        message: InboundMessage,
+    ) -> Result<()> {
+        let nft_program = &ctx.accounts.nft_program;
+        require!(
+            nft_program.dead_letter_retry == DeadLetterRetry::Anyone
+                || is_authority(nft_program, ctx.accounts.payer.key()),
+            NftError::Unauthorized
+        );
+        require!(!ctx.accounts.chain_info.inbound_paused, NftError::SourceChainPaused);
+        let failed_inbound = ctx.accounts.failed_inbound
+            .as_ref()
+            .ok_or(NftError::InvalidFailedInbound)?
+            .to_account_info();
+        let record = load_failed_inbound(&failed_inbound, nonce)?;
+        require!(
+            record.source_chain_id == ctx.accounts.chain_info.chain_id,
+            NftError::InvalidFailedInbound
+        );
+        let message_hash = canonical_message_hash(&message.data);
+        require!(message_hash == record.message_hash, NftError::FailedInboundHashMismatch);
+
+        // a record only gets written for a message that decoded fine
+        let message = decode_message(&message.data)?;
+        let plan = plan_inbound(nft_program, &ctx.accounts.nft_info, message)?;
+        require!(
+            !recipient_blocked(&ctx.accounts.blocked_recipient, plan.recipient)?,
+            NftError::RecipientBlocked
+        );
+
+        close_record(failed_inbound, ctx.accounts.payer.to_account_info())?;
+        emit_cpi!(InboundRetried {
+            nonce,
+            message_hash,
+            caller: ctx.accounts.payer.key(),
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("Failed inbound message with nonce {} retried", nonce);
+        receive_message(ctx, record.sender, message_hash, plan)
+    }
+
+    /// mints one transfer out of an inbound batch. takes the same accounts as on_call for
+    /// the leaf message, with the batch record as `inbound_batch`. the message has to
+    /// be in the tree at `leaf_index` (`proof` from the leaf up) and every leaf goes
+    /// through once. anyone can claim, the recipient comes from the message. a leaf
+    /// that breaks a business rule just fails and stays claimable
+    pub fn claim_inbound<'info>(
+        ctx: Context<'_, '_, 'info, 'info, OnCall<'info>>,
+        leaf_index: u64,
+        leaf: Vec<u8>,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let inbound_batch = ctx.accounts.inbound_batch
+            .as_ref()
+            .ok_or(NftError::InvalidInboundBatch)?
+            .to_account_info();
+        let mut batch = load_inbound_batch(&inbound_batch)?;
+        require!(
+            batch.source_chain_id == ctx.accounts.chain_info.chain_id,
+            NftError::InvalidInboundBatch
+        );
+        // the leaf stays claimable until the chain is unpaused
+        require!(!ctx.accounts.chain_info.inbound_paused, NftError::SourceChainPaused);
+        require!(verify_batch_leaf(&batch, leaf_index, &leaf, &proof), NftError::InvalidMerkleProof);
+        claim_batch_leaf(&mut batch, leaf_index)?;
+        batch.try_serialize(&mut &mut inbound_batch.try_borrow_mut_data()?[..])?;
+
+        // the root was checked like any message, the leaves only by the proof. they
+        // carry the root's nonce and are plain transfers
+        let message = decode_message(&leaf)?;
+        require!(
+            message.message_type == MessageType::Transfer && message.nonce == batch.nonce,
+            NftError::InvalidBatchLeaf
+        );
+        let message_hash = canonical_message_hash(&leaf);
+        let plan = plan_inbound(&ctx.accounts.nft_program, &ctx.accounts.nft_info, message)?;
+        require!(
+            !recipient_blocked(&ctx.accounts.blocked_recipient, plan.recipient)?,
+            NftError::RecipientBlocked
+        );
+
+        emit_cpi!(InboundClaimed {
+            batch_id: batch.batch_id,
+            leaf_index,
+            message_hash,
+            caller: ctx.accounts.payer.key(),
+            slot: Clock::get()?.slot,
+        });
+        msg!("Leaf {} of batch {} claimed", leaf_index, batch.batch_id);
+        receive_message(ctx, batch.sender, message_hash, plan)
+    }
+
+    /// mints a transfer claim mode kept as a pending claim. takes the same accounts as
+    /// on_call for the message, with the record as `pending_claim`. only the recipient
+    /// can claim, as the payer, so it pays for the mint, token account and metadata
+    /// and gets the rent of the record. the message is planned again like a dead
+    /// letter retry, one that breaks a rule now fails and stays claimable
+    pub fn claim_nft<'info>(
+        ctx: Context<'_, '_, 'info, 'info, OnCall<'info>>,
+        nonce: u64,
+    ) -> Result<()> {
+        let pending_claim = ctx.accounts.pending_claim
+            .as_ref()
+            .ok_or(NftError::InvalidPendingClaim)?
+            .to_account_info();
+        let claim = load_pending_claim(&pending_claim, nonce)?;
+        require!(
+            claim.source_chain_id == ctx.accounts.chain_info.chain_id,
+            NftError::InvalidPendingClaim
+        );
+        require_keys_eq!(ctx.accounts.payer.key(), claim.recipient, NftError::Unauthorized);
+        require!(!ctx.accounts.chain_info.inbound_paused, NftError::SourceChainPaused);
+
+        // records only get written for transfers that decoded and planned fine
+        let message = decode_message(&claim.payload)?;
+        let plan = plan_inbound(&ctx.accounts.nft_program, &ctx.accounts.nft_info, message)?;
+        require!(
+            !recipient_blocked(&ctx.accounts.blocked_recipient, plan.recipient)?,
+            NftError::RecipientBlocked
+        );
+
+        close_record(pending_claim, ctx.accounts.payer.to_account_info())?;
+        emit_cpi!(NftClaimed {
+            nonce,
+            mint: claim.mint,
+            recipient: claim.recipient,
+            message_hash: claim.message_hash,
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("Pending claim with nonce {} claimed by {}", nonce, claim.recipient);
+        receive_message(ctx, claim.sender, claim.message_hash, plan)
+    }
+
+    /// sends an nft that is still a pending claim on to another chain without minting
+    /// it here first, the record is all there is of it. only its recipient can, it
+    /// pays the chain fee and gets the rent of the record. the outbound message is
+    /// the one the nft came in with, for the bridged mint it would have had
+    pub fn transfer_claim_to_zetachain(
+        ctx: Context<TransferClaimToZetachain>,
+        claim_nonce: u64,
+        params: TransferParams,
+    ) -> Result<()> {
+        let TransferParams { destination_chain_id, recipient, .. } = params;
+        check_destination(&ctx.accounts.chain_info, &recipient)?;
+        let claim = &ctx.accounts.pending_claim;
+
+        require!(!is_blocked(&ctx.accounts.blocked_mint), NftError::MintBlocked);
+        let nonce = next_outbound_nonce(&mut ctx.accounts.nft_program)?;
+
+        charge_chain_fee(
+            &ctx.accounts.chain_info,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        charge_fee_token(
+            &ctx.accounts.chain_info,
+            &FeeTokenAccounts {
+                fee_mint: &ctx.accounts.fee_mint,
+                owner_fee_token_account: &ctx.accounts.owner_fee_token_account,
+                fee_vault: &ctx.accounts.fee_vault,
+                fee_token_program: &ctx.accounts.fee_token_program,
+                owner: &ctx.accounts.owner,
+                treasury: &ctx.accounts.treasury,
+            },
+        )?;
+
+        // the metadata stays what zetachain sent, only where it goes changes
+        let mut message = decode_message(&claim.payload)?;
+        message.mint = claim.mint;
+        message.recipient = recipient;
+        message.nonce = nonce;
+        let message_bytes = message.try_to_vec()?;
+
+        emit_cpi!(TransferInitiated {
+            mint: claim.mint,
+            destination_chain_id,
+            recipient,
+            nonce,
+            flat_fee_lamports: ctx.accounts.chain_info.flat_fee_lamports,
+            gas_fee_lamports: ctx.accounts.chain_info.gas_fee_lamports,
+            message_hash: canonical_message_hash(&message_bytes),
+        });
+
+        msg!("Unclaimed mint {} sent on to chain {} recipient {:?}, claim {} closed",
+            claim.mint, destination_chain_id, recipient, claim_nonce);
+        msg!("Message: {:?}", message_bytes);
+        Ok(())
+    }
+
+    /// authority only force unlock, gives a locked nft back to its owner when nothing
+    /// will come back for it, like a transfer the gateway never took. the owner cant:
+    /// by then the nft can exist on the other chain, an unlock message through on_call
+    /// is how it comes home
+    pub fn unlock_nft(ctx: Context<UnlockNft>) -> Result<()> {
+        // system program is only passed along for pnfts, older infos get upgraded then
+        if let Some(system_program) = &ctx.accounts.system_program {
+            upgrade_nft_info(
+                &mut ctx.accounts.nft_info,
+                &ctx.accounts.authority.to_account_info(),
+                &system_program.to_account_info(),
+            )?;
+        }
+        let nft_info = &mut ctx.accounts.nft_info;
+        let nft_program = &mut ctx.accounts.nft_program;
+
+        require!(nft_info.is_locked, NftError::TokenNotLocked);
+        require!(!is_blocked(&ctx.accounts.blocked_recipient), NftError::RecipientBlocked);
+        if nft_info.token_standard != NftStandard::ProgrammableNonFungible {
+            let program_token_account = &ctx.accounts.program_token_account;
+            check_not_frozen(program_token_account.key(), program_token_account)?;
+            let owner_token_account = &ctx.accounts.owner_token_account;
+            check_not_frozen(owner_token_account.key(), owner_token_account)?;
+        }
+
+        // update state to unlocked before the nft moves
+        nft_info.is_locked = false;
+        let lock_nonce = nft_info.lock_nonce;
+        let unlocked = release_lock(nft_program, nft_info, UnlockReason::ForceUnlock, lock_nonce)?;
+        
+        // move nft back to owner
+        if nft_info.token_standard == NftStandard::ProgrammableNonFungible {
+            transfer_pnft(
+                &PnftTransfer {
+                    token: ctx.accounts.program_token_account.to_account_info(),
+                    token_owner: nft_program.to_account_info(),
+                    destination_token: ctx.accounts.owner_token_account.to_account_info(),
+                    destination_owner: ctx.accounts.owner.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    metadata: pnft_account(&ctx.accounts.metadata)?,
+                    edition: pnft_account(&ctx.accounts.master_edition)?,
+                    token_record: pnft_account(&ctx.accounts.program_token_record)?,
+                    destination_token_record: pnft_account(&ctx.accounts.owner_token_record)?,
+                    authority: nft_program.to_account_info(),
+                    payer: ctx.accounts.authority.to_account_info(),
+                    system_program: pnft_account(&ctx.accounts.system_program)?,
+                    sysvar_instructions: pnft_account(&ctx.accounts.sysvar_instructions)?,
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    associated_token_program: pnft_account(&ctx.accounts.associated_token_program)?,
+                    token_metadata_program: pnft_account(&ctx.accounts.token_metadata_program)?,
+                },
+                &[&[seeds::NFT_PROGRAM, &[nft_program.bump]]],
+            )?;
+        } else {
+            transfer_checked(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.program_token_account.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.owner_token_account.to_account_info(),
+                        authority: nft_program.to_account_info(),
+                    },
+                ).with_signer(&[&[
+                    seeds::NFT_PROGRAM,
+                    &[nft_program.bump]
+                ]]),
+                1,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+
+        emit_cpi!(unlocked);
+
+        msg!("NFT unlocked for mint {}", nft_info.mint);
+        Ok(())
+    }
+
+    /// burn an nft, only whoever holds the token can do this. closes the token
+    /// account and the nft info pda and gives the rent back to the holder
+    pub fn burn_nft(ctx: Context<BurnNft>, mint: Pubkey) -> Result<()> {
+        let nft_info = &ctx.accounts.nft_info;
+
+        // locked ones are on another chain, burning here would strand them
+        require!(!nft_info.is_locked, NftError::TokenLocked);
+        require!(ctx.accounts.owner_token_account.amount == 1, NftError::NotHolder);
+        if nft_info.token_standard != NftStandard::ProgrammableNonFungible {
+            let owner_token_account = &ctx.accounts.owner_token_account;
+            check_not_frozen(owner_token_account.key(), owner_token_account)?;
+        }
+
+        if nft_info.token_standard == NftStandard::ProgrammableNonFungible {
+            // pnft token accounts are frozen, token metadata burns and closes everything.
+            // with the collection metadata in there it also shrinks the collection size
+            let collection_metadata = match nft_info.in_collection {
+                true => Some(collection_account(&ctx.accounts.collection_metadata)?),
+                false => None,
+            };
+            BurnV1CpiBuilder::new(&pnft_account(&ctx.accounts.token_metadata_program)?)
+                .authority(&ctx.accounts.owner.to_account_info())
+                .collection_metadata(collection_metadata.as_ref())
+                .metadata(&pnft_account(&ctx.accounts.metadata)?)
+                .edition(Some(&pnft_account(&ctx.accounts.master_edition)?))
+                .mint(&ctx.accounts.mint.to_account_info())
+                .token(&ctx.accounts.owner_token_account.to_account_info())
+                .token_record(Some(&pnft_account(&ctx.accounts.owner_token_record)?))
+                .system_program(&ctx.accounts.system_program.to_account_info())
+                .sysvar_instructions(&pnft_account(&ctx.accounts.sysvar_instructions)?)
+                .spl_token_program(&ctx.accounts.token_program.to_account_info())
+                .amount(1)
+                .invoke()?;
+        } else {
+            // a plain spl burn leaves the metadata alone, so take it out of the
+            // collection first or the collection size would never go down
+            if nft_info.in_collection {
+                let collection_mint = match nft_info.bridged_collection {
+                    true => collection_account(&ctx.accounts.bridged_collection_mint)?,
+                    false => collection_account(&ctx.accounts.collection_mint)?,
+                };
+                remove_from_collection(
+                    &CollectionItem {
+                        metadata: metaplex_account(&ctx.accounts.metadata)?,
+                        collection_mint,
+                        collection_metadata: collection_account(&ctx.accounts.collection_metadata)?,
+                        collection_master_edition: collection_account(&ctx.accounts.collection_master_edition)?,
+                        authority: ctx.accounts.nft_program.to_account_info(),
+                        delegate_record: None,
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        sysvar_instructions: collection_account(&ctx.accounts.sysvar_instructions)?,
+                        token_metadata_program: metaplex_account(&ctx.accounts.token_metadata_program)?,
+                    },
+                    &[&[seeds::NFT_PROGRAM, &[ctx.accounts.nft_program.bump]]],
+                )?;
+            }
+            burn(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.mint.to_account_info(),
+                        from: ctx.accounts.owner_token_account.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                1,
+            )?;
+            close_account(CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: ctx.accounts.owner_token_account.to_account_info(),
+                    destination: ctx.accounts.owner.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ))?;
+        }
+
+        if nft_info.in_collection {
+            let collection = match nft_info.bridged_collection {
+                true => collection_state(&mut ctx.accounts.bridged_collection)?,
+                false => collection_state(&mut ctx.accounts.collection)?,
+            };
+            bump_down(&mut collection.collection_size)?;
+        }
+
+        // the index slot stays taken so later indexes dont shift, it just points nowhere now
+        if nft_info.index.is_some() {
+            let mint_index = ctx.accounts.mint_index
+                .as_mut()
+                .ok_or(NftError::InvalidMintIndex)?;
+            mint_index.mint = Pubkey::default();
+        }
+
+        // burning one you got from a local mint frees up a slot under the wallet limit
+        if let Some(owner_stats) = ctx.accounts.owner_stats.as_mut() {
+            if nft_info.minted_to == ctx.accounts.owner.key() {
+                bump_down(&mut owner_stats.minted)?;
+            }
+        }
+
+        let nft_program = &mut ctx.accounts.nft_program;
+        // registered nfts never counted towards the supply
+        if !is_external(nft_info) {
+            bump_down(&mut nft_program.total_supply)?;
+        }
+        forget_immutable_metadata(nft_program, nft_info);
+
+        emit!(NftBurned {
+            mint,
+            owner: ctx.accounts.owner.key(),
+        });
+
+        msg!("NFT burned for mint {}", mint);
+        Ok(())
+    }
+}
+
+// shared mint logic, used by both mint_nft and mint_nft_pda once the mint exists
+#[allow(clippy::too_many_arguments)]
+fn process_mint_nft(
+    accounts: MintAccounts<'_, '_>,
+    mint_seeds: &[&[&[u8]]],
+    nft_info_bump: u8,
+    name: String,
+    symbol: String,
+    uri: String,
+    recipient: Pubkey,
+    token_standard: NftStandard,
+    creators: Option<Vec<NftCreator>>,
+    metadata_hash: Option<[u8; 32]>,
+    attributes: Vec<Attribute>,
+    royalty_bps: Option<u16>,
+) -> Result<NftMinted> {
+    require!(!accounts.nft_program.mint_paused, NftError::MintPaused);
+    check_supply_cap(accounts.nft_program)?;
+
+    // check the inputs so we dont break stuff
+    let uri = validate_metadata(&name, &symbol, &uri, accounts.nft_program)?;
+    validate_recipient(accounts.nft_program, recipient)?;
+    require!(!is_blocked(accounts.blocked_recipient), NftError::RecipientBlocked);
+    let creators = validate_creators(creators)?;
+    validate_attributes(&attributes)?;
+    let (royalty_bps, royalty_recipient) =
+        local_royalty(accounts.nft_program, royalty_bps, creators.is_some())?;
+
+    // when public minting is off only the authority or a granted minter can mint
+    if !accounts.nft_program.public_mint {
+        let payer = accounts.payer.key();
+        let is_minter = is_authority(accounts.nft_program, payer)
+            || accounts
+                .minter_role
+                .as_ref()
+                .is_some_and(|role| role.minter == payer);
+        require!(is_minter, NftError::NotMinter);
+    }
+
+    // per wallet limit for allowlist drops, 0 means no limit
+    let mut minted = accounts.owner_stats.minted;
+    bump(&mut minted)?;
+    let max_mints = accounts.nft_program.max_mints_per_wallet;
+    require!(max_mints == 0 || minted <= max_mints, NftError::WalletMintLimit);
+    accounts.owner_stats.minted = minted;
+
+    // charge the mint fee before minting, zero fee means free mint
+    let fee_lamports = accounts.nft_program.mint_fee_lamports;
+    if fee_lamports > 0 {
+        require!(
+            accounts.payer.lamports() >= fee_lamports,
+            NftError::InsufficientFunds
+        );
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: accounts.payer.to_account_info(),
+                    to: accounts.treasury.to_account_info(),
+                },
+            ),
+            fee_lamports,
+        )?;
+    }
+
+    // metaplex has its own hard limit on the uri
+    let metadata_mode = accounts.nft_program.metadata_mode;
+    if metadata_mode == MetadataMode::Metaplex {
+        require!(uri.len() <= MAX_METAPLEX_URI_LEN, NftError::UriTooLong);
+    }
+    require!(
+        metadata_mode == MetadataMode::Metaplex
+            || token_standard == NftStandard::NonFungible,
+        NftError::PnftRequiresMetaplex
+    );
+    require!(
+        metadata_mode == MetadataMode::Metaplex || creators.is_none(),
+        NftError::CreatorsRequireMetaplex
+    );
+    // like inbound nfts the default recipient goes in as an unverified 100% creator,
+    // extension mode only keeps it in nft info
+    let creators = creators.or_else(|| {
+        royalty_recipient
+            .filter(|_| metadata_mode == MetadataMode::Metaplex)
+            .map(|address| {
+                vec![Creator {
+                    address,
+                    verified: false,
+                    share: 100,
+                }]
+            })
+    });
+    let signer_seeds: &[&[&[u8]]] = &[&[seeds::NFT_PROGRAM, &[accounts.nft_program.bump]]];
+
+    // with a program collection every metaplex nft gets verified into it, extension
+    // mode nfts have no metaplex metadata so they stay out
+    let collection_mint = accounts.nft_program.collection_mint;
+    let joins_collection =
+        collection_mint != Pubkey::default() && metadata_mode == MetadataMode::Metaplex;
+    let collection = joins_collection.then_some(MetaplexCollection {
+        verified: false,
+        key: collection_mint,
+    });
+    // deferred verification leaves the collection unverified for verify_collection_item
+    let verify_now = joins_collection && !accounts.nft_program.defer_collection_verify;
+
+    // the mint is made here instead of in the accounts struct since extension
+    // mode needs the metadata pointer on it before it gets initialized
+    create_nft_mint(
+        accounts.mint,
+        &accounts.nft_program.to_account_info(),
+        &accounts.payer.to_account_info(),
+        &accounts.token_program.to_account_info(),
+        &accounts.system_program.to_account_info(),
+        metadata_mode,
+        mint_seeds,
+    )?;
+    // idempotent so a pda recipient (or an ata someone made up front) works too
+    anchor_spl::associated_token::create_idempotent(CpiContext::new(
+        accounts.associated_token_program.to_account_info(),
+        anchor_spl::associated_token::Create {
+            payer: accounts.payer.to_account_info(),
+            associated_token: accounts.token_account.to_account_info(),
+            authority: accounts.recipient.to_account_info(),
+            mint: accounts.mint.clone(),
+            system_program: accounts.system_program.to_account_info(),
+            token_program: accounts.token_program.to_account_info(),
+        },
+    ))?;
+
+    match token_standard {
+        NftStandard::NonFungible => {
+            // mint the token, only 1 for nft
+            mint_to(
+                CpiContext::new(
+                    accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: accounts.mint.clone(),
+                        to: accounts.token_account.to_account_info(),
+                        authority: accounts.nft_program.to_account_info(),
+                    },
+                ).with_signer(signer_seeds),
+                1, // nfts always have supply 1
+            )?;
+
+            if metadata_mode == MetadataMode::TokenExtension {
+                init_extension_metadata(
+                    accounts.mint,
+                    &accounts.nft_program.to_account_info(),
+                    &accounts.payer.to_account_info(),
+                    &accounts.token_program.to_account_info(),
+                    &accounts.system_program.to_account_info(),
+                    name.clone(),
+                    symbol.clone(),
+                    uri.clone(),
+                    signer_seeds,
+                )?;
+            } else {
+                // make the metadata for the nft
+                let data_v2 = DataV2 {
+                    name: name.clone(),
+                    symbol: symbol.clone(),
+                    uri: uri.clone(),
+                    seller_fee_basis_points: royalty_bps,
+                    creators: creators.clone(),
+                    collection: collection.clone(),
+                    uses: None,
+                };
+
+                create_metadata_accounts_v3(
+                    CpiContext::new(
+                        metaplex_account(accounts.token_metadata_program)?,
+                        CreateMetadataAccountsV3 {
+                            metadata: metaplex_account(accounts.metadata)?,
+                            mint: accounts.mint.clone(),
+                            mint_authority: accounts.nft_program.to_account_info(),
+                            update_authority: accounts.nft_program.to_account_info(),
+                            payer: accounts.payer.to_account_info(),
+                            system_program: accounts.system_program.to_account_info(),
+                            rent: accounts.rent.to_account_info(),
+                        },
+                    ).with_signer(signer_seeds),
+                    data_v2,
+                    false, // not mutable
+                    true,  // update authority is signer
+                    None,  // no collection details
+                )?;
+            }
+        }
+        NftStandard::ProgrammableNonFungible => {
+            // pnfts go through token metadata for both create and mint, it also
+            // makes the master edition and the token record and freezes the ata
+            let nft_program = accounts.nft_program.to_account_info();
+            let metadata = metaplex_account(accounts.metadata)?;
+            let master_edition = pnft_account(accounts.master_edition)?;
+            let token_record = pnft_account(accounts.token_record)?;
+            let sysvar_instructions = pnft_account(accounts.sysvar_instructions)?;
+            let mint = accounts.mint.clone();
+            let payer = accounts.payer.to_account_info();
+            let system_program = accounts.system_program.to_account_info();
+            let token_program = accounts.token_program.to_account_info();
+            let token_metadata_program = metaplex_account(accounts.token_metadata_program)?;
+
+            let mut create = CreateV1CpiBuilder::new(&token_metadata_program);
+            create
+                .metadata(&metadata)
+                .master_edition(Some(&master_edition))
+                .mint(&mint, false)
+                .authority(&nft_program)
+                .payer(&payer)
+                .update_authority(&nft_program, true)
+                .system_program(&system_program)
+                .sysvar_instructions(&sysvar_instructions)
+                .spl_token_program(Some(&token_program))
+                .name(name.clone())
+                .symbol(symbol.clone())
+                .uri(uri.clone())
+                .seller_fee_basis_points(royalty_bps)
+                .is_mutable(false)
+                .token_standard(TokenStandard::ProgrammableNonFungible)
+                .print_supply(PrintSupply::Zero);
+            if let Some(collection) = collection {
+                create.collection(collection);
+            }
+            if let Some(creators) = creators.clone() {
+                create.creators(creators);
+            }
+            create.invoke_signed(signer_seeds)?;
+
+            MintV1CpiBuilder::new(&token_metadata_program)
+                .token(&accounts.token_account.to_account_info())
+                .metadata(&metadata)
+                .master_edition(Some(&master_edition))
+                .token_record(Some(&token_record))
+                .mint(&mint)
+                .authority(&nft_program)
+                .payer(&payer)
+                .system_program(&system_program)
+                .sysvar_instructions(&sysvar_instructions)
+                .spl_token_program(&token_program)
+                .spl_ata_program(&accounts.associated_token_program.to_account_info())
+                .amount(1)
+                .invoke_signed(signer_seeds)?;
+        }
+    }
+
+    // the program pda can sign for itself, other creators use verify_creator later
+    let program_key = accounts.nft_program.key();
+    if creators
+        .as_ref()
+        .is_some_and(|creators| creators.iter().any(|creator| creator.address == program_key))
+    {
+        sign_metadata(
+            CpiContext::new(
+                metaplex_account(accounts.token_metadata_program)?,
+                SignMetadata {
+                    creator: accounts.nft_program.to_account_info(),
+                    metadata: metaplex_account(accounts.metadata)?,
+                },
+            )
+            .with_signer(signer_seeds),
+        )?;
+    }
+
+    if verify_now {
+        add_to_collection(
+            &CollectionItem {
+                metadata: metaplex_account(accounts.metadata)?,
+                collection_mint: collection_account(accounts.collection_mint)?,
+                collection_metadata: collection_account(accounts.collection_metadata)?,
+                collection_master_edition: collection_account(accounts.collection_master_edition)?,
+                authority: accounts.nft_program.to_account_info(),
+                delegate_record: None,
+                system_program: accounts.system_program.to_account_info(),
+                sysvar_instructions: collection_account(accounts.sysvar_instructions)?,
+                token_metadata_program: metaplex_account(accounts.token_metadata_program)?,
+            },
+            collection_state(accounts.collection)?,
+            signer_seeds,
+        )?;
+    }
+
+    // update the program state, add 1 to supply
+    let nft_program = &mut *accounts.nft_program;
+    bump(&mut nft_program.total_supply)?;
+    bump(&mut nft_program.mint_count)?;
+    let index = record_mint_index(accounts.mint_index, nft_program, accounts.mint.key())?;
+
+    // save nft info for crosschain stuff
+    let nft_info = &mut *accounts.nft_info;
+    nft_info.mint = accounts.mint.key();
+    nft_info.owner = recipient;
+    nft_info.metadata_uri = uri.clone();
+    nft_info.name = name.clone();
+    nft_info.symbol = symbol.clone();
+    nft_info.is_locked = false;
+    nft_info.token_standard = token_standard;
+    nft_info.compressed = false;
+    nft_info.asset_id = Pubkey::default();
+    nft_info.in_collection = verify_now;
+    nft_info.bridged_collection = false;
+    nft_info.master_mint = Pubkey::default();
+    nft_info.edition = 0;
+    nft_info.metadata_hash = metadata_hash;
+    nft_info.hash_mismatch = false;
+    nft_info.minted_to = recipient;
+    nft_info.bridged = false;
+    nft_info.index = Some(index);
+    nft_info.royalty_bps = royalty_bps;
+    nft_info.royalty_recipient = royalty_recipient;
+    nft_info.attributes = attributes;
+    nft_info.bump = nft_info_bump;
+    nft_info.version = NFT_INFO_VERSION;
+    nft_info.metadata_created = true;
+    nft_info.needs_metadata_repair = false;
+    nft_info.metadata_immutable = false;
+    nft_info.external = false;
+    nft_info.lock_nonce = 0;
+
+    let minted = NftMinted {
+        mint: accounts.mint.key(),
+        recipient,
+        name,
+        symbol,
+        uri,
+        token_id: Some(index),
+        slot: Clock::get()?.slot,
+        payer: accounts.payer.key(),
+        fee_lamports,
+        metadata_hash,
+    };
+
+    msg!("NFT minted: {}", accounts.mint.key());
+    Ok(minted)
+}
+
+// counters (supplies, counts, collection sizes) only move one at a time through
+// these two, lamports and sizes keep their own checked math
+fn bump(counter: &mut u64) -> Result<()> {
+    *counter = counter.checked_add(1).ok_or(NftError::CounterOverflow)?;
+    Ok(())
+}
+
+fn bump_down(counter: &mut u64) -> Result<()> {
+    *counter = counter.checked_sub(1).ok_or(NftError::CounterUnderflow)?;
+    Ok(())
+}
+
+// hands out the next index to a new mint, the caller sets the bump
+fn record_mint_index(
+    mint_index: &mut Account<MintIndex>,
+    nft_program: &mut NftProgramState,
+    mint: Pubkey,
+) -> Result<u64> {
+    let index = nft_program.index_count;
+    mint_index.index = index;
+    mint_index.mint = mint;
+    bump(&mut nft_program.index_count)?;
+    Ok(index)
+}
+
+// inbound nfts only get an index the first time they arrive, nft info is still
+// blank then. a returning nft must not pass one or it would take a slot for nothing
+fn index_inbound_mint(
+    nft_info: &NftInfo,
+    mint_index: &mut Option<Account<MintIndex>>,
+    mint_index_bump: Option<u8>,
+    nft_program: &mut NftProgramState,
+    mint: Pubkey,
+) -> Result<Option<u64>> {
+    if nft_info.mint != Pubkey::default() {
+        require!(mint_index.is_none(), NftError::InvalidMintIndex);
+        return Ok(nft_info.index);
+    }
+    let mint_index = mint_index.as_mut().ok_or(NftError::InvalidMintIndex)?;
+    mint_index.bump = mint_index_bump.ok_or(NftError::InvalidMintIndex)?;
+    record_mint_index(mint_index, nft_program, mint).map(Some)
+}
+
+// attributes are a short list of short strings, keys cant be empty
+fn validate_attributes(attributes: &[Attribute]) -> Result<()> {
+    require!(attributes.len() <= MAX_ATTRIBUTES, NftError::TooManyAttributes);
+    for attribute in attributes {
+        require!(
+            !attribute.key.is_empty() && attribute.key.len() <= MAX_ATTRIBUTE_KEY_LEN,
+            NftError::InvalidAttributeKey
+        );
+        require!(
+            attribute.value.len() <= MAX_ATTRIBUTE_VALUE_LEN,
+            NftError::AttributeValueTooLong
+        );
+    }
+    Ok(())
+}
+
+// creators follow the metaplex rules: at most 5, no repeats, shares add up to 100.
+// everyone starts unverified, token metadata only lets the creator verify
+fn validate_creators(creators: Option<Vec<NftCreator>>) -> Result<Option<Vec<Creator>>> {
+    let Some(creators) = creators else {
+        return Ok(None);
+    };
+    require!(
+        !creators.is_empty() && creators.len() <= MAX_CREATORS,
+        NftError::InvalidCreators
+    );
+    let total_share: u16 = creators.iter().map(|creator| creator.share as u16).sum();
+    require!(total_share == 100, NftError::InvalidCreators);
+    for (i, creator) in creators.iter().enumerate() {
+        require!(
+            creators[..i].iter().all(|other| other.address != creator.address),
+            NftError::InvalidCreators
+        );
+    }
+
+    Ok(Some(
+        creators
+            .into_iter()
+            .map(|creator| Creator {
+                address: creator.address,
+                verified: false,
+                share: creator.share,
+            })
+            .collect(),
+    ))
+}
+
+// on_call takes nft info with init_if_needed, so a mint colliding with a local
+// one would overwrite its record. fresh accounts still have the default mint
+fn check_inbound_nft_info(nft_info: &NftInfo) -> Result<()> {
+    require!(
+        nft_info.mint == Pubkey::default() || nft_info.bridged,
+        NftError::NftInfoCollision
+    );
+    Ok(())
+}
+
+// the program holds locked nfts for the bridge and its collection nfts, those are
+// never up for rescue. an nft info that isnt locked is just a stray token
+fn check_rescuable(
+    nft_program: &NftProgramState,
+    nft_info: Option<&NftInfo>,
+    mint: Pubkey,
+) -> Result<()> {
+    require!(
+        mint != nft_program.collection_mint && mint != nft_program.bridged_collection_mint,
+        NftError::TokenNotRescuable
+    );
+    require!(
+        !nft_info.is_some_and(|nft_info| nft_info.is_locked),
+        NftError::TokenNotRescuable
+    );
+    Ok(())
+}
+
+// only nft infos whose mint is gone for good can be swept. a locked nft sits in
+// our escrow waiting for the other chain, so check that first
+fn check_sweepable(nft_info: &NftInfo, supply: u64) -> Result<()> {
+    require!(!nft_info.is_locked, NftError::TokenLocked);
+    require!(supply == 0, NftError::MintHasSupply);
+    Ok(())
+}
+
+// compares the hash on record with the one in a message. only a mismatch between
+// two known hashes counts, in strict mode that fails and otherwise the nft gets
+// flagged and indexers get an event
+fn check_metadata_hash(
+    nft_info: &NftInfo,
+    incoming: Option<[u8; 32]>,
+    strict: bool,
+) -> Result<bool> {
+    let mismatch = match (nft_info.metadata_hash, incoming) {
+        (Some(known), Some(incoming)) => known != incoming,
+        _ => false,
+    };
+    if mismatch {
+        require!(!strict, NftError::MetadataHashMismatch);
+        emit!(MetadataHashMismatch {
+            mint: nft_info.mint,
+            expected: nft_info.metadata_hash,
+            received: incoming,
+        });
+    }
+    Ok(mismatch)
+}
+
+// compliance and block reasons are free text but have to be there and fit the pda
+fn validate_reason(reason: &str) -> Result<()> {
+    require!(
+        !reason.is_empty() && reason.len() <= MAX_REASON_LEN,
+        NftError::InvalidReason
+    );
+    Ok(())
+}
+
+// keeps the latest freeze or thaw for a holder, the events have the full history
+fn record_compliance_action(
+    compliance_action: &mut Account<'_, ComplianceAction>,
+    bump: u8,
+    mint: Pubkey,
+    holder: Pubkey,
+    frozen: bool,
+    reason: &str,
+) -> Result<()> {
+    compliance_action.mint = mint;
+    compliance_action.holder = holder;
+    compliance_action.frozen = frozen;
+    compliance_action.reason = reason.to_string();
+    compliance_action.updated_at = Clock::get()?.unix_timestamp;
+    compliance_action.bump = bump;
+    Ok(())
+}
+
+// recipients can be wallets or pdas of other programs (vaults, escrows), so off
+// curve keys are fine on purpose. nobody holds the key of the default pubkey, and
+// the program itself and its state pda cant do anything with a token, an nft sent
+// to them is burned for good while it still counts in total_supply. the last two
+// only get one when allowed. local mints and the 32 bytes from another chain go
+// through the same check
+fn validate_recipient(nft_program: &NftProgramState, recipient: Pubkey) -> Result<Pubkey> {
+    require_keys_neq!(recipient, Pubkey::default(), NftError::InvalidRecipient);
+    if !nft_program.allow_program_recipients {
+        require_keys_neq!(recipient, crate::ID, NftError::InvalidRecipient);
+        require_keys_neq!(recipient, seeds::find_program_state().0, NftError::InvalidRecipient);
+    }
+    Ok(recipient)
+}
+
+// a wallet or mint is blocked while its block pda exists. the program never moves
+// tokens because of a block, it only refuses to deliver them or bridge them
+fn is_blocked(block: &AccountInfo) -> bool {
+    *block.owner == crate::ID && !block.data_is_empty()
+}
+
+// for accounts the address cant be checked on, the recipient comes out of a message
+fn recipient_blocked(blocked_recipient: &AccountInfo, recipient: Pubkey) -> Result<bool> {
+    let (address, _) = seeds::find_blocked_recipient(&recipient);
+    require_keys_eq!(blocked_recipient.key(), address, NftError::InvalidBlockedRecipient);
+    Ok(is_blocked(blocked_recipient))
+}
+
+// checks name, symbol and uri before anything gets minted, each problem gets its
+// own error so clients can tell the user what to fix. returns the cleaned up uri
+fn validate_metadata(
+    name: &str,
+    symbol: &str,
+    uri: &str,
+    nft_program: &NftProgramState,
+) -> Result<String> {
+    validate_name_and_symbol(name, symbol)?;
+    validate_metadata_uri(uri, nft_program)
+}
+
+fn validate_name_and_symbol(name: &str, symbol: &str) -> Result<()> {
+    require!(!name.is_empty(), NftError::EmptyName);
+    require!(name.len() <= MAX_NAME_LEN, NftError::NameTooLong);
+    require!(symbol.len() <= MAX_SYMBOL_LEN, NftError::SymbolTooLong);
+    Ok(())
+}
+
+fn validate_metadata_uri(uri: &str, nft_program: &NftProgramState) -> Result<String> {
+    require!(
+        uri.len() <= nft_program.max_uri_len as usize,
+        NftError::UriTooLong
+    );
+    validate_uri(uri, &nft_program.allowed_uri_prefixes)
+}
+
+// the uri an inbound transfer is stored with and whether it still needs repair_metadata.
+// one that fails validation gets the placeholder uri when the authority set one, a
+// returning nft keeps the uri on record instead. compressed leaves cant be repaired,
+// compressed mode refuses it like without a placeholder. a flagged nft stays flagged
+// until it gets repaired, its metadata was created with the placeholder
+fn inbound_metadata_uri(
+    uri: &str,
+    nft_program: &NftProgramState,
+    nft_info: &NftInfo,
+) -> Result<(String, bool)> {
+    let returning = nft_info.mint != Pubkey::default();
+    let flagged = returning && nft_info.needs_metadata_repair;
+    match validate_metadata_uri(uri, nft_program) {
+        Ok(uri) => Ok((uri, flagged)),
+        Err(_) if !nft_program.placeholder_uri.is_empty() && !nft_program.compressed_mode => {
+            match returning {
+                true => Ok((nft_info.metadata_uri.clone(), flagged)),
+                false => Ok((nft_program.placeholder_uri.clone(), true)),
+            }
+        }
+        Err(error) => Err(error),
+    }
+}
+
+// the program default recipient, none while it is the default pubkey
+fn default_royalty_recipient(nft_program: &NftProgramState) -> Option<Pubkey> {
+    let recipient = nft_program.default_royalty_recipient;
+    (recipient != Pubkey::default()).then_some(recipient)
+}
+
+// local mints without a royalty arg get the program default. the default recipient
+// only stands in when no creators were passed, creators already say who gets paid
+fn local_royalty(
+    nft_program: &NftProgramState,
+    royalty_bps: Option<u16>,
+    has_creators: bool,
+) -> Result<(u16, Option<Pubkey>)> {
+    let royalty_bps = royalty_bps.unwrap_or(nft_program.default_royalty_bps);
+    require!(royalty_bps <= MAX_ROYALTY_BPS, NftError::InvalidRoyaltyBps);
+    let recipient = match has_creators {
+        true => None,
+        false => default_royalty_recipient(nft_program),
+    };
+    Ok((royalty_bps, recipient))
+}
+
+// no royalty in the message means the program default. royalties over 100% from
+// another chain are either clamped or rejected, per config
+fn inbound_royalty_bps(royalty_bps: Option<u16>, default_bps: u16, clamp: bool) -> Result<u16> {
+    let Some(royalty_bps) = royalty_bps else {
+        return Ok(default_bps);
+    };
+    if royalty_bps <= MAX_ROYALTY_BPS {
+        return Ok(royalty_bps);
+    }
+    require!(clamp, NftError::InvalidRoyaltyBps);
+    Ok(MAX_ROYALTY_BPS)
+}
+
+// evm collections can send an empty symbol, an empty name or ones longer than
+// metaplex allows. empty symbol -> the configured default, empty name -> "#<token id>".
+// too long ones fail with Reject (the tx reverts so the gateway can retry later) or
+// get cut with Truncate, so the metadata cpi and nft info write cant fail
+fn normalize_inbound_metadata(
+    message: &mut CrossChainMessage,
+    default_symbol: &str,
+    policy: InboundStringPolicy,
+) -> Result<()> {
+    let full_name = trim_padding(&message.name).to_string();
+    let full_symbol = trim_padding(&message.symbol).to_string();
+    let too_long = full_name.len() > MAX_NAME_LEN || full_symbol.len() > MAX_SYMBOL_LEN;
+    require!(
+        !too_long || policy == InboundStringPolicy::Truncate,
+        NftError::StringTooLong
+    );
+
+    // a metadata hash already covers the full name and symbol, without one keep
+    // a hash of the uncut values so they can still be checked against the origin
+    if too_long && message.metadata_hash.is_none() {
+        let full_values = (full_name.clone(), full_symbol.clone()).try_to_vec()?;
+        message.metadata_hash = Some(hash(&full_values).to_bytes());
+    }
+
+    let mut name = full_name;
+    if name.is_empty() {
+        name = default_inbound_name(&message.mint);
+    }
+    truncate_utf8(&mut name, MAX_NAME_LEN);
+    message.name = name;
+
+    let mut symbol = full_symbol;
+    if symbol.is_empty() {
+        symbol = default_symbol.to_string();
+    }
+    truncate_utf8(&mut symbol, MAX_SYMBOL_LEN);
+    message.symbol = symbol;
+    Ok(())
+}
+
+// evm token ids come through as a big endian uint256 in the mint field. ids that
+// fit a u64 print as a number, anything else falls back to the base58 id
+fn default_inbound_name(mint: &Pubkey) -> String {
+    let bytes = mint.to_bytes();
+    if bytes[..24].iter().all(|byte| *byte == 0) {
+        let mut token_id = [0u8; 8];
+        token_id.copy_from_slice(&bytes[24..]);
+        format!("#{}", u64::from_be_bytes(token_id))
+    } else {
+        format!("#{}", mint)
+    }
+}
+
+fn trim_padding(value: &str) -> &str {
+    value.trim_matches(|c: char| c == '\0' || c.is_whitespace())
+}
+
+// get_nft_info without the account loading
+fn nft_info_view(mint: Pubkey, nft_info: Option<&NftInfo>, nonce: u64) -> NftInfoView {
+    let status = match nft_info {
+        None => NftInfoStatus::NotFound,
+        Some(nft_info) => {
+            let mut uri = nft_info.metadata_uri.clone();
+            let uri_truncated = uri.len() > MAX_VIEW_URI_LEN;
+            truncate_utf8(&mut uri, MAX_VIEW_URI_LEN);
+            NftInfoStatus::Found {
+                owner: nft_info.owner,
+                is_locked: nft_info.is_locked,
+                bridged: nft_info.bridged,
+                uri,
+                uri_truncated,
+                name: nft_info.name.clone(),
+                locked_destination: nft_info.is_locked.then_some(nft_info.cross_chain_recipient),
+                nonce,
+            }
+        }
+    };
+    NftInfoView {
+        version: NFT_INFO_VIEW_VERSION,
+        mint,
+        status,
+    }
+}
+
+// cut on a char boundary so multibyte names dont end up as invalid utf-8
+fn truncate_utf8(value: &mut String, max_len: usize) {
+    if value.len() <= max_len {
+        return;
+    }
+    let mut end = max_len;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    value.truncate(end);
+}
+
+// trims trailing nuls/whitespace and checks the uri against the allowed prefixes,
+// returns the trimmed uri which is what gets stored. strings coming out of borsh
+// are already valid utf-8 so theres nothing extra to check there
+fn validate_uri(uri: &str, allowed_prefixes: &[String]) -> Result<String> {
+    let trimmed = uri.trim_end_matches(|c: char| c == '\0' || c.is_whitespace());
+    require!(!trimmed.is_empty(), NftError::EmptyUri);
+    require!(
+        allowed_prefixes
+            .iter()
+            .any(|prefix| trimmed.starts_with(prefix.as_str())),
+        NftError::InvalidUriScheme
+    );
+    Ok(trimmed.to_string())
+}
+
+// one {cid} in each, at the end of the pattern so it matches on the prefix
+fn validate_uri_rewrite(uri_rewrite: &UriRewrite) -> Result<()> {
+    let prefix = uri_rewrite.pattern.strip_suffix(URI_REWRITE_PLACEHOLDER).unwrap_or_default();
+    require!(
+        !prefix.is_empty()
+            && !prefix.contains(URI_REWRITE_PLACEHOLDER)
+            && uri_rewrite.pattern.len() <= MAX_URI_REWRITE_PATTERN_LEN,
+        NftError::InvalidUriRewrite
+    );
+    require!(
+        uri_rewrite.replacement.matches(URI_REWRITE_PLACEHOLDER).count() == 1
+            && uri_rewrite.replacement.len() <= MAX_URI_REWRITE_REPLACEMENT_LEN,
+        NftError::InvalidUriRewrite
+    );
+    Ok(())
+}
+
+// the uri an inbound nft's metadata gets, nft info keeps the one from the message.
+// no rewrite set, no match or a result metaplex wouldnt take leaves it as it is
+fn display_uri(nft_program: &NftProgramState, uri: &str) -> String {
+    nft_program
+        .uri_rewrite
+        .as_ref()
+        .and_then(|uri_rewrite| {
+            let prefix = uri_rewrite.pattern.strip_suffix(URI_REWRITE_PLACEHOLDER)?;
+            let cid = uri.strip_prefix(prefix).filter(|cid| !cid.is_empty())?;
+            Some(uri_rewrite.replacement.replacen(URI_REWRITE_PLACEHOLDER, cid, 1))
+        })
+        .filter(|rewritten| rewritten.len() <= MAX_METAPLEX_URI_LEN)
+        .unwrap_or_else(|| uri.to_string())
+}
+
+// grows an nft info account that was allocated under a smaller max uri length,
+// the payer tops up the rent for the extra bytes
+fn ensure_nft_info_space<'info>(
+    nft_info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    if nft_info.data_len() >= space {
+        return Ok(());
+    }
+
+    top_up_rent(nft_info, payer, system_program, space)?;
+    nft_info.realloc(space, false)?;
+    Ok(())
+}
+
+// name and recipient length have to make sense, the registry is read on every transfer
+fn validate_chain_config(config: &ChainConfig) -> Result<()> {
+    require!(
+        !config.name.is_empty() && config.name.len() <= MAX_CHAIN_NAME_LEN,
+        NftError::InvalidChainConfig
+    );
+    require!(
+        (1..=32).contains(&config.recipient_len),
+        NftError::InvalidChainConfig
+    );
+    Ok(())
+}
+
+// next nonce above the highest one taken. lower ones still in the window can come
+// in late, anything above is fine too
+fn expected_nonce(nft_program: &NftProgramState) -> u64 {
+    nft_program.nonce.saturating_add(1)
+}
+
+// outbound transfers get the next one of their own counter, callers dont pick it.
+// the inbound window never sees these
+fn next_outbound_nonce(nft_program: &mut NftProgramState) -> Result<u64> {
+    bump(&mut nft_program.outbound_nonce)?;
+    Ok(nft_program.outbound_nonce)
+}
+
+// json line for a rejected inbound nonce. events on a failed tx are dropped with
+// it, a log line still shows up in the simulation and the tx error logs
+fn message_rejected_log(expected_nonce: u64, received_nonce: u64, reason: NonceRejection) -> String {
+    format!(
+        "{{\"event\":\"MessageRejected\",\"expected_nonce\":{},\"received_nonce\":{},\"reason\":\"{}\"}}",
+        expected_nonce,
+        received_nonce,
+        reason.as_str(),
+    )
+}
+
+// inbound messages need a nonce that wasnt taken yet and isnt NONCE_WINDOW or more
+// below the highest one, or more than NONCE_WINDOW above it. a rejection is logged
+// with what we expected before failing. one above the expected nonce moves the
+// window up, the ones in between come back as the gap and can still arrive late
+fn accept_message_nonce(
+    nft_program: &mut NftProgramState,
+    received_nonce: u64,
+) -> Result<Option<NonceGap>> {
+    if received_nonce > nft_program.nonce {
+        let expected_nonce = expected_nonce(nft_program);
+        // one far ahead would push every open nonce out of the window at once
+        if received_nonce - nft_program.nonce > NONCE_WINDOW {
+            let reason = NonceRejection::Ahead;
+            msg!("{}", message_rejected_log(expected_nonce, received_nonce, reason));
+            return err!(NftError::InvalidNonce);
+        }
+        advance_nonce(nft_program, received_nonce);
+        return Ok((received_nonce > expected_nonce).then_some(NonceGap {
+            first_missing: expected_nonce,
+            last_missing: received_nonce - 1,
+            received_nonce,
+        }));
+    }
+
+    let offset = nft_program.nonce - received_nonce;
+    let rejection = if offset >= NONCE_WINDOW {
+        Some(NonceRejection::Stale)
+    } else if nonce_taken(&nft_program.nonce_window, offset) {
+        Some(NonceRejection::Replay)
+    } else {
+        None
+    };
+    if let Some(reason) = rejection {
+        msg!("{}", message_rejected_log(expected_nonce(nft_program), received_nonce, reason));
+        return err!(NftError::InvalidNonce);
+    }
+    mark_nonce(&mut nft_program.nonce_window, offset);
+    Ok(None)
+}
+
+// takes a nonce above the highest one: the window slides up under it and it is
+// marked. accept_message_nonce checks it is above
+fn advance_nonce(nft_program: &mut NftProgramState, nonce: u64) {
+    slide_nonce_window(&mut nft_program.nonce_window, nonce - nft_program.nonce);
+    mark_nonce(&mut nft_program.nonce_window, 0);
+    nft_program.nonce = nonce;
+}
+
+// the window is a 256 bit set over 4 words, lowest word first. bit i stands for
+// nonce `highest - i`
+fn nonce_taken(window: &[u64; 4], offset: u64) -> bool {
+    window[(offset / 64) as usize] & (1 << (offset % 64)) != 0
+}
+
+fn mark_nonce(window: &mut [u64; 4], offset: u64) {
+    window[(offset / 64) as usize] |= 1 << (offset % 64);
+}
+
+// the highest nonce went up by `by`: every bit moves up as far, the ones past the
+// top fall off and the new ones in between start out open
+fn slide_nonce_window(window: &mut [u64; 4], by: u64) {
+    if by >= NONCE_WINDOW {
+        *window = [0; 4];
+        return;
+    }
+    let words = (by / 64) as usize;
+    let bits = by % 64;
+    for i in (0..4).rev() {
+        let low = if i >= words { window[i - words] } else { 0 };
+        let carry = if bits > 0 && i > words { window[i - words - 1] >> (64 - bits) } else { 0 };
+        window[i] = (low << bits) | carry;
+    }
+}
+
+/// what the tss signs for an inbound message: the canonical message hash, then the
+/// nonce and the source chain id big endian, so `abi.encodePacked(hash, uint64(nonce),
+/// uint64(chain_id))` on the evm side. the secp256k1 program hashes it with keccak256
+pub fn tss_message(message_hash: &[u8; 32], nonce: u64, source_chain_id: u64) -> [u8; 48] {
+    let mut message = [0; 48];
+    message[..32].copy_from_slice(message_hash);
+    message[32..40].copy_from_slice(&nonce.to_be_bytes());
+    message[40..].copy_from_slice(&source_chain_id.to_be_bytes());
+    message
+}
+
+// the runtime verifies precompile instructions (secp256k1, ed25519) before the
+// transaction runs, a signature we want is one of those whose data says it signs it
+fn precompile_signs(
+    instructions: &AccountInfo,
+    program_id: Pubkey,
+    signs: impl Fn(&[u8], usize) -> bool,
+) -> bool {
+    let mut index = 0;
+    while let Ok(instruction) = sysvar::instructions::load_instruction_at_checked(index, instructions) {
+        if instruction.program_id == program_id && signs(&instruction.data, index) {
+            return true;
+        }
+        index += 1;
+    }
+    false
+}
+
+// a signature by the tss is a secp256k1 instruction naming the tss address and our message
+fn check_tss_signature(
+    instructions: &AccountInfo,
+    tss_address: &[u8; 20],
+    message: &[u8],
+) -> Result<()> {
+    let signed = precompile_signs(instructions, secp256k1_program::ID, |data, index| {
+        u8::try_from(index).map_or(false, |index| secp256k1_signs(data, index, tss_address, message))
+    });
+    if !signed {
+        msg!("No tss signature over the message");
+        return err!(NftError::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// what the authority signs for execute_signed_admin: the program id, the sha256 of
+/// the borsh encoded action and the admin nonce little endian. the program id keeps
+/// a signature for one deployment from working on another
+pub fn admin_message(action: &AdminAction, nonce: u64) -> [u8; 72] {
+    let mut message = [0; 72];
+    message[..32].copy_from_slice(crate::ID.as_ref());
+    message[32..64].copy_from_slice(&hash(&action.try_to_vec().unwrap()).to_bytes());
+    message[64..].copy_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+fn check_admin_signature(
+    instructions: &AccountInfo,
+    authority: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let signed = precompile_signs(instructions, ed25519_program::ID, |data, index| {
+        u16::try_from(index).map_or(false, |index| ed25519_signs(data, index, authority, message))
+    });
+    if !signed {
+        msg!("No authority signature over the admin action");
+        return err!(NftError::InvalidAdminSignature);
+    }
+    Ok(())
+}
+
+// ed25519 program data: a signature count and a padding byte, then 14 bytes of offsets
+// per signature. an instruction index of u16::MAX means the ed25519 instruction itself
+fn ed25519_signs(data: &[u8], own_index: u16, pubkey: &Pubkey, message: &[u8]) -> bool {
+    let (Some(&count), Some(offsets)) = (data.first(), data.get(2..)) else {
+        return false;
+    };
+    offsets.chunks_exact(14).take(count as usize).any(|offsets| {
+        let at = |i: usize| u16::from_le_bytes([offsets[i], offsets[i + 1]]);
+        let own = |i: usize| at(i) == u16::MAX || at(i) == own_index;
+        let (key_at, message_at, message_len) = (at(4) as usize, at(8) as usize, at(10) as usize);
+        own(6)
+            && own(12)
+            && data.get(key_at..key_at + 32) == Some(pubkey.as_ref())
+            && data.get(message_at..message_at + message_len) == Some(message)
+    })
+}
+
+// the admin setters and execute_signed_admin all end up here
+fn apply_admin_action(nft_program: &mut NftProgramState, action: AdminAction) {
+    match action {
+        AdminAction::SetMintPaused { mint_paused } => {
+            nft_program.mint_paused = mint_paused;
+            emit!(MintPausedSet { mint_paused });
+            msg!("Mint paused set to {}", mint_paused);
+        }
+        AdminAction::SetMintFee { mint_fee_lamports } => {
+            nft_program.mint_fee_lamports = mint_fee_lamports;
+            emit!(MintFeeSet { mint_fee_lamports });
+            msg!("Mint fee set to {} lamports", mint_fee_lamports);
+        }
+    }
+}
+
+// secp256k1 program data: a signature count, 11 bytes of offsets per signature, then
+// whatever the offsets point at. only offsets into this same instruction count, the
+// data of another one isnt what we looked at here
+fn secp256k1_signs(data: &[u8], own_index: u8, eth_address: &[u8; 20], message: &[u8]) -> bool {
+    let Some((&count, offsets)) = data.split_first() else {
+        return false;
+    };
+    offsets.chunks_exact(11).take(count as usize).any(|offsets| {
+        let at = |i: usize| u16::from_le_bytes([offsets[i], offsets[i + 1]]) as usize;
+        let (address_at, message_at, message_len) = (at(3), at(6), at(8));
+        offsets[5] == own_index
+            && offsets[10] == own_index
+            && data.get(address_at..address_at + 20) == Some(&eth_address[..])
+            && data.get(message_at..message_at + message_len) == Some(message)
+    })
+}
+
+// ordered delivery: true when a message is ahead of the expected nonce and has to
+// wait for the ones before it. more than PENDING_NONCE_WINDOW ahead is refused
+fn must_wait(nft_program: &NftProgramState, received_nonce: u64) -> Result<bool> {
+    let expected_nonce = expected_nonce(nft_program);
+    if !nft_program.ordered_delivery || received_nonce <= expected_nonce {
+        return Ok(false);
+    }
+    if received_nonce - expected_nonce >= PENDING_NONCE_WINDOW {
+        msg!("Nonce {} is too far ahead of nonce {}", received_nonce, expected_nonce);
+        return err!(NftError::NonceOutOfWindow);
+    }
+    Ok(true)
+}
+
+// messages come from other chains, the size and every length prefix get checked
+// before anything is allocated
+fn decode_message(data: &[u8]) -> Result<CrossChainMessage> {
+    CrossChainMessage::decode(data).map_err(|error| match error {
+        wire::DecodeError::TooLarge => NftError::MessageTooLarge.into(),
+        wire::DecodeError::Invalid => NftError::InvalidMessage.into(),
+    })
+}
+
+/// id of a crosschain message: keccak256 over the layout version byte followed by
+/// the borsh encoded message, so `keccak256(abi.encodePacked(uint8(MESSAGE_VERSION), message))`
+/// on the evm side. events, receipts and anything else that needs a message id use this
+pub fn canonical_message_hash(data: &[u8]) -> [u8; 32] {
+    wire::canonical_hash_with(data, |parts| keccak::hashv(parts).to_bytes())
+}
+
+// what a transfer out to the chain costs
+fn chain_fee(chain_info: &ChainInfo) -> Result<u64> {
+    let fee = chain_info.flat_fee_lamports
+        .checked_add(chain_info.gas_fee_lamports)
+        .ok_or(NftError::Overflow)?;
+    Ok(fee)
+}
+
+// takes the chain fee from whoever sends the nft out, into the treasury
+fn charge_chain_fee<'info>(
+    chain_info: &ChainInfo,
+    payer: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let fee = chain_fee(chain_info)?;
+    if fee == 0 {
+        return Ok(());
+    }
+    require!(payer.lamports() >= fee, NftError::InsufficientFee);
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            system_program.clone(),
+            anchor_lang::system_program::Transfer {
+                from: payer.clone(),
+                to: treasury.clone(),
+            },
+        ),
+        fee,
+    )
+}
+
+// the token side of a chain fee, from the owner's token account into the treasury's
+// ata of the fee mint. the accounts are only passed for a chain with a token fee
+fn charge_fee_token(chain_info: &ChainInfo, accounts: &FeeTokenAccounts<'_, '_>) -> Result<()> {
+    let Some(fee_token) = chain_info.fee_token else {
+        return Ok(());
+    };
+    if fee_token.amount == 0 {
+        return Ok(());
+    }
+    let (Some(fee_mint), Some(from), Some(fee_vault), Some(token_program)) = (
+        accounts.fee_mint,
+        accounts.owner_fee_token_account,
+        accounts.fee_vault,
+        accounts.fee_token_program,
+    ) else {
+        return err!(NftError::MissingFeeTokenAccounts);
+    };
+    require_keys_eq!(fee_mint.key(), fee_token.mint, NftError::FeeTokenMismatch);
+    require_keys_eq!(from.mint, fee_token.mint, NftError::FeeTokenMismatch);
+    let vault = get_associated_token_address_with_program_id(
+        &accounts.treasury.key(),
+        &fee_token.mint,
+        &token_program.key(),
+    );
+    require_keys_eq!(fee_vault.key(), vault, NftError::FeeTokenMismatch);
+    require!(from.amount >= fee_token.amount, NftError::InsufficientFeeToken);
+
+    transfer_checked(
+        CpiContext::new(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: fee_mint.to_account_info(),
+                to: fee_vault.to_account_info(),
+                authority: accounts.owner.to_account_info(),
+            },
+        ),
+        fee_token.amount,
+        fee_mint.decimals,
+    )
+}
+
+// recipients are left aligned in 32 bytes, anything past the chain's address
+// length has to be zero so a 32 byte value cant be sent to a 20 byte chain
+fn check_destination(chain_info: &ChainInfo, recipient: &[u8; 32]) -> Result<()> {
+    require!(chain_info.enabled, NftError::ChainDisabled);
+    let (address, padding) = recipient.split_at(chain_info.recipient_len as usize);
+    require!(
+        address.iter().any(|byte| *byte != 0) && padding.iter().all(|byte| *byte == 0),
+        NftError::InvalidRecipient
+    );
+    Ok(())
+}
+
+// the sender the gateway reports is a 20 byte zetachain address. the call fails
+// instead of being dropped, so the gateway reverts it on the source side
+fn check_source(chain_info: &ChainInfo, sender: &[u8; 20]) -> Result<()> {
+    require!(
+        chain_info.enabled && chain_info.inbound_enabled,
+        NftError::SourceChainNotAllowed
+    );
+    require!(chain_info.connected[..20] == sender[..], NftError::UnknownSender);
+    Ok(())
+}
+
+// what on_call works out from a message before it touches any account. every
+// business rule is checked here, so a message that fails one changed nothing and
+// can go to a dead letter record instead
+struct InboundPlan {
+    message: CrossChainMessage, // name and symbol normalized, royalty resolved
+    recipient: Pubkey, // who ends up with the nft
+    royalty_bps: u16,
+    royalty_from_message: bool,
+    metadata_uri: String,
+    hash_mismatch: bool,
+    needs_metadata_repair: bool, // got the placeholder uri, see inbound_metadata_uri
+}
+
+fn plan_inbound(
+    nft_program: &NftProgramState,
+    nft_info: &NftInfo,
+    mut message: CrossChainMessage,
+) -> Result<InboundPlan> {
+    match message.message_type {
+        MessageType::Transfer => {
+            // Check the recipient is valid pubkey, pdas are fine too
+            let recipient = validate_recipient(nft_program, message.recipient.into())?;
+
+            // Other chains dont share our name/symbol rules, fill in and cut down first
+            normalize_inbound_metadata(
+                &mut message,
+                &nft_program.default_symbol,
+                nft_program.inbound_string_policy,
+            )?;
+            // A message without a royalty gets the program default, the rest of
+            // the inbound path reads the resolved value back from the message
+            let royalty_from_message = message.royalty_bps.is_some();
+            let royalty_bps = inbound_royalty_bps(
+                message.royalty_bps,
+                nft_program.default_royalty_bps,
+                nft_program.clamp_royalty_bps,
+            )?;
+            message.royalty_bps = Some(royalty_bps);
+            message.royalty_recipient = message
+                .royalty_recipient
+                .or(default_royalty_recipient(nft_program));
+            validate_attributes(&message.attributes)?;
+
+            // Validate the metadata with the same rules as local mints, a bad uri
+            // can get the placeholder instead
+            validate_name_and_symbol(&message.name, &message.symbol)?;
+            let (metadata_uri, needs_metadata_repair) =
+                inbound_metadata_uri(&message.metadata_uri, nft_program, nft_info)?;
+
+            // An existing nft info has to be from an earlier arrival, never a local nft
+            check_inbound_nft_info(nft_info)?;
+
+            // A returning nft has a hash on record, the incoming one has to match
+            let hash_mismatch = check_metadata_hash(
+                nft_info,
+                message.metadata_hash,
+                nft_program.strict_metadata_hash,
+            )?;
+
+            // A first arrival mints like mint_nft does, so the pause and the cap hold
+            // it too. one with nft info here is still counted in total_supply
+            if nft_info.mint == Pubkey::default() {
+                require!(!nft_program.mint_paused, NftError::MintPaused);
+                check_supply_cap(nft_program)?;
+            }
+
+            Ok(InboundPlan {
+                message,
+                recipient,
+                royalty_bps,
+                royalty_from_message,
+                metadata_uri,
+                hash_mismatch,
+                needs_metadata_repair,
+            })
+        }
+        MessageType::Unlock => {
+            // Verify the NFT exists and is locked
+            require!(nft_info.is_locked, NftError::TokenNotLocked);
+
+            // Our own hash stays the reference, a different one coming back gets flagged
+            let hash_mismatch = check_metadata_hash(
+                nft_info,
+                message.metadata_hash,
+                nft_program.strict_metadata_hash,
+            )?;
+
+            Ok(InboundPlan {
+                message,
+                recipient: nft_info.owner,
+                royalty_bps: nft_info.royalty_bps,
+                royalty_from_message: false,
+                metadata_uri: nft_info.metadata_uri.clone(),
+                hash_mismatch,
+                needs_metadata_repair: nft_info.needs_metadata_repair,
+            })
+        }
+        // deliver_inbound records batch roots before planning, nothing to deliver
+        MessageType::BatchRoot => err!(NftError::InvalidMessage),
+    }
+}
+
+// on_call and drain_pending once the nonce is taken. a message that breaks a
+// business rule changed nothing so far, with a dead letter record passed along it
+// is kept there and the call still succeeds, the gateway would only revert a
+// message retrying cant fix. with a failed inbound record instead only its hash and
+// the reason are kept. a paused source chain or a blocked recipient is kept the
+// same way. in claim mode a transfer that passed is kept as a pending claim
+fn deliver_inbound<'info>(
+    ctx: Context<'_, '_, 'info, 'info, OnCall<'info>>,
+    sender: [u8; 20],
+    data: &[u8],
+    message: CrossChainMessage,
+    message_hash: [u8; 32],
+) -> Result<()> {
+    let nonce = message.nonce;
+    let paused = ctx.accounts.chain_info.inbound_paused;
+    // a batch root only gets recorded, the transfers in it come with claim_inbound.
+    // retrying only takes single messages, so a paused chain's root just fails
+    if message.message_type == MessageType::BatchRoot {
+        require!(!paused, NftError::SourceChainPaused);
+        return record_inbound_batch(ctx, sender, message, message_hash);
+    }
+
+    // a paused chain's message isnt looked at, it waits as a dead letter
+    let planned = if paused {
+        err!(NftError::SourceChainPaused)
+    } else {
+        plan_inbound(&ctx.accounts.nft_program, &ctx.accounts.nft_info, message)
+    };
+    // a blocked recipient is kept the same way, to retry once the block is lifted
+    let planned = match planned {
+        Ok(plan) if recipient_blocked(&ctx.accounts.blocked_recipient, plan.recipient)? => {
+            err!(NftError::RecipientBlocked)
+        }
+        planned => planned,
+    };
+    let plan = match planned {
+        Ok(plan) => plan,
+        Err(error) if ctx.accounts.dead_letter.is_some() => {
+            let reason = DeadLetterReason::from_error(&error);
+            record_dead_letter(
+                ctx.accounts,
+                message_hash,
+                ctx.accounts.chain_info.chain_id,
+                sender,
+                reason,
+                data,
+            )?;
+            emit_cpi!(MessageDeadLettered {
+                message_hash,
+                source_chain_id: ctx.accounts.chain_info.chain_id,
+                sender,
+                reason,
+                nonce,
+                slot: Clock::get()?.slot,
+            });
+            msg!("Message {:?} dead lettered: {:?}", message_hash, reason);
+            return Ok(());
+        }
+        Err(error) if ctx.accounts.failed_inbound.is_some() => {
+            let reason = DeadLetterReason::from_error(&error);
+            record_failed_inbound(ctx.accounts, nonce, message_hash, sender, reason)?;
+            emit_cpi!(InboundFailed {
+                nonce,
+                message_hash,
+                source_chain_id: ctx.accounts.chain_info.chain_id,
+                reason,
+                slot: Clock::get()?.slot,
+            });
+            msg!("Message with nonce {} failed: {:?}", nonce, reason);
+            return Ok(());
+        }
+        Err(error) => return Err(error),
+    };
+
+    // the recipient mints it with claim_nft, the relayer only pays for the record
+    let nft_program = &ctx.accounts.nft_program;
+    if plan.message.message_type == MessageType::Transfer
+        && nft_program.claim_mode
+        && !nft_program.compressed_mode
+    {
+        // a blocked mint isnt taken in at all, not even as a claim
+        require!(!is_blocked(&ctx.accounts.blocked_mint), NftError::MintBlocked);
+        check_bridged_mint(ctx.accounts.mint.key(), &plan.message.mint)?;
+        record_pending_claim(ctx.accounts, nonce, plan.recipient, message_hash, sender, data)?;
+        emit_cpi!(ClaimRecorded {
+            nonce,
+            mint: ctx.accounts.mint.key(),
+            recipient: plan.recipient,
+            message_hash,
+            source_chain_id: ctx.accounts.chain_info.chain_id,
+            slot: Clock::get()?.slot,
+        });
+        msg!("Transfer with nonce {} waits for {} to claim it", nonce, plan.recipient);
+        return Ok(());
+    }
+
+    receive_message(ctx, sender, message_hash, plan)
+}
+
+// mints or unlocks a message that passed plan_inbound. deliver_inbound gets here for
+// a fresh one, retry_dead_letter, retry_inbound, claim_inbound and claim_nft for a
+// stored one
+fn receive_message<'info>(
+    ctx: Context<'_, '_, 'info, 'info, OnCall<'info>>,
+    sender: [u8; 20],
+    message_hash: [u8; 32],
+    plan: InboundPlan,
+) -> Result<()> {
+    let InboundPlan {
+        message: cross_chain_message,
+        recipient: recipient_pubkey,
+        royalty_bps,
+        royalty_from_message,
+        metadata_uri,
+        hash_mismatch,
+        needs_metadata_repair,
+    } = plan;
+    // a blocked mint isnt taken in at all, not even as a dead letter
+    require!(!is_blocked(&ctx.accounts.blocked_mint), NftError::MintBlocked);
+    let nft_program = &mut ctx.accounts.nft_program;
+
+    // Emitted once the message went through
+    let received = CrossChainReceived {
+        source_chain_id: ctx.accounts.chain_info.chain_id,
+        sender,
+        message_type: cross_chain_message.message_type,
+        mint: ctx.accounts.mint.key(),
+        recipient: recipient_pubkey,
+        nonce: cross_chain_message.nonce,
+        message_hash,
+    };
+    
+    match cross_chain_message.message_type {
+        // plan_inbound turns batch roots away
+        MessageType::BatchRoot => return err!(NftError::InvalidMessage),
+        MessageType::Transfer => {
+            // Handle incoming NFT transfer from ZetaChain, plan_inbound checked
+            // the recipient and the metadata already
+            msg!("Handling cross-chain NFT transfer from ZetaChain to {}", recipient_pubkey);
+            let mint_bump = check_bridged_mint(ctx.accounts.mint.key(), &cross_chain_message.mint)?;
+            // the recipient ata comes typed after the on_call accounts, compressed mode
+            // has none
+            let transfer_accounts = inbound_accounts::<InboundTransfer>(
+                ctx.program_id,
+                ctx.remaining_accounts,
+                (!nft_program.compressed_mode).then(|| InboundKeys {
+                    nft_program: nft_program.key(),
+                    mint: ctx.accounts.mint.key(),
+                    owner: recipient_pubkey,
+                    token_program: ctx.accounts.token_program.key(),
+                }),
+            )?;
+
+            // an existing mint with its token out means this nft got delivered already,
+            // the same transfer again only reconciles the owner
+            let new_mint = ctx.accounts.mint.data_is_empty();
+            if !nft_program.compressed_mode
+                && !new_mint
+                && check_reused_mint(
+                    &ctx.accounts.mint,
+                    &ctx.accounts.token_program.key(),
+                    &nft_program.key(),
+                )?
+            {
+                // one that went back out is locked here for the bridge, it returns
+                // with an unlock and not a transfer
+                require!(
+                    ctx.accounts.nft_info.mint == ctx.accounts.mint.key()
+                        && !ctx.accounts.nft_info.is_locked,
+                    NftError::MintHasSupply
+                );
+                upgrade_nft_info(
+                    &mut ctx.accounts.nft_info,
+                    &ctx.accounts.payer.to_account_info(),
+                    &ctx.accounts.system_program.to_account_info(),
+                )?;
+                let nft_info = &mut ctx.accounts.nft_info;
+                let previous_owner = nft_info.owner;
+                nft_info.owner = recipient_pubkey;
+                msg!("NFT {} was already delivered, owner set to {}",
+                    nft_info.mint, recipient_pubkey);
+                emit_cpi!(AlreadyDelivered {
+                    mint: nft_info.mint,
+                    origin_mint: cross_chain_message.mint,
+                    previous_owner,
+                    owner: recipient_pubkey,
+                    nonce: cross_chain_message.nonce,
+                });
+                emit_cpi!(received);
+                return Ok(());
+            }
+
+            // The first arrival gets the next index, a returning nft keeps its own
+            let index = index_inbound_mint(
+                &ctx.accounts.nft_info,
+                &mut ctx.accounts.mint_index,
+                ctx.bumps.mint_index,
+                nft_program,
+                ctx.accounts.mint.key(),
+            )?;
+
+            emit_cpi!(NftReceived {
+                mint: ctx.accounts.mint.key(),
+                origin_mint: cross_chain_message.mint,
+                recipient: recipient_pubkey,
+                royalty_bps,
+                royalty_from_message,
+            });
+
+            // Compressed mode skips the mint, ata and metadata and adds a leaf instead
+            if nft_program.compressed_mode {
+                compressed::mint_inbound(
+                    ctx.accounts,
+                    ctx.bumps.nft_info,
+                    &cross_chain_message,
+                    recipient_pubkey,
+                    metadata_uri,
+                    hash_mismatch,
+                    index,
+                )?;
+                emit_cpi!(received);
+                return Ok(());
+            }
+
+            let metadata_mode = nft_program.metadata_mode;
+
+            // Accounts from before a max uri length bump or an older layout need to grow first
+            upgrade_nft_info(
+                &mut ctx.accounts.nft_info,
+                &ctx.accounts.payer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )?;
+            ensure_nft_info_space(
+                &ctx.accounts.nft_info.to_account_info(),
+                &ctx.accounts.payer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                NftInfo::space(nft_program.max_uri_len),
+            )?;
+
+            // Initialize or update NFT info account to track the NFT, all of it
+            // before the mint, token and metadata cpis below
+            let nft_info = &mut ctx.accounts.nft_info;
+            nft_info.mint = ctx.accounts.mint.key();
+            nft_info.owner = recipient_pubkey;
+            nft_info.metadata_uri = metadata_uri.clone();
+            nft_info.name = cross_chain_message.name.clone();
+            nft_info.symbol = cross_chain_message.symbol.clone();
+            nft_info.is_locked = false;
+            nft_info.cross_chain_recipient = [0; 32]; // Not applicable for incoming transfers
+            nft_info.token_standard = NftStandard::NonFungible;
+            nft_info.compressed = false;
+            nft_info.asset_id = Pubkey::default();
+            nft_info.master_mint = cross_chain_message.master_mint;
+            nft_info.edition = cross_chain_message.edition;
+            nft_info.metadata_hash = cross_chain_message.metadata_hash;
+            nft_info.hash_mismatch = hash_mismatch;
+            nft_info.minted_to = Pubkey::default(); // bridged in, not a local mint
+            nft_info.bridged = true;
+            nft_info.index = index;
+            nft_info.royalty_bps = royalty_bps;
+            nft_info.royalty_recipient = cross_chain_message.royalty_recipient;
+            nft_info.attributes = cross_chain_message.attributes.clone();
+            nft_info.bump = ctx.bumps.nft_info;
+            nft_info.version = NFT_INFO_VERSION;
+            // in lazy mode metaplex metadata waits for materialize_metadata
+            nft_info.metadata_created = metadata_mode == MetadataMode::TokenExtension
+                || !nft_program.lazy_metadata
+                || !metaplex_account(&ctx.accounts.metadata)?.data_is_empty();
+            nft_info.needs_metadata_repair = needs_metadata_repair;
+            nft_info.metadata_immutable = false; // counted once its metadata is created
+            nft_info.external = false;
+            nft_info.lock_nonce = 0;
+            if needs_metadata_repair {
+                msg!("Invalid metadata uri, {} gets the placeholder until repaired", nft_info.mint);
+            }
+            
+            // Update program state
+            bump(&mut nft_program.total_supply)?;
+            
+            // Create the mint the first time this nft arrives, the metadata
+            // pointer for extension mode has to go on before initialization
+            if new_mint {
+                create_nft_mint(
+                    &ctx.accounts.mint.to_account_info(),
+                    &nft_program.to_account_info(),
+                    &ctx.accounts.payer.to_account_info(),
+                    &ctx.accounts.token_program.to_account_info(),
+                    &ctx.accounts.system_program.to_account_info(),
+                    metadata_mode,
+                    &[&[
+                        seeds::NFT_MINT,
+                        cross_chain_message.mint.as_ref(),
+                        &[mint_bump]
+                    ]],
+                )?;
+            }
+            // otherwise it arrives again after a burn and reuses the mint, one without
+            // a master edition yet, checked above
+            
+            // Create associated token account for recipient if it doesn't exist,
+            // idempotent create doesnt care if the recipient is off curve
+            let recipient = ctx.accounts.nft_owner
+                .as_ref()
+                .ok_or(NftError::InvalidRecipient)?
+                .to_account_info();
+            require_keys_eq!(recipient.key(), recipient_pubkey, NftError::InvalidRecipient);
+            let recipient_token_account = transfer_accounts
+                .ok_or(NftError::InboundAccountsMismatch)?
+                .recipient_token_account
+                .to_account_info();
+            // a returning nft can find the recipient ata under a compliance hold
+            check_account_not_frozen(&recipient_token_account)?;
+            anchor_spl::associated_token::create_idempotent(
+                CpiContext::new(
+                    ctx.accounts.associated_token_program.to_account_info(),
+                    anchor_spl::associated_token::Create {
+                        payer: ctx.accounts.payer.to_account_info(),
+                        associated_token: recipient_token_account.clone(),
+                        authority: recipient,
+                        mint: ctx.accounts.mint.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                    }
+                )
+            )?;
+            
+            // Mint the token to the recipient's token account
+            mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: recipient_token_account,
+                        authority: nft_program.to_account_info(),
+                    },
+                    &[&[
+                        seeds::NFT_PROGRAM,
+                        &[nft_program.bump]
+                    ]]
+                ),
+                1 // NFTs have supply of 1
+            )?;
+            
+            // Create metadata and a master edition for the NFT if it doesn't exist,
+            // fresh metaplex metadata also joins the bridged collection, or the
+            // program one when there is no bridged collection. in lazy mode both
+            // wait for materialize_metadata instead
+            if metadata_mode == MetadataMode::TokenExtension {
+                if new_mint {
+                    init_extension_metadata(
+                        &ctx.accounts.mint.to_account_info(),
+                        &nft_program.to_account_info(),
+                        &ctx.accounts.payer.to_account_info(),
+                        &ctx.accounts.token_program.to_account_info(),
+                        &ctx.accounts.system_program.to_account_info(),
+                        cross_chain_message.name.clone(),
+                        cross_chain_message.symbol.clone(),
+                        display_uri(nft_program, &metadata_uri),
+                        &[&[seeds::NFT_PROGRAM, &[nft_program.bump]]],
+                    )?;
+                }
+            } else if metaplex_account(&ctx.accounts.metadata)?.data_is_empty()
+                && !nft_program.lazy_metadata
+            {
+                let is_mutable = nft_program.inbound_metadata_mutable || needs_metadata_repair;
+                let (verify_now, joins_bridged) = create_inbound_metadata(
+                    InboundMetadata {
+                        nft_program,
+                        mint: ctx.accounts.mint.to_account_info(),
+                        metadata: metaplex_account(&ctx.accounts.metadata)?,
+                        master_edition: metaplex_account(&ctx.accounts.master_edition)?,
+                        collection: &mut ctx.accounts.collection,
+                        collection_mint: &ctx.accounts.collection_mint,
+                        collection_metadata: &ctx.accounts.collection_metadata,
+                        collection_master_edition: &ctx.accounts.collection_master_edition,
+                        bridged_collection: &mut ctx.accounts.bridged_collection,
+                        bridged_collection_mint: &ctx.accounts.bridged_collection_mint,
+                        payer: ctx.accounts.payer.to_account_info(),
+                        rent: ctx.accounts.rent.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        sysvar_instructions: ctx.accounts.instruction_sysvar_account.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                        token_metadata_program: metaplex_account(&ctx.accounts.token_metadata_program)?,
+                    },
+                    cross_chain_message.name.clone(),
+                    cross_chain_message.symbol.clone(),
+                    metadata_uri.clone(),
+                    royalty_bps,
+                    cross_chain_message.royalty_recipient,
+                    is_mutable,
+                )?;
+                let nft_info = &mut ctx.accounts.nft_info;
+                // an nft that comes back keeps the membership it already has
+                if verify_now {
+                    nft_info.in_collection = true;
+                    nft_info.bridged_collection = joins_bridged;
+                }
+                if !is_mutable {
+                    record_immutable_metadata(nft_program, nft_info)?;
+                }
+            }
+            
+            msg!("NFT minted from cross-chain transfer: {} - {} to {}",
+                cross_chain_message.name, metadata_uri, recipient_pubkey);
+        }
+        MessageType::Unlock => {
+            // Handle unlock for return transfers
+            // This would be when an NFT is being sent back from ZetaChain to Solana
+            msg!("Handling NFT unlock for mint {}", cross_chain_message.mint);
+            // the message names the mint the nft went out with, bridged or local
+            require_keys_eq!(
+                ctx.accounts.mint.key(),
+                cross_chain_message.mint,
+                NftError::MintMismatch
+            );
+            
+            // For Unlock, we need to transfer an existing NFT back to the owner
+            // The NFT info account should already exist
+            upgrade_nft_info(
+                &mut ctx.accounts.nft_info,
+                &ctx.accounts.payer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )?;
+            let nft_info = &mut ctx.accounts.nft_info;
+            // the seeds already tie nft info to the mint account, spelled out so
+            // the unlock never runs on another nft's record
+            require_keys_eq!(
+                nft_info.mint,
+                cross_chain_message.mint,
+                NftError::NftInfoMismatch
+            );
+            
+            // plan_inbound made sure the nft is locked, a different hash got flagged
+            nft_info.hash_mismatch = hash_mismatch;
+
+            // the escrow and the owner ata come typed after the on_call accounts, a
+            // compressed nft has neither
+            let unlock_accounts = inbound_accounts::<InboundUnlock>(
+                ctx.program_id,
+                ctx.remaining_accounts,
+                (!nft_info.compressed).then(|| InboundKeys {
+                    nft_program: nft_program.key(),
+                    mint: ctx.accounts.mint.key(),
+                    owner: nft_info.owner,
+                    token_program: ctx.accounts.token_program.key(),
+                }),
+            )?;
+
+            // A compressed nft was burned on the way out, so it comes back as a new leaf
+            if nft_info.compressed {
+                let unlocked = compressed::return_to_owner(ctx.accounts, cross_chain_message.nonce)?;
+                emit_cpi!(unlocked);
+                emit_cpi!(received);
+                return Ok(());
+            }
+            
+            // the escrow is the program's ata for this mint, the nft sits there while
+            // locked. it goes back to the owner's ata and no one else's, anchor checked both
+            let InboundUnlock { program_token_account: escrow, owner_token_account } =
+                unlock_accounts.ok_or(NftError::InboundAccountsMismatch)?;
+            if nft_info.token_standard != NftStandard::ProgrammableNonFungible {
+                check_not_frozen(escrow.key(), &escrow)?;
+                check_account_not_frozen(&owner_token_account)?;
+            }
+            let program_token_account = escrow.to_account_info();
+            let owner_token_account = owner_token_account.to_account_info();
+
+            // Update NFT state to unlocked before the escrow and the nft move
+            nft_info.is_locked = false;
+            nft_info.cross_chain_recipient = [0; 32]; // Clear the cross-chain recipient
+            let unlocked = release_lock(
+                nft_program,
+                nft_info,
+                UnlockReason::Returned,
+                cross_chain_message.nonce,
+            )?;
+
+            // Create owner token account if it doesn't exist
+            if owner_token_account.data_is_empty() {
+                let owner = pnft_account(&ctx.accounts.nft_owner)?;
+                require_keys_eq!(owner.key(), nft_info.owner, NftError::InvalidRecipient);
+                anchor_spl::associated_token::create(
+                    CpiContext::new(
+                        ctx.accounts.associated_token_program.to_account_info(),
+                        anchor_spl::associated_token::Create {
+                            payer: ctx.accounts.payer.to_account_info(),
+                            associated_token: owner_token_account.clone(),
+                            authority: owner,
+                            mint: ctx.accounts.mint.to_account_info(),
+                            system_program: ctx.accounts.system_program.to_account_info(),
+                            token_program: ctx.accounts.token_program.to_account_info(),
+                        }
+                    )
+                )?;
+            }
+            
+            // Transfer the NFT back to the owner
+            if nft_info.token_standard == NftStandard::ProgrammableNonFungible {
+                let nft_owner = pnft_account(&ctx.accounts.nft_owner)?;
+                require_keys_eq!(nft_owner.key(), nft_info.owner, NftError::Unauthorized);
+                transfer_pnft(
+                    &PnftTransfer {
+                        token: program_token_account,
+                        token_owner: nft_program.to_account_info(),
+                        destination_token: owner_token_account,
+                        destination_owner: nft_owner,
+                        mint: ctx.accounts.mint.to_account_info(),
+                        metadata: pnft_account(&ctx.accounts.metadata)?,
+                        edition: pnft_account(&ctx.accounts.master_edition)?,
+                        token_record: pnft_account(&ctx.accounts.program_token_record)?,
+                        destination_token_record: pnft_account(&ctx.accounts.owner_token_record)?,
+                        authority: nft_program.to_account_info(),
+                        payer: ctx.accounts.payer.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        sysvar_instructions: ctx.accounts.instruction_sysvar_account.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                        associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+                        token_metadata_program: pnft_account(&ctx.accounts.token_metadata_program)?,
+                    },
+                    &[&[seeds::NFT_PROGRAM, &[nft_program.bump]]],
+                )?;
+            } else {
+                transfer_checked(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        TransferChecked {
+                            from: program_token_account,
+                            mint: ctx.accounts.mint.to_account_info(),
+                            to: owner_token_account,
+                            authority: nft_program.to_account_info(),
+                        },
+                        &[&[
+                            seeds::NFT_PROGRAM,
+                            &[nft_program.bump]
+                        ]]
+                    ),
+                    1,
+                    0, // our mints always have 0 decimals
+                )?;
+            }
+            
+            emit_cpi!(unlocked);
+            
+            msg!("NFT unlocked and transferred back to owner for mint {}", nft_info.mint);
+        }
+    }
+
+    emit_cpi!(received);
+    Ok(())
+}
+
+// writes the dead letter record for a message plan_inbound turned down. the pda is
+// [b"dead-letter", message hash], the relayer pays for it. a message hash only comes
+// by once (the nonce is taken), so the record is always new
+fn record_dead_letter(
+    accounts: &OnCall,
+    message_hash: [u8; 32],
+    source_chain_id: u64,
+    sender: [u8; 20],
+    reason: DeadLetterReason,
+    payload: &[u8],
+) -> Result<()> {
+    let dead_letter = accounts.dead_letter
+        .as_ref()
+        .ok_or(NftError::InvalidDeadLetter)?
+        .to_account_info();
+    let (address, bump) = seeds::find_dead_letter(&message_hash);
+    require_keys_eq!(dead_letter.key(), address, NftError::InvalidDeadLetter);
+
+    let space = DeadLetter::space(payload.len());
+    anchor_lang::system_program::create_account(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            anchor_lang::system_program::CreateAccount {
+                from: accounts.payer.to_account_info(),
+                to: dead_letter.clone(),
+            },
+        ).with_signer(&[&[seeds::DEAD_LETTER, message_hash.as_ref(), &[bump]]]),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID,
+    )?;
+
+    let record = DeadLetter {
+        message_hash,
+        source_chain_id,
+        sender,
+        reason,
+        slot: Clock::get()?.slot,
+        bump,
+        payload: payload.to_vec(),
+    };
+    record.try_serialize(&mut &mut dead_letter.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+// reads the record retry_dead_letter was given, it has to be ours and for the hash
+fn load_dead_letter(dead_letter: &AccountInfo, message_hash: [u8; 32]) -> Result<DeadLetter> {
+    require_keys_eq!(*dead_letter.owner, crate::ID, NftError::InvalidDeadLetter);
+    let record = DeadLetter::try_deserialize(&mut &dead_letter.try_borrow_data()?[..])?;
+    require!(record.message_hash == message_hash, NftError::InvalidDeadLetter);
+    Ok(record)
+}
+
+// writes the failed inbound record for a message plan_inbound turned down, without
+// the payload. the pda is [b"failed-inbound", nonce], the relayer pays for it. the
+// nonce is taken by then, so the record is always new
+fn record_failed_inbound(
+    accounts: &OnCall,
+    nonce: u64,
+    message_hash: [u8; 32],
+    sender: [u8; 20],
+    reason: DeadLetterReason,
+) -> Result<()> {
+    let failed_inbound = accounts.failed_inbound
+        .as_ref()
+        .ok_or(NftError::InvalidFailedInbound)?
+        .to_account_info();
+    let (address, bump) = seeds::find_failed_inbound(nonce);
+    require_keys_eq!(failed_inbound.key(), address, NftError::InvalidFailedInbound);
+
+    let space = 8 + FailedInbound::INIT_SPACE;
+    anchor_lang::system_program::create_account(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            anchor_lang::system_program::CreateAccount {
+                from: accounts.payer.to_account_info(),
+                to: failed_inbound.clone(),
+            },
+        ).with_signer(&[&[seeds::FAILED_INBOUND, nonce.to_le_bytes().as_ref(), &[bump]]]),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID,
+    )?;
+
+    let record = FailedInbound {
+        nonce,
+        message_hash,
+        source_chain_id: accounts.chain_info.chain_id,
+        sender,
+        reason,
+        slot: Clock::get()?.slot,
+        bump,
+    };
+    record.try_serialize(&mut &mut failed_inbound.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+// reads the record retry_inbound was given, it has to be ours and for the nonce
+fn load_failed_inbound(failed_inbound: &AccountInfo, nonce: u64) -> Result<FailedInbound> {
+    require_keys_eq!(*failed_inbound.owner, crate::ID, NftError::InvalidFailedInbound);
+    let record = FailedInbound::try_deserialize(&mut &failed_inbound.try_borrow_data()?[..])?;
+    require!(record.nonce == nonce, NftError::InvalidFailedInbound);
+    Ok(record)
+}
+
+// writes the pending record for a message ordered delivery holds back. the pda is
+// [b"pending-nonce", nonce], the relayer pays for it. the same nonce delivered
+// again while it waits fails, the account is already there
+fn record_pending_nonce(
+    accounts: &OnCall,
+    nonce: u64,
+    message_hash: [u8; 32],
+    sender: [u8; 20],
+) -> Result<()> {
+    // anchor made the index slot already, it would be taken out of turn and the
+    // message before this one couldnt get it
+    require!(accounts.mint_index.is_none(), NftError::MintIndexWhilePending);
+    let pending_nonce = accounts.pending_nonce
+        .as_ref()
+        .ok_or(NftError::InvalidPendingNonce)?
+        .to_account_info();
+    let (address, bump) = seeds::find_pending_nonce(nonce);
+    require_keys_eq!(pending_nonce.key(), address, NftError::InvalidPendingNonce);
+
+    let space = 8 + PendingNonce::INIT_SPACE;
+    anchor_lang::system_program::create_account(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            anchor_lang::system_program::CreateAccount {
+                from: accounts.payer.to_account_info(),
+                to: pending_nonce.clone(),
+            },
+        ).with_signer(&[&[seeds::PENDING_NONCE, nonce.to_le_bytes().as_ref(), &[bump]]]),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID,
+    )?;
+
+    let record = PendingNonce {
+        nonce,
+        message_hash,
+        source_chain_id: accounts.chain_info.chain_id,
+        sender,
+        slot: Clock::get()?.slot,
+        bump,
+    };
+    record.try_serialize(&mut &mut pending_nonce.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+// closes a record written with create_account, the rent goes to the destination.
+// the same steps as anchor's close for typed accounts
+fn close_record<'info>(record: AccountInfo<'info>, destination: AccountInfo<'info>) -> Result<()> {
+    let lamports = destination.lamports()
+        .checked_add(record.lamports())
+        .ok_or(NftError::CounterOverflow)?;
+    **destination.lamports.borrow_mut() = lamports;
+    **record.lamports.borrow_mut() = 0;
+
+    record.assign(&anchor_lang::system_program::ID);
+    record.realloc(0, false).map_err(Into::into)
+}
+
+// reads the record drain_pending was given, it has to be ours and for the message
+fn load_pending_nonce(pending_nonce: &AccountInfo, message_hash: [u8; 32]) -> Result<PendingNonce> {
+    require_keys_eq!(*pending_nonce.owner, crate::ID, NftError::InvalidPendingNonce);
+    let record = PendingNonce::try_deserialize(&mut &pending_nonce.try_borrow_data()?[..])?;
+    require!(record.message_hash == message_hash, NftError::InvalidPendingNonce);
+    Ok(record)
+}
+
+// writes the pending claim for a transfer claim mode keeps for its recipient. the
+// pda is [b"pending-claim", nonce], the relayer pays for it. like a held back
+// message it cant take an index slot, and the blank nft info anchor made for the
+// mint stays behind until the claim fills it in
+fn record_pending_claim(
+    accounts: &OnCall,
+    nonce: u64,
+    recipient: Pubkey,
+    message_hash: [u8; 32],
+    sender: [u8; 20],
+    payload: &[u8],
+) -> Result<()> {
+    require!(accounts.mint_index.is_none(), NftError::MintIndexBeforeClaim);
+    let pending_claim = accounts.pending_claim
+        .as_ref()
+        .ok_or(NftError::InvalidPendingClaim)?
+        .to_account_info();
+    let (address, bump) = seeds::find_pending_claim(nonce);
+    require_keys_eq!(pending_claim.key(), address, NftError::InvalidPendingClaim);
+
+    let space = PendingClaim::space(payload.len());
+    anchor_lang::system_program::create_account(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            anchor_lang::system_program::CreateAccount {
+                from: accounts.payer.to_account_info(),
+                to: pending_claim.clone(),
+            },
+        ).with_signer(&[&[seeds::PENDING_CLAIM, nonce.to_le_bytes().as_ref(), &[bump]]]),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID,
+    )?;
+
+    let record = PendingClaim {
+        nonce,
+        recipient,
+        mint: accounts.mint.key(),
+        message_hash,
+        source_chain_id: accounts.chain_info.chain_id,
+        sender,
+        slot: Clock::get()?.slot,
+        bump,
+        payload: payload.to_vec(),
+    };
+    record.try_serialize(&mut &mut pending_claim.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+// reads the record claim_nft was given, it has to be ours and for the nonce. a
+// claimed one is closed, so claiming it again ends up here too
+fn load_pending_claim(pending_claim: &AccountInfo, nonce: u64) -> Result<PendingClaim> {
+    require_keys_eq!(*pending_claim.owner, crate::ID, NftError::InvalidPendingClaim);
+    let record = PendingClaim::try_deserialize(&mut &pending_claim.try_borrow_data()?[..])?;
+    require!(record.nonce == nonce, NftError::InvalidPendingClaim);
+    Ok(record)
+}
+
+// writes the record of a batch root, [b"inbound-batch", batch id]. the relayer pays,
+// the account has a claimed bit per leaf. like a held back message the root cant
+// take an index slot, and the blank nft info anchor made for the mint stays behind
+fn record_inbound_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, OnCall<'info>>,
+    sender: [u8; 20],
+    message: CrossChainMessage,
+    message_hash: [u8; 32],
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let batch_id = message.mint;
+    let leaf_count = message.edition;
+    let root = message.metadata_hash.ok_or(NftError::InvalidInboundBatch)?;
+    require!(
+        (1..=MAX_BATCH_LEAVES).contains(&leaf_count) && accounts.mint_index.is_none(),
+        NftError::InvalidInboundBatch
+    );
+    let inbound_batch = accounts.inbound_batch
+        .as_ref()
+        .ok_or(NftError::InvalidInboundBatch)?
+        .to_account_info();
+    let (address, bump) = seeds::find_inbound_batch(&batch_id);
+    require_keys_eq!(inbound_batch.key(), address, NftError::InvalidInboundBatch);
+
+    let space = InboundBatch::space(leaf_count);
+    anchor_lang::system_program::create_account(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            anchor_lang::system_program::CreateAccount {
+                from: accounts.payer.to_account_info(),
+                to: inbound_batch.clone(),
+            },
+        ).with_signer(&[&[seeds::INBOUND_BATCH, batch_id.as_ref(), &[bump]]]),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID,
+    )?;
+
+    let record = InboundBatch {
+        batch_id,
+        root,
+        leaf_count,
+        claimed_count: 0,
+        nonce: message.nonce,
+        message_hash,
+        source_chain_id: accounts.chain_info.chain_id,
+        sender,
+        slot: Clock::get()?.slot,
+        bump,
+        claimed: vec![0; leaf_count.div_ceil(8) as usize],
+    };
+    record.try_serialize(&mut &mut inbound_batch.try_borrow_mut_data()?[..])?;
+
+    emit_cpi!(InboundBatchReceived {
+        batch_id,
+        root,
+        leaf_count,
+        nonce: message.nonce,
+        message_hash,
+        source_chain_id: accounts.chain_info.chain_id,
+        slot: record.slot,
+    });
+    msg!("Batch {} with {} leaves received", batch_id, leaf_count);
+    Ok(())
+}
+
+// reads the record claim_inbound was given, it has to be ours
+fn load_inbound_batch(inbound_batch: &AccountInfo) -> Result<InboundBatch> {
+    require_keys_eq!(*inbound_batch.owner, crate::ID, NftError::InvalidInboundBatch);
+    InboundBatch::try_deserialize(&mut &inbound_batch.try_borrow_data()?[..])
+}
+
+// the leaf at leaf_index is this message and the proof leads from it to the root. a
+// proof is exactly as long as the tree is deep, so it cant start from an inner node
+fn verify_batch_leaf(batch: &InboundBatch, leaf_index: u64, leaf: &[u8], proof: &[[u8; 32]]) -> bool {
+    let hashv = |parts: &[&[u8]]| keccak::hashv(parts).to_bytes();
+    leaf_index < batch.leaf_count
+        && proof.len() == wire::batch_depth(batch.leaf_count)
+        && wire::batch_root_from_proof_with(
+            leaf_index,
+            wire::batch_leaf_hash_with(leaf_index, leaf, hashv),
+            proof,
+            hashv,
+        ) == batch.root
+}
+
+// sets the claimed bit of the leaf, a second claim of it fails
+fn claim_batch_leaf(batch: &mut InboundBatch, leaf_index: u64) -> Result<()> {
+    let (byte, bit) = ((leaf_index / 8) as usize, 1 << (leaf_index % 8));
+    require!(batch.claimed[byte] & bit == 0, NftError::LeafAlreadyClaimed);
+    batch.claimed[byte] |= bit;
+    bump(&mut batch.claimed_count)
+}
+
+// an nft transferred in gets the pda of its origin mint as mint, returns the bump to sign with
+fn check_bridged_mint(mint: Pubkey, origin_mint: &Pubkey) -> Result<u8> {
+    let (expected, bump) = seeds::find_bridged_mint(origin_mint);
+    require_keys_eq!(mint, expected, NftError::MintMismatch);
+    Ok(bump)
+}
+
+// mint authority of an nft we made: the program, or the (master) edition pda once
+// token metadata took it over for a master edition, a print or a pnft
+fn is_nft_mint_authority(mint_authority: COption<Pubkey>, mint: Pubkey, nft_program: Pubkey) -> bool {
+    match mint_authority {
+        COption::Some(authority) => {
+            authority == nft_program || authority == MasterEdition::find_pda(&mint).0
+        }
+        COption::None => false,
+    }
+}
+
+// a bridged mint that already exists has to be one create_nft_mint made for us,
+// true when its token is out. more than the one token cant come from us. once the
+// master edition has the mint authority a burned one cant be minted again
+fn check_reused_mint(mint: &AccountInfo, token_program: &Pubkey, nft_program: &Pubkey) -> Result<bool> {
+    require_keys_eq!(*mint.owner, *token_program, NftError::MintMismatch);
+    let state = Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?;
+    require!(
+        is_nft_mint_authority(state.mint_authority, mint.key(), *nft_program),
+        NftError::MintMismatch
+    );
+    require!(state.supply <= 1, NftError::MintHasSupply);
+    require!(
+        state.supply == 1 || state.mint_authority == COption::Some(*nft_program),
+        NftError::MintRetired
+    );
+    Ok(state.supply == 1)
+}
+
+// a typed context on_call parses out of its remaining accounts
+trait InboundAccounts {
+    // accounts it takes, a call with more or fewer was built for another message type
+    const LEN: usize;
+}
+
+impl InboundAccounts for InboundTransfer<'_> {
+    const LEN: usize = 1;
+}
+
+impl InboundAccounts for InboundUnlock<'_> {
+    const LEN: usize = 2;
+}
+
+// on_call takes the token accounts of the message type it delivers after its own:
+// InboundTransfer for a transfer, InboundUnlock for an unlock, none in compressed mode
+// (`keys` is None then). anchor checks them against `keys`, read like instruction args
+fn inbound_accounts<'info, T>(
+    program_id: &Pubkey,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    keys: Option<InboundKeys>,
+) -> Result<Option<T>>
+where
+    T: Accounts<'info, T::Bumps> + Bumps + InboundAccounts,
+    T::Bumps: Default,
+{
+    let Some(keys) = keys else {
+        require!(remaining_accounts.is_empty(), NftError::InboundAccountsMismatch);
+        return Ok(None);
+    };
+    require!(remaining_accounts.len() == T::LEN, NftError::InboundAccountsMismatch);
+    let mut accounts = remaining_accounts;
+    let parsed = T::try_accounts(
+        program_id,
+        &mut accounts,
+        &keys.try_to_vec()?,
+        &mut T::Bumps::default(),
+        &mut BTreeSet::new(),
+    )?;
+    Ok(Some(parsed))
+}
+
+// a compliance hold freezes the token account and the token program then fails the
+// move with a bare AccountFrozen, this fails first and logs which account it was.
+// pnft accounts are always frozen by token metadata, callers skip those
+fn check_not_frozen(key: Pubkey, token_account: &TokenAccount) -> Result<()> {
+    if token_account.is_frozen() {
+        msg!("Token account {} for mint {} is frozen", key, token_account.mint);
+        return err!(NftError::TokenFrozen);
+    }
+    Ok(())
+}
+
+// same for the token accounts on_call only has as account infos, one that doesnt
+// exist yet gets created unfrozen
+fn check_account_not_frozen(account: &AccountInfo) -> Result<()> {
+    if account.data_is_empty() {
+        return Ok(());
+    }
+    let token_account = TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    check_not_frozen(account.key(), &token_account)
+}
+
+// on_call only takes calls that come through the gateway stored in state
+// `program_id` is the top level instruction from the instructions sysvar. under a
+// deeper cpi (gateway -> some program -> on_call) it would still say gateway, so the
+// stack height has to show the top level instruction called us directly
+fn check_gateway_caller(
+    nft_program: &NftProgramState,
+    program_id: Pubkey,
+    stack_height: usize,
+) -> Result<()> {
+    require_keys_eq!(program_id, nft_program.gateway, NftError::Unauthorized);
+    require!(
+        stack_height
+            == anchor_lang::solana_program::instruction::TRANSACTION_LEVEL_STACK_HEIGHT + 1,
+        NftError::Unauthorized
+    );
+    Ok(())
+}
+
+// every path that gives an nft back out of escrow ends here and gets the NftUnlocked
+// to emit back, so there is one for each. the nonce is the inbound message's for a
+// returned nft, the lock's outbound one (nft_info.lock_nonce) when nothing came back.
+// an account migrated from before locked_count started at 0 with nfts already
+// locked, so the count stops at 0 instead of failing their unlock
+fn release_lock(
+    nft_program: &mut NftProgramState,
+    nft_info: &NftInfo,
+    reason: UnlockReason,
+    nonce: u64,
+) -> Result<NftUnlocked> {
+    nft_program.locked_count = nft_program.locked_count.saturating_sub(1);
+
+    Ok(NftUnlocked {
+        mint: nft_info.mint,
+        owner: nft_info.owner,
+        reason,
+        nonce,
+        slot: Clock::get()?.slot,
+    })
+}
+
+// the default pubkey means the authority was renounced, nobody holds it then
+fn is_authority(nft_program: &NftProgramState, key: Pubkey) -> bool {
+    nft_program.authority != Pubkey::default() && nft_program.authority == key
+}
+
+// accounts with an older layout are smaller than the current one (every version
+// adds fields), so the length is checked too: the bytes an old account has where
+// the version goes now can be anything
+fn state_is_current(nft_program: &Account<NftProgramState>) -> bool {
+    nft_program.version == STATE_VERSION
+        && nft_program.to_account_info().data_len() >= 8 + NftProgramState::INIT_SPACE
+}
+
+// one more nft has to fit under max_supply, 0 means no cap
+fn check_supply_cap(nft_program: &NftProgramState) -> Result<()> {
+    require!(
+        nft_program.max_supply == 0 || nft_program.total_supply < nft_program.max_supply,
+        NftError::SupplyCapReached
+    );
+    Ok(())
+}
+
+// account size of the older state layouts, an account keeps the size of the
+// layout it was made with until migrate_state grows it
+const STATE_V17_SPACE: usize = 8 + NftProgramState::INIT_SPACE - 8; // before outbound_nonce
+const STATE_V16_SPACE: usize = STATE_V17_SPACE - 8; // before max_supply
+// before inbound_metadata_mutable and immutable_bridged_count
+const STATE_V15_SPACE: usize = STATE_V16_SPACE - 1 - 8;
+const STATE_V14_SPACE: usize = STATE_V15_SPACE - 1; // before claim_mode
+// before placeholder_uri, a string takes its length on top
+const STATE_V13_SPACE: usize = STATE_V14_SPACE - 4 - MAX_METAPLEX_URI_LEN;
+// before uri_rewrite, an Option takes a byte on top of what it holds
+const STATE_V12_SPACE: usize = STATE_V13_SPACE - 1 - UriRewrite::INIT_SPACE;
+const STATE_V11_SPACE: usize = STATE_V12_SPACE - 8; // before admin_nonce
+const STATE_V10_SPACE: usize = STATE_V11_SPACE - 21; // before the tss fields
+const STATE_V9_SPACE: usize = STATE_V10_SPACE - 32; // before nonce_window
+const STATE_V8_SPACE: usize = STATE_V9_SPACE - 1; // before ordered_delivery
+const STATE_V7_SPACE: usize = STATE_V8_SPACE - 1; // before allow_program_recipients
+const STATE_V6_SPACE: usize = STATE_V7_SPACE - 1; // before dead_letter_retry
+const STATE_V5_SPACE: usize = STATE_V6_SPACE - 1; // before lazy_metadata
+const STATE_V4_SPACE: usize = STATE_V5_SPACE - 1; // before sweep_rent_destination
+const STATE_V3_SPACE: usize = STATE_V4_SPACE - 8; // before locked_count
+const STATE_V2_SPACE: usize = STATE_V3_SPACE - 32; // before default_royalty_recipient
+#[cfg(test)] // only the layout tests go back this far
+const STATE_V1_SPACE: usize = STATE_V2_SPACE - 1; // before version
+
+// reads a state image of any older layout into the current one, along with the
+// version it had. fields an older layout doesnt have get their defaults here
+fn upgrade_state(data: &[u8]) -> Result<(NftProgramState, u8)> {
+    // the bytes where newer fields go now can be leftovers, so the size decides
+    let from_version = match data.len() {
+        len if len >= 8 + NftProgramState::INIT_SPACE => {
+            NftProgramState::try_deserialize(&mut &data[..])?.version
+        }
+        len if len >= STATE_V17_SPACE => 17,
+        len if len >= STATE_V16_SPACE => 16,
+        len if len >= STATE_V15_SPACE => 15,
+        len if len >= STATE_V14_SPACE => 14,
+        len if len >= STATE_V13_SPACE => 13,
+        len if len >= STATE_V12_SPACE => 12,
+        len if len >= STATE_V11_SPACE => 11,
+        len if len >= STATE_V10_SPACE => 10,
+        len if len >= STATE_V9_SPACE => 9,
+        len if len >= STATE_V8_SPACE => 8,
+        len if len >= STATE_V7_SPACE => 7,
+        len if len >= STATE_V6_SPACE => 6,
+        len if len >= STATE_V5_SPACE => 5,
+        len if len >= STATE_V4_SPACE => 4,
+        len if len >= STATE_V3_SPACE => 3,
+        len if len >= STATE_V2_SPACE => 2,
+        _ => 1,
+    };
+    require!(from_version < STATE_VERSION, NftError::StateAlreadyMigrated);
+    let mut state = read_old_state(data, from_version)?;
+
+    // v1 -> v2 only added the version itself
+    // v2 -> v3 added the default royalty recipient, none until the authority sets one
+    if from_version < 3 {
+        state.default_royalty_recipient = Pubkey::default();
+    }
+    // v3 -> v4 added locked_count, it counts the locks made from here on
+    if from_version < 4 {
+        state.locked_count = 0;
+    }
+    // v4 -> v5 added the sweep rent destination, rent goes to the treasury by default
+    if from_version < 5 {
+        state.sweep_rent_destination = SweepRentDestination::Treasury;
+    }
+    // v5 -> v6 added lazy metadata, off so inbound nfts keep getting metadata right away
+    if from_version < 6 {
+        state.lazy_metadata = false;
+    }
+    // v6 -> v7 added dead letter retry, authority only until it says otherwise
+    if from_version < 7 {
+        state.dead_letter_retry = DeadLetterRetry::Authority;
+    }
+    // v7 -> v8 added allow_program_recipients, off like for a new deployment
+    if from_version < 8 {
+        state.allow_program_recipients = false;
+    }
+    // v8 -> v9 added ordered delivery, off so nonces keep only having to go up
+    if from_version < 9 {
+        state.ordered_delivery = false;
+    }
+    // v9 -> v10 added the nonce window. everything at or below the highest nonce was
+    // taken or given up before, so it all counts as taken
+    if from_version < 10 {
+        state.nonce_window = [u64::MAX; 4];
+    }
+    // v10 -> v11 added the tss address, none and not required until the authority sets it
+    if from_version < 11 {
+        state.tss_address = [0; 20];
+        state.require_tss_signature = false;
+    }
+    // v11 -> v12 added the admin nonce, nothing was signed offline before
+    if from_version < 12 {
+        state.admin_nonce = 0;
+    }
+    // v12 -> v13 added the uri rewrite, off until the authority sets one
+    if from_version < 13 {
+        state.uri_rewrite = None;
+    }
+    // v13 -> v14 added the placeholder uri, inbound nfts with a bad uri keep going
+    // to dead letters until the authority sets one
+    if from_version < 14 {
+        state.placeholder_uri = String::new();
+    }
+    // v14 -> v15 added claim mode, inbound transfers keep getting minted right away
+    if from_version < 15 {
+        state.claim_mode = false;
+    }
+    // v15 -> v16 made inbound metadata mutable. what was created before is immutable
+    // and isnt counted yet, count_immutable_metadata catches up on it
+    if from_version < 16 {
+        state.inbound_metadata_mutable = true;
+        state.immutable_bridged_count = 0;
+    }
+    // v16 -> v17 added the supply cap, none until the authority sets one
+    if from_version < 17 {
+        state.max_supply = 0;
+    }
+    // v17 -> v18 gave outbound transfers their own counter. it starts at the old
+    // shared one, so zetachain never sees an outbound nonce twice
+    state.outbound_nonce = state.nonce;
+    state.version = STATE_VERSION;
+    Ok((state, from_version))
+}
+
+// an old account ends where its layout did, anything after that (up to the size it
+// was made with) can be left over from a longer string before. the fields a layout
+// has are read from the account, the ones after it from zeros, upgrade_state gives
+// those their defaults
+fn read_old_state(data: &[u8], from_version: u8) -> Result<NftProgramState> {
+    require!(
+        data.len() >= 8 && data[..8] == <NftProgramState as anchor_lang::Discriminator>::DISCRIMINATOR,
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    let zeros = [0u8; NftProgramState::INIT_SPACE];
+    let mut cursor = &data[8..];
+    // fields are only ever appended, once one is newer than the layout all later ones are
+    macro_rules! field {
+        () => {
+            AnchorDeserialize::deserialize(&mut cursor)
+                .map_err(|_| ErrorCode::AccountDidNotDeserialize)?
+        };
+        ($since:expr) => {{
+            if from_version < $since {
+                cursor = &zeros;
+            }
+            field!()
+        }};
+    }
+
+    Ok(NftProgramState {
+        authority: field!(),
+        pending_authority: field!(),
+        gateway: field!(),
+        total_supply: field!(),
+        mint_count: field!(),
+        index_count: field!(),
+        nonce: field!(),
+        mint_fee_lamports: field!(),
+        max_mints_per_wallet: field!(),
+        public_mint: field!(),
+        mint_paused: field!(),
+        default_token_standard: field!(),
+        default_symbol: field!(),
+        inbound_string_policy: field!(),
+        max_uri_len: field!(),
+        allowed_uri_prefixes: field!(),
+        metadata_mode: field!(),
+        compressed_mode: field!(),
+        merkle_tree: field!(),
+        collection_mint: field!(),
+        collection_delegate: field!(),
+        defer_collection_verify: field!(),
+        bridged_collection_mint: field!(),
+        strict_metadata_hash: field!(),
+        clamp_royalty_bps: field!(),
+        default_royalty_bps: field!(),
+        bump: field!(),
+        version: field!(2),
+        default_royalty_recipient: field!(3),
+        locked_count: field!(4),
+        sweep_rent_destination: field!(5),
+        lazy_metadata: field!(6),
+        dead_letter_retry: field!(7),
+        allow_program_recipients: field!(8),
+        ordered_delivery: field!(9),
+        nonce_window: field!(10),
+        tss_address: field!(11),
+        require_tss_signature: field!(11),
+        admin_nonce: field!(12),
+        uri_rewrite: field!(13),
+        placeholder_uri: field!(14),
+        claim_mode: field!(15),
+        inbound_metadata_mutable: field!(16),
+        immutable_bridged_count: field!(16),
+        max_supply: field!(17),
+        outbound_nonce: field!(18),
+    })
+}
+
+// fills in what an nft info from an older layout is missing and returns how many
+// bytes the account has to grow by. every version only appends fields, so an
+// account sized for the old layout needs exactly what was added since
+fn upgrade_nft_info_fields(nft_info: &mut NftInfo) -> usize {
+    // fresh accounts from init_if_needed are already sized for the current layout
+    if nft_info.version == NFT_INFO_VERSION || nft_info.mint == Pubkey::default() {
+        return 0;
+    }
+    let extra = match nft_info.version {
+        // v6 -> v7 added lock_nonce
+        6 => 8,
+        // v5 -> v6 added external
+        5 => 9,
+        // v4 -> v5 added metadata_immutable
+        4 => 10,
+        // v3 -> v4 added needs_metadata_repair
+        3 => 11,
+        // v2 -> v3 added metadata_created
+        2 => 12,
+        // anything else is a v1 account, the version byte is whatever was left there.
+        // v1 -> v2 only added the version itself
+        _ => 13,
+    };
+    // nothing from before the placeholder uri needs repair
+    if extra > 10 {
+        nft_info.needs_metadata_repair = false;
+    }
+    // every nft info from before lazy metadata got its metadata along with it
+    if extra > 11 {
+        nft_info.metadata_created = true;
+    }
+    // older metadata isnt counted yet, count_immutable_metadata does that
+    if extra > 9 {
+        nft_info.metadata_immutable = false;
+    }
+    // registering came later, everything before it was minted or bridged in here
+    if extra > 8 {
+        nft_info.external = false;
+    }
+    // a lock from before v7 didnt keep its nonce
+    nft_info.lock_nonce = 0;
+    nft_info.version = NFT_INFO_VERSION;
+    extra
+}
+
+// lazy migration, instructions that change an nft info call this first and the
+// payer of that instruction covers the extra rent
+fn upgrade_nft_info<'info>(
+    nft_info: &mut Account<'info, NftInfo>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let extra = upgrade_nft_info_fields(nft_info);
+    if extra > 0 {
+        let account = nft_info.to_account_info();
+        let space = account.data_len() + extra;
+        top_up_rent(&account, payer, system_program, space)?;
+        account.realloc(space, false)?;
+    }
+    Ok(())
+}
+
+// a bridged nft whose metaplex metadata is immutable now, counted once in
+// immutable_bridged_count
+fn record_immutable_metadata(nft_program: &mut NftProgramState, nft_info: &mut NftInfo) -> Result<()> {
+    if nft_info.bridged && !nft_info.metadata_immutable {
+        nft_info.metadata_immutable = true;
+        bump(&mut nft_program.immutable_bridged_count)?;
+    }
+    Ok(())
+}
+
+// the other way when its nft info goes away. burns and sweeps dont upgrade the
+// nft info they close, one from before v5 has leftover bytes where the flag is now
+fn forget_immutable_metadata(nft_program: &mut NftProgramState, nft_info: &NftInfo) {
+    if (5..=NFT_INFO_VERSION).contains(&nft_info.version) && nft_info.metadata_immutable {
+        nft_program.immutable_bridged_count = nft_program.immutable_bridged_count.saturating_sub(1);
+    }
+}
+
+// registered with register_existing_nft. burns, sweeps and account constraints see
+// nft infos before any upgrade, one from before v6 has leftover bytes where the flag is now
+fn is_external(nft_info: &NftInfo) -> bool {
+    (6..=NFT_INFO_VERSION).contains(&nft_info.version) && nft_info.external
+}
+
+// metaplex metadata of a registered nft, only token metadata can write at its pda
+fn external_metadata(metadata: &AccountInfo) -> Result<MetaplexMetadata> {
+    require!(
+        !metadata.data_is_empty() && *metadata.owner == mpl_token_metadata::ID,
+        NftError::MissingMetaplexAccount
+    );
+    Ok(MetaplexMetadata::safe_deserialize(&metadata.try_borrow_data()?)
+        .map_err(|_| NftError::MissingMetaplexAccount)?)
+}
+
+// a registered nft has to name a verified collection the authority approved. anyone
+// can write an unverified collection into their own metadata, so that doesnt count.
+// the approval pda comes from the metadata, the address is checked here
+fn check_collection_approved(
+    metadata: &MetaplexMetadata,
+    approved_collection: Option<&AccountInfo>,
+) -> Result<()> {
+    let collection = metadata
+        .collection
+        .as_ref()
+        .filter(|collection| collection.verified)
+        .ok_or(NftError::CollectionNotApproved)?;
+    let approved_collection = approved_collection.ok_or(NftError::CollectionNotApproved)?;
+    let (address, _) = seeds::find_approved_collection(&collection.key);
+    require_keys_eq!(approved_collection.key(), address, NftError::CollectionNotApproved);
+    require!(
+        *approved_collection.owner == crate::ID && !approved_collection.data_is_empty(),
+        NftError::CollectionNotApproved
+    );
+    Ok(())
+}
+
+// makes sure an account holds enough lamports to be rent exempt at the given size
+fn top_up_rent<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    let top_up = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if top_up > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    Ok(())
+}
+
+// creates an nft mint owned by the given token program, with the program as mint
+// and freeze authority. extension mode adds a metadata pointer back to the mint,
+// which has to happen before initialize_mint2
+fn create_nft_mint<'info>(
+    mint: &AccountInfo<'info>,
+    nft_program: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    metadata_mode: MetadataMode,
+    mint_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let extensions = match metadata_mode {
+        MetadataMode::Metaplex => vec![],
+        MetadataMode::TokenExtension => {
+            require_keys_eq!(
+                token_program.key(),
+                spl_token_2022::ID,
+                NftError::MetadataExtensionRequiresToken2022
+            );
+            vec![ExtensionType::MetadataPointer]
+        }
+    };
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions)?;
+    let rent = Rent::get()?.minimum_balance(space);
+
+    if mint.lamports() == 0 {
+        anchor_lang::system_program::create_account(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: mint.clone(),
+                },
+            ).with_signer(mint_seeds),
+            rent,
+            space as u64,
+            token_program.key,
+        )?;
+    } else {
+        // anyone can send lamports to a pda address before the nft arrives and
+        // create_account refuses a funded account, so top up, allocate and assign
+        // like anchor's init does
+        let top_up = rent.saturating_sub(mint.lamports());
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    anchor_lang::system_program::Transfer {
+                        from: payer.clone(),
+                        to: mint.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        anchor_lang::system_program::allocate(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Allocate {
+                    account_to_allocate: mint.clone(),
+                },
+            ).with_signer(mint_seeds),
+            space as u64,
+        )?;
+        anchor_lang::system_program::assign(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Assign {
+                    account_to_assign: mint.clone(),
+                },
+            ).with_signer(mint_seeds),
+            token_program.key,
+        )?;
+    }
+
+    if metadata_mode == MetadataMode::TokenExtension {
+        metadata_pointer_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                MetadataPointerInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.clone(),
+                },
+            ),
+            Some(nft_program.key()),
+            Some(mint.key()),
+        )?;
+    }
+
+    initialize_mint2(
+        CpiContext::new(
+            token_program.clone(),
+            InitializeMint2 { mint: mint.clone() },
+        ),
+        0, // nfts have 0 decimals
+        nft_program.key,
+        Some(nft_program.key),
+    )
+}
+
+// writes name, symbol and uri into the token-2022 metadata extension of the mint.
+// token-2022 grows the mint itself but the rent for it has to be there first
+#[allow(clippy::too_many_arguments)]
+fn init_extension_metadata<'info>(
+    mint: &AccountInfo<'info>,
+    nft_program: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    name: String,
+    symbol: String,
+    uri: String,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let token_metadata = ExtensionMetadata {
+        update_authority: Some(nft_program.key()).try_into()?,
+        mint: mint.key(),
+        name: name.clone(),
+        symbol: symbol.clone(),
+        uri: uri.clone(),
+        additional_metadata: vec![],
+    };
+    let space = mint
+        .data_len()
+        .checked_add(token_metadata.tlv_size_of()?)
+        .ok_or(NftError::Overflow)?;
+    top_up_rent(mint, payer, system_program, space)?;
+
+    token_metadata_initialize(
+        CpiContext::new(
+            token_program.clone(),
+            TokenMetadataInitialize {
+                token_program_id: token_program.clone(),
+                metadata: mint.clone(),
+                update_authority: nft_program.clone(),
+                mint_authority: nft_program.clone(),
+                mint: mint.clone(),
+            },
+        ).with_signer(signer_seeds),
+        name,
+        symbol,
+        uri,
+    )
+}
+
+// metaplex accounts are optional since extension mode doesnt touch metaplex
+fn metaplex_account<'info, T: ToAccountInfo<'info>>(account: &Option<T>) -> Result<AccountInfo<'info>> {
+    account
+        .as_ref()
+        .map(|account| account.to_account_info())
+        .ok_or_else(|| error!(NftError::MissingMetaplexAccount))
+}
+
+// optional accounts that only the pnft paths need, missing ones fail clearly
+fn pnft_account<'info, T: ToAccountInfo<'info>>(account: &Option<T>) -> Result<AccountInfo<'info>> {
+    account
+        .as_ref()
+        .map(|account| account.to_account_info())
+        .ok_or_else(|| error!(NftError::MissingPnftAccount))
+}
+
+// optional accounts that only the collection paths need
+fn collection_account<'info, T: ToAccountInfo<'info>>(account: &Option<T>) -> Result<AccountInfo<'info>> {
+    account
+        .as_ref()
+        .map(|account| account.to_account_info())
+        .ok_or_else(|| error!(NftError::MissingCollectionAccount))
+}
+
+fn collection_state<'a, 'info>(
+    collection: &'a mut Option<Account<'info, Collection>>,
+) -> Result<&'a mut Account<'info, Collection>> {
+    collection
+        .as_mut()
+        .ok_or_else(|| error!(NftError::MissingCollectionAccount))
+}
+
+// accounts for creating a sized collection nft held by the program
+pub struct CollectionNft<'info> {
+    pub nft_program: AccountInfo<'info>,
+    pub collection_mint: AccountInfo<'info>,
+    pub collection_metadata: AccountInfo<'info>,
+    pub collection_master_edition: AccountInfo<'info>,
+    pub collection_token_account: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+fn create_collection_nft(
+    accounts: &CollectionNft<'_>,
+    name: String,
+    symbol: String,
+    uri: String,
+    nft_program_bump: u8,
+    mint_seeds: &[&[u8]],
+) -> Result<()> {
+    let nft_program_seeds: &[&[u8]] = &[seeds::NFT_PROGRAM, &[nft_program_bump]];
+
+    // size starts at 0, token metadata keeps it up to date on every verify and burn
+    CreateV1CpiBuilder::new(&accounts.token_metadata_program)
+        .metadata(&accounts.collection_metadata)
+        .master_edition(Some(&accounts.collection_master_edition))
+        .mint(&accounts.collection_mint, true)
+        .authority(&accounts.nft_program)
+        .payer(&accounts.payer)
+        .update_authority(&accounts.nft_program, true)
+        .system_program(&accounts.system_program)
+        .sysvar_instructions(&accounts.sysvar_instructions)
+        .spl_token_program(Some(&accounts.token_program))
+        .name(name)
+        .symbol(symbol)
+        .uri(uri)
+        .seller_fee_basis_points(0)
+        .is_mutable(true)
+        .token_standard(TokenStandard::NonFungible)
+        .collection_details(CollectionDetails::V1 { size: 0 })
+        .print_supply(PrintSupply::Zero)
+        .invoke_signed(&[nft_program_seeds, mint_seeds])?;
+
+    MintV1CpiBuilder::new(&accounts.token_metadata_program)
+        .token(&accounts.collection_token_account)
+        .token_owner(Some(&accounts.nft_program))
+        .metadata(&accounts.collection_metadata)
+        .master_edition(Some(&accounts.collection_master_edition))
+        .mint(&accounts.collection_mint)
+        .authority(&accounts.nft_program)
+        .payer(&accounts.payer)
+        .system_program(&accounts.system_program)
+        .sysvar_instructions(&accounts.sysvar_instructions)
+        .spl_token_program(&accounts.token_program)
+        .spl_ata_program(&accounts.associated_token_program)
+        .amount(1)
+        .invoke_signed(&[nft_program_seeds])?;
+    Ok(())
+}
+
+// accounts for the metaplex metadata of an inbound nft, on_call creates it right
+// away and materialize_metadata later on in lazy mode
+pub struct InboundMetadata<'a, 'info> {
+    pub nft_program: &'a Account<'info, NftProgramState>,
+    pub mint: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub master_edition: AccountInfo<'info>,
+    pub collection: &'a mut Option<Account<'info, Collection>>,
+    pub collection_mint: &'a Option<UncheckedAccount<'info>>,
+    pub collection_metadata: &'a Option<UncheckedAccount<'info>>,
+    pub collection_master_edition: &'a Option<UncheckedAccount<'info>>,
+    pub bridged_collection: &'a mut Option<Account<'info, Collection>>,
+    pub bridged_collection_mint: &'a Option<UncheckedAccount<'info>>,
+    pub payer: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+// creates the metadata and the master edition and joins the bridged collection, or
+// the program one when there is no bridged collection. returns whether it got
+// verified into one and if that was the bridged one. the uri goes through the uri
+// rewrite. callers make it mutable unless set_inbound_metadata_mutable turned that
+// off, placeholder metadata always so repair_metadata can fix it
+fn create_inbound_metadata(
+    accounts: InboundMetadata<'_, '_>,
+    name: String,
+    symbol: String,
+    uri: String,
+    royalty_bps: u16,
+    royalty_recipient: Option<Pubkey>,
+    is_mutable: bool,
+) -> Result<(bool, bool)> {
+    let nft_program = accounts.nft_program;
+    let uri = display_uri(nft_program, &uri);
+    require!(uri.len() <= MAX_METAPLEX_URI_LEN, NftError::UriTooLong);
+    let joins_bridged = nft_program.bridged_collection_mint != Pubkey::default();
+    let collection_mint = match joins_bridged {
+        true => nft_program.bridged_collection_mint,
+        false => nft_program.collection_mint,
+    };
+    let joins_collection = collection_mint != Pubkey::default();
+    // verify_collection_item only knows the program collection, so
+    // the bridged one is always verified right away
+    let verify_now = joins_collection && (joins_bridged || !nft_program.defer_collection_verify);
+    let data_v2 = DataV2 {
+        name,
+        symbol,
+        uri,
+        seller_fee_basis_points: royalty_bps,
+        // the royalty recipient cant sign here, it stays unverified
+        creators: royalty_recipient.map(|address| {
+            vec![Creator {
+                address,
+                verified: false,
+                share: 100,
+            }]
+        }),
+        collection: joins_collection.then_some(MetaplexCollection {
+            verified: false,
+            key: collection_mint,
+        }),
+        uses: None,
+    };
+
+    anchor_spl::metadata::create_metadata_accounts_v3(
+        CpiContext::new_with_signer(
+            accounts.token_metadata_program.clone(),
+            anchor_spl::metadata::CreateMetadataAccountsV3 {
+                metadata: accounts.metadata.clone(),
+                mint: accounts.mint.clone(),
+                mint_authority: nft_program.to_account_info(),
+                update_authority: nft_program.to_account_info(),
+                payer: accounts.payer.clone(),
+                system_program: accounts.system_program.clone(),
+                rent: accounts.rent.clone(),
+            },
+            &[&[seeds::NFT_PROGRAM, &[nft_program.bump]]],
+        ),
+        data_v2,
+        is_mutable,
+        true, // update authority is signer
+        None,  // no collection details
+    )?;
+
+    // no prints and no more supply, token metadata moves the mint and freeze
+    // authority over to the edition pda
+    create_master_edition_v3(
+        CpiContext::new_with_signer(
+            accounts.token_metadata_program.clone(),
+            CreateMasterEditionV3 {
+                edition: accounts.master_edition,
+                mint: accounts.mint,
+                update_authority: nft_program.to_account_info(),
+                mint_authority: nft_program.to_account_info(),
+                payer: accounts.payer,
+                metadata: accounts.metadata.clone(),
+                token_program: accounts.token_program,
+                system_program: accounts.system_program.clone(),
+                rent: accounts.rent,
+            },
+            &[&[seeds::NFT_PROGRAM, &[nft_program.bump]]],
+        ),
+        Some(0),
+    )?;
+
+    if verify_now {
+        let (collection_mint, collection) = match joins_bridged {
+            true => (
+                collection_account(accounts.bridged_collection_mint)?,
+                collection_state(accounts.bridged_collection)?,
+            ),
+            false => (
+                collection_account(accounts.collection_mint)?,
+                collection_state(accounts.collection)?,
+            ),
+        };
+        add_to_collection(
+            &CollectionItem {
+                metadata: accounts.metadata,
+                collection_mint,
+                collection_metadata: collection_account(accounts.collection_metadata)?,
+                collection_master_edition: collection_account(accounts.collection_master_edition)?,
+                authority: nft_program.to_account_info(),
+                delegate_record: None,
+                system_program: accounts.system_program,
+                sysvar_instructions: accounts.sysvar_instructions,
+                token_metadata_program: accounts.token_metadata_program,
+            },
+            collection,
+            &[&[seeds::NFT_PROGRAM, &[nft_program.bump]]],
+        )?;
+    }
+    Ok((verify_now, joins_bridged))
+}
+
+// accounts for verifying an nft into a collection or taking it out
+pub struct CollectionItem<'info> {
+    pub metadata: AccountInfo<'info>,
+    pub collection_mint: AccountInfo<'info>,
+    pub collection_metadata: AccountInfo<'info>,
+    pub collection_master_edition: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub delegate_record: Option<AccountInfo<'info>>, // only when a collection delegate verifies
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+// token metadata bumps the sized collection count on verify, our copy follows it
+fn add_to_collection(
+    accounts: &CollectionItem<'_>,
+    collection: &mut Collection,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    VerifyCollectionV1CpiBuilder::new(&accounts.token_metadata_program)
+        .authority(&accounts.authority)
+        .delegate_record(accounts.delegate_record.as_ref())
+        .metadata(&accounts.metadata)
+        .collection_mint(&accounts.collection_mint)
+        .collection_metadata(Some(&accounts.collection_metadata))
+        .collection_master_edition(Some(&accounts.collection_master_edition))
+        .system_program(&accounts.system_program)
+        .sysvar_instructions(&accounts.sysvar_instructions)
+        .invoke_signed(signer_seeds)?;
+
+    bump(&mut collection.collection_size)?;
+    Ok(())
+}
+
+// the other direction, the caller updates our copy of the size
+fn remove_from_collection(
+    accounts: &CollectionItem<'_>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    UnverifyCollectionV1CpiBuilder::new(&accounts.token_metadata_program)
+        .authority(&accounts.authority)
+        .metadata(&accounts.metadata)
+        .collection_mint(&accounts.collection_mint)
+        .collection_metadata(Some(&accounts.collection_metadata))
+        .system_program(&accounts.system_program)
+        .sysvar_instructions(&accounts.sysvar_instructions)
+        .invoke_signed(signer_seeds)?;
+    Ok(())
+}
+
+// accounts for moving a pnft in or out of escrow
+pub struct PnftTransfer<'info> {
+    pub token: AccountInfo<'info>,
+    pub token_owner: AccountInfo<'info>,
+    pub destination_token: AccountInfo<'info>,
+    pub destination_owner: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub edition: AccountInfo<'info>,
+    pub token_record: AccountInfo<'info>,
+    pub destination_token_record: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+// pnft token accounts stay frozen, so escrow moves have to be a token metadata
+// transfer which also keeps the token records in sync
+fn transfer_pnft(accounts: &PnftTransfer<'_>, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    TransferV1CpiBuilder::new(&accounts.token_metadata_program)
+        .token(&accounts.token)
+        .token_owner(&accounts.token_owner)
+        .destination_token(&accounts.destination_token)
+        .destination_owner(&accounts.destination_owner)
+        .mint(&accounts.mint)
+        .metadata(&accounts.metadata)
+        .edition(Some(&accounts.edition))
+        .token_record(Some(&accounts.token_record))
+        .destination_token_record(Some(&accounts.destination_token_record))
+        .authority(&accounts.authority)
+        .payer(&accounts.payer)
+        .system_program(&accounts.system_program)
+        .sysvar_instructions(&accounts.sysvar_instructions)
+        .spl_token_program(&accounts.token_program)
+        .spl_ata_program(&accounts.associated_token_program)
+        .amount(1)
+        .invoke_signed(signer_seeds)?;
+    Ok(())
+}
+
+// the accounts both mint variants have in common
+pub struct MintAccounts<'a, 'info> {
+    pub nft_program: &'a mut Account<'info, NftProgramState>,
+    pub mint: &'a AccountInfo<'info>,
+    pub token_account: &'a UncheckedAccount<'info>,
+    pub recipient: &'a UncheckedAccount<'info>,
+    pub nft_info: &'a mut Account<'info, NftInfo>,
+    pub metadata: &'a Option<UncheckedAccount<'info>>,
+    pub treasury: &'a Account<'info, Treasury>,
+    pub minter_role: &'a Option<Account<'info, MinterRole>>,
+    pub owner_stats: &'a mut Account<'info, OwnerStats>,
+    pub mint_index: &'a mut Account<'info, MintIndex>,
+    pub master_edition: &'a Option<UncheckedAccount<'info>>,
+    pub token_record: &'a Option<UncheckedAccount<'info>>,
+    pub sysvar_instructions: &'a Option<UncheckedAccount<'info>>,
+    pub collection: &'a mut Option<Account<'info, Collection>>,
+    pub collection_mint: &'a Option<UncheckedAccount<'info>>,
+    pub collection_metadata: &'a Option<UncheckedAccount<'info>>,
+    pub collection_master_edition: &'a Option<UncheckedAccount<'info>>,
+    pub payer: &'a Signer<'info>,
+    pub rent: &'a Sysvar<'info, Rent>,
+    pub system_program: &'a Program<'info, System>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+    pub associated_token_program: &'a Program<'info, AssociatedToken>,
+    pub token_metadata_program: &'a Option<Program<'info, Metadata>>,
+    pub blocked_recipient: &'a UncheckedAccount<'info>,
+}
+
+// the token fee accounts both transfers out have, see charge_fee_token
+pub struct FeeTokenAccounts<'a, 'info> {
+    pub fee_mint: &'a Option<Box<InterfaceAccount<'info, Mint>>>,
+    pub owner_fee_token_account: &'a Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    pub fee_vault: &'a Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    pub fee_token_program: &'a Option<Interface<'info, TokenInterface>>,
+    pub owner: &'a Signer<'info>,
+    pub treasury: &'a Account<'info, Treasury>,
+}
+
+// account structs for all the instructions, dont mess with the order
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + NftProgramState::INIT_SPACE,
+        seeds = [seeds::NFT_PROGRAM],
+        bump
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [seeds::TREASURY],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// check: only stored, signs when it is the payer or authority_must_sign is set
+    pub authority: UncheckedAccount<'info>,
+
+    /// pays for the state and treasury, can be another key than the authority
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct NominateAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = nft_program.pending_authority == new_authority.key() @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuthorityNomination<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, signer.key())
+            || nft_program.pending_authority == signer.key() @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    /// the authority or the nominee
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RenounceAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct MigrateNftInfo<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump
+    )]
+    pub nft_info: Account<'info, NftInfo>,
+
+    /// anyone, pays for the extra space
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+    /// check: state in an older layout that NftProgramState cant load, the handler
+    /// parses it and checks the authority
+    #[account(mut, seeds = [seeds::NFT_PROGRAM], bump, owner = crate::ID)]
+    pub nft_program: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the extra space
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMintFee<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct GetMintByIndex<'info> {
+    #[account(
+        seeds = [seeds::INDEX, index.to_le_bytes().as_ref()],
+        bump = mint_index.bump
+    )]
+    pub mint_index: Account<'info, MintIndex>,
+}
+
+#[derive(Accounts)]
+pub struct GetVersion<'info> {
+    /// check: the state pda of the deployment asked, never read so any layout works.
+    /// the cpi client needs an accounts struct with a lifetime
+    #[account(seeds = [seeds::NFT_PROGRAM], bump)]
+    pub nft_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetStats<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        seeds = [seeds::TREASURY],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+pub struct ReportNonceGap<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct SkipMessage<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SkippedMessage::INIT_SPACE,
+        seeds = [seeds::SKIPPED_MESSAGE, nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub skipped_message: Account<'info, SkippedMessage>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the skipped message pda
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxMintsPerWallet<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxSupply<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGateway<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMintPaused<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPublicMint<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetStrictMetadataHash<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetDefaultRoyalty<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetClampRoyaltyBps<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetDefaultSymbol<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllowedUriPrefixes<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPlaceholderUri<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetUriRewrite<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetDefaultTokenStandard<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetInboundStringPolicy<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMetadataMode<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetLazyMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct MaterializeMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    /// check: the nft mint, nft info is keyed by it and we are its mint authority
+    #[account(address = mint_key)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// check: metadata account of the mint, created by token metadata
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint_key.as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// check: master edition pda of the mint, created by token metadata
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint_key.as_ref(),
+            b"edition",
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+
+    /// collection only: the program collection, needed once it exists
+    #[account(
+        mut,
+        seeds = [seeds::COLLECTION],
+        bump = collection.bump
+    )]
+    pub collection: Option<Account<'info, Collection>>,
+
+    /// check: collection mint pda
+    #[account(seeds = [seeds::COLLECTION_MINT], bump)]
+    pub collection_mint: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata against the collection mint
+    #[account(mut)]
+    pub collection_metadata: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata against the collection mint
+    pub collection_master_edition: Option<UncheckedAccount<'info>>,
+
+    /// bridged collection only: instead of `collection` once it exists
+    #[account(
+        mut,
+        seeds = [seeds::BRIDGED_COLLECTION],
+        bump = bridged_collection.bump
+    )]
+    pub bridged_collection: Option<Account<'info, Collection>>,
+
+    /// check: bridged collection mint pda
+    #[account(seeds = [seeds::BRIDGED_COLLECTION_MINT], bump)]
+    pub bridged_collection_mint: Option<UncheckedAccount<'info>>,
+
+    /// anyone, pays for the metadata
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// check: instructions sysvar, token metadata reads it when verifying
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct RepairMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    /// check: the nft mint, written to in extension mode
+    #[account(mut, address = mint_key)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// metaplex only, left out in extension mode
+    /// check: metadata account of the mint, validated by token metadata during update_v1
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint_key.as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// the owner or the authority, pays for any extra rent
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// check: instructions sysvar, token metadata reads it during update_v1
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct FreezeMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    /// check: the nft mint, nft info is keyed by it
+    #[account(address = mint_key)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// check: metadata account of the mint, validated by token metadata during update_v1
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint_key.as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// the owner or the authority, pays for any extra rent
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// check: instructions sysvar, token metadata reads it during update_v1
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct CountImmutableMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, mint.as_ref()],
+        bump = nft_info.bump
+    )]
+    pub nft_info: Account<'info, NftInfo>,
+
+    /// check: metadata account of the mint, read only
+    #[account(
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint.as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// anyone, pays for upgrading an older nft info
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetSweepRentDestination<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetDeadLetterRetry<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllowProgramRecipients<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteSignedAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    /// instruction sysvar account, the authority's ed25519 instruction is read from it
+    /// check: address is the sysvar
+    #[account(address = sysvar::instructions::id())]
+    pub instruction_sysvar_account: UncheckedAccount<'info>,
+
+    /// submits the signed action, doesnt need to be anyone in particular
+    pub relayer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTssAddress<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRequireTssSignature<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetOrderedDelivery<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetClaimMode<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetInboundMetadataMutable<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxUriLen<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u64)]
+pub struct RegisterChain<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ChainInfo::INIT_SPACE,
+        seeds = [seeds::CHAIN, chain_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub chain_info: Account<'info, ChainInfo>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the chain info pda
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u64)]
+pub struct SetChainInbound<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CHAIN, chain_id.to_le_bytes().as_ref()],
+        bump = chain_info.bump
+    )]
+    pub chain_info: Account<'info, ChainInfo>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u64)]
+pub struct SetChainFees<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CHAIN, chain_id.to_le_bytes().as_ref()],
+        bump = chain_info.bump
+    )]
+    pub chain_info: Account<'info, ChainInfo>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u64)]
+pub struct SetChainFeeCurrency<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CHAIN, chain_id.to_le_bytes().as_ref()],
+        bump = chain_info.bump
+    )]
+    pub chain_info: Account<'info, ChainInfo>,
+
+    /// owns the fee vaults
+    #[account(
+        seeds = [seeds::TREASURY],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the fee vault
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// token fee only: the fee mint
+    pub fee_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// token fee only: the treasury's ata of the fee mint, created if its missing
+    /// check: the associated token program checks the address
+    #[account(mut)]
+    pub fee_vault: Option<UncheckedAccount<'info>>,
+
+    /// token fee only: token program of the fee mint
+    pub fee_token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u64)]
+pub struct UpdateChain<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CHAIN, chain_id.to_le_bytes().as_ref()],
+        bump = chain_info.bump
+    )]
+    pub chain_info: Account<'info, ChainInfo>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct GetNftInfo<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    /// check: may not exist, the handler only reads it when we own it
+    #[account(seeds = [seeds::NFT_INFO, mint.as_ref()], bump)]
+    pub nft_info: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u64)]
+pub struct GetChainInfo<'info> {
+    #[account(
+        seeds = [seeds::CHAIN, chain_id.to_le_bytes().as_ref()],
+        bump = chain_info.bump
+    )]
+    pub chain_info: Account<'info, ChainInfo>,
+}
+
+#[derive(Accounts)]
+#[instruction(minter: Pubkey)]
+pub struct GrantMinter<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MinterRole::INIT_SPACE,
+        seeds = [seeds::MINTER, minter.as_ref()],
+        bump
+    )]
+    pub minter_role: Account<'info, MinterRole>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the minter role pda
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(minter: Pubkey)]
+pub struct RevokeMinter<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [seeds::MINTER, minter.as_ref()],
+        bump = minter_role.bump
+    )]
+    pub minter_role: Account<'info, MinterRole>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct BlockRecipient<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + BlockedRecipient::INIT_SPACE,
+        seeds = [seeds::BLOCKED_RECIPIENT, recipient.as_ref()],
+        bump
+    )]
+    pub blocked_recipient: Account<'info, BlockedRecipient>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the block pda
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct UnblockRecipient<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [seeds::BLOCKED_RECIPIENT, recipient.as_ref()],
+        bump = blocked_recipient.bump
+    )]
+    pub blocked_recipient: Account<'info, BlockedRecipient>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct BlockMint<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + BlockedMint::INIT_SPACE,
+        seeds = [seeds::BLOCKED_MINT, mint.as_ref()],
+        bump
+    )]
+    pub blocked_mint: Account<'info, BlockedMint>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the block pda
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct UnblockMint<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [seeds::BLOCKED_MINT, mint.as_ref()],
+        bump = blocked_mint.bump
+    )]
+    pub blocked_mint: Account<'info, BlockedMint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_mint: Pubkey)]
+pub struct ApproveCollection<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ApprovedCollection::INIT_SPACE,
+        seeds = [seeds::APPROVED_COLLECTION, collection_mint.as_ref()],
+        bump
+    )]
+    pub approved_collection: Account<'info, ApprovedCollection>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the approval pda
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_mint: Pubkey)]
+pub struct RevokeCollection<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [seeds::APPROVED_COLLECTION, collection_mint.as_ref()],
+        bump = approved_collection.bump
+    )]
+    pub approved_collection: Account<'info, ApprovedCollection>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey, holder_key: Pubkey)]
+pub struct ComplianceHold<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        address = mint_key,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = holder_key,
+        token::token_program = token_program,
+    )]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ComplianceAction::INIT_SPACE,
+        seeds = [seeds::COMPLIANCE, mint_key.as_ref(), holder_key.as_ref()],
+        bump
+    )]
+    pub compliance_action: Account<'info, ComplianceAction>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the compliance pda the first time
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        seeds = [seeds::TREASURY],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// check: any account can receive the lamports, authority picks it
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFeeToken<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    /// owns the fee vault and signs for it
+    #[account(
+        seeds = [seeds::TREASURY],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mint::token_program = token_program)]
+    pub fee_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = fee_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// any token account of the fee mint, authority picks it
+    #[account(
+        mut,
+        token::mint = fee_mint,
+        token::token_program = token_program,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct SweepOrphanedInfo<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump
+    )]
+    pub nft_info: Account<'info, NftInfo>,
+
+    // supply is read from the mint itself, either token program
+    #[account(address = mint_key)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// index slot of the nft, needed when it has one
+    #[account(
+        mut,
+        seeds = [seeds::INDEX, mint_index.index.to_le_bytes().as_ref()],
+        bump = mint_index.bump,
+        constraint = mint_index.mint == mint_key @ NftError::InvalidMintIndex
+    )]
+    pub mint_index: Option<Account<'info, MintIndex>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::TREASURY],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    // anyone, gets the rent when the destination is the caller
+    #[account(mut)]
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey, amount: u64, destination_key: Pubkey)]
+pub struct RescueToken<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        address = mint_key,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// check: nft info of the mint if we know it, the handler refuses locked ones
+    #[account(
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump
+    )]
+    pub nft_info: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = nft_program,
+        token::token_program = token_program,
+    )]
+    pub program_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = destination_key,
+        token::token_program = token_program,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(params: MintParams)]
+pub struct MintNft<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    /// fresh mint keypair, created and initialized in the handler
+    /// check: has to sign, checked here so a pda mint gets its own error
+    #[account(mut, constraint = mint.is_signer @ NftError::MintNotSigner)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// check: recipient ata, address checked here and created in the handler
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &recipient.key(),
+            &mint.key(),
+            &token_program.key(),
+        ),
+    )]
+    pub token_account: UncheckedAccount<'info>,
+
+    /// check: wallet the ata is created for, has to be the recipient arg
+    #[account(address = params.recipient)]
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = NftInfo::space(nft_program.max_uri_len),
+        seeds = [seeds::NFT_INFO, mint.key().as_ref()],
+        bump
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    /// metaplex mode only
+    /// check: this is the metadata account, dont use directly
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// treasury pda, receives the mint fee
+    #[account(
+        mut,
+        seeds = [seeds::TREASURY],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// minter role of the payer, only needed when public minting is off
+    #[account(
+        seeds = [seeds::MINTER, payer.key().as_ref()],
+        bump = minter_role.bump
+    )]
+    pub minter_role: Option<Account<'info, MinterRole>>,
+
+    /// local mints per recipient wallet, for max_mints_per_wallet
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OwnerStats::INIT_SPACE,
+        seeds = [seeds::OWNER_STATS, params.recipient.as_ref()],
+        bump
+    )]
+    pub owner_stats: Account<'info, OwnerStats>,
+
+    /// next slot in the enumerable index
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MintIndex::INIT_SPACE,
+        seeds = [seeds::INDEX, nft_program.index_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub mint_index: Account<'info, MintIndex>,
+
+    /// pnft only: master edition and token record, token metadata creates both
+    /// check: validated by token metadata during create_v1/mint_v1
+    #[account(mut)]
+    pub master_edition: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during mint_v1
+    #[account(mut)]
+    pub token_record: Option<UncheckedAccount<'info>>,
+
+    /// check: instructions sysvar, token metadata reads it for pnfts and collections
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
+    /// collection only: the program collection, needed once it exists
+    #[account(
+        mut,
+        seeds = [seeds::COLLECTION],
+        bump = collection.bump
+    )]
+    pub collection: Option<Account<'info, Collection>>,
+
+    /// check: collection mint pda
+    #[account(seeds = [seeds::COLLECTION_MINT], bump)]
+    pub collection_mint: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata against the collection mint
+    #[account(mut)]
+    pub collection_metadata: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata against the collection mint
+    pub collection_master_edition: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+
+    /// check: the recipient's block pda, the mint fails while it exists
+    #[account(seeds = [seeds::BLOCKED_RECIPIENT, params.recipient.as_ref()], bump)]
+    pub blocked_recipient: UncheckedAccount<'info>,
+}
+
+impl<'info> MintNft<'info> {
+    pub fn mint_accounts(&mut self) -> MintAccounts<'_, 'info> {
+        MintAccounts {
+            nft_program: &mut self.nft_program,
+            mint: &self.mint,
+            token_account: &self.token_account,
+            recipient: &self.recipient,
+            nft_info: &mut self.nft_info,
+            metadata: &self.metadata,
+            treasury: &self.treasury,
+            minter_role: &self.minter_role,
+            owner_stats: &mut self.owner_stats,
+            mint_index: &mut self.mint_index,
+            master_edition: &self.master_edition,
+            token_record: &self.token_record,
+            sysvar_instructions: &self.sysvar_instructions,
+            collection: &mut self.collection,
+            collection_mint: &self.collection_mint,
+            collection_metadata: &self.collection_metadata,
+            collection_master_edition: &self.collection_master_edition,
+            payer: &self.payer,
+            rent: &self.rent,
+            system_program: &self.system_program,
+            token_program: &self.token_program,
+            associated_token_program: &self.associated_token_program,
+            token_metadata_program: &self.token_metadata_program,
+            blocked_recipient: &self.blocked_recipient,
+        }
+    }
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(params: MintParams)]
+pub struct MintNftPda<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    /// mint pda, derived from the supply before this mint
+    /// check: created and initialized in the handler. a raw constraint instead of
+    /// seeds so a keypair or stale mint gets its own error
+    #[account(
+        mut,
+        constraint = mint.key() == seeds::find_pda_mint(nft_program.mint_count).0
+            @ NftError::MintNotProgramDerived
+    )]
+    pub mint: UncheckedAccount<'info>,
+
+    /// check: recipient ata, address checked here and created in the handler
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &recipient.key(),
+            &mint.key(),
+            &token_program.key(),
+        ),
+    )]
+    pub token_account: UncheckedAccount<'info>,
+
+    /// check: wallet the ata is created for, has to be the recipient arg
+    #[account(address = params.recipient)]
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = NftInfo::space(nft_program.max_uri_len),
+        seeds = [seeds::NFT_INFO, mint.key().as_ref()],
+        bump
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    /// metaplex mode only
+    /// check: this is the metadata account, dont use directly
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// treasury pda, receives the mint fee
+    #[account(
+        mut,
+        seeds = [seeds::TREASURY],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// minter role of the payer, only needed when public minting is off
+    #[account(
+        seeds = [seeds::MINTER, payer.key().as_ref()],
+        bump = minter_role.bump
+    )]
+    pub minter_role: Option<Account<'info, MinterRole>>,
+
+    /// local mints per recipient wallet, for max_mints_per_wallet
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OwnerStats::INIT_SPACE,
+        seeds = [seeds::OWNER_STATS, params.recipient.as_ref()],
+        bump
+    )]
+    pub owner_stats: Account<'info, OwnerStats>,
+
+    /// next slot in the enumerable index
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MintIndex::INIT_SPACE,
+        seeds = [seeds::INDEX, nft_program.index_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub mint_index: Account<'info, MintIndex>,
+
+    /// pnft only: master edition and token record, token metadata creates both
+    /// check: validated by token metadata during create_v1/mint_v1
+    #[account(mut)]
+    pub master_edition: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during mint_v1
+    #[account(mut)]
+    pub token_record: Option<UncheckedAccount<'info>>,
+
+    /// check: instructions sysvar, token metadata reads it for pnfts and collections
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
+    /// collection only: the program collection, needed once it exists
+    #[account(
+        mut,
+        seeds = [seeds::COLLECTION],
+        bump = collection.bump
+    )]
+    pub collection: Option<Account<'info, Collection>>,
+
+    /// check: collection mint pda
+    #[account(seeds = [seeds::COLLECTION_MINT], bump)]
+    pub collection_mint: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata against the collection mint
+    #[account(mut)]
+    pub collection_metadata: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata against the collection mint
+    pub collection_master_edition: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+
+    /// check: the recipient's block pda, the mint fails while it exists
+    #[account(seeds = [seeds::BLOCKED_RECIPIENT, params.recipient.as_ref()], bump)]
+    pub blocked_recipient: UncheckedAccount<'info>,
+}
+
+impl<'info> MintNftPda<'info> {
+    pub fn mint_accounts(&mut self) -> MintAccounts<'_, 'info> {
+        MintAccounts {
+            nft_program: &mut self.nft_program,
+            mint: &self.mint,
+            token_account: &self.token_account,
+            recipient: &self.recipient,
+            nft_info: &mut self.nft_info,
+            metadata: &self.metadata,
+            treasury: &self.treasury,
+            minter_role: &self.minter_role,
+            owner_stats: &mut self.owner_stats,
+            mint_index: &mut self.mint_index,
+            master_edition: &self.master_edition,
+            token_record: &self.token_record,
+            sysvar_instructions: &self.sysvar_instructions,
+            collection: &mut self.collection,
+            collection_mint: &self.collection_mint,
+            collection_metadata: &self.collection_metadata,
+            collection_master_edition: &self.collection_master_edition,
+            payer: &self.payer,
+            rent: &self.rent,
+            system_program: &self.system_program,
+            token_program: &self.token_program,
+            associated_token_program: &self.associated_token_program,
+            token_metadata_program: &self.token_metadata_program,
+            blocked_recipient: &self.blocked_recipient,
+        }
+    }
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(claim_nonce: u64, params: TransferParams)]
+pub struct TransferClaimToZetachain<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    /// registry entry of the destination chain
+    #[account(
+        seeds = [seeds::CHAIN, params.destination_chain_id.to_le_bytes().as_ref()],
+        bump = chain_info.bump
+    )]
+    pub chain_info: Account<'info, ChainInfo>,
+
+    /// gets the chain fee
+    #[account(
+        mut,
+        seeds = [seeds::TREASURY],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// the unclaimed nft, closed once it is on its way
+    #[account(
+        mut,
+        seeds = [seeds::PENDING_CLAIM, claim_nonce.to_le_bytes().as_ref()],
+        bump = pending_claim.bump,
+        constraint = pending_claim.recipient == owner.key() @ NftError::Unauthorized,
+        close = owner
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// check: block pda of the bridged mint, a blocked one doesnt go out
+    #[account(seeds = [seeds::BLOCKED_MINT, pending_claim.mint.as_ref()], bump)]
+    pub blocked_mint: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// token fee only: the chain's fee mint
+    pub fee_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// token fee only: the owner's token account of the fee mint, pays the fee
+    #[account(mut)]
+    pub owner_fee_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// token fee only: the treasury's ata of the fee mint, see set_chain_fee_currency
+    #[account(mut)]
+    pub fee_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// token fee only: token program of the fee mint
+    pub fee_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct RegisterExistingNft<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    /// created on the first registration, checked in the handler when it exists
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = NftInfo::space(nft_program.max_uri_len),
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    /// holds the nft, pays for the nft info
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// a real nft that isnt ours, a mint the program is mint authority of came from
+    /// mint_nft or on_call
+    #[account(
+        address = mint_key,
+        mint::token_program = token_program,
+        constraint = mint.decimals == 0 @ NftError::MintDecimalsNotZero,
+        constraint = mint.supply == 1 @ NftError::MintSupplyNotOne,
+        constraint = mint.mint_authority != COption::Some(nft_program.key())
+            @ NftError::InvalidMintAuthority
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        token::mint = mint,
+        token::authority = holder,
+        token::token_program = token_program,
+    )]
+    pub holder_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// check: metaplex metadata pda of the mint, owner and contents checked in the handler
+    #[account(
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint_key.as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// check: approval pda of the collection in the metadata, checked in the handler
+    pub approved_collection: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct DeregisterNft<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    // nft_info.owner can be stale after a plain spl transfer, the token account is what counts
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump,
+        close = holder
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    /// holds the nft, gets the rent of the nft info
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        address = mint_key,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        token::mint = mint,
+        token::authority = holder,
+        token::token_program = token_program,
+    )]
+    pub holder_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(params: TransferParams)]
+pub struct TransferToZetachain<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    /// registry entry of the destination chain
+    #[account(
+        seeds = [seeds::CHAIN, params.destination_chain_id.to_le_bytes().as_ref()],
+        bump = chain_info.bump
+    )]
+    pub chain_info: Account<'info, ChainInfo>,
+
+    /// gets the chain fee
+    #[account(
+        mut,
+        seeds = [seeds::TREASURY],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, nft_info.mint.as_ref()],
+        bump = nft_info.bump,
+        constraint = nft_info.owner == owner.key()
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// has to be a real nft, whatever ended up in nft info. a registered one keeps
+    /// the mint authority it was minted with
+    #[account(
+        address = nft_info.mint,
+        mint::token_program = token_program,
+        constraint = mint.decimals == 0 @ NftError::MintDecimalsNotZero,
+        constraint = mint.supply == 1 @ NftError::MintSupplyNotOne,
+        constraint = is_external(&nft_info)
+            || is_nft_mint_authority(mint.mint_authority, mint.key(), nft_program.key())
+            @ NftError::InvalidMintAuthority
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = nft_program,
+        associated_token::token_program = token_program,
+    )]
+    pub program_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// pnft only: accounts token metadata needs to move the nft into escrow. a
+    /// registered nft passes its metadata either way, for the collection check
+    /// check: validated by token metadata during transfer_v1, the address by the
+    /// handler for a registered nft
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during transfer_v1
+    pub master_edition: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during transfer_v1
+    #[account(mut)]
+    pub owner_token_record: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during transfer_v1
+    #[account(mut)]
+    pub program_token_record: Option<UncheckedAccount<'info>>,
+
+    /// check: instructions sysvar, token metadata reads it for pnfts
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// check: the mint's block pda, the transfer fails while it exists
+    #[account(seeds = [seeds::BLOCKED_MINT, mint.key().as_ref()], bump)]
+    pub blocked_mint: UncheckedAccount<'info>,
+
+    /// registered nfts only: approval pda of the collection in the metadata
+    /// check: address checked in the handler
+    pub approved_collection: Option<UncheckedAccount<'info>>,
+
+    /// token fee only: the chain's fee mint
+    pub fee_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// token fee only: the owner's token account of the fee mint, pays the fee
+    #[account(mut)]
+    pub owner_fee_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// token fee only: the treasury's ata of the fee mint, see set_chain_fee_currency
+    #[account(mut)]
+    pub fee_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// token fee only: token program of the fee mint
+    pub fee_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct HandleCrossChainCall<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UnlockNft<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, nft_info.mint.as_ref()],
+        bump = nft_info.bump,
+        constraint = nft_info.owner == owner.key()
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    /// check: the owner in nft info, the nft goes back to its ata. doesnt sign
+    pub owner: UncheckedAccount<'info>,
+
+    /// pays for an nft info upgrade and a pnft's token record
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        address = nft_info.mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = nft_program,
+        associated_token::token_program = token_program,
+    )]
+    pub program_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// pnft only: accounts token metadata needs to move the nft out of escrow
+    /// check: validated by token metadata during transfer_v1
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during transfer_v1
+    pub master_edition: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during transfer_v1
+    #[account(mut)]
+    pub owner_token_record: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during transfer_v1
+    #[account(mut)]
+    pub program_token_record: Option<UncheckedAccount<'info>>,
+
+    /// check: instructions sysvar, token metadata reads it for pnfts
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+    pub system_program: Option<Program<'info, System>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// check: the owner's block pda, the nft stays in escrow while it exists
+    #[account(seeds = [seeds::BLOCKED_RECIPIENT, owner.key().as_ref()], bump)]
+    pub blocked_recipient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct BurnNft<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    // nft_info.owner can be stale after a plain spl transfer, the token account is what counts
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump,
+        close = owner
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        address = mint_key,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = owner,
+        token::token_program = token_program,
+    )]
+    pub owner_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// metaplex metadata, needed for pnfts and nfts in the collection
+    /// check: validated by token metadata during burn_v1 or unverify
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// pnft only: accounts token metadata needs for burn_v1
+
+    /// check: validated by token metadata during burn_v1
+    #[account(mut)]
+    pub master_edition: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during burn_v1
+    #[account(mut)]
+    pub owner_token_record: Option<UncheckedAccount<'info>>,
+
+    /// check: instructions sysvar, token metadata reads it for pnfts and collections
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
+    /// collection only: the program collection, needed once it exists
+    #[account(
+        mut,
+        seeds = [seeds::COLLECTION],
+        bump = collection.bump
+    )]
+    pub collection: Option<Account<'info, Collection>>,
+
+    /// check: collection mint pda
+    #[account(seeds = [seeds::COLLECTION_MINT], bump)]
+    pub collection_mint: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata against the collection mint
+    #[account(mut)]
+    pub collection_metadata: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata against the collection mint
+    pub collection_master_edition: Option<UncheckedAccount<'info>>,
+
+    /// bridged collection only: instead of `collection` for nfts that joined it
+    #[account(
+        mut,
+        seeds = [seeds::BRIDGED_COLLECTION],
+        bump = bridged_collection.bump
+    )]
+    pub bridged_collection: Option<Account<'info, Collection>>,
+
+    /// check: bridged collection mint pda
+    #[account(seeds = [seeds::BRIDGED_COLLECTION_MINT], bump)]
+    pub bridged_collection_mint: Option<UncheckedAccount<'info>>,
+
+    /// owner stats of the burner, only counts down if the nft was minted to them
+    #[account(
+        mut,
+        seeds = [seeds::OWNER_STATS, owner.key().as_ref()],
+        bump = owner_stats.bump
+    )]
+    pub owner_stats: Option<Account<'info, OwnerStats>>,
+
+    /// index slot of the nft, needed when it has one so it can be tombstoned
+    #[account(
+        mut,
+        seeds = [seeds::INDEX, mint_index.index.to_le_bytes().as_ref()],
+        bump = mint_index.bump,
+        constraint = mint_index.mint == mint_key @ NftError::InvalidMintIndex
+    )]
+    pub mint_index: Option<Account<'info, MintIndex>>,
+
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct CreateMasterEdition<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump
+    )]
+    pub nft_info: Account<'info, NftInfo>,
+
+    #[account(mut, address = mint_key)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// check: metadata of the nft, validated by token metadata
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint_key.as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// check: master edition pda, created by token metadata
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint_key.as_ref(),
+            b"edition",
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the master edition account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(master_mint_key: Pubkey, edition_number: u64, recipient_key: Pubkey)]
+pub struct MintEdition<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    /// nft info of the master, prints copy its name, symbol and uri
+    #[account(
+        seeds = [seeds::NFT_INFO, master_mint_key.as_ref()],
+        bump = master_info.bump
+    )]
+    pub master_info: Box<Account<'info, NftInfo>>,
+
+    #[account(address = master_mint_key)]
+    pub master_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// check: master metadata, validated by token metadata
+    #[account(
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            master_mint_key.as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub master_metadata: UncheckedAccount<'info>,
+
+    /// check: master edition pda, read here for max_supply
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            master_mint_key.as_ref(),
+            b"edition",
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+
+    /// check: edition marker for this edition number, validated by token metadata
+    #[account(mut)]
+    pub edition_mark: UncheckedAccount<'info>,
+
+    #[account(
+        token::mint = master_mint,
+        token::authority = master_owner,
+        constraint = master_token_account.amount == 1 @ NftError::NotHolder
+    )]
+    pub master_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// whoever holds the master decides who gets prints
+    pub master_owner: Signer<'info>,
+
+    /// fresh mint keypair for the print, created and initialized in the handler
+    #[account(mut)]
+    pub mint: Signer<'info>,
+
+    /// check: recipient ata, address checked here and created in the handler
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &recipient.key(),
+            &mint.key(),
+            &token_program.key(),
+        ),
+    )]
+    pub token_account: UncheckedAccount<'info>,
+
+    /// check: wallet the ata is created for, has to be the recipient arg
+    #[account(address = recipient_key)]
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = NftInfo::space(nft_program.max_uri_len),
+        seeds = [seeds::NFT_INFO, mint.key().as_ref()],
+        bump
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    /// check: print metadata, created by token metadata
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// check: print edition pda, created by token metadata
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref(),
+            b"edition",
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub edition: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+
+    /// check: the recipient's block pda, the print fails while it exists
+    #[account(seeds = [seeds::BLOCKED_RECIPIENT, recipient_key.as_ref()], bump)]
+    pub blocked_recipient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct VerifyCreator<'info> {
+    /// only nfts minted or bridged in by this program
+    #[account(
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump
+    )]
+    pub nft_info: Account<'info, NftInfo>,
+
+    /// check: metadata of the nft, token metadata checks the creator is listed
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint_key.as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    pub creator: Signer<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCollection<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Collection::INIT_SPACE,
+        seeds = [seeds::COLLECTION],
+        bump
+    )]
+    pub collection: Account<'info, Collection>,
+
+    /// check: collection mint pda, token metadata creates it during create_v1
+    #[account(mut, seeds = [seeds::COLLECTION_MINT], bump)]
+    pub collection_mint: UncheckedAccount<'info>,
+
+    /// check: validated by token metadata during create_v1
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// check: validated by token metadata during create_v1
+    #[account(mut)]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// check: program ata for the collection nft, created by token metadata during mint_v1
+    #[account(mut)]
+    pub collection_token_account: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the collection accounts, the authority can be a pda that cant
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// check: instructions sysvar, token metadata reads it
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+pub struct CreateBridgedCollection<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Collection::INIT_SPACE,
+        seeds = [seeds::BRIDGED_COLLECTION],
+        bump
+    )]
+    pub bridged_collection: Account<'info, Collection>,
+
+    /// check: bridged collection mint pda, token metadata creates it during create_v1
+    #[account(mut, seeds = [seeds::BRIDGED_COLLECTION_MINT], bump)]
+    pub bridged_collection_mint: UncheckedAccount<'info>,
+
+    /// check: validated by token metadata during create_v1
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// check: validated by token metadata during create_v1
+    #[account(mut)]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// check: program ata for the collection nft, created by token metadata during mint_v1
+    #[account(mut)]
+    pub collection_token_account: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the collection accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// check: instructions sysvar, token metadata reads it
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate_key: Pubkey)]
+pub struct ApproveCollectionDelegate<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    /// check: the delegate key
+    #[account(address = delegate_key)]
+    pub delegate: UncheckedAccount<'info>,
+
+    /// created by token metadata
+    /// check: metaplex collection delegate record for the program update authority
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            collection_mint.key().as_ref(),
+            b"collection_delegate",
+            nft_program.key().as_ref(),
+            delegate.key().as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub delegate_record: UncheckedAccount<'info>,
+
+    /// check: collection mint pda
+    #[account(seeds = [seeds::COLLECTION_MINT], bump)]
+    pub collection_mint: UncheckedAccount<'info>,
+
+    /// check: validated by token metadata against the collection mint
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// check: validated by token metadata against the collection mint
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the delegate record
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// check: instructions sysvar, token metadata reads it
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeCollectionDelegate<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    /// check: the delegate key
+    #[account(address = nft_program.collection_delegate)]
+    pub delegate: UncheckedAccount<'info>,
+
+    /// closed by token metadata
+    /// check: metaplex collection delegate record for the program update authority
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            collection_mint.key().as_ref(),
+            b"collection_delegate",
+            nft_program.key().as_ref(),
+            delegate.key().as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub delegate_record: UncheckedAccount<'info>,
+
+    /// check: collection mint pda
+    #[account(seeds = [seeds::COLLECTION_MINT], bump)]
+    pub collection_mint: UncheckedAccount<'info>,
+
+    /// check: validated by token metadata against the collection mint
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// check: validated by token metadata against the collection mint
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    /// gets the delegate record rent back
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// check: instructions sysvar, token metadata reads it
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct VerifyCollectionItem<'info> {
+    #[account(
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump
+    )]
+    pub nft_info: Account<'info, NftInfo>,
+
+    /// check: metadata of the nft, token metadata checks its collection field
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint_key.as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::COLLECTION],
+        bump = collection.bump
+    )]
+    pub collection: Account<'info, Collection>,
+
+    /// check: collection mint pda
+    #[account(seeds = [seeds::COLLECTION_MINT], bump)]
+    pub collection_mint: UncheckedAccount<'info>,
+
+    /// check: validated by token metadata against the collection mint
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// check: validated by token metadata against the collection mint
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// delegate only: the delegate record of the verifier
+    /// check: metaplex collection delegate record, checked by token metadata
+    #[account(
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            collection_mint.key().as_ref(),
+            b"collection_delegate",
+            nft_program.key().as_ref(),
+            verifier.key().as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub delegate_record: Option<UncheckedAccount<'info>>,
+
+    /// the program authority or the approved collection delegate, pays for the
+    /// upgrade of an older nft info
+    #[account(mut)]
+    pub verifier: Signer<'info>,
+
+    /// check: instructions sysvar, token metadata reads it
+    #[account(address = sysvar::instructions::id())]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_key: Pubkey)]
+pub struct SetAttributes<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, mint_key.as_ref()],
+        bump = nft_info.bump
+    )]
+    pub nft_info: Account<'info, NftInfo>,
+
+    /// pays for the upgrade of an older nft info
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        address = mint_key,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        token::mint = mint,
+        token::authority = owner,
+        token::token_program = token_program,
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// check: metaplex metadata pda of the mint, empty for extension mode nfts
+    #[account(
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint_key.as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetDeferCollectionVerify<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTree<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    /// check: bubblegum tree config pda, created by bubblegum
+    #[account(
+        mut,
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = BUBBLEGUM_PROGRAM_ID
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// check: allocated by the authority for the compression program, bubblegum initializes it
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    /// pays for the tree config
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// check: bubblegum program
+    #[account(address = BUBBLEGUM_PROGRAM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    /// check: spl account compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// check: spl noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetTree<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCompressedMode<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated,
+        constraint = is_authority(&nft_program, authority.key()) @ NftError::Unauthorized
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(params: TransferParams)]
+pub struct TransferCompressedToZetachain<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Account<'info, NftProgramState>,
+
+    /// registry entry of the destination chain
+    #[account(
+        seeds = [seeds::CHAIN, params.destination_chain_id.to_le_bytes().as_ref()],
+        bump = chain_info.bump
+    )]
+    pub chain_info: Account<'info, ChainInfo>,
+
+    /// gets the chain fee
+    #[account(
+        mut,
+        seeds = [seeds::TREASURY],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [seeds::NFT_INFO, nft_info.mint.as_ref()],
+        bump = nft_info.bump,
+        constraint = nft_info.owner == owner.key(),
+        constraint = nft_info.compressed @ NftError::NotCompressed
+    )]
+    pub nft_info: Account<'info, NftInfo>,
+
+    /// leaf owner, signs the burn
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// check: validated by bubblegum during burn
+    #[account(mut)]
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// check: has to be the configured program tree
+    #[account(mut, address = nft_program.merkle_tree)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// check: bubblegum program
+    #[account(address = BUBBLEGUM_PROGRAM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    /// check: spl account compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// check: spl noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// check: block pda of the nft, the transfer fails while it exists
+    #[account(seeds = [seeds::BLOCKED_MINT, nft_info.mint.as_ref()], bump)]
+    pub blocked_mint: UncheckedAccount<'info>,
+
+    /// token fee only: the chain's fee mint
+    pub fee_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// token fee only: the owner's token account of the fee mint, pays the fee
+    #[account(mut)]
+    pub owner_fee_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// token fee only: the treasury's ata of the fee mint, see set_chain_fee_currency
+    #[account(mut)]
+    pub fee_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// token fee only: token program of the fee mint
+    pub fee_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// account struct for the on_call function
+/// handles incoming cross-chain calls from the gateway program. the token accounts of
+/// the message type follow as remaining accounts, see InboundTransfer and InboundUnlock
+#[event_cpi]
+#[derive(Accounts)]
+pub struct OnCall<'info> {
+    /// nft program state account
+    #[account(
+        mut,
+        seeds = [seeds::NFT_PROGRAM],
+        bump = nft_program.bump,
+        constraint = state_is_current(&nft_program) @ NftError::StateNotMigrated
+    )]
+    pub nft_program: Box<Account<'info, NftProgramState>>,
+
+    /// registry entry of the chain the call comes from, its connected contract
+    /// has to be the sender
+    #[account(
+        seeds = [seeds::CHAIN, chain_info.chain_id.to_le_bytes().as_ref()],
+        bump = chain_info.bump
+    )]
+    pub chain_info: Account<'info, ChainInfo>,
+
+    /// mint account for the nft, a pda under the given token program
+    /// check: checked against the mint in the message by the handler (the instruction
+    /// data starts with the gateway's amount, not a mint key). a transfer uses the pda
+    /// of the origin mint, created and initialized the first time the nft arrives, an
+    /// unlock the mint the nft was locked under
+    #[account(mut)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// nft info account to track nft metadata and ownership
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = NftInfo::space(nft_program.max_uri_len),
+        seeds = [seeds::NFT_INFO, mint.key().as_ref()],
+        bump
+    )]
+    pub nft_info: Box<Account<'info, NftInfo>>,
+
+    /// metadata account for the nft (metaplex), not needed in extension mode
+    /// check: this is the metadata account, dont use directly
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// transfers, pnft unlocks, unlocks to an owner without an ata yet and compressed
+    /// mode: the wallet or pda getting the nft
+    /// check: checked against the recipient or nft_info.owner in the handler
+    pub nft_owner: Option<UncheckedAccount<'info>>,
+
+    /// compressed mode only: bubblegum tree config of the program tree
+    /// check: validated by bubblegum during mint_v1
+    #[account(mut)]
+    pub tree_config: Option<UncheckedAccount<'info>>,
+
+    /// check: has to be the configured program tree
+    #[account(mut, address = nft_program.merkle_tree)]
+    pub merkle_tree: Option<UncheckedAccount<'info>>,
+
+    /// check: bubblegum program
+    #[account(address = BUBBLEGUM_PROGRAM_ID)]
+    pub bubblegum_program: Option<UncheckedAccount<'info>>,
+
+    /// check: spl account compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: Option<UncheckedAccount<'info>>,
+
+    /// check: spl noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: Option<UncheckedAccount<'info>>,
+
+    /// metaplex transfers: the master edition created with the metadata. pnft unlocks:
+    /// the master edition, with both token records below
+    /// check: validated by token metadata during create_master_edition_v3 and transfer_v1
+    #[account(mut)]
+    pub master_edition: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during transfer_v1
+    #[account(mut)]
+    pub owner_token_record: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata during transfer_v1
+    #[account(mut)]
+    pub program_token_record: Option<UncheckedAccount<'info>>,
+
+    /// collection only: the program collection, needed once it exists
+    #[account(
+        mut,
+        seeds = [seeds::COLLECTION],
+        bump = collection.bump
+    )]
+    pub collection: Option<Account<'info, Collection>>,
+
+    /// check: collection mint pda
+    #[account(seeds = [seeds::COLLECTION_MINT], bump)]
+    pub collection_mint: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata against the collection mint
+    #[account(mut)]
+    pub collection_metadata: Option<UncheckedAccount<'info>>,
+
+    /// check: validated by token metadata against the collection mint
+    pub collection_master_edition: Option<UncheckedAccount<'info>>,
+
+    /// bridged collection only: new nfts join it instead of `collection` once it exists
+    #[account(
+        mut,
+        seeds = [seeds::BRIDGED_COLLECTION],
+        bump = bridged_collection.bump
+    )]
+    pub bridged_collection: Option<Account<'info, Collection>>,
+
+    /// check: bridged collection mint pda
+    #[account(seeds = [seeds::BRIDGED_COLLECTION_MINT], bump)]
+    pub bridged_collection_mint: Option<UncheckedAccount<'info>>,
+
+    /// transfers only: next slot in the enumerable index, only on the first arrival
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MintIndex::INIT_SPACE,
+        seeds = [seeds::INDEX, nft_program.index_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub mint_index: Option<Account<'info, MintIndex>>,
+
+    /// payer for account creation
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// system accounts
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+
+    /// instruction sysvar account for verifying caller is gateway
+    /// check: this is used to verify the caller is the gateway program
+    #[account(address = sysvar::instructions::id())]
+    pub instruction_sysvar_account: UncheckedAccount<'info>,
+
+    /// dead letter record [b"dead-letter", message hash]: on_call creates it for a
+    /// message that breaks a business rule, retry_dead_letter reads and closes it
+    /// check: address and contents checked in the handler
+    #[account(mut)]
+    pub dead_letter: Option<UncheckedAccount<'info>>,
+
+    /// ordered delivery only: pending record [b"pending-nonce", nonce], on_call creates
+    /// it for a message ahead of its turn, drain_pending reads and closes it
+    /// check: address and contents checked in the handler
+    #[account(mut)]
+    pub pending_nonce: Option<UncheckedAccount<'info>>,
+
+    /// batch record [b"inbound-batch", batch id]: on_call creates it for a batch root,
+    /// claim_inbound marks leaves claimed in it
+    /// check: address and contents checked in the handler
+    #[account(mut)]
+    pub inbound_batch: Option<UncheckedAccount<'info>>,
+
+    /// block pda [b"blocked-recipient", recipient] of the recipient, nft_info.owner
+    /// for unlocks. a message for a blocked wallet goes to a dead letter
+    /// check: address checked against the planned recipient in the handler
+    pub blocked_recipient: UncheckedAccount<'info>,
+
+    /// check: block pda of the mint, nothing for a blocked mint is taken in
+    #[account(seeds = [seeds::BLOCKED_MINT, mint.key().as_ref()], bump)]
+    pub blocked_mint: UncheckedAccount<'info>,
+
+    /// claim mode only: pending claim [b"pending-claim", nonce], on_call creates it
+    /// for a transfer, claim_nft reads and closes it
+    /// check: address and contents checked in the handler
+    #[account(mut)]
+    pub pending_claim: Option<UncheckedAccount<'info>>,
+
+    /// failed inbound record [b"failed-inbound", nonce]: on_call creates it for a
+    /// message that breaks a business rule when no dead letter is passed,
+    /// retry_inbound reads and closes it
+    /// check: address and contents checked in the handler
+    #[account(mut)]
+    pub failed_inbound: Option<UncheckedAccount<'info>>,
+}
+
+/// what the typed inbound accounts are checked against, on_call hands it to anchor
+/// like instruction args
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct InboundKeys {
+    pub nft_program: Pubkey,
+    pub mint: Pubkey,
+    /// the recipient of a transfer, nft_info.owner for an unlock
+    pub owner: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// the token account a transfer adds after the on_call accounts, parsed once the
+/// message passed plan_inbound. none in compressed mode
+#[derive(Accounts)]
+#[instruction(keys: InboundKeys)]
+pub struct InboundTransfer<'info> {
+    /// the recipient's ata, created if needed
+    /// check: the ata of the recipient in the message, created by the handler
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &keys.owner,
+            &keys.mint,
+            &keys.token_program,
+        ) @ NftError::InvalidRecipient
+    )]
+    pub recipient_token_account: UncheckedAccount<'info>,
+}
+
+/// the token accounts an unlock adds after the on_call accounts, parsed once the
+/// message passed plan_inbound, so the nft is locked and the escrow exists. none for
+/// a compressed nft
+#[derive(Accounts)]
+#[instruction(keys: InboundKeys)]
+pub struct InboundUnlock<'info> {
+    /// the escrow, the program's ata the locked nft sits in
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &keys.nft_program,
+            &keys.mint,
+            &keys.token_program,
+        ) @ NftError::EscrowMismatch,
+        constraint = program_token_account.mint == keys.mint @ NftError::EscrowMismatch,
+        constraint = program_token_account.owner == keys.nft_program @ NftError::EscrowMismatch
+    )]
+    pub program_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// the owner's ata the nft goes back to, created if needed
+    /// check: the ata of nft_info.owner, created by the handler
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &keys.owner,
+            &keys.mint,
+            &keys.token_program,
+        ) @ NftError::InvalidRecipient
+    )]
+    pub owner_token_account: UncheckedAccount<'info>,
+}
+
+// layout of NftProgramState, 1 is the layout from before the version field
+pub const STATE_VERSION: u8 = 18;
+// layout of NftInfo, 1 is the layout from before the version field
+pub const NFT_INFO_VERSION: u8 = 7;
+// layout of ProgramStats
+pub const STATS_VERSION: u8 = 3;
+// layout of NftInfoView
+pub const NFT_INFO_VIEW_VERSION: u8 = 1;
+// uri cap in NftInfoView, the whole view has to fit the 1024 bytes of return data
+pub const MAX_VIEW_URI_LEN: usize = 800;
+// get_version feature bits, set when the build has the cargo feature
+pub const FEATURE_COMPRESSED: u64 = 1 << 0;
+// programs compressed mode talks to
+pub const BUBBLEGUM_PROGRAM_ID: Pubkey = pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+pub const SPL_ACCOUNT_COMPRESSION_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+pub const SPL_NOOP_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+// uri prefixes allowed out of the box, authority can change them later
+pub const DEFAULT_URI_PREFIXES: [&str; 3] = ["https://", "ipfs://", "ar://"];
+// on-chain attribute limits, must match the max_len on NftInfo.attributes and Attribute
+pub const MAX_ATTRIBUTES: usize = 8;
+pub const MAX_ATTRIBUTE_KEY_LEN: usize = 16;
+pub const MAX_ATTRIBUTE_VALUE_LEN: usize = 32;
+// seller fee cap, 100% in basis points
+pub const MAX_ROYALTY_BPS: u16 = 10_000;
+// symbol for inbound nfts that arrive without one, authority can change it
+pub const DEFAULT_INBOUND_SYMBOL: &str = "UNFT";
+// compliance reasons, must match the max_len on ComplianceAction.reason
+pub const MAX_REASON_LEN: usize = 64;
+// chain registry names
+pub const MAX_CHAIN_NAME_LEN: usize = 32;
+// ordered delivery: how far past the expected nonce a message can arrive and wait
+pub const PENDING_NONCE_WINDOW: u64 = 32;
+// replay window: nonces this far below the highest one taken or more are refused,
+// must match the bits in NftProgramState.nonce_window
+pub const NONCE_WINDOW: u64 = 256;
+// same as metaplex MAX_CREATOR_LIMIT
+pub const MAX_CREATORS: usize = 5;
+// name and symbol limits, same as metaplex
+pub const MAX_NAME_LEN: usize = 32;
+pub const MAX_SYMBOL_LEN: usize = 10;
+// uri length limits, metaplex caps its own copy at 200 no matter what we store
+pub const DEFAULT_MAX_URI_LEN: u16 = 200;
+pub const MAX_URI_LEN_CAP: u16 = 1024;
+pub const MAX_METAPLEX_URI_LEN: usize = 200;
+// must match the max_len on NftProgramState.allowed_uri_prefixes
+pub const MAX_URI_PREFIXES: usize = 4;
+pub const MAX_URI_PREFIX_LEN: usize = 16;
+// must match the max_len on UriRewrite
+pub const MAX_URI_REWRITE_PATTERN_LEN: usize = 32;
+pub const MAX_URI_REWRITE_REPLACEMENT_LEN: usize = 96;
+// stands for the rest of the uri in a uri rewrite
+pub const URI_REWRITE_PLACEHOLDER: &str = "{cid}";
+
+// program state, stores main info for the contract
+#[account]
+#[derive(InitSpace)]
+pub struct NftProgramState {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey, // nominated, becomes authority once it accepts, default = none
+    pub gateway: Pubkey,
+    pub total_supply: u64, // nfts that currently exist, burns take it down
+    pub mint_count: u64, // local mints ever made, never goes down so pda mint seeds dont repeat
+    pub index_count: u64, // index slots handed out, local and inbound, burns leave a tombstone
+    pub nonce: u64, // highest inbound nonce taken, for replay protection
+    pub mint_fee_lamports: u64, // charged per local mint, 0 = free
+    pub max_mints_per_wallet: u64, // local mints one recipient can get, 0 = no limit
+    pub public_mint: bool, // when false only minters can call mint_nft
+    pub mint_paused: bool, // stops local and inbound mints, bridging and unlocks keep going
+    pub default_token_standard: NftStandard, // used when mint_nft doesnt pass one
+    #[max_len(10)]
+    pub default_symbol: String, // inbound nfts with an empty symbol get this one
+    pub inbound_string_policy: InboundStringPolicy, // what to do with inbound names/symbols over the limits
+    pub max_uri_len: u16, // longest uri we store, nft info is sized from this
+    #[max_len(4, 16)]
+    pub allowed_uri_prefixes: Vec<String>, // uris must start with one of these
+    pub metadata_mode: MetadataMode, // where new nfts keep their metadata
+    pub compressed_mode: bool, // inbound transfers mint bubblegum leaves instead of full nfts
+    pub merkle_tree: Pubkey, // tree compressed mode mints into
+    pub collection_mint: Pubkey, // program collection new nfts join, default = none yet
+    pub collection_delegate: Pubkey, // external key that can verify into the collection, default = none
+    pub defer_collection_verify: bool, // new nfts join the collection unverified, verified later
+    pub bridged_collection_mint: Pubkey, // collection inbound nfts join instead, default = the program one
+    pub strict_metadata_hash: bool, // reject inbound hash mismatches instead of flagging them
+    pub clamp_royalty_bps: bool, // clamp inbound royalties over 10_000 bps instead of rejecting
+    pub default_royalty_bps: u16, // royalty when a mint or inbound message doesnt give one
+    pub bump: u8,
+    // new fields go below this one, see STATE_VERSION and upgrade_state
+    pub version: u8, // layout version, migrate_state brings older accounts up to STATE_VERSION
+    pub default_royalty_recipient: Pubkey, // paid when no creators/message recipient, default = none
+    pub locked_count: u64, // nfts locked for a bridge transfer right now
+    pub sweep_rent_destination: SweepRentDestination, // who gets the rent of swept nft infos
+    pub lazy_metadata: bool, // inbound nfts skip the metaplex metadata until materialize_metadata
+    pub dead_letter_retry: DeadLetterRetry, // who can call retry_dead_letter and retry_inbound
+    pub allow_program_recipients: bool, // nfts can be minted or sent to the program id or state pda
+    pub ordered_delivery: bool, // inbound messages ahead of the expected nonce wait for drain_pending
+    pub nonce_window: [u64; 4], // nonces taken at or below `nonce`, see accept_message_nonce
+    pub tss_address: [u8; 20], // zetachain tss that signs inbound messages, zeros = none
+    pub require_tss_signature: bool, // on_call needs a tss signature over the message
+    pub admin_nonce: u64, // next nonce execute_signed_admin takes
+    pub uri_rewrite: Option<UriRewrite>, // inbound metadata gets the rewritten uri, None = off
+    #[max_len(200)] // must match MAX_METAPLEX_URI_LEN
+    pub placeholder_uri: String, // inbound nfts with an invalid uri get this one, empty = off
+    pub claim_mode: bool, // inbound transfers wait as pending claims for their recipient
+    pub inbound_metadata_mutable: bool, // inbound metaplex metadata is created mutable, see freeze_metadata
+    pub immutable_bridged_count: u64, // bridged nfts known to have immutable metaplex metadata
+    pub max_supply: u64, // cap on total_supply for local and inbound mints, 0 = no cap
+    pub outbound_nonce: u64, // last nonce an outbound transfer got, the program counts them up
+}
+
+// minter role, existence of this pda means the wallet can mint
+#[account]
+#[derive(InitSpace)]
+pub struct MinterRole {
+    pub minter: Pubkey,
+    pub bump: u8,
+}
+
+// existence of this pda means nothing gets minted, delivered or unlocked to the
+// wallet. [b"blocked-recipient", recipient]
+#[account]
+#[derive(InitSpace)]
+pub struct BlockedRecipient {
+    pub recipient: Pubkey,
+    pub blocked_at: i64,
+    pub bump: u8,
+}
+
+// existence of this pda keeps the nft out of the bridge both ways, the token itself
+// is left alone. [b"blocked-mint", mint]
+#[account]
+#[derive(InitSpace)]
+pub struct BlockedMint {
+    pub mint: Pubkey,
+    #[max_len(64)] // must match MAX_REASON_LEN
+    pub reason: String,
+    pub blocked_at: i64,
+    pub bump: u8,
+}
+
+// registered nfts verified into this collection can be bridged, ours dont need one.
+// [b"approved-collection", collection_mint]
+#[account]
+#[derive(InitSpace)]
+pub struct ApprovedCollection {
+    pub collection_mint: Pubkey,
+    pub approved_at: i64,
+    pub bump: u8,
+}
+
+// program collection, size mirrors the sized collection on the collection metadata
+// so clients dont have to parse metaplex accounts
+#[account]
+#[derive(InitSpace)]
+pub struct Collection {
+    pub mint: Pubkey,
+    pub collection_size: u64,
+    pub bump: u8,
+}
+
+// chain registry entry, one per chain we bridge with, seeds [b"chain", chain_id le]
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct ChainInfo {
+    pub chain_id: u64,
+    #[max_len(32)]
+    pub name: String,
+    pub chain_kind: ChainKind,
+    pub enabled: bool, // off stops transfers to and from the chain
+    pub recipient_len: u8, // address length on the chain, recipients are left aligned in 32 bytes
+    pub connected: [u8; 32], // our contract on that chain, left aligned like recipients
+    pub flat_fee_lamports: u64, // charged per transfer out to the chain
+    pub gas_fee_lamports: u64, // destination gas, charged on top of the flat fee
+    pub inbound_enabled: bool, // off stops only transfers from the chain, see set_chain_inbound
+    pub inbound_paused: bool, // messages from the chain go to dead letters
+    pub fee_token: Option<FeeToken>, // flat fee in an spl token instead, see set_chain_fee_currency
+    pub bump: u8,
+}
+
+impl ChainInfo {
+    fn apply(&mut self, config: ChainConfig) {
+        self.name = config.name;
+        self.chain_kind = config.chain_kind;
+        self.enabled = config.enabled;
+        self.recipient_len = config.recipient_len;
+        self.connected = config.connected;
+    }
+}
+
+// index -> mint, so clients can enumerate without scanning every nft info
+#[account]
+#[derive(InitSpace)]
+pub struct MintIndex {
+    pub index: u64,
+    pub mint: Pubkey, // default once the nft is burned
+    pub bump: u8,
+}
+
+// inbound message that decoded fine but broke a business rule, kept so it can be
+// retried once the problem is fixed. [b"dead-letter", message hash]
+#[account]
+#[derive(InitSpace)]
+pub struct DeadLetter {
+    pub message_hash: [u8; 32], // see canonical_message_hash
+    pub source_chain_id: u64,
+    pub sender: [u8; 20],
+    pub reason: DeadLetterReason,
+    pub slot: u64, // when on_call turned it down
+    pub bump: u8,
+    #[max_len(0)]
+    pub payload: Vec<u8>, // the raw message data, the account is sized for it
+}
+
+impl DeadLetter {
+    pub fn space(payload_len: usize) -> usize {
+        8 + DeadLetter::INIT_SPACE + payload_len
+    }
+}
+
+// inbound message that decoded fine but broke a business rule, like a dead letter
+// but only the hash is kept. retry_inbound takes the message again.
+// [b"failed-inbound", nonce]
+#[account]
+#[derive(InitSpace)]
+pub struct FailedInbound {
+    pub nonce: u64,
+    pub message_hash: [u8; 32], // see canonical_message_hash
+    pub source_chain_id: u64,
+    pub sender: [u8; 20],
+    pub reason: DeadLetterReason,
+    pub slot: u64, // when on_call turned it down
+    pub bump: u8,
+}
+
+// local mint count per recipient wallet
+#[account]
+#[derive(InitSpace)]
+pub struct OwnerStats {
+    pub owner: Pubkey,
+    pub minted: u64, // goes down again when they burn one that was minted to them
+    pub bump: u8,
+}
+
+// last compliance action on a holder's token account for one mint
+#[account]
+#[derive(InitSpace)]
+pub struct ComplianceAction {
+    pub mint: Pubkey,
+    pub holder: Pubkey,
+    pub frozen: bool,
+    #[max_len(64)] // must match MAX_REASON_LEN
+    pub reason: String,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+// an inbound nonce the authority gave up on with skip_message
+#[account]
+#[derive(InitSpace)]
+pub struct SkippedMessage {
+    pub nonce: u64,
+    pub authority: Pubkey,
+    #[max_len(64)] // must match MAX_REASON_LEN
+    pub reason: String,
+    pub skipped_at: i64,
+    pub bump: u8,
+}
+
+// inbound message ordered delivery holds back until the nonces before it are taken,
+// only the hash is kept. [b"pending-nonce", nonce]
+#[account]
+#[derive(InitSpace)]
+pub struct PendingNonce {
+    pub nonce: u64,
+    pub message_hash: [u8; 32], // see canonical_message_hash
+    pub source_chain_id: u64,
+    pub sender: [u8; 20],
+    pub slot: u64, // when on_call held it back
+    pub bump: u8,
+}
+
+// an inbound transfer claim mode keeps for its recipient instead of minting it,
+// claim_nft mints it and transfer_claim_to_zetachain sends it on unminted.
+// [b"pending-claim", nonce]
+#[account]
+#[derive(InitSpace)]
+pub struct PendingClaim {
+    pub nonce: u64,
+    pub recipient: Pubkey, // the only one who can claim it or send it on
+    pub mint: Pubkey, // the bridged mint the nft gets, not created yet
+    pub message_hash: [u8; 32], // see canonical_message_hash
+    pub source_chain_id: u64,
+    pub sender: [u8; 20],
+    pub slot: u64, // when on_call recorded it
+    pub bump: u8,
+    #[max_len(0)]
+    pub payload: Vec<u8>, // the raw message data, name, uri and the rest come from it
+}
+
+impl PendingClaim {
+    pub fn space(payload_len: usize) -> usize {
+        8 + PendingClaim::INIT_SPACE + payload_len
+    }
+}
+
+// root of an inbound batch delivered through on_call, the transfers under it get
+// claimed one by one with claim_inbound. [b"inbound-batch", batch id]
+#[account]
+#[derive(InitSpace)]
+pub struct InboundBatch {
+    pub batch_id: Pubkey, // the mint field of the root message
+    pub root: [u8; 32], // see wire::batch_leaf_hash
+    pub leaf_count: u64,
+    pub claimed_count: u64,
+    pub nonce: u64, // of the root message, every leaf carries it too
+    pub message_hash: [u8; 32], // of the root message
+    pub source_chain_id: u64,
+    pub sender: [u8; 20],
+    pub slot: u64, // when on_call recorded it
+    pub bump: u8,
+    #[max_len(0)]
+    pub claimed: Vec<u8>, // a bit per leaf, the account is sized for leaf_count
+}
+
+impl InboundBatch {
+    pub fn space(leaf_count: u64) -> usize {
+        8 + InboundBatch::INIT_SPACE + leaf_count.div_ceil(8) as usize
+    }
+}
+
+// treasury pda, just holds the lamports collected from fees
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub bump: u8,
+}
+
+// nft tracking info, stores all the data for each nft
+#[account]
+#[derive(InitSpace)]
+pub struct NftInfo {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    #[max_len(200)] // default, real allocation follows max_uri_len, see NftInfo::space
+    pub metadata_uri: String,
+    #[max_len(32)]
+    pub name: String,
+    #[max_len(10)]
+    pub symbol: String,
+    pub is_locked: bool,
+    pub cross_chain_recipient: [u8; 32],
+    pub token_standard: NftStandard, // pnfts need token metadata transfers to lock/unlock
+    pub compressed: bool, // bubblegum leaf, mint is only the derived id then
+    pub asset_id: Pubkey, // bubblegum asset id of the current leaf, default if not compressed
+    pub in_collection: bool, // verified into the program collection
+    pub bridged_collection: bool, // in_collection means the bridged collection, not the program one
+    pub master_mint: Pubkey, // prints only: the master edition mint, default otherwise
+    pub edition: u64, // prints only: edition number, 0 otherwise
+    pub metadata_hash: Option<[u8; 32]>, // sha256 of the metadata json, if the minter gave one
+    pub hash_mismatch: bool, // last crossing came with a different hash than the one on record
+    pub minted_to: Pubkey, // local mints only: recipient whose owner stats counted it
+    pub bridged: bool, // created by on_call, local mints and prints never are
+    pub index: Option<u64>, // slot in the enumerable index, prints dont get one
+    pub royalty_bps: u16, // seller fee from the origin chain, 0 for local mints
+    pub royalty_recipient: Option<Pubkey>, // who the origin chain pays royalties to, if it said
+    #[max_len(8)]
+    pub attributes: Vec<Attribute>, // on-chain traits, see MAX_ATTRIBUTES
+    pub bump: u8,
+    // new fields go below this one, see NFT_INFO_VERSION and upgrade_nft_info
+    pub version: u8, // layout version, older accounts get upgraded when something changes them
+    pub metadata_created: bool, // false while a lazy inbound nft has no metaplex metadata yet
+    pub needs_metadata_repair: bool, // arrived with an invalid uri, has the placeholder until repair_metadata
+    pub metadata_immutable: bool, // bridged only: immutable metaplex metadata, counted in immutable_bridged_count
+    pub external: bool, // minted outside the program, tracked since register_existing_nft
+    pub lock_nonce: u64, // outbound nonce of the transfer that locked it last, 0 if none since v7
+}
+
+// what kind of chain a registry entry is, for clients and relayers
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum ChainKind {
+    Evm,
+    Bitcoin,
+    Solana,
+    Other,
+}
+
+/// what the flat part of a chain fee is paid in, set_chain_fee_currency argument
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeCurrency {
+    /// lamports into the treasury, the same flat fee set_chain_fees sets
+    Sol(u64),
+    /// base units of an spl token into the treasury's ata of the mint
+    Spl { mint: Pubkey, amount: u64 },
+}
+
+// flat chain fee in an spl token, the chain entry keeps it like this
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct FeeToken {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+// register_chain / update_chain arguments, everything in a chain entry but its id
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ChainConfig {
+    pub name: String,
+    pub chain_kind: ChainKind,
+    pub enabled: bool,
+    pub recipient_len: u8,
+    pub connected: [u8; 32],
+}
+
+// mint_nft / mint_nft_pda arguments. borsh writes a struct as its fields in order,
+// so the instruction data is the same as with the old positional args. new fields
+// go at the end
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MintParams {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub recipient: Pubkey,
+    pub token_standard: Option<NftStandard>, // program default when None
+    pub creators: Option<Vec<NftCreator>>,
+    pub metadata_hash: Option<[u8; 32]>,
+    pub attributes: Option<Vec<Attribute>>,
+    pub royalty_bps: Option<u16>, // program default royalty when None
+}
+
+// transfer_to_zetachain / transfer_compressed_to_zetachain arguments
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferParams {
+    pub destination_chain_id: u64,
+    pub recipient: [u8; 32], // left aligned, the chain entry says how many bytes count
+    // deprecated and ignored, the program numbers outbound transfers itself. it stays
+    // so the args after it (transfer_compressed_to_zetachain) keep their offsets
+    pub nonce: u64,
+}
+
+// on_call arguments the way the gateway sends them, data is a borsh CrossChainMessage
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InboundMessage {
+    pub amount: u64,
+    pub sender: [u8; 20],
+    pub data: Vec<u8>,
+}
+
+// which metaplex token standard an nft was minted with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum NftStandard {
+    NonFungible,
+    ProgrammableNonFungible,
+}
+
+/// a creator to list on a new nft, shares across all creators add up to 100
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NftCreator {
+    pub address: Pubkey,
+    pub share: u8,
+}
+
+/// how on_call rewrites inbound uris for the metadata, see set_uri_rewrite
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct UriRewrite {
+    /// matched on the part before `{cid}`, e.g. `ipfs://{cid}`
+    #[max_len(32)] // must match MAX_URI_REWRITE_PATTERN_LEN
+    pub pattern: String,
+    /// `{cid}` gets the rest of the uri, e.g. `https://gateway.example/ipfs/{cid}`
+    #[max_len(96)] // must match MAX_URI_REWRITE_REPLACEMENT_LEN
+    pub replacement: String,
+}
+
+/// what on_call does with an inbound name or symbol longer than metaplex allows
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum InboundStringPolicy {
+    /// cut at a utf-8 boundary to 32/10 bytes
+    Truncate,
+    /// fail with StringTooLong, nothing is consumed so the message can be retried
+    Reject,
+}
+
+/// where name, symbol and uri live for newly minted nfts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum MetadataMode {
+    /// metaplex metadata account, needs the metadata and token metadata program accounts
+    Metaplex,
+    /// token-2022 metadata extension stored on the mint itself, needs the token-2022
+    /// program and no metaplex accounts. only plain nfts, pnfts need metaplex
+    TokenExtension,
+}
+
+/// which path gave an nft back out of escrow, see NftUnlocked
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnlockReason {
+    /// the authority gave it back with unlock_nft, nothing came back for it
+    ForceUnlock,
+    /// it came back from another chain through on_call
+    Returned,
+}
+
+/// who gets the rent of nft infos closed by sweep_orphaned_info
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum SweepRentDestination {
+    /// the program treasury, withdrawable like mint fees
+    Treasury,
+    /// whoever called the sweep, pays keepers for cleaning up
+    Caller,
+}
+
+/// admin changes the authority can sign offline for execute_signed_admin, each does
+/// what the setter of the same name does
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum AdminAction {
+    SetMintPaused { mint_paused: bool },
+    SetMintFee { mint_fee_lamports: u64 },
+}
+
+/// who can call retry_dead_letter and retry_inbound
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum DeadLetterRetry {
+    /// only the program authority
+    Authority,
+    /// anyone, they get the rent of the record
+    Anyone,
+}
+
+/// business rule an inbound message broke, kept in its dead letter record
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum DeadLetterReason {
+    /// a transfer to the default pubkey, or the program while that isnt allowed
+    InvalidRecipient,
+    /// name, symbol, uri, attributes or royalty the program doesnt take
+    InvalidMetadata,
+    /// the mint collides with a local nft
+    NftInfoCollision,
+    /// strict mode and the metadata hash doesnt match the one on record
+    MetadataHashMismatch,
+    /// an unlock for an nft that isnt locked here
+    NotLocked,
+    /// the chain it came from was paused for inbound, nothing was checked yet
+    SourceChainPaused,
+    /// the recipient, or the owner for an unlock, is blocked
+    RecipientBlocked,
+    /// a first arrival while mints were paused
+    MintPaused,
+    /// a first arrival with total_supply at max_supply
+    SupplyCapReached,
+}
+
+impl DeadLetterReason {
+    // plan_inbound only checks business rules, the metadata checks have the most
+    // errors so everything not listed here comes from them
+    pub fn from_error(error: &Error) -> Self {
+        let code = match error {
+            Error::AnchorError(error) => error.error_code_number,
+            Error::ProgramError(_) => return DeadLetterReason::InvalidMetadata,
+        };
+        if code == u32::from(NftError::InvalidRecipient) {
+            DeadLetterReason::InvalidRecipient
+        } else if code == u32::from(NftError::NftInfoCollision) {
+            DeadLetterReason::NftInfoCollision
+        } else if code == u32::from(NftError::MetadataHashMismatch) {
+            DeadLetterReason::MetadataHashMismatch
+        } else if code == u32::from(NftError::TokenNotLocked) {
+            DeadLetterReason::NotLocked
+        } else if code == u32::from(NftError::SourceChainPaused) {
+            DeadLetterReason::SourceChainPaused
+        } else if code == u32::from(NftError::RecipientBlocked) {
+            DeadLetterReason::RecipientBlocked
+        } else if code == u32::from(NftError::MintPaused) {
+            DeadLetterReason::MintPaused
+        } else if code == u32::from(NftError::SupplyCapReached) {
+            DeadLetterReason::SupplyCapReached
+        } else {
+            DeadLetterReason::InvalidMetadata
+        }
+    }
+}
+
+impl NftInfo {
+    // INIT_SPACE assumes a 200 byte uri, swap that for the configured max
+    pub fn space(max_uri_len: u16) -> usize {
+        8 + NftInfo::INIT_SPACE - DEFAULT_MAX_URI_LEN as usize + max_uri_len as usize
+    }
+}
+
+// what get_version returns, semver from the crate version plus what the build
+// can do. FEATURE_* bits make up features_bitmask
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgramVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    pub message_version: u8, // layout of CrossChainMessage, see MESSAGE_VERSION
+    pub features_bitmask: u64,
+}
+
+impl ProgramVersion {
+    pub fn current() -> Self {
+        // cargo always sets these to plain numbers
+        let part = |value: &str| value.parse().unwrap_or_default();
+        let mut features = 0;
+        if cfg!(feature = "compressed") {
+            features |= FEATURE_COMPRESSED;
+        }
+        Self {
+            major: part(env!("CARGO_PKG_VERSION_MAJOR")),
+            minor: part(env!("CARGO_PKG_VERSION_MINOR")),
+            patch: part(env!("CARGO_PKG_VERSION_PATCH")),
+            message_version: MESSAGE_VERSION,
+            features_bitmask: features,
+        }
+    }
+}
+
+// what get_stats returns. version comes first so clients can tell which fields
+// follow, new ones go at the end with a STATS_VERSION bump
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgramStats {
+    pub version: u8,
+    pub total_supply: u64,
+    pub locked_count: u64,
+    pub nonce: u64, // highest inbound nonce taken
+    pub mint_paused: bool,
+    pub treasury_lamports: u64, // whole balance, rent exempt minimum included
+    pub immutable_bridged_count: u64, // bridged nfts known to have immutable metaplex metadata
+    pub outbound_nonce: u64, // nonce of the last outbound transfer
+}
+
+// what get_nft_info returns. version comes first so clients can tell which fields
+// follow, new ones go at the end of Found with a NFT_INFO_VIEW_VERSION bump
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NftInfoView {
+    pub version: u8,
+    pub mint: Pubkey,
+    pub status: NftInfoStatus,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum NftInfoStatus {
+    /// no nft info for the mint, never minted here, burned or swept
+    NotFound,
+    Found {
+        owner: Pubkey,
+        is_locked: bool,
+        bridged: bool, // came in through on_call, the origin chain isnt recorded
+        uri: String, // cut at MAX_VIEW_URI_LEN
+        uri_truncated: bool,
+        name: String,
+        locked_destination: Option<[u8; 32]>, // recipient on the other chain while locked
+        nonce: u64, // program nonce right now, messages share one nonce
+    },
+}
+
+// crosschain message, see the wire crate for the layout. Pubkey encodes like [u8; 32]
+pub type CrossChainMessage = wire::CrossChainMessage<Pubkey>;
+
+// outbound transfer of an nft we track, everything but the recipient comes from nft info
+pub fn transfer_message(nft_info: &NftInfo, recipient: [u8; 32], nonce: u64) -> CrossChainMessage {
+    CrossChainMessage {
+        message_type: MessageType::Transfer,
+        mint: nft_info.mint,
+        recipient,
+        metadata_uri: nft_info.metadata_uri.clone(),
+        name: nft_info.name.clone(),
+        symbol: nft_info.symbol.clone(),
+        nonce,
+        master_mint: nft_info.master_mint,
+        edition: nft_info.edition,
+        metadata_hash: nft_info.metadata_hash,
+        royalty_bps: Some(nft_info.royalty_bps),
+        royalty_recipient: nft_info.royalty_recipient,
+        attributes: nft_info.attributes.clone(),
+    }
+}
+
+// why accept_message_nonce turned a message away
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonceRejection {
+    Replay, // a nonce that was taken already
+    Stale,  // NONCE_WINDOW or more below the highest one
+    Ahead,  // more than NONCE_WINDOW above the highest one
+}
+
+impl NonceRejection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NonceRejection::Replay => "replay",
+            NonceRejection::Stale => "stale",
+            NonceRejection::Ahead => "ahead",
+        }
+    }
+}
+
+// events, so indexers dont have to parse logs
+#[event]
+pub struct NftMinted {
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub token_id: Option<u64>, // slot in the enumerable index, prints dont have one
+    pub slot: u64,
+    pub payer: Pubkey,
+    pub fee_lamports: u64, // mint fee charged to the payer, 0 when minting is free
+    pub metadata_hash: Option<[u8; 32]>,
+}
+
+// inbound transfer, royalty_from_message is false when the program default was used
+#[event]
+pub struct NftReceived {
+    pub mint: Pubkey,
+    pub origin_mint: Pubkey,
+    pub recipient: Pubkey,
+    pub royalty_bps: u16,
+    pub royalty_from_message: bool,
+}
+
+// inbound transfer of an nft that was delivered before, nothing minted, only the
+// owner in nft info changed
+#[event]
+pub struct AlreadyDelivered {
+    pub mint: Pubkey,
+    pub origin_mint: Pubkey,
+    pub previous_owner: Pubkey,
+    pub owner: Pubkey,
+    pub nonce: u64,
+}
+
+#[event]
+pub struct MetadataMaterialized {
+    pub mint: Pubkey,
+    pub payer: Pubkey,
+}
+
+#[event]
+pub struct MetadataRepaired {
+    pub mint: Pubkey,
+    pub uri: String,
+    pub metadata_hash: [u8; 32],
+    pub repaired_by: Pubkey,
+}
+
+#[event]
+pub struct MetadataFrozen {
+    pub mint: Pubkey,
+    pub frozen_by: Pubkey,
+}
+
+#[event]
+pub struct ChainRegistered {
+    pub chain_id: u64,
+    pub name: String,
+    pub chain_kind: ChainKind,
+}
+
+#[event]
+pub struct ChainUpdated {
+    pub chain_id: u64,
+    pub name: String,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct ChainFeesSet {
+    pub chain_id: u64,
+    pub flat_fee_lamports: u64,
+    pub gas_fee_lamports: u64,
+}
+
+#[event]
+pub struct ChainFeeCurrencySet {
+    pub chain_id: u64,
+    pub fee_currency: FeeCurrency,
+}
+
+#[event]
+pub struct ChainInboundSet {
+    pub chain_id: u64,
+    pub inbound_enabled: bool,
+}
+
+#[event]
+pub struct RecipientBlockSet {
+    pub recipient: Pubkey,
+    pub blocked: bool,
+}
+
+#[event]
+pub struct CollectionApprovalSet {
+    pub collection_mint: Pubkey,
+    pub approved: bool,
+}
+
+#[event]
+pub struct MintBlockSet {
+    pub mint: Pubkey,
+    pub blocked: bool,
+    pub reason: String,
+}
+
+#[event]
+pub struct ChainInboundPausedSet {
+    pub chain_id: u64,
+    pub inbound_paused: bool,
+}
+
+// outbound transfer, the fees are what was charged for it
+#[event]
+pub struct TransferInitiated {
+    pub mint: Pubkey,
+    pub destination_chain_id: u64,
+    pub recipient: [u8; 32],
+    pub nonce: u64,
+    pub flat_fee_lamports: u64,
+    pub gas_fee_lamports: u64,
+    pub message_hash: [u8; 32], // see canonical_message_hash
+}
+
+// answer to report_nonce_gap
+#[event]
+pub struct NonceReported {
+    pub last_nonce: u64,
+    pub expected_nonce: u64, // next nonce above the highest one, see expected_nonce
+    pub slot: u64,
+}
+
+// an inbound message came in above the expected nonce, first..=last havent arrived.
+// they still can while they are inside the nonce window
+#[event]
+pub struct NonceGap {
+    pub first_missing: u64,
+    pub last_missing: u64,
+    pub received_nonce: u64,
+}
+
+// skip_message took a nonce without a delivery
+#[event]
+pub struct MessageSkipped {
+    pub nonce: u64,
+    pub last_nonce: u64, // the highest nonce before the skip, anything between stays open in the window
+    pub authority: Pubkey,
+    pub reason: String,
+}
+
+// on_call kept a message that broke a business rule instead of failing, see DeadLetter
+#[event]
+pub struct MessageDeadLettered {
+    pub message_hash: [u8; 32],
+    pub source_chain_id: u64,
+    pub sender: [u8; 20],
+    pub reason: DeadLetterReason,
+    pub nonce: u64,
+    pub slot: u64,
+}
+
+// on_call held a message back for ordered delivery, see PendingNonce
+#[event]
+pub struct MessageBuffered {
+    pub nonce: u64,
+    pub expected_nonce: u64, // the nonce it waits for
+    pub message_hash: [u8; 32],
+    pub source_chain_id: u64,
+    pub slot: u64,
+}
+
+// drain_pending ran a held back message, its record is closed
+#[event]
+pub struct PendingDrained {
+    pub nonce: u64,
+    pub message_hash: [u8; 32],
+    pub caller: Pubkey,
+    pub slot: u64,
+}
+
+// on_call kept a transfer as a pending claim for its recipient, see PendingClaim
+#[event]
+pub struct ClaimRecorded {
+    pub nonce: u64,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub message_hash: [u8; 32],
+    pub source_chain_id: u64,
+    pub slot: u64,
+}
+
+// the recipient claimed a pending claim, its record is closed. CrossChainReceived
+// follows for the transfer
+#[event]
+pub struct NftClaimed {
+    pub nonce: u64,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub message_hash: [u8; 32],
+    pub slot: u64,
+}
+
+// a dead lettered message went through on retry, its record is closed
+#[event]
+pub struct DeadLetterRetried {
+    pub message_hash: [u8; 32],
+    pub caller: Pubkey,
+    pub slot: u64,
+}
+
+// on_call kept the hash of a message that broke a business rule, see FailedInbound
+#[event]
+pub struct InboundFailed {
+    pub nonce: u64,
+    pub message_hash: [u8; 32],
+    pub source_chain_id: u64,
+    pub reason: DeadLetterReason,
+    pub slot: u64,
+}
+
+// retry_inbound ran a failed message again, its record is closed
+#[event]
+pub struct InboundRetried {
+    pub nonce: u64,
+    pub message_hash: [u8; 32],
+    pub caller: Pubkey,
+    pub slot: u64,
+}
+
+// inbound message that went through on_call, any type. relayers match
+// message_hash against the outbound tx on zetachain
+#[event]
+pub struct CrossChainReceived {
+    pub source_chain_id: u64,
+    pub sender: [u8; 20],
+    pub message_type: MessageType,
+    pub mint: Pubkey, // our mint, for inbound transfers the derived one
+    pub recipient: Pubkey, // who holds the nft now
+    pub nonce: u64,
+    pub message_hash: [u8; 32], // see canonical_message_hash
+}
+
+#[event]
+pub struct MetadataHashMismatch {
+    pub mint: Pubkey,
+    pub expected: Option<[u8; 32]>,
+    pub received: Option<[u8; 32]>,
+}
+
+#[event]
+pub struct NftUnlocked {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub reason: UnlockReason,
+    pub nonce: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct NftRegistered {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub slot: u64,
+}
+
+#[event]
+pub struct NftDeregistered {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct NftBurned {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct AccountFrozen {
+    pub mint: Pubkey,
+    pub holder: Pubkey,
+    pub reason: String,
+}
+
+#[event]
+pub struct AccountThawed {
+    pub mint: Pubkey,
+    pub holder: Pubkey,
+    pub reason: String,
+}
+
+#[event]
+pub struct NftInfoSwept {
+    pub mint: Pubkey,
+    pub rent_destination: Pubkey,
+    pub lamports: u64, // nft info and index slot together
+}
+
+#[event]
+pub struct TokenRescued {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub destination: Pubkey,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct FeeTokenWithdrawn {
+    pub mint: Pubkey,
+    pub destination: Pubkey, // token account
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct GatewayUpdated {
+    pub previous: Pubkey,
+    pub gateway: Pubkey,
+}
+
+#[event]
+pub struct DefaultRoyaltySet {
+    pub royalty_bps: u16,
+    pub royalty_recipient: Pubkey,
+}
+
+#[event]
+pub struct MintPausedSet {
+    pub mint_paused: bool,
+}
+
+#[event]
+pub struct MintFeeSet {
+    pub mint_fee_lamports: u64,
+}
+
+// on_call recorded the root of an inbound batch
+#[event]
+pub struct InboundBatchReceived {
+    pub batch_id: Pubkey,
+    pub root: [u8; 32],
+    pub leaf_count: u64,
+    pub nonce: u64,
+    pub message_hash: [u8; 32],
+    pub source_chain_id: u64,
+    pub slot: u64,
+}
+
+// a leaf of an inbound batch was claimed, CrossChainReceived follows for the transfer
+#[event]
+pub struct InboundClaimed {
+    pub batch_id: Pubkey,
+    pub leaf_index: u64,
+    pub message_hash: [u8; 32],
+    pub caller: Pubkey,
+    pub slot: u64,
+}
+
+// an admin action the authority signed offline went through execute_signed_admin
+#[event]
+pub struct SignedAdminExecuted {
+    pub action: AdminAction,
+    pub admin_nonce: u64,
+    pub relayer: Pubkey,
+}
+
+#[event]
+pub struct AuthorityNominated {
+    pub authority: Pubkey,
+    pub nominee: Pubkey,
+}
+
+#[event]
+pub struct AuthorityAccepted {
+    pub previous: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityRenounced {
+    pub previous: Pubkey,
+}
+
+#[event]
+pub struct AuthorityNominationCancelled {
+    pub nominee: Pubkey,
+    pub cancelled_by: Pubkey,
+}
+
+// error types for the program, try to keep them clear
+#[error_code]
+pub enum NftError {
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Invalid cross-chain message")]
+    InvalidMessage,
+    #[msg("Token not found")]
+    TokenNotFound,
+    #[msg("Token is locked")]
+    TokenLocked,
+    #[msg("Token is not locked")]
+    TokenNotLocked,
+    #[msg("Invalid nonce")]
+    InvalidNonce,
+    #[msg("Invalid recipient")]
+    InvalidRecipient,
+    #[msg("Invalid metadata")]
+    InvalidMetadata, // generic fallback, prefer the specific ones
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Insufficient funds to pay the fee")]
+    InsufficientFunds,
+    #[msg("Insufficient treasury balance")]
+    InsufficientTreasury,
+    #[msg("Signer does not hold the minter role")]
+    NotMinter,
+    #[msg("Uri does not start with an allowed prefix")]
+    InvalidUriScheme,
+    #[msg("Invalid list of allowed uri prefixes")]
+    InvalidUriPrefixes,
+    #[msg("Name is too long")]
+    NameTooLong,
+    #[msg("Symbol is too long")]
+    SymbolTooLong,
+    #[msg("Uri is too long")]
+    UriTooLong,
+    #[msg("Name is empty")]
+    EmptyName,
+    #[msg("Uri is empty")]
+    EmptyUri,
+    #[msg("Account required for programmable nfts is missing")]
+    MissingPnftAccount,
+    #[msg("Account required for metaplex metadata is missing")]
+    MissingMetaplexAccount,
+    #[msg("Metadata extension mode needs the token-2022 program")]
+    MetadataExtensionRequiresToken2022,
+    #[msg("Programmable nfts need metaplex metadata")]
+    PnftRequiresMetaplex,
+    #[msg("Account required for compressed nfts is missing")]
+    MissingCompressedAccount,
+    #[msg("Compressed nfts are not enabled in this build")]
+    CompressedNotEnabled,
+    #[msg("No merkle tree configured for compressed mints")]
+    TreeNotSet,
+    #[msg("Nft is not compressed")]
+    NotCompressed,
+    #[msg("Signer does not hold this nft")]
+    NotHolder,
+    #[msg("Account required for the program collection is missing")]
+    MissingCollectionAccount,
+    #[msg("Creators must be 1 to 5 unique addresses with shares adding up to 100")]
+    InvalidCreators,
+    #[msg("Creators need metaplex metadata")]
+    CreatorsRequireMetaplex,
+    #[msg("Only plain metaplex nfts that are not prints can become master editions")]
+    NotMasterEligible,
+    #[msg("Account is not a master edition")]
+    NotMasterEdition,
+    #[msg("Master edition has no prints left")]
+    MaxEditionsReached,
+    #[msg("Edition numbers start at 1")]
+    InvalidEditionNumber,
+    #[msg("Metadata hash does not match the one on record")]
+    MetadataHashMismatch,
+    #[msg("Token account is frozen")]
+    TokenFrozen,
+    #[msg("Reason is empty or too long")]
+    InvalidReason,
+    #[msg("Wallet reached the mint limit")]
+    WalletMintLimit,
+    #[msg("Mint index account is missing or not expected")]
+    InvalidMintIndex,
+    #[msg("Default symbol must be 1 to 10 bytes")]
+    InvalidDefaultSymbol,
+    #[msg("Royalty basis points above 10000")]
+    InvalidRoyaltyBps,
+    #[msg("A collection delegate is already approved")]
+    CollectionDelegateSet,
+    #[msg("NFT is already verified into the collection")]
+    AlreadyInCollection,
+    #[msg("Inbound name or symbol is too long")]
+    StringTooLong,
+    #[msg("At most 8 attributes")]
+    TooManyAttributes,
+    #[msg("Attribute key must be 1 to 16 bytes")]
+    InvalidAttributeKey,
+    #[msg("Attribute value is longer than 32 bytes")]
+    AttributeValueTooLong,
+    #[msg("Metadata is immutable")]
+    MetadataImmutable,
+    #[msg("Nft info belongs to a local mint")]
+    NftInfoCollision,
+    #[msg("Invalid authority")]
+    InvalidAuthority,
+    #[msg("No pending authority")]
+    NoPendingAuthority,
+    #[msg("Minting is paused")]
+    MintPaused,
+    #[msg("State account needs migrate_state first")]
+    StateNotMigrated,
+    #[msg("State account is already on the current version")]
+    StateAlreadyMigrated,
+    #[msg("Renounce needs the current authority as confirmation")]
+    RenounceNotConfirmed,
+    #[msg("Locked bridge nfts and collection nfts cant be rescued")]
+    TokenNotRescuable,
+    #[msg("Gateway cant be the default pubkey")]
+    InvalidGateway,
+    #[msg("Chain name must be 1 to 32 bytes and recipient length 1 to 32")]
+    InvalidChainConfig,
+    #[msg("Chain is disabled")]
+    ChainDisabled,
+    #[msg("Sender is not the connected contract of the chain")]
+    UnknownSender,
+    #[msg("Mint still has supply")]
+    MintHasSupply,
+    #[msg("Metadata was already created")]
+    MetadataAlreadyCreated,
+    #[msg("Not enough lamports for the chain fee")]
+    InsufficientFee,
+    #[msg("Dead letter record doesnt match the message")]
+    InvalidDeadLetter,
+    #[msg("Mint account doesnt match the mint in the message")]
+    MintMismatch,
+    #[msg("Cross-chain message is over the size limit")]
+    MessageTooLarge,
+    #[msg("Mint has to have 0 decimals")]
+    MintDecimalsNotZero,
+    #[msg("Mint supply has to be 1")]
+    MintSupplyNotOne,
+    #[msg("Mint authority isnt the program or the edition of the mint")]
+    InvalidMintAuthority,
+    #[msg("Nft info is for another mint than the message")]
+    NftInfoMismatch,
+    #[msg("Escrow token account isnt the program's account for the mint")]
+    EscrowMismatch,
+    #[msg("Counter would go over its maximum")]
+    CounterOverflow,
+    #[msg("Counter would go below zero")]
+    CounterUnderflow,
+    #[msg("Mint has to sign, mint_nft_pda derives the mint instead")]
+    MintNotSigner,
+    #[msg("Mint isnt the next program derived mint, mint_nft_with_keypair takes a keypair mint")]
+    MintNotProgramDerived,
+    #[msg("Owner token account doesnt hold the nft")]
+    TokenNotHeld,
+    #[msg("Owner token account is for another mint than the nft info")]
+    TokenAccountMintMismatch,
+    #[msg("Pending nonce record is missing or doesnt match the message")]
+    InvalidPendingNonce,
+    #[msg("Nonce is too far ahead of the expected one to wait")]
+    NonceOutOfWindow,
+    #[msg("Nonces before this message havent been taken yet")]
+    NonceNotReady,
+    #[msg("A message waiting for its nonce cant take an index slot yet")]
+    MintIndexWhilePending,
+    #[msg("Inbound message isnt signed by the tss address")]
+    InvalidSignature,
+    #[msg("Tss address has to be set while signatures are required")]
+    TssAddressNotSet,
+    #[msg("Admin action is signed for another admin nonce")]
+    InvalidAdminNonce,
+    #[msg("Admin action isnt signed by the authority")]
+    InvalidAdminSignature,
+    #[msg("Inbound batch record is missing, malformed or from another chain")]
+    InvalidInboundBatch,
+    #[msg("Merkle proof doesnt lead from the leaf to the batch root")]
+    InvalidMerkleProof,
+    #[msg("Batch leaf was claimed already")]
+    LeafAlreadyClaimed,
+    #[msg("Batch leaf has to be a transfer with the nonce of its root")]
+    InvalidBatchLeaf,
+    #[msg("Messages from this chain arent accepted")]
+    SourceChainNotAllowed,
+    #[msg("Inbound from this chain is paused")]
+    SourceChainPaused,
+    #[msg("Recipient is blocked")]
+    RecipientBlocked,
+    #[msg("Blocked recipient account doesnt belong to the recipient")]
+    InvalidBlockedRecipient,
+    #[msg("Mint is blocked from the bridge")]
+    MintBlocked,
+    #[msg("Invalid uri rewrite, pattern and replacement need one placeholder, the pattern at its end")]
+    InvalidUriRewrite,
+    #[msg("Nft metadata doesnt need repair")]
+    MetadataNotPendingRepair,
+    #[msg("Pending claim record is missing or doesnt match the nonce")]
+    InvalidPendingClaim,
+    #[msg("An nft waiting to be claimed cant take an index slot yet")]
+    MintIndexBeforeClaim,
+    #[msg("Token accounts dont match the message type")]
+    InboundAccountsMismatch,
+    #[msg("Nft metadata needs repair first")]
+    MetadataPendingRepair,
+    #[msg("Metadata is still mutable")]
+    MetadataMutable,
+    #[msg("Nft didnt come in through the bridge")]
+    NotBridged,
+    #[msg("Mint was burned here and its master edition keeps it from being minted again")]
+    MintRetired,
+    #[msg("Total supply is at max supply")]
+    SupplyCapReached,
+    #[msg("Nft was minted or bridged in by the program, it doesnt need registering")]
+    NotExternal,
+    #[msg("Nft isnt verified into an approved collection")]
+    CollectionNotApproved,
+    #[msg("Chain fee is paid in a token and its accounts are missing")]
+    MissingFeeTokenAccounts,
+    #[msg("Fee token account or vault isnt of the chain's fee mint")]
+    FeeTokenMismatch,
+    #[msg("Not enough of the fee token for the chain fee")]
+    InsufficientFeeToken,
+    #[msg("Failed inbound record is missing or doesnt match the nonce")]
+    InvalidFailedInbound,
+    #[msg("Message doesnt hash to the one the failed inbound record kept")]
+    FailedInboundHashMismatch,
+    #[msg("Max uri length has to be between 1 and 1024")]
+    InvalidMaxUriLen,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inbound_message(name: &str, symbol: &str, mint: Pubkey) -> CrossChainMessage {
+        CrossChainMessage {
+            message_type: MessageType::Transfer,
+            mint,
+            recipient: [1; 32],
+            metadata_uri: "https://example.com/nft.json".to_string(),
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            nonce: 1,
+            master_mint: Pubkey::default(),
+            edition: 0,
+            metadata_hash: None,
+            royalty_bps: None,
+            royalty_recipient: None,
+            attributes: vec![],
+        }
+    }
+
+    fn token_id_mint(token_id: u64) -> Pubkey {
+        let mut bytes = [0u8; 32];
+        bytes[24..].copy_from_slice(&token_id.to_be_bytes());
+        Pubkey::new_from_array(bytes)
+    }
+
+    #[test]
+    fn test_empty_name_uses_token_id() {
+        let mut message = inbound_message("", "EVM", token_id_mint(42));
+
+        normalize_inbound_metadata(&mut message, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Truncate).unwrap();
+
+        assert_eq!(message.name, "#42");
+        assert_eq!(message.symbol, "EVM");
+    }
+
+    #[test]
+    fn test_empty_symbol_uses_default() {
+        let mut message = inbound_message("Punk", "", token_id_mint(7));
+
+        normalize_inbound_metadata(&mut message, "ZETA", InboundStringPolicy::Truncate).unwrap();
+
+        assert_eq!(message.name, "Punk");
+        assert_eq!(message.symbol, "ZETA");
+    }
+
+    #[test]
+    fn test_empty_name_and_symbol() {
+        let mut message = inbound_message(" \0", "\0\0", token_id_mint(u64::MAX));
+
+        normalize_inbound_metadata(&mut message, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Truncate).unwrap();
+
+        assert_eq!(message.name, format!("#{}", u64::MAX));
+        assert_eq!(message.symbol, DEFAULT_INBOUND_SYMBOL);
+    }
+
+    #[test]
+    fn test_large_token_id_name_fits() {
+        let mint = Pubkey::new_from_array([9; 32]);
+        let mut message = inbound_message("", "", mint);
+
+        normalize_inbound_metadata(&mut message, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Truncate).unwrap();
+
+        assert!(message.name.starts_with('#'));
+        assert_eq!(message.name.len(), MAX_NAME_LEN);
+    }
+
+    #[test]
+    fn test_long_name_and_symbol_are_cut_on_char_boundaries() {
+        let mut message = inbound_message(&"é".repeat(20), "SYMBOLTOOLONG", token_id_mint(1));
+
+        normalize_inbound_metadata(&mut message, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Truncate).unwrap();
+
+        assert_eq!(message.name, "é".repeat(16));
+        assert_eq!(message.symbol, "SYMBOLTOOL");
+    }
+
+    #[test]
+    fn test_cut_point_inside_a_multibyte_char() {
+        // 1 + 11 * 3 bytes, byte 32 is in the middle of the 11th euro sign
+        let name = format!("a{}", "€".repeat(11));
+        // 1 + 3 * 4 bytes, byte 10 is in the middle of the 3rd crab
+        let symbol = format!("A{}", "🦀".repeat(3));
+        let mut message = inbound_message(&name, &symbol, token_id_mint(1));
+
+        normalize_inbound_metadata(&mut message, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Truncate).unwrap();
+
+        assert_eq!(message.name, format!("a{}", "€".repeat(10)));
+        assert_eq!(message.name.len(), 31);
+        assert_eq!(message.symbol, format!("A{}", "🦀".repeat(2)));
+        assert_eq!(message.symbol.len(), 9);
+    }
+
+    #[test]
+    fn test_truncating_keeps_a_hash_of_the_full_values() {
+        let name = "n".repeat(90);
+        let mut message = inbound_message(&name, "EVM", token_id_mint(1));
+
+        normalize_inbound_metadata(&mut message, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Truncate).unwrap();
+
+        let full_values = (name, "EVM".to_string()).try_to_vec().unwrap();
+        assert_eq!(message.metadata_hash, Some(hash(&full_values).to_bytes()));
+    }
+
+    #[test]
+    fn test_truncating_leaves_a_metadata_hash_alone() {
+        let mut message = inbound_message(&"n".repeat(90), "EVM", token_id_mint(1));
+        message.metadata_hash = Some([7; 32]);
+
+        normalize_inbound_metadata(&mut message, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Truncate).unwrap();
+
+        assert_eq!(message.metadata_hash, Some([7; 32]));
+    }
+
+    #[test]
+    fn test_short_values_get_no_hash() {
+        let mut message = inbound_message("Punk", "PNK", token_id_mint(1));
+
+        normalize_inbound_metadata(&mut message, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Reject).unwrap();
+
+        assert_eq!(message.metadata_hash, None);
+    }
+
+    #[test]
+    fn test_reject_policy_fails_on_long_strings() {
+        let mut long_name = inbound_message(&"€".repeat(11), "EVM", token_id_mint(1));
+        let mut long_symbol = inbound_message("Punk", "SYMBOLTOOLONG", token_id_mint(1));
+
+        assert!(normalize_inbound_metadata(&mut long_name, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Reject).is_err());
+        assert!(normalize_inbound_metadata(&mut long_symbol, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Reject).is_err());
+    }
+
+    #[test]
+    fn test_reject_policy_still_fills_in_defaults() {
+        // the default name for a large token id gets cut, thats ours and not rejected
+        let mut message = inbound_message("", "", Pubkey::new_from_array([9; 32]));
+
+        normalize_inbound_metadata(&mut message, DEFAULT_INBOUND_SYMBOL, InboundStringPolicy::Reject).unwrap();
+
+        assert_eq!(message.name.len(), MAX_NAME_LEN);
+        assert_eq!(message.symbol, DEFAULT_INBOUND_SYMBOL);
+    }
+
+    #[test]
+    fn test_royalty_bps_over_the_cap() {
+        assert_eq!(inbound_royalty_bps(Some(500), 0, false).unwrap(), 500);
+        assert_eq!(inbound_royalty_bps(Some(MAX_ROYALTY_BPS), 0, false).unwrap(), MAX_ROYALTY_BPS);
+        assert!(inbound_royalty_bps(Some(MAX_ROYALTY_BPS + 1), 0, false).is_err());
+        assert_eq!(inbound_royalty_bps(Some(u16::MAX), 0, true).unwrap(), MAX_ROYALTY_BPS);
+    }
+
+    #[test]
+    fn test_missing_royalty_uses_the_default() {
+        assert_eq!(inbound_royalty_bps(None, 250, false).unwrap(), 250);
+        // an explicit zero is a real value, not a missing one
+        assert_eq!(inbound_royalty_bps(Some(0), 250, false).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_royalty_survives_a_round_trip() {
+        let royalty_recipient = Pubkey::new_unique();
+
+        // comes in from zeta with a royalty
+        let mut inbound = inbound_message("Punk", "PNK", token_id_mint(3));
+        inbound.royalty_bps = Some(750);
+        inbound.royalty_recipient = Some(royalty_recipient);
+        let received = CrossChainMessage::try_from_slice(&inbound.try_to_vec().unwrap()).unwrap();
+        let royalty_bps = inbound_royalty_bps(received.royalty_bps, 0, false).unwrap();
+
+        // tracked in nft info like on_call does, then sent back out
+        let nft_info = NftInfo {
+            mint: received.mint,
+            owner: Pubkey::new_unique(),
+            metadata_uri: received.metadata_uri.clone(),
+            name: received.name.clone(),
+            symbol: received.symbol.clone(),
+            is_locked: false,
+            cross_chain_recipient: [0; 32],
+            token_standard: NftStandard::NonFungible,
+            compressed: false,
+            asset_id: Pubkey::default(),
+            in_collection: false,
+            bridged_collection: false,
+            master_mint: received.master_mint,
+            edition: received.edition,
+            metadata_hash: received.metadata_hash,
+            hash_mismatch: false,
+            minted_to: Pubkey::default(),
+            bridged: true,
+            index: Some(0),
+            royalty_bps,
+            royalty_recipient: received.royalty_recipient,
+            attributes: received.attributes.clone(),
+            bump: 255,
+            version: NFT_INFO_VERSION,
+            metadata_created: true,
+            needs_metadata_repair: false,
+            metadata_immutable: false,
+            external: false,
+            lock_nonce: 0,
+        };
+        let outbound = transfer_message(&nft_info, [2; 32], 2);
+
+        // and comes back again
+        let returned = CrossChainMessage::try_from_slice(&outbound.try_to_vec().unwrap()).unwrap();
+        assert_eq!(returned.royalty_bps, Some(750));
+        assert_eq!(returned.royalty_recipient, Some(royalty_recipient));
+    }
+
+    fn stored_nft_info(mint: Pubkey, bridged: bool) -> NftInfo {
+        NftInfo {
+            mint,
+            owner: Pubkey::new_unique(),
+            metadata_uri: String::new(),
+            name: String::new(),
+            symbol: String::new(),
+            is_locked: false,
+            cross_chain_recipient: [0; 32],
+            token_standard: NftStandard::NonFungible,
+            compressed: false,
+            asset_id: Pubkey::default(),
+            in_collection: false,
+            bridged_collection: false,
+            master_mint: Pubkey::default(),
+            edition: 0,
+            metadata_hash: None,
+            hash_mismatch: false,
+            minted_to: Pubkey::default(),
+            bridged,
+            index: None,
+            royalty_bps: 0,
+            royalty_recipient: None,
+            attributes: vec![],
+            bump: 255,
+            version: NFT_INFO_VERSION,
+            metadata_created: true,
+            needs_metadata_repair: false,
+            metadata_immutable: false,
+            external: false,
+            lock_nonce: 0,
+        }
+    }
+
+    #[test]
+    fn test_inbound_cant_take_over_a_local_nft_info() {
+        // a message whose mint lands on a local nft's info pda
+        let collided = stored_nft_info(Pubkey::new_unique(), false);
+
+        assert!(check_inbound_nft_info(&collided).is_err());
+    }
+
+    #[test]
+    fn test_inbound_reuses_fresh_and_bridged_nft_info() {
+        assert!(check_inbound_nft_info(&stored_nft_info(Pubkey::default(), false)).is_ok());
+        assert!(check_inbound_nft_info(&stored_nft_info(Pubkey::new_unique(), true)).is_ok());
+    }
+
+    fn v1_state() -> NftProgramState {
+        NftProgramState {
+            authority: Pubkey::new_unique(),
+            pending_authority: Pubkey::default(),
+            gateway: Pubkey::new_unique(),
+            total_supply: 3,
+            mint_count: 5,
+            index_count: 5,
+            nonce: 42,
+            mint_fee_lamports: 1_000,
+            max_mints_per_wallet: 0,
+            public_mint: true,
+            mint_paused: false,
+            default_token_standard: NftStandard::NonFungible,
+            default_symbol: "ZETA123456".to_string(),
+            inbound_string_policy: InboundStringPolicy::Truncate,
+            max_uri_len: DEFAULT_MAX_URI_LEN,
+            allowed_uri_prefixes: vec!["https://".to_string(); MAX_URI_PREFIXES],
+            metadata_mode: MetadataMode::Metaplex,
+            compressed_mode: false,
+            merkle_tree: Pubkey::default(),
+            collection_mint: Pubkey::new_unique(),
+            collection_delegate: Pubkey::default(),
+            defer_collection_verify: false,
+            bridged_collection_mint: Pubkey::default(),
+            strict_metadata_hash: true,
+            clamp_royalty_bps: false,
+            default_royalty_bps: 250,
+            bump: 254,
+            version: 0,
+            default_royalty_recipient: Pubkey::default(),
+            locked_count: 0,
+            sweep_rent_destination: SweepRentDestination::Treasury,
+            lazy_metadata: false,
+            dead_letter_retry: DeadLetterRetry::Authority,
+            allow_program_recipients: false,
+            ordered_delivery: false,
+            nonce_window: [u64::MAX; 4],
+            tss_address: [0; 20],
+            require_tss_signature: false,
+            admin_nonce: 0,
+            uri_rewrite: None,
+            placeholder_uri: String::new(),
+            claim_mode: false,
+            inbound_metadata_mutable: true,
+            immutable_bridged_count: 0,
+            max_supply: 0,
+            outbound_nonce: 0,
+        }
+    }
+
+    // an account made with an older layout: the serialized state without the
+    // fields that came later, allocated at the size of that layout
+    fn old_image(state: &NftProgramState, version: u8, tail: u8) -> Vec<u8> {
+        let (later_fields, space) = match version {
+            1 => (1 + 32 + 8 + 1 + 1 + 1 + 1 + 1 + 32 + 21 + 8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V1_SPACE),
+            2 => (32 + 8 + 1 + 1 + 1 + 1 + 1 + 32 + 21 + 8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V2_SPACE),
+            3 => (8 + 1 + 1 + 1 + 1 + 1 + 32 + 21 + 8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V3_SPACE),
+            4 => (1 + 1 + 1 + 1 + 1 + 32 + 21 + 8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V4_SPACE),
+            5 => (1 + 1 + 1 + 1 + 32 + 21 + 8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V5_SPACE),
+            6 => (1 + 1 + 1 + 32 + 21 + 8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V6_SPACE),
+            7 => (1 + 1 + 32 + 21 + 8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V7_SPACE),
+            8 => (1 + 32 + 21 + 8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V8_SPACE),
+            9 => (32 + 21 + 8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V9_SPACE),
+            10 => (21 + 8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V10_SPACE),
+            11 => (8 + 1 + 4 + 1 + 9 + 8 + 8, STATE_V11_SPACE),
+            12 => (1 + 4 + 1 + 9 + 8 + 8, STATE_V12_SPACE),
+            13 => (4 + 1 + 9 + 8 + 8, STATE_V13_SPACE), // uri_rewrite is None, placeholder_uri empty
+            14 => (1 + 9 + 8 + 8, STATE_V14_SPACE),
+            15 => (9 + 8 + 8, STATE_V15_SPACE),
+            16 => (8 + 8, STATE_V16_SPACE),
+            _ => (8, STATE_V17_SPACE),
+        };
+        let mut image = Vec::new();
+        state.try_serialize(&mut image).unwrap();
+        image.truncate(image.len() - later_fields);
+        image.resize(space, tail);
+        image
+    }
+
+    #[test]
+    fn test_upgrade_v1_state() {
+        let old = v1_state();
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 1, 0)).unwrap();
+
+        assert_eq!(from_version, 1);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.authority, old.authority);
+        assert_eq!(state.nonce, old.nonce);
+        assert_eq!(state.default_symbol, old.default_symbol);
+        assert_eq!(state.allowed_uri_prefixes, old.allowed_uri_prefixes);
+        assert_eq!(state.default_royalty_bps, old.default_royalty_bps);
+        assert_eq!(state.bump, old.bump);
+    }
+
+    #[test]
+    fn test_upgrade_ignores_leftover_bytes() {
+        // a shorter symbol than before leaves old bytes behind the serialized state
+        let mut old = v1_state();
+        old.default_symbol = "UNFT".to_string();
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 1, STATE_VERSION)).unwrap();
+
+        assert_eq!(from_version, 1);
+        assert_eq!(state.default_symbol, "UNFT");
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.default_royalty_recipient, Pubkey::default());
+    }
+
+    #[test]
+    fn test_upgrade_v2_state() {
+        let mut old = v1_state();
+        old.version = 2;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 2, 0xff)).unwrap();
+
+        assert_eq!(from_version, 2);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.default_royalty_bps, old.default_royalty_bps);
+        assert_eq!(state.default_royalty_recipient, Pubkey::default());
+        assert_eq!(state.locked_count, 0);
+        assert_eq!(state.bump, old.bump);
+    }
+
+    #[test]
+    fn test_upgrade_v3_state_keeps_the_royalty_recipient() {
+        let mut old = v1_state();
+        old.version = 3;
+        old.default_royalty_recipient = Pubkey::new_unique();
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 3, 0xff)).unwrap();
+
+        assert_eq!(from_version, 3);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.default_royalty_recipient, old.default_royalty_recipient);
+        assert_eq!(state.locked_count, 0);
+    }
+
+    #[test]
+    fn test_upgrade_v4_state_keeps_the_locked_count() {
+        let mut old = v1_state();
+        old.version = 4;
+        old.locked_count = 7;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 4, 0xff)).unwrap();
+
+        assert_eq!(from_version, 4);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.locked_count, 7);
+        assert_eq!(state.sweep_rent_destination, SweepRentDestination::Treasury);
+    }
+
+    #[test]
+    fn test_upgrade_v5_state_keeps_the_sweep_destination() {
+        let mut old = v1_state();
+        old.version = 5;
+        old.sweep_rent_destination = SweepRentDestination::Caller;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 5, 0xff)).unwrap();
+
+        assert_eq!(from_version, 5);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.sweep_rent_destination, SweepRentDestination::Caller);
+        assert!(!state.lazy_metadata);
+        assert_eq!(state.dead_letter_retry, DeadLetterRetry::Authority);
+    }
+
+    #[test]
+    fn test_upgrade_v6_state_keeps_lazy_metadata() {
+        let mut old = v1_state();
+        old.version = 6;
+        old.lazy_metadata = true;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 6, 0xff)).unwrap();
+
+        assert_eq!(from_version, 6);
+        assert_eq!(state.version, STATE_VERSION);
+        assert!(state.lazy_metadata);
+        assert_eq!(state.dead_letter_retry, DeadLetterRetry::Authority);
+        assert!(!state.allow_program_recipients);
+    }
+
+    #[test]
+    fn test_upgrade_v7_state_keeps_dead_letter_retry() {
+        let mut old = v1_state();
+        old.version = 7;
+        old.dead_letter_retry = DeadLetterRetry::Anyone;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 7, 0xff)).unwrap();
+
+        assert_eq!(from_version, 7);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.dead_letter_retry, DeadLetterRetry::Anyone);
+        assert!(!state.allow_program_recipients);
+    }
+
+    #[test]
+    fn test_upgrade_v8_state_keeps_allow_program_recipients() {
+        let mut old = v1_state();
+        old.version = 8;
+        old.allow_program_recipients = true;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 8, 0xff)).unwrap();
+
+        assert_eq!(from_version, 8);
+        assert_eq!(state.version, STATE_VERSION);
+        assert!(state.allow_program_recipients);
+        assert!(!state.ordered_delivery);
+    }
+
+    #[test]
+    fn test_upgrade_v9_state_counts_every_old_nonce_as_taken() {
+        let mut old = v1_state();
+        old.version = 9;
+        old.nonce = 500;
+        old.ordered_delivery = true;
+
+        // the tail is zeros, an open window would let old nonces in again
+        let (mut state, from_version) = upgrade_state(&old_image(&old, 9, 0)).unwrap();
+
+        assert_eq!(from_version, 9);
+        assert_eq!(state.version, STATE_VERSION);
+        assert!(state.ordered_delivery);
+        assert_eq!(state.nonce_window, [u64::MAX; 4]);
+        assert!(accept_message_nonce(&mut state, 499).is_err());
+        assert!(accept_message_nonce(&mut state, 300).is_err());
+    }
+
+    #[test]
+    fn test_upgrade_v10_state_keeps_the_nonce_window() {
+        let mut old = v1_state();
+        old.version = 10;
+        old.nonce_window = [1, 2, 3, 4];
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 10, 0xff)).unwrap();
+
+        assert_eq!(from_version, 10);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.nonce_window, [1, 2, 3, 4]);
+        assert_eq!(state.tss_address, [0; 20]);
+        assert!(!state.require_tss_signature);
+    }
+
+    #[test]
+    fn test_upgrade_v11_state_keeps_the_tss_fields() {
+        let mut old = v1_state();
+        old.version = 11;
+        old.tss_address = [7; 20];
+        old.require_tss_signature = true;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 11, 0xff)).unwrap();
+
+        assert_eq!(from_version, 11);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.tss_address, [7; 20]);
+        assert!(state.require_tss_signature);
+        assert_eq!(state.admin_nonce, 0);
+    }
+
+    #[test]
+    fn test_upgrade_v12_state_keeps_the_admin_nonce() {
+        let mut old = v1_state();
+        old.version = 12;
+        old.admin_nonce = 9;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 12, 0xff)).unwrap();
+
+        assert_eq!(from_version, 12);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.admin_nonce, 9);
+        assert_eq!(state.uri_rewrite, None);
+    }
+
+    #[test]
+    fn test_upgrade_v13_state_keeps_the_uri_rewrite() {
+        let mut old = v1_state();
+        old.version = 13;
+        old.uri_rewrite = Some(ipfs_gateway());
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 13, 0xff)).unwrap();
+
+        assert_eq!(from_version, 13);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.uri_rewrite, Some(ipfs_gateway()));
+        assert_eq!(state.placeholder_uri, "");
+    }
+
+    #[test]
+    fn test_upgrade_v14_state_keeps_the_placeholder_uri() {
+        let mut old = v1_state();
+        old.version = 14;
+        old.placeholder_uri = "https://example.com/unavailable.json".to_string();
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 14, 0xff)).unwrap();
+
+        assert_eq!(from_version, 14);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.placeholder_uri, "https://example.com/unavailable.json");
+        assert!(!state.claim_mode);
+        assert!(state.inbound_metadata_mutable);
+    }
+
+    #[test]
+    fn test_upgrade_v15_state_keeps_claim_mode() {
+        let mut old = v1_state();
+        old.version = 15;
+        old.claim_mode = true;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 15, 0xff)).unwrap();
+
+        assert_eq!(from_version, 15);
+        assert_eq!(state.version, STATE_VERSION);
+        assert!(state.claim_mode);
+        // metadata from here on is mutable, none of the immutable one is counted yet
+        assert!(state.inbound_metadata_mutable);
+        assert_eq!(state.immutable_bridged_count, 0);
+    }
+
+    #[test]
+    fn test_upgrade_v16_state_keeps_the_metadata_settings() {
+        let mut old = v1_state();
+        old.version = 16;
+        old.inbound_metadata_mutable = false;
+        old.immutable_bridged_count = 5;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 16, 0xff)).unwrap();
+
+        assert_eq!(from_version, 16);
+        assert_eq!(state.version, STATE_VERSION);
+        assert!(!state.inbound_metadata_mutable);
+        assert_eq!(state.immutable_bridged_count, 5);
+        assert_eq!(state.max_supply, 0);
+    }
+
+    #[test]
+    fn test_upgrade_v17_state_starts_outbound_at_the_old_nonce() {
+        let mut old = v1_state();
+        old.version = 17;
+        old.max_supply = 9;
+
+        let (state, from_version) = upgrade_state(&old_image(&old, 17, 0xff)).unwrap();
+
+        assert_eq!(from_version, 17);
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.max_supply, 9);
+        assert_eq!(state.nonce, old.nonce);
+        assert_eq!(state.outbound_nonce, old.nonce);
+    }
+
+    #[test]
+    fn test_supply_cap() {
+        let mut state = v1_state();
+        assert!(check_supply_cap(&state).is_ok());
+        state.max_supply = 4;
+        assert!(check_supply_cap(&state).is_ok());
+        state.max_supply = 3;
+        assert_eq!(check_supply_cap(&state).unwrap_err(), NftError::SupplyCapReached.into());
+        // a cap under the supply only stops new ones
+        state.max_supply = 1;
+        assert!(check_supply_cap(&state).is_err());
+    }
+
+    fn ipfs_gateway() -> UriRewrite {
+        UriRewrite {
+            pattern: "ipfs://{cid}".to_string(),
+            replacement: "https://gateway.example/ipfs/{cid}".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_uri_rewrite_needs_one_cid_at_the_end_of_the_pattern() {
+        assert!(validate_uri_rewrite(&ipfs_gateway()).is_ok());
+        let subdomain = UriRewrite {
+            replacement: "https://{cid}.ipfs.gateway.example".to_string(),
+            ..ipfs_gateway()
+        };
+        assert!(validate_uri_rewrite(&subdomain).is_ok());
+
+        for (pattern, replacement) in [
+            ("ipfs://", "https://gateway.example/ipfs/{cid}"),
+            ("{cid}", "https://gateway.example/ipfs/{cid}"),
+            ("ipfs://{cid}/meta", "https://gateway.example/ipfs/{cid}"),
+            ("ipfs://{cid}{cid}", "https://gateway.example/ipfs/{cid}"),
+            ("ipfs://{cid}", "https://gateway.example/ipfs/"),
+            ("ipfs://{cid}", "https://gateway.example/{cid}/{cid}"),
+        ] {
+            let uri_rewrite = UriRewrite {
+                pattern: pattern.to_string(),
+                replacement: replacement.to_string(),
+            };
+            assert!(validate_uri_rewrite(&uri_rewrite).is_err(), "{pattern} -> {replacement}");
+        }
+        let long = UriRewrite {
+            replacement: format!("https://{}/{{cid}}", "g".repeat(MAX_URI_REWRITE_REPLACEMENT_LEN)),
+            ..ipfs_gateway()
+        };
+        assert!(validate_uri_rewrite(&long).is_err());
+    }
+
+    #[test]
+    fn test_display_uri_rewrites_only_matching_uris() {
+        let mut state = v1_state();
+        let uri = "ipfs://bafybeigdyrzt/1.json";
+        assert_eq!(display_uri(&state, uri), uri);
+
+        state.uri_rewrite = Some(ipfs_gateway());
+        assert_eq!(
+            display_uri(&state, uri),
+            "https://gateway.example/ipfs/bafybeigdyrzt/1.json"
+        );
+        // no match or nothing after the prefix passes through
+        assert_eq!(display_uri(&state, "https://example.com/1.json"), "https://example.com/1.json");
+        assert_eq!(display_uri(&state, "ar://abc"), "ar://abc");
+        assert_eq!(display_uri(&state, "ipfs://"), "ipfs://");
+        // too long for metaplex once rewritten, kept as it came
+        let long = format!("ipfs://{}", "c".repeat(MAX_METAPLEX_URI_LEN - 7));
+        assert_eq!(display_uri(&state, &long), long);
+    }
+
+    // ed25519 program data with one signature, laid out like solana_sdk's
+    // new_ed25519_instruction: offsets, pubkey, signature, message
+    fn ed25519_data(index: u16, pubkey: &Pubkey, message: &[u8]) -> Vec<u8> {
+        let (key_at, signature_at, message_at) = (16u16, 48u16, 112u16);
+        let mut data = vec![1, 0];
+        for offset in [signature_at, index, key_at, index, message_at, message.len() as u16, index] {
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data.extend_from_slice(pubkey.as_ref());
+        data.extend_from_slice(&[9; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn test_admin_message_covers_program_action_and_nonce() {
+        let pause = AdminAction::SetMintPaused { mint_paused: true };
+        let message = admin_message(&pause, 3);
+
+        assert_eq!(message[..32], crate::ID.to_bytes());
+        assert_eq!(message[64..], 3u64.to_le_bytes());
+        assert_ne!(message, admin_message(&pause, 4));
+        assert_ne!(message, admin_message(&AdminAction::SetMintPaused { mint_paused: false }, 3));
+        assert_ne!(message, admin_message(&AdminAction::SetMintFee { mint_fee_lamports: 1 }, 3));
+    }
+
+    #[test]
+    fn test_ed25519_signs_needs_the_key_and_message_in_place() {
+        let authority = Pubkey::new_unique();
+        let message = admin_message(&AdminAction::SetMintFee { mint_fee_lamports: 5 }, 0);
+
+        // the ed25519 instruction itself, by index or by u16::MAX
+        assert!(ed25519_signs(&ed25519_data(u16::MAX, &authority, &message), 1, &authority, &message));
+        assert!(ed25519_signs(&ed25519_data(1, &authority, &message), 1, &authority, &message));
+
+        // another signer, another action or nonce, data in another instruction
+        let data = ed25519_data(u16::MAX, &authority, &message);
+        assert!(!ed25519_signs(&data, 1, &Pubkey::new_unique(), &message));
+        let other = admin_message(&AdminAction::SetMintFee { mint_fee_lamports: 5 }, 1);
+        assert!(!ed25519_signs(&data, 1, &authority, &other));
+        assert!(!ed25519_signs(&ed25519_data(0, &authority, &message), 1, &authority, &message));
+
+        // no signatures, or offsets past the end
+        let mut none = data.clone();
+        none[0] = 0;
+        assert!(!ed25519_signs(&none, 1, &authority, &message));
+        assert!(!ed25519_signs(&data[..data.len() - 1], 1, &authority, &message));
+        assert!(!ed25519_signs(&[1], 1, &authority, &message));
+    }
+
+    // anchor builds the whole context on the stack of the entrypoint's try_accounts,
+    // sbf gives a frame 4kb. the heavy accounts are boxed, a new unboxed NftInfo or
+    // NftProgramState (~400 bytes each) in one of these pushes it over. pointers are
+    // 64 bit on both sides, so the host sizes are the sbf ones
+    const MAX_CONTEXT_SIZE: usize = 1024;
+
+    #[test]
+    fn test_large_contexts_stay_boxed() {
+        let sizes = [
+            ("OnCall", std::mem::size_of::<OnCall<'static>>()),
+            ("MintNft", std::mem::size_of::<MintNft<'static>>()),
+            ("MintNftPda", std::mem::size_of::<MintNftPda<'static>>()),
+            ("MintEdition", std::mem::size_of::<MintEdition<'static>>()),
+            ("TransferToZetachain", std::mem::size_of::<TransferToZetachain<'static>>()),
+            ("UnlockNft", std::mem::size_of::<UnlockNft<'static>>()),
+            ("BurnNft", std::mem::size_of::<BurnNft<'static>>()),
+            ("MaterializeMetadata", std::mem::size_of::<MaterializeMetadata<'static>>()),
+        ];
+        for (name, size) in sizes {
+            assert!(size <= MAX_CONTEXT_SIZE, "{name} is {size} bytes, box its large accounts");
+        }
+    }
+
+    #[test]
+    fn test_inbound_accounts_len_counts_the_context() {
+        let transfer = __client_accounts_inbound_transfer::InboundTransfer {
+            recipient_token_account: Pubkey::new_unique(),
+        };
+        assert_eq!(transfer.to_account_metas(None).len(), InboundTransfer::LEN);
+        let unlock = __client_accounts_inbound_unlock::InboundUnlock {
+            program_token_account: Pubkey::new_unique(),
+            owner_token_account: Pubkey::new_unique(),
+        };
+        assert_eq!(unlock.to_account_metas(None).len(), InboundUnlock::LEN);
+    }
+
+    #[test]
+    fn test_counters_stop_at_their_bounds() {
+        let mut count = u64::MAX - 1;
+        bump(&mut count).unwrap();
+        assert_eq!(count, u64::MAX);
+        assert_eq!(bump(&mut count).unwrap_err(), NftError::CounterOverflow.into());
+        assert_eq!(count, u64::MAX);
+
+        let mut count = 1;
+        bump_down(&mut count).unwrap();
+        assert_eq!(count, 0);
+        assert_eq!(bump_down(&mut count).unwrap_err(), NftError::CounterUnderflow.into());
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_sweep_refuses_locked_and_live_nfts() {
+        let mut nft_info = stored_nft_info(Pubkey::new_unique(), false);
+        assert!(check_sweepable(&nft_info, 0).is_ok());
+        assert!(check_sweepable(&nft_info, 1).is_err());
+
+        // locked ones are still in our escrow, even a zero supply wouldnt make that safe
+        nft_info.is_locked = true;
+        assert!(check_sweepable(&nft_info, 0).is_err());
+    }
+
+    #[test]
+    fn test_local_royalty_falls_back_to_the_default() {
+        let mut state = v1_state();
+        let recipient = Pubkey::new_unique();
+
+        // no default recipient yet
+        assert_eq!(local_royalty(&state, None, false).unwrap(), (250, None));
+
+        state.default_royalty_recipient = recipient;
+        assert_eq!(local_royalty(&state, None, false).unwrap(), (250, Some(recipient)));
+        assert_eq!(local_royalty(&state, Some(500), false).unwrap(), (500, Some(recipient)));
+        // creators say who gets paid, the default recipient stays out
+        assert_eq!(local_royalty(&state, None, true).unwrap(), (250, None));
+        assert!(local_royalty(&state, Some(MAX_ROYALTY_BPS + 1), false).is_err());
+    }
+
+    #[test]
+    fn test_upgrade_rejects_current_state() {
+        let mut state = v1_state();
+        state.version = STATE_VERSION;
+        let mut image = Vec::new();
+        state.try_serialize(&mut image).unwrap();
+        image.resize(8 + NftProgramState::INIT_SPACE, 0);
+
+        assert!(upgrade_state(&image).is_err());
+    }
+
+    #[test]
+    fn test_renounced_authority_matches_nobody() {
+        let mut state = v1_state();
+        let authority = state.authority;
+        assert!(is_authority(&state, authority));
+
+        state.authority = Pubkey::default();
+        assert!(!is_authority(&state, authority));
+        assert!(!is_authority(&state, Pubkey::default()));
+    }
+
+    #[test]
+    fn test_rescue_refuses_locked_and_collection_nfts() {
+        let state = v1_state();
+        let mut locked = stored_nft_info(Pubkey::new_unique(), true);
+        locked.is_locked = true;
+        assert!(check_rescuable(&state, Some(&locked), locked.mint).is_err());
+        assert!(check_rescuable(&state, None, state.collection_mint).is_err());
+    }
+
+    fn evm_chain() -> ChainInfo {
+        let mut connected = [0; 32];
+        connected[..20].copy_from_slice(&[7; 20]);
+        ChainInfo {
+            chain_id: 1,
+            name: "Ethereum".to_string(),
+            chain_kind: ChainKind::Evm,
+            enabled: true,
+            recipient_len: 20,
+            connected,
+            flat_fee_lamports: 0,
+            gas_fee_lamports: 0,
+            inbound_enabled: true,
+            inbound_paused: false,
+            fee_token: None,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_chain_fee_adds_up() {
+        let mut chain = evm_chain();
+        assert_eq!(chain_fee(&chain).unwrap(), 0);
+
+        chain.flat_fee_lamports = 5_000;
+        chain.gas_fee_lamports = 120_000;
+        assert_eq!(chain_fee(&chain).unwrap(), 125_000);
+
+        chain.gas_fee_lamports = u64::MAX;
+        assert!(chain_fee(&chain).is_err());
+    }
+
+    #[test]
+    fn test_destination_recipient_fits_the_chain() {
+        let chain = evm_chain();
+        let mut recipient = [0; 32];
+        recipient[..20].copy_from_slice(&[1; 20]);
+        assert!(check_destination(&chain, &recipient).is_ok());
+
+        // a 32 byte value doesnt fit a 20 byte chain
+        assert!(check_destination(&chain, &[1; 32]).is_err());
+        assert!(check_destination(&chain, &[0; 32]).is_err());
+    }
+
+    #[test]
+    fn test_disabled_chain_blocks_both_ways() {
+        let mut chain = evm_chain();
+        let mut recipient = [0; 32];
+        recipient[..20].copy_from_slice(&[1; 20]);
+        assert!(check_source(&chain, &[7; 20]).is_ok());
+        assert!(check_source(&chain, &[8; 20]).is_err());
+
+        chain.enabled = false;
+        assert!(check_destination(&chain, &recipient).is_err());
+        assert!(check_source(&chain, &[7; 20]).is_err());
+    }
+
+    #[test]
+    fn test_inbound_can_be_closed_on_its_own() {
+        let mut chain = evm_chain();
+        let mut recipient = [0; 32];
+        recipient[..20].copy_from_slice(&[1; 20]);
+
+        chain.inbound_enabled = false;
+        assert_eq!(check_source(&chain, &[7; 20]).unwrap_err(), NftError::SourceChainNotAllowed.into());
+        assert!(check_destination(&chain, &recipient).is_ok());
+
+        // inbound on doesnt open a disabled chain
+        chain.inbound_enabled = true;
+        chain.enabled = false;
+        assert_eq!(check_source(&chain, &[7; 20]).unwrap_err(), NftError::SourceChainNotAllowed.into());
+    }
+
+    #[test]
+    fn test_chain_config_bounds() {
+        let config = |name: &str, recipient_len| ChainConfig {
+            name: name.to_string(),
+            chain_kind: ChainKind::Other,
+            enabled: true,
+            recipient_len,
+            connected: [0; 32],
+        };
+        assert!(validate_chain_config(&config("Bitcoin", 32)).is_ok());
+        assert!(validate_chain_config(&config("", 20)).is_err());
+        assert!(validate_chain_config(&config(&"a".repeat(33), 20)).is_err());
+        assert!(validate_chain_config(&config("Base", 0)).is_err());
+        assert!(validate_chain_config(&config("Base", 33)).is_err());
+    }
+
+    #[test]
+    fn test_rescue_allows_stray_tokens() {
+        let state = v1_state();
+        let unlocked = stored_nft_info(Pubkey::new_unique(), true);
+        assert!(check_rescuable(&state, Some(&unlocked), unlocked.mint).is_ok());
+        assert!(check_rescuable(&state, None, Pubkey::new_unique()).is_ok());
+    }
+
+    #[test]
+    fn test_on_call_checks_the_stored_gateway() {
+        use crate::gateway::ZETACHAIN;
+
+        let mut state = v1_state();
+        let localnet_gateway = Pubkey::new_unique();
+        state.gateway = localnet_gateway;
+
+        assert!(check_gateway_caller(&state, localnet_gateway, 2).is_ok());
+        assert!(check_gateway_caller(&state, ZETACHAIN, 2).is_err());
+
+        state.gateway = ZETACHAIN;
+        assert!(check_gateway_caller(&state, ZETACHAIN, 2).is_ok());
+        assert!(check_gateway_caller(&state, localnet_gateway, 2).is_err());
+    }
+
+    #[test]
+    fn test_on_call_only_takes_a_direct_gateway_cpi() {
+        let state = v1_state();
+
+        // called as the top level instruction, no gateway in between
+        assert!(check_gateway_caller(&state, state.gateway, 1).is_err());
+        // gateway -> another program -> on_call, the sysvar still shows the gateway
+        assert!(check_gateway_caller(&state, state.gateway, 3).is_err());
+        assert!(check_gateway_caller(&state, state.gateway, 2).is_ok());
+    }
+
+    #[test]
+    fn test_bridged_mint_has_to_match_the_origin_mint() {
+        let origin_mint = Pubkey::new_unique();
+        let (mint, bump) = seeds::find_bridged_mint(&origin_mint);
+
+        assert_eq!(check_bridged_mint(mint, &origin_mint).unwrap(), bump);
+        // the mint of some other nft
+        assert!(check_bridged_mint(mint, &Pubkey::new_unique()).is_err());
+        assert!(check_bridged_mint(origin_mint, &origin_mint).is_err());
+    }
+
+    #[test]
+    fn test_default_gateway_for_cluster() {
+        use crate::gateway::{default_gateway_for_cluster, LOCALNET, ZETACHAIN};
+
+        assert_eq!(default_gateway_for_cluster("localnet"), Some(LOCALNET));
+        assert_eq!(default_gateway_for_cluster("devnet"), Some(ZETACHAIN));
+        assert_eq!(default_gateway_for_cluster("mainnet-beta"), Some(ZETACHAIN));
+        assert_eq!(default_gateway_for_cluster("testnet"), None);
+    }
+
+    #[test]
+    fn test_version_matches_the_crate() {
+        let version = ProgramVersion::current();
+        let semver = format!("{}.{}.{}", version.major, version.minor, version.patch);
+
+        assert_eq!(semver, env!("CARGO_PKG_VERSION"));
+        assert_eq!(version.message_version, MESSAGE_VERSION);
+        assert_eq!(version.features_bitmask & FEATURE_COMPRESSED != 0, cfg!(feature = "compressed"));
+    }
+
+    // an nft info of an older layout: serialized without the fields that came
+    // later, whatever was left in the account after it
+    fn old_nft_info(nft_info: &NftInfo, version: u8, leftover: u8) -> NftInfo {
+        let mut old = nft_info.clone();
+        old.version = version;
+        let later_fields = match version {
+            1 => 1 + 1 + 1 + 1 + 1 + 8,
+            2 => 1 + 1 + 1 + 1 + 8,
+            3 => 1 + 1 + 1 + 8,
+            4 => 1 + 1 + 8,
+            5 => 1 + 8,
+            _ => 8,
+        };
+        let mut image = Vec::new();
+        old.try_serialize(&mut image).unwrap();
+        image.truncate(image.len() - later_fields);
+        image.resize(NftInfo::space(DEFAULT_MAX_URI_LEN) - later_fields, leftover);
+        NftInfo::try_deserialize(&mut image.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_v1_nft_info_reads_and_bridges() {
+        let mut original = stored_nft_info(Pubkey::new_unique(), true);
+        original.royalty_bps = 500;
+
+        let mut nft_info = old_nft_info(&original, 1, 0);
+        assert_eq!(nft_info.mint, original.mint);
+        assert_eq!(nft_info.royalty_bps, 500);
+
+        assert_eq!(upgrade_nft_info_fields(&mut nft_info), 13);
+        assert_eq!(nft_info.version, NFT_INFO_VERSION);
+        assert!(nft_info.metadata_created);
+        assert!(!nft_info.needs_metadata_repair);
+        assert!(!nft_info.metadata_immutable);
+        assert_eq!(upgrade_nft_info_fields(&mut nft_info), 0);
+
+        let message = transfer_message(&nft_info, [2; 32], 1);
+        assert_eq!(message.mint, original.mint);
+        assert_eq!(message.royalty_bps, Some(500));
+    }
+
+    #[test]
+    fn test_v1_nft_info_with_leftover_bytes() {
+        // a leftover byte that looks like some other version is still a v1 account.
+        // only the version byte, the flags after it dont decode from anything but 0 or 1
+        let mut nft_info = old_nft_info(&stored_nft_info(Pubkey::new_unique(), false), 1, 0);
+        nft_info.version = NFT_INFO_VERSION + 2;
+
+        assert_eq!(upgrade_nft_info_fields(&mut nft_info), 13);
+        assert_eq!(nft_info.version, NFT_INFO_VERSION);
+    }
+
+    #[test]
+    fn test_v2_nft_info_had_its_metadata_created() {
+        // the leftover byte where metadata_created goes now reads as false
+        let mut nft_info = old_nft_info(&stored_nft_info(Pubkey::new_unique(), true), 2, 0);
+        assert!(!nft_info.metadata_created);
+
+        assert_eq!(upgrade_nft_info_fields(&mut nft_info), 12);
+        assert_eq!(nft_info.version, NFT_INFO_VERSION);
+        assert!(nft_info.metadata_created);
+    }
+
+    #[test]
+    fn test_v3_nft_info_keeps_its_lazy_metadata_state() {
+        let mut original = stored_nft_info(Pubkey::new_unique(), true);
+        original.metadata_created = false;
+        let mut nft_info = old_nft_info(&original, 3, 1);
+        assert!(nft_info.needs_metadata_repair);
+
+        assert_eq!(upgrade_nft_info_fields(&mut nft_info), 11);
+        assert_eq!(nft_info.version, NFT_INFO_VERSION);
+        assert!(!nft_info.metadata_created);
+        assert!(!nft_info.needs_metadata_repair);
+    }
+
+    #[test]
+    fn test_v4_nft_info_keeps_its_repair_flag() {
+        let mut original = stored_nft_info(Pubkey::new_unique(), true);
+        original.needs_metadata_repair = true;
+        // the leftover byte where metadata_immutable goes now reads as true
+        let mut nft_info = old_nft_info(&original, 4, 1);
+        assert!(nft_info.metadata_immutable);
+
+        assert_eq!(upgrade_nft_info_fields(&mut nft_info), 10);
+        assert_eq!(nft_info.version, NFT_INFO_VERSION);
+        assert!(nft_info.needs_metadata_repair);
+        assert!(!nft_info.metadata_immutable);
+    }
+
+    #[test]
+    fn test_v5_nft_info_keeps_its_immutable_count_and_isnt_external() {
+        let mut original = stored_nft_info(Pubkey::new_unique(), true);
+        original.metadata_immutable = true;
+        // the leftover byte where external goes now reads as true
+        let mut nft_info = old_nft_info(&original, 5, 1);
+        assert!(nft_info.external);
+
+        assert!(!is_external(&nft_info));
+
+        assert_eq!(upgrade_nft_info_fields(&mut nft_info), 9);
+        assert_eq!(nft_info.version, NFT_INFO_VERSION);
+        assert!(nft_info.metadata_immutable);
+        assert!(!nft_info.external);
+    }
+
+    #[test]
+    fn test_v6_nft_info_stays_external_without_a_lock_nonce() {
+        let mut original = stored_nft_info(Pubkey::new_unique(), false);
+        original.external = true;
+        // leftover bytes where lock_nonce goes now
+        let mut nft_info = old_nft_info(&original, 6, 0xff);
+        assert_ne!(nft_info.lock_nonce, 0);
+
+        // the flag is real on a v6 account, upgraded or not
+        assert!(is_external(&nft_info));
+
+        assert_eq!(upgrade_nft_info_fields(&mut nft_info), 8);
+        assert_eq!(nft_info.version, NFT_INFO_VERSION);
+        assert!(is_external(&nft_info));
+        assert_eq!(nft_info.lock_nonce, 0);
+    }
+
+    #[test]
+    fn test_immutable_metadata_counts_bridged_nfts_once() {
+        let mut state = v1_state();
+        let mut bridged = stored_nft_info(Pubkey::new_unique(), true);
+        let mut local = stored_nft_info(Pubkey::new_unique(), false);
+
+        record_immutable_metadata(&mut state, &mut bridged).unwrap();
+        record_immutable_metadata(&mut state, &mut bridged).unwrap();
+        record_immutable_metadata(&mut state, &mut local).unwrap();
+        assert_eq!(state.immutable_bridged_count, 1);
+        assert!(bridged.metadata_immutable);
+        assert!(!local.metadata_immutable);
+
+        // an older nft info going away isnt trusted with its leftover flag
+        let mut older = bridged.clone();
+        older.version = 4;
+        forget_immutable_metadata(&mut state, &older);
+        assert_eq!(state.immutable_bridged_count, 1);
+        forget_immutable_metadata(&mut state, &bridged);
+        assert_eq!(state.immutable_bridged_count, 0);
+        forget_immutable_metadata(&mut state, &bridged);
+        assert_eq!(state.immutable_bridged_count, 0);
+    }
+
+    #[test]
+    fn test_fresh_nft_info_needs_no_space() {
+        let mut nft_info = stored_nft_info(Pubkey::default(), false);
+        nft_info.version = 0;
+
+        assert_eq!(upgrade_nft_info_fields(&mut nft_info), 0);
+    }
+
+    fn attribute(key: &str, value: &str) -> Attribute {
+        Attribute {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_attribute_bounds() {
+        let full = vec![attribute(&"k".repeat(16), &"v".repeat(32)); MAX_ATTRIBUTES];
+        assert!(validate_attributes(&full).is_ok());
+        assert!(validate_attributes(&[]).is_ok());
+
+        let too_many = vec![attribute("k", "v"); MAX_ATTRIBUTES + 1];
+        assert!(validate_attributes(&too_many).is_err());
+        assert!(validate_attributes(&[attribute("", "v")]).is_err());
+        assert!(validate_attributes(&[attribute(&"k".repeat(17), "v")]).is_err());
+        assert!(validate_attributes(&[attribute("k", &"v".repeat(33))]).is_err());
+    }
+
+    #[test]
+    fn test_attributes_travel_in_the_message() {
+        let mut message = inbound_message("Punk", "PNK", token_id_mint(1));
+        message.attributes = vec![attribute("eyes", "laser"), attribute("hat", "")];
+
+        let decoded = CrossChainMessage::try_from_slice(&message.try_to_vec().unwrap()).unwrap();
+
+        assert_eq!(decoded.attributes, message.attributes);
+    }
+
+    #[test]
+    fn test_message_hash_matches_both_sides() {
+        // what transfer_to_zetachain hashes is what on_call gets back as data
+        let message = inbound_message("Punk", "PNK", token_id_mint(1));
+        let outbound = message.try_to_vec().unwrap();
+        let inbound = CrossChainMessage::try_from_slice(&outbound).unwrap();
+
+        // the syscall keccak here, the wire crates software keccak in canonical_hash
+        assert_eq!(canonical_message_hash(&outbound), inbound.canonical_hash());
+        assert_eq!(message.canonical_hash(), inbound.canonical_hash());
+
+        let mut other = message.clone();
+        other.nonce += 1;
+        assert_ne!(message.canonical_hash(), other.canonical_hash());
+    }
+
+    fn batch_leaves(count: u64) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|i| {
+                let mut message = hash_fixture_transfer();
+                message.recipient = [i as u8 + 1; 32];
+                message.try_to_vec().unwrap()
+            })
+            .collect()
+    }
+
+    fn inbound_batch(leaves: &[Vec<u8>]) -> InboundBatch {
+        let leaf_count = leaves.len() as u64;
+        InboundBatch {
+            batch_id: Pubkey::new_unique(),
+            root: wire::batch_root(leaves),
+            leaf_count,
+            claimed_count: 0,
+            nonce: 7,
+            message_hash: [0; 32],
+            source_chain_id: 7001,
+            sender: [0; 20],
+            slot: 0,
+            bump: 255,
+            claimed: vec![0; leaf_count.div_ceil(8) as usize],
+        }
+    }
+
+    #[test]
+    fn test_batch_proofs_from_the_wire_crate_verify_here() {
+        let leaves = batch_leaves(5);
+        let batch = inbound_batch(&leaves);
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = wire::batch_proof(&leaves, index);
+            assert!(verify_batch_leaf(&batch, index as u64, leaf, &proof));
+        }
+
+        let proof = wire::batch_proof(&leaves, 2);
+        // another message, another index, a proof cut short or run past the end
+        assert!(!verify_batch_leaf(&batch, 2, &leaves[3], &proof));
+        assert!(!verify_batch_leaf(&batch, 3, &leaves[2], &proof));
+        assert!(!verify_batch_leaf(&batch, 2, &leaves[2], &proof[1..]));
+        let mut longer = proof.clone();
+        longer.push([0; 32]);
+        assert!(!verify_batch_leaf(&batch, 2, &leaves[2], &longer));
+        // the padding leaves of the tree arent leaves of the batch
+        let padding = wire::batch_proof(&leaves, 5);
+        assert!(!verify_batch_leaf(&batch, 5, &[], &padding));
+    }
+
+    #[test]
+    fn test_batch_leaves_are_claimed_once() {
+        let mut batch = inbound_batch(&batch_leaves(10));
+        assert_eq!(batch.claimed.len(), 2);
+        assert_eq!(InboundBatch::space(10), 8 + InboundBatch::INIT_SPACE + 2);
+
+        claim_batch_leaf(&mut batch, 9).unwrap();
+        claim_batch_leaf(&mut batch, 0).unwrap();
+        assert_eq!(batch.claimed, [0b1, 0b10]);
+        assert_eq!(batch.claimed_count, 2);
+        assert!(claim_batch_leaf(&mut batch, 9).is_err());
+        assert_eq!(batch.claimed_count, 2);
+    }
+
+    // fixed fixtures, the evm side computes the same values. if one of these
+    // changes the message layout changed and MESSAGE_VERSION has to go up
+    fn hash_fixture_transfer() -> CrossChainMessage {
+        CrossChainMessage {
+            message_type: MessageType::Transfer,
+            mint: Pubkey::new_from_array([1; 32]),
+            recipient: [2; 32],
+            metadata_uri: "https://example.com/nft.json".to_string(),
+            name: "Punk".to_string(),
+            symbol: "PNK".to_string(),
+            nonce: 7,
+            master_mint: Pubkey::default(),
+            edition: 0,
+            metadata_hash: None,
+            royalty_bps: Some(500),
+            royalty_recipient: None,
+            attributes: vec![attribute("eyes", "laser")],
+        }
+    }
+
+    fn hash_fixture_unlock() -> CrossChainMessage {
+        CrossChainMessage {
+            message_type: MessageType::Unlock,
+            mint: Pubkey::new_from_array([3; 32]),
+            recipient: [0; 32],
+            metadata_uri: String::new(),
+            name: String::new(),
+            symbol: String::new(),
+            nonce: u64::MAX,
+            master_mint: Pubkey::new_from_array([5; 32]),
+            edition: 12,
+            metadata_hash: Some([9; 32]),
+            royalty_bps: None,
+            royalty_recipient: Some(Pubkey::new_from_array([4; 32])),
+            attributes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_canonical_hash_known_answers() {
+        assert_eq!(MESSAGE_VERSION, 1);
+        assert_eq!(
+            hash_fixture_transfer().canonical_hash(),
+            [
+                0x39, 0x65, 0xa9, 0xd8, 0x84, 0x3b, 0x91, 0xd8, 0x7e, 0x42, 0xf0, 0x3f, 0x70, 0x8b,
+                0xff, 0x9d, 0xf5, 0x7e, 0x42, 0x0a, 0xd9, 0x23, 0x28, 0xaa, 0xac, 0x40, 0xc0, 0xf8,
+                0x16, 0x75, 0x87, 0x20,
+            ]
+        );
+        assert_eq!(
+            hash_fixture_unlock().canonical_hash(),
+            [
+                0xe7, 0xad, 0x54, 0x2a, 0x8f, 0xcb, 0x63, 0x01, 0xf7, 0xbc, 0xe7, 0x98, 0x15, 0xc5,
+                0xcd, 0xd5, 0xc2, 0x48, 0xf8, 0x8a, 0x6d, 0x23, 0xdd, 0x6f, 0xff, 0xc8, 0xbf, 0xdf,
+                0x52, 0xf8, 0x68, 0x08,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nft_mint_authority() {
+        let nft_program = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let edition = MasterEdition::find_pda(&mint).0;
+
+        assert!(is_nft_mint_authority(COption::Some(nft_program), mint, nft_program));
+        assert!(is_nft_mint_authority(COption::Some(edition), mint, nft_program));
+        // the edition of another mint, someone elses key or no authority at all
+        assert!(!is_nft_mint_authority(
+            COption::Some(MasterEdition::find_pda(&Pubkey::new_unique()).0),
+            mint,
+            nft_program
+        ));
+        assert!(!is_nft_mint_authority(COption::Some(Pubkey::new_unique()), mint, nft_program));
+        assert!(!is_nft_mint_authority(COption::None, mint, nft_program));
+    }
+
+    #[test]
+    fn test_decode_message_bounds_the_input() {
+        let message = hash_fixture_transfer();
+        let bytes = message.try_to_vec().unwrap();
+        assert_eq!(decode_message(&bytes).unwrap().canonical_hash(), message.canonical_hash());
+
+        let oversized = vec![0; wire::MAX_MESSAGE_LEN + 1];
+        assert_eq!(decode_message(&oversized).unwrap_err(), NftError::MessageTooLarge.into());
+
+        // a uri declaring 4gb
+        let mut huge = bytes.clone();
+        huge[65..69].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(decode_message(&huge).unwrap_err(), NftError::MessageTooLarge.into());
+
+        assert_eq!(decode_message(&bytes[..10]).unwrap_err(), NftError::InvalidMessage.into());
+    }
+
+    #[test]
+    fn test_message_caps_leave_room_for_the_program_limits() {
+        // anything the program would take or cut down itself has to decode first
+        assert_eq!(wire::MAX_MESSAGE_URI_LEN, MAX_URI_LEN_CAP as usize);
+        const _: () = {
+            assert!(wire::MAX_MESSAGE_NAME_LEN > MAX_NAME_LEN);
+            assert!(wire::MAX_MESSAGE_SYMBOL_LEN > MAX_SYMBOL_LEN);
+            assert!(wire::MAX_MESSAGE_ATTRIBUTES > MAX_ATTRIBUTES);
+            assert!(wire::MAX_MESSAGE_ATTRIBUTE_KEY_LEN > MAX_ATTRIBUTE_KEY_LEN);
+            assert!(wire::MAX_MESSAGE_ATTRIBUTE_VALUE_LEN > MAX_ATTRIBUTE_VALUE_LEN);
+        };
+    }
+
+    #[test]
+    fn test_wire_message_encodes_like_the_program_message() {
+        let bytes = hash_fixture_unlock().try_to_vec().unwrap();
+        let plain = wire::CrossChainMessage::<[u8; 32]>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(plain.mint, [3; 32]);
+        assert_eq!(plain.royalty_recipient, Some([4; 32]));
+        assert_eq!(plain.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_canonical_hash_is_keccak_of_versioned_bytes() {
+        assert_eq!(canonical_message_hash(&[]), keccak::hash(&[MESSAGE_VERSION]).to_bytes());
+
+        let bytes = hash_fixture_transfer().try_to_vec().unwrap();
+        let mut versioned = vec![MESSAGE_VERSION];
+        versioned.extend_from_slice(&bytes);
+        assert_eq!(canonical_message_hash(&bytes), keccak::hash(&versioned).to_bytes());
+    }
+
+    #[test]
+    fn test_message_nonce_rejections() {
+        let mut state = v1_state();
+        state.nonce = 10;
+
+        assert!(accept_message_nonce(&mut state, 10).is_err());
+        assert!(accept_message_nonce(&mut state, 3).is_err());
+        assert_eq!(state.nonce, 10);
+        assert_eq!(expected_nonce(&state), 11);
+
+        // gaps are fine, the next message just has to go above. the missing range
+        // comes back for the event
+        let gap = accept_message_nonce(&mut state, 15).unwrap().unwrap();
+        assert_eq!((gap.first_missing, gap.last_missing, gap.received_nonce), (11, 14, 15));
+        assert_eq!(state.nonce, 15);
+        assert_eq!(expected_nonce(&state), 16);
+        assert!(accept_message_nonce(&mut state, 16).unwrap().is_none());
+
+        // and the missing ones can still come in late, once each
+        assert!(accept_message_nonce(&mut state, 12).unwrap().is_none());
+        assert!(accept_message_nonce(&mut state, 12).is_err());
+        assert_eq!(state.nonce, 16);
+
+        state.nonce = u64::MAX;
+        assert_eq!(expected_nonce(&state), u64::MAX);
+    }
+
+    #[test]
+    fn test_nonces_far_below_the_window_are_stale() {
+        let mut state = v1_state();
+        state.nonce = 1_000;
+        state.nonce_window = [0; 4];
+
+        assert!(accept_message_nonce(&mut state, 1_000 - NONCE_WINDOW).is_err());
+        assert!(accept_message_nonce(&mut state, 0).is_err());
+        accept_message_nonce(&mut state, 1_000 - NONCE_WINDOW + 1).unwrap();
+        accept_message_nonce(&mut state, 999).unwrap();
+
+        // moving up pushes the oldest ones out
+        accept_message_nonce(&mut state, 1_001).unwrap();
+        assert!(accept_message_nonce(&mut state, 1_001 - NONCE_WINDOW).is_err());
+        accept_message_nonce(&mut state, 1_002 - NONCE_WINDOW).unwrap();
+    }
+
+    #[test]
+    fn test_nonces_far_ahead_of_the_window_are_refused() {
+        let mut state = v1_state();
+        state.nonce = 1_000;
+
+        // one that far up would open every nonce in between and drop the window
+        assert!(accept_message_nonce(&mut state, 1_000 + NONCE_WINDOW + 1).is_err());
+        assert!(accept_message_nonce(&mut state, u64::MAX).is_err());
+        assert_eq!(state.nonce, 1_000);
+        assert_eq!(state.nonce_window, [u64::MAX; 4]);
+
+        accept_message_nonce(&mut state, 1_000 + NONCE_WINDOW).unwrap();
+        assert_eq!(state.nonce, 1_000 + NONCE_WINDOW);
+        assert!(accept_message_nonce(&mut state, 1_000 + 2 * NONCE_WINDOW + 1).is_err());
+    }
+
+    #[test]
+    fn test_outbound_nonces_stay_out_of_the_inbound_window() {
+        let mut state = v1_state();
+        state.nonce = 10;
+        state.outbound_nonce = 3;
+
+        assert_eq!(next_outbound_nonce(&mut state).unwrap(), 4);
+        assert_eq!(next_outbound_nonce(&mut state).unwrap(), 5);
+        assert_eq!((state.nonce, state.nonce_window), (10, [u64::MAX; 4]));
+        accept_message_nonce(&mut state, 11).unwrap();
+        assert_eq!(state.outbound_nonce, 5);
+
+        state.outbound_nonce = u64::MAX;
+        assert!(next_outbound_nonce(&mut state).is_err());
+    }
+
+    #[test]
+    fn test_nonce_window_slides_across_words() {
+        let taken = |window: &[u64; 4]| -> Vec<u64> {
+            (0..NONCE_WINDOW).filter(|offset| nonce_taken(window, *offset)).collect()
+        };
+        let mut window = [0; 4];
+        for offset in [0, 63, 64, 200] {
+            mark_nonce(&mut window, offset);
+        }
+
+        slide_nonce_window(&mut window, 1);
+        assert_eq!(taken(&window), vec![1, 64, 65, 201]);
+        slide_nonce_window(&mut window, 64);
+        assert_eq!(taken(&window), vec![65, 128, 129]);
+        slide_nonce_window(&mut window, 126);
+        assert_eq!(taken(&window), vec![191, 254, 255]);
+        slide_nonce_window(&mut window, 0);
+        assert_eq!(taken(&window), vec![191, 254, 255]);
+        slide_nonce_window(&mut window, 1);
+        assert_eq!(taken(&window), vec![192, 255]);
+        slide_nonce_window(&mut window, NONCE_WINDOW);
+        assert_eq!(window, [0; 4]);
+
+        let mut window = [u64::MAX; 4];
+        slide_nonce_window(&mut window, NONCE_WINDOW - 1);
+        assert_eq!(taken(&window), vec![NONCE_WINDOW - 1]);
+        let mut window = [u64::MAX; 4];
+        slide_nonce_window(&mut window, u64::MAX);
+        assert_eq!(window, [0; 4]);
+    }
+
+    #[test]
+    fn test_nonce_window_matches_a_plain_set() {
+        use std::collections::BTreeSet;
+
+        // every nonce ever taken, and what the window should say about one
+        let mut state = v1_state();
+        state.nonce = 0;
+        let mut taken = BTreeSet::new();
+        let expected = |taken: &BTreeSet<u64>, highest: u64, nonce: u64| {
+            match nonce > highest {
+                true => nonce - highest <= NONCE_WINDOW,
+                false => highest - nonce < NONCE_WINDOW && nonce != 0 && !taken.contains(&nonce),
+            }
+        };
+
+        // jumps of every size, late arrivals, duplicates and nonces far below or ahead
+        let mut seed = 7u64;
+        for _ in 0..20_000 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let highest = state.nonce;
+            let nonce = match (seed >> 33) % 8 {
+                0 => highest + 1 + (seed >> 40) % 3,
+                1 => highest + 1 + (seed >> 40) % 300,
+                2 | 3 => highest.saturating_sub((seed >> 40) % NONCE_WINDOW),
+                4 => highest.saturating_sub((seed >> 40) % (2 * NONCE_WINDOW)),
+                5 => highest.saturating_sub(NONCE_WINDOW - 1 + (seed >> 40) % 3),
+                _ => *taken.iter().nth_back(((seed >> 40) % 16) as usize).unwrap_or(&0),
+            };
+
+            let accepted = accept_message_nonce(&mut state, nonce).is_ok();
+            assert_eq!(accepted, expected(&taken, highest, nonce), "nonce {nonce} at {highest}");
+            if accepted {
+                taken.insert(nonce);
+            }
+            assert_eq!(state.nonce, highest.max(if accepted { nonce } else { 0 }));
+            for offset in 0..NONCE_WINDOW.min(state.nonce) {
+                let nonce = state.nonce - offset;
+                assert_eq!(nonce_taken(&state.nonce_window, offset), taken.contains(&nonce));
+            }
+        }
+    }
+
+    // signed offline with the test key [0x5a; 32] over tss_message(&[0x11; 32], 9, 7001)
+    const TSS_FIXTURE_ADDRESS: [u8; 20] = [
+        0xa8, 0x04, 0x9b, 0xb6, 0x81, 0x81, 0x79, 0x91, 0x24, 0xf9, 0x8e, 0x46, 0x7d, 0xd7,
+        0x49, 0xe1, 0x20, 0xab, 0xfa, 0x64,
+    ];
+    const TSS_FIXTURE_SIGNATURE: [u8; 64] = [
+        0x3b, 0x0d, 0x1f, 0x82, 0x6a, 0x13, 0x62, 0xba, 0x3e, 0x3a, 0xfa, 0x9a, 0x87, 0x14,
+        0xa7, 0xb9, 0x21, 0x33, 0x59, 0x92, 0x9a, 0xc9, 0x16, 0xc7, 0xdf, 0xee, 0x60, 0x06,
+        0x36, 0xa1, 0xfa, 0x64, 0x4c, 0xec, 0x67, 0xcb, 0x29, 0xb8, 0xce, 0x08, 0xa3, 0xe4,
+        0x7e, 0xb2, 0xb6, 0x6c, 0xd5, 0x68, 0x8c, 0xf9, 0x8f, 0x54, 0x17, 0xfc, 0x58, 0x46,
+        0x9a, 0x05, 0xc9, 0xd2, 0x90, 0xf9, 0xff, 0xb0,
+    ];
+    const TSS_FIXTURE_RECOVERY_ID: u8 = 0;
+
+    // secp256k1 program data with one signature, laid out like solana_sdk's
+    // new_secp256k1_instruction: offsets, address, signature, recovery id, message
+    fn secp256k1_data(index: u8, address: &[u8; 20], message: &[u8]) -> Vec<u8> {
+        let (address_at, signature_at, message_at) = (12u16, 32u16, 97u16);
+        let mut data = vec![1];
+        data.extend_from_slice(&signature_at.to_le_bytes());
+        data.push(index);
+        data.extend_from_slice(&address_at.to_le_bytes());
+        data.push(index);
+        data.extend_from_slice(&message_at.to_le_bytes());
+        data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+        data.push(index);
+        data.extend_from_slice(address);
+        data.extend_from_slice(&TSS_FIXTURE_SIGNATURE);
+        data.push(TSS_FIXTURE_RECOVERY_ID);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn test_tss_fixture_recovers_to_the_tss_address() {
+        let message = tss_message(&[0x11; 32], 9, 7001);
+        assert_eq!(&message[32..40], &9u64.to_be_bytes());
+        assert_eq!(&message[40..], &7001u64.to_be_bytes());
+
+        let recovered = anchor_lang::solana_program::secp256k1_recover::secp256k1_recover(
+            &keccak::hash(&message).to_bytes(),
+            TSS_FIXTURE_RECOVERY_ID,
+            &TSS_FIXTURE_SIGNATURE,
+        )
+        .unwrap();
+        assert_eq!(keccak::hash(&recovered.to_bytes()).to_bytes()[12..], TSS_FIXTURE_ADDRESS);
+    }
+
+    #[test]
+    fn test_secp256k1_signs_needs_the_address_and_message_in_place() {
+        let message = tss_message(&[0x11; 32], 9, 7001);
+        let data = secp256k1_data(2, &TSS_FIXTURE_ADDRESS, &message);
+        assert!(secp256k1_signs(&data, 2, &TSS_FIXTURE_ADDRESS, &message));
+
+        // another signer, another nonce or chain, data in another instruction
+        assert!(!secp256k1_signs(&data, 2, &[1; 20], &message));
+        assert!(!secp256k1_signs(&data, 2, &TSS_FIXTURE_ADDRESS, &tss_message(&[0x11; 32], 10, 7001)));
+        assert!(!secp256k1_signs(&data, 2, &TSS_FIXTURE_ADDRESS, &tss_message(&[0x11; 32], 9, 1)));
+        assert!(!secp256k1_signs(&data, 1, &TSS_FIXTURE_ADDRESS, &message));
+
+        // no signatures, or offsets past the end
+        let mut none = data.clone();
+        none[0] = 0;
+        assert!(!secp256k1_signs(&none, 2, &TSS_FIXTURE_ADDRESS, &message));
+        assert!(!secp256k1_signs(&data[..data.len() - 1], 2, &TSS_FIXTURE_ADDRESS, &message));
+        assert!(!secp256k1_signs(&[], 2, &TSS_FIXTURE_ADDRESS, &message));
+    }
+
+    #[test]
+    fn test_ordered_delivery_waits_inside_the_window() {
+        let mut state = v1_state();
+        state.nonce = 10;
+
+        // off, anything above goes through and gives up the gap
+        assert!(!must_wait(&state, 15).unwrap());
+
+        state.ordered_delivery = true;
+        assert!(!must_wait(&state, 11).unwrap());
+        assert!(!must_wait(&state, 10).unwrap()); // accept_message_nonce turns it away
+        assert!(must_wait(&state, 12).unwrap());
+        assert!(must_wait(&state, 11 + PENDING_NONCE_WINDOW - 1).unwrap());
+        assert!(must_wait(&state, 11 + PENDING_NONCE_WINDOW).is_err());
+    }
+
+    #[test]
+    fn test_message_rejected_log() {
+        assert_eq!(
+            message_rejected_log(11, 10, NonceRejection::Replay),
+            r#"{"event":"MessageRejected","expected_nonce":11,"received_nonce":10,"reason":"replay"}"#
+        );
+        assert_eq!(
+            message_rejected_log(11, 3, NonceRejection::Stale),
+            r#"{"event":"MessageRejected","expected_nonce":11,"received_nonce":3,"reason":"stale"}"#
+        );
+    }
+
+    fn inbound_transfer(recipient: [u8; 32]) -> CrossChainMessage {
+        let mut message = inbound_message("Punk", "PNK", token_id_mint(1));
+        message.recipient = recipient;
+        message
+    }
+
+    fn dead_letter_reason(result: Result<InboundPlan>) -> DeadLetterReason {
+        DeadLetterReason::from_error(&result.err().unwrap())
+    }
+
+    #[test]
+    fn test_plan_inbound_transfer() {
+        let state = v1_state();
+        let fresh = stored_nft_info(Pubkey::default(), false);
 
-        msg!("NFT unlocked for mint {}", nft_info.mint);
-        Ok(())
+        let plan = plan_inbound(&state, &fresh, inbound_transfer([7; 32])).unwrap();
+
+        assert_eq!(plan.recipient, Pubkey::new_from_array([7; 32]));
+        assert_eq!(plan.message.royalty_bps, Some(plan.royalty_bps));
+        assert!(!plan.hash_mismatch);
     }
-}
 
-// account structs for all the instructions, dont mess with the order
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + NftProgramState::INIT_SPACE,
-        seeds = [b"nft-program"],
-        bump
-    )]
-    pub nft_program: Account<'info, NftProgramState>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+    #[test]
+    fn test_plan_inbound_failures_map_to_dead_letter_reasons() {
+        let state = v1_state();
+        let fresh = stored_nft_info(Pubkey::default(), false);
 
-#[derive(Accounts)]
-#[instruction(name: String, symbol: String, uri: String, recipient: Pubkey)]
-pub struct MintNft<'info> {
-    #[account(
-        mut,
-        seeds = [b"nft-program"],
-        bump = nft_program.bump
-    )]
-    pub nft_program: Account<'info, NftProgramState>,
+        let to_nobody = plan_inbound(&state, &fresh, inbound_transfer([0; 32]));
+        assert_eq!(dead_letter_reason(to_nobody), DeadLetterReason::InvalidRecipient);
+        let to_program = plan_inbound(&state, &fresh, inbound_transfer(crate::ID.to_bytes()));
+        assert_eq!(dead_letter_reason(to_program), DeadLetterReason::InvalidRecipient);
 
-    #[account(
-        init,
-        payer = payer,
-        mint::decimals = 0,
-        mint::authority = nft_program,
-    )]
-    pub mint: Account<'info, Mint>,
+        let mut bad_uri = inbound_transfer([7; 32]);
+        bad_uri.metadata_uri = "ftp://example.com".to_string();
+        let bad_uri = plan_inbound(&state, &fresh, bad_uri);
+        assert_eq!(dead_letter_reason(bad_uri), DeadLetterReason::InvalidMetadata);
 
-    #[account(
-        init,
-        payer = payer,
-        associated_token::mint = mint,
-        associated_token::authority = recipient,
-    )]
-    pub token_account: Account<'info, TokenAccount>,
+        let local = stored_nft_info(Pubkey::new_unique(), false);
+        let collided = plan_inbound(&state, &local, inbound_transfer([7; 32]));
+        assert_eq!(dead_letter_reason(collided), DeadLetterReason::NftInfoCollision);
 
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + NftInfo::INIT_SPACE,
-        seeds = [b"nft-info", mint.key().as_ref()],
-        bump
-    )]
-    pub nft_info: Account<'info, NftInfo>,
+        let mut unlock = inbound_transfer([7; 32]);
+        unlock.message_type = MessageType::Unlock;
+        let not_locked = plan_inbound(&state, &local, unlock);
+        assert_eq!(dead_letter_reason(not_locked), DeadLetterReason::NotLocked);
 
-    /// check: this is the metadata account, dont use directly
-    #[account(
-        mut,
-        seeds = [
-            b"metadata",
-            token_metadata_program.key().as_ref(),
-            mint.key().as_ref(),
-        ],
-        bump,
-        seeds::program = token_metadata_program.key()
-    )]
-    pub metadata: UncheckedAccount<'info>,
+        let mut compressed = v1_state();
+        compressed.compressed_mode = true;
+        compressed.placeholder_uri = "https://example.com/unavailable.json".to_string();
+        let mut bad_uri = inbound_transfer([7; 32]);
+        bad_uri.metadata_uri = "ftp://example.com".to_string();
+        let no_placeholder = plan_inbound(&compressed, &fresh, bad_uri);
+        assert_eq!(dead_letter_reason(no_placeholder), DeadLetterReason::InvalidMetadata);
 
-    #[account(mut)]
-    pub payer: Signer<'info>,
+        let paused: Result<InboundPlan> = err!(NftError::SourceChainPaused);
+        assert_eq!(dead_letter_reason(paused), DeadLetterReason::SourceChainPaused);
+        let blocked: Result<InboundPlan> = err!(NftError::RecipientBlocked);
+        assert_eq!(dead_letter_reason(blocked), DeadLetterReason::RecipientBlocked);
+    }
 
-    pub rent: Sysvar<'info, Rent>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_metadata_program: Program<'info, Metadata>,
-}
+    #[test]
+    fn test_plan_inbound_holds_first_arrivals_while_paused_or_capped() {
+        let fresh = stored_nft_info(Pubkey::default(), false);
+        let returning = stored_nft_info(Pubkey::new_unique(), true);
 
-#[derive(Accounts)]
-pub struct TransferToZetachain<'info> {
-    #[account(
-        mut,
-        seeds = [b"nft-program"],
-        bump = nft_program.bump
-    )]
-    pub nft_program: Account<'info, NftProgramState>,
+        let mut paused = v1_state();
+        paused.mint_paused = true;
+        let held = plan_inbound(&paused, &fresh, inbound_transfer([7; 32]));
+        assert_eq!(dead_letter_reason(held), DeadLetterReason::MintPaused);
 
-    #[account(
-        mut,
-        seeds = [b"nft-info", nft_info.mint.as_ref()],
-        bump = nft_info.bump,
-        constraint = nft_info.owner == owner.key()
-    )]
-    pub nft_info: Account<'info, NftInfo>,
+        let mut capped = v1_state();
+        capped.max_supply = capped.total_supply;
+        let held = plan_inbound(&capped, &fresh, inbound_transfer([7; 32]));
+        assert_eq!(dead_letter_reason(held), DeadLetterReason::SupplyCapReached);
 
-    pub owner: Signer<'info>,
-    
-    #[account(
-        mut,
-        associated_token::mint = nft_info.mint,
-        associated_token::authority = owner,
-    )]
-    pub owner_token_account: Account<'info, TokenAccount>,
+        // an nft with nft info here is counted already, it only changes hands
+        assert!(plan_inbound(&paused, &returning, inbound_transfer([7; 32])).is_ok());
+        assert!(plan_inbound(&capped, &returning, inbound_transfer([7; 32])).is_ok());
+    }
 
-    #[account(
-        init_if_needed,
-        payer = owner,
-        associated_token::mint = nft_info.mint,
-        associated_token::authority = nft_program,
-    )]
-    pub program_token_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-}
+    #[test]
+    fn test_plan_inbound_falls_back_to_the_placeholder_uri() {
+        let mut state = v1_state();
+        state.placeholder_uri = "https://example.com/unavailable.json".to_string();
+        let fresh = stored_nft_info(Pubkey::default(), false);
+        let mut junk = inbound_transfer([7; 32]);
+        junk.metadata_uri = "ftp://example.com".to_string();
 
-#[derive(Accounts)]
-pub struct HandleCrossChainCall<'info> {
-    #[account(
-        mut,
-        seeds = [b"nft-program"],
-        bump = nft_program.bump
-    )]
-    pub nft_program: Account<'info, NftProgramState>,
-}
+        let plan = plan_inbound(&state, &fresh, junk.clone()).unwrap();
+        assert_eq!(plan.metadata_uri, state.placeholder_uri);
+        assert!(plan.needs_metadata_repair);
+        let plan = plan_inbound(&state, &fresh, inbound_transfer([7; 32])).unwrap();
+        assert!(!plan.needs_metadata_repair);
 
-#[derive(Accounts)]
-pub struct UnlockNft<'info> {
-    #[account(
-        mut,
-        seeds = [b"nft-program"],
-        bump = nft_program.bump
-    )]
-    pub nft_program: Account<'info, NftProgramState>,
+        // a returning nft keeps its uri, and its flag until it gets repaired
+        let mut returning = stored_nft_info(Pubkey::new_unique(), true);
+        returning.metadata_uri = "https://example.com/old.json".to_string();
+        let plan = plan_inbound(&state, &returning, junk.clone()).unwrap();
+        assert_eq!(plan.metadata_uri, returning.metadata_uri);
+        assert!(!plan.needs_metadata_repair);
+        returning.needs_metadata_repair = true;
+        let plan = plan_inbound(&state, &returning, inbound_transfer([7; 32])).unwrap();
+        assert!(plan.needs_metadata_repair);
 
-    #[account(
-        mut,
-        seeds = [b"nft-info", nft_info.mint.as_ref()],
-        bump = nft_info.bump,
-        constraint = nft_info.owner == owner.key()
-    )]
-    pub nft_info: Account<'info, NftInfo>,
+        // only the uri gets a fallback
+        junk.recipient = [0; 32];
+        let to_nobody = plan_inbound(&state, &fresh, junk);
+        assert_eq!(dead_letter_reason(to_nobody), DeadLetterReason::InvalidRecipient);
+    }
 
-    pub owner: Signer<'info>,
-    
-    #[account(
-        mut,
-        associated_token::mint = nft_info.mint,
-        associated_token::authority = owner,
-    )]
-    pub owner_token_account: Account<'info, TokenAccount>,
+    #[test]
+    fn test_recipient_blocked_only_by_its_own_record() {
+        let recipient = Pubkey::new_unique();
+        let (address, _) = seeds::find_blocked_recipient(&recipient);
+        let mut lamports = 0;
+        let mut data = vec![];
+        let system = anchor_lang::system_program::ID;
+        let empty = AccountInfo::new(&address, false, false, &mut lamports, &mut data, &system, false, 0);
+        assert!(!recipient_blocked(&empty, recipient).unwrap());
+        assert_eq!(
+            recipient_blocked(&empty, Pubkey::new_unique()).unwrap_err(),
+            NftError::InvalidBlockedRecipient.into()
+        );
 
-    #[account(
-        mut,
-        associated_token::mint = nft_info.mint,
-        associated_token::authority = nft_program,
-    )]
-    pub program_token_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-}
+        let mut lamports = 1;
+        let mut data = vec![1; 8 + BlockedRecipient::INIT_SPACE];
+        let record = AccountInfo::new(&address, false, false, &mut lamports, &mut data, &crate::ID, false, 0);
+        assert!(recipient_blocked(&record, recipient).unwrap());
+    }
 
-/// account struct for the on_call function
-/// handles incoming cross-chain calls from the gateway program
-#[derive(Accounts)]
-#[instruction(mint_key: Pubkey)]
-pub struct OnCall<'info> {
-    /// nft program state account
-    #[account(
-        mut,
-        seeds = [b"nft-program"],
-        bump = nft_program.bump
-    )]
-    pub nft_program: Account<'info, NftProgramState>,
+    #[test]
+    fn test_recipient() {
+        let mut state = v1_state();
+        let wallet = Pubkey::new_unique();
+        let program_state = seeds::find_program_state().0;
 
-    /// mint account for the nft, created as a pda if needed
-    #[account(
-        init_if_needed,
-        payer = payer,
-        seeds = [b"nft-mint", mint_key.as_ref()],
-        bump,
-        space = 82, // token mint account size
-    )]
-    pub mint: Account<'info, Mint>,
+        assert_eq!(validate_recipient(&state, wallet).unwrap(), wallet);
+        assert!(validate_recipient(&state, Pubkey::default()).is_err());
+        assert!(validate_recipient(&state, crate::ID).is_err());
+        assert!(validate_recipient(&state, program_state).is_err());
 
-    /// nft info account to track nft metadata and ownership
-    #[account(
-        init_if_needed,
-        payer = payer,
-        space = 8 + NftInfo::INIT_SPACE,
-        seeds = [b"nft-info", mint.key().as_ref()],
-        bump
-    )]
-    pub nft_info: Account<'info, NftInfo>,
+        // the flag opens up the program, never the default pubkey
+        state.allow_program_recipients = true;
+        assert!(validate_recipient(&state, Pubkey::default()).is_err());
+        assert_eq!(validate_recipient(&state, crate::ID).unwrap(), crate::ID);
+        assert_eq!(validate_recipient(&state, program_state).unwrap(), program_state);
+    }
 
-    /// metadata account for the nft (metaplex)
-    /// check: this is the metadata account, dont use directly
-    #[account(
-        mut,
-        seeds = [
-            b"metadata",
-            token_metadata_program.key().as_ref(),
-            mint.key().as_ref(),
-        ],
-        bump,
-        seeds::program = token_metadata_program.key()
-    )]
-    pub metadata: UncheckedAccount<'info>,
+    #[test]
+    fn test_dead_letter_space_fits_the_payload() {
+        let payload = inbound_transfer([7; 32]).try_to_vec().unwrap();
+        let record = DeadLetter {
+            message_hash: canonical_message_hash(&payload),
+            source_chain_id: 1,
+            sender: [1; 20],
+            reason: DeadLetterReason::NotLocked,
+            slot: 9,
+            bump: 255,
+            payload: payload.clone(),
+        };
 
-    /// recipient's token account, created if needed
-    /// check: recipient token account, constraints checked in the handler
-    pub recipient_token_account: AccountInfo<'info>,
+        let mut image = Vec::new();
+        record.try_serialize(&mut image).unwrap();
 
-    /// program's token account for holding locked nfts
-    /// check: program token account, constraints checked in the handler
-    pub program_token_account: AccountInfo<'info>,
+        assert_eq!(image.len(), DeadLetter::space(payload.len()));
+        let stored = DeadLetter::try_deserialize(&mut image.as_slice()).unwrap();
+        assert_eq!(stored.payload, payload);
+        assert_eq!(stored.reason, DeadLetterReason::NotLocked);
+    }
 
-    /// owner's token account for returning unlocked nfts
-    /// check: owner token account, constraints checked in the handler
-    pub owner_token_account: AccountInfo<'info>,
+    #[test]
+    fn test_pending_claim_space_fits_the_payload() {
+        let payload = inbound_transfer([7; 32]).try_to_vec().unwrap();
+        let record = PendingClaim {
+            nonce: 3,
+            recipient: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            message_hash: canonical_message_hash(&payload),
+            source_chain_id: 1,
+            sender: [1; 20],
+            slot: 9,
+            bump: 255,
+            payload: payload.clone(),
+        };
 
-    /// payer for account creation
-    #[account(mut)]
-    pub payer: Signer<'info>,
+        let mut image = Vec::new();
+        record.try_serialize(&mut image).unwrap();
 
-    /// system accounts
-    pub rent: Sysvar<'info, Rent>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_metadata_program: Program<'info, Metadata>,
+        assert_eq!(image.len(), PendingClaim::space(payload.len()));
+        let stored = PendingClaim::try_deserialize(&mut image.as_slice()).unwrap();
+        assert_eq!(stored.payload, payload);
+        assert_eq!(stored.recipient, record.recipient);
+    }
 
-    /// instruction sysvar account for verifying caller is gateway
-    /// check: this is used to verify the caller is the gateway program
-    #[account(address = sysvar::instructions::id())]
-    pub instruction_sysvar_account: UncheckedAccount<'info>,
-}
+    #[test]
+    fn test_seed_helpers_match_the_account_seeds() {
+        // spelled out like the constraints had them, a changed constant has to show up here
+        let find = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &crate::ID);
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let hash = [9u8; 32];
 
-// program state, stores main info for the contract
-#[account]
-#[derive(InitSpace)]
-pub struct NftProgramState {
-    pub authority: Pubkey,
-    pub gateway: Pubkey,
-    pub total_supply: u64,
-    pub nonce: u64, // for replay protection, dont let it repeat
-    pub bump: u8,
-}
+        assert_eq!(seeds::find_program_state(), find(&[b"nft-program"]));
+        assert_eq!(seeds::find_treasury(), find(&[b"treasury"]));
+        assert_eq!(seeds::find_nft_info(&mint), find(&[b"nft-info", mint.as_ref()]));
+        assert_eq!(seeds::find_bridged_mint(&mint), find(&[b"nft-mint", mint.as_ref()]));
+        assert_eq!(seeds::find_pda_mint(3), find(&[b"mint", &3u64.to_le_bytes()]));
+        assert_eq!(seeds::find_owner_stats(&owner), find(&[b"owner-stats", owner.as_ref()]));
+        assert_eq!(seeds::find_minter_role(&owner), find(&[b"minter", owner.as_ref()]));
+        assert_eq!(seeds::find_mint_index(7), find(&[b"index", &7u64.to_le_bytes()]));
+        assert_eq!(seeds::find_chain_info(7001), find(&[b"chain", &7001u64.to_le_bytes()]));
+        assert_eq!(seeds::find_dead_letter(&hash), find(&[b"dead-letter", &hash]));
+        assert_eq!(seeds::find_pending_claim(4), find(&[b"pending-claim", &4u64.to_le_bytes()]));
+        assert_eq!(
+            seeds::find_compliance_action(&mint, &owner),
+            find(&[b"compliance", mint.as_ref(), owner.as_ref()])
+        );
+        assert_eq!(
+            seeds::find_blocked_recipient(&owner),
+            find(&[b"blocked-recipient", owner.as_ref()])
+        );
+        assert_eq!(seeds::find_blocked_mint(&mint), find(&[b"blocked-mint", mint.as_ref()]));
+        assert_eq!(seeds::find_collection(), find(&[b"collection"]));
+        assert_eq!(seeds::find_collection_mint(), find(&[b"collection-mint"]));
+        assert_eq!(seeds::find_bridged_collection(), find(&[b"bridged-collection"]));
+        assert_eq!(seeds::find_bridged_collection_mint(), find(&[b"bridged-collection-mint"]));
+    }
 
-// nft tracking info, stores all the data for each nft
-#[account]
-#[derive(InitSpace)]
-pub struct NftInfo {
-    pub mint: Pubkey,
-    pub owner: Pubkey,
-    #[max_len(200)]
-    pub metadata_uri: String,
-    #[max_len(32)]
-    pub name: String,
-    #[max_len(10)]
-    pub symbol: String,
-    pub is_locked: bool,
-    pub cross_chain_recipient: [u8; 32],
-    pub bump: u8,
-}
+    #[test]
+    fn test_nft_info_view_covers_found_locked_and_missing() {
+        let mint = Pubkey::new_unique();
+        let mut nft_info = stored_nft_info(mint, true);
+        nft_info.metadata_uri = "https://example.com/1.json".to_string();
+        nft_info.name = "Test".to_string();
+        nft_info.cross_chain_recipient = [4; 32];
 
-// crosschain message struct, used for sending nft data between chains
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct CrossChainMessage {
-    pub message_type: MessageType,
-    pub mint: Pubkey,
-    pub recipient: [u8; 32],
-    #[max_len(200)]
-    pub metadata_uri: String,
-    #[max_len(32)]
-    pub name: String,
-    #[max_len(10)]
-    pub symbol: String,
-    pub nonce: u64,
-}
+        let view = nft_info_view(mint, Some(&nft_info), 12);
+        assert_eq!(view.version, NFT_INFO_VIEW_VERSION);
+        assert_eq!(
+            view.status,
+            NftInfoStatus::Found {
+                owner: nft_info.owner,
+                is_locked: false,
+                bridged: true,
+                uri: "https://example.com/1.json".to_string(),
+                uri_truncated: false,
+                name: "Test".to_string(),
+                // the recipient of an earlier crossing doesnt count once unlocked
+                locked_destination: None,
+                nonce: 12,
+            }
+        );
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub enum MessageType {
-    Transfer,
-    Unlock,
-}
+        nft_info.is_locked = true;
+        let view = nft_info_view(mint, Some(&nft_info), 13);
+        match view.status {
+            NftInfoStatus::Found { is_locked, locked_destination, .. } => {
+                assert!(is_locked);
+                assert_eq!(locked_destination, Some([4; 32]));
+            }
+            NftInfoStatus::NotFound => panic!("expected Found"),
+        }
 
-// error types for the program, try to keep them clear
-#[error_code]
-pub enum NftError {
-    #[msg("Unauthorized")]
-    Unauthorized,
-    #[msg("Invalid cross-chain message")]
-    InvalidMessage,
-    #[msg("Token not found")]
-    TokenNotFound,
-    #[msg("Token is locked")]
-    TokenLocked,
-    #[msg("Token is not locked")]
-    TokenNotLocked,
-    #[msg("Invalid nonce")]
-    InvalidNonce,
-    #[msg("Invalid recipient")]
-    InvalidRecipient,
-    #[msg("Invalid metadata")]
-    InvalidMetadata,
-    #[msg("Arithmetic overflow")]
-    Overflow,
-}
\ No newline at end of file
+        let view = nft_info_view(mint, None, 13);
+        assert_eq!(view.mint, mint);
+        assert_eq!(view.status, NftInfoStatus::NotFound);
+    }
+
+    #[test]
+    fn test_nft_info_view_fits_return_data() {
+        let mint = Pubkey::new_unique();
+        let mut nft_info = stored_nft_info(mint, false);
+        nft_info.metadata_uri = "u".repeat(MAX_URI_LEN_CAP as usize);
+        nft_info.name = "n".repeat(32);
+        nft_info.is_locked = true;
+
+        let view = nft_info_view(mint, Some(&nft_info), u64::MAX);
+        assert!(view.try_to_vec().unwrap().len() <= anchor_lang::solana_program::program::MAX_RETURN_DATA);
+        match view.status {
+            NftInfoStatus::Found { uri, uri_truncated, .. } => {
+                assert_eq!(uri.len(), MAX_VIEW_URI_LEN);
+                assert!(uri_truncated);
+            }
+            NftInfoStatus::NotFound => panic!("expected Found"),
+        }
+    }
+
+    #[test]
+    fn test_param_structs_keep_the_positional_wire_format() {
+        let recipient = Pubkey::new_unique();
+        let params = MintParams {
+            name: "Test".to_string(),
+            symbol: "TST".to_string(),
+            uri: "https://example.com/1.json".to_string(),
+            recipient,
+            token_standard: Some(NftStandard::NonFungible),
+            creators: None,
+            metadata_hash: Some([3; 32]),
+            attributes: None,
+            royalty_bps: Some(250),
+        };
+        let positional = (
+            "Test".to_string(),
+            "TST".to_string(),
+            "https://example.com/1.json".to_string(),
+            recipient,
+            Some(NftStandard::NonFungible),
+            None::<Vec<NftCreator>>,
+            Some([3u8; 32]),
+            None::<Vec<Attribute>>,
+            Some(250u16),
+        );
+        assert_eq!(params.try_to_vec().unwrap(), positional.try_to_vec().unwrap());
+
+        let transfer = TransferParams { destination_chain_id: 7001, recipient: [5; 32], nonce: 9 };
+        assert_eq!(
+            transfer.try_to_vec().unwrap(),
+            (7001u64, [5u8; 32], 9u64).try_to_vec().unwrap()
+        );
+        // the ignored nonce keeps what follows the params where older clients put it
+        let compressed = (7001u64, [5u8; 32], 9u64, [6u8; 32]).try_to_vec().unwrap();
+        let mut data = compressed.as_slice();
+        assert_eq!(TransferParams::deserialize(&mut data).unwrap(), transfer);
+        assert_eq!(<[u8; 32]>::deserialize(&mut data).unwrap(), [6; 32]);
+
+        // the gateway builds on_call data itself, the layout cant move
+        let inbound = InboundMessage { amount: 0, sender: [1; 20], data: vec![7, 8] };
+        assert_eq!(
+            inbound.try_to_vec().unwrap(),
+            (0u64, [1u8; 20], vec![7u8, 8]).try_to_vec().unwrap()
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! setup and helpers shared by the program-test suites, each suite is its own
+//! crate and uses only part of them
+#![allow(dead_code)]
+
+use anchor_lang::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::TokenAccount;
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use universal_nft::{
+    ChainConfig, ChainKind, CrossChainMessage, MessageType, NftError, NftInfo, NftProgramState,
+};
+use universal_nft_client::{
+    build_initialize, build_mint_nft, build_on_call, build_register_chain,
+    build_transfer_to_zetachain, pda,
+    InboundMessage, LockKeys, MintNftKeys, MintParams, OnCallKeys, TransferParams,
+};
+
+pub const ZETACHAIN: u64 = 7001;
+// connected contract on zetachain, every inbound call comes from it
+pub const SENDER: [u8; 20] = zeta_echo::UNIVERSAL_APP;
+
+pub const NAME: &str = "test nft";
+pub const SYMBOL: &str = "TEST";
+pub const URI: &str = "https://test.com/metadata.json";
+
+// evm recipients are left aligned in 32 bytes
+pub fn evm_recipient() -> [u8; 32] {
+    let mut recipient = [0; 32];
+    recipient[..20].copy_from_slice(&[2; 20]);
+    recipient
+}
+
+pub struct Env {
+    pub context: ProgramTestContext,
+    pub authority: Keypair,
+}
+
+// initialized with the mock gateway as gateway and zetachain registered
+pub async fn setup() -> Env {
+    let mut program_test = ProgramTest::new("universal_nft", universal_nft::ID, None);
+    program_test.add_program("mock_gateway", mock_gateway::ID, None);
+    program_test.add_program("zeta_echo", zeta_echo::ID, None);
+    program_test.add_program("mpl_token_metadata", mpl_token_metadata::ID, None);
+
+    let mut env = Env {
+        context: program_test.start_with_context().await,
+        authority: Keypair::new(),
+    };
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+
+    env.send(&[build_initialize(payer, authority.pubkey(), mock_gateway::ID, false)], &[])
+        .await
+        .unwrap();
+
+    env.send(
+        &[build_register_chain(authority.pubkey(), payer, ZETACHAIN, &connected_chain(true))],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    env
+}
+
+// an evm chain whose connected contract is SENDER, like zetachain
+pub fn connected_chain(enabled: bool) -> ChainConfig {
+    let mut connected = [0; 32];
+    connected[..20].copy_from_slice(&SENDER);
+    ChainConfig {
+        name: "ZetaChain Athens".to_string(),
+        chain_kind: ChainKind::Evm,
+        enabled,
+        recipient_len: 20,
+        connected,
+    }
+}
+
+impl Env {
+    pub fn payer(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    // the payer signs everything, a fresh blockhash keeps a resent transaction from
+    // being deduplicated before it reaches the program
+    pub async fn transaction(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Transaction {
+        self.transaction_with_limit(instructions, signers, 400_000).await
+    }
+
+    pub async fn transaction_with_limit(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+        compute_unit_limit: u32,
+    ) -> Transaction {
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit)];
+        all.extend_from_slice(instructions);
+        let mut keypairs = vec![&self.context.payer];
+        keypairs.extend_from_slice(signers);
+        Transaction::new_signed_with_payer(&all, Some(&self.payer()), &keypairs, blockhash)
+    }
+
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let transaction = self.transaction(instructions, signers).await;
+        self.context.banks_client.process_transaction(transaction).await
+    }
+
+    // for a transaction that has to go through, returns its logs and return data
+    pub async fn send_for_output(&mut self, instructions: &[Instruction]) -> (Vec<String>, Vec<u8>) {
+        let transaction = self.transaction(instructions, &[]).await;
+        let outcome = self
+            .context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        outcome.result.unwrap();
+        let metadata = outcome.metadata.expect("bank returns metadata");
+        let return_data = metadata.return_data.map(|data| data.data).unwrap_or_default();
+        (metadata.log_messages, return_data)
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, key: Pubkey) -> T {
+        let account = self
+            .context
+            .banks_client
+            .get_account(key)
+            .await
+            .unwrap()
+            .expect("account exists");
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn state(&mut self) -> NftProgramState {
+        self.account(pda::nft_program()).await
+    }
+
+    pub async fn token_amount(&mut self, owner: &Pubkey, mint: &Pubkey) -> u64 {
+        self.account::<TokenAccount>(get_associated_token_address(owner, mint))
+            .await
+            .amount
+    }
+
+    // mints a metaplex nft to the payer
+    pub async fn mint_nft(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let keys = self.mint_keys(mint.pubkey()).await;
+        let params = self.mint_params();
+        self.send(&[build_mint_nft(&keys, &params)], &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    // metaplex mint of the payer, for mint_nft and mint_nft_pda
+    pub async fn mint_keys(&mut self, mint: Pubkey) -> MintNftKeys {
+        MintNftKeys {
+            payer: self.payer(),
+            mint,
+            token_program: anchor_spl::token::ID,
+            index_count: self.state().await.index_count,
+            metaplex: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn mint_params(&self) -> MintParams {
+        MintParams {
+            name: NAME.to_string(),
+            symbol: SYMBOL.to_string(),
+            uri: URI.to_string(),
+            recipient: self.payer(),
+            token_standard: None,
+            creators: None,
+            metadata_hash: None,
+            attributes: None,
+            royalty_bps: None,
+        }
+    }
+
+    pub fn lock_keys(&self, mint: Pubkey) -> LockKeys {
+        LockKeys {
+            owner: self.payer(),
+            mint,
+            token_program: anchor_spl::token::ID,
+            programmable: false,
+            collection: None,
+            fee_token: None,
+        }
+    }
+
+    pub fn transfer_params() -> TransferParams {
+        TransferParams {
+            destination_chain_id: ZETACHAIN,
+            recipient: evm_recipient(),
+            nonce: 0,
+        }
+    }
+
+    pub async fn transfer_to_zetachain(&mut self, mint: Pubkey) -> Result<(), BanksClientError> {
+        let transfer = build_transfer_to_zetachain(&self.lock_keys(mint), &Self::transfer_params());
+        self.send(&[transfer], &[]).await
+    }
+
+    // on_call as the gateway makes it, for the payer. a transfer names the origin
+    // mint on zetachain and its first arrival takes the next index slot, an unlock
+    // names the local mint
+    pub async fn deliver(&mut self, message: &InboundMessage) -> Result<(), BanksClientError> {
+        let execute = self.on_call_instruction(message).await;
+        self.send(&[execute], &[]).await
+    }
+
+    // the mock gateway execute that delivers the message
+    pub async fn on_call_instruction(&mut self, message: &InboundMessage) -> Instruction {
+        let keys = self.on_call_keys(message).await;
+        through_gateway(build_on_call(&keys, message), message)
+    }
+
+    // keys on_call needs for the message, delivered to the payer
+    pub async fn on_call_keys(&mut self, message: &InboundMessage) -> OnCallKeys {
+        let decoded = CrossChainMessage::try_from_slice(&message.data).unwrap();
+        let unlock = decoded.message_type == MessageType::Unlock;
+        let first_arrival = !unlock
+            && self
+                .context
+                .banks_client
+                .get_account(pda::nft_info(&pda::bridged_mint(&decoded.mint)))
+                .await
+                .unwrap()
+                .is_none();
+        OnCallKeys {
+            payer: self.payer(),
+            source_chain_id: ZETACHAIN,
+            origin_mint: decoded.mint,
+            unlock,
+            token_program: anchor_spl::token::ID,
+            nft_owner: self.payer(),
+            metaplex: true,
+            index_count: if first_arrival {
+                Some(self.state().await.index_count)
+            } else {
+                None
+            },
+            ..Default::default()
+        }
+    }
+
+    pub async fn on_call(
+        &mut self,
+        message_type: MessageType,
+        message_mint: Pubkey,
+        nonce: u64,
+    ) -> Result<(), BanksClientError> {
+        let message = inbound_message(message_type, message_mint, self.payer(), nonce);
+        self.deliver(&message).await
+    }
+
+    // locks the nft on its way to zetachain, the echo sends it back as an unlock
+    // with `return_nonce` and the gateway delivers it
+    pub async fn round_trip(&mut self, mint: Pubkey, return_nonce: u64) {
+        self.round_trip_with(self.lock_keys(mint), return_nonce).await
+    }
+
+    // same for keys lock_keys doesnt make, like a registered nft with its collection
+    pub async fn round_trip_with(&mut self, keys: LockKeys, return_nonce: u64) {
+        let transfer = build_transfer_to_zetachain(&keys, &Self::transfer_params());
+        let (logs, _) = self.send_for_output(&[transfer]).await;
+        assert!(self.account::<NftInfo>(pda::nft_info(&keys.mint)).await.is_locked);
+
+        let echo = Instruction {
+            program_id: zeta_echo::ID,
+            accounts: zeta_echo::accounts::Echo {}.to_account_metas(None),
+            data: zeta_echo::instruction::Echo {
+                outbound: outbound_message(&logs),
+                message_type: MessageType::Unlock,
+                recipient: self.payer(),
+                nonce: return_nonce,
+            }
+            .data(),
+        };
+        let (_, return_data) = self.send_for_output(&[echo]).await;
+        let message = InboundMessage {
+            amount: 0,
+            sender: zeta_echo::UNIVERSAL_APP,
+            data: Vec::<u8>::try_from_slice(&return_data).unwrap(),
+        };
+        self.deliver(&message).await.unwrap();
+    }
+}
+
+// transfer_to_zetachain logs the message bytes it would hand the gateway
+pub fn outbound_message(logs: &[String]) -> Vec<u8> {
+    let bytes = logs
+        .iter()
+        .find_map(|log| log.strip_prefix("Program log: Message: "))
+        .expect("outbound message logged");
+    bytes
+        .trim_matches(|c| c == '[' || c == ']')
+        .split(", ")
+        .map(|byte| byte.parse().unwrap())
+        .collect()
+}
+
+pub fn inbound_message(
+    message_type: MessageType,
+    mint: Pubkey,
+    recipient: Pubkey,
+    nonce: u64,
+) -> InboundMessage {
+    let message = CrossChainMessage {
+        message_type,
+        mint,
+        recipient: recipient.to_bytes(),
+        metadata_uri: URI.to_string(),
+        name: NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        nonce,
+        master_mint: Pubkey::default(),
+        edition: 0,
+        metadata_hash: None,
+        royalty_bps: None,
+        royalty_recipient: None,
+        attributes: vec![],
+    };
+    InboundMessage {
+        amount: 0,
+        sender: SENDER,
+        data: message.try_to_vec().unwrap(),
+    }
+}
+
+// mock gateway execute, its accounts followed by on_call's in on_call order
+pub fn through_gateway(on_call: Instruction, message: &InboundMessage) -> Instruction {
+    let mut accounts = mock_gateway::accounts::Execute {
+        target_program: universal_nft::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(on_call.accounts);
+    Instruction {
+        program_id: mock_gateway::ID,
+        accounts,
+        data: mock_gateway::instruction::Execute {
+            amount: message.amount,
+            sender: message.sender,
+            data: message.data.clone(),
+        }
+        .data(),
+    }
+}
+
+// the compute budget instruction goes first, the program call is instruction 1
+pub fn assert_nft_error(result: Result<(), BanksClientError>, error: NftError) {
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(1, InstructionError::Custom(error.into()))
+    );
+}
+
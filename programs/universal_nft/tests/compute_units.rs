@@ -0,0 +1,204 @@
+//! compute unit benchmarks, each instruction runs with the largest inputs it takes and
+//! has to stay under its threshold below. runs with the other program-test suites, the
+//! numbers only show with `--nocapture`:
+//! `cargo test-sbf --manifest-path programs/universal_nft/Cargo.toml --test compute_units -- --nocapture`
+
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::AnchorSerialize;
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use universal_nft::{
+    Attribute, CrossChainMessage, MessageType, MAX_ATTRIBUTES, MAX_ATTRIBUTE_KEY_LEN,
+    MAX_ATTRIBUTE_VALUE_LEN, MAX_METAPLEX_URI_LEN, MAX_NAME_LEN, MAX_SYMBOL_LEN,
+};
+use universal_nft_client::{
+    build_mint_nft, build_transfer_to_zetachain, build_unlock_nft, pda, InboundMessage,
+    MintNftKeys, MintParams,
+};
+
+// ceilings in compute units for the universal nft instruction alone, cpis it makes
+// included. a single instruction gets 200k unless the transaction asks for more, a
+// transaction never more than 1.4M. raising one needs a reason in the pr
+const MINT_NFT: u64 = 200_000; // wallets mint without a compute budget instruction
+const TRANSFER_TO_ZETACHAIN: u64 = 100_000;
+const UNLOCK_NFT: u64 = 80_000;
+const ON_CALL_TRANSFER: u64 = 200_000; // the gateway runs in the same 200k by default
+const ON_CALL_UNLOCK: u64 = 100_000;
+
+// the transactions get the whole 1.4M, so one over its threshold still runs and
+// reports how far over it is
+const METERED_LIMIT: u32 = 1_400_000;
+
+struct Usage {
+    program: u64,     // the universal nft instruction
+    transaction: u64, // everything, compute budget and gateway included
+}
+
+// runs the transaction, it has to go through
+async fn metered(env: &mut Env, instructions: &[Instruction], signers: &[&Keypair]) -> Usage {
+    let transaction = env
+        .transaction_with_limit(instructions, signers, METERED_LIMIT)
+        .await;
+    let outcome = env
+        .context
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    outcome.result.unwrap();
+    let metadata = outcome.metadata.expect("bank returns metadata");
+    Usage {
+        program: program_units(&metadata.log_messages),
+        transaction: metadata.compute_units_consumed,
+    }
+}
+
+// the runtime logs `Program <id> consumed <n> of <m> compute units` when an invocation
+// returns. the outermost universal nft invocation returns last, event self cpis and
+// the gateway call around it dont change that
+fn program_units(logs: &[String]) -> u64 {
+    let prefix = format!("Program {} consumed ", universal_nft::ID);
+    logs.iter()
+        .rev()
+        .find_map(|log| log.strip_prefix(&prefix))
+        .and_then(|rest| rest.split(' ').next())
+        .expect("universal nft invocation logged")
+        .parse()
+        .unwrap()
+}
+
+fn check(name: &str, usage: Usage, threshold: u64) {
+    println!(
+        "compute units {name}: {} (transaction {}), threshold {threshold}",
+        usage.program, usage.transaction
+    );
+    assert!(
+        usage.program <= threshold,
+        "{name} used {} compute units, threshold is {threshold}",
+        usage.program
+    );
+}
+
+fn max_uri() -> String {
+    let uri = "https://test.com/";
+    format!("{uri}{}", "u".repeat(MAX_METAPLEX_URI_LEN - uri.len()))
+}
+
+fn max_attributes() -> Vec<Attribute> {
+    (0..MAX_ATTRIBUTES)
+        .map(|i| Attribute {
+            key: format!("{i}").repeat(MAX_ATTRIBUTE_KEY_LEN),
+            value: "v".repeat(MAX_ATTRIBUTE_VALUE_LEN),
+        })
+        .collect()
+}
+
+fn max_params(recipient: Pubkey) -> MintParams {
+    MintParams {
+        name: "n".repeat(MAX_NAME_LEN),
+        symbol: "S".repeat(MAX_SYMBOL_LEN),
+        uri: max_uri(),
+        recipient,
+        token_standard: None,
+        creators: None,
+        metadata_hash: Some([7; 32]),
+        attributes: Some(max_attributes()),
+        royalty_bps: Some(500),
+    }
+}
+
+// a new mint, so the token account and the metaplex metadata get created
+async fn mint_max_nft(env: &mut Env) -> (Pubkey, Usage) {
+    let mint = Keypair::new();
+    let keys = MintNftKeys {
+        payer: env.payer(),
+        mint: mint.pubkey(),
+        token_program: anchor_spl::token::ID,
+        index_count: env.state().await.index_count,
+        metaplex: true,
+        ..Default::default()
+    };
+    let mint_nft = build_mint_nft(&keys, &max_params(env.payer()));
+    let usage = metered(env, &[mint_nft], &[&mint]).await;
+    (mint.pubkey(), usage)
+}
+
+// as a transfer this is the first arrival of the nft, the bridged mint, token account,
+// metadata and index slot all get created
+fn max_inbound(
+    message_type: MessageType,
+    mint: Pubkey,
+    recipient: Pubkey,
+    nonce: u64,
+) -> InboundMessage {
+    let message = CrossChainMessage {
+        message_type,
+        mint,
+        recipient: recipient.to_bytes(),
+        metadata_uri: max_uri(),
+        name: "n".repeat(MAX_NAME_LEN),
+        symbol: "S".repeat(MAX_SYMBOL_LEN),
+        nonce,
+        master_mint: Pubkey::default(),
+        edition: 0,
+        metadata_hash: Some([7; 32]),
+        royalty_bps: Some(500),
+        royalty_recipient: Some(Pubkey::new_unique()),
+        attributes: max_attributes(),
+    };
+    InboundMessage {
+        amount: 0,
+        sender: SENDER,
+        data: message.try_to_vec().unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn test_mint_nft_compute_units() {
+    let mut env = setup().await;
+
+    let (_, usage) = mint_max_nft(&mut env).await;
+
+    check("mint_nft", usage, MINT_NFT);
+}
+
+#[tokio::test]
+async fn test_transfer_to_zetachain_and_unlock_nft_compute_units() {
+    let mut env = setup().await;
+    let (mint, _) = mint_max_nft(&mut env).await;
+
+    let transfer = build_transfer_to_zetachain(&env.lock_keys(mint), &Env::transfer_params());
+    let usage = metered(&mut env, &[transfer], &[]).await;
+    check("transfer_to_zetachain", usage, TRANSFER_TO_ZETACHAIN);
+
+    let authority = env.authority.insecure_clone();
+    let unlock = build_unlock_nft(&env.lock_keys(mint), authority.pubkey());
+    let usage = metered(&mut env, &[unlock], &[&authority]).await;
+    check("unlock_nft", usage, UNLOCK_NFT);
+}
+
+#[tokio::test]
+async fn test_on_call_compute_units() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+
+    let message = max_inbound(MessageType::Transfer, origin_mint, payer, 1);
+    let on_call = env.on_call_instruction(&message).await;
+    let usage = metered(&mut env, &[on_call], &[]).await;
+    check("on_call transfer", usage, ON_CALL_TRANSFER);
+
+    env.transfer_to_zetachain(mint).await.unwrap();
+    let message = max_inbound(MessageType::Unlock, mint, payer, 2);
+    let on_call = env.on_call_instruction(&message).await;
+    let usage = metered(&mut env, &[on_call], &[]).await;
+    check("on_call unlock", usage, ON_CALL_UNLOCK);
+}
@@ -0,0 +1,2387 @@
+//! end to end tests against the built program in a solana-program-test bank.
+//!
+//! run with `anchor build` then `cargo test-sbf --manifest-path programs/universal_nft/Cargo.toml`
+//! (`anchor run test-sbf` does both), the program, the mock gateway and the zetachain
+//! echo are loaded from target/deploy. token metadata isnt built here, dump it into tests/fixtures once:
+//! `solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s programs/universal_nft/tests/fixtures/mpl_token_metadata.so`.
+//! a plain cargo test leaves it out, there is nothing built to load then
+
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AnchorDeserialize, AnchorSerialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account;
+use anchor_spl::token::{spl_token, Mint};
+use common::*;
+use mpl_token_metadata::accounts::{MasterEdition, Metadata};
+use mpl_token_metadata::instructions::{
+    CreateMasterEditionV3Builder, CreateMetadataAccountV3Builder, VerifyCollectionV1Builder,
+};
+use mpl_token_metadata::types::{Collection, DataV2};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::TransactionError,
+};
+use universal_nft::{
+    canonical_message_hash, wire, BlockedMint, CrossChainMessage, DeadLetter, DeadLetterReason,
+    DeadLetterRetry, FailedInbound, InboundBatch, MessageType, NftError, NftInfo, PendingClaim, PendingNonce,
+    SkippedMessage,
+    UriRewrite, NONCE_WINDOW, STATE_VERSION,
+};
+use universal_nft_client::{
+    admin_message, build_admin_signature, build_approve_collection, build_block_mint,
+    build_block_recipient, build_claim_inbound, build_claim_nft, build_deregister_nft,
+    build_drain_pending, build_execute_signed_admin, build_mint_nft, build_mint_nft_pda,
+    build_on_call, build_register_chain, build_register_existing_nft, build_retry_dead_letter,
+    build_retry_inbound, build_revoke_collection, build_set_allow_program_recipients,
+    build_set_chain_inbound, build_set_chain_inbound_paused, build_set_claim_mode,
+    build_set_dead_letter_retry, build_set_inbound_metadata_mutable, build_set_lazy_metadata,
+    build_set_max_supply, build_set_ordered_delivery, build_set_placeholder_uri,
+    build_set_require_tss_signature, build_set_tss_address, build_set_uri_rewrite,
+    build_skip_message, build_transfer_claim_to_zetachain, build_transfer_to_zetachain,
+    build_tss_signature, build_unblock_mint, build_unblock_recipient, build_unlock_nft,
+    build_update_chain, pda, tss_message, AdminAction, InboundMessage, LockKeys, MintParams,
+    OnCallKeys,
+};
+
+// rewrites the mint account in the bank, for mints the program would never make
+async fn patch_mint(env: &mut Env, mint: Pubkey, patch: impl FnOnce(&mut spl_token::state::Mint)) {
+    let mut account = env
+        .context
+        .banks_client
+        .get_account(mint)
+        .await
+        .unwrap()
+        .expect("mint exists");
+    let mut state = spl_token::state::Mint::unpack(&account.data).unwrap();
+    patch(&mut state);
+    spl_token::state::Mint::pack(state, &mut account.data).unwrap();
+    env.context.set_account(&mint, &account.into());
+}
+
+// same for a token account
+async fn patch_token_account(
+    env: &mut Env,
+    token_account: Pubkey,
+    patch: impl FnOnce(&mut spl_token::state::Account),
+) {
+    let mut account = env
+        .context
+        .banks_client
+        .get_account(token_account)
+        .await
+        .unwrap()
+        .expect("token account exists");
+    let mut state = spl_token::state::Account::unpack(&account.data).unwrap();
+    patch(&mut state);
+    spl_token::state::Account::pack(state, &mut account.data).unwrap();
+    env.context.set_account(&token_account, &account.into());
+}
+
+// writes a token account the program never made, e.g. at an ata that doesnt exist yet
+fn set_token_account(env: &mut Env, address: Pubkey, state: spl_token::state::Account) {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(state, &mut data).unwrap();
+    let account = Account {
+        lamports: 10_000_000,
+        data,
+        owner: spl_token::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    env.context.set_account(&address, &account.into());
+}
+
+// what freeze_token_account and thaw_token_account do to the account
+async fn set_frozen(env: &mut Env, token_account: Pubkey, frozen: bool) {
+    let state = match frozen {
+        true => spl_token::state::AccountState::Frozen,
+        false => spl_token::state::AccountState::Initialized,
+    };
+    patch_token_account(env, token_account, |account| account.state = state).await;
+}
+
+fn repair_metadata(signer: Pubkey, mint: Pubkey, uri: &str, metadata_hash: [u8; 32]) -> Instruction {
+    Instruction {
+        program_id: universal_nft::ID,
+        accounts: universal_nft::accounts::RepairMetadata {
+            nft_program: pda::nft_program(),
+            nft_info: pda::nft_info(&mint),
+            mint,
+            metadata: Some(pda::metadata(&mint)),
+            signer,
+            sysvar_instructions: Some(solana_sdk::sysvar::instructions::ID),
+            token_metadata_program: Some(mpl_token_metadata::ID),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: universal_nft::instruction::RepairMetadata {
+            mint,
+            uri: uri.to_string(),
+            metadata_hash,
+        }
+        .data(),
+    }
+}
+
+fn freeze_metadata(signer: Pubkey, mint: Pubkey) -> Instruction {
+    Instruction {
+        program_id: universal_nft::ID,
+        accounts: universal_nft::accounts::FreezeMetadata {
+            nft_program: pda::nft_program(),
+            nft_info: pda::nft_info(&mint),
+            mint,
+            metadata: pda::metadata(&mint),
+            signer,
+            sysvar_instructions: solana_sdk::sysvar::instructions::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: universal_nft::instruction::FreezeMetadata { mint }.data(),
+    }
+}
+
+fn count_immutable_metadata(payer: Pubkey, mint: Pubkey) -> Instruction {
+    Instruction {
+        program_id: universal_nft::ID,
+        accounts: universal_nft::accounts::CountImmutableMetadata {
+            nft_program: pda::nft_program(),
+            nft_info: pda::nft_info(&mint),
+            metadata: pda::metadata(&mint),
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: universal_nft::instruction::CountImmutableMetadata { mint }.data(),
+    }
+}
+
+async fn metadata(env: &mut Env, mint: Pubkey) -> Metadata {
+    let account = env
+        .context
+        .banks_client
+        .get_account(pda::metadata(&mint))
+        .await
+        .unwrap()
+        .expect("metadata exists");
+    Metadata::safe_deserialize(&account.data).unwrap()
+}
+
+async fn master_edition(env: &mut Env, mint: Pubkey) -> MasterEdition {
+    let account = env
+        .context
+        .banks_client
+        .get_account(pda::master_edition(&mint))
+        .await
+        .unwrap()
+        .expect("master edition exists");
+    MasterEdition::from_bytes(&account.data).unwrap()
+}
+
+// a metaplex nft minted to the payer outside the program: the payer's own mint,
+// metadata and a master edition that takes the mint authority over. without
+// metadata its just an spl mint with one token
+async fn external_nft(env: &mut Env, with_metadata: bool, collection: Option<Pubkey>) -> Pubkey {
+    let payer = env.payer();
+    let mint = Keypair::new();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let mut instructions = vec![
+        system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), &payer, Some(&payer), 0)
+            .unwrap(),
+        create_associated_token_account(&payer, &payer, &mint.pubkey(), &spl_token::ID),
+        spl_token::instruction::mint_to(
+            &spl_token::ID,
+            &mint.pubkey(),
+            &get_associated_token_address(&payer, &mint.pubkey()),
+            &payer,
+            &[],
+            1,
+        )
+        .unwrap(),
+    ];
+    if with_metadata {
+        instructions.push(
+            CreateMetadataAccountV3Builder::new()
+                .metadata(pda::metadata(&mint.pubkey()))
+                .mint(mint.pubkey())
+                .mint_authority(payer)
+                .payer(payer)
+                .update_authority(payer, true)
+                .data(DataV2 {
+                    name: "external nft".to_string(),
+                    symbol: "EXT".to_string(),
+                    uri: "https://external.com/1.json".to_string(),
+                    seller_fee_basis_points: 250,
+                    creators: None,
+                    collection: collection.map(|key| Collection { verified: false, key }),
+                    uses: None,
+                })
+                .is_mutable(true)
+                .instruction(),
+        );
+        instructions.push(
+            CreateMasterEditionV3Builder::new()
+                .edition(pda::master_edition(&mint.pubkey()))
+                .mint(mint.pubkey())
+                .update_authority(payer)
+                .mint_authority(payer)
+                .payer(payer)
+                .metadata(pda::metadata(&mint.pubkey()))
+                .max_supply(0)
+                .instruction(),
+        );
+    }
+    env.send(&instructions, &[&mint]).await.unwrap();
+    mint.pubkey()
+}
+
+// the payer is the update authority of every external nft, so it verifies them
+fn verify_collection(payer: Pubkey, mint: Pubkey, collection_mint: Pubkey) -> Instruction {
+    VerifyCollectionV1Builder::new()
+        .authority(payer)
+        .metadata(pda::metadata(&mint))
+        .collection_mint(collection_mint)
+        .collection_metadata(Some(pda::metadata(&collection_mint)))
+        .collection_master_edition(Some(pda::master_edition(&collection_mint)))
+        .instruction()
+}
+
+// an external nft verified into a collection the authority approved
+async fn approved_external_nft(env: &mut Env) -> LockKeys {
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    let collection = external_nft(env, true, None).await;
+    env.send(&[build_approve_collection(authority.pubkey(), payer, collection)], &[&authority])
+        .await
+        .unwrap();
+    let mint = external_nft(env, true, Some(collection)).await;
+    env.send(&[verify_collection(payer, mint, collection)], &[]).await.unwrap();
+    LockKeys { collection: Some(collection), ..env.lock_keys(mint) }
+}
+
+// metaplex pads the strings with nuls
+async fn metadata_uri(env: &mut Env, mint: Pubkey) -> String {
+    metadata(env, mint).await.uri.trim_end_matches('\0').to_string()
+}
+
+// materialize_metadata of a bridged nft outside any collection
+fn materialize_metadata(payer: Pubkey, mint: Pubkey) -> Instruction {
+    Instruction {
+        program_id: universal_nft::ID,
+        accounts: universal_nft::accounts::MaterializeMetadata {
+            nft_program: pda::nft_program(),
+            nft_info: pda::nft_info(&mint),
+            mint,
+            metadata: pda::metadata(&mint),
+            master_edition: pda::master_edition(&mint),
+            collection: None,
+            collection_mint: None,
+            collection_metadata: None,
+            collection_master_edition: None,
+            bridged_collection: None,
+            bridged_collection_mint: None,
+            payer,
+            sysvar_instructions: solana_sdk::sysvar::instructions::ID,
+            rent: solana_sdk::sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+        }
+        .to_account_metas(None),
+        data: universal_nft::instruction::MaterializeMetadata { mint }.data(),
+    }
+}
+
+// burn_nft of a bridged nft outside any collection, it has an index slot
+fn burn_nft(owner: Pubkey, mint: Pubkey, index: u64) -> Instruction {
+    Instruction {
+        program_id: universal_nft::ID,
+        accounts: universal_nft::accounts::BurnNft {
+            nft_program: pda::nft_program(),
+            nft_info: pda::nft_info(&mint),
+            owner,
+            mint,
+            owner_token_account: get_associated_token_address(&owner, &mint),
+            metadata: None,
+            master_edition: None,
+            owner_token_record: None,
+            sysvar_instructions: None,
+            collection: None,
+            collection_mint: None,
+            collection_metadata: None,
+            collection_master_edition: None,
+            bridged_collection: None,
+            bridged_collection_mint: None,
+            owner_stats: None,
+            mint_index: Some(pda::mint_index(index)),
+            token_metadata_program: None,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+        }
+        .to_account_metas(None),
+        data: universal_nft::instruction::BurnNft { mint }.data(),
+    }
+}
+
+#[tokio::test]
+async fn test_initialize() {
+    let mut env = setup().await;
+
+    let state = env.state().await;
+    assert_eq!(state.version, STATE_VERSION);
+    assert_eq!(state.authority, env.authority.pubkey());
+    assert_eq!(state.gateway, mock_gateway::ID);
+    assert_eq!(state.nonce, 0);
+    assert_eq!(state.total_supply, 0);
+}
+
+#[tokio::test]
+async fn test_mint_nft_creates_metadata() {
+    let mut env = setup().await;
+    let payer = env.payer();
+
+    let mint = env.mint_nft().await;
+
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert_eq!(nft_info.owner, payer);
+    assert_eq!(nft_info.name, NAME);
+    assert!(!nft_info.is_locked);
+    assert!(!nft_info.bridged);
+
+    let account = env
+        .context
+        .banks_client
+        .get_account(pda::metadata(&mint))
+        .await
+        .unwrap()
+        .expect("metadata exists");
+    let metadata = Metadata::safe_deserialize(&account.data).unwrap();
+    assert_eq!(metadata.mint, mint);
+    // metaplex pads the strings with nuls
+    assert_eq!(metadata.name.trim_end_matches('\0'), NAME);
+    assert_eq!(metadata.symbol.trim_end_matches('\0'), SYMBOL);
+    assert_eq!(metadata.uri.trim_end_matches('\0'), URI);
+    assert_eq!(env.state().await.total_supply, 1);
+}
+
+#[tokio::test]
+async fn test_mint_nft_with_keypair() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let mint = Keypair::new();
+    let keys = env.mint_keys(mint.pubkey()).await;
+    let params = env.mint_params();
+    // same accounts as mint_nft, only the instruction name differs
+    let mut mint_nft = build_mint_nft(&keys, &params);
+    mint_nft.data = universal_nft::instruction::MintNftWithKeypair { params }.data();
+
+    env.send(&[mint_nft], &[&mint]).await.unwrap();
+
+    assert_eq!(env.token_amount(&payer, &mint.pubkey()).await, 1);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint.pubkey())).await;
+    assert_eq!(nft_info.owner, payer);
+}
+
+#[tokio::test]
+async fn test_mint_nft_pda_mints_the_derived_mint() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    env.mint_nft().await;
+    let mint = pda::pda_mint(env.state().await.mint_count);
+    let keys = env.mint_keys(mint).await;
+    let params = env.mint_params();
+
+    env.send(&[build_mint_nft_pda(&keys, &params)], &[]).await.unwrap();
+
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert_eq!(nft_info.owner, payer);
+    assert_eq!(env.state().await.total_supply, 2);
+}
+
+#[tokio::test]
+async fn test_mint_nft_to_the_program_is_rejected_unless_allowed() {
+    let mut env = setup().await;
+    let mint = Keypair::new();
+    let keys = env.mint_keys(mint.pubkey()).await;
+    let params = env.mint_params();
+    let mint_to = |recipient| build_mint_nft(&keys, &MintParams { recipient, ..params.clone() });
+
+    // nobody could ever move an nft minted to these
+    for recipient in [Pubkey::default(), universal_nft::ID, pda::nft_program()] {
+        let result = env.send(&[mint_to(recipient)], &[&mint]).await;
+        assert_nft_error(result, NftError::InvalidRecipient);
+    }
+
+    let authority = env.authority.insecure_clone();
+    let allow = build_set_allow_program_recipients(authority.pubkey(), true);
+    env.send(&[allow], &[&authority]).await.unwrap();
+
+    // the flag never opens up the default pubkey
+    let result = env.send(&[mint_to(Pubkey::default())], &[&mint]).await;
+    assert_nft_error(result, NftError::InvalidRecipient);
+    env.send(&[mint_to(pda::nft_program())], &[&mint]).await.unwrap();
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint.pubkey()).await, 1);
+    assert_eq!(env.state().await.total_supply, 1);
+}
+
+#[tokio::test]
+async fn test_mint_nft_with_the_wrong_kind_of_mint_is_rejected() {
+    let mut env = setup().await;
+    let params = env.mint_params();
+
+    // the pda mint cant sign, mint_nft_pda is the one for it
+    let pda_mint = pda::pda_mint(env.state().await.mint_count);
+    let keys = env.mint_keys(pda_mint).await;
+    let mut mint_nft = build_mint_nft(&keys, &params);
+    mint_nft.accounts[1].is_signer = false;
+    let result = env.send(&[mint_nft], &[]).await;
+    assert_nft_error(result, NftError::MintNotSigner);
+
+    // a keypair mint is refused by mint_nft_pda even when it signs
+    let mint = Keypair::new();
+    let keys = env.mint_keys(mint.pubkey()).await;
+    let mut mint_nft_pda = build_mint_nft_pda(&keys, &params);
+    mint_nft_pda.accounts[1].is_signer = true;
+    let result = env.send(&[mint_nft_pda], &[&mint]).await;
+    assert_nft_error(result, NftError::MintNotProgramDerived);
+
+    assert_eq!(env.state().await.total_supply, 0);
+}
+
+#[tokio::test]
+async fn test_transfer_to_zetachain_locks_the_nft() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let mint = env.mint_nft().await;
+
+    env.transfer_to_zetachain(mint).await.unwrap();
+
+    assert_eq!(env.token_amount(&payer, &mint).await, 0);
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 1);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert!(nft_info.is_locked);
+    assert_eq!(nft_info.cross_chain_recipient, evm_recipient());
+    let state = env.state().await;
+    assert_eq!(state.outbound_nonce, 1);
+    assert_eq!(state.nonce, 0);
+    assert_eq!(state.locked_count, 1);
+
+    // a locked nft cant go out twice
+    assert_nft_error(env.transfer_to_zetachain(mint).await, NftError::TokenLocked);
+}
+
+#[tokio::test]
+async fn test_transfer_to_zetachain_with_a_failing_cpi_leaves_no_state() {
+    let mut env = setup().await;
+    let mint = env.mint_nft().await;
+    // the lock is written before the token move, the token program then fails it
+    // on a program ata that cant take one more token
+    let escrow = get_associated_token_address(&pda::nft_program(), &mint);
+    let full = spl_token::state::Account {
+        mint,
+        owner: pda::nft_program(),
+        amount: u64::MAX,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    set_token_account(&mut env, escrow, full);
+
+    let result = env.transfer_to_zetachain(mint).await;
+
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(spl_token::error::TokenError::Overflow as u32)
+        )
+    );
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert!(!nft_info.is_locked);
+    assert_eq!(nft_info.cross_chain_recipient, [0; 32]);
+    let state = env.state().await;
+    assert_eq!(state.outbound_nonce, 0);
+    assert_eq!(state.locked_count, 0);
+
+    // nothing left behind, with the escrow emptied it goes out with the same nonce
+    patch_token_account(&mut env, escrow, |account| account.amount = 0).await;
+    env.transfer_to_zetachain(mint).await.unwrap();
+    assert!(env.account::<NftInfo>(pda::nft_info(&mint)).await.is_locked);
+    let state = env.state().await;
+    assert_eq!(state.outbound_nonce, 1);
+    assert_eq!(state.locked_count, 1);
+}
+
+#[tokio::test]
+async fn test_frozen_accounts_fail_transfer_and_burn_with_token_frozen() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+    let index = env.account::<NftInfo>(pda::nft_info(&mint)).await.index.unwrap();
+    let token_account = get_associated_token_address(&payer, &mint);
+    let escrow = get_associated_token_address(&pda::nft_program(), &mint);
+
+    set_frozen(&mut env, token_account, true).await;
+    assert_nft_error(env.transfer_to_zetachain(mint).await, NftError::TokenFrozen);
+    let result = env.send(&[burn_nft(payer, mint, index)], &[]).await;
+    assert_nft_error(result, NftError::TokenFrozen);
+
+    // the program ata can be under a hold too
+    set_frozen(&mut env, token_account, false).await;
+    let frozen = spl_token::state::Account {
+        mint,
+        owner: pda::nft_program(),
+        state: spl_token::state::AccountState::Frozen,
+        ..Default::default()
+    };
+    set_token_account(&mut env, escrow, frozen);
+    assert_nft_error(env.transfer_to_zetachain(mint).await, NftError::TokenFrozen);
+
+    set_frozen(&mut env, escrow, false).await;
+    env.transfer_to_zetachain(mint).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unlock_nft_with_a_frozen_account_fails_with_token_frozen() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let mint = env.mint_nft().await;
+    env.transfer_to_zetachain(mint).await.unwrap();
+    let token_account = get_associated_token_address(&payer, &mint);
+    let escrow = get_associated_token_address(&pda::nft_program(), &mint);
+    let authority = env.authority.insecure_clone();
+    let unlock = build_unlock_nft(&env.lock_keys(mint), authority.pubkey());
+
+    set_frozen(&mut env, escrow, true).await;
+    let result = env.send(&[unlock.clone()], &[&authority]).await;
+    assert_nft_error(result, NftError::TokenFrozen);
+
+    set_frozen(&mut env, escrow, false).await;
+    set_frozen(&mut env, token_account, true).await;
+    let result = env.send(&[unlock.clone()], &[&authority]).await;
+    assert_nft_error(result, NftError::TokenFrozen);
+
+    set_frozen(&mut env, token_account, false).await;
+    env.send(&[unlock], &[&authority]).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+}
+
+#[tokio::test]
+async fn test_on_call_with_a_frozen_account_fails_with_token_frozen() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    // no master edition in lazy mode, so the nft can arrive again after its burn
+    let authority = env.authority.insecure_clone();
+    env.send(&[build_set_lazy_metadata(authority.pubkey(), true)], &[&authority])
+        .await
+        .unwrap();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    let token_account = get_associated_token_address(&payer, &mint);
+    let escrow = get_associated_token_address(&pda::nft_program(), &mint);
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+    env.transfer_to_zetachain(mint).await.unwrap();
+
+    // unlock out of a frozen escrow, then into a frozen owner ata
+    set_frozen(&mut env, escrow, true).await;
+    assert_nft_error(env.on_call(MessageType::Unlock, mint, 3).await, NftError::TokenFrozen);
+    set_frozen(&mut env, escrow, false).await;
+    set_frozen(&mut env, token_account, true).await;
+    assert_nft_error(env.on_call(MessageType::Unlock, mint, 3).await, NftError::TokenFrozen);
+    set_frozen(&mut env, token_account, false).await;
+    env.on_call(MessageType::Unlock, mint, 3).await.unwrap();
+
+    // burned and arriving again, the recipient ata got recreated under a hold
+    let index = env.account::<NftInfo>(pda::nft_info(&mint)).await.index.unwrap();
+    env.send(&[burn_nft(payer, mint, index)], &[]).await.unwrap();
+    let frozen = spl_token::state::Account {
+        mint,
+        owner: payer,
+        state: spl_token::state::AccountState::Frozen,
+        ..Default::default()
+    };
+    set_token_account(&mut env, token_account, frozen);
+    let result = env.on_call(MessageType::Transfer, origin_mint, 4).await;
+    assert_nft_error(result, NftError::TokenFrozen);
+
+    set_frozen(&mut env, token_account, false).await;
+    env.on_call(MessageType::Transfer, origin_mint, 4).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+}
+
+#[tokio::test]
+async fn test_transfer_to_zetachain_needs_the_token_held() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let mint = env.mint_nft().await;
+    let token_account = get_associated_token_address(&payer, &mint);
+
+    patch_token_account(&mut env, token_account, |account| account.amount = 0).await;
+    assert_nft_error(env.transfer_to_zetachain(mint).await, NftError::TokenNotHeld);
+
+    // the right address, but the account inside is for another mint
+    patch_token_account(&mut env, token_account, |account| {
+        account.amount = 1;
+        account.mint = Pubkey::new_unique();
+    })
+    .await;
+    assert_nft_error(
+        env.transfer_to_zetachain(mint).await,
+        NftError::TokenAccountMintMismatch,
+    );
+
+    assert!(!env.account::<NftInfo>(pda::nft_info(&mint)).await.is_locked);
+    assert_eq!(env.state().await.locked_count, 0);
+}
+
+#[tokio::test]
+async fn test_transfer_to_zetachain_checks_the_mint() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let mint = env.mint_nft().await;
+    let original = env.account::<Mint>(mint).await;
+
+    patch_mint(&mut env, mint, |state| state.decimals = 6).await;
+    assert_nft_error(env.transfer_to_zetachain(mint).await, NftError::MintDecimalsNotZero);
+
+    patch_mint(&mut env, mint, |state| {
+        state.decimals = 0;
+        state.supply = 2;
+    })
+    .await;
+    assert_nft_error(env.transfer_to_zetachain(mint).await, NftError::MintSupplyNotOne);
+
+    patch_mint(&mut env, mint, |state| {
+        state.supply = 1;
+        state.mint_authority = Some(payer).into();
+    })
+    .await;
+    assert_nft_error(env.transfer_to_zetachain(mint).await, NftError::InvalidMintAuthority);
+
+    // the real one goes out
+    patch_mint(&mut env, mint, |state| state.mint_authority = original.mint_authority).await;
+    env.transfer_to_zetachain(mint).await.unwrap();
+    assert!(env.account::<NftInfo>(pda::nft_info(&mint)).await.is_locked);
+}
+
+#[tokio::test]
+async fn test_unlock_nft_returns_the_nft() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let mint = env.mint_nft().await;
+    env.transfer_to_zetachain(mint).await.unwrap();
+    // the lock keeps the outbound nonce, NftUnlocked gives it back
+    let outbound_nonce = env.state().await.outbound_nonce;
+    assert_eq!(env.account::<NftInfo>(pda::nft_info(&mint)).await.lock_nonce, outbound_nonce);
+
+    let authority = env.authority.insecure_clone();
+    let unlock = build_unlock_nft(&env.lock_keys(mint), authority.pubkey());
+    env.send(&[unlock], &[&authority]).await.unwrap();
+
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 0);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert!(!nft_info.is_locked);
+    assert_eq!(env.state().await.locked_count, 0);
+}
+
+#[tokio::test]
+async fn test_owner_cant_unlock_a_confirmed_transfer() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let mint = env.mint_nft().await;
+    env.transfer_to_zetachain(mint).await.unwrap();
+
+    // the copy can exist on the other chain by now, the owner cant take it out of escrow
+    let unlock = build_unlock_nft(&env.lock_keys(mint), payer);
+    assert_nft_error(env.send(&[unlock], &[]).await, NftError::Unauthorized);
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 1);
+    assert!(env.account::<NftInfo>(pda::nft_info(&mint)).await.is_locked);
+
+    // it comes home with the unlock message for it
+    env.on_call(MessageType::Unlock, mint, 1).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert!(!env.account::<NftInfo>(pda::nft_info(&mint)).await.is_locked);
+    assert_eq!(env.state().await.locked_count, 0);
+}
+
+#[tokio::test]
+async fn test_registered_external_nft_bridges_out_and_back() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let keys = approved_external_nft(&mut env).await;
+    let mint = keys.mint;
+
+    // without nft info the program doesnt know it
+    let transfer = build_transfer_to_zetachain(&keys, &Env::transfer_params());
+    assert!(env.send(&[transfer], &[]).await.is_err());
+
+    env.send(&[build_register_existing_nft(&keys)], &[]).await.unwrap();
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert!(nft_info.external);
+    assert!(!nft_info.bridged);
+    assert_eq!(nft_info.owner, payer);
+    assert_eq!(nft_info.name, "external nft");
+    assert_eq!(nft_info.symbol, "EXT");
+    assert_eq!(nft_info.metadata_uri, "https://external.com/1.json");
+    assert_eq!(nft_info.royalty_bps, 250);
+    assert_eq!(nft_info.index, None);
+    assert_eq!(env.state().await.total_supply, 0);
+
+    // out to zetachain and back through the gateway
+    env.round_trip_with(keys.clone(), 2).await;
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 0);
+
+    // and back with a force unlock
+    let transfer = build_transfer_to_zetachain(&keys, &Env::transfer_params());
+    env.send(&[transfer], &[]).await.unwrap();
+    let authority = env.authority.insecure_clone();
+    env.send(&[build_unlock_nft(&keys, authority.pubkey())], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert!(!env.account::<NftInfo>(pda::nft_info(&mint)).await.is_locked);
+    let state = env.state().await;
+    assert_eq!(state.locked_count, 0);
+    assert_eq!(state.total_supply, 0);
+
+    // the program never took the mint over
+    let mint_account: Mint = env.account(mint).await;
+    assert_eq!(mint_account.mint_authority.unwrap(), pda::master_edition(&mint));
+}
+
+#[tokio::test]
+async fn test_register_existing_nft_takes_only_held_nfts_minted_elsewhere() {
+    let mut env = setup().await;
+    let payer = env.payer();
+
+    // one of ours is minted by the program
+    let ours = env.mint_nft().await;
+    let result = env.send(&[build_register_existing_nft(&env.lock_keys(ours))], &[]).await;
+    assert_nft_error(result, NftError::InvalidMintAuthority);
+
+    let bare = external_nft(&mut env, false, None).await;
+    let result = env.send(&[build_register_existing_nft(&env.lock_keys(bare))], &[]).await;
+    assert_nft_error(result, NftError::MissingMetaplexAccount);
+
+    // after a plain spl transfer the buyer registers again and takes the nft info over
+    let keys = approved_external_nft(&mut env).await;
+    let mint = keys.mint;
+    env.send(&[build_register_existing_nft(&keys)], &[]).await.unwrap();
+    let buyer = Keypair::new();
+    env.send(
+        &[
+            system_instruction::transfer(&payer, &buyer.pubkey(), 100_000_000),
+            create_associated_token_account(&payer, &buyer.pubkey(), &mint, &spl_token::ID),
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &get_associated_token_address(&payer, &mint),
+                &get_associated_token_address(&buyer.pubkey(), &mint),
+                &payer,
+                &[],
+                1,
+            )
+            .unwrap(),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let result = env.send(&[build_register_existing_nft(&keys)], &[]).await;
+    assert_nft_error(result, NftError::NotHolder);
+
+    let buyer_keys = LockKeys { owner: buyer.pubkey(), ..keys };
+    env.send(&[build_register_existing_nft(&buyer_keys)], &[&buyer]).await.unwrap();
+    assert_eq!(env.account::<NftInfo>(pda::nft_info(&mint)).await.owner, buyer.pubkey());
+
+    // while its locked for the bridge nobody takes it over
+    let transfer = build_transfer_to_zetachain(&buyer_keys, &Env::transfer_params());
+    env.send(&[transfer], &[&buyer]).await.unwrap();
+    let result = env.send(&[build_register_existing_nft(&buyer_keys)], &[&buyer]).await;
+    assert_nft_error(result, NftError::TokenLocked);
+}
+
+#[tokio::test]
+async fn test_deregister_nft_gives_back_only_unlocked_external_nfts() {
+    let mut env = setup().await;
+    let payer = env.payer();
+
+    // nfts the program minted keep their nft info
+    let ours = env.mint_nft().await;
+    let result = env.send(&[build_deregister_nft(&env.lock_keys(ours))], &[]).await;
+    assert_nft_error(result, NftError::NotExternal);
+
+    let keys = approved_external_nft(&mut env).await;
+    let mint = keys.mint;
+    env.send(&[build_register_existing_nft(&keys)], &[]).await.unwrap();
+    let transfer = build_transfer_to_zetachain(&keys, &Env::transfer_params());
+    env.send(&[transfer], &[]).await.unwrap();
+    let result = env.send(&[build_deregister_nft(&keys)], &[]).await;
+    assert_nft_error(result, NftError::TokenLocked);
+
+    let authority = env.authority.insecure_clone();
+    env.send(&[build_unlock_nft(&keys, authority.pubkey())], &[&authority])
+        .await
+        .unwrap();
+    env.send(&[build_deregister_nft(&keys)], &[]).await.unwrap();
+
+    let nft_info = env.context.banks_client.get_account(pda::nft_info(&mint)).await.unwrap();
+    assert!(nft_info.is_none());
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    let state = env.state().await;
+    assert_eq!(state.total_supply, 1); // only ours ever counted
+    assert_eq!(state.locked_count, 0);
+
+    // and it can be registered again
+    env.send(&[build_register_existing_nft(&keys)], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_only_registered_nfts_of_approved_collections_get_bridged() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+
+    // no collection, or one that isnt verified, never gets registered
+    let loose = external_nft(&mut env, true, None).await;
+    let result = env.send(&[build_register_existing_nft(&env.lock_keys(loose))], &[]).await;
+    assert_nft_error(result, NftError::CollectionNotApproved);
+
+    let keys = approved_external_nft(&mut env).await;
+    let collection = keys.collection.unwrap();
+    let unverified = external_nft(&mut env, true, Some(collection)).await;
+    let unverified_keys = LockKeys { collection: Some(collection), ..env.lock_keys(unverified) };
+    let result = env.send(&[build_register_existing_nft(&unverified_keys)], &[]).await;
+    assert_nft_error(result, NftError::CollectionNotApproved);
+
+    // the approval of another collection doesnt count
+    let other = external_nft(&mut env, true, None).await;
+    env.send(&[build_approve_collection(authority.pubkey(), payer, other)], &[&authority])
+        .await
+        .unwrap();
+    let wrong_keys = LockKeys { collection: Some(other), ..keys.clone() };
+    let result = env.send(&[build_register_existing_nft(&wrong_keys)], &[]).await;
+    assert_nft_error(result, NftError::CollectionNotApproved);
+
+    // only the authority approves
+    let approve = build_approve_collection(payer, payer, Pubkey::new_unique());
+    let result = env.send(&[approve], &[]).await;
+    assert_nft_error(result, NftError::Unauthorized);
+
+    env.send(&[build_register_existing_nft(&keys)], &[]).await.unwrap();
+    let transfer = build_transfer_to_zetachain(&keys, &Env::transfer_params());
+    env.send(&[transfer], &[]).await.unwrap();
+    env.send(&[build_unlock_nft(&keys, authority.pubkey())], &[&authority])
+        .await
+        .unwrap();
+
+    // once revoked the registered nft stays home
+    env.send(&[build_revoke_collection(authority.pubkey(), collection)], &[&authority])
+        .await
+        .unwrap();
+    let approval = env
+        .context
+        .banks_client
+        .get_account(pda::approved_collection(&collection))
+        .await
+        .unwrap();
+    assert!(approval.is_none());
+    let transfer = build_transfer_to_zetachain(&keys, &Env::transfer_params());
+    assert_nft_error(env.send(&[transfer], &[]).await, NftError::CollectionNotApproved);
+    assert_eq!(env.token_amount(&payer, &keys.mint).await, 1);
+
+    // nfts the program minted dont need an approval
+    let ours = env.mint_nft().await;
+    env.transfer_to_zetachain(ours).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_on_call_transfer_mints_through_the_gateway() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert_eq!(nft_info.mint, mint);
+    assert_eq!(nft_info.owner, payer);
+    assert_eq!(nft_info.name, NAME);
+    assert_eq!(nft_info.metadata_uri, URI);
+    assert!(nft_info.bridged);
+    let state = env.state().await;
+    assert_eq!(state.nonce, 1);
+    assert_eq!(state.total_supply, 1);
+}
+
+#[tokio::test]
+async fn test_uri_rewrite_only_touches_the_metadata_and_round_trips() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    let gateway = UriRewrite {
+        pattern: "ipfs://{cid}".to_string(),
+        replacement: "https://gateway.example/ipfs/{cid}".to_string(),
+    };
+    let set = build_set_uri_rewrite(payer, Some(&gateway));
+    assert_nft_error(env.send(&[set], &[]).await, NftError::Unauthorized);
+    let bad = UriRewrite { pattern: "ipfs://".to_string(), ..gateway.clone() };
+    let set = build_set_uri_rewrite(authority.pubkey(), Some(&bad));
+    assert_nft_error(env.send(&[set], &[&authority]).await, NftError::InvalidUriRewrite);
+    env.send(&[build_set_uri_rewrite(authority.pubkey(), Some(&gateway))], &[&authority])
+        .await
+        .unwrap();
+
+    let original = "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi/1.json";
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    let mut message = inbound_message(MessageType::Transfer, origin_mint, payer, 1);
+    let mut decoded = CrossChainMessage::try_from_slice(&message.data).unwrap();
+    decoded.metadata_uri = original.to_string();
+    message.data = decoded.try_to_vec().unwrap();
+    env.deliver(&message).await.unwrap();
+
+    let rewritten = format!("https://gateway.example/ipfs/{}", &original["ipfs://".len()..]);
+    assert_eq!(metadata_uri(&mut env, mint).await, rewritten);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert_eq!(nft_info.metadata_uri, original);
+
+    // it leaves with the uri it came with
+    let transfer = build_transfer_to_zetachain(&env.lock_keys(mint), &Env::transfer_params());
+    let (logs, _) = env.send_for_output(&[transfer]).await;
+    let outbound = CrossChainMessage::try_from_slice(&outbound_message(&logs)).unwrap();
+    assert_eq!(outbound.metadata_uri, original);
+
+    // and comes back to the same record
+    env.on_call(MessageType::Unlock, mint, 3).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert_eq!(nft_info.metadata_uri, original);
+    assert_eq!(metadata_uri(&mut env, mint).await, rewritten);
+
+    // a uri the pattern doesnt match passes through
+    let other = Pubkey::new_unique();
+    env.on_call(MessageType::Transfer, other, 4).await.unwrap();
+    assert_eq!(metadata_uri(&mut env, pda::bridged_mint(&other)).await, URI);
+
+    env.send(&[build_set_uri_rewrite(authority.pubkey(), None)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(env.state().await.uri_rewrite, None);
+}
+
+#[tokio::test]
+async fn test_junk_uri_gets_the_placeholder_until_the_owner_repairs_it() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    let placeholder = "https://example.com/metadata-unavailable.json";
+    env.send(&[build_set_placeholder_uri(authority.pubkey(), placeholder)], &[&authority])
+        .await
+        .unwrap();
+
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    let mut message = inbound_message(MessageType::Transfer, origin_mint, payer, 1);
+    let mut decoded = CrossChainMessage::try_from_slice(&message.data).unwrap();
+    decoded.metadata_uri = "javascript:alert(1)".to_string();
+    decoded.metadata_hash = Some([5; 32]);
+    message.data = decoded.try_to_vec().unwrap();
+    env.deliver(&message).await.unwrap();
+
+    // minted anyway, with the placeholder and a mutable metadata
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert!(nft_info.needs_metadata_repair);
+    assert_eq!(nft_info.metadata_uri, placeholder);
+    assert_eq!(metadata_uri(&mut env, mint).await, placeholder);
+    assert!(metadata(&mut env, mint).await.is_mutable);
+
+    let fixed = "https://example.com/punk/1.json";
+    let stranger = Keypair::new();
+    let repair = repair_metadata(stranger.pubkey(), mint, fixed, [5; 32]);
+    assert_nft_error(env.send(&[repair], &[&stranger]).await, NftError::Unauthorized);
+    let repair = repair_metadata(payer, mint, fixed, [6; 32]);
+    assert_nft_error(env.send(&[repair], &[]).await, NftError::MetadataHashMismatch);
+    let repair = repair_metadata(payer, mint, "ftp://example.com/1.json", [5; 32]);
+    assert_nft_error(env.send(&[repair], &[]).await, NftError::InvalidUriScheme);
+
+    env.send(&[repair_metadata(payer, mint, fixed, [5; 32])], &[]).await.unwrap();
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert!(!nft_info.needs_metadata_repair);
+    assert_eq!(nft_info.metadata_uri, fixed);
+    assert_eq!(metadata_uri(&mut env, mint).await, fixed);
+    // like any inbound metadata it stays mutable
+    assert!(metadata(&mut env, mint).await.is_mutable);
+    let repair = repair_metadata(payer, mint, fixed, [5; 32]);
+    assert_nft_error(env.send(&[repair], &[]).await, NftError::MetadataNotPendingRepair);
+
+    // with the placeholder off a junk uri is a dead letter again
+    env.send(&[build_set_placeholder_uri(authority.pubkey(), "")], &[&authority])
+        .await
+        .unwrap();
+    let mut junk = inbound_message(MessageType::Transfer, Pubkey::new_unique(), payer, 2);
+    let mut decoded = CrossChainMessage::try_from_slice(&junk.data).unwrap();
+    decoded.metadata_uri = "javascript:alert(1)".to_string();
+    junk.data = decoded.try_to_vec().unwrap();
+    assert_nft_error(env.deliver(&junk).await, NftError::InvalidUriScheme);
+}
+
+#[tokio::test]
+async fn test_inbound_metadata_stays_mutable_until_frozen() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+    assert!(metadata(&mut env, mint).await.is_mutable);
+
+    // still mutable, so there is nothing to count
+    let count = count_immutable_metadata(payer, mint);
+    assert_nft_error(env.send(&[count], &[]).await, NftError::MetadataMutable);
+
+    // the program updates it for the owner, for good
+    let stranger = Keypair::new();
+    let freeze = freeze_metadata(stranger.pubkey(), mint);
+    assert_nft_error(env.send(&[freeze], &[&stranger]).await, NftError::Unauthorized);
+    env.send(&[freeze_metadata(payer, mint)], &[]).await.unwrap();
+    assert!(!metadata(&mut env, mint).await.is_mutable);
+    assert_eq!(metadata_uri(&mut env, mint).await, URI);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert!(nft_info.metadata_immutable);
+    assert_eq!(env.state().await.immutable_bridged_count, 1);
+    let freeze = freeze_metadata(payer, mint);
+    assert_nft_error(env.send(&[freeze], &[]).await, NftError::MetadataImmutable);
+
+    // counting it again doesnt change anything
+    env.send(&[count_immutable_metadata(payer, mint)], &[]).await.unwrap();
+    assert_eq!(env.state().await.immutable_bridged_count, 1);
+
+    // with mutable inbound metadata off a new arrival is immutable and counted right away
+    env.send(&[build_set_inbound_metadata_mutable(authority.pubkey(), false)], &[&authority])
+        .await
+        .unwrap();
+    let other_origin = Pubkey::new_unique();
+    let other = pda::bridged_mint(&other_origin);
+    env.on_call(MessageType::Transfer, other_origin, 2).await.unwrap();
+    assert!(!metadata(&mut env, other).await.is_mutable);
+    assert_eq!(env.state().await.immutable_bridged_count, 2);
+
+    env.send(&[build_set_inbound_metadata_mutable(authority.pubkey(), true)], &[&authority])
+        .await
+        .unwrap();
+    assert!(env.state().await.inbound_metadata_mutable);
+}
+
+#[tokio::test]
+async fn test_on_call_transfer_into_a_funded_mint_address() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    // lamports sent to the bridged mint before the nft arrives, less than its rent
+    env.send(&[system_instruction::transfer(&payer, &mint, 1_000_000)], &[])
+        .await
+        .unwrap();
+
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    let state: Mint = env.account(mint).await;
+    assert_eq!(state.supply, 1);
+    assert_eq!(state.decimals, 0);
+    // the master edition took the mint over
+    assert_eq!(state.mint_authority, Some(pda::master_edition(&mint)).into());
+}
+
+#[tokio::test]
+async fn test_on_call_failing_after_the_mint_leaves_nothing_behind() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    let message = inbound_message(MessageType::Transfer, origin_mint, payer, 1);
+    let execute = env.on_call_instruction(&message).await;
+
+    // the delivery runs out of compute right at the end, after the mint, the ata
+    // and the metadata cpi all went through
+    let transaction = env.transaction(&[execute.clone()], &[]).await;
+    let simulation = env.context.banks_client.simulate_transaction(transaction).await.unwrap();
+    let units = simulation.simulation_details.unwrap().units_consumed as u32;
+    let transaction = env.transaction_with_limit(&[execute.clone()], &[], units - 1_000).await;
+    let result = env.context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(1, InstructionError::ComputationalBudgetExceeded)
+    );
+
+    // the transaction is all or nothing, no token, nft info or metadata and the
+    // nonce is still free
+    for key in [mint, pda::nft_info(&mint), pda::metadata(&mint)] {
+        assert!(env.context.banks_client.get_account(key).await.unwrap().is_none());
+    }
+    assert_eq!(env.state().await.nonce, 0);
+
+    // so the same message goes through once it is delivered again
+    env.send(&[execute], &[]).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert!(env.account::<NftInfo>(pda::nft_info(&mint)).await.metadata_created);
+    assert_eq!(env.state().await.nonce, 1);
+}
+
+#[tokio::test]
+async fn test_nonce_gaps_are_reported_and_skipped_messages_stay_out() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    env.on_call(MessageType::Transfer, Pubkey::new_unique(), 1).await.unwrap();
+
+    let message = inbound_message(MessageType::Transfer, Pubkey::new_unique(), payer, 4);
+    let execute = env.on_call_instruction(&message).await;
+    let (logs, _) = env.send_for_output(&[execute]).await;
+    assert!(logs.iter().any(|log| log.contains("Nonces 2 to 3 are missing")));
+    // they can still come in late, once
+    env.on_call(MessageType::Transfer, Pubkey::new_unique(), 3).await.unwrap();
+    assert_eq!(env.state().await.nonce, 4);
+
+    // only the authority skips, and only nonces that are still open
+    let authority = env.authority.insecure_clone();
+    let result = env.send(&[build_skip_message(payer, payer, 6, "bad payload")], &[]).await;
+    assert_nft_error(result, NftError::Unauthorized);
+    let skip = |nonce| build_skip_message(authority.pubkey(), payer, nonce, "bad payload");
+    let result = env.send(&[skip(4)], &[&authority]).await;
+    assert_nft_error(result, NftError::InvalidNonce);
+    env.send(&[skip(6)], &[&authority]).await.unwrap();
+
+    let skipped: SkippedMessage =
+        env.account(universal_nft::seeds::find_skipped_message(6).0).await;
+    assert_eq!(skipped.nonce, 6);
+    assert_eq!(skipped.authority, authority.pubkey());
+    assert_eq!(skipped.reason, "bad payload");
+    assert_eq!(env.state().await.nonce, 6);
+
+    // the skipped message itself can no longer be delivered, the next one can
+    let result = env.on_call(MessageType::Transfer, Pubkey::new_unique(), 6).await;
+    assert_nft_error(result, NftError::InvalidNonce);
+    env.on_call(MessageType::Transfer, Pubkey::new_unique(), 7).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_ordered_delivery_holds_early_nonces_until_drained() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    env.send(&[build_set_ordered_delivery(authority.pubkey(), true)], &[&authority])
+        .await
+        .unwrap();
+    let first = inbound_message(MessageType::Transfer, Pubkey::new_unique(), payer, 1);
+    let second_mint = Pubkey::new_unique();
+    let second = inbound_message(MessageType::Transfer, second_mint, payer, 2);
+
+    // nonce 2 lands first, it can only wait in a record and without an index slot
+    let execute = env.on_call_instruction(&second).await;
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::InvalidPendingNonce);
+    let with_index = OnCallKeys { pending_nonce: Some(2), ..env.on_call_keys(&second).await };
+    let execute = through_gateway(build_on_call(&with_index, &second), &second);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::MintIndexWhilePending);
+    let waiting = OnCallKeys { index_count: None, ..with_index };
+    let execute = through_gateway(build_on_call(&waiting, &second), &second);
+    env.send(&[execute], &[]).await.unwrap();
+
+    let pending: PendingNonce = env.account(pda::pending_nonce(2)).await;
+    assert_eq!(pending.nonce, 2);
+    assert_eq!(pending.message_hash, canonical_message_hash(&second.data));
+    assert_eq!(env.state().await.nonce, 0);
+    let second_bridged = pda::bridged_mint(&second_mint);
+    assert!(env.context.banks_client.get_account(second_bridged).await.unwrap().is_none());
+
+    let too_far = inbound_message(MessageType::Transfer, Pubkey::new_unique(), payer, 33);
+    let keys = OnCallKeys {
+        pending_nonce: Some(33),
+        index_count: None,
+        ..env.on_call_keys(&too_far).await
+    };
+    let execute = through_gateway(build_on_call(&keys, &too_far), &too_far);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::NonceOutOfWindow);
+
+    // its turn hasnt come yet. holding it back left a blank nft info, so it still
+    // counts as a first arrival and needs the index slot
+    let drain_keys = OnCallKeys {
+        pending_nonce: Some(2),
+        index_count: Some(env.state().await.index_count),
+        ..env.on_call_keys(&second).await
+    };
+    let drain = build_drain_pending(&drain_keys, &second);
+    assert_nft_error(env.send(&[drain], &[]).await, NftError::NonceNotReady);
+
+    env.deliver(&first).await.unwrap();
+    assert_eq!(env.state().await.nonce, 1);
+
+    // the payload has to be the one the record was made for
+    let forged = inbound_message(MessageType::Transfer, second_mint, Pubkey::new_unique(), 2);
+    let drain = build_drain_pending(&drain_keys, &forged);
+    assert_nft_error(env.send(&[drain], &[]).await, NftError::InvalidPendingNonce);
+
+    // the index slot moved on with the first delivery
+    let drain_keys = OnCallKeys {
+        pending_nonce: Some(2),
+        index_count: Some(env.state().await.index_count),
+        ..env.on_call_keys(&second).await
+    };
+    env.send(&[build_drain_pending(&drain_keys, &second)], &[]).await.unwrap();
+    assert_eq!(env.state().await.nonce, 2);
+    assert_eq!(env.token_amount(&payer, &second_bridged).await, 1);
+    let record = env.context.banks_client.get_account(pda::pending_nonce(2)).await;
+    assert!(record.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_inbound_only_from_chains_open_for_it() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+
+    // registered and enabled
+    env.on_call(MessageType::Transfer, Pubkey::new_unique(), 1).await.unwrap();
+
+    // inbound closed on its own, transfers out still go
+    env.send(
+        &[build_set_chain_inbound(authority.pubkey(), ZETACHAIN, false)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let message = inbound_message(MessageType::Transfer, Pubkey::new_unique(), payer, 3);
+    assert_nft_error(env.deliver(&message).await, NftError::SourceChainNotAllowed);
+    let mint = env.mint_nft().await;
+    env.transfer_to_zetachain(mint).await.unwrap();
+
+    // inbound open but the chain disabled
+    env.send(
+        &[
+            build_set_chain_inbound(authority.pubkey(), ZETACHAIN, true),
+            build_update_chain(authority.pubkey(), ZETACHAIN, &connected_chain(false)),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    assert_nft_error(env.deliver(&message).await, NftError::SourceChainNotAllowed);
+
+    // a chain that was never registered has no entry to pass along
+    let unknown = OnCallKeys { source_chain_id: 1, ..env.on_call_keys(&message).await };
+    let execute = through_gateway(build_on_call(&unknown, &message), &message);
+    assert_eq!(
+        env.send(&[execute], &[]).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(anchor_lang::error::ErrorCode::AccountNotInitialized.into())
+        )
+    );
+
+    // nothing was taken, the same message goes through once the chain is back
+    env.send(
+        &[build_update_chain(authority.pubkey(), ZETACHAIN, &connected_chain(true))],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    env.deliver(&message).await.unwrap();
+    assert_eq!(env.state().await.nonce, 3);
+}
+
+#[tokio::test]
+async fn test_paused_chain_keeps_its_messages_as_dead_letters() {
+    const OTHER_CHAIN: u64 = 97;
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    env.send(
+        &[build_register_chain(authority.pubkey(), payer, OTHER_CHAIN, &connected_chain(true))],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    // only the authority pauses
+    let stranger = Keypair::new();
+    let pause = build_set_chain_inbound_paused(stranger.pubkey(), ZETACHAIN, true);
+    assert_nft_error(env.send(&[pause], &[&stranger]).await, NftError::Unauthorized);
+    env.send(&[build_set_chain_inbound_paused(authority.pubkey(), ZETACHAIN, true)], &[&authority])
+        .await
+        .unwrap();
+
+    let origin_mint = Pubkey::new_unique();
+    let message = inbound_message(MessageType::Transfer, origin_mint, payer, 1);
+    let message_hash = canonical_message_hash(&message.data);
+    // without a record to keep it in the message fails and the nonce stays free
+    assert_nft_error(env.deliver(&message).await, NftError::SourceChainPaused);
+    assert_eq!(env.state().await.nonce, 0);
+
+    let keys = OnCallKeys { dead_letter: Some(message_hash), ..env.on_call_keys(&message).await };
+    let execute = through_gateway(build_on_call(&keys, &message), &message);
+    env.send(&[execute], &[]).await.unwrap();
+    let record: DeadLetter = env.account(pda::dead_letter(&message_hash)).await;
+    assert_eq!(record.reason, DeadLetterReason::SourceChainPaused);
+    assert_eq!(record.source_chain_id, ZETACHAIN);
+    assert_eq!(env.state().await.nonce, 1);
+    let mint = pda::bridged_mint(&origin_mint);
+    assert!(env.context.banks_client.get_account(mint).await.unwrap().is_none());
+
+    // the other chain keeps delivering, and transfers out to the paused one still go
+    let other_mint = Pubkey::new_unique();
+    let other = inbound_message(MessageType::Transfer, other_mint, payer, 2);
+    let other_keys = OnCallKeys { source_chain_id: OTHER_CHAIN, ..env.on_call_keys(&other).await };
+    let execute = through_gateway(build_on_call(&other_keys, &other), &other);
+    env.send(&[execute], &[]).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &pda::bridged_mint(&other_mint)).await, 1);
+    let local = env.mint_nft().await;
+    env.transfer_to_zetachain(local).await.unwrap();
+
+    // retrying waits for the pause to be lifted
+    env.send(
+        &[build_set_dead_letter_retry(authority.pubkey(), DeadLetterRetry::Anyone)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    // other nfts took index slots since, the keys are looked up again
+    let keys = OnCallKeys { dead_letter: Some(message_hash), ..env.on_call_keys(&message).await };
+    let retry = build_retry_dead_letter(&keys);
+    assert_nft_error(env.send(&[retry.clone()], &[]).await, NftError::SourceChainPaused);
+    env.send(&[build_set_chain_inbound_paused(authority.pubkey(), ZETACHAIN, false)], &[&authority])
+        .await
+        .unwrap();
+    env.send(&[retry], &[]).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+}
+
+#[tokio::test]
+async fn test_supply_cap_keeps_inbound_mints_as_dead_letters_until_raised() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    let first_origin = Pubkey::new_unique();
+    let first = pda::bridged_mint(&first_origin);
+    env.on_call(MessageType::Transfer, first_origin, 1).await.unwrap();
+    env.send(&[build_set_max_supply(authority.pubkey(), 1)], &[&authority])
+        .await
+        .unwrap();
+
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    let message = inbound_message(MessageType::Transfer, origin_mint, payer, 2);
+    let message_hash = canonical_message_hash(&message.data);
+    assert_nft_error(env.deliver(&message).await, NftError::SupplyCapReached);
+
+    let keys = OnCallKeys { dead_letter: Some(message_hash), ..env.on_call_keys(&message).await };
+    let execute = through_gateway(build_on_call(&keys, &message), &message);
+    env.send(&[execute], &[]).await.unwrap();
+    let record: DeadLetter = env.account(pda::dead_letter(&message_hash)).await;
+    assert_eq!(record.reason, DeadLetterReason::SupplyCapReached);
+    assert!(env.context.banks_client.get_account(mint).await.unwrap().is_none());
+    assert_eq!(env.state().await.total_supply, 1);
+
+    // an unlock adds nothing to the supply and goes through at the cap
+    env.transfer_to_zetachain(first).await.unwrap();
+    env.on_call(MessageType::Unlock, first, 4).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &first).await, 1);
+
+    // the retry is held the same way until the cap goes up
+    env.send(
+        &[build_set_dead_letter_retry(authority.pubkey(), DeadLetterRetry::Anyone)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let retry = build_retry_dead_letter(&keys);
+    assert_nft_error(env.send(&[retry.clone()], &[]).await, NftError::SupplyCapReached);
+    env.send(&[build_set_max_supply(authority.pubkey(), 2)], &[&authority])
+        .await
+        .unwrap();
+    env.send(&[retry], &[]).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert_eq!(env.state().await.total_supply, 2);
+}
+
+// signed offline with the test key [0x5a; 32] over tss_message for the transfer of
+// [5; 32] to [6; 32], nonce 1 and 2, from ZETACHAIN
+const TSS_ADDRESS: [u8; 20] = [
+    0xa8, 0x04, 0x9b, 0xb6, 0x81, 0x81, 0x79, 0x91, 0x24, 0xf9, 0x8e, 0x46, 0x7d, 0xd7, 0x49, 0xe1,
+    0x20, 0xab, 0xfa, 0x64,
+];
+const TSS_SIGNATURE_NONCE_1: ([u8; 64], u8) = (
+    [
+        0x17, 0x4e, 0x51, 0x0a, 0xf1, 0x66, 0x61, 0x57, 0xc7, 0x5c, 0xff, 0x38, 0x57, 0x38, 0x76,
+        0x66, 0x71, 0x8d, 0xa3, 0xbb, 0xcc, 0xbc, 0xc8, 0xf1, 0xdc, 0x3f, 0xdf, 0xa6, 0xbf, 0x5b,
+        0xaf, 0x6e, 0x6e, 0x86, 0x94, 0xd1, 0x2f, 0x9b, 0xd2, 0xc9, 0xf6, 0x6f, 0xa1, 0x09, 0x05,
+        0xd5, 0x40, 0xf6, 0x6c, 0xe6, 0x76, 0x0c, 0x84, 0x16, 0x97, 0x4c, 0xf9, 0x42, 0x59, 0x0f,
+        0xf5, 0xe0, 0x76, 0x69,
+    ],
+    0,
+);
+const TSS_SIGNATURE_NONCE_2: ([u8; 64], u8) = (
+    [
+        0x20, 0x38, 0x14, 0x89, 0x13, 0x52, 0xc6, 0x6a, 0xbc, 0xbe, 0x6b, 0x6f, 0x35, 0x83, 0xcf,
+        0xa7, 0xf7, 0x4d, 0xf5, 0x75, 0xb4, 0xbf, 0x49, 0xde, 0x82, 0xf2, 0x56, 0xa8, 0x43, 0x25,
+        0xad, 0x46, 0x4f, 0xf8, 0xb0, 0x0a, 0x3f, 0x3d, 0x5f, 0xb0, 0x1b, 0xac, 0x3b, 0x97, 0x14,
+        0x65, 0xa6, 0x7b, 0x44, 0xb1, 0x82, 0x99, 0x5e, 0x49, 0x2b, 0xde, 0xe0, 0xf2, 0x21, 0x1b,
+        0x72, 0x53, 0xa7, 0x4d,
+    ],
+    1,
+);
+
+#[tokio::test]
+async fn test_required_tss_signature_gates_on_call() {
+    let mut env = setup().await;
+    let authority = env.authority.insecure_clone();
+    assert_nft_error(
+        env.send(&[build_set_require_tss_signature(authority.pubkey(), true)], &[&authority]).await,
+        NftError::TssAddressNotSet,
+    );
+    env.send(
+        &[
+            build_set_tss_address(authority.pubkey(), TSS_ADDRESS),
+            build_set_require_tss_signature(authority.pubkey(), true),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    assert!(env.state().await.require_tss_signature);
+
+    let recipient = Pubkey::new_from_array([6; 32]);
+    let message = inbound_message(MessageType::Transfer, Pubkey::new_from_array([5; 32]), recipient, 1);
+    let keys = OnCallKeys { nft_owner: recipient, ..env.on_call_keys(&message).await };
+    let execute = through_gateway(build_on_call(&keys, &message), &message);
+    let signed = tss_message(&canonical_message_hash(&message.data), 1, ZETACHAIN);
+
+    // the gateway alone isnt enough any more
+    assert_nft_error(env.send(&[execute.clone()], &[]).await, NftError::InvalidSignature);
+
+    // a real tss signature, but for the same transfer with the next nonce. the compute
+    // budget instruction is 0 and the gateway call 1, so the signature sits at 2
+    let (signature, recovery_id) = TSS_SIGNATURE_NONCE_2;
+    let replayed = inbound_message(MessageType::Transfer, Pubkey::new_from_array([5; 32]), recipient, 2);
+    let next = tss_message(&canonical_message_hash(&replayed.data), 2, ZETACHAIN);
+    let wrong = build_tss_signature(2, TSS_ADDRESS, signature, recovery_id, &next);
+    assert_nft_error(env.send(&[execute.clone(), wrong], &[]).await, NftError::InvalidSignature);
+
+    let (signature, recovery_id) = TSS_SIGNATURE_NONCE_1;
+    let tss = build_tss_signature(2, TSS_ADDRESS, signature, recovery_id, &signed);
+    env.send(&[execute, tss], &[]).await.unwrap();
+    assert_eq!(env.state().await.nonce, 1);
+    let bridged = pda::bridged_mint(&Pubkey::new_from_array([5; 32]));
+    assert_eq!(env.token_amount(&recipient, &bridged).await, 1);
+}
+
+// the root message of a batch over these leaves, the gateway delivers it like any other
+fn batch_root_message(batch_id: Pubkey, leaves: &[Vec<u8>], nonce: u64) -> InboundMessage {
+    let message = CrossChainMessage {
+        message_type: MessageType::BatchRoot,
+        mint: batch_id,
+        recipient: [0; 32],
+        metadata_uri: String::new(),
+        name: String::new(),
+        symbol: String::new(),
+        nonce,
+        master_mint: Pubkey::default(),
+        edition: leaves.len() as u64,
+        metadata_hash: Some(wire::batch_root(leaves)),
+        royalty_bps: None,
+        royalty_recipient: None,
+        attributes: vec![],
+    };
+    InboundMessage {
+        amount: 0,
+        sender: SENDER,
+        data: message.try_to_vec().unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn test_batch_leaves_are_claimed_once_with_a_proof() {
+    let mut env = setup().await;
+    let batch_id = Pubkey::new_unique();
+    let recipients = [env.payer(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let messages: Vec<_> = recipients
+        .iter()
+        .map(|recipient| inbound_message(MessageType::Transfer, Pubkey::new_unique(), *recipient, 1))
+        .collect();
+    let leaves: Vec<_> = messages.iter().map(|message| message.data.clone()).collect();
+
+    // only the root comes through the gateway, it takes the nonce
+    let root = batch_root_message(batch_id, &leaves, 1);
+    let keys = OnCallKeys {
+        index_count: None,
+        inbound_batch: Some(batch_id),
+        ..env.on_call_keys(&root).await
+    };
+    env.send(&[through_gateway(build_on_call(&keys, &root), &root)], &[]).await.unwrap();
+    let batch: InboundBatch = env.account(pda::inbound_batch(&batch_id)).await;
+    assert_eq!(batch.root, wire::batch_root(&leaves));
+    assert_eq!(batch.leaf_count, 3);
+    assert_eq!(batch.nonce, 1);
+    assert_eq!(env.state().await.nonce, 1);
+
+    // a keeper claims for the second recipient
+    let claim_keys = OnCallKeys {
+        nft_owner: recipients[1],
+        inbound_batch: Some(batch_id),
+        ..env.on_call_keys(&messages[1]).await
+    };
+    let wrong_proof = wire::batch_proof(&leaves, 0);
+    let claim = build_claim_inbound(&claim_keys, 1, &leaves[1], &wrong_proof);
+    assert_nft_error(env.send(&[claim], &[]).await, NftError::InvalidMerkleProof);
+    let proof = wire::batch_proof(&leaves, 1);
+    let other_leaf = build_claim_inbound(&claim_keys, 1, &leaves[2], &proof);
+    assert_nft_error(env.send(&[other_leaf], &[]).await, NftError::InvalidMerkleProof);
+
+    env.send(&[build_claim_inbound(&claim_keys, 1, &leaves[1], &proof)], &[]).await.unwrap();
+    let origin = CrossChainMessage::try_from_slice(&leaves[1]).unwrap().mint;
+    assert_eq!(env.token_amount(&recipients[1], &pda::bridged_mint(&origin)).await, 1);
+    let batch: InboundBatch = env.account(pda::inbound_batch(&batch_id)).await;
+    assert_eq!(batch.claimed, [0b010]);
+    assert_eq!(batch.claimed_count, 1);
+
+    // the nft info is there now, so no index slot either
+    let again = OnCallKeys {
+        nft_owner: recipients[1],
+        inbound_batch: Some(batch_id),
+        ..env.on_call_keys(&messages[1]).await
+    };
+    let claim = build_claim_inbound(&again, 1, &leaves[1], &proof);
+    assert_nft_error(env.send(&[claim], &[]).await, NftError::LeafAlreadyClaimed);
+}
+
+#[tokio::test]
+async fn test_claim_mode_leaves_minting_to_the_recipient() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    env.send(&[build_set_claim_mode(authority.pubkey(), true)], &[&authority]).await.unwrap();
+    let recipient = Keypair::new();
+    env.send(&[system_instruction::transfer(&payer, &recipient.pubkey(), 100_000_000)], &[])
+        .await
+        .unwrap();
+
+    // the relayer only pays for the claim record, it cant take an index slot
+    let origin = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin);
+    let message = inbound_message(MessageType::Transfer, origin, recipient.pubkey(), 1);
+    let keys = OnCallKeys {
+        nft_owner: recipient.pubkey(),
+        pending_claim: Some(1),
+        ..env.on_call_keys(&message).await
+    };
+    let execute = through_gateway(build_on_call(&keys, &message), &message);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::MintIndexBeforeClaim);
+    let no_record = OnCallKeys { pending_claim: None, index_count: None, ..keys.clone() };
+    let execute = through_gateway(build_on_call(&no_record, &message), &message);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::InvalidPendingClaim);
+    let relayed = OnCallKeys { index_count: None, ..keys };
+    env.send(&[through_gateway(build_on_call(&relayed, &message), &message)], &[])
+        .await
+        .unwrap();
+
+    let claim: PendingClaim = env.account(pda::pending_claim(1)).await;
+    assert_eq!(claim.recipient, recipient.pubkey());
+    assert_eq!(claim.mint, mint);
+    assert_eq!(claim.message_hash, canonical_message_hash(&message.data));
+    assert_eq!(claim.payload, message.data);
+    assert_eq!(env.state().await.nonce, 1);
+    assert!(env.context.banks_client.get_account(mint).await.unwrap().is_none());
+
+    // only the recipient claims, as the payer. the blank nft info still needs its slot
+    let claim_keys = OnCallKeys {
+        payer: recipient.pubkey(),
+        nft_owner: recipient.pubkey(),
+        pending_claim: Some(1),
+        index_count: Some(env.state().await.index_count),
+        ..env.on_call_keys(&message).await
+    };
+    let stranger = OnCallKeys { payer, ..claim_keys.clone() };
+    assert_nft_error(env.send(&[build_claim_nft(&stranger)], &[]).await, NftError::Unauthorized);
+    env.send(&[build_claim_nft(&claim_keys)], &[&recipient]).await.unwrap();
+    assert_eq!(env.token_amount(&recipient.pubkey(), &mint).await, 1);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert_eq!(nft_info.owner, recipient.pubkey());
+    assert!(nft_info.index.is_some());
+    assert!(env.context.banks_client.get_account(pda::pending_claim(1)).await.unwrap().is_none());
+
+    // the record is gone, so a second claim has nothing to mint
+    let again = OnCallKeys { index_count: None, ..claim_keys };
+    let result = env.send(&[build_claim_nft(&again)], &[&recipient]).await;
+    assert_nft_error(result, NftError::InvalidPendingClaim);
+
+    // an unclaimed nft goes on to another chain straight from its record
+    let origin = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin);
+    let message = inbound_message(MessageType::Transfer, origin, recipient.pubkey(), 2);
+    let keys = OnCallKeys {
+        nft_owner: recipient.pubkey(),
+        pending_claim: Some(2),
+        index_count: None,
+        ..env.on_call_keys(&message).await
+    };
+    env.send(&[through_gateway(build_on_call(&keys, &message), &message)], &[])
+        .await
+        .unwrap();
+
+    let params = Env::transfer_params();
+    let stranger = build_transfer_claim_to_zetachain(payer, origin, 2, &params, None);
+    assert_nft_error(env.send(&[stranger], &[]).await, NftError::Unauthorized);
+    let send_on = build_transfer_claim_to_zetachain(recipient.pubkey(), origin, 2, &params, None);
+    let transaction = env.transaction(&[send_on], &[&recipient]).await;
+    let outcome = env
+        .context
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    outcome.result.unwrap();
+    let logs = outcome.metadata.expect("bank returns metadata").log_messages;
+    let outbound = CrossChainMessage::try_from_slice(&outbound_message(&logs)).unwrap();
+    assert_eq!(outbound.message_type, MessageType::Transfer);
+    assert_eq!(outbound.mint, mint);
+    assert_eq!(outbound.recipient, evm_recipient());
+    assert_eq!(outbound.nonce, 1);
+    assert_eq!(outbound.metadata_uri, URI);
+    let state = env.state().await;
+    assert_eq!(state.outbound_nonce, 1);
+    assert_eq!(state.nonce, 2);
+    assert!(env.context.banks_client.get_account(pda::pending_claim(2)).await.unwrap().is_none());
+    assert!(env.context.banks_client.get_account(mint).await.unwrap().is_none());
+
+    let claim_keys = OnCallKeys {
+        payer: recipient.pubkey(),
+        nft_owner: recipient.pubkey(),
+        pending_claim: Some(2),
+        index_count: Some(env.state().await.index_count),
+        ..env.on_call_keys(&message).await
+    };
+    let result = env.send(&[build_claim_nft(&claim_keys)], &[&recipient]).await;
+    assert_nft_error(result, NftError::InvalidPendingClaim);
+}
+
+// the ed25519 instruction with `signer`'s signature over the action at that nonce
+fn admin_signature(signer: &Keypair, action: &AdminAction, nonce: u64) -> Instruction {
+    let message = admin_message(action, nonce);
+    let signature = signer.sign_message(&message);
+    build_admin_signature(signer.pubkey(), signature.as_ref().try_into().unwrap(), &message)
+}
+
+#[tokio::test]
+async fn test_signed_admin_actions_run_once_as_signed() {
+    let mut env = setup().await;
+    let relayer = env.payer();
+    let authority = env.authority.insecure_clone();
+    let pause = AdminAction::SetMintPaused { mint_paused: true };
+
+    // the authority only signs, the relayer submits and pays
+    env.send(
+        &[build_execute_signed_admin(relayer, &pause, 0), admin_signature(&authority, &pause, 0)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = env.state().await;
+    assert!(state.mint_paused);
+    assert_eq!(state.admin_nonce, 1);
+
+    // the same signed action again
+    let replay = [build_execute_signed_admin(relayer, &pause, 0), admin_signature(&authority, &pause, 0)];
+    assert_nft_error(env.send(&replay, &[]).await, NftError::InvalidAdminNonce);
+
+    // signed for a later nonce than the one it runs at
+    let fee = AdminAction::SetMintFee { mint_fee_lamports: 5 };
+    let early = [build_execute_signed_admin(relayer, &fee, 1), admin_signature(&authority, &fee, 2)];
+    assert_nft_error(env.send(&early, &[]).await, NftError::InvalidAdminSignature);
+
+    // the relayer swaps in another fee under the authority's signature
+    let tampered = AdminAction::SetMintFee { mint_fee_lamports: 500 };
+    let swapped = [build_execute_signed_admin(relayer, &tampered, 1), admin_signature(&authority, &fee, 1)];
+    assert_nft_error(env.send(&swapped, &[]).await, NftError::InvalidAdminSignature);
+
+    // or signs it itself
+    let payer = env.context.payer.insecure_clone();
+    let self_signed = [build_execute_signed_admin(relayer, &tampered, 1), admin_signature(&payer, &tampered, 1)];
+    assert_nft_error(env.send(&self_signed, &[]).await, NftError::InvalidAdminSignature);
+
+    env.send(
+        &[build_execute_signed_admin(relayer, &fee, 1), admin_signature(&authority, &fee, 1)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = env.state().await;
+    assert_eq!(state.mint_fee_lamports, 5);
+    assert_eq!(state.admin_nonce, 2);
+}
+
+#[tokio::test]
+async fn test_dead_letters_keep_retryable_messages_until_retried() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    // the program state pda as recipient, fixable by allowing program recipients
+    let message = inbound_message(MessageType::Transfer, origin_mint, pda::nft_program(), 1);
+    let message_hash = canonical_message_hash(&message.data);
+    let keys = OnCallKeys {
+        payer,
+        source_chain_id: ZETACHAIN,
+        origin_mint,
+        token_program: anchor_spl::token::ID,
+        nft_owner: pda::nft_program(),
+        metaplex: true,
+        index_count: Some(env.state().await.index_count),
+        dead_letter: Some(message_hash),
+        ..Default::default()
+    };
+
+    // a sender that isnt the connected contract is never kept
+    let forged = InboundMessage { sender: [9; 20], ..message.clone() };
+    let execute = through_gateway(build_on_call(&keys, &forged), &forged);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::UnknownSender);
+
+    let execute = through_gateway(build_on_call(&keys, &message), &message);
+    env.send(&[execute], &[]).await.unwrap();
+    let record: DeadLetter = env.account(pda::dead_letter(&message_hash)).await;
+    assert_eq!(record.reason, DeadLetterReason::InvalidRecipient);
+    assert_eq!(record.payload, message.data);
+    assert_eq!(env.state().await.nonce, 1);
+    assert!(env.context.banks_client.get_account(mint).await.unwrap().is_none());
+
+    env.send(
+        &[build_set_dead_letter_retry(authority.pubkey(), DeadLetterRetry::Anyone)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    env.send(&[build_set_allow_program_recipients(authority.pubkey(), true)], &[&authority])
+        .await
+        .unwrap();
+
+    // the record has to be the one filed under the hash the retry names
+    let mut retry = build_retry_dead_letter(&keys);
+    retry.data = universal_nft::instruction::RetryDeadLetter {
+        mint_key: origin_mint,
+        message_hash: [7; 32],
+    }
+    .data();
+    assert_nft_error(env.send(&[retry], &[]).await, NftError::InvalidDeadLetter);
+
+    env.send(&[build_retry_dead_letter(&keys)], &[]).await.unwrap();
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 1);
+    let record = env.context.banks_client.get_account(pda::dead_letter(&message_hash)).await;
+    assert!(record.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_failed_inbound_keeps_the_hash_until_the_message_is_retried() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    // the program state pda as recipient, fixable by allowing program recipients
+    let message = inbound_message(MessageType::Transfer, origin_mint, pda::nft_program(), 1);
+    let message_hash = canonical_message_hash(&message.data);
+    let keys = OnCallKeys {
+        payer,
+        source_chain_id: ZETACHAIN,
+        origin_mint,
+        token_program: anchor_spl::token::ID,
+        nft_owner: pda::nft_program(),
+        metaplex: true,
+        index_count: Some(env.state().await.index_count),
+        failed_inbound: Some(1),
+        ..Default::default()
+    };
+
+    // a sender that isnt the connected contract is never kept
+    let forged = InboundMessage { sender: [9; 20], ..message.clone() };
+    let execute = through_gateway(build_on_call(&keys, &forged), &forged);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::UnknownSender);
+    let record = env.context.banks_client.get_account(pda::failed_inbound(1)).await;
+    assert!(record.unwrap().is_none());
+
+    let execute = through_gateway(build_on_call(&keys, &message), &message);
+    env.send(&[execute], &[]).await.unwrap();
+    let record: FailedInbound = env.account(pda::failed_inbound(1)).await;
+    assert_eq!(record.nonce, 1);
+    assert_eq!(record.message_hash, message_hash);
+    assert_eq!(record.reason, DeadLetterReason::InvalidRecipient);
+    assert_eq!(env.state().await.nonce, 1);
+    assert!(env.context.banks_client.get_account(mint).await.unwrap().is_none());
+
+    // only the authority retries until it says otherwise
+    let retry = build_retry_inbound(&keys, &message);
+    assert_nft_error(env.send(&[retry], &[]).await, NftError::Unauthorized);
+    env.send(
+        &[build_set_dead_letter_retry(authority.pubkey(), DeadLetterRetry::Anyone)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    env.send(&[build_set_allow_program_recipients(authority.pubkey(), true)], &[&authority])
+        .await
+        .unwrap();
+
+    // the payload has to be the one that failed, not one sending the nft elsewhere
+    let redirected = inbound_message(MessageType::Transfer, origin_mint, payer, 1);
+    let retry = build_retry_inbound(&keys, &redirected);
+    assert_nft_error(env.send(&[retry], &[]).await, NftError::FailedInboundHashMismatch);
+
+    // and the record the one for the nonce the retry names
+    let other_nonce = OnCallKeys { failed_inbound: Some(2), ..keys.clone() };
+    let retry = build_retry_inbound(&other_nonce, &message);
+    assert_nft_error(env.send(&[retry], &[]).await, NftError::InvalidFailedInbound);
+
+    env.send(&[build_retry_inbound(&keys, &message)], &[]).await.unwrap();
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 1);
+    let record = env.context.banks_client.get_account(pda::failed_inbound(1)).await;
+    assert!(record.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_blocked_recipient_gets_nothing_until_unblocked() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    let held = env.mint_nft().await;
+
+    let stranger = Keypair::new();
+    let block = build_block_recipient(stranger.pubkey(), payer, payer);
+    assert_nft_error(env.send(&[block], &[&stranger]).await, NftError::Unauthorized);
+    env.send(&[build_block_recipient(authority.pubkey(), payer, payer)], &[&authority])
+        .await
+        .unwrap();
+
+    // nothing is taken from the wallet, it can still send what it holds out
+    env.transfer_to_zetachain(held).await.unwrap();
+    let unlock = build_unlock_nft(&env.lock_keys(held), authority.pubkey());
+    assert_nft_error(env.send(&[unlock], &[&authority]).await, NftError::RecipientBlocked);
+
+    let mint = Keypair::new();
+    let keys = env.mint_keys(mint.pubkey()).await;
+    let mint_nft = build_mint_nft(&keys, &env.mint_params());
+    assert_nft_error(env.send(&[mint_nft], &[&mint]).await, NftError::RecipientBlocked);
+
+    // an inbound transfer for the wallet is kept as a dead letter
+    let origin_mint = Pubkey::new_unique();
+    let message = inbound_message(MessageType::Transfer, origin_mint, payer, 2);
+    let message_hash = canonical_message_hash(&message.data);
+    let keys = OnCallKeys { dead_letter: Some(message_hash), ..env.on_call_keys(&message).await };
+    let execute = through_gateway(build_on_call(&keys, &message), &message);
+    env.send(&[execute], &[]).await.unwrap();
+    let record: DeadLetter = env.account(pda::dead_letter(&message_hash)).await;
+    assert_eq!(record.reason, DeadLetterReason::RecipientBlocked);
+    let mint = pda::bridged_mint(&origin_mint);
+    assert!(env.context.banks_client.get_account(mint).await.unwrap().is_none());
+
+    // the block pda is checked against the owner of the nft coming back, not taken
+    // on trust
+    let returned = inbound_message(MessageType::Unlock, held, payer, 3);
+    let mut on_call = build_on_call(&env.on_call_keys(&returned).await, &returned);
+    let blocked = pda::blocked_recipient(&payer);
+    for meta in on_call.accounts.iter_mut().filter(|meta| meta.pubkey == blocked) {
+        meta.pubkey = pda::blocked_recipient(&Pubkey::new_unique());
+    }
+    let execute = through_gateway(on_call, &returned);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::InvalidBlockedRecipient);
+    assert_nft_error(env.deliver(&returned).await, NftError::RecipientBlocked);
+
+    env.send(&[build_unblock_recipient(authority.pubkey(), payer)], &[&authority])
+        .await
+        .unwrap();
+    env.send(
+        &[build_set_dead_letter_retry(authority.pubkey(), DeadLetterRetry::Anyone)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    env.send(&[build_retry_dead_letter(&keys)], &[]).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    env.deliver(&returned).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &held).await, 1);
+}
+
+#[tokio::test]
+async fn test_blocked_mint_stays_off_the_bridge_but_moves_on_solana() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    let mint = env.mint_nft().await;
+
+    let stranger = Keypair::new();
+    let block = build_block_mint(stranger.pubkey(), payer, mint, "stolen");
+    assert_nft_error(env.send(&[block], &[&stranger]).await, NftError::Unauthorized);
+    env.send(&[build_block_mint(authority.pubkey(), payer, mint, "stolen")], &[&authority])
+        .await
+        .unwrap();
+    let record: BlockedMint = env.account(pda::blocked_mint(&mint)).await;
+    assert_eq!(record.reason, "stolen");
+    assert_nft_error(env.transfer_to_zetachain(mint).await, NftError::MintBlocked);
+
+    // the holder can still move it around with plain spl transfers
+    let friend = Keypair::new();
+    let held = get_associated_token_address(&payer, &mint);
+    let friend_account = get_associated_token_address(&friend.pubkey(), &mint);
+    let empty = spl_token::state::Account {
+        mint,
+        owner: friend.pubkey(),
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    set_token_account(&mut env, friend_account, empty);
+    let give =
+        spl_token::instruction::transfer(&spl_token::ID, &held, &friend_account, &payer, &[], 1)
+            .unwrap();
+    env.send(&[give], &[]).await.unwrap();
+    assert_eq!(env.token_amount(&friend.pubkey(), &mint).await, 1);
+    assert_eq!(env.token_amount(&payer, &mint).await, 0);
+    let back = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &friend_account,
+        &held,
+        &friend.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    env.send(&[back], &[&friend]).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+
+    env.send(&[build_unblock_mint(authority.pubkey(), mint, "recovered")], &[&authority])
+        .await
+        .unwrap();
+    let record = env.context.banks_client.get_account(pda::blocked_mint(&mint)).await;
+    assert!(record.unwrap().is_none());
+    env.transfer_to_zetachain(mint).await.unwrap();
+
+    // blocked while out, the unlock fails outright instead of becoming a dead letter
+    env.send(&[build_block_mint(authority.pubkey(), payer, mint, "stolen")], &[&authority])
+        .await
+        .unwrap();
+    let returned = inbound_message(MessageType::Unlock, mint, payer, 2);
+    let message_hash = canonical_message_hash(&returned.data);
+    let keys = OnCallKeys { dead_letter: Some(message_hash), ..env.on_call_keys(&returned).await };
+    let execute = through_gateway(build_on_call(&keys, &returned), &returned);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::MintBlocked);
+    let record = env.context.banks_client.get_account(pda::dead_letter(&message_hash)).await;
+    assert!(record.unwrap().is_none());
+
+    env.send(&[build_unblock_mint(authority.pubkey(), mint, "recovered")], &[&authority])
+        .await
+        .unwrap();
+    env.deliver(&returned).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+}
+
+#[tokio::test]
+async fn test_on_call_transfer_delivered_twice_mints_once() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+    let index = env.account::<NftInfo>(pda::nft_info(&mint)).await.index;
+
+    // the same nft again, this time naming another recipient
+    let recipient = Pubkey::new_unique();
+    let keys = OnCallKeys {
+        payer,
+        source_chain_id: ZETACHAIN,
+        origin_mint,
+        token_program: anchor_spl::token::ID,
+        nft_owner: recipient,
+        metaplex: true,
+        ..Default::default()
+    };
+    let message = inbound_message(MessageType::Transfer, origin_mint, recipient, 2);
+    let execute = through_gateway(build_on_call(&keys, &message), &message);
+    let (logs, _) = env.send_for_output(&[execute]).await;
+
+    // nothing minted, the token stays where it is and only the owner on record moves
+    assert_eq!(env.account::<Mint>(mint).await.supply, 1);
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert_eq!(nft_info.owner, recipient);
+    assert_eq!(nft_info.index, index);
+    let state = env.state().await;
+    assert_eq!(state.nonce, 2);
+    assert_eq!(state.total_supply, 1);
+    assert!(logs.iter().any(|log| log.contains("was already delivered")));
+}
+
+#[tokio::test]
+async fn test_on_call_transfer_of_a_locked_nft_is_rejected() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+
+    // locked for the bridge it comes back with an unlock, a transfer cant mint a second one
+    env.transfer_to_zetachain(mint).await.unwrap();
+    assert_nft_error(
+        env.on_call(MessageType::Transfer, origin_mint, 3).await,
+        NftError::MintHasSupply,
+    );
+    assert_eq!(env.account::<Mint>(mint).await.supply, 1);
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 1);
+    assert_eq!(env.token_amount(&payer, &mint).await, 0);
+}
+
+#[tokio::test]
+async fn test_on_call_transfer_after_a_burn_reuses_the_mint() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    // lazy mode leaves the mint authority with the program until the metadata
+    let authority = env.authority.insecure_clone();
+    env.send(&[build_set_lazy_metadata(authority.pubkey(), true)], &[&authority])
+        .await
+        .unwrap();
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+    let index = env.account::<NftInfo>(pda::nft_info(&mint)).await.index.unwrap();
+    env.send(&[burn_nft(payer, mint, index)], &[]).await.unwrap();
+    assert_eq!(env.account::<Mint>(mint).await.supply, 0);
+
+    env.on_call(MessageType::Transfer, origin_mint, 2).await.unwrap();
+
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert_eq!(env.account::<Mint>(mint).await.supply, 1);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert_eq!(nft_info.mint, mint);
+    assert!(nft_info.bridged);
+    // a new arrival as far as the index goes
+    assert_eq!(nft_info.index, Some(index + 1));
+}
+
+#[tokio::test]
+async fn test_inbound_mint_gets_a_master_edition_without_prints() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+
+    let edition = master_edition(&mut env, mint).await;
+    assert_eq!(edition.max_supply, Some(0));
+    assert_eq!(edition.supply, 0);
+    let state: Mint = env.account(mint).await;
+    assert_eq!(state.mint_authority, Some(pda::master_edition(&mint)).into());
+    assert_eq!(state.freeze_authority, Some(pda::master_edition(&mint)).into());
+
+    // only token metadata signs for the edition pda, any other mint_to fails
+    let mint_more = spl_token::instruction::mint_to(
+        &spl_token::ID,
+        &mint,
+        &get_associated_token_address(&payer, &mint),
+        &payer,
+        &[],
+        1,
+    )
+    .unwrap();
+    assert!(env.send(&[mint_more], &[]).await.is_err());
+    assert_eq!(env.account::<Mint>(mint).await.supply, 1);
+
+    // nor can the same nft arrive again once it was burned
+    let index = env.account::<NftInfo>(pda::nft_info(&mint)).await.index.unwrap();
+    env.send(&[burn_nft(payer, mint, index)], &[]).await.unwrap();
+    assert_nft_error(
+        env.on_call(MessageType::Transfer, origin_mint, 2).await,
+        NftError::MintRetired,
+    );
+    assert_eq!(env.account::<Mint>(mint).await.supply, 0);
+}
+
+#[tokio::test]
+async fn test_materialize_metadata_adds_the_master_edition() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let authority = env.authority.insecure_clone();
+    env.send(&[build_set_lazy_metadata(authority.pubkey(), true)], &[&authority])
+        .await
+        .unwrap();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+    let state: Mint = env.account(mint).await;
+    assert_eq!(state.mint_authority, Some(pda::nft_program()).into());
+
+    env.send(&[materialize_metadata(payer, mint)], &[]).await.unwrap();
+
+    assert_eq!(master_edition(&mut env, mint).await.max_supply, Some(0));
+    let state: Mint = env.account(mint).await;
+    assert_eq!(state.mint_authority, Some(pda::master_edition(&mint)).into());
+    assert!(env.account::<NftInfo>(pda::nft_info(&mint)).await.metadata_created);
+}
+
+#[tokio::test]
+async fn test_on_call_unlock_returns_a_bridged_nft() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+    env.transfer_to_zetachain(mint).await.unwrap();
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 1);
+
+    env.on_call(MessageType::Unlock, mint, 3).await.unwrap();
+
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 0);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert!(!nft_info.is_locked);
+    assert_eq!(env.state().await.nonce, 3);
+}
+
+#[tokio::test]
+async fn test_on_call_unlock_with_accounts_of_another_nft_is_rejected() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mint = pda::bridged_mint(&first);
+    let other = pda::bridged_mint(&second);
+    env.on_call(MessageType::Transfer, first, 1).await.unwrap();
+    env.on_call(MessageType::Transfer, second, 2).await.unwrap();
+    env.transfer_to_zetachain(mint).await.unwrap();
+    env.transfer_to_zetachain(other).await.unwrap();
+
+    // the escrow of the other nft
+    let message = inbound_message(MessageType::Unlock, mint, payer, 5);
+    let mut execute = env.on_call_instruction(&message).await;
+    let escrow = get_associated_token_address(&pda::nft_program(), &mint);
+    let other_escrow = get_associated_token_address(&pda::nft_program(), &other);
+    for meta in execute.accounts.iter_mut().filter(|meta| meta.pubkey == escrow) {
+        meta.pubkey = other_escrow;
+    }
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::EscrowMismatch);
+
+    // the message names one nft, every account is the other's
+    let keys = OnCallKeys {
+        payer,
+        source_chain_id: ZETACHAIN,
+        origin_mint: other,
+        unlock: true,
+        token_program: anchor_spl::token::ID,
+        nft_owner: payer,
+        metaplex: true,
+        ..Default::default()
+    };
+    let execute = through_gateway(build_on_call(&keys, &message), &message);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::MintMismatch);
+
+    // both stay locked and the nonce wasnt used up
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 1);
+    assert_eq!(env.token_amount(&pda::nft_program(), &other).await, 1);
+    assert_eq!(env.state().await.nonce, 2);
+    env.on_call(MessageType::Unlock, mint, 5).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+}
+
+#[tokio::test]
+async fn test_on_call_with_token_accounts_of_the_other_message_type_is_rejected() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+
+    // a transfer carrying the escrow and the owner ata of an unlock
+    let message = inbound_message(MessageType::Transfer, origin_mint, payer, 1);
+    let keys = env.on_call_keys(&message).await;
+    let mut on_call = build_on_call(&keys, &message);
+    let unlock = OnCallKeys { unlock: true, origin_mint: mint, ..keys };
+    // the recipient ata is the only account after the on_call ones
+    let shared = on_call.accounts.len() - 1;
+    on_call.accounts.truncate(shared);
+    on_call.accounts.extend_from_slice(&build_on_call(&unlock, &message).accounts[shared..]);
+    let execute = through_gateway(on_call, &message);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::InboundAccountsMismatch);
+    env.deliver(&message).await.unwrap();
+    env.transfer_to_zetachain(mint).await.unwrap();
+
+    // an unlock carrying the recipient ata of a transfer
+    let returned = inbound_message(MessageType::Unlock, mint, payer, 3);
+    let keys = env.on_call_keys(&returned).await;
+    let mut on_call = build_on_call(&keys, &returned);
+    let transfer = OnCallKeys { unlock: false, origin_mint, ..keys.clone() };
+    let shared = on_call.accounts.len() - 2;
+    on_call.accounts.truncate(shared);
+    on_call.accounts.extend_from_slice(&build_on_call(&transfer, &returned).accounts[shared..]);
+    let execute = through_gateway(on_call, &returned);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::InboundAccountsMismatch);
+
+    // or with an account too many
+    let mut on_call = build_on_call(&keys, &returned);
+    on_call.accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+    let execute = through_gateway(on_call, &returned);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::InboundAccountsMismatch);
+
+    // an unlock returning the nft to an ata that isnt the owner's, the owner ata
+    // comes last
+    let mut on_call = build_on_call(&keys, &returned);
+    let owner_ata = on_call.accounts.len() - 1;
+    on_call.accounts[owner_ata].pubkey = get_associated_token_address(&Pubkey::new_unique(), &mint);
+    let execute = through_gateway(on_call, &returned);
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::InvalidRecipient);
+
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 1);
+    env.deliver(&returned).await.unwrap();
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+}
+
+#[tokio::test]
+async fn test_on_call_transfer_onto_another_mint_is_rejected() {
+    let mut env = setup().await;
+    let origin_mint = Pubkey::new_unique();
+    let other_mint = Pubkey::new_unique();
+    // the accounts of the other nft, the message names origin_mint
+    let keys = OnCallKeys {
+        payer: env.payer(),
+        source_chain_id: ZETACHAIN,
+        origin_mint: other_mint,
+        token_program: anchor_spl::token::ID,
+        nft_owner: env.payer(),
+        metaplex: true,
+        index_count: Some(0),
+        ..Default::default()
+    };
+    let message = inbound_message(MessageType::Transfer, origin_mint, env.payer(), 1);
+    let execute = through_gateway(build_on_call(&keys, &message), &message);
+
+    assert_nft_error(env.send(&[execute], &[]).await, NftError::MintMismatch);
+    assert_eq!(env.state().await.nonce, 0);
+}
+
+#[tokio::test]
+async fn test_replayed_stale_and_far_ahead_messages_are_rejected() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    env.on_call(MessageType::Transfer, origin_mint, 5).await.unwrap();
+
+    // the same message again
+    assert_nft_error(
+        env.on_call(MessageType::Transfer, origin_mint, 5).await,
+        NftError::InvalidNonce,
+    );
+    // one more than a window ahead would push every open nonce out at once
+    let far_ahead = 5 + NONCE_WINDOW + 1;
+    assert_nft_error(
+        env.on_call(MessageType::Transfer, Pubkey::new_unique(), far_ahead).await,
+        NftError::InvalidNonce,
+    );
+    assert_eq!(env.state().await.nonce, 5);
+
+    // once the highest nonce is a window further up, the ones below are stale
+    let last = 4 + NONCE_WINDOW;
+    env.on_call(MessageType::Transfer, Pubkey::new_unique(), last).await.unwrap();
+    assert_nft_error(
+        env.on_call(MessageType::Transfer, origin_mint, 4).await,
+        NftError::InvalidNonce,
+    );
+
+    assert_eq!(env.state().await.nonce, last);
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert!(!env.account::<NftInfo>(pda::nft_info(&mint)).await.is_locked);
+}
+
+#[tokio::test]
+async fn test_on_call_outside_the_gateway_is_rejected() {
+    let mut env = setup().await;
+    let origin_mint = Pubkey::new_unique();
+    let keys = OnCallKeys {
+        payer: env.payer(),
+        source_chain_id: ZETACHAIN,
+        origin_mint,
+        token_program: anchor_spl::token::ID,
+        nft_owner: env.payer(),
+        metaplex: true,
+        index_count: Some(0),
+        ..Default::default()
+    };
+    let message = inbound_message(MessageType::Transfer, origin_mint, env.payer(), 1);
+
+    assert_nft_error(
+        env.send(&[build_on_call(&keys, &message)], &[]).await,
+        NftError::Unauthorized,
+    );
+    assert_eq!(env.state().await.nonce, 0);
+}
+
+#[tokio::test]
+async fn test_local_nft_round_trip() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let mint = env.mint_nft().await;
+
+    env.round_trip(mint, 2).await;
+
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 0);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert!(!nft_info.is_locked);
+    assert_eq!(nft_info.owner, payer);
+    let state = env.state().await;
+    assert_eq!(state.nonce, 2);
+    assert_eq!(state.outbound_nonce, 1);
+    assert_eq!(state.locked_count, 0);
+
+    // it can go out again with the next nonce
+    env.round_trip(mint, 4).await;
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    let state = env.state().await;
+    assert_eq!(state.nonce, 4);
+    assert_eq!(state.outbound_nonce, 2);
+}
+
+#[tokio::test]
+async fn test_bridged_nft_round_trip() {
+    let mut env = setup().await;
+    let payer = env.payer();
+    let origin_mint = Pubkey::new_unique();
+    let mint = pda::bridged_mint(&origin_mint);
+    env.on_call(MessageType::Transfer, origin_mint, 1).await.unwrap();
+
+    env.round_trip(mint, 3).await;
+
+    assert_eq!(env.token_amount(&payer, &mint).await, 1);
+    assert_eq!(env.token_amount(&pda::nft_program(), &mint).await, 0);
+    let nft_info: NftInfo = env.account(pda::nft_info(&mint)).await;
+    assert!(!nft_info.is_locked);
+    assert!(nft_info.bridged);
+    let state = env.state().await;
+    assert_eq!(state.nonce, 3);
+    assert_eq!(state.locked_count, 0);
+    assert_eq!(state.total_supply, 1);
+}
@@ -1,25 +1,43 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { UniversalNft } from "../target/types/universal_nft";
+import { MockMultisig } from "../target/types/mock_multisig";
+import { NftLaunchpad } from "../target/types/nft_launchpad";
+import { MockGateway } from "../target/types/mock_gateway";
 import { 
   PublicKey, 
   Keypair, 
   SystemProgram,
-  SYSVAR_RENT_PUBKEY 
+  SYSVAR_RENT_PUBKEY,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  TransactionInstruction,
+  AccountMeta,
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   getAssociatedTokenAddress,
+  getAssociatedTokenAddressSync,
   createAssociatedTokenAccount,
-  getAccount
+  createMint,
+  mintTo as mintSplTokens,
+  burn as burnSplTokens,
+  getAccount,
+  getMint,
+  getTokenMetadata,
 } from "@solana/spl-token";
 import { expect } from "chai";
 import { BN } from "bn.js";
+import { createHash } from "crypto";
+import { readFileSync } from "fs";
 
 // metaplex metadata program id
 const METADATA_PROGRAM_ID = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
 
+// mock evm address, recipients are left aligned in 32 bytes
+const evmAddress = Array.from(Buffer.concat([Buffer.alloc(20, 1), Buffer.alloc(12)]));
+
 describe("universal nft", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
@@ -34,6 +52,7 @@ describe("universal nft", () => {
   // program pdas
   let nftProgramPda: PublicKey;
   let nftProgramBump: number;
+  let treasuryPda: PublicKey;
   let gatewayPda: PublicKey;
   
   // nft data
@@ -63,6 +82,11 @@ describe("universal nft", () => {
       [Buffer.from("nft-program")],
       program.programId
     );
+
+    [treasuryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
     
     [nftInfoPda, nftInfoBump] = PublicKey.findProgramAddressSync(
       [Buffer.from("nft-info"), mint.publicKey.toBuffer()],
@@ -87,50 +111,337 @@ describe("universal nft", () => {
   });
 
   describe("initialization", () => {
-    it("initializes the universal nft program", async () => {
-      const tx = await program.methods
-        .initialize(gatewayPda)
+    // the provider wallet deploys and pays, the authority key stays cold
+    const initialize = (authorityKey: PublicKey, authorityMustSign: boolean) =>
+      program.methods
+        .initialize(gatewayPda, authorityMustSign)
         .accounts({
           nftProgram: nftProgramPda,
-          authority: authority.publicKey,
+          treasury: treasuryPda,
+          authority: authorityKey,
+          payer: provider.wallet.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([authority])
         .rpc();
 
+    it("refuses the default pubkey as authority", async () => {
+      try {
+        await initialize(PublicKey.default, false);
+        expect.fail("should have failed with InvalidAuthority");
+      } catch (error) {
+        expect(error.message).to.include("InvalidAuthority");
+      }
+    });
+
+    it("requires the authority signature when asked to", async () => {
+      try {
+        await initialize(authority.publicKey, true);
+        expect.fail("should have failed with AccountNotSigner");
+      } catch (error) {
+        expect(error.message).to.include("AccountNotSigner");
+      }
+      expect(await provider.connection.getAccountInfo(nftProgramPda)).to.be.null;
+    });
+
+    it("initializes the universal nft program", async () => {
+      const tx = await initialize(authority.publicKey, false);
+
       console.log("initialize tx:", tx);
 
       // verify program state
       const programState = await program.account.nftProgramState.fetch(nftProgramPda);
       expect(programState.authority.toString()).to.equal(authority.publicKey.toString());
+      expect(programState.pendingAuthority.toString()).to.equal(PublicKey.default.toString());
+      expect(programState.version).to.equal(18);
       expect(programState.gateway.toString()).to.equal(gatewayPda.toString());
       expect(programState.totalSupply.toString()).to.equal("0");
       expect(programState.nonce.toString()).to.equal("0");
+      expect(programState.outboundNonce.toString()).to.equal("0");
+      expect(programState.mintFeeLamports.toString()).to.equal("0");
+      expect(programState.mintPaused).to.be.false;
+      expect(programState.lockedCount.toNumber()).to.equal(0);
+      expect(programState.sweepRentDestination).to.deep.equal({ treasury: {} });
+      expect(programState.lazyMetadata).to.be.false;
+      expect(programState.defaultSymbol).to.equal("UNFT");
+      expect(programState.inboundStringPolicy).to.deep.equal({ truncate: {} });
+      expect(programState.metadataMode).to.deep.equal({ metaplex: {} });
+    });
+
+    it("reports the crate version through get_version", async () => {
+      const cargoToml = readFileSync("programs/universal_nft/Cargo.toml", "utf8");
+      const crateVersion = cargoToml.match(/^version = "(.+)"$/m)[1];
+
+      const version = await program.methods.getVersion().accounts({}).view();
+      expect(`${version.major}.${version.minor}.${version.patch}`).to.equal(crateVersion);
+      expect(version.messageVersion).to.equal(1);
+    });
+
+    it("registers zetachain as a chain to bridge with", async () => {
+      await program.methods
+        .registerChain(new BN(7001), {
+          name: "ZetaChain Athens",
+          chainKind: { evm: {} },
+          enabled: true,
+          recipientLen: 20,
+          connected: evmAddress,
+        })
+        .accounts({
+          nftProgram: nftProgramPda,
+          authority: authority.publicKey,
+          payer: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const chainInfo = await program.methods.getChainInfo(new BN(7001)).view();
+      expect(chainInfo.name).to.equal("ZetaChain Athens");
+      expect(chainInfo.enabled).to.be.true;
+    });
+
+    it("lets the authority point on_call at another gateway", async () => {
+      const updateGateway = (gateway: PublicKey, signer: Keypair) =>
+        program.methods
+          .updateGateway(gateway)
+          .accounts({ nftProgram: nftProgramPda, authority: signer.publicKey })
+          .signers([signer])
+          .rpc();
+      const localGateway = Keypair.generate().publicKey;
+
+      try {
+        await updateGateway(localGateway, user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+      try {
+        await updateGateway(PublicKey.default, authority);
+        expect.fail("should have failed with InvalidGateway");
+      } catch (error) {
+        expect(error.message).to.include("InvalidGateway");
+      }
+
+      await updateGateway(localGateway, authority);
+      let programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.gateway.toString()).to.equal(localGateway.toString());
+
+      await updateGateway(gatewayPda, authority);
+      programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.gateway.toString()).to.equal(gatewayPda.toString());
     });
   });
 
+  // optional accounts only the pnft escrow paths use
+  const noPnftAccounts = {
+    metadata: null,
+    masterEdition: null,
+    ownerTokenRecord: null,
+    programTokenRecord: null,
+    sysvarInstructions: null,
+    tokenMetadataProgram: null,
+  };
+
+  // optional accounts only transfers to a chain with a token fee use
+  const noFeeTokenAccounts = {
+    feeMint: null,
+    ownerFeeTokenAccount: null,
+    feeVault: null,
+    feeTokenProgram: null,
+  };
+
+  // optional accounts only nfts in the program collection use
+  const noCollectionAccounts = {
+    collection: null,
+    collectionMint: null,
+    collectionMetadata: null,
+    collectionMasterEdition: null,
+  };
+
+  // metaplex pdas a pnft needs
+  const masterEditionPda = (mintKey: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), mintKey.toBuffer(), Buffer.from("edition")],
+      METADATA_PROGRAM_ID
+    )[0];
+  const tokenRecordPda = (mintKey: PublicKey, token: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("metadata"),
+        METADATA_PROGRAM_ID.toBuffer(),
+        mintKey.toBuffer(),
+        Buffer.from("token_record"),
+        token.toBuffer(),
+      ],
+      METADATA_PROGRAM_ID
+    )[0];
+
+  // per wallet local mint counter
+  const ownerStatsPda = (owner: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("owner-stats"), owner.toBuffer()], program.programId)[0];
+
+  // block pda, only exists while the wallet is blocked but always passed
+  const blockedRecipientPda = (recipient: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("blocked-recipient"), recipient.toBuffer()], program.programId)[0];
+
+  // same for a mint blocked from the bridge
+  const blockedMintPda = (mint: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("blocked-mint"), mint.toBuffer()], program.programId)[0];
+
+  // enumerable index slots, a mint takes the slot at the current index count
+  const mintIndexPda = (index: BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("index"), index.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+  const nextMintIndex = async () =>
+    mintIndexPda((await program.account.nftProgramState.fetch(nftProgramPda)).indexCount);
+  const mintIndexOf = async (mintKey: PublicKey) => {
+    const { index } = await program.account.nftInfo.fetch(
+      PublicKey.findProgramAddressSync([Buffer.from("nft-info"), mintKey.toBuffer()], program.programId)[0]
+    );
+    return index === null ? null : mintIndexPda(index);
+  };
+
+  // builds the account set for a fresh mint_nft call
+  // mint_nft / mint_nft_pda take one MintParams, optional fields default to null
+  const mintParams = (
+    name: string,
+    symbol: string,
+    uri: string,
+    recipient: PublicKey,
+    optional: {
+      tokenStandard?: any;
+      creators?: { address: PublicKey; share: number }[] | null;
+      metadataHash?: number[];
+      attributes?: { key: string; value: string }[];
+      royaltyBps?: number | null;
+    } = {}
+  ) => ({
+    name,
+    symbol,
+    uri,
+    recipient,
+    tokenStandard: null,
+    creators: null,
+    metadataHash: null,
+    attributes: null,
+    royaltyBps: null,
+    ...optional,
+  });
+
+  const mintNftAccounts = async (
+    newMint: PublicKey,
+    owner: PublicKey,
+    payer: PublicKey,
+    tokenProgram: PublicKey = TOKEN_PROGRAM_ID
+  ) => ({
+    nftProgram: nftProgramPda,
+    mint: newMint,
+    tokenAccount: getAssociatedTokenAddressSync(newMint, owner, true, tokenProgram),
+    recipient: owner,
+    nftInfo: PublicKey.findProgramAddressSync(
+      [Buffer.from("nft-info"), newMint.toBuffer()],
+      program.programId
+    )[0],
+    metadata: PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), newMint.toBuffer()],
+      METADATA_PROGRAM_ID
+    )[0],
+    treasury: treasuryPda,
+    minterRole: null,
+    ownerStats: ownerStatsPda(owner),
+    mintIndex: await nextMintIndex(),
+    masterEdition: null,
+    tokenRecord: null,
+    sysvarInstructions: null,
+    ...noCollectionAccounts,
+    payer,
+    rent: SYSVAR_RENT_PUBKEY,
+    systemProgram: SystemProgram.programId,
+    tokenProgram,
+    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    tokenMetadataProgram: METADATA_PROGRAM_ID,
+    blockedRecipient: blockedRecipientPda(owner),
+  });
+
+  // decodes the anchor events a confirmed transaction emitted
+  const eventsOf = async (signature: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+    return { slot: tx.slot, events: Array.from(parser.parseLogs(tx.meta.logMessages)) };
+  };
+
+  // bridge events go out through a self cpi (emit_cpi!) so log truncation cant drop
+  // them, they are in the inner instruction data: 8 byte event ix tag then the event
+  const cpiEventsOf = async (signature: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const keys = tx.transaction.message.getAccountKeys({
+      accountKeysFromLookups: tx.meta.loadedAddresses,
+    });
+    const events = tx.meta.innerInstructions
+      .flatMap((inner) => inner.instructions)
+      .filter((ix) => keys.get(ix.programIdIndex).equals(program.programId))
+      .map((ix) => {
+        const data = anchor.utils.bytes.bs58.decode(ix.data);
+        return program.coder.events.decode(anchor.utils.bytes.base64.encode(data.subarray(8)));
+      })
+      .filter((event) => event !== null);
+    return { slot: tx.slot, events };
+  };
+
   describe("nft minting", () => {
     it("mints a new nft with metadata", async () => {
+      const firstIndex = (await program.account.nftProgramState.fetch(nftProgramPda)).indexCount;
       const tx = await program.methods
-        .mintNft(nftName, nftSymbol, nftUri, recipient.publicKey)
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
         .accounts({
           nftProgram: nftProgramPda,
           mint: mint.publicKey,
           tokenAccount: tokenAccount,
+          recipient: recipient.publicKey,
           nftInfo: nftInfoPda,
           metadata: metadataPda,
+          treasury: treasuryPda,
+          minterRole: null,
+          ownerStats: ownerStatsPda(recipient.publicKey),
+          mintIndex: await nextMintIndex(),
+          masterEdition: null,
+          tokenRecord: null,
+          sysvarInstructions: null,
+          ...noCollectionAccounts,
           payer: authority.publicKey,
           rent: SYSVAR_RENT_PUBKEY,
           systemProgram: SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           tokenMetadataProgram: METADATA_PROGRAM_ID,
+          blockedRecipient: blockedRecipientPda(recipient.publicKey),
         })
         .signers([authority, mint])
-        .rpc();
+        .rpc({ commitment: "confirmed" });
 
       console.log("mint nft tx:", tx);
 
+      // indexers read the mint from the event, not the logs
+      const { slot, events } = await cpiEventsOf(tx);
+      const minted = events.find((event) => event.name === "nftMinted");
+      expect(minted).to.not.be.undefined;
+      expect(minted.data.mint.toString()).to.equal(mint.publicKey.toString());
+      expect(minted.data.recipient.toString()).to.equal(recipient.publicKey.toString());
+      expect(minted.data.name).to.equal(nftName);
+      expect(minted.data.symbol).to.equal(nftSymbol);
+      expect(minted.data.uri).to.equal(nftUri);
+      expect(minted.data.tokenId.toString()).to.equal(firstIndex.toString());
+      expect(minted.data.slot.toNumber()).to.equal(slot);
+      expect(minted.data.payer.toString()).to.equal(authority.publicKey.toString());
+      expect(minted.data.feeLamports.toNumber()).to.equal(0);
+
       // verify nft was minted
       const tokenAccountInfo = await getAccount(provider.connection, tokenAccount);
       expect(tokenAccountInfo.amount.toString()).to.equal("1");
@@ -149,213 +460,2994 @@ describe("universal nft", () => {
       const programState = await program.account.nftProgramState.fetch(nftProgramPda);
       expect(programState.totalSupply.toString()).to.equal("1");
     });
-  });
 
-  describe("cross-chain transfer", () => {
-    const destinationChainId = new BN(7001); // zetachain testnet
-    const evmRecipient = Array.from(Buffer.alloc(32, 1)); // mock evm address
-    const nonce = new BN(Date.now());
-
-    it("initiates cross-chain transfer to zetachain", async () => {
-      // create program token account first
-      await createAssociatedTokenAccount(
-        provider.connection,
-        authority,
-        mint.publicKey,
-        nftProgramPda,
-        {},
-        TOKEN_PROGRAM_ID,
-        ASSOCIATED_TOKEN_PROGRAM_ID
-      );
+    it("mints to a pda owned by another program", async () => {
+      // stands in for an escrow or vault of some other protocol, off curve
+      const dummyProgram = Keypair.generate().publicKey;
+      const [vault] = PublicKey.findProgramAddressSync([Buffer.from("vault")], dummyProgram);
+      expect(PublicKey.isOnCurve(vault.toBytes())).to.be.false;
 
-      const tx = await program.methods
-        .transferToZetachain(destinationChainId, evmRecipient, nonce)
-        .accounts({
-          nftProgram: nftProgramPda,
-          nftInfo: nftInfoPda,
-          owner: recipient.publicKey,
-          ownerTokenAccount: tokenAccount,
-          programTokenAccount: programTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([recipient])
+      const vaultMint = Keypair.generate();
+      const accounts = await mintNftAccounts(vaultMint.publicKey, vault, authority.publicKey);
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, vault))
+        .accounts(accounts)
+        .signers([authority, vaultMint])
         .rpc();
 
-      console.log("transfer to zetachain tx:", tx);
-
-      // verify nft was locked (transferred to program)
-      const ownerTokenAccountInfo = await getAccount(provider.connection, tokenAccount);
-      expect(ownerTokenAccountInfo.amount.toString()).to.equal("0");
-
-      const programTokenAccountInfo = await getAccount(provider.connection, programTokenAccount);
-      expect(programTokenAccountInfo.amount.toString()).to.equal("1");
-
-      // verify nft info updated
-      const nftInfo = await program.account.nftInfo.fetch(nftInfoPda);
-      expect(nftInfo.isLocked).to.be.true;
-      expect(Array.from(nftInfo.crossChainRecipient)).to.deep.equal(evmRecipient);
+      const tokenAccountInfo = await getAccount(provider.connection, accounts.tokenAccount);
+      expect(tokenAccountInfo.owner.toString()).to.equal(vault.toString());
+      expect(tokenAccountInfo.amount.toString()).to.equal("1");
 
-      // verify program nonce updated
-      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
-      expect(programState.nonce.toString()).to.equal(nonce.toString());
+      const nftInfo = await program.account.nftInfo.fetch(accounts.nftInfo);
+      expect(nftInfo.owner.toString()).to.equal(vault.toString());
     });
 
-    it("handles cross-chain call from zetachain", async () => {
-      const sender = Array.from(Buffer.alloc(32, 2));
-      const sourceChainId = new BN(7001);
-      const newNonce = nonce.add(new BN(1));
-      
-      // mock cross-chain message
-      const crossChainMessage = {
-        messageType: { transfer: {} },
-        mint: mint.publicKey,
-        recipient: evmRecipient,
-        metadataUri: nftUri,
-        name: nftName,
-        symbol: nftSymbol,
-        nonce: newNonce,
-      };
-      
-      const messageBuffer = Buffer.from(JSON.stringify(crossChainMessage));
+    it("stores the metadata hash given at mint", async () => {
+      const hashedMint = Keypair.generate();
+      const metadataHash = Array.from(createHash("sha256").update('{"name":"test nft"}').digest());
+      const accounts = await mintNftAccounts(hashedMint.publicKey, recipient.publicKey, authority.publicKey);
 
-      const tx = await program.methods
-        .handleCrossChainCall(sender, sourceChainId, Array.from(messageBuffer), newNonce)
-        .accounts({
-          nftProgram: nftProgramPda,
-        })
-        .signers([authority])
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey, { metadataHash }))
+        .accounts(accounts)
+        .signers([authority, hashedMint])
         .rpc();
 
-      console.log("handle cross-chain call tx:", tx);
-
-      // verify nonce updated
-      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
-      expect(programState.nonce.toString()).to.equal(newNonce.toString());
+      const nftInfo = await program.account.nftInfo.fetch(accounts.nftInfo);
+      expect(Array.from(nftInfo.metadataHash)).to.deep.equal(metadataHash);
+      expect(nftInfo.hashMismatch).to.be.false;
     });
 
-    it("unlocks nft after cross-chain return", async () => {
-      const unlockNonce = new BN(Date.now() + 1000);
-
-      const tx = await program.methods
-        .unlockNft(unlockNonce)
-        .accounts({
-          nftProgram: nftProgramPda,
-          nftInfo: nftInfoPda,
-          owner: recipient.publicKey,
-          ownerTokenAccount: tokenAccount,
-          programTokenAccount: programTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .signers([recipient])
-        .rpc();
-
-      console.log("unlock nft tx:", tx);
-
-      // verify nft returned to owner
-      const ownerTokenAccountInfo = await getAccount(provider.connection, tokenAccount);
-      expect(ownerTokenAccountInfo.amount.toString()).to.equal("1");
-
-      const programTokenAccountInfo = await getAccount(provider.connection, programTokenAccount);
-      expect(programTokenAccountInfo.amount.toString()).to.equal("0");
-
-      // verify nft info updated
-      const nftInfo = await program.account.nftInfo.fetch(nftInfoPda);
-      expect(nftInfo.isLocked).to.be.false;
+    it("only the authority can make hash checks strict", async () => {
+      try {
+        await program.methods
+          .setStrictMetadataHash(true)
+          .accounts({ nftProgram: nftProgramPda, authority: user.publicKey })
+          .signers([user])
+          .rpc();
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
 
-      // verify program nonce updated
-      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
-      expect(programState.nonce.toString()).to.equal(unlockNonce.toString());
+      for (const strict of [true, false]) {
+        await program.methods
+          .setStrictMetadataHash(strict)
+          .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+        expect(programState.strictMetadataHash).to.equal(strict);
+      }
     });
-  });
-
-  describe("security tests", () => {
-    it("prevents unauthorized transfers", async () => {
-      const unauthorizedUser = Keypair.generate();
-      await provider.connection.requestAirdrop(unauthorizedUser.publicKey, anchor.web3.LAMPORTS_PER_SOL);
-      await new Promise(resolve => setTimeout(resolve, 500));
 
+    it("rejects the default pubkey as recipient", async () => {
+      const newMint = Keypair.generate();
       try {
         await program.methods
-          .transferToZetachain(new BN(7001), Array.from(Buffer.alloc(32, 1)), new BN(Date.now()))
-          .accounts({
-            nftProgram: nftProgramPda,
-            nftInfo: nftInfoPda,
-            owner: unauthorizedUser.publicKey,
-            ownerTokenAccount: tokenAccount,
-            programTokenAccount: programTokenAccount,
-            tokenProgram: TOKEN_PROGRAM_ID,
-            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([unauthorizedUser])
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, PublicKey.default))
+          .accounts(await mintNftAccounts(newMint.publicKey, PublicKey.default, authority.publicKey))
+          .signers([authority, newMint])
           .rpc();
-        
-        expect.fail("should have failed with unauthorized error");
+        expect.fail("should have failed with InvalidRecipient");
       } catch (error) {
-        expect(error.message).to.include("Unauthorized");
+        expect(error.message).to.include("InvalidRecipient");
       }
     });
+  });
 
-    it("prevents replay attacks with old nonce", async () => {
-      const oldNonce = new BN(1); // old nonce
+  describe("pda mints", () => {
+    // the mint address is a pure function of the mint count before the mint
+    const mintPdaForCount = (count: BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("mint"), count.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    it("mints several nfts at deterministic addresses", async () => {
+      for (let i = 0; i < 3; i++) {
+        const { totalSupply, mintCount } = await program.account.nftProgramState.fetch(nftProgramPda);
+        const expectedMint = mintPdaForCount(mintCount);
 
-      try {
         await program.methods
-          .handleCrossChainCall(
-            Array.from(Buffer.alloc(32, 2)),
-            new BN(7001),
-            Array.from(Buffer.from("test message")),
-            oldNonce
-          )
-          .accounts({
-            nftProgram: nftProgramPda,
-          })
+          .mintNftPda(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+          .accounts(await mintNftAccounts(expectedMint, recipient.publicKey, authority.publicKey))
           .signers([authority])
           .rpc();
-        
-        expect.fail("should have failed with invalid nonce error");
-      } catch (error) {
-        expect(error.message).to.include("InvalidNonce");
+
+        const nftInfo = await program.account.nftInfo.fetch(
+          PublicKey.findProgramAddressSync(
+            [Buffer.from("nft-info"), expectedMint.toBuffer()],
+            program.programId
+          )[0]
+        );
+        expect(nftInfo.mint.toString()).to.equal(expectedMint.toString());
+
+        const tokenAccountInfo = await getAccount(
+          provider.connection,
+          await getAssociatedTokenAddress(expectedMint, recipient.publicKey)
+        );
+        expect(tokenAccountInfo.amount.toString()).to.equal("1");
+
+        const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+        expect(programState.totalSupply.toString()).to.equal(totalSupply.addn(1).toString());
+        expect(programState.mintCount.toString()).to.equal(mintCount.addn(1).toString());
       }
     });
 
-    it("prevents unlocking non-locked nfts", async () => {
+    it("rejects a mint address that does not match the next mint count", async () => {
+      const { mintCount } = await program.account.nftProgramState.fetch(nftProgramPda);
+      const staleMint = mintPdaForCount(mintCount.subn(1));
+
       try {
         await program.methods
-          .unlockNft(new BN(Date.now()))
-          .accounts({
-            nftProgram: nftProgramPda,
-            nftInfo: nftInfoPda,
-            owner: recipient.publicKey,
-            ownerTokenAccount: tokenAccount,
-            programTokenAccount: programTokenAccount,
-            tokenProgram: TOKEN_PROGRAM_ID,
-          })
-          .signers([recipient])
+          .mintNftPda(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+          .accounts(await mintNftAccounts(staleMint, recipient.publicKey, authority.publicKey))
+          .signers([authority])
           .rpc();
-        
-        expect.fail("should have failed with token not locked error");
+
+        expect.fail("should have failed with MintNotProgramDerived");
       } catch (error) {
-        expect(error.message).to.include("TokenNotLocked");
+        expect(error.message).to.include("MintNotProgramDerived");
       }
     });
   });
 
-  describe("edge cases", () => {
-    it("handles invalid metadata inputs", async () => {
-      const longName = "a".repeat(50); // exceeds max length
-      const newMint = Keypair.generate();
+  describe("creators", () => {
+    const creatorMint = Keypair.generate();
+    let creatorMetadata: PublicKey;
 
-      try {
-        await program.methods
-          .mintNft(longName, nftSymbol, nftUri, recipient.publicKey)
-          .accounts({
-            nftProgram: nftProgramPda,
-            mint: newMint.publicKey,
-            tokenAccount: await getAssociatedTokenAddress(newMint.publicKey, recipient.publicKey),
-            nftInfo: PublicKey.findProgramAddressSync(
+    // metaplex metadata is borsh with variable length fields, walk to the creators
+    const readCreators = async (metadataKey: PublicKey) => {
+      const data = (await provider.connection.getAccountInfo(metadataKey)).data;
+      let offset = 1 + 32 + 32; // key, update authority, mint
+      for (let i = 0; i < 3; i++) offset += 4 + data.readUInt32LE(offset); // name, symbol, uri
+      offset += 2; // seller fee basis points
+      if (data[offset++] === 0) return [];
+      const count = data.readUInt32LE(offset);
+      offset += 4;
+      return Array.from({ length: count }, (_, i) => {
+        const start = offset + i * 34;
+        return {
+          address: new PublicKey(data.subarray(start, start + 32)),
+          verified: data[start + 32] === 1,
+          share: data[start + 33],
+        };
+      });
+    };
+
+    it("verifies the program pda when it is a listed creator", async () => {
+      const accounts = await mintNftAccounts(creatorMint.publicKey, recipient.publicKey, authority.publicKey);
+      creatorMetadata = accounts.metadata;
+
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey, { creators: [
+          { address: nftProgramPda, share: 50 },
+          { address: user.publicKey, share: 50 },
+        ] }))
+        .accounts(accounts)
+        .signers([authority, creatorMint])
+        .rpc();
+
+      const creators = await readCreators(creatorMetadata);
+      expect(creators.map((creator) => creator.address.toString())).to.deep.equal([
+        nftProgramPda.toString(),
+        user.publicKey.toString(),
+      ]);
+      expect(creators.map((creator) => creator.verified)).to.deep.equal([true, false]);
+    });
+
+    it("lets an external creator verify through the program", async () => {
+      await program.methods
+        .verifyCreator(creatorMint.publicKey)
+        .accounts({
+          nftInfo: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft-info"), creatorMint.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          metadata: creatorMetadata,
+          creator: user.publicKey,
+          tokenMetadataProgram: METADATA_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+
+      const creators = await readCreators(creatorMetadata);
+      expect(creators.map((creator) => creator.verified)).to.deep.equal([true, true]);
+    });
+
+    it("rejects creator shares that dont add up to 100", async () => {
+      const newMint = Keypair.generate();
+      try {
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey, { creators: [
+            { address: nftProgramPda, share: 60 },
+            { address: user.publicKey, share: 60 },
+          ] }))
+          .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+          .signers([authority, newMint])
+          .rpc();
+        expect.fail("should have failed with InvalidCreators");
+      } catch (error) {
+        expect(error.message).to.include("InvalidCreators");
+      }
+    });
+  });
+
+  describe("uri validation", () => {
+    const setPrefixes = (prefixes: string[], signer: Keypair = authority) =>
+      program.methods
+        .setAllowedUriPrefixes(prefixes)
+        .accounts({ nftProgram: nftProgramPda, authority: signer.publicKey })
+        .signers([signer])
+        .rpc();
+
+    const mintWithUri = async (uri: string) => {
+      const newMint = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, uri, recipient.publicKey))
+        .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, newMint])
+        .rpc();
+      return newMint.publicKey;
+    };
+
+    const expectRejected = async (uri: string, errorName: string) => {
+      try {
+        await mintWithUri(uri);
+        expect.fail(`should have rejected ${JSON.stringify(uri)}`);
+      } catch (error) {
+        expect(error.message).to.include(errorName);
+      }
+    };
+
+    after(async () => {
+      await setPrefixes(["https://", "ipfs://", "ar://"]);
+    });
+
+    it("rejects an empty uri", async () => {
+      await expectRejected("", "EmptyUri");
+      await expectRejected(" \0\0", "EmptyUri");
+    });
+
+    it("rejects uris with a scheme that is not allowed", async () => {
+      await expectRejected("http://test.com/metadata.json", "InvalidUriScheme");
+      await expectRejected("ftp://test.com/metadata.json", "InvalidUriScheme");
+      await expectRejected("data:application/json,{}", "InvalidUriScheme");
+      await expectRejected("test.com/metadata.json", "InvalidUriScheme");
+    });
+
+    it("accepts ipfs and arweave uris and trims trailing nuls and whitespace", async () => {
+      await mintWithUri("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi");
+      const mintKey = await mintWithUri("ar://abc123 \0\0");
+
+      const nftInfo = await program.account.nftInfo.fetch(
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("nft-info"), mintKey.toBuffer()],
+          program.programId
+        )[0]
+      );
+      expect(nftInfo.metadataUri).to.equal("ar://abc123");
+    });
+
+    it("lets the authority update the allowed prefixes", async () => {
+      await setPrefixes(["https://cdn.example.com/"]);
+
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.allowedUriPrefixes).to.deep.equal(["https://cdn.example.com/"]);
+
+      await mintWithUri("https://cdn.example.com/1.json");
+      await expectRejected("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi", "InvalidUriScheme");
+    });
+
+    it("rejects prefix updates from anyone but the authority", async () => {
+      try {
+        await setPrefixes(["http://"], user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("max uri length", () => {
+    const setMaxUriLen = (maxUriLen: number) =>
+      program.methods
+        .setMaxUriLen(maxUriLen)
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+    after(async () => {
+      await setMaxUriLen(200);
+    });
+
+    it("enforces a lowered limit on mint", async () => {
+      await setMaxUriLen(40);
+
+      const newMint = Keypair.generate();
+      try {
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, "https://test.com/" + "a".repeat(40), recipient.publicKey))
+          .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+          .signers([authority, newMint])
+          .rpc();
+
+        expect.fail("should have failed with a metadata error");
+      } catch (error) {
+        expect(error.message).to.include("UriTooLong");
+      }
+    });
+
+    it("rejects a limit of 0 or over the cap", async () => {
+      for (const maxUriLen of [0, 1025]) {
+        try {
+          await setMaxUriLen(maxUriLen);
+          expect.fail("should have failed with InvalidMaxUriLen");
+        } catch (error) {
+          expect(error.message).to.include("InvalidMaxUriLen");
+        }
+      }
+    });
+
+    it("sizes new nft info accounts from the configured limit", async () => {
+      await setMaxUriLen(40);
+      const smallMint = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(smallMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, smallMint])
+        .rpc();
+
+      await setMaxUriLen(200);
+      const largeMint = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(largeMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, largeMint])
+        .rpc();
+
+      const infoSize = async (mintKey: PublicKey) =>
+        (
+          await provider.connection.getAccountInfo(
+            PublicKey.findProgramAddressSync([Buffer.from("nft-info"), mintKey.toBuffer()], program.programId)[0]
+          )
+        ).data.length;
+      expect((await infoSize(largeMint.publicKey)) - (await infoSize(smallMint.publicKey))).to.equal(160);
+    });
+
+    it("still caps the metaplex copy of the uri at 200 bytes", async () => {
+      await setMaxUriLen(400);
+
+      const newMint = Keypair.generate();
+      try {
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, "https://arweave.net/" + "a".repeat(250), recipient.publicKey))
+          .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+          .signers([authority, newMint])
+          .rpc();
+
+        expect.fail("should have failed with a metadata error");
+      } catch (error) {
+        expect(error.message).to.include("UriTooLong");
+      }
+    });
+  });
+
+  describe("default symbol", () => {
+    it("lets the authority change the inbound default symbol", async () => {
+      await program.methods
+        .setDefaultSymbol("ZETA")
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+      let programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.defaultSymbol).to.equal("ZETA");
+
+      await program.methods
+        .setDefaultSymbol("UNFT")
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+      programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.defaultSymbol).to.equal("UNFT");
+    });
+
+    it("rejects an empty or too long default symbol", async () => {
+      for (const symbol of ["", "SYMBOLTOOLONG"]) {
+        try {
+          await program.methods
+            .setDefaultSymbol(symbol)
+            .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+            .signers([authority])
+            .rpc();
+
+          expect.fail("should have failed with InvalidDefaultSymbol");
+        } catch (error) {
+          expect(error.message).to.include("InvalidDefaultSymbol");
+        }
+      }
+    });
+
+    it("switches the inbound string policy", async () => {
+      for (const policy of [{ reject: {} }, { truncate: {} }]) {
+        await program.methods
+          .setInboundStringPolicy(policy)
+          .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+        expect(programState.inboundStringPolicy).to.deep.equal(policy);
+      }
+
+      try {
+        await program.methods
+          .setInboundStringPolicy({ reject: {} })
+          .accounts({ nftProgram: nftProgramPda, authority: user.publicKey })
+          .signers([user])
+          .rpc();
+        expect.fail("should have failed with Unauthorized");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("only the authority can set the default symbol", async () => {
+      try {
+        await program.methods
+          .setDefaultSymbol("HACK")
+          .accounts({ nftProgram: nftProgramPda, authority: user.publicKey })
+          .signers([user])
+          .rpc();
+
+        expect.fail("should have failed with Unauthorized");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("mint fees", () => {
+    const mintFee = new BN(10_000_000); // 0.01 sol
+
+    after(async () => {
+      // reset so the other suites mint for free
+      await program.methods
+        .setMintFee(new BN(0))
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("mints without touching the treasury when the fee is zero", async () => {
+      const newMint = Keypair.generate();
+      const treasuryBefore = await provider.connection.getBalance(treasuryPda);
+
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, newMint])
+        .rpc();
+
+      const treasuryAfter = await provider.connection.getBalance(treasuryPda);
+      expect(treasuryAfter).to.equal(treasuryBefore);
+    });
+
+    it("charges the configured fee into the treasury", async () => {
+      await program.methods
+        .setMintFee(mintFee)
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+      const newMint = Keypair.generate();
+      const treasuryBefore = await provider.connection.getBalance(treasuryPda);
+
+      const tx = await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, newMint])
+        .rpc({ commitment: "confirmed" });
+
+      const treasuryAfter = await provider.connection.getBalance(treasuryPda);
+      expect(treasuryAfter - treasuryBefore).to.equal(mintFee.toNumber());
+
+      const { events } = await cpiEventsOf(tx);
+      const minted = events.find((event) => event.name === "nftMinted");
+      expect(minted.data.feeLamports.toString()).to.equal(mintFee.toString());
+      expect(minted.data.payer.toString()).to.equal(authority.publicKey.toString());
+    });
+
+    it("rejects a payer that cannot cover the fee", async () => {
+      await program.methods
+        .setMintFee(new BN(5 * anchor.web3.LAMPORTS_PER_SOL))
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+      const newMint = Keypair.generate();
+      try {
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, user.publicKey))
+          .accounts(await mintNftAccounts(newMint.publicKey, user.publicKey, user.publicKey))
+          .signers([user, newMint])
+          .rpc();
+
+        expect.fail("should have failed with insufficient funds error");
+      } catch (error) {
+        expect(error.message).to.include("InsufficientFunds");
+      }
+    });
+
+    it("only lets the authority change the fee", async () => {
+      try {
+        await program.methods
+          .setMintFee(new BN(1))
+          .accounts({ nftProgram: nftProgramPda, authority: user.publicKey })
+          .signers([user])
+          .rpc();
+
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("minter roles", () => {
+    const minterRolePda = (minter: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("minter"), minter.toBuffer()], program.programId)[0];
+
+    const setPublicMint = (publicMint: boolean) =>
+      program.methods
+        .setPublicMint(publicMint)
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+    after(async () => {
+      await setPublicMint(true);
+    });
+
+    it("lets anyone mint while public minting is on", async () => {
+      const newMint = Keypair.generate();
+
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, user.publicKey))
+        .accounts(await mintNftAccounts(newMint.publicKey, user.publicKey, user.publicKey))
+        .signers([user, newMint])
+        .rpc();
+
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.publicMint).to.be.true;
+    });
+
+    it("lets a granted minter mint while public minting is off", async () => {
+      await setPublicMint(false);
+      await program.methods
+        .grantMinter(user.publicKey)
+        .accounts({
+          nftProgram: nftProgramPda,
+          minterRole: minterRolePda(user.publicKey),
+          authority: authority.publicKey,
+          payer: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const newMint = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, user.publicKey))
+        .accounts({
+          ...(await mintNftAccounts(newMint.publicKey, user.publicKey, user.publicKey)),
+          minterRole: minterRolePda(user.publicKey),
+        })
+        .signers([user, newMint])
+        .rpc();
+
+      const role = await program.account.minterRole.fetch(minterRolePda(user.publicKey));
+      expect(role.minter.toString()).to.equal(user.publicKey.toString());
+    });
+
+    it("rejects mints without the role while public minting is off", async () => {
+      await program.methods
+        .revokeMinter(user.publicKey)
+        .accounts({
+          nftProgram: nftProgramPda,
+          minterRole: minterRolePda(user.publicKey),
+          authority: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+
+      const newMint = Keypair.generate();
+      try {
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, user.publicKey))
+          .accounts(await mintNftAccounts(newMint.publicKey, user.publicKey, user.publicKey))
+          .signers([user, newMint])
+          .rpc();
+
+        expect.fail("should have failed with not minter error");
+      } catch (error) {
+        expect(error.message).to.include("NotMinter");
+      }
+
+      // the authority is always a minter
+      const authorityMint = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, user.publicKey))
+        .accounts(await mintNftAccounts(authorityMint.publicKey, user.publicKey, authority.publicKey))
+        .signers([authority, authorityMint])
+        .rpc();
+    });
+  });
+
+  describe("mint pause", () => {
+    const bridgedMint = Keypair.generate();
+    const setMintPaused = (mintPaused: boolean, signer: Keypair = authority) =>
+      program.methods
+        .setMintPaused(mintPaused)
+        .accounts({ nftProgram: nftProgramPda, authority: signer.publicKey })
+        .signers([signer])
+        .rpc();
+
+    before(async () => {
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(bridgedMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, bridgedMint])
+        .rpc();
+    });
+
+    after(async () => {
+      await setMintPaused(false);
+    });
+
+    it("only the authority can pause minting", async () => {
+      try {
+        await setMintPaused(true, user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("rejects local mints while paused", async () => {
+      await setMintPaused(true);
+      expect((await program.account.nftProgramState.fetch(nftProgramPda)).mintPaused).to.be.true;
+
+      const newMint = Keypair.generate();
+      try {
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+          .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+          .signers([authority, newMint])
+          .rpc();
+        expect.fail("should have failed with MintPaused");
+      } catch (error) {
+        expect(error.message).to.include("MintPaused");
+      }
+
+      const { mintCount } = await program.account.nftProgramState.fetch(nftProgramPda);
+      const [pdaMint] = PublicKey.findProgramAddressSync(
+        [Buffer.from("mint"), mintCount.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      try {
+        await program.methods
+          .mintNftPda(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+          .accounts(await mintNftAccounts(pdaMint, recipient.publicKey, authority.publicKey))
+          .signers([authority])
+          .rpc();
+        expect.fail("should have failed with MintPaused");
+      } catch (error) {
+        expect(error.message).to.include("MintPaused");
+      }
+    });
+
+    it("keeps bridging while paused", async () => {
+      const mint = bridgedMint.publicKey;
+      const nftInfo = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft-info"), mint.toBuffer()],
+        program.programId
+      )[0];
+      const bridgeAccounts = {
+        nftProgram: nftProgramPda,
+        nftInfo,
+        mint,
+        owner: recipient.publicKey,
+        ownerTokenAccount: await getAssociatedTokenAddress(mint, recipient.publicKey),
+        programTokenAccount: await getAssociatedTokenAddress(mint, nftProgramPda, true),
+        ...noPnftAccounts,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+
+      await program.methods
+        .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+        .accounts({
+          ...bridgeAccounts,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([recipient])
+        .rpc();
+      expect((await program.account.nftInfo.fetch(nftInfo)).isLocked).to.be.true;
+
+      await program.methods
+        .unlockNft()
+        .accounts({ ...bridgeAccounts, authority: authority.publicKey, systemProgram: null, associatedTokenProgram: null })
+        .signers([authority])
+        .rpc();
+      expect((await program.account.nftInfo.fetch(nftInfo)).isLocked).to.be.false;
+    });
+
+    it("mints again once unpaused", async () => {
+      await setMintPaused(false);
+
+      const newMint = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, newMint])
+        .rpc();
+    });
+  });
+
+  describe("treasury", () => {
+    const destination = Keypair.generate();
+
+    // lamports the authority can actually pull out
+    const availableInTreasury = async () => {
+      const info = await provider.connection.getAccountInfo(treasuryPda);
+      const rentExempt = await provider.connection.getMinimumBalanceForRentExemption(info.data.length);
+      return info.lamports - rentExempt;
+    };
+
+    it("withdraws part of the collected fees", async () => {
+      const available = await availableInTreasury();
+      expect(available).to.be.greaterThan(0);
+      const amount = Math.floor(available / 2);
+
+      await program.methods
+        .withdrawTreasury(new BN(amount))
+        .accounts({
+          nftProgram: nftProgramPda,
+          treasury: treasuryPda,
+          destination: destination.publicKey,
+          authority: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+
+      expect(await provider.connection.getBalance(destination.publicKey)).to.equal(amount);
+      expect(await availableInTreasury()).to.equal(available - amount);
+    });
+
+    it("withdraws everything above the rent exempt minimum", async () => {
+      const available = await availableInTreasury();
+
+      await program.methods
+        .withdrawTreasury(new BN(available))
+        .accounts({
+          nftProgram: nftProgramPda,
+          treasury: treasuryPda,
+          destination: destination.publicKey,
+          authority: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+
+      expect(await availableInTreasury()).to.equal(0);
+
+      try {
+        await program.methods
+          .withdrawTreasury(new BN(1))
+          .accounts({
+            nftProgram: nftProgramPda,
+            treasury: treasuryPda,
+            destination: destination.publicKey,
+            authority: authority.publicKey,
+          })
+          .signers([authority])
+          .rpc();
+
+        expect.fail("should have failed with insufficient treasury error");
+      } catch (error) {
+        expect(error.message).to.include("InsufficientTreasury");
+      }
+    });
+
+    it("rejects withdrawals from anyone but the authority", async () => {
+      try {
+        await program.methods
+          .withdrawTreasury(new BN(0))
+          .accounts({
+            nftProgram: nftProgramPda,
+            treasury: treasuryPda,
+            destination: user.publicKey,
+            authority: user.publicKey,
+          })
+          .signers([user])
+          .rpc();
+
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("programmable nfts", () => {
+    const pnftMint = Keypair.generate();
+    let ownerToken: PublicKey;
+    let escrowToken: PublicKey;
+    let pnftInfoPda: PublicKey;
+
+    // everything token metadata needs to move the pnft between owner and escrow
+    const pnftEscrowAccounts = () => ({
+      mint: pnftMint.publicKey,
+      metadata: PublicKey.findProgramAddressSync(
+        [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), pnftMint.publicKey.toBuffer()],
+        METADATA_PROGRAM_ID
+      )[0],
+      masterEdition: masterEditionPda(pnftMint.publicKey),
+      ownerTokenRecord: tokenRecordPda(pnftMint.publicKey, ownerToken),
+      programTokenRecord: tokenRecordPda(pnftMint.publicKey, escrowToken),
+      sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      tokenMetadataProgram: METADATA_PROGRAM_ID,
+    });
+
+    before(async () => {
+      ownerToken = await getAssociatedTokenAddress(pnftMint.publicKey, recipient.publicKey);
+      escrowToken = await getAssociatedTokenAddress(pnftMint.publicKey, nftProgramPda, true);
+      [pnftInfoPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft-info"), pnftMint.publicKey.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("mints a pnft with a token record and a frozen token account", async () => {
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey, { tokenStandard: { programmableNonFungible: {} } }))
+        .accounts({
+          ...(await mintNftAccounts(pnftMint.publicKey, recipient.publicKey, authority.publicKey)),
+          masterEdition: masterEditionPda(pnftMint.publicKey),
+          tokenRecord: tokenRecordPda(pnftMint.publicKey, ownerToken),
+          sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([authority, pnftMint])
+        .rpc();
+
+      const tokenAccountInfo = await getAccount(provider.connection, ownerToken);
+      expect(tokenAccountInfo.amount.toString()).to.equal("1");
+      expect(tokenAccountInfo.isFrozen).to.be.true;
+
+      const tokenRecord = await provider.connection.getAccountInfo(tokenRecordPda(pnftMint.publicKey, ownerToken));
+      expect(tokenRecord).to.not.be.null;
+
+      const nftInfo = await program.account.nftInfo.fetch(pnftInfoPda);
+      expect(nftInfo.tokenStandard).to.deep.equal({ programmableNonFungible: {} });
+    });
+
+    it("locks and unlocks a pnft through token metadata transfers", async () => {
+      await program.methods
+        .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: pnftInfoPda,
+          owner: recipient.publicKey,
+          ownerTokenAccount: ownerToken,
+          programTokenAccount: escrowToken,
+          ...pnftEscrowAccounts(),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([recipient])
+        .rpc();
+
+      expect((await getAccount(provider.connection, escrowToken)).amount.toString()).to.equal("1");
+      expect((await program.account.nftInfo.fetch(pnftInfoPda)).isLocked).to.be.true;
+
+      await program.methods
+        .unlockNft()
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: pnftInfoPda,
+          owner: recipient.publicKey,
+          authority: authority.publicKey,
+          ownerTokenAccount: ownerToken,
+          programTokenAccount: escrowToken,
+          ...pnftEscrowAccounts(),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+      const ownerTokenInfo = await getAccount(provider.connection, ownerToken);
+      expect(ownerTokenInfo.amount.toString()).to.equal("1");
+      expect(ownerTokenInfo.isFrozen).to.be.true;
+      expect((await program.account.nftInfo.fetch(pnftInfoPda)).isLocked).to.be.false;
+    });
+
+    it("fails clearly when the pnft accounts are left out", async () => {
+      try {
+        await program.methods
+          .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+          .accounts({
+            nftProgram: nftProgramPda,
+            nftInfo: pnftInfoPda,
+            mint: pnftMint.publicKey,
+            owner: recipient.publicKey,
+            ownerTokenAccount: ownerToken,
+            programTokenAccount: escrowToken,
+            ...noPnftAccounts,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            approvedCollection: null,
+            ...noFeeTokenAccounts,
+          })
+          .signers([recipient])
+          .rpc();
+
+        expect.fail("should have failed with missing pnft account error");
+      } catch (error) {
+        expect(error.message).to.include("MissingPnftAccount");
+      }
+    });
+  });
+
+  describe("cross-chain transfer", () => {
+    const destinationChainId = new BN(7001); // zetachain testnet
+    const evmRecipient = evmAddress;
+
+    it("local mints go out without a royalty", async () => {
+      const nftInfo = await program.account.nftInfo.fetch(nftInfoPda);
+      expect(nftInfo.royaltyBps).to.equal(0);
+      expect(nftInfo.royaltyRecipient).to.be.null;
+    });
+
+    it("sets the default royalty for inbound nfts without one", async () => {
+      await program.methods
+        .setDefaultRoyalty(500, PublicKey.default)
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+      let programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.defaultRoyaltyBps).to.equal(500);
+
+      try {
+        await program.methods
+          .setDefaultRoyalty(10_001, PublicKey.default)
+          .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        expect.fail("should have failed with InvalidRoyaltyBps");
+      } catch (error) {
+        expect(error.message).to.include("InvalidRoyaltyBps");
+      }
+
+      try {
+        await program.methods
+          .setDefaultRoyalty(100, PublicKey.default)
+          .accounts({ nftProgram: nftProgramPda, authority: user.publicKey })
+          .signers([user])
+          .rpc();
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      await program.methods
+        .setDefaultRoyalty(0, PublicKey.default)
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+      programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.defaultRoyaltyBps).to.equal(0);
+    });
+
+    it("local mints fall back to the default royalty", async () => {
+      const setDefaultRoyalty = (royaltyBps: number, royaltyRecipient: PublicKey) =>
+        program.methods
+          .setDefaultRoyalty(royaltyBps, royaltyRecipient)
+          .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+      const mintWithRoyalty = async (royaltyBps: number | null, creators = null) => {
+        const royaltyMint = Keypair.generate();
+        const accounts = await mintNftAccounts(royaltyMint.publicKey, recipient.publicKey, authority.publicKey);
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey, { creators, royaltyBps }))
+          .accounts(accounts)
+          .signers([authority, royaltyMint])
+          .rpc();
+        return program.account.nftInfo.fetch(accounts.nftInfo);
+      };
+
+      const listener = await program.addEventListener("defaultRoyaltySet", (event) => {
+        expect(event.royaltyBps).to.equal(300);
+        expect(event.royaltyRecipient.toString()).to.equal(user.publicKey.toString());
+      });
+      await setDefaultRoyalty(300, user.publicKey);
+      await program.removeEventListener(listener);
+
+      let nftInfo = await mintWithRoyalty(null);
+      expect(nftInfo.royaltyBps).to.equal(300);
+      expect(nftInfo.royaltyRecipient.toString()).to.equal(user.publicKey.toString());
+
+      nftInfo = await mintWithRoyalty(150);
+      expect(nftInfo.royaltyBps).to.equal(150);
+
+      // explicit creators already say who gets paid
+      nftInfo = await mintWithRoyalty(null, [{ address: user.publicKey, share: 100 }]);
+      expect(nftInfo.royaltyBps).to.equal(300);
+      expect(nftInfo.royaltyRecipient).to.be.null;
+
+      await setDefaultRoyalty(0, PublicKey.default);
+      nftInfo = await mintWithRoyalty(null);
+      expect(nftInfo.royaltyBps).to.equal(0);
+      expect(nftInfo.royaltyRecipient).to.be.null;
+    });
+
+    it("only the authority can switch inbound royalty clamping", async () => {
+      try {
+        await program.methods
+          .setClampRoyaltyBps(true)
+          .accounts({ nftProgram: nftProgramPda, authority: user.publicKey })
+          .signers([user])
+          .rpc();
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      for (const clamp of [true, false]) {
+        await program.methods
+          .setClampRoyaltyBps(clamp)
+          .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+        expect(programState.clampRoyaltyBps).to.equal(clamp);
+      }
+    });
+
+    it("initiates cross-chain transfer to zetachain", async () => {
+      // create program token account first
+      await createAssociatedTokenAccount(
+        provider.connection,
+        authority,
+        mint.publicKey,
+        nftProgramPda,
+        {},
+        TOKEN_PROGRAM_ID,
+        ASSOCIATED_TOKEN_PROGRAM_ID
+      );
+
+      const { outboundNonce } = await program.account.nftProgramState.fetch(nftProgramPda);
+      const tx = await program.methods
+        .transferToZetachain({ destinationChainId: destinationChainId, recipient: evmRecipient, nonce: new BN(0) })
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: nftInfoPda,
+          mint: mint.publicKey,
+          owner: recipient.publicKey,
+          ownerTokenAccount: tokenAccount,
+          programTokenAccount: programTokenAccount,
+          ...noPnftAccounts,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([recipient])
+        .rpc();
+
+      console.log("transfer to zetachain tx:", tx);
+
+      // verify nft was locked (transferred to program)
+      const ownerTokenAccountInfo = await getAccount(provider.connection, tokenAccount);
+      expect(ownerTokenAccountInfo.amount.toString()).to.equal("0");
+
+      const programTokenAccountInfo = await getAccount(provider.connection, programTokenAccount);
+      expect(programTokenAccountInfo.amount.toString()).to.equal("1");
+
+      // verify nft info updated
+      const nftInfo = await program.account.nftInfo.fetch(nftInfoPda);
+      expect(nftInfo.isLocked).to.be.true;
+      expect(Array.from(nftInfo.crossChainRecipient)).to.deep.equal(evmRecipient);
+
+      // the program numbered the transfer, inbound nonces are left alone
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.outboundNonce.toString()).to.equal(outboundNonce.addn(1).toString());
+    });
+
+    it("handles cross-chain call from zetachain", async () => {
+      const sender = Array.from(Buffer.alloc(32, 2));
+      const sourceChainId = new BN(7001);
+      const { nonce } = await program.account.nftProgramState.fetch(nftProgramPda);
+      const newNonce = nonce.addn(1);
+      
+      // mock cross-chain message
+      const crossChainMessage = {
+        messageType: { transfer: {} },
+        mint: mint.publicKey,
+        recipient: evmRecipient,
+        metadataUri: nftUri,
+        name: nftName,
+        symbol: nftSymbol,
+        nonce: newNonce,
+      };
+      
+      const messageBuffer = Buffer.from(JSON.stringify(crossChainMessage));
+
+      const tx = await program.methods
+        .handleCrossChainCall(sender, sourceChainId, Array.from(messageBuffer), newNonce)
+        .accounts({
+          nftProgram: nftProgramPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("handle cross-chain call tx:", tx);
+
+      // verify nonce updated
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.nonce.toString()).to.equal(newNonce.toString());
+    });
+
+    it("lets only the authority force unlock a locked nft", async () => {
+      const { nonce } = await program.account.nftProgramState.fetch(nftProgramPda);
+      // the outbound nonce the transfer locked it with
+      const { lockNonce } = await program.account.nftInfo.fetch(nftInfoPda);
+      expect(lockNonce.toNumber()).to.be.greaterThan(0);
+
+      // the owner cant take it out of escrow, only the authority can force it
+      try {
+        await program.methods
+          .unlockNft()
+          .accounts({
+            nftProgram: nftProgramPda,
+            nftInfo: nftInfoPda,
+            mint: mint.publicKey,
+            owner: recipient.publicKey,
+            authority: recipient.publicKey,
+            ownerTokenAccount: tokenAccount,
+            programTokenAccount: programTokenAccount,
+            ...noPnftAccounts,
+            systemProgram: null,
+            associatedTokenProgram: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([recipient])
+          .rpc();
+        expect.fail("should have failed with unauthorized");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      const tx = await program.methods
+        .unlockNft()
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: nftInfoPda,
+          mint: mint.publicKey,
+          owner: recipient.publicKey,
+          authority: authority.publicKey,
+          ownerTokenAccount: tokenAccount,
+          programTokenAccount: programTokenAccount,
+          ...noPnftAccounts,
+          systemProgram: null,
+          associatedTokenProgram: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+      console.log("unlock nft tx:", tx);
+
+      const { slot, events } = await cpiEventsOf(tx);
+      const unlocked = events.find((event) => event.name === "nftUnlocked");
+      expect(unlocked.data.mint.toString()).to.equal(mint.publicKey.toString());
+      expect(unlocked.data.owner.toString()).to.equal(recipient.publicKey.toString());
+      expect(unlocked.data.reason).to.deep.equal({ forceUnlock: {} });
+      expect(unlocked.data.nonce.toString()).to.equal(lockNonce.toString());
+      expect(unlocked.data.slot.toNumber()).to.equal(slot);
+
+      // verify nft returned to owner
+      const ownerTokenAccountInfo = await getAccount(provider.connection, tokenAccount);
+      expect(ownerTokenAccountInfo.amount.toString()).to.equal("1");
+
+      const programTokenAccountInfo = await getAccount(provider.connection, programTokenAccount);
+      expect(programTokenAccountInfo.amount.toString()).to.equal("0");
+
+      // verify nft info updated
+      const nftInfo = await program.account.nftInfo.fetch(nftInfoPda);
+      expect(nftInfo.isLocked).to.be.false;
+
+      // an unlock doesnt take a nonce
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.nonce.toString()).to.equal(nonce.toString());
+    });
+  });
+
+  describe("enumerable index", () => {
+    it("looks up mints by index", async () => {
+      const { indexCount: start } = await program.account.nftProgramState.fetch(nftProgramPda);
+      const minted: PublicKey[] = [];
+
+      for (let i = 0; i < 3; i++) {
+        const newMint = Keypair.generate();
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+          .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+          .signers([authority, newMint])
+          .rpc();
+        minted.push(newMint.publicKey);
+      }
+
+      const { indexCount } = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(indexCount.toString()).to.equal(start.addn(3).toString());
+
+      for (let i = 0; i < 3; i++) {
+        const index = start.addn(i);
+        const mintAtIndex = await program.methods
+          .getMintByIndex(index)
+          .accounts({ mintIndex: mintIndexPda(index) })
+          .view();
+        expect(mintAtIndex.toString()).to.equal(minted[i].toString());
+      }
+    });
+
+    it("has no slot past the index count", async () => {
+      const { indexCount } = await program.account.nftProgramState.fetch(nftProgramPda);
+
+      try {
+        await program.methods
+          .getMintByIndex(indexCount)
+          .accounts({ mintIndex: mintIndexPda(indexCount) })
+          .view();
+
+        expect.fail("should have failed with AccountNotInitialized");
+      } catch (error) {
+        expect(error.message).to.include("AccountNotInitialized");
+      }
+    });
+  });
+
+  describe("burning", () => {
+    const burnMint = Keypair.generate();
+    let burnToken: PublicKey;
+    let burnEscrow: PublicKey;
+    let burnInfoPda: PublicKey;
+    let burnIndexPda: PublicKey;
+
+    const burnAccounts = (signer: PublicKey = recipient.publicKey) => ({
+      nftProgram: nftProgramPda,
+      nftInfo: burnInfoPda,
+      owner: signer,
+      mint: burnMint.publicKey,
+      ownerTokenAccount: burnToken,
+      ...noPnftAccounts,
+      ...noCollectionAccounts,
+      bridgedCollection: null,
+      bridgedCollectionMint: null,
+      ownerStats: null,
+      mintIndex: burnIndexPda,
+      systemProgram: SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+
+    before(async () => {
+      burnToken = await getAssociatedTokenAddress(burnMint.publicKey, recipient.publicKey);
+      burnEscrow = await getAssociatedTokenAddress(burnMint.publicKey, nftProgramPda, true);
+      [burnInfoPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft-info"), burnMint.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(burnMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, burnMint])
+        .rpc();
+      burnIndexPda = await mintIndexOf(burnMint.publicKey);
+    });
+
+    it("cannot burn a locked nft", async () => {
+      await program.methods
+        .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: burnInfoPda,
+          mint: burnMint.publicKey,
+          owner: recipient.publicKey,
+          ownerTokenAccount: burnToken,
+          programTokenAccount: burnEscrow,
+          ...noPnftAccounts,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([recipient])
+        .rpc();
+
+      try {
+        await program.methods
+          .burnNft(burnMint.publicKey)
+          .accounts(burnAccounts())
+          .signers([recipient])
+          .rpc();
+
+        expect.fail("should have failed with TokenLocked");
+      } catch (error) {
+        expect(error.message).to.include("TokenLocked");
+      }
+
+      await program.methods
+        .unlockNft()
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: burnInfoPda,
+          mint: burnMint.publicKey,
+          owner: recipient.publicKey,
+          authority: authority.publicKey,
+          ownerTokenAccount: burnToken,
+          programTokenAccount: burnEscrow,
+          ...noPnftAccounts,
+          systemProgram: null,
+          associatedTokenProgram: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("only the holder can burn", async () => {
+      try {
+        await program.methods
+          .burnNft(burnMint.publicKey)
+          .accounts(burnAccounts(user.publicKey))
+          .signers([user])
+          .rpc();
+
+        expect.fail("should have failed with a token owner error");
+      } catch (error) {
+        expect(error.message).to.include("ConstraintTokenOwner");
+      }
+    });
+
+    it("burns the nft and takes it out of the supply", async () => {
+      const before = await program.account.nftProgramState.fetch(nftProgramPda);
+
+      await program.methods
+        .burnNft(burnMint.publicKey)
+        .accounts(burnAccounts())
+        .signers([recipient])
+        .rpc();
+
+      const after = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(after.totalSupply.toString()).to.equal(before.totalSupply.subn(1).toString());
+      // pda mint seeds must not repeat, so the mint count stays put
+      expect(after.mintCount.toString()).to.equal(before.mintCount.toString());
+
+      expect(await provider.connection.getAccountInfo(burnInfoPda)).to.be.null;
+      expect(await provider.connection.getAccountInfo(burnToken)).to.be.null;
+
+      const mintInfo = await getMint(provider.connection, burnMint.publicKey);
+      expect(mintInfo.supply.toString()).to.equal("0");
+
+      // the index slot stays but no longer points at the burned mint
+      const slot = await program.account.mintIndex.fetch(burnIndexPda);
+      expect(slot.mint.toString()).to.equal(PublicKey.default.toString());
+    });
+  });
+
+  describe("orphaned nft info sweep", () => {
+    const nftInfoOf = (mintKey: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("nft-info"), mintKey.toBuffer()], program.programId)[0];
+
+    const sweep = async (mintKey: PublicKey) =>
+      program.methods
+        .sweepOrphanedInfo(mintKey)
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: nftInfoOf(mintKey),
+          mint: mintKey,
+          mintIndex: await mintIndexOf(mintKey),
+          treasury: treasuryPda,
+          caller: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+
+    // a plain nft the holder then burns with the token program, never telling us
+    const mintOrphan = async (burnIt: boolean) => {
+      const orphan = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(orphan.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, orphan])
+        .rpc();
+      if (burnIt) {
+        await burnSplTokens(
+          provider.connection,
+          authority,
+          getAssociatedTokenAddressSync(orphan.publicKey, recipient.publicKey),
+          orphan.publicKey,
+          recipient,
+          1
+        );
+      }
+      return orphan.publicKey;
+    };
+
+    const lockAccounts = (mintKey: PublicKey) => ({
+      nftProgram: nftProgramPda,
+      nftInfo: nftInfoOf(mintKey),
+      mint: mintKey,
+      owner: recipient.publicKey,
+      ownerTokenAccount: getAssociatedTokenAddressSync(mintKey, recipient.publicKey),
+      programTokenAccount: getAssociatedTokenAddressSync(mintKey, nftProgramPda, true),
+      ...noPnftAccounts,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+
+    it("refuses a mint that still has supply", async () => {
+      const live = await mintOrphan(false);
+
+      try {
+        await sweep(live);
+        expect.fail("should have failed with MintHasSupply");
+      } catch (error) {
+        expect(error.message).to.include("MintHasSupply");
+      }
+      expect(await provider.connection.getAccountInfo(nftInfoOf(live))).to.not.be.null;
+    });
+
+    it("refuses an nft locked for the bridge", async () => {
+      const locked = await mintOrphan(false);
+      await program.methods
+        .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+        .accounts({
+          ...lockAccounts(locked),
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([recipient])
+        .rpc();
+
+      try {
+        await sweep(locked);
+        expect.fail("should have failed with TokenLocked");
+      } catch (error) {
+        expect(error.message).to.include("TokenLocked");
+      }
+      expect((await program.account.nftInfo.fetch(nftInfoOf(locked))).isLocked).to.be.true;
+
+      await program.methods
+        .unlockNft()
+        .accounts({
+          ...lockAccounts(locked),
+          authority: authority.publicKey,
+          systemProgram: null,
+          associatedTokenProgram: null,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("sweeps a burned nft into the treasury", async () => {
+      const orphan = await mintOrphan(true);
+      const indexPda = await mintIndexOf(orphan);
+      const rent =
+        (await provider.connection.getBalance(nftInfoOf(orphan))) +
+        (await provider.connection.getBalance(indexPda));
+      const before = await program.account.nftProgramState.fetch(nftProgramPda);
+      const treasuryBefore = await provider.connection.getBalance(treasuryPda);
+
+      await sweep(orphan);
+
+      expect(await provider.connection.getAccountInfo(nftInfoOf(orphan))).to.be.null;
+      expect(await provider.connection.getAccountInfo(indexPda)).to.be.null;
+      expect(await provider.connection.getBalance(treasuryPda)).to.equal(treasuryBefore + rent);
+      const after = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(after.totalSupply.toString()).to.equal(before.totalSupply.subn(1).toString());
+    });
+
+    it("pays the caller when configured to", async () => {
+      const orphan = await mintOrphan(true);
+      const rent =
+        (await provider.connection.getBalance(nftInfoOf(orphan))) +
+        (await provider.connection.getBalance(await mintIndexOf(orphan)));
+      await program.methods
+        .setSweepRentDestination({ caller: {} })
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+      const callerBefore = await provider.connection.getBalance(user.publicKey);
+
+      await sweep(orphan);
+
+      // the provider wallet pays the fee, the caller only signs
+      expect(await provider.connection.getBalance(user.publicKey)).to.equal(callerBefore + rent);
+
+      await program.methods
+        .setSweepRentDestination({ treasury: {} })
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+    });
+  });
+
+  describe("wallet mint limit", () => {
+    const limited = Keypair.generate();
+    const limitedMints = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+
+    const mintTo = async (newMint: Keypair) =>
+      program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, limited.publicKey))
+        .accounts(await mintNftAccounts(newMint.publicKey, limited.publicKey, authority.publicKey))
+        .signers([authority, newMint])
+        .rpc();
+
+    after(async () => {
+      await program.methods
+        .setMaxMintsPerWallet(new BN(0))
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("only the authority can set the limit", async () => {
+      try {
+        await program.methods
+          .setMaxMintsPerWallet(new BN(1))
+          .accounts({ nftProgram: nftProgramPda, authority: user.publicKey })
+          .signers([user])
+          .rpc();
+
+        expect.fail("should have failed with Unauthorized");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("stops a wallet at the limit and frees a slot on burn", async () => {
+      await program.methods
+        .setMaxMintsPerWallet(new BN(2))
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+      await mintTo(limitedMints[0]);
+      await mintTo(limitedMints[1]);
+
+      const stats = await program.account.ownerStats.fetch(ownerStatsPda(limited.publicKey));
+      expect(stats.owner.toString()).to.equal(limited.publicKey.toString());
+      expect(stats.minted.toString()).to.equal("2");
+
+      try {
+        await mintTo(limitedMints[2]);
+        expect.fail("should have failed with WalletMintLimit");
+      } catch (error) {
+        expect(error.message).to.include("WalletMintLimit");
+      }
+
+      const burned = limitedMints[0].publicKey;
+      await program.methods
+        .burnNft(burned)
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft-info"), burned.toBuffer()],
+            program.programId
+          )[0],
+          owner: limited.publicKey,
+          mint: burned,
+          ownerTokenAccount: getAssociatedTokenAddressSync(burned, limited.publicKey),
+          ...noPnftAccounts,
+          ...noCollectionAccounts,
+          bridgedCollection: null,
+          bridgedCollectionMint: null,
+          ownerStats: ownerStatsPda(limited.publicKey),
+          mintIndex: await mintIndexOf(burned),
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([limited])
+        .rpc();
+
+      const afterBurn = await program.account.ownerStats.fetch(ownerStatsPda(limited.publicKey));
+      expect(afterBurn.minted.toString()).to.equal("1");
+
+      await mintTo(limitedMints[2]);
+    });
+  });
+
+  describe("print editions", () => {
+    const masterMint = Keypair.generate();
+    const maxSupply = 2;
+
+    const nftInfoPdaFor = (mintKey: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("nft-info"), mintKey.toBuffer()], program.programId)[0];
+    const metadataPdaFor = (mintKey: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), mintKey.toBuffer()],
+        METADATA_PROGRAM_ID
+      )[0];
+    const editionMarkPda = (editionNumber: number) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("metadata"),
+          METADATA_PROGRAM_ID.toBuffer(),
+          masterMint.publicKey.toBuffer(),
+          Buffer.from("edition"),
+          Buffer.from(Math.floor(editionNumber / 248).toString()),
+        ],
+        METADATA_PROGRAM_ID
+      )[0];
+
+    const mintEdition = async (editionNumber: number) => {
+      const printMint = Keypair.generate();
+      await program.methods
+        .mintEdition(masterMint.publicKey, new BN(editionNumber), user.publicKey)
+        .accounts({
+          nftProgram: nftProgramPda,
+          masterInfo: nftInfoPdaFor(masterMint.publicKey),
+          masterMint: masterMint.publicKey,
+          masterMetadata: metadataPdaFor(masterMint.publicKey),
+          masterEdition: masterEditionPda(masterMint.publicKey),
+          editionMark: editionMarkPda(editionNumber),
+          masterTokenAccount: getAssociatedTokenAddressSync(masterMint.publicKey, recipient.publicKey),
+          masterOwner: recipient.publicKey,
+          mint: printMint.publicKey,
+          tokenAccount: getAssociatedTokenAddressSync(printMint.publicKey, user.publicKey),
+          recipient: user.publicKey,
+          nftInfo: nftInfoPdaFor(printMint.publicKey),
+          metadata: metadataPdaFor(printMint.publicKey),
+          edition: masterEditionPda(printMint.publicKey),
+          payer: authority.publicKey,
+          rent: SYSVAR_RENT_PUBKEY,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenMetadataProgram: METADATA_PROGRAM_ID,
+          blockedRecipient: blockedRecipientPda(user.publicKey),
+        })
+        .signers([recipient, printMint, authority])
+        .rpc();
+      return printMint.publicKey;
+    };
+
+    before(async () => {
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(masterMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, masterMint])
+        .rpc();
+    });
+
+    it("turns an nft into a master edition", async () => {
+      await program.methods
+        .createMasterEdition(masterMint.publicKey, new BN(maxSupply))
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: nftInfoPdaFor(masterMint.publicKey),
+          mint: masterMint.publicKey,
+          metadata: metadataPdaFor(masterMint.publicKey),
+          masterEdition: masterEditionPda(masterMint.publicKey),
+          authority: authority.publicKey,
+          payer: authority.publicKey,
+          rent: SYSVAR_RENT_PUBKEY,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenMetadataProgram: METADATA_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+      const masterEdition = await provider.connection.getAccountInfo(masterEditionPda(masterMint.publicKey));
+      expect(masterEdition.owner.toString()).to.equal(METADATA_PROGRAM_ID.toString());
+    });
+
+    it("mints prints up to the max supply", async () => {
+      for (let editionNumber = 1; editionNumber <= maxSupply; editionNumber++) {
+        const printMint = await mintEdition(editionNumber);
+
+        const nftInfo = await program.account.nftInfo.fetch(nftInfoPdaFor(printMint));
+        expect(nftInfo.masterMint.toString()).to.equal(masterMint.publicKey.toString());
+        expect(nftInfo.edition.toNumber()).to.equal(editionNumber);
+        expect(nftInfo.owner.toString()).to.equal(user.publicKey.toString());
+        expect(nftInfo.metadataUri).to.equal(nftUri);
+
+        const tokenAccountInfo = await getAccount(
+          provider.connection,
+          getAssociatedTokenAddressSync(printMint, user.publicKey)
+        );
+        expect(tokenAccountInfo.amount.toString()).to.equal("1");
+      }
+    });
+
+    it("rejects prints past the max supply", async () => {
+      try {
+        await mintEdition(maxSupply + 1);
+        expect.fail("should have failed with MaxEditionsReached");
+      } catch (error) {
+        expect(error.message).to.include("MaxEditionsReached");
+      }
+    });
+  });
+
+  describe("compliance holds", () => {
+    const heldMint = Keypair.generate();
+    let heldToken: PublicKey;
+    let heldInfoPda: PublicKey;
+    let compliancePda: PublicKey;
+
+    const holdAccounts = (signer: PublicKey = authority.publicKey) => ({
+      nftProgram: nftProgramPda,
+      mint: heldMint.publicKey,
+      tokenAccount: heldToken,
+      complianceAction: compliancePda,
+      authority: signer,
+      payer: signer,
+      systemProgram: SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+
+    before(async () => {
+      heldToken = await getAssociatedTokenAddress(heldMint.publicKey, recipient.publicKey);
+      [heldInfoPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft-info"), heldMint.publicKey.toBuffer()],
+        program.programId
+      );
+      [compliancePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("compliance"), heldMint.publicKey.toBuffer(), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(heldMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, heldMint])
+        .rpc();
+    });
+
+    it("only the authority can freeze", async () => {
+      try {
+        await program.methods
+          .freezeTokenAccount(heldMint.publicKey, recipient.publicKey, "court order")
+          .accounts(holdAccounts(user.publicKey))
+          .signers([user])
+          .rpc();
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("freezes a holder and records why", async () => {
+      await program.methods
+        .freezeTokenAccount(heldMint.publicKey, recipient.publicKey, "court order")
+        .accounts(holdAccounts())
+        .signers([authority])
+        .rpc();
+
+      const tokenAccountInfo = await getAccount(provider.connection, heldToken);
+      expect(tokenAccountInfo.isFrozen).to.be.true;
+
+      const complianceAction = await program.account.complianceAction.fetch(compliancePda);
+      expect(complianceAction.frozen).to.be.true;
+      expect(complianceAction.reason).to.equal("court order");
+      expect(complianceAction.holder.toString()).to.equal(recipient.publicKey.toString());
+    });
+
+    it("blocks bridging while frozen", async () => {
+      try {
+        await program.methods
+          .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+          .accounts({
+            nftProgram: nftProgramPda,
+            nftInfo: heldInfoPda,
+            mint: heldMint.publicKey,
+            owner: recipient.publicKey,
+            ownerTokenAccount: heldToken,
+            programTokenAccount: await getAssociatedTokenAddress(heldMint.publicKey, nftProgramPda, true),
+            ...noPnftAccounts,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            approvedCollection: null,
+            ...noFeeTokenAccounts,
+          })
+          .signers([recipient])
+          .rpc();
+        expect.fail("should have failed with TokenFrozen");
+      } catch (error) {
+        expect(error.message).to.include("TokenFrozen");
+      }
+    });
+
+    it("rejects a missing reason", async () => {
+      try {
+        await program.methods
+          .thawTokenAccount(heldMint.publicKey, recipient.publicKey, "")
+          .accounts(holdAccounts())
+          .signers([authority])
+          .rpc();
+        expect.fail("should have failed with InvalidReason");
+      } catch (error) {
+        expect(error.message).to.include("InvalidReason");
+      }
+    });
+
+    it("thaws the holder again", async () => {
+      await program.methods
+        .thawTokenAccount(heldMint.publicKey, recipient.publicKey, "order lifted")
+        .accounts(holdAccounts())
+        .signers([authority])
+        .rpc();
+
+      const tokenAccountInfo = await getAccount(provider.connection, heldToken);
+      expect(tokenAccountInfo.isFrozen).to.be.false;
+
+      const complianceAction = await program.account.complianceAction.fetch(compliancePda);
+      expect(complianceAction.frozen).to.be.false;
+      expect(complianceAction.reason).to.equal("order lifted");
+    });
+  });
+
+  describe("token-2022 mints", () => {
+    const mint2022 = Keypair.generate();
+    let ownerToken: PublicKey;
+    let escrowToken: PublicKey;
+    let info2022Pda: PublicKey;
+
+    before(async () => {
+      ownerToken = await getAssociatedTokenAddress(mint2022.publicKey, recipient.publicKey, false, TOKEN_2022_PROGRAM_ID);
+      escrowToken = await getAssociatedTokenAddress(mint2022.publicKey, nftProgramPda, true, TOKEN_2022_PROGRAM_ID);
+      [info2022Pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft-info"), mint2022.publicKey.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("mints an nft under the token-2022 program", async () => {
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(mint2022.publicKey, recipient.publicKey, authority.publicKey, TOKEN_2022_PROGRAM_ID))
+        .signers([authority, mint2022])
+        .rpc();
+
+      const mintAccount = await provider.connection.getAccountInfo(mint2022.publicKey);
+      expect(mintAccount.owner.toString()).to.equal(TOKEN_2022_PROGRAM_ID.toString());
+
+      const tokenAccountInfo = await getAccount(provider.connection, ownerToken, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(tokenAccountInfo.amount.toString()).to.equal("1");
+    });
+
+    it("locks and unlocks a token-2022 nft", async () => {
+      await program.methods
+        .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: info2022Pda,
+          mint: mint2022.publicKey,
+          owner: recipient.publicKey,
+          ownerTokenAccount: ownerToken,
+          programTokenAccount: escrowToken,
+          ...noPnftAccounts,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([recipient])
+        .rpc();
+
+      const escrowInfo = await getAccount(provider.connection, escrowToken, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(escrowInfo.amount.toString()).to.equal("1");
+      expect((await program.account.nftInfo.fetch(info2022Pda)).isLocked).to.be.true;
+
+      await program.methods
+        .unlockNft()
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: info2022Pda,
+          mint: mint2022.publicKey,
+          owner: recipient.publicKey,
+          authority: authority.publicKey,
+          ownerTokenAccount: ownerToken,
+          programTokenAccount: escrowToken,
+          ...noPnftAccounts,
+          systemProgram: null,
+          associatedTokenProgram: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+      const ownerInfo = await getAccount(provider.connection, ownerToken, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(ownerInfo.amount.toString()).to.equal("1");
+      expect((await program.account.nftInfo.fetch(info2022Pda)).isLocked).to.be.false;
+    });
+
+    it("rejects a token program that does not own the mint", async () => {
+      try {
+        await program.methods
+          .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+          .accounts({
+            nftProgram: nftProgramPda,
+            nftInfo: info2022Pda,
+            mint: mint2022.publicKey,
+            owner: recipient.publicKey,
+            ownerTokenAccount: ownerToken,
+            programTokenAccount: escrowToken,
+            ...noPnftAccounts,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            approvedCollection: null,
+            ...noFeeTokenAccounts,
+          })
+          .signers([recipient])
+          .rpc();
+
+        expect.fail("should have failed with token program mismatch");
+      } catch (error) {
+        expect(error.message).to.include("ConstraintMintTokenProgram");
+      }
+    });
+  });
+
+  describe("attributes", () => {
+    const traits = [
+      { key: "eyes", value: "laser" },
+      { key: "background", value: "zeta green" },
+    ];
+    const nftInfoPdaFor = (mintKey: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("nft-info"), mintKey.toBuffer()], program.programId)[0];
+    const setAttributesAccounts = (mintKey: PublicKey, tokenProgram: PublicKey = TOKEN_PROGRAM_ID) => ({
+      nftInfo: nftInfoPdaFor(mintKey),
+      owner: recipient.publicKey,
+      mint: mintKey,
+      ownerTokenAccount: getAssociatedTokenAddressSync(mintKey, recipient.publicKey, false, tokenProgram),
+      metadata: PublicKey.findProgramAddressSync(
+        [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), mintKey.toBuffer()],
+        METADATA_PROGRAM_ID
+      )[0],
+      tokenProgram,
+    });
+
+    it("stores attributes on chain at mint", async () => {
+      const newMint = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey, { attributes: traits }))
+        .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, newMint])
+        .rpc();
+
+      const nftInfo = await program.account.nftInfo.fetch(nftInfoPdaFor(newMint.publicKey));
+      expect(nftInfo.attributes).to.deep.equal(traits);
+
+      // metaplex metadata is immutable, so are the attributes
+      try {
+        await program.methods
+          .setAttributes(newMint.publicKey, [])
+          .accounts(setAttributesAccounts(newMint.publicKey))
+          .signers([recipient])
+          .rpc();
+        expect.fail("should have failed with MetadataImmutable");
+      } catch (error) {
+        expect(error.message).to.include("MetadataImmutable");
+      }
+    });
+
+    it("enforces the attribute bounds", async () => {
+      const cases: [{ key: string; value: string }[], string][] = [
+        [Array(9).fill({ key: "k", value: "v" }), "TooManyAttributes"],
+        [[{ key: "", value: "v" }], "InvalidAttributeKey"],
+        [[{ key: "k".repeat(17), value: "v" }], "InvalidAttributeKey"],
+        [[{ key: "k", value: "v".repeat(33) }], "AttributeValueTooLong"],
+      ];
+      for (const [attributes, expected] of cases) {
+        const newMint = Keypair.generate();
+        try {
+          await program.methods
+            .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey, { attributes }))
+            .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+            .signers([authority, newMint])
+            .rpc();
+          expect.fail(`should have failed with ${expected}`);
+        } catch (error) {
+          expect(error.message).to.include(expected);
+        }
+      }
+    });
+
+    it("lets the holder update attributes of an extension mode nft", async () => {
+      const setMetadataMode = (metadataMode: object) =>
+        program.methods
+          .setMetadataMode(metadataMode as any)
+          .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+      await setMetadataMode({ tokenExtension: {} });
+
+      const extMint = Keypair.generate();
+      try {
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey, { attributes: traits }))
+          .accounts({
+            ...(await mintNftAccounts(extMint.publicKey, recipient.publicKey, authority.publicKey, TOKEN_2022_PROGRAM_ID)),
+            metadata: null,
+            tokenMetadataProgram: null,
+          })
+          .signers([authority, extMint])
+          .rpc();
+      } finally {
+        await setMetadataMode({ metaplex: {} });
+      }
+
+      const updated = [{ key: "eyes", value: "closed" }];
+      await program.methods
+        .setAttributes(extMint.publicKey, updated)
+        .accounts(setAttributesAccounts(extMint.publicKey, TOKEN_2022_PROGRAM_ID))
+        .signers([recipient])
+        .rpc();
+      expect((await program.account.nftInfo.fetch(nftInfoPdaFor(extMint.publicKey))).attributes).to.deep.equal(updated);
+
+      try {
+        await program.methods
+          .setAttributes(extMint.publicKey, [])
+          .accounts({
+            ...setAttributesAccounts(extMint.publicKey, TOKEN_2022_PROGRAM_ID),
+            owner: user.publicKey,
+          })
+          .signers([user])
+          .rpc();
+        expect.fail("should have failed with a token owner error");
+      } catch (error) {
+        expect(error.message).to.include("ConstraintTokenOwner");
+      }
+    });
+  });
+
+  describe("token-2022 metadata extension", () => {
+    const setMetadataMode = (metadataMode: object) =>
+      program.methods
+        .setMetadataMode(metadataMode as any)
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+    after(async () => {
+      await setMetadataMode({ metaplex: {} });
+    });
+
+    it("stores name, symbol and uri on the mint without a metaplex account", async () => {
+      await setMetadataMode({ tokenExtension: {} });
+      const extMint = Keypair.generate();
+
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts({
+          ...(await mintNftAccounts(extMint.publicKey, recipient.publicKey, authority.publicKey, TOKEN_2022_PROGRAM_ID)),
+          metadata: null,
+          tokenMetadataProgram: null,
+        })
+        .signers([authority, extMint])
+        .rpc();
+
+      const tokenMetadata = await getTokenMetadata(provider.connection, extMint.publicKey);
+      expect(tokenMetadata.name).to.equal(nftName);
+      expect(tokenMetadata.symbol).to.equal(nftSymbol);
+      expect(tokenMetadata.uri).to.equal(nftUri);
+      expect(tokenMetadata.updateAuthority.toString()).to.equal(nftProgramPda.toString());
+    });
+
+    it("rejects extension mode with the legacy token program", async () => {
+      await setMetadataMode({ tokenExtension: {} });
+      const extMint = Keypair.generate();
+
+      try {
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+          .accounts({
+            ...(await mintNftAccounts(extMint.publicKey, recipient.publicKey, authority.publicKey)),
+            metadata: null,
+            tokenMetadataProgram: null,
+          })
+          .signers([authority, extMint])
+          .rpc();
+
+        expect.fail("should have failed with token-2022 required error");
+      } catch (error) {
+        expect(error.message).to.include("MetadataExtensionRequiresToken2022");
+      }
+    });
+
+    it("still creates a readable metaplex account in metaplex mode", async () => {
+      await setMetadataMode({ metaplex: {} });
+      const metaplexMint = Keypair.generate();
+      const accounts = await mintNftAccounts(metaplexMint.publicKey, recipient.publicKey, authority.publicKey);
+
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(accounts)
+        .signers([authority, metaplexMint])
+        .rpc();
+
+      const metadataAccount = await provider.connection.getAccountInfo(accounts.metadata);
+      expect(metadataAccount.owner.toString()).to.equal(METADATA_PROGRAM_ID.toString());
+      // metaplex pads the name out to 32 bytes after its length prefix
+      expect(metadataAccount.data.subarray(69, 69 + nftName.length).toString()).to.equal(nftName);
+    });
+
+    it("only lets the authority change the mode", async () => {
+      try {
+        await program.methods
+          .setMetadataMode({ tokenExtension: {} })
+          .accounts({ nftProgram: nftProgramPda, authority: user.publicKey })
+          .signers([user])
+          .rpc();
+
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("compressed mode", () => {
+    it("lets the authority point compressed mints at a tree", async () => {
+      const merkleTree = Keypair.generate().publicKey;
+
+      await program.methods
+        .setTree(merkleTree)
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.merkleTree.toString()).to.equal(merkleTree.toString());
+      expect(programState.compressedMode).to.be.false;
+    });
+
+    it("refuses to turn compressed mode on in a build without bubblegum", async () => {
+      try {
+        await program.methods
+          .setCompressedMode(true)
+          .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+
+        expect.fail("should have failed with compressed not enabled error");
+      } catch (error) {
+        expect(error.message).to.include("CompressedNotEnabled");
+      }
+    });
+
+    it("only lets the authority change the tree", async () => {
+      try {
+        await program.methods
+          .setTree(Keypair.generate().publicKey)
+          .accounts({ nftProgram: nftProgramPda, authority: user.publicKey })
+          .signers([user])
+          .rpc();
+
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("lazy metadata", () => {
+    const setLazyMetadata = (lazyMetadata: boolean, signer: Keypair) =>
+      program.methods
+        .setLazyMetadata(lazyMetadata)
+        .accounts({ nftProgram: nftProgramPda, authority: signer.publicKey })
+        .signers([signer])
+        .rpc();
+
+    it("lets only the authority turn lazy metadata on and off", async () => {
+      try {
+        await setLazyMetadata(true, user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      await setLazyMetadata(true, authority);
+      expect((await program.account.nftProgramState.fetch(nftProgramPda)).lazyMetadata).to.be.true;
+      await setLazyMetadata(false, authority);
+      expect((await program.account.nftProgramState.fetch(nftProgramPda)).lazyMetadata).to.be.false;
+    });
+
+    it("wont create metadata twice", async () => {
+      // local mints always come with their metadata
+      const nftInfo = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft-info"), mint.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      expect((await program.account.nftInfo.fetch(nftInfo)).metadataCreated).to.be.true;
+
+      try {
+        await program.methods
+          .materializeMetadata(mint.publicKey)
+          .accounts({
+            nftProgram: nftProgramPda,
+            nftInfo,
+            mint: mint.publicKey,
+            metadata: PublicKey.findProgramAddressSync(
+              [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), mint.publicKey.toBuffer()],
+              METADATA_PROGRAM_ID
+            )[0],
+            masterEdition: masterEditionPda(mint.publicKey),
+            ...noCollectionAccounts,
+            bridgedCollection: null,
+            bridgedCollectionMint: null,
+            payer: user.publicKey,
+            sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+            rent: SYSVAR_RENT_PUBKEY,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            tokenMetadataProgram: METADATA_PROGRAM_ID,
+          })
+          .signers([user])
+          .rpc();
+        expect.fail("should have failed with MetadataAlreadyCreated");
+      } catch (error) {
+        expect(error.message).to.include("MetadataAlreadyCreated");
+      }
+    });
+  });
+
+  describe("dead letters", () => {
+    const setDeadLetterRetry = (retry: object, signer: Keypair) =>
+      program.methods
+        .setDeadLetterRetry(retry)
+        .accounts({ nftProgram: nftProgramPda, authority: signer.publicKey })
+        .signers([signer])
+        .rpc();
+
+    it("starts with authority only retries", async () => {
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.deadLetterRetry).to.deep.equal({ authority: {} });
+    });
+
+    it("lets only the authority open up retries", async () => {
+      try {
+        await setDeadLetterRetry({ anyone: {} }, user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      await setDeadLetterRetry({ anyone: {} }, authority);
+      expect((await program.account.nftProgramState.fetch(nftProgramPda)).deadLetterRetry).to.deep.equal({
+        anyone: {},
+      });
+      await setDeadLetterRetry({ authority: {} }, authority);
+      expect((await program.account.nftProgramState.fetch(nftProgramPda)).deadLetterRetry).to.deep.equal({
+        authority: {},
+      });
+    });
+  });
+
+  describe("authority handover", () => {
+    const nominate = (nominee: PublicKey, signer: Keypair) =>
+      program.methods
+        .nominateAuthority(nominee)
+        .accounts({ nftProgram: nftProgramPda, authority: signer.publicKey })
+        .signers([signer])
+        .rpc();
+    const accept = (signer: Keypair) =>
+      program.methods
+        .acceptAuthority()
+        .accounts({ nftProgram: nftProgramPda, newAuthority: signer.publicKey })
+        .signers([signer])
+        .rpc();
+    const cancel = (signer: Keypair) =>
+      program.methods
+        .cancelAuthorityNomination()
+        .accounts({ nftProgram: nftProgramPda, signer: signer.publicKey })
+        .signers([signer])
+        .rpc();
+    const setMintFee = (signer: Keypair) =>
+      program.methods
+        .setMintFee(new BN(0))
+        .accounts({ nftProgram: nftProgramPda, authority: signer.publicKey })
+        .signers([signer])
+        .rpc();
+    const pendingAuthority = async () =>
+      (await program.account.nftProgramState.fetch(nftProgramPda)).pendingAuthority.toString();
+
+    it("only the authority can nominate", async () => {
+      try {
+        await nominate(user.publicKey, user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("gives the nominee no powers until it accepts", async () => {
+      await nominate(user.publicKey, authority);
+      expect(await pendingAuthority()).to.equal(user.publicKey.toString());
+
+      try {
+        await setMintFee(user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      try {
+        await accept(Keypair.generate());
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("lets the nominee cancel", async () => {
+      await cancel(user);
+      expect(await pendingAuthority()).to.equal(PublicKey.default.toString());
+
+      try {
+        await accept(user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+      try {
+        await cancel(authority);
+        expect.fail("should have failed with NoPendingAuthority");
+      } catch (error) {
+        expect(error.message).to.include("NoPendingAuthority");
+      }
+    });
+
+    it("hands over on acceptance and back again", async () => {
+      await nominate(user.publicKey, authority);
+      await accept(user);
+
+      let programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.authority.toString()).to.equal(user.publicKey.toString());
+      expect(programState.pendingAuthority.toString()).to.equal(PublicKey.default.toString());
+      try {
+        await setMintFee(authority);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+      await setMintFee(user);
+
+      // the authority can cancel its own nomination too
+      await nominate(authority.publicKey, user);
+      await cancel(user);
+      expect(await pendingAuthority()).to.equal(PublicKey.default.toString());
+
+      await nominate(authority.publicKey, user);
+      await accept(authority);
+      programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.authority.toString()).to.equal(authority.publicKey.toString());
+    });
+
+    it("rejects nominating the current authority", async () => {
+      try {
+        await nominate(authority.publicKey, authority);
+        expect.fail("should have failed with InvalidAuthority");
+      } catch (error) {
+        expect(error.message).to.include("InvalidAuthority");
+      }
+    });
+  });
+
+  describe("state migration", () => {
+    it("has nothing to migrate on a fresh deployment", async () => {
+      try {
+        await program.methods
+          .migrateState()
+          .accounts({
+            nftProgram: nftProgramPda,
+            authority: authority.publicKey,
+            payer: authority.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        expect.fail("should have failed with StateAlreadyMigrated");
+      } catch (error) {
+        expect(error.message).to.include("StateAlreadyMigrated");
+      }
+    });
+
+    it("leaves a current nft info alone in migrate_nft_info", async () => {
+      const before = await provider.connection.getAccountInfo(nftInfoPda);
+
+      await program.methods
+        .migrateNftInfo(mint.publicKey)
+        .accounts({
+          nftInfo: nftInfoPda,
+          payer: user.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+      const after = await provider.connection.getAccountInfo(nftInfoPda);
+      expect(after.data.length).to.equal(before.data.length);
+      const nftInfo = await program.account.nftInfo.fetch(nftInfoPda);
+      expect(nftInfo.version).to.equal(6);
+    });
+  });
+
+  describe("multisig authority", () => {
+    const multisig = anchor.workspace.MockMultisig as Program<MockMultisig>;
+    const [vault] = PublicKey.findProgramAddressSync([Buffer.from("vault")], multisig.programId);
+    const minter = Keypair.generate();
+    const minterRolePda = PublicKey.findProgramAddressSync(
+      [Buffer.from("minter"), minter.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    // the vault cant sign the outer transaction, the mock multisig signs for it in the cpi
+    const execute = (ix: TransactionInstruction) =>
+      multisig.methods
+        .execute(ix.data)
+        .accounts({ vault, targetProgram: program.programId })
+        .remainingAccounts(ix.keys.map((key) => (key.pubkey.equals(vault) ? { ...key, isSigner: false } : key)))
+        .rpc();
+
+    it("hands the authority to a multisig vault", async () => {
+      await program.methods
+        .nominateAuthority(vault)
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+      await execute(
+        await program.methods
+          .acceptAuthority()
+          .accounts({ nftProgram: nftProgramPda, newAuthority: vault })
+          .instruction()
+      );
+
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.authority.toString()).to.equal(vault.toString());
+    });
+
+    it("administers through the multisig with a separate payer", async () => {
+      const grantMinter = (signer: PublicKey) =>
+        program.methods
+          .grantMinter(minter.publicKey)
+          .accounts({
+            nftProgram: nftProgramPda,
+            minterRole: minterRolePda,
+            authority: signer,
+            payer: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          });
+
+      try {
+        await grantMinter(authority.publicKey).signers([authority]).rpc();
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      await execute(await grantMinter(vault).instruction());
+      const role = await program.account.minterRole.fetch(minterRolePda);
+      expect(role.minter.toString()).to.equal(minter.publicKey.toString());
+    });
+
+    it("hands the authority back", async () => {
+      await execute(
+        await program.methods
+          .nominateAuthority(authority.publicKey)
+          .accounts({ nftProgram: nftProgramPda, authority: vault })
+          .instruction()
+      );
+      await program.methods
+        .acceptAuthority()
+        .accounts({ nftProgram: nftProgramPda, newAuthority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.authority.toString()).to.equal(authority.publicKey.toString());
+    });
+  });
+
+  describe("cpi callers", () => {
+    const launchpad = anchor.workspace.NftLaunchpad as Program<NftLaunchpad>;
+    const buyer = Keypair.generate();
+    const [drop] = PublicKey.findProgramAddressSync(
+      [Buffer.from("drop"), authority.publicKey.toBuffer()],
+      launchpad.programId
+    );
+    const [eventAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("__event_authority")],
+      program.programId
+    );
+
+    const mintFromDrop = async (newMint: Keypair) => {
+      const accounts = await mintNftAccounts(newMint.publicKey, buyer.publicKey, buyer.publicKey);
+      return launchpad.methods
+        .mintFromDrop(nftName, nftSymbol, nftUri)
+        .accounts({
+          drop,
+          buyer: buyer.publicKey,
+          mint: newMint.publicKey,
+          nftProgram: accounts.nftProgram,
+          tokenAccount: accounts.tokenAccount,
+          nftInfo: accounts.nftInfo,
+          metadata: accounts.metadata,
+          treasury: accounts.treasury,
+          ownerStats: accounts.ownerStats,
+          mintIndex: accounts.mintIndex,
+          rent: SYSVAR_RENT_PUBKEY,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenMetadataProgram: METADATA_PROGRAM_ID,
+          blockedRecipient: accounts.blockedRecipient,
+          eventAuthority,
+          universalNftProgram: program.programId,
+        })
+        .signers([buyer, newMint])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    before(async () => {
+      await provider.connection.requestAirdrop(buyer.publicKey, 2 * anchor.web3.LAMPORTS_PER_SOL);
+      await new Promise(resolve => setTimeout(resolve, 500));
+
+      await launchpad.methods
+        .createDrop(new BN(1))
+        .accounts({ drop, creator: authority.publicKey, systemProgram: SystemProgram.programId })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("mints through another program", async () => {
+      const newMint = Keypair.generate();
+      const { totalSupply } = await program.account.nftProgramState.fetch(nftProgramPda);
+
+      const tx = await mintFromDrop(newMint);
+
+      const buyerAta = getAssociatedTokenAddressSync(newMint.publicKey, buyer.publicKey);
+      expect((await getAccount(provider.connection, buyerAta)).amount.toString()).to.equal("1");
+      const nftInfo = await program.account.nftInfo.fetch(
+        PublicKey.findProgramAddressSync([Buffer.from("nft-info"), newMint.publicKey.toBuffer()], program.programId)[0]
+      );
+      expect(nftInfo.owner.toString()).to.equal(buyer.publicKey.toString());
+      const state = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(state.totalSupply.toString()).to.equal(totalSupply.addn(1).toString());
+      expect((await launchpad.account.drop.fetch(drop)).minted.toNumber()).to.equal(1);
+
+      // the event still comes out of the nested self cpi
+      const { events } = await cpiEventsOf(tx);
+      const minted = events.find((event) => event.name === "nftMinted");
+      expect(minted.data.mint.toString()).to.equal(newMint.publicKey.toString());
+      expect(minted.data.payer.toString()).to.equal(buyer.publicKey.toString());
+    });
+
+    it("stops at the drop supply", async () => {
+      try {
+        await mintFromDrop(Keypair.generate());
+        expect.fail("should have failed with SoldOut");
+      } catch (error) {
+        expect(error.message).to.include("SoldOut");
+      }
+    });
+  });
+
+  describe("mock gateway", () => {
+    const mockGateway = anchor.workspace.MockGateway as Program<MockGateway>;
+    const owner = Keypair.generate();
+    const originMint = Keypair.generate().publicKey;
+    // chain 7001 is zetachain, evmAddress is its connected contract
+    const zetachainId = new BN(7001);
+    const sender = evmAddress.slice(0, 20);
+    const [chainInfo] = PublicKey.findProgramAddressSync(
+      [Buffer.from("chain"), zetachainId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [bridgedMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("nft-mint"), originMint.toBuffer()],
+      program.programId
+    );
+    const [bridgedNftInfo] = PublicKey.findProgramAddressSync(
+      [Buffer.from("nft-info"), bridgedMint.toBuffer()],
+      program.programId
+    );
+    const [eventAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("__event_authority")],
+      program.programId
+    );
+    const ownerAta = getAssociatedTokenAddressSync(bridgedMint, owner.publicKey);
+    const escrowAta = getAssociatedTokenAddressSync(bridgedMint, nftProgramPda, true);
+
+    const updateGateway = (gateway: PublicKey) =>
+      program.methods
+        .updateGateway(gateway)
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+    // borsh CrossChainMessage, the way the zetachain contract encodes it
+    const borshString = (value: string) => {
+      const len = Buffer.alloc(4);
+      len.writeUInt32LE(Buffer.byteLength(value));
+      return Buffer.concat([len, Buffer.from(value)]);
+    };
+    // a transfer names the origin mint, an unlock the local mint it went out with
+    const encodeMessage = (messageType: "transfer" | "unlock", messageMint: PublicKey, nonce: BN) =>
+      Buffer.concat([
+        Buffer.from([messageType === "transfer" ? 0 : 1]),
+        messageMint.toBuffer(),
+        owner.publicKey.toBuffer(),
+        borshString(nftUri),
+        borshString(nftName),
+        borshString(nftSymbol),
+        nonce.toArrayLike(Buffer, "le", 8),
+        PublicKey.default.toBuffer(), // master_mint
+        Buffer.alloc(8), // edition
+        Buffer.from([0, 0, 0]), // no metadata_hash, royalty_bps, royalty_recipient
+        Buffer.alloc(4), // no attributes
+      ]);
+    // inbound nonces cant jump more than a window past the highest one
+    const nextNonce = async () => (await program.account.nftProgramState.fetch(nftProgramPda)).nonce.addn(1);
+
+    // on_call accounts in the order the gateway has to pass them as remaining
+    // accounts. a missing optional account is the program id, like anchor sends it.
+    // a transfer passes the master edition it creates
+    const none = { pubkey: program.programId, isSigner: false, isWritable: false };
+    const readonly = (pubkey: PublicKey) => ({ pubkey, isSigner: false, isWritable: false });
+    const writable = (pubkey: PublicKey) => ({ pubkey, isSigner: false, isWritable: true });
+    const onCallAccounts = (mintIndex: PublicKey | null, unlock = false): Record<string, AccountMeta> => ({
+      nftProgram: writable(nftProgramPda),
+      chainInfo: readonly(chainInfo),
+      mint: writable(bridgedMint),
+      nftInfo: writable(bridgedNftInfo),
+      metadata: writable(
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), bridgedMint.toBuffer()],
+          METADATA_PROGRAM_ID
+        )[0]
+      ),
+      nftOwner: readonly(owner.publicKey),
+      treeConfig: none,
+      merkleTree: none,
+      bubblegumProgram: none,
+      compressionProgram: none,
+      logWrapper: none,
+      masterEdition: unlock ? none : writable(masterEditionPda(bridgedMint)),
+      ownerTokenRecord: none,
+      programTokenRecord: none,
+      collection: none,
+      collectionMint: none,
+      collectionMetadata: none,
+      collectionMasterEdition: none,
+      bridgedCollection: none,
+      bridgedCollectionMint: none,
+      mintIndex: mintIndex ? writable(mintIndex) : none,
+      payer: { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: true },
+      rent: readonly(SYSVAR_RENT_PUBKEY),
+      systemProgram: readonly(SystemProgram.programId),
+      tokenProgram: readonly(TOKEN_PROGRAM_ID),
+      associatedTokenProgram: readonly(ASSOCIATED_TOKEN_PROGRAM_ID),
+      tokenMetadataProgram: readonly(METADATA_PROGRAM_ID),
+      instructionSysvarAccount: readonly(SYSVAR_INSTRUCTIONS_PUBKEY),
+      deadLetter: none,
+      pendingNonce: none,
+      inboundBatch: none,
+      blockedRecipient: readonly(blockedRecipientPda(owner.publicKey)),
+      blockedMint: readonly(blockedMintPda(bridgedMint)),
+      pendingClaim: none,
+      failedInbound: none,
+      eventAuthority: readonly(eventAuthority),
+      program: readonly(program.programId),
+    });
+
+    // the token accounts of the message type follow them, typed by the program: a
+    // transfer passes the recipient ata (InboundTransfer), an unlock the escrow and
+    // the owner ata it moves the nft between (InboundUnlock)
+    const inboundAccounts = (unlock: boolean): AccountMeta[] =>
+      unlock ? [writable(escrowAta), writable(ownerAta)] : [writable(ownerAta)];
+
+    const execute = (data: Buffer, accounts: Record<string, AccountMeta>, unlock = false) =>
+      mockGateway.methods
+        .execute(new BN(0), sender, data)
+        .accounts({ targetProgram: program.programId })
+        .remainingAccounts([...Object.values(accounts), ...inboundAccounts(unlock)])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      await provider.connection.requestAirdrop(owner.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      await new Promise(resolve => setTimeout(resolve, 500));
+      await updateGateway(mockGateway.programId);
+    });
+
+    after(async () => {
+      await updateGateway(gatewayPda);
+    });
+
+    it("is deployed at the dev gateway id", async () => {
+      expect(mockGateway.programId.toString()).to.equal("94U5AHQMKkV5txNJ17QPXWoh474PheGou6cNP2FEuL1d");
+    });
+
+    it("passes on_call accounts in the order anchor expects", async () => {
+      const onCall = program.idl.instructions.find((ix) => ix.name === "onCall");
+      expect(Object.keys(onCallAccounts(null))).to.deep.equal(onCall.accounts.map((account) => account.name));
+    });
+
+    it("mints an nft that arrives through the gateway", async () => {
+      const { totalSupply } = await program.account.nftProgramState.fetch(nftProgramPda);
+
+      const tx = await execute(
+        encodeMessage("transfer", originMint, await nextNonce()),
+        onCallAccounts(await nextMintIndex())
+      );
+
+      expect((await getAccount(provider.connection, ownerAta)).amount.toString()).to.equal("1");
+      const nftInfo = await program.account.nftInfo.fetch(bridgedNftInfo);
+      expect(nftInfo.owner.toString()).to.equal(owner.publicKey.toString());
+      expect(nftInfo.bridged).to.be.true;
+      expect(nftInfo.name).to.equal(nftName);
+      // the master edition holds the mint authority, no more supply can be minted
+      const edition = await provider.connection.getAccountInfo(masterEditionPda(bridgedMint));
+      expect(edition.owner.toString()).to.equal(METADATA_PROGRAM_ID.toString());
+      const bridged = await getMint(provider.connection, bridgedMint);
+      expect(bridged.mintAuthority.toString()).to.equal(masterEditionPda(bridgedMint).toString());
+      const state = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(state.totalSupply.toString()).to.equal(totalSupply.addn(1).toString());
+
+      const { events } = await cpiEventsOf(tx);
+      const received = events.find((event) => event.name === "nftReceived");
+      expect(received.data.mint.toString()).to.equal(bridgedMint.toString());
+      expect(received.data.originMint.toString()).to.equal(originMint.toString());
+    });
+
+    it("unlocks the nft when it comes back through the gateway", async () => {
+      await program.methods
+        .transferToZetachain({ destinationChainId: zetachainId, recipient: evmAddress, nonce: new BN(0) })
+        .accounts({
+          nftProgram: nftProgramPda,
+          chainInfo,
+          nftInfo: bridgedNftInfo,
+          mint: bridgedMint,
+          owner: owner.publicKey,
+          ownerTokenAccount: ownerAta,
+          programTokenAccount: escrowAta,
+          ...noPnftAccounts,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([owner])
+        .rpc();
+      expect((await getAccount(provider.connection, escrowAta)).amount.toString()).to.equal("1");
+
+      const returned = encodeMessage("unlock", bridgedMint, await nextNonce());
+      try {
+        await execute(returned, onCallAccounts(null));
+        expect.fail("should have failed with InboundAccountsMismatch");
+      } catch (error) {
+        expect(error.message).to.include("InboundAccountsMismatch");
+      }
+
+      await execute(returned, onCallAccounts(null, true), true);
+
+      expect((await getAccount(provider.connection, ownerAta)).amount.toString()).to.equal("1");
+      expect((await getAccount(provider.connection, escrowAta)).amount.toString()).to.equal("0");
+      expect((await program.account.nftInfo.fetch(bridgedNftInfo)).isLocked).to.be.false;
+    });
+
+    it("refuses accounts for another nft than the message", async () => {
+      try {
+        await execute(
+          encodeMessage("transfer", Keypair.generate().publicKey, await nextNonce()),
+          onCallAccounts(null)
+        );
+        expect.fail("should have failed with MintMismatch");
+      } catch (error) {
+        expect(error.message).to.include("MintMismatch");
+      }
+    });
+
+    it("still refuses on_call sent straight to the program", async () => {
+      const onCall = new TransactionInstruction({
+        programId: program.programId,
+        keys: [...Object.values(onCallAccounts(null)), ...inboundAccounts(false)],
+        data: program.coder.instruction.encode("onCall", {
+          message: { amount: new BN(0), sender, data: encodeMessage("transfer", originMint, await nextNonce()) },
+        }),
+      });
+
+      try {
+        await provider.sendAndConfirm(new anchor.web3.Transaction().add(onCall));
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.logs.join("\n")).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("security tests", () => {
+    it("prevents unauthorized transfers", async () => {
+      const unauthorizedUser = Keypair.generate();
+      await provider.connection.requestAirdrop(unauthorizedUser.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      await new Promise(resolve => setTimeout(resolve, 500));
+
+      try {
+        await program.methods
+          .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+          .accounts({
+            nftProgram: nftProgramPda,
+            nftInfo: nftInfoPda,
+            mint: mint.publicKey,
+            owner: unauthorizedUser.publicKey,
+            ownerTokenAccount: tokenAccount,
+            programTokenAccount: programTokenAccount,
+            ...noPnftAccounts,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            approvedCollection: null,
+            ...noFeeTokenAccounts,
+          })
+          .signers([unauthorizedUser])
+          .rpc();
+        
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("prevents replay attacks with old nonce", async () => {
+      // the highest nonce is always taken
+      const { nonce: oldNonce } = await program.account.nftProgramState.fetch(nftProgramPda);
+
+      try {
+        await program.methods
+          .handleCrossChainCall(
+            Array.from(Buffer.alloc(32, 2)),
+            new BN(7001),
+            Array.from(Buffer.from("test message")),
+            oldNonce
+          )
+          .accounts({
+            nftProgram: nftProgramPda,
+          })
+          .signers([authority])
+          .rpc();
+        
+        expect.fail("should have failed with invalid nonce error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidNonce");
+
+        // the logs say what nonce was expected instead
+        const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+        const line = error.logs.find((log) => log.includes("MessageRejected"));
+        const rejected = JSON.parse(line.slice(line.indexOf("{")));
+        expect(rejected.expected_nonce).to.equal(programState.nonce.toNumber() + 1);
+        expect(rejected.received_nonce).to.equal(oldNonce.toNumber());
+        expect(rejected.reason).to.equal("replay");
+      }
+    });
+
+    it("refuses a nonce more than a window ahead", async () => {
+      const { nonce } = await program.account.nftProgramState.fetch(nftProgramPda);
+      const farAhead = nonce.addn(257); // NONCE_WINDOW + 1
+
+      try {
+        await program.methods
+          .handleCrossChainCall(
+            Array.from(Buffer.alloc(32, 2)),
+            new BN(7001),
+            Array.from(Buffer.from("test message")),
+            farAhead
+          )
+          .accounts({
+            nftProgram: nftProgramPda,
+          })
+          .signers([authority])
+          .rpc();
+
+        expect.fail("should have failed with invalid nonce error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidNonce");
+        const line = error.logs.find((log) => log.includes("MessageRejected"));
+        const rejected = JSON.parse(line.slice(line.indexOf("{")));
+        expect(rejected.received_nonce).to.equal(farAhead.toNumber());
+        expect(rejected.reason).to.equal("ahead");
+      }
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.nonce.toString()).to.equal(nonce.toString());
+    });
+
+    it("reports the nonce the next message needs", async () => {
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+
+      const signature = await program.methods
+        .reportNonceGap()
+        .accounts({ nftProgram: nftProgramPda })
+        .rpc({ commitment: "confirmed" });
+
+      const { events } = await eventsOf(signature);
+      const reported = events.find((event) => event.name === "nonceReported").data;
+      expect(reported.lastNonce.toString()).to.equal(programState.nonce.toString());
+      expect(reported.expectedNonce.toString()).to.equal(programState.nonce.addn(1).toString());
+    });
+
+    it("prevents unlocking non-locked nfts", async () => {
+      try {
+        await program.methods
+          .unlockNft()
+          .accounts({
+            nftProgram: nftProgramPda,
+            nftInfo: nftInfoPda,
+            mint: mint.publicKey,
+            owner: recipient.publicKey,
+            authority: authority.publicKey,
+            ownerTokenAccount: tokenAccount,
+            programTokenAccount: programTokenAccount,
+            ...noPnftAccounts,
+            systemProgram: null,
+            associatedTokenProgram: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([authority])
+          .rpc();
+        
+        expect.fail("should have failed with token not locked error");
+      } catch (error) {
+        expect(error.message).to.include("TokenNotLocked");
+      }
+    });
+  });
+
+  describe("edge cases", () => {
+    it("handles invalid metadata inputs", async () => {
+      const longName = "a".repeat(50); // exceeds max length
+      const newMint = Keypair.generate();
+
+      try {
+        await program.methods
+          .mintNft(mintParams(longName, nftSymbol, nftUri, recipient.publicKey))
+          .accounts({
+            nftProgram: nftProgramPda,
+            mint: newMint.publicKey,
+            tokenAccount: await getAssociatedTokenAddress(newMint.publicKey, recipient.publicKey),
+            recipient: recipient.publicKey,
+            nftInfo: PublicKey.findProgramAddressSync(
               [Buffer.from("nft-info"), newMint.publicKey.toBuffer()],
               program.programId
             )[0],
@@ -367,44 +3459,1119 @@ describe("universal nft", () => {
               ],
               METADATA_PROGRAM_ID
             )[0],
+            treasury: treasuryPda,
+            minterRole: null,
+            ownerStats: ownerStatsPda(recipient.publicKey),
+            mintIndex: await nextMintIndex(),
+            masterEdition: null,
+            tokenRecord: null,
+            sysvarInstructions: null,
             payer: authority.publicKey,
             rent: SYSVAR_RENT_PUBKEY,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             tokenMetadataProgram: METADATA_PROGRAM_ID,
+            blockedRecipient: blockedRecipientPda(recipient.publicKey),
+          })
+          .signers([authority, newMint])
+          .rpc();
+        
+        expect.fail("should have failed with a metadata error");
+      } catch (error) {
+        expect(error.message).to.include("NameTooLong");
+      }
+    });
+
+    it("reports a specific error for each metadata problem", async () => {
+      const cases: [string, string, string, string][] = [
+        ["", nftSymbol, nftUri, "EmptyName"],
+        [nftName, "S".repeat(11), nftUri, "SymbolTooLong"],
+        [nftName, nftSymbol, "https://test.com/" + "a".repeat(200), "UriTooLong"],
+        [nftName, nftSymbol, "", "EmptyUri"],
+      ];
+
+      for (const [name, symbol, uri, errorName] of cases) {
+        const newMint = Keypair.generate();
+        try {
+          await program.methods
+            .mintNft(mintParams(name, symbol, uri, recipient.publicKey))
+            .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+            .signers([authority, newMint])
+            .rpc();
+
+          expect.fail(`should have failed with ${errorName}`);
+        } catch (error) {
+          expect(error.message).to.include(errorName);
+        }
+      }
+    });
+
+    it("validates cross-chain message parsing", async () => {
+      const invalidMessage = Array.from(Buffer.from("invalid json"));
+      const { nonce } = await program.account.nftProgramState.fetch(nftProgramPda);
+      const newNonce = nonce.addn(1);
+
+      try {
+        await program.methods
+          .handleCrossChainCall(
+            Array.from(Buffer.alloc(32, 2)),
+            new BN(7001),
+            invalidMessage,
+            newNonce
+          )
+          .accounts({
+            nftProgram: nftProgramPda,
+          })
+          .signers([authority])
+          .rpc();
+        
+        expect.fail("should have failed with invalid message error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidMessage");
+      }
+    });
+  });
+
+  // runs last, once the collection exists every metaplex mint has to bring it along
+  describe("collections", () => {
+    const [collectionPda] = PublicKey.findProgramAddressSync([Buffer.from("collection")], program.programId);
+    const [collectionMint] = PublicKey.findProgramAddressSync([Buffer.from("collection-mint")], program.programId);
+    const [collectionMetadata] = PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), collectionMint.toBuffer()],
+      METADATA_PROGRAM_ID
+    );
+    const collectionAccounts = {
+      collection: collectionPda,
+      collectionMint,
+      collectionMetadata,
+      collectionMasterEdition: masterEditionPda(collectionMint),
+      sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+    };
+    const itemMints: Keypair[] = [];
+
+    const metadataPdaFor = (mintKey: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), mintKey.toBuffer()],
+        METADATA_PROGRAM_ID
+      )[0];
+    const nftInfoPdaFor = (mintKey: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("nft-info"), mintKey.toBuffer()], program.programId)[0];
+
+    // metaplex metadata is borsh with variable length fields, walk to collection_details
+    const readCollectionSize = async (metadataKey: PublicKey) => {
+      const data = (await provider.connection.getAccountInfo(metadataKey)).data;
+      let offset = 1 + 32 + 32; // key, update authority, mint
+      for (let i = 0; i < 3; i++) offset += 4 + data.readUInt32LE(offset); // name, symbol, uri
+      offset += 2; // seller fee basis points
+      if (data[offset++] === 1) offset += 4 + data.readUInt32LE(offset) * 34; // creators
+      offset += 2; // primary sale happened, is mutable
+      if (data[offset++] === 1) offset += 1; // edition nonce
+      if (data[offset++] === 1) offset += 1; // token standard
+      if (data[offset++] === 1) offset += 33; // collection
+      if (data[offset++] === 1) offset += 17; // uses
+      expect(data[offset++]).to.equal(1); // collection details is set
+      expect(data[offset++]).to.equal(0); // and it is V1 { size }
+      return new BN(data.subarray(offset, offset + 8), "le");
+    };
+
+    const expectSizesInSync = async (expected: number) => {
+      const { collectionSize } = await program.account.collection.fetch(collectionPda);
+      expect(collectionSize.toNumber()).to.equal(expected);
+      expect((await readCollectionSize(collectionMetadata)).toNumber()).to.equal(expected);
+    };
+
+    const createCollection = (signer: Keypair) =>
+      program.methods
+        .createCollection("test collection", "TCOL", "https://test.com/collection.json")
+        .accounts({
+          nftProgram: nftProgramPda,
+          collection: collectionPda,
+          collectionMint,
+          collectionMetadata,
+          collectionMasterEdition: masterEditionPda(collectionMint),
+          collectionTokenAccount: getAssociatedTokenAddressSync(collectionMint, nftProgramPda, true),
+          authority: signer.publicKey,
+          payer: signer.publicKey,
+          sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenMetadataProgram: METADATA_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+
+    const mintIntoCollection = async () => {
+      const itemMint = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts({
+          ...(await mintNftAccounts(itemMint.publicKey, recipient.publicKey, authority.publicKey)),
+          ...collectionAccounts,
+        })
+        .signers([authority, itemMint])
+        .rpc();
+      itemMints.push(itemMint);
+      return itemMint;
+    };
+
+    it("only the authority can create the collection", async () => {
+      try {
+        await createCollection(user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("creates a sized collection", async () => {
+      await createCollection(authority);
+
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.collectionMint.toString()).to.equal(collectionMint.toString());
+      await expectSizesInSync(0);
+    });
+
+    it("verifies new mints into the collection", async () => {
+      for (let i = 0; i < 3; i++) {
+        await mintIntoCollection();
+      }
+
+      const nftInfo = await program.account.nftInfo.fetch(nftInfoPdaFor(itemMints[0].publicKey));
+      expect(nftInfo.inCollection).to.be.true;
+      await expectSizesInSync(3);
+    });
+
+    it("rejects mints that leave out the collection accounts", async () => {
+      const newMint = Keypair.generate();
+      try {
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+          .accounts(await mintNftAccounts(newMint.publicKey, recipient.publicKey, authority.publicKey))
+          .signers([authority, newMint])
+          .rpc();
+        expect.fail("should have failed with MissingCollectionAccount");
+      } catch (error) {
+        expect(error.message).to.include("MissingCollectionAccount");
+      }
+    });
+
+    it("keeps the size when an nft is bridged out and back", async () => {
+      const bridged = itemMints[1].publicKey;
+      const ownerToken = await getAssociatedTokenAddress(bridged, recipient.publicKey);
+      const escrowToken = await getAssociatedTokenAddress(bridged, nftProgramPda, true);
+      const bridgeAccounts = {
+        nftProgram: nftProgramPda,
+        nftInfo: nftInfoPdaFor(bridged),
+        mint: bridged,
+        owner: recipient.publicKey,
+        ownerTokenAccount: ownerToken,
+        programTokenAccount: escrowToken,
+        ...noPnftAccounts,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+
+      await program.methods
+        .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+        .accounts({
+          ...bridgeAccounts,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([recipient])
+        .rpc();
+      await expectSizesInSync(3);
+
+      await program.methods
+        .unlockNft()
+        .accounts({ ...bridgeAccounts, authority: authority.publicKey, systemProgram: null, associatedTokenProgram: null })
+        .signers([authority])
+        .rpc();
+      await expectSizesInSync(3);
+    });
+
+    it("shrinks the collection when a member is burned", async () => {
+      const burned = itemMints[2].publicKey;
+      await program.methods
+        .burnNft(burned)
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: nftInfoPdaFor(burned),
+          owner: recipient.publicKey,
+          mint: burned,
+          ownerTokenAccount: await getAssociatedTokenAddress(burned, recipient.publicKey),
+          ...noPnftAccounts,
+          metadata: metadataPdaFor(burned),
+          tokenMetadataProgram: METADATA_PROGRAM_ID,
+          ...collectionAccounts,
+          bridgedCollection: null,
+          bridgedCollectionMint: null,
+          ownerStats: null,
+          mintIndex: await mintIndexOf(burned),
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([recipient])
+        .rpc();
+      await expectSizesInSync(2);
+
+      // and mints after a burn still count up from the right place
+      await mintIntoCollection();
+      await expectSizesInSync(3);
+    });
+
+    describe("deferred verification", () => {
+      const delegate = Keypair.generate();
+      const delegateRecordFor = (delegateKey: PublicKey) =>
+        PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("metadata"),
+            METADATA_PROGRAM_ID.toBuffer(),
+            collectionMint.toBuffer(),
+            Buffer.from("collection_delegate"),
+            nftProgramPda.toBuffer(),
+            delegateKey.toBuffer(),
+          ],
+          METADATA_PROGRAM_ID
+        )[0];
+      const delegateAccounts = (delegateKey: PublicKey, signer: PublicKey = authority.publicKey) => ({
+        nftProgram: nftProgramPda,
+        delegate: delegateKey,
+        delegateRecord: delegateRecordFor(delegateKey),
+        collectionMint,
+        collectionMetadata,
+        collectionMasterEdition: masterEditionPda(collectionMint),
+        authority: signer,
+        payer: signer,
+        sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+        tokenMetadataProgram: METADATA_PROGRAM_ID,
+      });
+      const verifyAccounts = (mintKey: PublicKey, verifier: PublicKey, delegateRecord: PublicKey | null) => ({
+        nftProgram: nftProgramPda,
+        nftInfo: nftInfoPdaFor(mintKey),
+        metadata: metadataPdaFor(mintKey),
+        collection: collectionPda,
+        collectionMint,
+        collectionMetadata,
+        collectionMasterEdition: masterEditionPda(collectionMint),
+        delegateRecord,
+        verifier,
+        sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+        tokenMetadataProgram: METADATA_PROGRAM_ID,
+      });
+      const setDefer = (defer: boolean) =>
+        program.methods
+          .setDeferCollectionVerify(defer)
+          .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+
+      // deferred mints only need the collection mint key, not the collection accounts
+      const mintDeferred = async () => {
+        const itemMint = Keypair.generate();
+        await program.methods
+          .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+          .accounts(await mintNftAccounts(itemMint.publicKey, recipient.publicKey, authority.publicKey))
+          .signers([authority, itemMint])
+          .rpc();
+        return itemMint.publicKey;
+      };
+
+      before(async () => {
+        await setDefer(true);
+      });
+
+      after(async () => {
+        await setDefer(false);
+      });
+
+      it("mints without verifying and lets the authority verify later", async () => {
+        const deferred = await mintDeferred();
+        expect((await program.account.nftInfo.fetch(nftInfoPdaFor(deferred))).inCollection).to.be.false;
+        await expectSizesInSync(3);
+
+        await program.methods
+          .verifyCollectionItem(deferred)
+          .accounts(verifyAccounts(deferred, authority.publicKey, null))
+          .signers([authority])
+          .rpc();
+
+        expect((await program.account.nftInfo.fetch(nftInfoPdaFor(deferred))).inCollection).to.be.true;
+        await expectSizesInSync(4);
+
+        try {
+          await program.methods
+            .verifyCollectionItem(deferred)
+            .accounts(verifyAccounts(deferred, authority.publicKey, null))
+            .signers([authority])
+            .rpc();
+          expect.fail("should have failed with AlreadyInCollection");
+        } catch (error) {
+          expect(error.message).to.include("AlreadyInCollection");
+        }
+      });
+
+      it("only the authority can approve a collection delegate", async () => {
+        try {
+          await program.methods
+            .approveCollectionDelegate(delegate.publicKey)
+            .accounts(delegateAccounts(delegate.publicKey, user.publicKey))
+            .signers([user])
+            .rpc();
+          expect.fail("should have failed with unauthorized error");
+        } catch (error) {
+          expect(error.message).to.include("Unauthorized");
+        }
+      });
+
+      it("lets the approved delegate verify and nobody else", async () => {
+        await program.methods
+          .approveCollectionDelegate(delegate.publicKey)
+          .accounts(delegateAccounts(delegate.publicKey))
+          .signers([authority])
+          .rpc();
+        const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+        expect(programState.collectionDelegate.toString()).to.equal(delegate.publicKey.toString());
+
+        const deferred = await mintDeferred();
+        try {
+          await program.methods
+            .verifyCollectionItem(deferred)
+            .accounts(verifyAccounts(deferred, user.publicKey, delegateRecordFor(user.publicKey)))
+            .signers([user])
+            .rpc();
+          expect.fail("should have failed with unauthorized error");
+        } catch (error) {
+          expect(error.message).to.include("Unauthorized");
+        }
+
+        await program.methods
+          .verifyCollectionItem(deferred)
+          .accounts(verifyAccounts(deferred, delegate.publicKey, delegateRecordFor(delegate.publicKey)))
+          .signers([delegate])
+          .rpc();
+        expect((await program.account.nftInfo.fetch(nftInfoPdaFor(deferred))).inCollection).to.be.true;
+        await expectSizesInSync(5);
+      });
+
+      it("revokes the delegate", async () => {
+        await program.methods
+          .revokeCollectionDelegate()
+          .accounts(delegateAccounts(delegate.publicKey))
+          .signers([authority])
+          .rpc();
+
+        const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+        expect(programState.collectionDelegate.toString()).to.equal(PublicKey.default.toString());
+        expect(await provider.connection.getAccountInfo(delegateRecordFor(delegate.publicKey))).to.be.null;
+
+        const deferred = await mintDeferred();
+        try {
+          await program.methods
+            .verifyCollectionItem(deferred)
+            .accounts(verifyAccounts(deferred, delegate.publicKey, delegateRecordFor(delegate.publicKey)))
+            .signers([delegate])
+            .rpc();
+          expect.fail("should have failed with unauthorized error");
+        } catch (error) {
+          expect(error.message).to.include("Unauthorized");
+        }
+      });
+    });
+
+    describe("bridged collection", () => {
+      const [bridgedCollectionPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("bridged-collection")],
+        program.programId
+      );
+      const [bridgedCollectionMint] = PublicKey.findProgramAddressSync(
+        [Buffer.from("bridged-collection-mint")],
+        program.programId
+      );
+      const bridgedCollectionMetadata = metadataPdaFor(bridgedCollectionMint);
+
+      const createBridgedCollection = (signer: Keypair) =>
+        program.methods
+          .createBridgedCollection("bridged", "BRDG", "https://test.com/bridged.json")
+          .accounts({
+            nftProgram: nftProgramPda,
+            bridgedCollection: bridgedCollectionPda,
+            bridgedCollectionMint,
+            collectionMetadata: bridgedCollectionMetadata,
+            collectionMasterEdition: masterEditionPda(bridgedCollectionMint),
+            collectionTokenAccount: getAssociatedTokenAddressSync(bridgedCollectionMint, nftProgramPda, true),
+            authority: signer.publicKey,
+            payer: signer.publicKey,
+            sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenMetadataProgram: METADATA_PROGRAM_ID,
           })
-          .signers([authority, newMint])
+          .signers([signer])
           .rpc();
-        
-        expect.fail("should have failed with invalid metadata error");
+
+      it("only the authority can create the bridged collection", async () => {
+        try {
+          await createBridgedCollection(user);
+          expect.fail("should have failed with unauthorized error");
+        } catch (error) {
+          expect(error.message).to.include("Unauthorized");
+        }
+      });
+
+      it("creates a separate sized collection for bridged nfts", async () => {
+        const before = await program.account.collection.fetch(collectionPda);
+        await createBridgedCollection(authority);
+
+        const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+        expect(programState.bridgedCollectionMint.toString()).to.equal(bridgedCollectionMint.toString());
+        // local mints keep going into the program collection
+        expect(programState.collectionMint.toString()).to.equal(collectionMint.toString());
+
+        const bridgedCollection = await program.account.collection.fetch(bridgedCollectionPda);
+        expect(bridgedCollection.mint.toString()).to.equal(bridgedCollectionMint.toString());
+        expect(bridgedCollection.collectionSize.toNumber()).to.equal(0);
+        expect((await readCollectionSize(bridgedCollectionMetadata)).toNumber()).to.equal(0);
+
+        await mintIntoCollection();
+        const after = await program.account.collection.fetch(collectionPda);
+        expect(after.collectionSize.toNumber()).to.equal(before.collectionSize.toNumber() + 1);
+      });
+    });
+  });
+
+  describe("chain registry", () => {
+    const chainId = new BN(1);
+    const chainInfoPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("chain"), chainId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+    const config = (enabled: boolean, name = "Ethereum") => ({
+      name,
+      chainKind: { evm: {} },
+      enabled,
+      recipientLen: 20,
+      connected: evmAddress,
+    });
+    const updateChain = (enabled: boolean, signer: Keypair = authority) =>
+      program.methods
+        .updateChain(chainId, config(enabled))
+        .accounts({ nftProgram: nftProgramPda, chainInfo: chainInfoPda, authority: signer.publicKey })
+        .signers([signer])
+        .rpc();
+    const bridgeMint = Keypair.generate();
+    const bridgeOut = (recipientAddress: number[]) =>
+      program.methods
+        .transferToZetachain({ destinationChainId: chainId, recipient: recipientAddress, nonce: new BN(0) })
+        .accounts({
+          nftProgram: nftProgramPda,
+          chainInfo: chainInfoPda,
+          nftInfo: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft-info"), bridgeMint.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          mint: bridgeMint.publicKey,
+          owner: recipient.publicKey,
+          ownerTokenAccount: getAssociatedTokenAddressSync(bridgeMint.publicKey, recipient.publicKey),
+          programTokenAccount: getAssociatedTokenAddressSync(bridgeMint.publicKey, nftProgramPda, true),
+          ...noPnftAccounts,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([recipient])
+        .rpc();
+
+    before(async () => {
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(bridgeMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, bridgeMint])
+        .rpc();
+    });
+
+    it("registers a chain with its metadata", async () => {
+      try {
+        await program.methods
+          .registerChain(chainId, config(true, "a".repeat(33)))
+          .accounts({ nftProgram: nftProgramPda, chainInfo: chainInfoPda, authority: authority.publicKey, payer: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        expect.fail("should have failed with InvalidChainConfig");
       } catch (error) {
-        expect(error.message).to.include("InvalidMetadata");
+        expect(error.message).to.include("InvalidChainConfig");
       }
+
+      await program.methods
+        .registerChain(chainId, config(true))
+        .accounts({ nftProgram: nftProgramPda, chainInfo: chainInfoPda, authority: authority.publicKey, payer: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+      const chainInfo = await program.methods.getChainInfo(chainId).accounts({ chainInfo: chainInfoPda }).view();
+      expect(chainInfo.chainId.toNumber()).to.equal(1);
+      expect(chainInfo.name).to.equal("Ethereum");
+      expect(chainInfo.chainKind).to.deep.equal({ evm: {} });
+      expect(chainInfo.recipientLen).to.equal(20);
+      expect(Array.from(chainInfo.connected)).to.deep.equal(evmAddress);
     });
 
-    it("validates cross-chain message parsing", async () => {
-      const invalidMessage = Array.from(Buffer.from("invalid json"));
-      const newNonce = new BN(Date.now() + 2000);
+    it("only the authority can update a chain", async () => {
+      try {
+        await updateChain(false, user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
 
+    it("rejects recipients that dont fit the chain", async () => {
       try {
-        await program.methods
-          .handleCrossChainCall(
-            Array.from(Buffer.alloc(32, 2)),
-            new BN(7001),
-            invalidMessage,
-            newNonce
-          )
+        await bridgeOut(Array.from(Buffer.alloc(32, 1)));
+        expect.fail("should have failed with InvalidRecipient");
+      } catch (error) {
+        expect(error.message).to.include("InvalidRecipient");
+      }
+    });
+
+    it("stops transfers to a disabled chain", async () => {
+      await updateChain(false);
+      expect((await program.account.chainInfo.fetch(chainInfoPda)).enabled).to.be.false;
+
+      try {
+        await bridgeOut(evmAddress);
+        expect.fail("should have failed with ChainDisabled");
+      } catch (error) {
+        expect(error.message).to.include("ChainDisabled");
+      }
+
+      await updateChain(true);
+      await bridgeOut(evmAddress);
+      const nftInfo = await program.account.nftInfo.fetch(
+        PublicKey.findProgramAddressSync([Buffer.from("nft-info"), bridgeMint.publicKey.toBuffer()], program.programId)[0]
+      );
+      expect(nftInfo.isLocked).to.be.true;
+    });
+  });
+
+  describe("chain fees", () => {
+    const chainPda = (chainId: BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("chain"), chainId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    const ethereum = new BN(1);
+    const bsc = new BN(56);
+    let lastTransfer: string;
+
+    const setChainFees = (chainId: BN, flat: number, gas: number, signer: Keypair = authority) =>
+      program.methods
+        .setChainFees(chainId, new BN(flat), new BN(gas))
+        .accounts({ nftProgram: nftProgramPda, chainInfo: chainPda(chainId), authority: signer.publicKey })
+        .signers([signer])
+        .rpc();
+
+    // mints a fresh nft to recipient and sends it out, returns what the treasury got
+    // in lamports
+    const bridgeOut = async (chainId: BN, feeAccounts: object = noFeeTokenAccounts) => {
+      const feeMint = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(feeMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, feeMint])
+        .rpc();
+
+      const treasuryBefore = await provider.connection.getBalance(treasuryPda);
+      lastTransfer = await program.methods
+        .transferToZetachain({ destinationChainId: chainId, recipient: evmAddress, nonce: new BN(0) })
+        .accounts({
+          nftProgram: nftProgramPda,
+          chainInfo: chainPda(chainId),
+          treasury: treasuryPda,
+          nftInfo: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft-info"), feeMint.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          mint: feeMint.publicKey,
+          owner: recipient.publicKey,
+          ownerTokenAccount: getAssociatedTokenAddressSync(feeMint.publicKey, recipient.publicKey),
+          programTokenAccount: getAssociatedTokenAddressSync(feeMint.publicKey, nftProgramPda, true),
+          ...noPnftAccounts,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...feeAccounts,
+        })
+        .signers([recipient])
+        .rpc({ commitment: "confirmed" });
+      return (await provider.connection.getBalance(treasuryPda)) - treasuryBefore;
+    };
+
+    before(async () => {
+      await program.methods
+        .registerChain(bsc, {
+          name: "BNB Smart Chain",
+          chainKind: { evm: {} },
+          enabled: true,
+          recipientLen: 20,
+          connected: evmAddress,
+        })
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey, payer: authority.publicKey })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("only the authority can set chain fees", async () => {
+      try {
+        await setChainFees(ethereum, 1, 1, user);
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("charges each chain its own fee", async () => {
+      await setChainFees(ethereum, 10_000, 500_000);
+      await setChainFees(bsc, 10_000, 20_000);
+
+      expect(await bridgeOut(ethereum)).to.equal(510_000);
+      const { events } = await cpiEventsOf(lastTransfer);
+      const initiated = events.find((event) => event.name === "transferInitiated").data;
+      expect(initiated.destinationChainId.toString()).to.equal(ethereum.toString());
+      expect(initiated.flatFeeLamports.toNumber()).to.equal(10_000);
+      expect(initiated.gasFeeLamports.toNumber()).to.equal(500_000);
+      // canonical hash of the outbound message, on_call reports the same hash on arrival
+      expect(initiated.messageHash).to.have.lengthOf(32);
+      expect(initiated.messageHash.some((b) => b !== 0)).to.be.true;
+
+      expect(await bridgeOut(bsc)).to.equal(30_000);
+    });
+
+    it("uses a new fee right away", async () => {
+      await setChainFees(bsc, 0, 40_000);
+      expect(await bridgeOut(bsc)).to.equal(40_000);
+
+      const chainInfo = await program.account.chainInfo.fetch(chainPda(bsc));
+      expect(chainInfo.flatFeeLamports.toNumber()).to.equal(0);
+      expect(chainInfo.gasFeeLamports.toNumber()).to.equal(40_000);
+    });
+
+    it("fails when the sender cant cover the fee", async () => {
+      const balance = await provider.connection.getBalance(recipient.publicKey);
+      await setChainFees(bsc, balance, 1);
+
+      try {
+        await bridgeOut(bsc);
+        expect.fail("should have failed with InsufficientFee");
+      } catch (error) {
+        expect(error.message).to.include("InsufficientFee");
+      }
+
+      await setChainFees(bsc, 0, 0);
+      await setChainFees(ethereum, 0, 0);
+    });
+
+    describe("in a token", () => {
+      let feeMint: PublicKey;
+      const feeVault = (mint: PublicKey = feeMint) => getAssociatedTokenAddressSync(mint, treasuryPda, true);
+      const feeAccounts = (mint: PublicKey = feeMint) => ({
+        feeMint: mint,
+        ownerFeeTokenAccount: getAssociatedTokenAddressSync(mint, recipient.publicKey),
+        feeVault: feeVault(mint),
+        feeTokenProgram: TOKEN_PROGRAM_ID,
+      });
+      const tokenBalance = async (account: PublicKey) =>
+        Number((await getAccount(provider.connection, account)).amount);
+
+      // the token accounts are only passed for an spl fee
+      const setFeeCurrency = (chainId: BN, feeCurrency, mint: PublicKey | null = null) =>
+        program.methods
+          .setChainFeeCurrency(chainId, feeCurrency)
           .accounts({
             nftProgram: nftProgramPda,
+            chainInfo: chainPda(chainId),
+            treasury: treasuryPda,
+            authority: authority.publicKey,
+            payer: authority.publicKey,
+            feeMint: mint,
+            feeVault: mint && feeVault(mint),
+            feeTokenProgram: mint && TOKEN_PROGRAM_ID,
+            associatedTokenProgram: mint && ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
           })
           .signers([authority])
           .rpc();
-        
-        expect.fail("should have failed with invalid message error");
+
+      const withdrawFeeToken = (amount: number, destination: PublicKey, signer: Keypair = authority) =>
+        program.methods
+          .withdrawFeeToken(new BN(amount))
+          .accounts({
+            nftProgram: nftProgramPda,
+            treasury: treasuryPda,
+            feeMint,
+            feeVault: feeVault(),
+            destinationTokenAccount: destination,
+            authority: signer.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([signer])
+          .rpc();
+
+      before(async () => {
+        // usdc like, 6 decimals
+        feeMint = await createMint(provider.connection, authority, authority.publicKey, null, 6);
+        const senderAccount = await createAssociatedTokenAccount(
+          provider.connection,
+          authority,
+          feeMint,
+          recipient.publicKey
+        );
+        await mintSplTokens(provider.connection, authority, feeMint, senderAccount, authority, 1_500_000);
+        await setFeeCurrency(bsc, { spl: { mint: feeMint, amount: new BN(1_000_000) } }, feeMint);
+      });
+
+      it("collects the flat fee in the token, the gas fee in lamports", async () => {
+        await setChainFees(bsc, 0, 20_000);
+        const chainInfo = await program.account.chainInfo.fetch(chainPda(bsc));
+        expect(chainInfo.feeToken.mint.toString()).to.equal(feeMint.toString());
+        expect(chainInfo.feeToken.amount.toNumber()).to.equal(1_000_000);
+
+        expect(await bridgeOut(bsc, feeAccounts())).to.equal(20_000);
+        expect(await tokenBalance(feeVault())).to.equal(1_000_000);
+        expect(await tokenBalance(feeAccounts().ownerFeeTokenAccount)).to.equal(500_000);
+      });
+
+      it("needs the fee token accounts", async () => {
+        try {
+          await bridgeOut(bsc);
+          expect.fail("should have failed with MissingFeeTokenAccounts");
+        } catch (error) {
+          expect(error.message).to.include("MissingFeeTokenAccounts");
+        }
+      });
+
+      it("refuses a fee account of another mint", async () => {
+        const otherMint = await createMint(provider.connection, authority, authority.publicKey, null, 6);
+        const otherAccount = await createAssociatedTokenAccount(
+          provider.connection,
+          authority,
+          otherMint,
+          recipient.publicKey
+        );
+        await mintSplTokens(provider.connection, authority, otherMint, otherAccount, authority, 5_000_000);
+
+        try {
+          await bridgeOut(bsc, { ...feeAccounts(), ownerFeeTokenAccount: otherAccount });
+          expect.fail("should have failed with FeeTokenMismatch");
+        } catch (error) {
+          expect(error.message).to.include("FeeTokenMismatch");
+        }
+        expect(await tokenBalance(otherAccount)).to.equal(5_000_000);
+      });
+
+      it("fails when the sender cant cover the token fee", async () => {
+        try {
+          await bridgeOut(bsc, feeAccounts());
+          expect.fail("should have failed with InsufficientFeeToken");
+        } catch (error) {
+          expect(error.message).to.include("InsufficientFeeToken");
+        }
+        expect(await tokenBalance(feeAccounts().ownerFeeTokenAccount)).to.equal(500_000);
+        expect(await tokenBalance(feeVault())).to.equal(1_000_000);
+      });
+
+      it("lets only the authority withdraw the token fees", async () => {
+        const destination = await createAssociatedTokenAccount(
+          provider.connection,
+          authority,
+          feeMint,
+          authority.publicKey
+        );
+
+        try {
+          await withdrawFeeToken(1, destination, user);
+          expect.fail("should have failed with unauthorized error");
+        } catch (error) {
+          expect(error.message).to.include("Unauthorized");
+        }
+
+        try {
+          await withdrawFeeToken(1_000_001, destination);
+          expect.fail("should have failed with insufficient treasury error");
+        } catch (error) {
+          expect(error.message).to.include("InsufficientTreasury");
+        }
+
+        await withdrawFeeToken(1_000_000, destination);
+        expect(await tokenBalance(destination)).to.equal(1_000_000);
+        expect(await tokenBalance(feeVault())).to.equal(0);
+      });
+
+      after(async () => {
+        await setFeeCurrency(bsc, { sol: { 0: new BN(0) } });
+        await setChainFees(bsc, 0, 0);
+        expect((await program.account.chainInfo.fetch(chainPda(bsc))).feeToken).to.be.null;
+      });
+    });
+  });
+
+  describe("stats", () => {
+    it("returns a snapshot that matches the accounts", async () => {
+      const stats = await program.methods
+        .getStats()
+        .accounts({ nftProgram: nftProgramPda, treasury: treasuryPda })
+        .view();
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      const treasuryBalance = await provider.connection.getBalance(treasuryPda);
+
+      expect(stats.version).to.equal(3);
+      expect(stats.totalSupply.toString()).to.equal(programState.totalSupply.toString());
+      expect(stats.lockedCount.toString()).to.equal(programState.lockedCount.toString());
+      expect(stats.nonce.toString()).to.equal(programState.nonce.toString());
+      expect(stats.mintPaused).to.equal(programState.mintPaused);
+      expect(stats.treasuryLamports.toNumber()).to.equal(treasuryBalance);
+      expect(stats.immutableBridgedCount.toString()).to.equal(programState.immutableBridgedCount.toString());
+      expect(stats.outboundNonce.toString()).to.equal(programState.outboundNonce.toString());
+      // the chain registry tests left at least one nft locked
+      expect(stats.lockedCount.toNumber()).to.be.greaterThan(0);
+    });
+  });
+
+  describe("nft info view", () => {
+    const viewMint = Keypair.generate();
+    const chainId = new BN(1); // registered by the chain registry tests
+    const viewNftInfo = (mintKey: PublicKey) =>
+      program.methods
+        .getNftInfo(mintKey)
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo: PublicKey.findProgramAddressSync([Buffer.from("nft-info"), mintKey.toBuffer()], program.programId)[0],
+        })
+        .view();
+
+    before(async () => {
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(viewMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, viewMint])
+        .rpc();
+    });
+
+    it("returns the bridge status of a minted nft", async () => {
+      const view = await viewNftInfo(viewMint.publicKey);
+      const { nonce } = await program.account.nftProgramState.fetch(nftProgramPda);
+
+      expect(view.version).to.equal(1);
+      expect(view.mint.toString()).to.equal(viewMint.publicKey.toString());
+      const found = view.status.found;
+      expect(found.owner.toString()).to.equal(recipient.publicKey.toString());
+      expect(found.isLocked).to.be.false;
+      expect(found.bridged).to.be.false;
+      expect(found.uri).to.equal(nftUri);
+      expect(found.uriTruncated).to.be.false;
+      expect(found.name).to.equal(nftName);
+      expect(found.lockedDestination).to.be.null;
+      expect(found.nonce.toString()).to.equal(nonce.toString());
+    });
+
+    it("shows where a locked nft went", async () => {
+      await program.methods
+        .transferToZetachain({ destinationChainId: chainId, recipient: evmAddress, nonce: new BN(0) })
+        .accounts({
+          nftProgram: nftProgramPda,
+          chainInfo: PublicKey.findProgramAddressSync(
+            [Buffer.from("chain"), chainId.toArrayLike(Buffer, "le", 8)],
+            program.programId
+          )[0],
+          nftInfo: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft-info"), viewMint.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          mint: viewMint.publicKey,
+          owner: recipient.publicKey,
+          ownerTokenAccount: getAssociatedTokenAddressSync(viewMint.publicKey, recipient.publicKey),
+          programTokenAccount: getAssociatedTokenAddressSync(viewMint.publicKey, nftProgramPda, true),
+          ...noPnftAccounts,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([recipient])
+        .rpc();
+
+      const found = (await viewNftInfo(viewMint.publicKey)).status.found;
+      expect(found.isLocked).to.be.true;
+      expect(found.lockedDestination).to.deep.equal(evmAddress);
+    });
+
+    it("reports an unknown mint as not found instead of failing", async () => {
+      const unknown = Keypair.generate().publicKey;
+      const view = await viewNftInfo(unknown);
+
+      expect(view.version).to.equal(1);
+      expect(view.mint.toString()).to.equal(unknown.toString());
+      expect(view.status.notFound).to.not.be.undefined;
+      expect(view.status.found).to.be.undefined;
+    });
+  });
+
+  describe("token rescue", () => {
+    const rescue = (mintKey: PublicKey, amount: number, destination: PublicKey) =>
+      program.methods
+        .rescueToken(mintKey, new BN(amount), destination)
+        .accounts({
+          nftProgram: nftProgramPda,
+          mint: mintKey,
+          nftInfo: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft-info"), mintKey.toBuffer()],
+            program.programId
+          )[0],
+          programTokenAccount: getAssociatedTokenAddressSync(mintKey, nftProgramPda, true),
+          destinationTokenAccount: getAssociatedTokenAddressSync(mintKey, destination),
+          authority: authority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+    it("rescues an unrelated token sent to the program", async () => {
+      const strayMint = await createMint(provider.connection, authority, authority.publicKey, null, 6);
+      const programAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        authority,
+        strayMint,
+        nftProgramPda,
+        {},
+        TOKEN_PROGRAM_ID,
+        ASSOCIATED_TOKEN_PROGRAM_ID,
+        true
+      );
+      const destinationAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        authority,
+        strayMint,
+        user.publicKey
+      );
+      await mintSplTokens(provider.connection, authority, strayMint, programAccount, authority, 5_000_000);
+
+      const listener = await program.addEventListener("tokenRescued", (event) => {
+        expect(event.mint.toString()).to.equal(strayMint.toString());
+        expect(event.amount.toNumber()).to.equal(3_000_000);
+        expect(event.destination.toString()).to.equal(user.publicKey.toString());
+      });
+      await rescue(strayMint, 3_000_000, user.publicKey);
+      await program.removeEventListener(listener);
+
+      expect((await getAccount(provider.connection, destinationAccount)).amount.toString()).to.equal("3000000");
+      expect((await getAccount(provider.connection, programAccount)).amount.toString()).to.equal("2000000");
+    });
+
+    it("refuses to move an nft locked for the bridge", async () => {
+      const lockedMint = Keypair.generate();
+      await program.methods
+        .mintNft(mintParams(nftName, nftSymbol, nftUri, recipient.publicKey))
+        .accounts(await mintNftAccounts(lockedMint.publicKey, recipient.publicKey, authority.publicKey))
+        .signers([authority, lockedMint])
+        .rpc();
+      const nftInfo = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft-info"), lockedMint.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      await program.methods
+        .transferToZetachain({ destinationChainId: new BN(7001), recipient: evmAddress, nonce: new BN(0) })
+        .accounts({
+          nftProgram: nftProgramPda,
+          nftInfo,
+          mint: lockedMint.publicKey,
+          owner: recipient.publicKey,
+          ownerTokenAccount: getAssociatedTokenAddressSync(lockedMint.publicKey, recipient.publicKey),
+          programTokenAccount: getAssociatedTokenAddressSync(lockedMint.publicKey, nftProgramPda, true),
+          ...noPnftAccounts,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          approvedCollection: null,
+          ...noFeeTokenAccounts,
+        })
+        .signers([recipient])
+        .rpc();
+
+      try {
+        await rescue(lockedMint.publicKey, 1, recipient.publicKey);
+        expect.fail("should have failed with TokenNotRescuable");
       } catch (error) {
-        expect(error.message).to.include("InvalidMessage");
+        expect(error.message).to.include("TokenNotRescuable");
+      }
+      expect((await program.account.nftInfo.fetch(nftInfo)).isLocked).to.be.true;
+    });
+  });
+
+  // renouncing is permanent, so this runs last
+  describe("renounce authority", () => {
+    const renounce = (confirm: PublicKey) =>
+      program.methods
+        .renounceAuthority(confirm)
+        .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+    it("needs the current authority as confirmation", async () => {
+      try {
+        await renounce(user.publicKey);
+        expect.fail("should have failed with RenounceNotConfirmed");
+      } catch (error) {
+        expect(error.message).to.include("RenounceNotConfirmed");
+      }
+    });
+
+    it("leaves the program without an authority", async () => {
+      const listener = await program.addEventListener("authorityRenounced", (event) => {
+        expect(event.previous.toString()).to.equal(authority.publicKey.toString());
+      });
+      await renounce(authority.publicKey);
+      await program.removeEventListener(listener);
+
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.authority.toString()).to.equal(PublicKey.default.toString());
+      expect(programState.pendingAuthority.toString()).to.equal(PublicKey.default.toString());
+    });
+
+    it("rejects admin instructions afterwards", async () => {
+      try {
+        await program.methods
+          .setMintPaused(true)
+          .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      try {
+        await program.methods
+          .nominateAuthority(user.publicKey)
+          .accounts({ nftProgram: nftProgramPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        expect.fail("should have failed with unauthorized error");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
       }
+
+      const programState = await program.account.nftProgramState.fetch(nftProgramPda);
+      expect(programState.mintPaused).to.be.false;
     });
   });
-});
\ No newline at end of file
+});